@@ -41,6 +41,26 @@ where
     Ok(len)
 }
 
+/** Format a number in accordance to the DICOM Decimal String (DS)
+ * value representation.
+ *
+ * The shortest representation of the number is used
+ * if it fits in the 16 characters allowed for a single value,
+ * otherwise the number is written in scientific notation
+ * with as much precision as possible.
+ */
+pub fn format_decimal_string(value: f64) -> String {
+    let s = value.to_string();
+    if s.len() <= 16 {
+        return s;
+    }
+    (0..=15)
+        .rev()
+        .map(|precision| format!("{:.*e}", precision, value))
+        .find(|s| s.len() <= 16)
+        .unwrap_or(s)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -103,4 +123,23 @@ mod test {
         assert_eq!(from_utf8(&data).unwrap(), "2018122404+0100");
         assert_eq!(bytes, 15);
     }
+
+    #[test]
+    fn test_format_decimal_string() {
+        assert_eq!(format_decimal_string(12.5), "12.5");
+        assert_eq!(format_decimal_string(-0.75), "-0.75");
+        assert_eq!(format_decimal_string(100.), "100");
+
+        for value in [0.1 + 0.2, -1. / 3., 1e300, -2.5e-200, 123456789.12345679] {
+            let s = format_decimal_string(value);
+            assert!(s.len() <= 16, "{} is too long", s);
+            let parsed: f64 = s.parse().unwrap();
+            assert!(
+                ((parsed - value) / value).abs() < 1e-9,
+                "{} != {}",
+                s,
+                value
+            );
+        }
+    }
 }
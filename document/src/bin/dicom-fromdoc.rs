@@ -0,0 +1,168 @@
+//! A CLI tool for encapsulating a PDF or CDA document
+//! into a new DICOM file.
+//!
+//! The document is stored as is,
+//! in an _Encapsulated PDF_ or _Encapsulated CDA_ object.
+//! New SOP instance, study instance and series instance UIDs are generated.
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use dicom_core::{DataElement, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::document::{DocumentType, EncapsulatedDocumentBuilder};
+use snafu::{OptionExt, Report, Whatever};
+
+/// Exit code for when an error emerged while reading the document.
+const ERROR_READ: i32 = -2;
+/// Exit code for when an error emerged while building the DICOM object.
+const ERROR_BUILD: i32 = -3;
+/// Exit code for when an error emerged while writing the file.
+const ERROR_WRITE: i32 = -4;
+/// Exit code for any other error.
+const ERROR_OTHER: i32 = -128;
+
+/// Encapsulate a PDF or CDA document into a new DICOM file
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// Path to the document file
+    file: PathBuf,
+    /// Path to the output file
+    /// (default is to replace the document's extension with `.dcm`)
+    #[arg(short = 'o', long = "out")]
+    output: Option<PathBuf>,
+    /// The type of document
+    /// (default is to detect it from the document's contents)
+    #[arg(long = "type", value_enum)]
+    document_type: Option<DocumentKind>,
+    /// The document title (default is the file name without extension)
+    #[arg(long = "title")]
+    title: Option<String>,
+    /// The patient's name
+    #[arg(long = "patient-name")]
+    patient_name: Option<String>,
+    /// The patient ID
+    #[arg(long = "patient-id")]
+    patient_id: Option<String>,
+    /// The HL7 instance identifier of a CDA document (`root^extension`)
+    /// (default is to read it from the document)
+    #[arg(long = "hl7-instance-identifier")]
+    hl7_instance_identifier: Option<String>,
+    /// Print more information about the output file
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+/// The type of document to encapsulate
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum DocumentKind {
+    /// Portable Document Format
+    Pdf,
+    /// HL7 Clinical Document Architecture
+    Cda,
+}
+
+impl From<DocumentKind> for DocumentType {
+    fn from(kind: DocumentKind) -> Self {
+        match kind {
+            DocumentKind::Pdf => DocumentType::Pdf,
+            DocumentKind::Cda => DocumentType::Cda,
+        }
+    }
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", Report::from_error(e));
+        });
+
+    run().unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_OTHER);
+    });
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        file,
+        output,
+        document_type,
+        title,
+        patient_name,
+        patient_id,
+        hl7_instance_identifier,
+        verbose,
+    } = App::parse();
+
+    let output = output.unwrap_or_else(|| {
+        let mut path = file.clone();
+        path.set_extension("dcm");
+        path
+    });
+
+    let document = std::fs::read(&file).unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_READ);
+    });
+
+    let document_type = match document_type {
+        Some(kind) => kind.into(),
+        None => DocumentType::detect(&document).with_whatever_context(|| {
+            format!(
+                "Could not detect the type of document {}, please specify --type",
+                file.display()
+            )
+        })?,
+    };
+
+    let title = title.or_else(|| {
+        file.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+    });
+
+    let mut builder = EncapsulatedDocumentBuilder::new(document_type);
+    if let Some(title) = title {
+        builder = builder.document_title(title);
+    }
+    if let Some(patient_name) = patient_name {
+        builder = builder.attribute(DataElement::new(tags::PATIENT_NAME, VR::PN, patient_name));
+    }
+    if let Some(patient_id) = patient_id {
+        builder = builder.attribute(DataElement::new(tags::PATIENT_ID, VR::LO, patient_id));
+    }
+    if let Some(identifier) = hl7_instance_identifier {
+        builder = builder.hl7_instance_identifier(identifier);
+    }
+
+    let obj = builder.build(document).unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_BUILD);
+    });
+
+    obj.write_to_file(&output).unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_WRITE);
+    });
+
+    if verbose {
+        println!(
+            "Encapsulated {} document saved to {}",
+            document_type.mime_type(),
+            output.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
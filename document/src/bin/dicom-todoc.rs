@@ -0,0 +1,93 @@
+//! A CLI tool for extracting the document
+//! encapsulated in a DICOM file,
+//! such as an _Encapsulated PDF_ or _Encapsulated CDA_ object.
+use std::path::PathBuf;
+
+use clap::Parser;
+use dicom_object::document::extract_document;
+use dicom_object::open_file;
+use snafu::Report;
+
+/// Exit code for when an error emerged while reading the DICOM file.
+const ERROR_READ: i32 = -2;
+/// Exit code for when an error emerged while extracting the document.
+const ERROR_EXTRACT: i32 = -3;
+/// Exit code for when an error emerged while writing the file.
+const ERROR_WRITE: i32 = -4;
+
+/// Extract the document encapsulated in a DICOM file
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// Path to the DICOM file
+    file: PathBuf,
+    /// Path to the output file
+    /// (default is to replace the DICOM file's extension
+    /// with the document's usual extension)
+    #[arg(short = 'o', long = "out")]
+    output: Option<PathBuf>,
+    /// Print more information about the document
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", Report::from_error(e));
+        });
+
+    let App {
+        file,
+        output,
+        verbose,
+    } = App::parse();
+
+    let obj = open_file(&file).unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_READ);
+    });
+
+    let document = extract_document(&obj).unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_EXTRACT);
+    });
+
+    let output = output.unwrap_or_else(|| {
+        let extension = document
+            .document_type()
+            .map(|t| t.extension())
+            .unwrap_or("bin");
+        let mut path = file.clone();
+        path.set_extension(extension);
+        path
+    });
+
+    std::fs::write(&output, &document.data).unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_WRITE);
+    });
+
+    if verbose {
+        if let Some(title) = &document.title {
+            println!("Title: {}", title);
+        }
+        println!(
+            "Document ({}, {} bytes) saved to {}",
+            document.mime_type,
+            document.data.len(),
+            output.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -38,12 +38,14 @@ use clap::ValueEnum;
 use dicom_core::dictionary::UidDictionary;
 use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
 use dicom_core::header::Header;
+use dicom_core::ops::{AttributeSelector, AttributeSelectorStep};
 use dicom_core::value::{PrimitiveValue, Value as DicomValue};
-use dicom_core::VR;
+use dicom_core::{Tag, VR};
 #[cfg(feature = "sop-class")]
 use dicom_dictionary_std::StandardSopClassDictionary;
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_json::DicomJson;
+use dicom_object::file::Diagnostic;
 use dicom_object::mem::{InMemDicomObject, InMemElement};
 use dicom_object::{FileDicomObject, FileMetaTable, StandardDataDictionary};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
@@ -124,6 +126,8 @@ pub struct DumpOptions {
     pub no_text_limit: bool,
     /// never trim out any values (implies `no_text_limit`)
     pub no_limit: bool,
+    /// parsing diagnostics to highlight next to the offending elements
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl DumpOptions {
@@ -183,6 +187,20 @@ impl DumpOptions {
         self
     }
 
+    /// Set the parsing diagnostics to highlight in text output.
+    ///
+    /// Each diagnostic is printed right below
+    /// the data element at its [selector](Diagnostic::selector).
+    /// Diagnostics without a selector,
+    /// or of a data element which is not part of the dumped data set,
+    /// are printed in a summary after the data set.
+    /// These can be collected when opening the file
+    /// via [`OpenFileOptions::diagnostics`](dicom_object::OpenFileOptions::diagnostics).
+    pub fn diagnostics(&mut self, diagnostics: Vec<Diagnostic>) -> &mut Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
     /// Dump the contents of an open DICOM file to standard output.
     pub fn dump_file<D>(&self, obj: &FileDicomObject<InMemDicomObject<D>>) -> IoResult<()>
     where
@@ -233,7 +251,16 @@ impl DumpOptions {
 
                 writeln!(to, "{:-<58}", "")?;
 
-                dump(&mut to, obj, width, 0, no_text_limit, no_limit)?;
+                dump(
+                    &mut to,
+                    obj,
+                    width,
+                    0,
+                    no_text_limit,
+                    no_limit,
+                    DiagnosticScope::root(&self.diagnostics),
+                )?;
+                dump_remaining_diagnostics(&mut to, obj, &self.diagnostics)?;
 
                 Ok(())
             },
@@ -290,7 +317,16 @@ impl DumpOptions {
                     (true, true)
                 };
 
-                dump(&mut to, obj, width, 0, no_text_limit, no_limit)?;
+                dump(
+                    &mut to,
+                    obj,
+                    width,
+                    0,
+                    no_text_limit,
+                    no_limit,
+                    DiagnosticScope::root(&self.diagnostics),
+                )?;
+                dump_remaining_diagnostics(&mut to, obj, &self.diagnostics)?;
 
                 Ok(())
             }
@@ -580,6 +616,102 @@ where
     Ok(())
 }
 
+/// The parsing diagnostics to highlight,
+/// along with the location of the data set being dumped.
+#[derive(Debug, Copy, Clone)]
+struct DiagnosticScope<'a> {
+    diagnostics: &'a [Diagnostic],
+    /// the sequence items leading to the data set being dumped
+    path: &'a [AttributeSelectorStep],
+}
+
+impl<'a> DiagnosticScope<'a> {
+    fn root(diagnostics: &'a [Diagnostic]) -> Self {
+        DiagnosticScope {
+            diagnostics,
+            path: &[],
+        }
+    }
+
+    /// Iterate over the diagnostics
+    /// of the data element with the given tag in this data set.
+    fn of_element(&self, tag: Tag) -> impl Iterator<Item = &'a Diagnostic> {
+        let selector = AttributeSelector::new(
+            self.path
+                .iter()
+                .copied()
+                .chain(std::iter::once(AttributeSelectorStep::Tag(tag))),
+        );
+        self.diagnostics
+            .iter()
+            .filter(move |d| d.selector.is_some() && d.selector == selector)
+    }
+}
+
+/// Print the diagnostics which could not be shown
+/// next to a data element of the dumped data set.
+fn dump_remaining_diagnostics<W, D>(
+    to: &mut W,
+    obj: &InMemDicomObject<D>,
+    diagnostics: &[Diagnostic],
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    let remaining: Vec<_> = diagnostics
+        .iter()
+        .filter(|d| match &d.selector {
+            Some(selector) => !has_element_at(obj, selector),
+            None => true,
+        })
+        .collect();
+    if remaining.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(to, "{:-<58}", "")?;
+    for diagnostic in remaining {
+        writeln!(
+            to,
+            "^ {}",
+            DumpValue::Invalid(match &diagnostic.selector {
+                Some(selector) => format!(
+                    "{} at {:#x}: {}",
+                    selector, diagnostic.position, diagnostic.kind
+                ),
+                None => diagnostic.to_string(),
+            })
+        )?;
+    }
+    Ok(())
+}
+
+/// Check whether the data element at the given selector
+/// is part of the data set, and would therefore be dumped.
+fn has_element_at<D>(obj: &InMemDicomObject<D>, selector: &AttributeSelector) -> bool {
+    fn find<D>(obj: &InMemDicomObject<D>, tag: Tag) -> Option<&InMemElement<D>> {
+        obj.into_iter().find(|e| e.tag() == tag)
+    }
+
+    let mut obj = obj;
+    for step in selector.iter() {
+        match *step {
+            AttributeSelectorStep::Tag(tag) => return find(obj, tag).is_some(),
+            AttributeSelectorStep::Nested { tag, item } => {
+                match find(obj, tag)
+                    .and_then(|e| e.items())
+                    .and_then(|items| items.get(item as usize))
+                {
+                    Some(item) => obj = item,
+                    None => return false,
+                }
+            }
+        }
+    }
+    false
+}
+
 fn dump<W, D>(
     to: &mut W,
     obj: &InMemDicomObject<D>,
@@ -587,13 +719,34 @@ fn dump<W, D>(
     depth: u32,
     no_text_limit: bool,
     no_limit: bool,
+    diagnostics: DiagnosticScope<'_>,
 ) -> IoResult<()>
 where
     W: ?Sized + Write,
     D: DataDictionary,
 {
     for elem in obj {
-        dump_element(&mut *to, elem, width, depth, no_text_limit, no_limit)?;
+        dump_element_impl(
+            &mut *to,
+            elem,
+            width,
+            depth,
+            no_text_limit,
+            no_limit,
+            diagnostics,
+        )?;
+        for diagnostic in diagnostics.of_element(elem.tag()) {
+            writeln!(
+                to,
+                "{:indent$}  ^ {}",
+                "",
+                DumpValue::Invalid(format!(
+                    "{} (at {:#x})",
+                    diagnostic.kind, diagnostic.position
+                )),
+                indent = (depth * 2) as usize,
+            )?;
+        }
     }
 
     Ok(())
@@ -607,6 +760,30 @@ pub fn dump_element<W, D>(
     no_text_limit: bool,
     no_limit: bool,
 ) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    dump_element_impl(
+        to,
+        elem,
+        width,
+        depth,
+        no_text_limit,
+        no_limit,
+        DiagnosticScope::root(&[]),
+    )
+}
+
+fn dump_element_impl<W, D>(
+    to: &mut W,
+    elem: &InMemElement<D>,
+    width: u32,
+    depth: u32,
+    no_text_limit: bool,
+    no_limit: bool,
+    diagnostics: DiagnosticScope<'_>,
+) -> IoResult<()>
 where
     W: ?Sized + Write,
     D: DataDictionary,
@@ -633,8 +810,28 @@ where
                 vm,
                 if vm == 1 { "" } else { "s" },
             )?;
-            for item in seq.items() {
-                dump_item(&mut *to, item, width, depth + 2, no_text_limit, no_limit)?;
+            for (i, item) in seq.items().iter().enumerate() {
+                let path: Vec<_> = diagnostics
+                    .path
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(AttributeSelectorStep::Nested {
+                        tag: elem.tag(),
+                        item: i as u32,
+                    }))
+                    .collect();
+                dump_item(
+                    &mut *to,
+                    item,
+                    width,
+                    depth + 2,
+                    no_text_limit,
+                    no_limit,
+                    DiagnosticScope {
+                        diagnostics: diagnostics.diagnostics,
+                        path: &path,
+                    },
+                )?;
             }
             to.write_all(&indent)?;
             writeln!(
@@ -726,6 +923,7 @@ fn dump_item<W, D>(
     depth: u32,
     no_text_limit: bool,
     no_limit: bool,
+    diagnostics: DiagnosticScope<'_>,
 ) -> IoResult<()>
 where
     W: ?Sized + Write,
@@ -739,7 +937,15 @@ where
         DumpValue::TagNum("(FFFE,E000)"),
         DumpValue::Alias("Item"),
     )?;
-    dump(to, item, width, depth + 1, no_text_limit, no_limit)?;
+    dump(
+        to,
+        item,
+        width,
+        depth + 1,
+        no_text_limit,
+        no_limit,
+        diagnostics,
+    )?;
     writeln!(
         to,
         "{}{} {}",
@@ -1115,6 +1321,152 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dump_object_to_highlights_diagnostics() {
+        use dicom_object::file::{Diagnostic, DiagnosticKind};
+
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from("OT")),
+            DataElement::new(
+                tags::PATIENT_NAME,
+                VR::UN,
+                PrimitiveValue::from(vec![0x4Au8, 0x6F]),
+            ),
+        ]);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .color_mode(ColorMode::Never)
+            .diagnostics(vec![Diagnostic {
+                position: 0x1a,
+                tag: Some(tags::PATIENT_NAME),
+                selector: Some(tags::PATIENT_NAME.into()),
+                kind: DiagnosticKind::UnknownVr { expected: VR::PN },
+            }])
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+
+        let lines: Vec<_> = std::str::from_utf8(&out)
+            .expect("output is not valid UTF-8")
+            .lines()
+            .collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("(0008,0060)"));
+        assert!(lines[1].starts_with("(0010,0010)"));
+        assert_eq!(
+            lines[2],
+            "  ^ value representation read as UN instead of PN (at 0x1a)"
+        );
+    }
+
+    #[test]
+    fn dump_object_to_summarizes_remaining_diagnostics() {
+        use dicom_object::file::{Diagnostic, DiagnosticKind};
+
+        let obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::MODALITY,
+            VR::CS,
+            PrimitiveValue::from("OT"),
+        )]);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .color_mode(ColorMode::Never)
+            .diagnostics(vec![
+                Diagnostic {
+                    position: 0x1a,
+                    tag: Some(tags::MODALITY),
+                    selector: Some(tags::MODALITY.into()),
+                    kind: DiagnosticKind::OddLength { len: 1 },
+                },
+                // not in the data set
+                Diagnostic {
+                    position: 0x40,
+                    tag: Some(tags::SOP_CLASS_UID),
+                    selector: Some(
+                        (tags::REFERENCED_SERIES_SEQUENCE, 0, tags::SOP_CLASS_UID).into(),
+                    ),
+                    kind: DiagnosticKind::OddLength { len: 3 },
+                },
+                // without a selector
+                Diagnostic {
+                    position: 0x80,
+                    tag: None,
+                    selector: None,
+                    kind: DiagnosticKind::OddLength { len: 5 },
+                },
+            ])
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+
+        let lines: Vec<_> = std::str::from_utf8(&out)
+            .expect("output is not valid UTF-8")
+            .lines()
+            .collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("(0008,0060)"));
+        assert_eq!(lines[1], "  ^ odd value length 1 (at 0x1a)");
+        assert!(lines[2].starts_with("-----"));
+        assert_eq!(
+            lines[3],
+            "^ (0008,1115)[0].(0008,0016) at 0x40: odd value length 3"
+        );
+        assert_eq!(lines[4], "^ at 0x80: odd value length 5");
+    }
+
+    #[test]
+    fn dump_object_to_highlights_nested_diagnostics() {
+        use dicom_core::value::DataSetSequence;
+        use dicom_object::file::{Diagnostic, DiagnosticKind};
+
+        let item = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            PrimitiveValue::from("1.2"),
+        )]);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, PrimitiveValue::from("1.2")),
+            DataElement::new(
+                tags::REFERENCED_SERIES_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item.clone(), item]),
+            ),
+        ]);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .color_mode(ColorMode::Never)
+            .diagnostics(vec![Diagnostic {
+                position: 0x40,
+                tag: Some(tags::SOP_CLASS_UID),
+                selector: Some((tags::REFERENCED_SERIES_SEQUENCE, 1, tags::SOP_CLASS_UID).into()),
+                kind: DiagnosticKind::OddLength { len: 3 },
+            }])
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+
+        let lines: Vec<_> = std::str::from_utf8(&out)
+            .expect("output is not valid UTF-8")
+            .lines()
+            .collect();
+
+        // only the element in the second item is highlighted
+        let highlighted: Vec<_> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start().starts_with('^'))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(highlighted.len(), 1);
+        let i = highlighted[0];
+        assert!(lines[i - 1].trim_start().starts_with("(0008,0016)"));
+        assert!(lines[i - 2].trim_start().starts_with("(FFFE,E000)"));
+        assert!(lines[i - 3].trim_start().starts_with("(FFFE,E00D)"));
+        assert_eq!(lines[i].trim(), "^ odd value length 3 (at 0x40)");
+    }
+
     #[test]
     fn dump_json() {
         // create object
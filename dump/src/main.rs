@@ -4,6 +4,7 @@ use clap::Parser;
 use dicom_core::Tag;
 use dicom_dictionary_std::tags;
 use dicom_dump::{ColorMode, DumpOptions, DumpFormat};
+use dicom_object::file::Diagnostics;
 use dicom_object::{OpenFileOptions, StandardDataDictionary};
 use snafu::{Report, Whatever};
 use std::io::{ErrorKind, IsTerminal};
@@ -102,12 +103,28 @@ fn run() -> Result<(), Whatever> {
         // Write filename to stderr to make piping easier, i.e. dicom-dump -o json file.dcm | jq
         eprintln!("{}: ", filename.display());
 
+        let diagnostics = Diagnostics::new();
         let open_options = match read_until {
             Some(stop_tag) => OpenFileOptions::new().read_until(stop_tag),
             None => OpenFileOptions::new(),
-        };
+        }
+        .diagnostics(diagnostics.clone());
+
+        let result = open_options.open_file(filename);
+
+        // text output shows anomalies next to their data elements
+        // or in a summary at the end
+        let diagnostics = diagnostics.take();
+        if result.is_ok() && options.format == DumpFormat::Text {
+            options.diagnostics(diagnostics);
+        } else {
+            for diagnostic in diagnostics {
+                eprintln!("[WARN] {}", diagnostic);
+            }
+            options.diagnostics(Vec::new());
+        }
 
-        match open_options.open_file(filename) {
+        match result {
             Err(e) => {
                 eprintln!("{}", Report::from_error(e));
                 if fail_first {
@@ -15,6 +15,7 @@
 use dicom_core::{ops::AttributeOp, value::C};
 use snafu::Snafu;
 use std::borrow::Cow;
+use std::ops::Range;
 
 /// The possible error conditions when decoding (reading) pixel data.
 ///
@@ -155,6 +156,24 @@ pub trait PixelDataObject {
     /// or `None` if it is not defined
     fn photometric_interpretation(&self) -> Option<&str>;
 
+    /// Return the _Pixel Representation_, or `None` if it is not defined.
+    ///
+    /// The default implementation always returns `None`,
+    /// which is equivalent to unsigned samples
+    /// (pixel representation of 0).
+    fn pixel_representation(&self) -> Option<u16> {
+        None
+    }
+
+    /// Return the _Planar Configuration_, or `None` if it is not defined.
+    ///
+    /// The default implementation always returns `None`,
+    /// which is equivalent to samples being interleaved
+    /// (planar configuration of 0).
+    fn planar_configuration(&self) -> Option<u16> {
+        None
+    }
+
     /// Return the _Number Of Frames_, or `None` if it is not defined
     fn number_of_frames(&self) -> Option<u32>;
 
@@ -177,6 +196,33 @@ pub trait PixelDataObject {
     /// or `None` if no offset table is available.
     fn offset_table(&self) -> Option<Cow<[u32]>>;
 
+    /// Return the object's _Extended Offset Table_,
+    /// or `None` if no extended offset table is available.
+    ///
+    /// The default implementation always returns `None`.
+    fn extended_offset_table(&self) -> Option<Cow<'_, [u64]>> {
+        None
+    }
+
+    /// Return the encoded pixel data of a specific frame by index
+    /// (where 0 is the first frame),
+    /// by gathering all fragments which make up the frame,
+    /// or `None` if the frame could not be located.
+    ///
+    /// The fragments of each frame are determined
+    /// through a [`FrameIndex`].
+    /// When the frame is contained in a single fragment,
+    /// this is equivalent to retrieving that fragment,
+    /// so that no other fragments need to be touched.
+    ///
+    /// The default implementation builds a new frame index on every call.
+    /// When retrieving many frames of the same object,
+    /// prefer building the index once with [`FrameIndex::from_object`]
+    /// and using [`FrameIndex::frame_data`].
+    fn frame_data(&self, frame: u32) -> Option<Cow<'_, [u8]>> {
+        FrameIndex::from_object(self)?.frame_data(self, frame)
+    }
+
     /// Should return either a byte slice/vector if the pixel data is native
     /// or the list of byte fragments and offset table if encapsulated.
     ///
@@ -184,6 +230,299 @@ pub trait PixelDataObject {
     fn raw_pixel_data(&self) -> Option<RawPixelData>;
 }
 
+/// An index of the fragments making up each frame
+/// of an encapsulated pixel data element.
+///
+/// Frames are located with the first available source of information
+/// from the following:
+///
+/// 1. the _Extended Offset Table_ or the _Basic Offset Table_;
+/// 2. a one-to-one correspondence between fragments and frames,
+///    or a single frame spanning all fragments;
+/// 3. the end of image marker (`FF D9`) at the end of a fragment,
+///    as defined by the JPEG family of encodings,
+///    which closes a frame.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_encoding::adapters::FrameIndex;
+/// // 3 fragments of 10 bytes, 2 frames starting at fragments #0 and #2
+/// let index = FrameIndex::new(2, &[10, 10, 10], &[0, 36], |_| false).unwrap();
+/// assert_eq!(index.len(), 2);
+/// assert_eq!(index.fragments(0), Some(0..2));
+/// assert_eq!(index.fragments(1), Some(2..3));
+/// assert_eq!(index.fragments(2), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameIndex {
+    /// the range of fragment indices of each frame
+    frames: Vec<Range<usize>>,
+}
+
+impl FrameIndex {
+    /// Build a frame index from the given properties
+    /// of an encapsulated pixel data element.
+    ///
+    /// - `number_of_frames`: the number of frames in the pixel data
+    /// - `fragment_lengths`:
+    ///   the length in bytes of each fragment,
+    ///   excluding the basic offset table
+    /// - `offset_table`:
+    ///   the byte offset of each frame
+    ///   relative to the start of the first fragment item,
+    ///   as in the _Extended Offset Table_ or the _Basic Offset Table_,
+    ///   or empty if no offset table is available
+    /// - `ends_frame`:
+    ///   a function telling whether the fragment at the given index
+    ///   is the last fragment of a frame,
+    ///   only called when the frames cannot be located otherwise
+    ///   (see [`ends_with_eoi_marker`](Self::ends_with_eoi_marker))
+    ///
+    /// Returns `None` if the fragments could not be attributed
+    /// to the given number of frames.
+    pub fn new(
+        number_of_frames: u32,
+        fragment_lengths: &[u32],
+        offset_table: &[u64],
+        mut ends_frame: impl FnMut(usize) -> bool,
+    ) -> Option<Self> {
+        let number_of_frames = number_of_frames as usize;
+        let number_of_fragments = fragment_lengths.len();
+        if number_of_frames == 0 || number_of_fragments == 0 {
+            return None;
+        }
+
+        if offset_table.len() == number_of_frames {
+            if let Some(index) = Self::from_offset_table(fragment_lengths, offset_table) {
+                return Some(index);
+            }
+        }
+
+        if number_of_fragments == number_of_frames {
+            return Some(FrameIndex {
+                frames: (0..number_of_fragments).map(|i| i..i + 1).collect(),
+            });
+        }
+        if number_of_frames == 1 {
+            return Some(FrameIndex {
+                frames: std::iter::once(0..number_of_fragments).collect(),
+            });
+        }
+
+        // look for the fragments closing each frame
+        let mut frames = Vec::with_capacity(number_of_frames);
+        let mut start = 0;
+        for i in 0..number_of_fragments {
+            if i == number_of_fragments - 1 || ends_frame(i) {
+                frames.push(start..i + 1);
+                start = i + 1;
+            }
+        }
+        if frames.len() != number_of_frames {
+            return None;
+        }
+        Some(FrameIndex { frames })
+    }
+
+    fn from_offset_table(fragment_lengths: &[u32], offset_table: &[u64]) -> Option<Self> {
+        // the byte position of each fragment item
+        let mut position = 0;
+        let positions: Vec<u64> = fragment_lengths
+            .iter()
+            .map(|len| {
+                let p = position;
+                position += u64::from(*len) + 8;
+                p
+            })
+            .collect();
+
+        let starts = offset_table
+            .iter()
+            .map(|offset| positions.binary_search(offset).ok())
+            .collect::<Option<Vec<usize>>>()?;
+
+        let ends = starts
+            .iter()
+            .skip(1)
+            .copied()
+            .chain(std::iter::once(fragment_lengths.len()));
+        let frames: Vec<_> = starts.iter().zip(ends).map(|(s, e)| *s..e).collect();
+        if frames.iter().any(|range| range.is_empty()) {
+            return None;
+        }
+        Some(FrameIndex { frames })
+    }
+
+    /// Build a frame index for the pixel data of the given object.
+    ///
+    /// The _Extended Offset Table_ has precedence
+    /// over the _Basic Offset Table_.
+    /// Returns `None` if the pixel data is not encapsulated
+    /// or the frames could not be located.
+    pub fn from_object<O>(src: &O) -> Option<Self>
+    where
+        O: PixelDataObject + ?Sized,
+    {
+        let number_of_fragments = src.number_of_fragments()? as usize;
+        let fragment_lengths = (0..number_of_fragments)
+            .map(|i| src.fragment(i).map(|fragment| fragment.len() as u32))
+            .collect::<Option<Vec<_>>>()?;
+        let offset_table: Vec<u64> = match src.extended_offset_table() {
+            Some(table) if !table.is_empty() => table.into_owned(),
+            _ => src
+                .offset_table()
+                .map(|table| table.iter().map(|offset| u64::from(*offset)).collect())
+                .unwrap_or_default(),
+        };
+
+        Self::new(
+            src.number_of_frames().unwrap_or(1),
+            &fragment_lengths,
+            &offset_table,
+            |i| {
+                src.fragment(i)
+                    .map(|fragment| Self::ends_with_eoi_marker(&fragment))
+                    .unwrap_or(false)
+            },
+        )
+    }
+
+    /// Check whether the given fragment data ends
+    /// with an end of image marker (`FF D9`),
+    /// possibly followed by a single padding byte.
+    ///
+    /// This marker ends the code stream of
+    /// JPEG, JPEG-LS, and JPEG 2000 images.
+    pub fn ends_with_eoi_marker(fragment: &[u8]) -> bool {
+        fragment.ends_with(&[0xFF, 0xD9]) || fragment.ends_with(&[0xFF, 0xD9, 0x00])
+    }
+
+    /// Get the range of fragment indices making up the given frame,
+    /// or `None` if the frame is out of bounds.
+    pub fn fragments(&self, frame: u32) -> Option<Range<usize>> {
+        self.frames.get(frame as usize).cloned()
+    }
+
+    /// Gather the encoded pixel data of the given frame
+    /// from the fragments of the given object,
+    /// which must be the object that this index was built from.
+    ///
+    /// Returns `None` if the frame or any of its fragments
+    /// could not be located.
+    pub fn frame_data<'a, O>(&self, src: &'a O, frame: u32) -> Option<Cow<'a, [u8]>>
+    where
+        O: PixelDataObject + ?Sized,
+    {
+        let fragments = self.fragments(frame)?;
+        if fragments.len() == 1 {
+            return src.fragment(fragments.start);
+        }
+        let mut data = Vec::new();
+        for fragment in fragments {
+            data.extend_from_slice(&src.fragment(fragment)?);
+        }
+        Some(Cow::Owned(data))
+    }
+
+    /// Get the number of frames in the index.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Check whether the index has no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// A pixel data object with a frame index built up front,
+/// so that retrieving the data of each frame
+/// does not require locating all frames again.
+struct IndexedPixelDataObject<'a> {
+    inner: &'a dyn PixelDataObject,
+    /// the frame index, or `None` if the pixel data is not encapsulated
+    index: Option<FrameIndex>,
+}
+
+impl<'a> IndexedPixelDataObject<'a> {
+    fn new(inner: &'a dyn PixelDataObject) -> Self {
+        IndexedPixelDataObject {
+            inner,
+            index: FrameIndex::from_object(inner),
+        }
+    }
+}
+
+impl PixelDataObject for IndexedPixelDataObject<'_> {
+    fn transfer_syntax_uid(&self) -> &str {
+        self.inner.transfer_syntax_uid()
+    }
+
+    fn rows(&self) -> Option<u16> {
+        self.inner.rows()
+    }
+
+    fn cols(&self) -> Option<u16> {
+        self.inner.cols()
+    }
+
+    fn samples_per_pixel(&self) -> Option<u16> {
+        self.inner.samples_per_pixel()
+    }
+
+    fn bits_allocated(&self) -> Option<u16> {
+        self.inner.bits_allocated()
+    }
+
+    fn bits_stored(&self) -> Option<u16> {
+        self.inner.bits_stored()
+    }
+
+    fn photometric_interpretation(&self) -> Option<&str> {
+        self.inner.photometric_interpretation()
+    }
+
+    fn pixel_representation(&self) -> Option<u16> {
+        self.inner.pixel_representation()
+    }
+
+    fn planar_configuration(&self) -> Option<u16> {
+        self.inner.planar_configuration()
+    }
+
+    fn number_of_frames(&self) -> Option<u32> {
+        self.inner.number_of_frames()
+    }
+
+    fn number_of_fragments(&self) -> Option<u32> {
+        self.inner.number_of_fragments()
+    }
+
+    fn fragment(&self, fragment: usize) -> Option<Cow<'_, [u8]>> {
+        self.inner.fragment(fragment)
+    }
+
+    fn offset_table(&self) -> Option<Cow<'_, [u32]>> {
+        self.inner.offset_table()
+    }
+
+    fn extended_offset_table(&self) -> Option<Cow<'_, [u64]>> {
+        self.inner.extended_offset_table()
+    }
+
+    fn frame_data(&self, frame: u32) -> Option<Cow<'_, [u8]>> {
+        match &self.index {
+            Some(index) => index.frame_data(self.inner, frame),
+            None => self.inner.frame_data(frame),
+        }
+    }
+
+    fn raw_pixel_data(&self) -> Option<RawPixelData> {
+        self.inner.raw_pixel_data()
+    }
+}
+
 /// Custom options when encoding pixel data into an encapsulated form.
 #[derive(Debug, Default, Clone)]
 #[non_exhaustive]
@@ -245,8 +584,10 @@ pub trait PixelDataReader {
     /// and images in _MONOCHROME2_ continue to be in _MONOCHROME2_).
     fn decode(&self, src: &dyn PixelDataObject, dst: &mut Vec<u8>) -> DecodeResult<()> {
         let frames = src.number_of_frames().unwrap_or(1);
+        // locate the frames only once
+        let src = IndexedPixelDataObject::new(src);
         for frame in 0..frames {
-            self.decode_frame(src, frame, dst)?;
+            self.decode_frame(&src, frame, dst)?;
         }
         Ok(())
     }
@@ -446,3 +787,150 @@ impl PixelDataWriter for crate::transfer_syntax::NeverAdapter {
         unreachable!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeResult, FrameIndex, PixelDataObject, PixelDataReader, RawPixelData};
+    use std::borrow::Cow;
+    use std::cell::Cell;
+
+    #[test]
+    fn frame_index_from_offset_table() {
+        // 4 fragments, 2 frames of 2 fragments each
+        let lengths = [100, 20, 50, 30];
+        let index = FrameIndex::new(2, &lengths, &[0, 136], |_| false).unwrap();
+        assert_eq!(index.fragments(0), Some(0..2));
+        assert_eq!(index.fragments(1), Some(2..4));
+
+        // offsets which do not point to fragment items are rejected,
+        // falling back to the other strategies
+        let index = FrameIndex::new(2, &lengths, &[0, 100], |i| i == 1).unwrap();
+        assert_eq!(index.fragments(0), Some(0..2));
+        assert_eq!(index.fragments(1), Some(2..4));
+        assert_eq!(FrameIndex::new(2, &lengths, &[0, 100], |_| false), None);
+    }
+
+    #[test]
+    fn frame_index_without_offset_table() {
+        // one fragment per frame
+        let index = FrameIndex::new(3, &[10, 10, 10], &[], |_| unreachable!()).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.fragments(2), Some(2..3));
+
+        // single frame
+        let index = FrameIndex::new(1, &[10, 10, 10], &[], |_| unreachable!()).unwrap();
+        assert_eq!(index.fragments(0), Some(0..3));
+
+        // frames ending with a marker
+        let index = FrameIndex::new(2, &[10, 10, 10], &[], |i| i == 1).unwrap();
+        assert_eq!(index.fragments(0), Some(0..2));
+        assert_eq!(index.fragments(1), Some(2..3));
+
+        // not enough frame markers
+        assert_eq!(FrameIndex::new(2, &[10, 10, 10], &[], |_| false), None);
+    }
+
+    #[test]
+    fn eoi_marker() {
+        assert!(FrameIndex::ends_with_eoi_marker(&[
+            0xFF, 0xD8, 0x00, 0xFF, 0xD9
+        ]));
+        assert!(FrameIndex::ends_with_eoi_marker(&[
+            0xFF, 0xD8, 0xFF, 0xD9, 0x00
+        ]));
+        assert!(!FrameIndex::ends_with_eoi_marker(&[0xFF, 0xD8, 0x00, 0x00]));
+    }
+
+    /// An object of 3 frames in 2 fragments each,
+    /// which counts how many times the frames were located.
+    struct TwoFragmentsPerFrame {
+        fragments: Vec<Vec<u8>>,
+        offset_table_calls: Cell<u32>,
+    }
+
+    impl PixelDataObject for TwoFragmentsPerFrame {
+        fn transfer_syntax_uid(&self) -> &str {
+            "1.2.840.10008.1.2.5"
+        }
+
+        fn rows(&self) -> Option<u16> {
+            Some(1)
+        }
+
+        fn cols(&self) -> Option<u16> {
+            Some(2)
+        }
+
+        fn samples_per_pixel(&self) -> Option<u16> {
+            Some(1)
+        }
+
+        fn bits_allocated(&self) -> Option<u16> {
+            Some(8)
+        }
+
+        fn bits_stored(&self) -> Option<u16> {
+            Some(8)
+        }
+
+        fn photometric_interpretation(&self) -> Option<&str> {
+            Some("MONOCHROME2")
+        }
+
+        fn number_of_frames(&self) -> Option<u32> {
+            Some(3)
+        }
+
+        fn number_of_fragments(&self) -> Option<u32> {
+            Some(self.fragments.len() as u32)
+        }
+
+        fn fragment(&self, fragment: usize) -> Option<Cow<'_, [u8]>> {
+            self.fragments.get(fragment).map(|f| Cow::Borrowed(&f[..]))
+        }
+
+        fn offset_table(&self) -> Option<Cow<'_, [u32]>> {
+            self.offset_table_calls
+                .set(self.offset_table_calls.get() + 1);
+            Some(Cow::Owned(vec![0, 20, 40]))
+        }
+
+        fn raw_pixel_data(&self) -> Option<RawPixelData> {
+            None
+        }
+    }
+
+    /// A reader which copies the encoded frame data as is.
+    struct CopyFrames;
+
+    impl PixelDataReader for CopyFrames {
+        fn decode_frame(
+            &self,
+            src: &dyn PixelDataObject,
+            frame: u32,
+            dst: &mut Vec<u8>,
+        ) -> DecodeResult<()> {
+            dst.extend_from_slice(&src.frame_data(frame).unwrap());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn decode_locates_frames_once() {
+        let src = TwoFragmentsPerFrame {
+            fragments: (0..6_u8).map(|i| vec![i, i]).collect(),
+            offset_table_calls: Cell::new(0),
+        };
+
+        let index = FrameIndex::from_object(&src).unwrap();
+        assert_eq!(index.frame_data(&src, 1).unwrap(), &[2, 2, 3, 3][..]);
+        assert_eq!(index.frame_data(&src, 3), None);
+        assert_eq!(src.frame_data(2).unwrap(), &[4, 4, 5, 5][..]);
+        src.offset_table_calls.set(0);
+
+        let mut dst = Vec::new();
+        CopyFrames.decode(&src, &mut dst).unwrap();
+        assert_eq!(dst, vec![0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5]);
+        assert_eq!(src.offset_table_calls.get(), 1);
+    }
+}
@@ -52,12 +52,15 @@ impl Decode for ExplicitVRBigEndianDecoder {
             | VR::OD
             | VR::OF
             | VR::OL
+            | VR::OV
             | VR::OW
             | VR::SQ
+            | VR::SV
             | VR::UC
             | VR::UR
             | VR::UT
-            | VR::UN => {
+            | VR::UN
+            | VR::UV => {
                 // read 2 reserved bytes, then 4 bytes for data length
                 source
                     .read_exact(&mut buf[0..2])
@@ -101,12 +101,15 @@ impl Encode for ExplicitVRBigEndianEncoder {
             | VR::OD
             | VR::OF
             | VR::OL
+            | VR::OV
             | VR::OW
             | VR::SQ
+            | VR::SV
             | VR::UC
             | VR::UR
             | VR::UT
-            | VR::UN => {
+            | VR::UN
+            | VR::UV => {
                 let mut buf = [0u8; 12];
                 BigEndian::write_u16(&mut buf[0..], de.tag().group());
                 BigEndian::write_u16(&mut buf[2..], de.tag().element());
@@ -1,7 +1,7 @@
 //! This module contains all DICOM data element encoding logic.
 use byteordered::Endianness;
 use dicom_core::value::serialize::{encode_date, encode_datetime, encode_time};
-use dicom_core::{DataElementHeader, PrimitiveValue, Tag};
+use dicom_core::{DataElementHeader, PrimitiveValue, Tag, VR};
 use snafu::{Backtrace, ResultExt, Snafu};
 use std::fmt;
 use std::io::{self, Write};
@@ -294,6 +294,13 @@ pub trait Encode {
     where
         W: Write;
 
+    /// Obtain the number of bytes that the given data element header
+    /// occupies once encoded, without writing it anywhere.
+    fn element_header_len(&self, de: DataElementHeader) -> usize {
+        // writing to a sink never fails
+        self.encode_element_header(io::sink(), de).unwrap_or(0)
+    }
+
     /// Encode and write a DICOM sequence item header to the given destination.
     /* Although item element headers are always a tag and length sequence regardless of TS,
     the encoding of the length is unknown at this level. So no default impl. */
@@ -357,6 +364,10 @@ where
         (**self).encode_element_header(to, de)
     }
 
+    fn element_header_len(&self, de: DataElementHeader) -> usize {
+        (**self).element_header_len(de)
+    }
+
     fn encode_item_header<W>(&self, to: W, len: u32) -> Result<()>
     where
         W: Write,
@@ -411,6 +422,10 @@ where
         (**self).encode_element_header(to, de)
     }
 
+    fn element_header_len(&self, de: DataElementHeader) -> usize {
+        (**self).element_header_len(de)
+    }
+
     fn encode_item_header<W>(&self, to: W, len: u32) -> Result<()>
     where
         W: Write,
@@ -464,6 +479,30 @@ pub trait EncodeTo<W: ?Sized> {
     where
         W: Write;
 
+    /// Obtain the number of bytes that the given data element header
+    /// occupies once encoded, without writing it anywhere.
+    ///
+    /// The default implementation assumes an explicit VR encoding.
+    /// Encoders of other kinds should override this method.
+    fn element_header_len(&self, de: DataElementHeader) -> usize {
+        match de.vr {
+            VR::OB
+            | VR::OD
+            | VR::OF
+            | VR::OL
+            | VR::OV
+            | VR::OW
+            | VR::SQ
+            | VR::SV
+            | VR::UC
+            | VR::UR
+            | VR::UT
+            | VR::UN
+            | VR::UV => 12,
+            _ => 8,
+        }
+    }
+
     /// Encode and write a DICOM sequence item header to the given destination.
     /* Although item element headers are always a tag and length sequence regardless of TS,
     the encoding of the length is unknown at this level. So no default impl. */
@@ -516,6 +555,10 @@ where
         (**self).encode_element_header(to, de)
     }
 
+    fn element_header_len(&self, de: DataElementHeader) -> usize {
+        (**self).element_header_len(de)
+    }
+
     fn encode_item_header(&self, to: &mut W, len: u32) -> Result<()>
     where
         W: Write,
@@ -571,6 +614,10 @@ where
         (**self).encode_element_header(to, de)
     }
 
+    fn element_header_len(&self, de: DataElementHeader) -> usize {
+        (**self).element_header_len(de)
+    }
+
     fn encode_item_header(&self, to: &mut W, len: u32) -> Result<()>
     where
         W: Write,
@@ -726,6 +773,10 @@ where
         self.inner.encode_element_header(to, de)
     }
 
+    fn element_header_len(&self, de: DataElementHeader) -> usize {
+        self.inner.element_header_len(de)
+    }
+
     fn encode_item_header(&self, to: &mut W, len: u32) -> Result<()> {
         self.inner.encode_item_header(to, len)
     }
@@ -766,4 +817,99 @@ mod tests {
         is_encode(&boxed);
         is_encode_to::<dyn Write, _>(&EncoderFor::new(boxed));
     }
+
+    /// The default header length of [`EncodeTo`]
+    /// agrees with what the explicit VR encoders write and decoders read.
+    #[test]
+    fn explicit_vr_element_header_len() {
+        use crate::decode::explicit_be::ExplicitVRBigEndianDecoder;
+        use crate::decode::explicit_le::ExplicitVRLittleEndianDecoder;
+        use crate::decode::Decode;
+        use crate::encode::explicit_be::ExplicitVRBigEndianEncoder;
+        use crate::encode::explicit_le::ExplicitVRLittleEndianEncoder;
+        use dicom_core::Length;
+
+        struct DefaultHeaderLen;
+
+        impl EncodeTo<Vec<u8>> for DefaultHeaderLen {
+            fn encode_tag(&self, _to: &mut Vec<u8>, _tag: Tag) -> Result<()> {
+                unimplemented!()
+            }
+
+            fn encode_element_header(
+                &self,
+                _to: &mut Vec<u8>,
+                _de: DataElementHeader,
+            ) -> Result<usize> {
+                unimplemented!()
+            }
+
+            fn encode_item_header(&self, _to: &mut Vec<u8>, _len: u32) -> Result<()> {
+                unimplemented!()
+            }
+
+            fn encode_item_delimiter(&self, _to: &mut Vec<u8>) -> Result<()> {
+                unimplemented!()
+            }
+
+            fn encode_sequence_delimiter(&self, _to: &mut Vec<u8>) -> Result<()> {
+                unimplemented!()
+            }
+
+            fn encode_primitive(
+                &self,
+                _to: &mut Vec<u8>,
+                _value: &PrimitiveValue,
+            ) -> Result<usize> {
+                unimplemented!()
+            }
+
+            fn encode_offset_table(
+                &self,
+                _to: &mut Vec<u8>,
+                _offset_table: &[u32],
+            ) -> Result<usize> {
+                unimplemented!()
+            }
+        }
+
+        for (vr, expected_len) in [
+            (VR::OB, 12),
+            (VR::OV, 12),
+            (VR::SQ, 12),
+            (VR::SV, 12),
+            (VR::UN, 12),
+            (VR::UV, 12),
+            (VR::FD, 8),
+            (VR::UI, 8),
+        ] {
+            let header = DataElementHeader::new(Tag(0x0009, 0x1010), vr, Length(8));
+            assert_eq!(
+                DefaultHeaderLen.element_header_len(header),
+                expected_len,
+                "default header length for {:?}",
+                vr
+            );
+
+            let mut out = Vec::new();
+            let written = ExplicitVRLittleEndianEncoder::default()
+                .encode_element_header(&mut out, header)
+                .unwrap();
+            assert_eq!(written, expected_len, "LE header length for {:?}", vr);
+            let (decoded, read) = ExplicitVRLittleEndianDecoder::default()
+                .decode_header(&mut out.as_slice())
+                .unwrap();
+            assert_eq!((decoded, read), (header, expected_len));
+
+            let mut out = Vec::new();
+            let written = ExplicitVRBigEndianEncoder::default()
+                .encode_element_header(&mut out, header)
+                .unwrap();
+            assert_eq!(written, expected_len, "BE header length for {:?}", vr);
+            let (decoded, read) = ExplicitVRBigEndianDecoder::default()
+                .decode_header(&mut out.as_slice())
+                .unwrap();
+            assert_eq!((decoded, read), (header, expected_len));
+        }
+    }
 }
@@ -320,6 +320,11 @@ pub type AdapterFreeTransferSyntax =
     TransferSyntax<NeverAdapter, NeverPixelAdapter, NeverPixelAdapter>;
 
 /// An adapter of byte read and write streams.
+///
+/// Implementations of this trait transform the raw byte stream
+/// of a DICOM data set as encoded in a given transfer syntax,
+/// such as by compressing and decompressing it.
+/// The byte stream of the file meta group is never adapted.
 pub trait DataRWAdapter<R, W> {
     /// The type of the adapted reader.
     type Reader: Read;
@@ -503,6 +508,11 @@ impl<D, R, W> TransferSyntax<D, R, W> {
         self.byte_order
     }
 
+    /// Obtain whether this transfer syntax encodes data sets in explicit VR.
+    pub const fn explicit_vr(&self) -> bool {
+        self.explicit_vr
+    }
+
     /// Obtain this transfer syntax' codec specification.
     pub fn codec(&self) -> &Codec<D, R, W> {
         &self.codec
@@ -525,6 +535,17 @@ impl<D, R, W> TransferSyntax<D, R, W> {
         matches!(self.codec, Codec::None)
     }
 
+    /// Check whether pixel data in this transfer syntax
+    /// is always in its native, unencapsulated form,
+    /// and that reading and writing data sets is supported.
+    ///
+    /// This is the case for the transfer syntaxes which are codec free,
+    /// as well as those which only adapt the data set byte stream,
+    /// such as _Deflated Explicit VR Little Endian_.
+    pub fn is_native_pixel_data(&self) -> bool {
+        matches!(self.codec, Codec::None | Codec::Dataset(Some(_)))
+    }
+
     /// Check whether neither reading nor writing of data sets is supported.
     /// If this is `true`, encoding and decoding will not be available.
     pub fn is_unsupported(&self) -> bool {
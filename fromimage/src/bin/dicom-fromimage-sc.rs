@@ -0,0 +1,177 @@
+//! A CLI tool for creating a new Secondary Capture DICOM file
+//! from one or more image files.
+//!
+//! Each image becomes one frame of the new object.
+//! Patient and study attributes can be given
+//! via command line options or via a DICOM JSON template.
+//! New SOP instance, study instance and series instance UIDs are generated
+//! unless present in the template.
+use std::path::PathBuf;
+
+use clap::Parser;
+use dicom_core::{DataElement, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_pixeldata::secondary_capture::SecondaryCaptureBuilder;
+use snafu::{Report, ResultExt, Whatever};
+
+/// Exit code for when an error emerged while reading an input file.
+const ERROR_READ: i32 = -2;
+/// Exit code for when an error emerged while building the DICOM object.
+const ERROR_BUILD: i32 = -3;
+/// Exit code for when an error emerged while writing the file.
+const ERROR_WRITE: i32 = -4;
+/// Exit code for any other error.
+const ERROR_OTHER: i32 = -128;
+
+/// Create a new Secondary Capture DICOM file from image files
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// Paths to the image files, one per frame
+    #[arg(required = true)]
+    img_files: Vec<PathBuf>,
+    /// Path to the output file
+    /// (default is to replace the first image's extension with `.dcm`)
+    #[arg(short = 'o', long = "out")]
+    output: Option<PathBuf>,
+    /// Path to a DICOM JSON file with the attributes to include
+    #[arg(long = "template")]
+    template: Option<PathBuf>,
+    /// The patient's name
+    #[arg(long = "patient-name")]
+    patient_name: Option<String>,
+    /// The patient ID
+    #[arg(long = "patient-id")]
+    patient_id: Option<String>,
+    /// The study description
+    #[arg(long = "study-description")]
+    study_description: Option<String>,
+    /// The conversion type (default is WSD, workstation)
+    #[arg(long = "conversion-type")]
+    conversion_type: Option<String>,
+    /// Encapsulate JPEG files as is instead of writing native pixel data
+    #[arg(long = "encapsulate-jpeg")]
+    encapsulate_jpeg: bool,
+    /// Create a multi-frame Secondary Capture image even from a single image
+    #[arg(long = "multi-frame")]
+    multi_frame: bool,
+    /// Print more information about the output file
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", Report::from_error(e));
+        });
+
+    run().unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_OTHER);
+    });
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        img_files,
+        output,
+        template,
+        patient_name,
+        patient_id,
+        study_description,
+        conversion_type,
+        encapsulate_jpeg,
+        multi_frame,
+        verbose,
+    } = App::parse();
+
+    let output = output.unwrap_or_else(|| {
+        let mut path = img_files[0].clone();
+        path.set_extension("dcm");
+        path
+    });
+
+    let mut builder = SecondaryCaptureBuilder::new().multi_frame(multi_frame);
+    if let Some(template) = template {
+        let json = std::fs::read_to_string(&template).unwrap_or_else(|e| {
+            tracing::error!("{}", Report::from_error(e));
+            std::process::exit(ERROR_READ);
+        });
+        let attributes: InMemDicomObject =
+            dicom_json::from_str(&json).with_whatever_context(|_| {
+                format!("Could not parse template {}", template.display())
+            })?;
+        builder = builder.attributes(attributes);
+    }
+    if let Some(patient_name) = patient_name {
+        builder = builder.attribute(DataElement::new(tags::PATIENT_NAME, VR::PN, patient_name));
+    }
+    if let Some(patient_id) = patient_id {
+        builder = builder.attribute(DataElement::new(tags::PATIENT_ID, VR::LO, patient_id));
+    }
+    if let Some(study_description) = study_description {
+        builder = builder.attribute(DataElement::new(
+            tags::STUDY_DESCRIPTION,
+            VR::LO,
+            study_description,
+        ));
+    }
+    if let Some(conversion_type) = conversion_type {
+        builder = builder.conversion_type(conversion_type);
+    }
+
+    let obj = if encapsulate_jpeg {
+        let frames = img_files
+            .iter()
+            .map(std::fs::read)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| {
+                tracing::error!("{}", Report::from_error(e));
+                std::process::exit(ERROR_READ);
+            });
+        builder.build_from_jpeg(frames)
+    } else {
+        let images = img_files
+            .iter()
+            .map(|path| image::ImageReader::open(path)?.decode().map_err(Into::into))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()
+            .unwrap_or_else(|e| {
+                tracing::error!("{}", e);
+                std::process::exit(ERROR_READ);
+            });
+        builder.build_from_images(&images)
+    }
+    .unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_BUILD);
+    });
+
+    obj.write_to_file(&output).unwrap_or_else(|e| {
+        tracing::error!("{}", Report::from_error(e));
+        std::process::exit(ERROR_WRITE);
+    });
+
+    if verbose {
+        println!(
+            "Secondary Capture image ({} frame(s), transfer syntax {}) saved to {}",
+            img_files.len(),
+            obj.meta().transfer_syntax(),
+            output.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -7,6 +7,12 @@
 //! The _Presentation LUT Shape_ attribute is set to `IDENTITY`.
 //! Other attributes are copied as is.
 //!
+//! Floating point images,
+//! or any image when requested via `--float`,
+//! are written as _Float Pixel Data_ or _Double Float Pixel Data_
+//! from the image luminance instead
+//! (integer sample values are normalized to the range between 0 and 1).
+//!
 //! The new DICOM object is saved to a new file,
 //! with the same SOP instance UID and SOP class UID as the base file,
 //! encoded in Explicit VR Little Endian.
@@ -15,7 +21,7 @@
 
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use dicom_core::{
     value::{PixelFragmentSequence, PrimitiveValue},
     DataElement, DicomValue, VR,
@@ -45,6 +51,10 @@ struct App {
     /// instead of writing native pixel data
     #[arg(long)]
     encapsulate: bool,
+    /// Write the image luminance as floating point pixel data
+    /// (default for floating point images)
+    #[arg(long = "float", value_enum, conflicts_with = "encapsulate")]
+    float: Option<FloatPixelData>,
     /// Retain the implementation class UID and version name from base DICOM
     #[arg(long)]
     retain_implementation: bool,
@@ -53,6 +63,15 @@ struct App {
     verbose: bool,
 }
 
+/// Floating point pixel data attribute to write
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum FloatPixelData {
+    /// Float Pixel Data, 32 bits per sample
+    Float,
+    /// Double Float Pixel Data, 64 bits per sample
+    Double,
+}
+
 fn main() {
     tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
         .unwrap_or_else(|e| {
@@ -64,6 +83,7 @@ fn main() {
         img_file,
         output,
         encapsulate,
+        float,
         transfer_syntax,
         retain_implementation,
         verbose,
@@ -83,7 +103,7 @@ fn main() {
     if encapsulate {
         inject_encapsulated(&mut obj, img_file, verbose)
     } else {
-        inject_image(&mut obj, img_file, verbose)
+        inject_image(&mut obj, img_file, float, verbose)
     }
     .unwrap_or_else(|e| {
         tracing::error!("{}", snafu::Report::from_error(e));
@@ -130,7 +150,12 @@ fn main() {
     }
 }
 
-fn inject_image(obj: &mut DefaultDicomObject, img_file: PathBuf, verbose: bool) -> Result<()> {
+fn inject_image(
+    obj: &mut DefaultDicomObject,
+    img_file: PathBuf,
+    float: Option<FloatPixelData>,
+    verbose: bool,
+) -> Result<()> {
     let image_reader = image::ImageReader::open(img_file).unwrap_or_else(|e| {
         tracing::error!("{}", snafu::Report::from_error(e));
         std::process::exit(-1);
@@ -143,6 +168,15 @@ fn inject_image(obj: &mut DefaultDicomObject, img_file: PathBuf, verbose: bool)
 
     let color = img.color();
 
+    let float = float.or(match color {
+        image::ColorType::Rgb32F | image::ColorType::Rgba32F => Some(FloatPixelData::Float),
+        _ => None,
+    });
+    if let Some(float) = float {
+        inject_float_image(obj, &img, float, verbose);
+        return Ok(());
+    }
+
     let bits_stored: u16 = match color {
         image::ColorType::L8 => 8,
         image::ColorType::L16 => 16,
@@ -156,6 +190,104 @@ fn inject_image(obj: &mut DefaultDicomObject, img_file: PathBuf, verbose: bool)
 
     update_from_img(obj, &img, verbose);
 
+    remove_image_attributes(obj);
+
+    let pixeldata = img.into_bytes();
+
+    obj.put(DataElement::new(
+        tags::PIXEL_DATA,
+        if bits_stored == 8 { VR::OB } else { VR::OW },
+        PrimitiveValue::from(pixeldata),
+    ));
+
+    Ok(())
+}
+
+/// Replace the image with the luminance of the given image
+/// as floating point pixel data,
+/// following the _Floating Point Image Pixel_ module.
+fn inject_float_image(
+    obj: &mut DefaultDicomObject,
+    img: &DynamicImage,
+    float: FloatPixelData,
+    verbose: bool,
+) {
+    let width = img.width();
+    let height = img.height();
+
+    if verbose {
+        println!(
+            "{}x{} {:?} image as {:?}",
+            width,
+            height,
+            img.color(),
+            float
+        );
+    }
+
+    let bits_allocated: u16 = match float {
+        FloatPixelData::Float => 32,
+        FloatPixelData::Double => 64,
+    };
+
+    obj.put(DataElement::new(
+        tags::PHOTOMETRIC_INTERPRETATION,
+        VR::CS,
+        PrimitiveValue::from("MONOCHROME2"),
+    ));
+    obj.put(DataElement::new(
+        tags::SAMPLES_PER_PIXEL,
+        VR::US,
+        PrimitiveValue::from(1_u16),
+    ));
+    obj.put(DataElement::new(
+        tags::COLUMNS,
+        VR::US,
+        PrimitiveValue::from(width as u16),
+    ));
+    obj.put(DataElement::new(
+        tags::ROWS,
+        VR::US,
+        PrimitiveValue::from(height as u16),
+    ));
+    obj.put(DataElement::new(
+        tags::BITS_ALLOCATED,
+        VR::US,
+        PrimitiveValue::from(bits_allocated),
+    ));
+
+    // not part of the floating point image pixel module
+    for tag in [
+        tags::PLANAR_CONFIGURATION,
+        tags::BITS_STORED,
+        tags::HIGH_BIT,
+        tags::PIXEL_REPRESENTATION,
+        tags::PRESENTATION_LUT_SHAPE,
+    ] {
+        obj.remove_element(tag);
+    }
+
+    remove_image_attributes(obj);
+
+    let samples = img.to_luma32f().into_raw();
+    let (tag, vr, value) = match float {
+        FloatPixelData::Float => (
+            tags::FLOAT_PIXEL_DATA,
+            VR::OF,
+            PrimitiveValue::F32(samples.into()),
+        ),
+        FloatPixelData::Double => (
+            tags::DOUBLE_FLOAT_PIXEL_DATA,
+            VR::OD,
+            PrimitiveValue::F64(samples.into_iter().map(f64::from).collect()),
+        ),
+    };
+    obj.put(DataElement::new(tag, vr, value));
+}
+
+/// Remove attributes which may no longer apply to the new image,
+/// including all pixel data attributes.
+fn remove_image_attributes(obj: &mut DefaultDicomObject) {
     for tag in [
         tags::NUMBER_OF_FRAMES,
         tags::PIXEL_ASPECT_RATIO,
@@ -173,19 +305,12 @@ fn inject_image(obj: &mut DefaultDicomObject, img_file: PathBuf, verbose: bool)
         tags::PIXEL_DATA_PROVIDER_URL,
         tags::EXTENDED_OFFSET_TABLE,
         tags::EXTENDED_OFFSET_TABLE_LENGTHS,
+        tags::PIXEL_DATA,
+        tags::FLOAT_PIXEL_DATA,
+        tags::DOUBLE_FLOAT_PIXEL_DATA,
     ] {
         obj.remove_element(tag);
     }
-
-    let pixeldata = img.into_bytes();
-
-    obj.put(DataElement::new(
-        tags::PIXEL_DATA,
-        if bits_stored == 8 { VR::OB } else { VR::OW },
-        PrimitiveValue::from(pixeldata),
-    ));
-
-    Ok(())
 }
 
 fn inject_encapsulated(
@@ -0,0 +1,530 @@
+//! Encapsulation of documents into DICOM objects.
+//!
+//! This module supports the _Encapsulated PDF_ and _Encapsulated CDA_
+//! information object definitions,
+//! in which a document file is stored verbatim
+//! in the _Encapsulated Document_ attribute.
+//!
+//! Use [`EncapsulatedDocumentBuilder`] to create a new DICOM object
+//! from a document,
+//! and [`extract_document`] to retrieve the document back
+//! from an existing DICOM object.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_object::document::{extract_document, DocumentType, EncapsulatedDocumentBuilder};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let pdf = b"%PDF-1.4\n%%EOF\n".to_vec();
+//!
+//! let obj = EncapsulatedDocumentBuilder::new(DocumentType::Pdf)
+//!     .document_title("Radiology report")
+//!     .build(pdf.clone())?;
+//!
+//! let document = extract_document(&obj)?;
+//! assert_eq!(document.mime_type, "application/pdf");
+//! assert_eq!(&*document.data, &pdf[..]);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+use std::borrow::Cow;
+
+use dicom_core::value::{ConvertValueError, DataSetSequence};
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::{tags, uids};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::instance::{put_empty_attributes, put_missing_uids};
+use crate::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject, WithMetaError};
+
+/// An error which may occur when encapsulating or extracting a document.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Document of {len} bytes is too large to encapsulate
+    DocumentTooLarge { len: usize },
+    /// Missing HL7 instance identifier of the CDA document
+    MissingHl7InstanceIdentifier,
+    /// Could not build file meta table
+    BuildMeta { source: WithMetaError },
+    /// Missing attribute {name} for an encapsulated document
+    MissingAttribute { name: &'static str },
+    /// Could not read attribute {name}
+    ConvertAttribute {
+        name: &'static str,
+        source: ConvertValueError,
+    },
+    /// Encapsulated document length {len} exceeds the size of the data ({size} bytes)
+    InvalidDocumentLength { len: u32, size: usize },
+}
+
+/// Result type for document encapsulation and extraction.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A type of document which can be encapsulated in a DICOM object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DocumentType {
+    /// Portable Document Format (PDF)
+    Pdf,
+    /// HL7 Clinical Document Architecture (CDA), in XML
+    Cda,
+}
+
+impl DocumentType {
+    /// Guess the type of a document from its contents.
+    ///
+    /// Returns `None` if the document is neither a PDF file
+    /// nor a CDA document.
+    pub fn detect(document: &[u8]) -> Option<Self> {
+        if document.starts_with(b"%PDF-") {
+            return Some(DocumentType::Pdf);
+        }
+        // look for the root element in the beginning of the document
+        let head = &document[..document.len().min(4096)];
+        if head.windows(18).any(|w| w == b"<ClinicalDocument ")
+            || head.windows(18).any(|w| w == b"<ClinicalDocument>")
+        {
+            return Some(DocumentType::Cda);
+        }
+        None
+    }
+
+    /// Obtain the MIME type of this type of document.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            DocumentType::Pdf => "application/pdf",
+            DocumentType::Cda => "text/XML",
+        }
+    }
+
+    /// Obtain the storage SOP class UID
+    /// of the information object definition for this type of document.
+    pub fn sop_class_uid(self) -> &'static str {
+        match self {
+            DocumentType::Pdf => uids::ENCAPSULATED_PDF_STORAGE,
+            DocumentType::Cda => uids::ENCAPSULATED_CDA_STORAGE,
+        }
+    }
+
+    /// Obtain the usual file extension for this type of document.
+    pub fn extension(self) -> &'static str {
+        match self {
+            DocumentType::Pdf => "pdf",
+            DocumentType::Cda => "xml",
+        }
+    }
+}
+
+/// A builder for a new DICOM object encapsulating a document.
+///
+/// The new object is encoded in Explicit VR Little Endian.
+/// The document is stored as is in the _Encapsulated Document_ attribute,
+/// padded with a trailing null byte if it has an odd length,
+/// and its original length is recorded
+/// in _Encapsulated Document Length_.
+///
+/// The document starts a new study and series of its own,
+/// unless their instance UIDs are provided as attributes.
+/// The patient and study attributes,
+/// as well as the content date and time of the document,
+/// are left empty unless provided,
+/// and the document is recorded as the first instance of a series
+/// converted at a workstation (Conversion Type `WSD`).
+#[derive(Debug, Clone)]
+pub struct EncapsulatedDocumentBuilder {
+    document_type: DocumentType,
+    document_title: Option<String>,
+    hl7_instance_identifier: Option<String>,
+    attributes: InMemDicomObject,
+}
+
+impl EncapsulatedDocumentBuilder {
+    /// Create a new builder for the given type of document.
+    pub fn new(document_type: DocumentType) -> Self {
+        EncapsulatedDocumentBuilder {
+            document_type,
+            document_title: None,
+            hl7_instance_identifier: None,
+            attributes: InMemDicomObject::new_empty(),
+        }
+    }
+
+    /// Define the title of the document.
+    pub fn document_title(mut self, document_title: impl Into<String>) -> Self {
+        self.document_title = Some(document_title.into());
+        self
+    }
+
+    /// Define the HL7 instance identifier of a CDA document,
+    /// in the form `root^extension`.
+    ///
+    /// If not defined,
+    /// it is taken from the `id` element of the CDA document.
+    pub fn hl7_instance_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.hl7_instance_identifier = Some(identifier.into());
+        self
+    }
+
+    /// Add an attribute to the new object,
+    /// such as the patient's name or the study date.
+    pub fn attribute(mut self, element: DataElement<InMemDicomObject>) -> Self {
+        self.attributes.put(element);
+        self
+    }
+
+    /// Add all attributes of the given data set to the new object.
+    pub fn attributes(mut self, attributes: InMemDicomObject) -> Self {
+        for element in attributes {
+            self.attributes.put(element);
+        }
+        self
+    }
+
+    /// Build the new DICOM object encapsulating the given document.
+    pub fn build(self, mut document: Vec<u8>) -> Result<DefaultDicomObject> {
+        let len = document.len();
+        ensure!(len < u32::MAX as usize, DocumentTooLargeSnafu { len });
+
+        let hl7_instance_identifier = match self.document_type {
+            DocumentType::Cda => Some(
+                self.hl7_instance_identifier
+                    .or_else(|| read_cda_identifier(&document))
+                    .context(MissingHl7InstanceIdentifierSnafu)?,
+            ),
+            _ => None,
+        };
+
+        let mut obj = InMemDicomObject::new_empty();
+
+        // type 2 attributes of the IOD, left empty unless provided
+        put_empty_attributes(&mut obj);
+        for (tag, vr) in [
+            (tags::CONTENT_DATE, VR::DA),
+            (tags::CONTENT_TIME, VR::TM),
+            (tags::ACQUISITION_DATE_TIME, VR::DT),
+        ] {
+            obj.put(DataElement::new(tag, vr, PrimitiveValue::Empty));
+        }
+        obj.put(DataElement::new(
+            tags::CONCEPT_NAME_CODE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::empty(),
+        ));
+        obj.put(DataElement::new(tags::SERIES_NUMBER, VR::IS, "1"));
+        obj.put(DataElement::new(tags::INSTANCE_NUMBER, VR::IS, "1"));
+        obj.put(DataElement::new(tags::BURNED_IN_ANNOTATION, VR::CS, "YES"));
+        obj.put(DataElement::new(tags::CONVERSION_TYPE, VR::CS, "WSD"));
+        obj.put(DataElement::new(
+            tags::DOCUMENT_TITLE,
+            VR::ST,
+            self.document_title.unwrap_or_default(),
+        ));
+
+        // provided attributes
+        for element in self.attributes {
+            obj.put(element);
+        }
+
+        // generated identifiers
+        put_missing_uids(&mut obj);
+
+        // encapsulated document series and module
+        obj.put(DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            self.document_type.sop_class_uid(),
+        ));
+        obj.put(DataElement::new(tags::MODALITY, VR::CS, "DOC"));
+        if let Some(identifier) = hl7_instance_identifier {
+            obj.put(DataElement::new(
+                tags::HL7_INSTANCE_IDENTIFIER,
+                VR::ST,
+                identifier,
+            ));
+        }
+        obj.put(DataElement::new(
+            tags::MIME_TYPE_OF_ENCAPSULATED_DOCUMENT,
+            VR::LO,
+            self.document_type.mime_type(),
+        ));
+        obj.put(DataElement::new(
+            tags::ENCAPSULATED_DOCUMENT_LENGTH,
+            VR::UL,
+            PrimitiveValue::from(len as u32),
+        ));
+        if len % 2 != 0 {
+            document.push(0);
+        }
+        obj.put(DataElement::new(
+            tags::ENCAPSULATED_DOCUMENT,
+            VR::OB,
+            PrimitiveValue::from(document),
+        ));
+
+        obj.with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .context(BuildMetaSnafu)
+    }
+}
+
+/// Read the HL7 instance identifier of a CDA document
+/// from the attributes of its first `id` element.
+fn read_cda_identifier(document: &[u8]) -> Option<String> {
+    let document = std::str::from_utf8(document).ok()?;
+    let start = document.find("<ClinicalDocument")?;
+    let document = &document[start..];
+    let id = document
+        .match_indices("<id")
+        .map(|(i, _)| &document[i + 3..])
+        .find(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '/'))?;
+    let id = &id[..id.find('>')?];
+
+    let attribute = |name: &str| {
+        let mut rest = id;
+        loop {
+            let i = rest.find(name)?;
+            let preceded_by_space = rest[..i].ends_with(|c: char| c.is_whitespace());
+            rest = rest[i + name.len()..].trim_start();
+            if !preceded_by_space {
+                continue;
+            }
+            if let Some(value) = rest.strip_prefix('=') {
+                let value = value.trim_start();
+                // attribute values are enclosed in single or double quotes
+                let quote = value.chars().next().filter(|c| matches!(c, '\'' | '"'))?;
+                let value = &value[quote.len_utf8()..];
+                return Some(&value[..value.find(quote)?]);
+            }
+        }
+    };
+
+    let root = attribute("root")?;
+    Some(match attribute("extension") {
+        Some(extension) => format!("{}^{}", root, extension),
+        None => root.to_string(),
+    })
+}
+
+/// A document extracted from a DICOM object.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct EncapsulatedDocument<'a> {
+    /// The MIME type of the document
+    pub mime_type: Cow<'a, str>,
+    /// The title of the document, if any
+    pub title: Option<Cow<'a, str>>,
+    /// The document data, without padding
+    pub data: Cow<'a, [u8]>,
+}
+
+impl EncapsulatedDocument<'_> {
+    /// Obtain the type of the document from its MIME type,
+    /// if it is a known type.
+    pub fn document_type(&self) -> Option<DocumentType> {
+        match self.mime_type.trim() {
+            m if m.eq_ignore_ascii_case("application/pdf") => Some(DocumentType::Pdf),
+            m if m.eq_ignore_ascii_case("text/xml") => Some(DocumentType::Cda),
+            _ => None,
+        }
+    }
+}
+
+/// Retrieve the document encapsulated in a DICOM object.
+///
+/// Padding is removed according to the _Encapsulated Document Length_
+/// attribute if present.
+/// Otherwise, a trailing null byte is removed from PDF documents,
+/// and trailing null bytes and spaces are removed from XML documents.
+pub fn extract_document(obj: &InMemDicomObject) -> Result<EncapsulatedDocument<'_>> {
+    let mime_type = read_str(
+        obj,
+        tags::MIME_TYPE_OF_ENCAPSULATED_DOCUMENT,
+        "MIMETypeOfEncapsulatedDocument",
+    )?
+    .context(MissingAttributeSnafu {
+        name: "MIMETypeOfEncapsulatedDocument",
+    })?;
+    let title = read_str(obj, tags::DOCUMENT_TITLE, "DocumentTitle")?.filter(|t| !t.is_empty());
+
+    let data = obj
+        .get(tags::ENCAPSULATED_DOCUMENT)
+        .context(MissingAttributeSnafu {
+            name: "EncapsulatedDocument",
+        })?
+        .to_bytes()
+        .context(ConvertAttributeSnafu {
+            name: "EncapsulatedDocument",
+        })?;
+
+    let len = match obj.get(tags::ENCAPSULATED_DOCUMENT_LENGTH) {
+        Some(e) if e.value().multiplicity() > 0 => {
+            let len: u32 = e.to_int().context(ConvertAttributeSnafu {
+                name: "EncapsulatedDocumentLength",
+            })?;
+            ensure!(
+                len as usize <= data.len(),
+                InvalidDocumentLengthSnafu {
+                    len,
+                    size: data.len()
+                }
+            );
+            len as usize
+        }
+        _ => {
+            let pdf = mime_type.trim().eq_ignore_ascii_case("application/pdf");
+            let padding = data
+                .iter()
+                .rev()
+                .take(if pdf { 1 } else { usize::MAX })
+                .take_while(|&&b| b == 0 || (!pdf && b == b' '))
+                .count();
+            data.len() - padding
+        }
+    };
+
+    let data = match data {
+        Cow::Borrowed(data) => Cow::Borrowed(&data[..len]),
+        Cow::Owned(mut data) => {
+            data.truncate(len);
+            Cow::Owned(data)
+        }
+    };
+
+    Ok(EncapsulatedDocument {
+        mime_type,
+        title,
+        data,
+    })
+}
+
+fn read_str<'a>(
+    obj: &'a InMemDicomObject,
+    tag: Tag,
+    name: &'static str,
+) -> Result<Option<Cow<'a, str>>> {
+    obj.get(tag)
+        .map(|e| {
+            e.to_str().map(|s| match s {
+                Cow::Borrowed(s) => Cow::Borrowed(s.trim_end_matches([' ', '\0'])),
+                Cow::Owned(s) => Cow::Owned(s.trim_end_matches([' ', '\0']).to_string()),
+            })
+        })
+        .transpose()
+        .context(ConvertAttributeSnafu { name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CDA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ClinicalDocument xmlns="urn:hl7-org:v3">
+  <typeId root="2.16.840.1.113883.1.3" extension="POCD_HD000040"/>
+  <id root="2.16.840.1.113883.19.4" extension="c266"/>
+  <title>Good Health Clinic Consultation Note</title>
+</ClinicalDocument>
+"#;
+
+    #[test]
+    fn detect_document_type() {
+        assert_eq!(DocumentType::detect(b"%PDF-1.7\n"), Some(DocumentType::Pdf));
+        assert_eq!(
+            DocumentType::detect(CDA.as_bytes()),
+            Some(DocumentType::Cda)
+        );
+        assert_eq!(DocumentType::detect(b"<html></html>"), None);
+    }
+
+    #[test]
+    fn read_identifier_of_cda_document() {
+        assert_eq!(
+            read_cda_identifier(CDA.as_bytes()).as_deref(),
+            Some("2.16.840.1.113883.19.4^c266")
+        );
+        assert_eq!(
+            read_cda_identifier(b"<ClinicalDocument><id root='1.2.3'/></ClinicalDocument>")
+                .as_deref(),
+            Some("1.2.3")
+        );
+        assert_eq!(read_cda_identifier(b"<ClinicalDocument/>"), None);
+        // unquoted attribute values are not valid XML
+        assert_eq!(
+            read_cda_identifier(
+                "<ClinicalDocument><id root=é1.2.3é/></ClinicalDocument>".as_bytes()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn encapsulate_and_extract_pdf() {
+        // odd length
+        let pdf = b"%PDF-1.4\n%%EOF\n".to_vec();
+        let obj = EncapsulatedDocumentBuilder::new(DocumentType::Pdf)
+            .document_title("Report")
+            .attribute(DataElement::new(tags::PATIENT_ID, VR::LO, "123"))
+            .build(pdf.clone())
+            .unwrap();
+
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::ENCAPSULATED_PDF_STORAGE
+        );
+        assert_eq!(
+            obj.element(tags::PATIENT_ID).unwrap().to_str().unwrap(),
+            "123"
+        );
+        assert_eq!(
+            obj.element(tags::MODALITY).unwrap().to_str().unwrap(),
+            "DOC"
+        );
+        assert_eq!(
+            obj.element(tags::ENCAPSULATED_DOCUMENT_LENGTH)
+                .unwrap()
+                .to_int::<u32>()
+                .unwrap(),
+            pdf.len() as u32
+        );
+        assert_eq!(
+            obj.element(tags::ENCAPSULATED_DOCUMENT)
+                .unwrap()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            pdf.len() + 1
+        );
+
+        let document = extract_document(&obj).unwrap();
+        assert_eq!(document.document_type(), Some(DocumentType::Pdf));
+        assert_eq!(document.title.as_deref(), Some("Report"));
+        assert_eq!(&*document.data, &pdf[..]);
+    }
+
+    #[test]
+    fn encapsulate_and_extract_cda() {
+        let obj = EncapsulatedDocumentBuilder::new(DocumentType::Cda)
+            .build(CDA.as_bytes().to_vec())
+            .unwrap();
+        assert_eq!(
+            obj.element(tags::HL7_INSTANCE_IDENTIFIER)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "2.16.840.1.113883.19.4^c266"
+        );
+
+        // without the document length, trailing padding is removed
+        let mut obj = obj.into_inner();
+        obj.remove_element(tags::ENCAPSULATED_DOCUMENT_LENGTH);
+        let document = extract_document(&obj).unwrap();
+        assert_eq!(document.document_type(), Some(DocumentType::Cda));
+        assert_eq!(document.title, None);
+        assert_eq!(&*document.data, CDA.as_bytes());
+
+        // CDA documents require an instance identifier
+        assert!(EncapsulatedDocumentBuilder::new(DocumentType::Cda)
+            .build(b"<ClinicalDocument/>".to_vec())
+            .is_err());
+    }
+}
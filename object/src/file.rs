@@ -5,8 +5,12 @@ use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 
 // re-export from dicom_parser
 pub use dicom_parser::dataset::read::OddLengthStrategy;
+pub use dicom_parser::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics};
 
-use crate::{DefaultDicomObject, ReadError};
+use crate::recover::{recover_from_bytes, RecoveryOptions, RecoveryWarning};
+use crate::{DefaultDicomObject, OpenFileSnafu, ReadError, ReadFileSnafu, ReadSourceSnafu};
+use snafu::ResultExt;
+use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
@@ -62,6 +66,8 @@ pub struct OpenFileOptions<D = StandardDataDictionary, T = TransferSyntaxRegistr
     read_until: Option<Tag>,
     read_preamble: ReadPreamble,
     odd_length: OddLengthStrategy,
+    diagnostics: Option<Diagnostics>,
+    preserve_encoding: bool,
 }
 
 impl OpenFileOptions {
@@ -102,6 +108,59 @@ impl<D, T> OpenFileOptions<D, T> {
         self
     }
 
+    /// Set a collector for non-fatal anomalies found while reading the data set,
+    /// such as odd value lengths or text in an invalid character set.
+    ///
+    /// The collector can be cloned beforehand
+    /// so that the diagnostics can be retrieved after the file is read.
+    ///
+    /// ```no_run
+    /// # use dicom_object::OpenFileOptions;
+    /// use dicom_object::file::Diagnostics;
+    ///
+    /// let diagnostics = Diagnostics::new();
+    /// let file = OpenFileOptions::new()
+    ///     .diagnostics(diagnostics.clone())
+    ///     .open_file("path/to/file.dcm")?;
+    /// for diagnostic in diagnostics.take() {
+    ///     eprintln!("[WARN] {}", diagnostic);
+    /// }
+    /// # Result::<(), Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    /// Set whether to keep the original encoding details of the data set,
+    /// so that the object can be written back byte for byte.
+    ///
+    /// When enabled,
+    /// the original order of the data elements is recorded,
+    /// including data elements with a repeated tag,
+    /// and odd value lengths are kept when writing.
+    /// Defined and undefined sequence and item lengths,
+    /// as well as the padding of text values,
+    /// are kept regardless of this option.
+    /// The object is written in the standard way again
+    /// once it is modified.
+    ///
+    /// Text which is not valid in the declared character set
+    /// cannot be reproduced exactly.
+    ///
+    /// ```no_run
+    /// # use dicom_object::OpenFileOptions;
+    /// let file = OpenFileOptions::new()
+    ///     .preserve_encoding(true)
+    ///     .open_file("path/to/file.dcm")?;
+    /// file.write_to_file("path/to/copy.dcm")?;
+    /// # Result::<(), Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn preserve_encoding(mut self, preserve_encoding: bool) -> Self {
+        self.preserve_encoding = preserve_encoding;
+        self
+    }
+
     /// Set the transfer syntax index to use when reading the file.
     pub fn transfer_syntax_index<Tr>(self, ts_index: Tr) -> OpenFileOptions<D, Tr>
     where
@@ -113,11 +172,13 @@ impl<D, T> OpenFileOptions<D, T> {
             read_preamble: self.read_preamble,
             ts_index,
             odd_length: self.odd_length,
+            diagnostics: self.diagnostics,
+            preserve_encoding: self.preserve_encoding,
         }
     }
 
     /// Set the transfer syntax index to use when reading the file.
-    #[deprecated(since="0.8.1", note="please use `transfer_syntax_index` instead")]
+    #[deprecated(since = "0.8.1", note = "please use `transfer_syntax_index` instead")]
     pub fn tranfer_syntax_index<Tr>(self, ts_index: Tr) -> OpenFileOptions<D, Tr>
     where
         Tr: TransferSyntaxIndex,
@@ -137,6 +198,8 @@ impl<D, T> OpenFileOptions<D, T> {
             read_preamble: self.read_preamble,
             ts_index: self.ts_index,
             odd_length: self.odd_length,
+            diagnostics: self.diagnostics,
+            preserve_encoding: self.preserve_encoding,
         }
     }
 
@@ -155,6 +218,8 @@ impl<D, T> OpenFileOptions<D, T> {
             self.read_until,
             self.read_preamble,
             self.odd_length,
+            self.diagnostics,
+            self.preserve_encoding,
         )
     }
 
@@ -177,8 +242,94 @@ impl<D, T> OpenFileOptions<D, T> {
             self.read_until,
             self.read_preamble,
             self.odd_length,
+            self.diagnostics,
+            self.preserve_encoding,
         )
     }
+
+    /// Open the file at the given path in recovery mode.
+    ///
+    /// Unlike [`open_file`](OpenFileOptions::open_file),
+    /// this method tolerates a missing or malformed file meta group,
+    /// a transfer syntax which does not match the actual encoding,
+    /// and truncated or otherwise unreadable trailing data.
+    /// All data elements read before the point of corruption are kept.
+    /// Each irregularity found is reported
+    /// in the returned list of warnings.
+    ///
+    /// See the [`recover`](crate::recover) module for more details.
+    pub fn open_file_with_recovery<P>(
+        self,
+        path: P,
+    ) -> Result<(DefaultDicomObject<D>, Vec<RecoveryWarning>)>
+    where
+        P: AsRef<Path>,
+        D: DataDictionary,
+        D: Clone,
+        T: TransferSyntaxIndex,
+    {
+        let path = path.as_ref();
+        let mut file = File::open(path).with_context(|_| OpenFileSnafu { filename: path })?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .with_context(|_| ReadFileSnafu { filename: path })?;
+        self.recover(&bytes, Vec::new())
+    }
+
+    /// Obtain a DICOM object by reading from a byte source in recovery mode.
+    ///
+    /// The whole source is read to the end.
+    /// If reading fails midway,
+    /// the bytes obtained so far are used and a warning is reported.
+    ///
+    /// See [`open_file_with_recovery`](OpenFileOptions::open_file_with_recovery)
+    /// for the problems which can be recovered from.
+    pub fn from_reader_with_recovery<R>(
+        self,
+        mut from: R,
+    ) -> Result<(DefaultDicomObject<D>, Vec<RecoveryWarning>)>
+    where
+        R: Read,
+        D: DataDictionary,
+        D: Clone,
+        T: TransferSyntaxIndex,
+    {
+        let mut bytes = Vec::new();
+        let mut warnings = Vec::new();
+        if let Err(e) = from.read_to_end(&mut bytes) {
+            if bytes.is_empty() {
+                return Err(e).context(ReadSourceSnafu);
+            }
+            warnings.push(RecoveryWarning::IncompleteSource {
+                bytes_read: bytes.len() as u64,
+                reason: e.to_string(),
+            });
+        }
+        self.recover(&bytes, warnings)
+    }
+
+    fn recover(
+        self,
+        bytes: &[u8],
+        mut warnings: Vec<RecoveryWarning>,
+    ) -> Result<(DefaultDicomObject<D>, Vec<RecoveryWarning>)>
+    where
+        D: DataDictionary,
+        D: Clone,
+        T: TransferSyntaxIndex,
+    {
+        let options = RecoveryOptions {
+            dict: self.data_dictionary,
+            ts_index: self.ts_index,
+            read_until: self.read_until,
+            read_preamble: self.read_preamble,
+            odd_length: self.odd_length,
+            diagnostics: self.diagnostics,
+            preserve_encoding: self.preserve_encoding,
+        };
+        let obj = recover_from_bytes(bytes, options, &mut warnings)?;
+        Ok((obj, warnings))
+    }
 }
 
 /// An enumerate of supported options for
@@ -0,0 +1,83 @@
+//! Common attributes of newly created composite instances.
+//!
+//! These functions help in building new DICOM objects from scratch,
+//! by filling in the attributes shared by most information object definitions:
+//! the type 2 attributes of the common modules,
+//! which may be left empty,
+//! and the instance UIDs,
+//! which are generated with [`new_uid`](crate::uid::new_uid).
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_core::{DataElement, VR};
+//! # use dicom_dictionary_std::tags;
+//! use dicom_object::instance::{put_empty_attributes, put_missing_uids};
+//! use dicom_object::InMemDicomObject;
+//!
+//! let mut obj = InMemDicomObject::new_empty();
+//! put_empty_attributes(&mut obj);
+//! obj.put(DataElement::new(tags::PATIENT_ID, VR::LO, "12345"));
+//! put_missing_uids(&mut obj);
+//!
+//! assert_eq!(obj.element(tags::PATIENT_ID)?.to_str()?, "12345");
+//! assert_eq!(obj.element(tags::PATIENT_NAME)?.to_str()?, "");
+//! assert!(obj.get(tags::SOP_INSTANCE_UID).is_some());
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::uid::new_uid;
+use crate::InMemDicomObject;
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+
+/// Put the type 2 attributes of the _Patient_, _General Study_,
+/// _General Series_ and _General Equipment_ modules,
+/// all of them empty.
+pub fn put_empty_attributes(obj: &mut InMemDicomObject) {
+    for (tag, vr) in [
+        (tags::PATIENT_NAME, VR::PN),
+        (tags::PATIENT_ID, VR::LO),
+        (tags::PATIENT_BIRTH_DATE, VR::DA),
+        (tags::PATIENT_SEX, VR::CS),
+        (tags::STUDY_DATE, VR::DA),
+        (tags::STUDY_TIME, VR::TM),
+        (tags::REFERRING_PHYSICIAN_NAME, VR::PN),
+        (tags::STUDY_ID, VR::SH),
+        (tags::ACCESSION_NUMBER, VR::SH),
+        (tags::SERIES_NUMBER, VR::IS),
+        (tags::MANUFACTURER, VR::LO),
+    ] {
+        obj.put(DataElement::new(tag, vr, PrimitiveValue::Empty));
+    }
+}
+
+/// Put the type 2 attributes of the _General Image_ module,
+/// all of them empty.
+pub fn put_empty_image_attributes(obj: &mut InMemDicomObject) {
+    for (tag, vr) in [
+        (tags::INSTANCE_NUMBER, VR::IS),
+        (tags::PATIENT_ORIENTATION, VR::CS),
+        (tags::CONTENT_DATE, VR::DA),
+        (tags::CONTENT_TIME, VR::TM),
+    ] {
+        obj.put(DataElement::new(tag, vr, PrimitiveValue::Empty));
+    }
+}
+
+/// Generate the SOP instance, study instance and series instance UIDs
+/// which are not already in the object.
+///
+/// Providing the study or series instance UID beforehand
+/// places the new instance in an existing study or series.
+pub fn put_missing_uids(obj: &mut InMemDicomObject) {
+    for tag in [
+        tags::SOP_INSTANCE_UID,
+        tags::STUDY_INSTANCE_UID,
+        tags::SERIES_INSTANCE_UID,
+    ] {
+        if obj.get(tag).is_none() {
+            obj.put(DataElement::new(tag, VR::UI, new_uid()));
+        }
+    }
+}
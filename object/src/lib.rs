@@ -133,11 +133,16 @@
 //! # }
 //! # run().unwrap();
 //! ```
+pub mod document;
 pub mod file;
+pub mod instance;
 pub mod mem;
 pub mod meta;
 pub mod ops;
+pub mod recover;
+pub mod sr;
 pub mod tokens;
+pub mod uid;
 
 pub use crate::file::{from_reader, open_file, OpenFileOptions};
 pub use crate::mem::InMemDicomObject;
@@ -146,14 +151,17 @@ use dicom_core::ops::AttributeSelector;
 use dicom_core::DataDictionary;
 pub use dicom_core::Tag;
 pub use dicom_dictionary_std::StandardDataDictionary;
+pub use dicom_parser::dataset::write::{DataSetWriterOptions, SequenceLengthStrategy};
 
 /// The default implementation of a root DICOM object.
 pub type DefaultDicomObject<D = StandardDataDictionary> = FileDicomObject<mem::InMemDicomObject<D>>;
 
 use dicom_core::header::{GroupNumber, Header};
 use dicom_encoding::adapters::{PixelDataObject, RawPixelData};
+use dicom_encoding::text::SpecificCharacterSet;
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
-use dicom_parser::dataset::{DataSetWriter, IntoTokens};
+use dicom_encoding::{Codec, TransferSyntax};
+use dicom_parser::dataset::{DataSetWriter, DataToken, IntoTokens};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use smallvec::SmallVec;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
@@ -217,6 +225,11 @@ pub enum ReadError {
         backtrace: Backtrace,
         source: std::io::Error,
     },
+    /// Could not read from the byte source
+    ReadSource {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
     /// Could not read preamble bytes
     ReadPreambleBytes {
         backtrace: Backtrace,
@@ -293,6 +306,11 @@ pub enum WriteError {
     },
     #[snafu(display("Unsupported transfer syntax `{}`", uid))]
     WriteUnsupportedTransferSyntax { uid: String, backtrace: Backtrace },
+    #[snafu(display("Could not write data set"))]
+    WriteDataSet {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
 }
 
 /// An error which may occur during private element look-up or insertion
@@ -454,6 +472,25 @@ where
     /// Preamble, magic code, and file meta group will be included
     /// before the inner object.
     pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), WriteError> {
+        self.write_to_file_with_options(path, self.writer_options())
+    }
+
+    /// Write the entire object as a DICOM file
+    /// into the given file path,
+    /// using the given options for writing the inner data set.
+    /// Preamble, magic code, and file meta group will be included
+    /// before the inner object.
+    ///
+    /// The options are used as given.
+    /// To keep the original encoding of an object
+    /// read with encoding preservation,
+    /// enable [`preserve_encoding`](DataSetWriterOptions::preserve_encoding)
+    /// in the options as well.
+    pub fn write_to_file_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: DataSetWriterOptions,
+    ) -> Result<(), WriteError> {
         let path = path.as_ref();
         let file = File::create(path).context(WriteFileSnafu { filename: path })?;
         let mut to = BufWriter::new(file);
@@ -475,14 +512,15 @@ where
             .with_context(|| WriteUnsupportedTransferSyntaxSnafu {
                 uid: self.meta.transfer_syntax.clone(),
             })?;
-        let mut dset_writer = DataSetWriter::with_ts(to, ts).context(CreatePrinterSnafu)?;
 
         // We use the default options, because only the inner object knows if something needs to change
-        dset_writer
-            .write_sequence((&self.obj).into_tokens())
-            .context(PrintDataSetSnafu)?;
-
-        Ok(())
+        write_dataset_tokens(
+            to,
+            ts,
+            SpecificCharacterSet::default(),
+            (&self.obj).into_tokens(),
+            options,
+        )
     }
 
     /// Write the entire object as a DICOM file
@@ -490,6 +528,19 @@ where
     /// Preamble, magic code, and file meta group will be included
     /// before the inner object.
     pub fn write_all<W: Write>(&self, to: W) -> Result<(), WriteError> {
+        self.write_all_with_options(to, self.writer_options())
+    }
+
+    /// Write the entire object as a DICOM file
+    /// into the given writer,
+    /// using the given options for writing the inner data set.
+    /// Preamble, magic code, and file meta group will be included
+    /// before the inner object.
+    pub fn write_all_with_options<W: Write>(
+        &self,
+        to: W,
+        options: DataSetWriterOptions,
+    ) -> Result<(), WriteError> {
         let mut to = BufWriter::new(to);
 
         // write preamble
@@ -507,14 +558,21 @@ where
             .with_context(|| WriteUnsupportedTransferSyntaxSnafu {
                 uid: self.meta.transfer_syntax.clone(),
             })?;
-        let mut dset_writer = DataSetWriter::with_ts(to, ts).context(CreatePrinterSnafu)?;
 
         // We use the default options, because only the inner object knows if something needs to change
-        dset_writer
-            .write_sequence((&self.obj).into_tokens())
-            .context(PrintDataSetSnafu)?;
+        write_dataset_tokens(
+            to,
+            ts,
+            SpecificCharacterSet::default(),
+            (&self.obj).into_tokens(),
+            options,
+        )
+    }
 
-        Ok(())
+    /// The options for writing the inner data set,
+    /// keeping its original encoding if it was preserved.
+    fn writer_options(&self) -> DataSetWriterOptions {
+        DataSetWriterOptions::default().preserve_encoding((&self.obj).preserves_encoding())
     }
 
     /// Write the file meta group set into the given writer.
@@ -529,6 +587,19 @@ where
     ///
     /// The transfer syntax is selected from the file meta table.
     pub fn write_dataset<W: Write>(&self, to: W) -> Result<(), WriteError> {
+        self.write_dataset_with_options(to, self.writer_options())
+    }
+
+    /// Write the inner data set into the given writer,
+    /// using the given options,
+    /// without preamble, magic code, nor file meta group.
+    ///
+    /// The transfer syntax is selected from the file meta table.
+    pub fn write_dataset_with_options<W: Write>(
+        &self,
+        to: W,
+        options: DataSetWriterOptions,
+    ) -> Result<(), WriteError> {
         let to = BufWriter::new(to);
 
         // prepare encoder
@@ -537,13 +608,84 @@ where
             .with_context(|| WriteUnsupportedTransferSyntaxSnafu {
                 uid: self.meta.transfer_syntax.clone(),
             })?;
-        let mut dset_writer = DataSetWriter::with_ts(to, ts).context(CreatePrinterSnafu)?;
 
         // write object
-        dset_writer
-            .write_sequence((&self.obj).into_tokens())
-            .context(PrintDataSetSnafu)?;
+        write_dataset_tokens(
+            to,
+            ts,
+            SpecificCharacterSet::default(),
+            (&self.obj).into_tokens(),
+            options,
+        )
+    }
+}
+
+/// Write a sequence of data set tokens into the given writer,
+/// encoded in accordance to the given transfer syntax.
+///
+/// If the transfer syntax declares a data set adapter
+/// (as is the case of _Deflated Explicit VR Little Endian_),
+/// the data set is adapted before it reaches the writer.
+pub(crate) fn write_dataset_tokens<W, I>(
+    mut to: W,
+    ts: &TransferSyntax,
+    cs: SpecificCharacterSet,
+    tokens: I,
+    options: DataSetWriterOptions,
+) -> Result<(), WriteError>
+where
+    W: Write,
+    I: IntoIterator<Item = DataToken>,
+{
+    let adapter = match ts.codec() {
+        Codec::Dataset(Some(adapter)) => adapter,
+        _ => return write_tokens(to, ts, cs, tokens, options),
+    };
+
+    // The adapted writer only writes its trailing bytes when dropped,
+    // where errors are lost.
+    // Adapting into memory first ensures that
+    // any failure to write the complete data set is reported.
+    let buffer = SharedBuffer::default();
+    {
+        let mut adapted = adapter.adapt_writer(Box::new(buffer.clone()));
+        write_tokens(&mut adapted, ts, cs, tokens, options)?;
+        adapted.flush().context(WriteDataSetSnafu)?;
+    }
+    to.write_all(&buffer.0.take()).context(WriteDataSetSnafu)?;
+    to.flush().context(WriteDataSetSnafu)
+}
 
+/// Write a sequence of data set tokens into the given writer as is.
+fn write_tokens<W, I>(
+    to: W,
+    ts: &TransferSyntax,
+    cs: SpecificCharacterSet,
+    tokens: I,
+    options: DataSetWriterOptions,
+) -> Result<(), WriteError>
+where
+    W: Write,
+    I: IntoIterator<Item = DataToken>,
+{
+    DataSetWriter::with_ts_cs(to, ts, cs)
+        .context(CreatePrinterSnafu)?
+        .with_options(options)
+        .write_sequence(tokens)
+        .context(PrintDataSetSnafu)
+}
+
+/// An in-memory byte sink which remains accessible
+/// after being handed over to a data set adapter.
+#[derive(Debug, Default, Clone)]
+struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
         Ok(())
     }
 }
@@ -680,6 +822,20 @@ where
             .map(|s| s.trim_end())
     }
 
+    /// Return the PixelRepresentation attribute or None if it is not set
+    fn pixel_representation(&self) -> Option<u16> {
+        self.get(dicom_dictionary_std::tags::PIXEL_REPRESENTATION)?
+            .uint16()
+            .ok()
+    }
+
+    /// Return the PlanarConfiguration attribute or None if it is not set
+    fn planar_configuration(&self) -> Option<u16> {
+        self.get(dicom_dictionary_std::tags::PLANAR_CONFIGURATION)?
+            .uint16()
+            .ok()
+    }
+
     /// Return the NumberOfFrames attribute or None if it is not set
     fn number_of_frames(&self) -> Option<u32> {
         self.get(dicom_dictionary_std::tags::NUMBER_OF_FRAMES)?
@@ -723,6 +879,30 @@ where
         }
     }
 
+    /// Return the Extended Offset Table attribute
+    /// or None if it is not found
+    fn extended_offset_table(&self) -> Option<Cow<'_, [u64]>> {
+        let table = self.get(dicom_dictionary_std::tags::EXTENDED_OFFSET_TABLE)?;
+        match table.value() {
+            dicom_core::DicomValue::Primitive(dicom_core::PrimitiveValue::U64(v)) => {
+                Some(Cow::Borrowed(v))
+            }
+            dicom_core::DicomValue::Primitive(dicom_core::PrimitiveValue::U8(bytes)) => {
+                Some(Cow::Owned(
+                    bytes
+                        .chunks_exact(8)
+                        .map(|chunk| {
+                            let mut buf = [0; 8];
+                            buf.copy_from_slice(chunk);
+                            u64::from_le_bytes(buf)
+                        })
+                        .collect(),
+                ))
+            }
+            value => value.to_multi_int().ok().map(Cow::Owned),
+        }
+    }
+
     /// Should return either a byte slice/vector if native pixel data
     /// or byte fragments if encapsulated.
     /// Returns None if no pixel data is found
@@ -753,10 +933,14 @@ where
 
 #[cfg(test)]
 mod tests {
-    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, Length, PrimitiveValue, VR};
 
     use crate::meta::FileMetaTableBuilder;
-    use crate::{AccessError, FileDicomObject, InMemDicomObject};
+    use crate::{
+        AccessError, DataSetWriterOptions, FileDicomObject, InMemDicomObject,
+        SequenceLengthStrategy,
+    };
 
     fn assert_type_not_too_large<T>(max_size: usize) {
         let size = std::mem::size_of::<T>();
@@ -889,6 +1073,67 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn file_dicom_object_write_with_sequence_length_strategy() {
+        let item = InMemDicomObject::from_element_iter([DataElement::new(
+            dicom_dictionary_std::tags::REFERENCED_SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from("1.2.3"),
+        )]);
+        let obj = InMemDicomObject::from_element_iter([DataElement::new(
+            dicom_dictionary_std::tags::REFERENCED_IMAGE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::new(vec![item], Length::UNDEFINED),
+        )]);
+        let obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.7")
+                    .media_storage_sop_instance_uid("1.2.987654321")
+                    .transfer_syntax("1.2.840.10008.1.2.1"),
+            )
+            .unwrap();
+
+        let mut undefined = Vec::new();
+        obj.write_dataset_with_options(
+            &mut undefined,
+            DataSetWriterOptions::default().sequence_length(SequenceLengthStrategy::Undefined),
+        )
+        .unwrap();
+        let mut explicit = Vec::new();
+        obj.write_dataset_with_options(
+            &mut explicit,
+            DataSetWriterOptions::default().sequence_length(SequenceLengthStrategy::Explicit),
+        )
+        .unwrap();
+
+        // sequence and item delimiters are gone
+        assert_eq!(explicit.len() + 16, undefined.len());
+        // sequence length: 8 + 14
+        assert_eq!(&explicit[8..12], &[0x16, 0x00, 0x00, 0x00]);
+        // item length: 14
+        assert_eq!(&explicit[16..20], &[0x0e, 0x00, 0x00, 0x00]);
+
+        let ts = dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased();
+        for data in [explicit, undefined] {
+            let read = InMemDicomObject::read_dataset_with_ts(&data[..], &ts).unwrap();
+            let items = read
+                .element(dicom_dictionary_std::tags::REFERENCED_IMAGE_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap();
+            assert_eq!(items.len(), 1);
+            assert_eq!(
+                items[0]
+                    .element(dicom_dictionary_std::tags::REFERENCED_SOP_INSTANCE_UID)
+                    .unwrap()
+                    .to_str()
+                    .unwrap(),
+                "1.2.3",
+            );
+        }
+    }
+
     #[test]
     pub fn file_dicom_can_update_meta() {
         let meta = FileMetaTableBuilder::new()
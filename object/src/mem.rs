@@ -36,10 +36,18 @@
 //! If necessary, this number can be obtained via the [`HasLength`] trait.
 //! However, any modifications made to the object will reset this length
 //! to [_undefined_](dicom_core::Length::UNDEFINED).
+//!
+//! Likewise, objects read with
+//! [encoding preservation](crate::OpenFileOptions::preserve_encoding)
+//! remember the original order of their data elements,
+//! including elements with a repeated tag,
+//! until they are modified.
 use dicom_core::ops::{
     ApplyOp, AttributeAction, AttributeOp, AttributeSelector, AttributeSelectorStep,
 };
 use dicom_parser::dataset::read::{DataSetReaderOptions, OddLengthStrategy};
+use dicom_parser::dataset::write::DataSetWriterOptions;
+use dicom_parser::diagnostics::Diagnostics;
 use itertools::Itertools;
 use smallvec::SmallVec;
 use snafu::{ensure, OptionExt, ResultExt};
@@ -56,12 +64,12 @@ use crate::ops::{
 use crate::{meta::FileMetaTable, FileMetaTableBuilder};
 use crate::{
     AccessByNameError, AccessError, AtAccessError, BuildMetaTableSnafu, CreateParserSnafu,
-    CreatePrinterSnafu, DicomObject, ElementNotFoundSnafu, FileDicomObject, InvalidGroupSnafu,
+    DicomObject, ElementNotFoundSnafu, FileDicomObject, InvalidGroupSnafu,
     MissingElementValueSnafu, MissingLeafElementSnafu, NoSpaceSnafu, NoSuchAttributeNameSnafu,
     NoSuchDataElementAliasSnafu, NoSuchDataElementTagSnafu, NotASequenceSnafu, OpenFileSnafu,
     ParseMetaDataSetSnafu, ParseSopAttributeSnafu, PrematureEndSnafu, PrepareMetaTableSnafu,
     PrintDataSetSnafu, PrivateCreatorNotFoundSnafu, PrivateElementError, ReadError, ReadFileSnafu,
-    ReadPreambleBytesSnafu, ReadTokenSnafu, ReadUnsupportedTransferSyntaxSnafu,
+    ReadPreambleBytesSnafu, ReadSourceSnafu, ReadTokenSnafu, ReadUnsupportedTransferSyntaxSnafu,
     UnexpectedTokenSnafu, WithMetaError, WriteError,
 };
 use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
@@ -70,7 +78,7 @@ use dicom_core::value::{DataSetSequence, PixelFragmentSequence, Value, ValueType
 use dicom_core::{DataElement, Length, PrimitiveValue, Tag, VR};
 use dicom_dictionary_std::{tags, StandardDataDictionary};
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
-use dicom_encoding::{encode::EncodeTo, text::SpecificCharacterSet, TransferSyntax};
+use dicom_encoding::{encode::EncodeTo, text::SpecificCharacterSet, Codec, TransferSyntax};
 use dicom_parser::dataset::{DataSetReader, DataToken, IntoTokensOptions};
 use dicom_parser::{
     dataset::{read::Error as ParserError, DataSetWriter, IntoTokens},
@@ -106,6 +114,57 @@ pub struct InMemDicomObject<D = StandardDataDictionary> {
     /// because changing the character set may change the length in bytes of
     /// stored text. It has to be public for now because we need
     pub(crate) charset_changed: bool,
+    /// The original arrangement of the data elements,
+    /// only present if the object was read with encoding preservation
+    /// and has not been modified since.
+    layout: Option<Box<EncodingLayout<D>>>,
+}
+
+/// The original arrangement of the data elements in an encoded data set.
+#[derive(Debug, Clone)]
+struct EncodingLayout<D> {
+    /// the data set's elements in their original order
+    order: Vec<LayoutSlot>,
+    /// elements which were superseded by a later element with the same tag
+    duplicates: Vec<InMemElement<D>>,
+}
+
+/// The place of a data element in an [`EncodingLayout`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum LayoutSlot {
+    /// the element with this tag in the object's element map
+    Entry(Tag),
+    /// the element at this index in the list of duplicates
+    Duplicate(usize),
+}
+
+impl<D> EncodingLayout<D> {
+    fn new() -> Self {
+        EncodingLayout {
+            order: Vec::new(),
+            duplicates: Vec::new(),
+        }
+    }
+
+    /// Insert an element into the given element map,
+    /// recording its position in the data set.
+    fn insert(&mut self, entries: &mut BTreeMap<Tag, InMemElement<D>>, elem: InMemElement<D>) {
+        let tag = elem.tag();
+        if let Some(previous) = entries.insert(tag, elem) {
+            // the superseded element keeps its original position
+            let index = self.duplicates.len();
+            self.duplicates.push(previous);
+            if let Some(slot) = self
+                .order
+                .iter_mut()
+                .rev()
+                .find(|slot| **slot == LayoutSlot::Entry(tag))
+            {
+                *slot = LayoutSlot::Duplicate(index);
+            }
+        }
+        self.order.push(LayoutSlot::Entry(tag));
+    }
 }
 
 impl<D> PartialEq for InMemDicomObject<D> {
@@ -175,6 +234,7 @@ impl InMemDicomObject<StandardDataDictionary> {
             dict: StandardDataDictionary,
             len: Length::UNDEFINED,
             charset_changed: false,
+            layout: None,
         }
     }
 
@@ -277,6 +337,7 @@ where
                 dict,
                 len: Length::UNDEFINED,
                 charset_changed: false,
+                layout: None,
             },
         }
     }
@@ -317,6 +378,8 @@ where
             None,
             ReadPreamble::Auto,
             Default::default(),
+            None,
+            false,
         )
     }
 
@@ -345,6 +408,7 @@ where
         Ok(ReadPreamble::Auto)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn open_file_with_all_options<P, R>(
         path: P,
         dict: D,
@@ -352,6 +416,8 @@ where
         read_until: Option<Tag>,
         mut read_preamble: ReadPreamble,
         odd_length: OddLengthStrategy,
+        diagnostics: Option<Diagnostics>,
+        preserve_encoding: bool,
     ) -> Result<Self, ReadError>
     where
         P: AsRef<Path>,
@@ -380,19 +446,15 @@ where
         if let Some(ts) = ts_index.get(&meta.transfer_syntax) {
             let mut options = DataSetReaderOptions::default();
             options.odd_length = odd_length;
-            let mut dataset = DataSetReader::new_with_ts_cs_options(
+            let obj = InMemDicomObject::read_dataset_adapted(
                 file,
+                dict,
                 ts,
                 SpecificCharacterSet::default(),
                 options,
-            )
-            .context(CreateParserSnafu)?;
-            let obj = InMemDicomObject::build_object(
-                &mut dataset,
-                dict,
-                false,
-                Length::UNDEFINED,
+                diagnostics,
                 read_until,
+                preserve_encoding,
             )?;
 
             // if Media Storage SOP Class UID is empty attempt to infer from SOP Class UID
@@ -465,9 +527,12 @@ where
             None,
             ReadPreamble::Auto,
             Default::default(),
+            None,
+            false,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn from_reader_with_all_options<'s, S, R>(
         src: S,
         dict: D,
@@ -475,6 +540,8 @@ where
         read_until: Option<Tag>,
         mut read_preamble: ReadPreamble,
         odd_length: OddLengthStrategy,
+        diagnostics: Option<Diagnostics>,
+        preserve_encoding: bool,
     ) -> Result<Self, ReadError>
     where
         S: Read + 's,
@@ -500,18 +567,15 @@ where
         if let Some(ts) = ts_index.get(&meta.transfer_syntax) {
             let mut options = DataSetReaderOptions::default();
             options.odd_length = odd_length;
-            let mut dataset = DataSetReader::new_with_ts_options(
+            let obj = InMemDicomObject::read_dataset_adapted(
                 file,
+                dict,
                 ts,
+                SpecificCharacterSet::default(),
                 options,
-            )
-            .context(CreateParserSnafu)?;
-            let obj = InMemDicomObject::build_object(
-                &mut dataset,
-                dict,
-                false,
-                Length::UNDEFINED,
+                diagnostics,
                 read_until,
+                preserve_encoding,
             )?;
             Ok(FileDicomObject { meta, obj })
         } else {
@@ -533,6 +597,7 @@ impl FileDicomObject<InMemDicomObject<StandardDataDictionary>> {
                 dict: StandardDataDictionary,
                 len: Length::UNDEFINED,
                 charset_changed: false,
+                layout: None,
             },
         }
     }
@@ -550,6 +615,7 @@ where
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            layout: None,
         }
     }
 
@@ -564,6 +630,7 @@ where
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            layout: None,
         })
     }
 
@@ -578,6 +645,7 @@ where
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            layout: None,
         }
     }
 
@@ -615,6 +683,7 @@ where
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            layout: None,
         }
     }
 
@@ -627,7 +696,7 @@ where
         D: DataDictionary,
     {
         let mut dataset = DataSetReader::new(decoder, Default::default());
-        InMemDicomObject::build_object(&mut dataset, dict, false, Length::UNDEFINED, None)
+        InMemDicomObject::build_object(&mut dataset, dict, false, Length::UNDEFINED, None, false)
     }
 
     /// Read an object from a source,
@@ -663,8 +732,16 @@ where
         D: DataDictionary,
     {
         let from = BufReader::new(from);
-        let mut dataset = DataSetReader::new_with_ts_cs(from, ts, cs).context(CreateParserSnafu)?;
-        InMemDicomObject::build_object(&mut dataset, dict, false, Length::UNDEFINED, None)
+        InMemDicomObject::read_dataset_adapted(
+            from,
+            dict,
+            ts,
+            cs,
+            Default::default(),
+            None,
+            None,
+            false,
+        )
     }
 
     // Standard methods follow. They are not placed as a trait implementation
@@ -833,7 +910,7 @@ where
     /// This might invalidate all sequence and item lengths if the charset of the
     /// element changes.
     pub fn put_element(&mut self, elt: InMemElement<D>) -> Option<InMemElement<D>> {
-        self.len = Length::UNDEFINED;
+        self.invalidate_encoding();
         self.invalidate_if_charset_changed(elt.tag());
         self.entries.insert(elt.tag(), elt)
     }
@@ -929,7 +1006,7 @@ where
     /// reporting whether it was present.
     pub fn remove_element(&mut self, tag: Tag) -> bool {
         if self.entries.remove(&tag).is_some() {
-            self.len = Length::UNDEFINED;
+            self.invalidate_encoding();
             true
         } else {
             false
@@ -942,7 +1019,7 @@ where
         let tag = self.lookup_name(name)?;
         Ok(self.entries.remove(&tag).is_some()).map(|removed| {
             if removed {
-                self.len = Length::UNDEFINED;
+                self.invalidate_encoding();
             }
             removed
         })
@@ -953,7 +1030,7 @@ where
         self.entries
             .remove(&tag)
             .map(|e| {
-                self.len = Length::UNDEFINED;
+                self.invalidate_encoding();
                 e
             })
             .context(NoSuchDataElementTagSnafu { tag })
@@ -964,7 +1041,7 @@ where
     /// returns `None` otherwise.
     pub fn take(&mut self, tag: Tag) -> Option<InMemElement<D>> {
         self.entries.remove(&tag).map(|e| {
-            self.len = Length::UNDEFINED;
+            self.invalidate_encoding();
             e
         })
     }
@@ -978,7 +1055,7 @@ where
         self.entries
             .remove(&tag)
             .map(|e| {
-                self.len = Length::UNDEFINED;
+                self.invalidate_encoding();
                 e
             })
             .with_context(|| NoSuchDataElementAliasSnafu {
@@ -994,7 +1071,7 @@ where
     /// and those for which `f(&element)` returns `false` are removed.
     pub fn retain(&mut self, mut f: impl FnMut(&InMemElement<D>) -> bool) {
         self.entries.retain(|_, elem| f(elem));
-        self.len = Length::UNDEFINED;
+        self.invalidate_encoding();
     }
 
     /// Obtain a temporary mutable reference to a DICOM value by tag,
@@ -1032,7 +1109,7 @@ where
         self.invalidate_if_charset_changed(tag);
         if let Some(e) = self.entries.get_mut(&tag) {
             e.update_value(f);
-            self.len = Length::UNDEFINED;
+            self.invalidate_encoding();
             true
         } else {
             false
@@ -1093,11 +1170,11 @@ where
         selector: impl Into<AttributeSelector>,
         f: impl FnMut(&mut Value<InMemDicomObject<D>, InMemFragment>),
     ) -> Result<(), AtAccessError> {
-        self.entry_at_mut(selector)
-            .map(|e| e.update_value(f))
-            .map(|_| {
-                self.len = Length::UNDEFINED;
-            })
+        let selector: AttributeSelector = selector.into();
+        // look up the element first,
+        // so that nothing is invalidated if it does not exist
+        self.entry_at(selector.clone())?;
+        self.entry_at_mut(selector).map(|e| e.update_value(f))
     }
 
     /// Obtain the DICOM value by finding the element
@@ -1239,7 +1316,9 @@ where
 
     // Get a mutable reference to a particular entry by AttributeSelector
     //
-    // Should be private for the same reason as `self.get_mut`
+    // Should be private for the same reason as `self.get_mut`.
+    // As the element may be modified,
+    // the encoding of every data set along the path is invalidated.
     fn entry_at_mut(
         &mut self,
         selector: impl Into<AttributeSelector>,
@@ -1248,6 +1327,7 @@ where
 
         let mut obj = self;
         for (i, step) in selector.iter().enumerate() {
+            obj.invalidate_encoding();
             match step {
                 // reached the leaf
                 AttributeSelectorStep::Tag(tag) => {
@@ -1359,6 +1439,9 @@ where
                         }
                     };
 
+                    // the original encoding no longer applies
+                    obj.layout = None;
+
                     // get items
                     let items = obj
                         .entries
@@ -1400,7 +1483,7 @@ where
                     let vr = e.vr();
                     // replace element
                     *e = DataElement::empty(tag, vr);
-                    self.len = Length::UNDEFINED;
+                    self.invalidate_encoding();
                 }
                 Ok(())
             }
@@ -1477,7 +1560,7 @@ where
                 Value::from(new_value)
             };
             *e = DataElement::new(tag, vr, new_value);
-            self.len = Length::UNDEFINED;
+            self.invalidate_encoding();
         } else {
             // infer VR from tag
             let vr = dicom_dictionary_std::StandardDataDictionary
@@ -1755,7 +1838,9 @@ where
         E: EncodeTo<W>,
     {
         // prepare data set writer
-        let mut dset_writer = DataSetWriter::new(to, encoder);
+        let mut dset_writer = DataSetWriter::new(to, encoder).with_options(
+            DataSetWriterOptions::default().preserve_encoding(self.preserves_encoding()),
+        );
         let required_options = IntoTokensOptions::new(self.charset_changed);
         // write object
         dset_writer
@@ -1781,16 +1866,16 @@ where
     where
         W: Write,
     {
-        // prepare data set writer
-        let mut dset_writer = DataSetWriter::with_ts_cs(to, ts, cs).context(CreatePrinterSnafu)?;
         let required_options = IntoTokensOptions::new(self.charset_changed);
 
         // write object
-        dset_writer
-            .write_sequence(self.into_tokens_with_options(required_options))
-            .context(PrintDataSetSnafu)?;
-
-        Ok(())
+        crate::write_dataset_tokens(
+            to,
+            ts,
+            cs,
+            self.into_tokens_with_options(required_options),
+            DataSetWriterOptions::default().preserve_encoding(self.preserves_encoding()),
+        )
     }
 
     /// Write this object's data set into the given writer,
@@ -1882,18 +1967,75 @@ where
 
     // private methods
 
+    /// Read an object from the given byte source
+    /// in accordance to the given transfer syntax.
+    ///
+    /// If the transfer syntax declares a data set adapter
+    /// (as is the case of _Deflated Explicit VR Little Endian_),
+    /// the source is adapted before the data set is parsed.
+    #[allow(clippy::too_many_arguments)]
+    fn read_dataset_adapted<S>(
+        mut from: S,
+        dict: D,
+        ts: &TransferSyntax,
+        cs: SpecificCharacterSet,
+        options: DataSetReaderOptions,
+        diagnostics: Option<Diagnostics>,
+        read_until: Option<Tag>,
+        preserve_encoding: bool,
+    ) -> Result<Self, ReadError>
+    where
+        S: Read,
+    {
+        let from: Box<dyn Read + '_> = if let Codec::Dataset(Some(adapter)) = ts.codec() {
+            // adapters only take owned byte sources,
+            // so the encoded data set is fetched into memory first
+            let mut data = Vec::new();
+            from.read_to_end(&mut data).context(ReadSourceSnafu)?;
+            Box::new(BufReader::new(
+                adapter.adapt_reader(Box::new(std::io::Cursor::new(data))),
+            ))
+        } else {
+            Box::new(from)
+        };
+
+        let mut dataset = DataSetReader::new_with_ts_cs_options(from, ts, cs, options)
+            .context(CreateParserSnafu)?;
+        if let Some(diagnostics) = diagnostics {
+            dataset = dataset.with_diagnostics(diagnostics);
+        }
+        InMemDicomObject::build_object(
+            &mut dataset,
+            dict,
+            false,
+            Length::UNDEFINED,
+            read_until,
+            preserve_encoding,
+        )
+    }
+
     /// Build an object by consuming a data set parser.
-    fn build_object<I>(
+    ///
+    /// If `preserve_encoding` is true,
+    /// the original order of the data elements is recorded,
+    /// including elements with a repeated tag.
+    pub(crate) fn build_object<I>(
         dataset: &mut I,
         dict: D,
         in_item: bool,
         len: Length,
         read_until: Option<Tag>,
+        preserve_encoding: bool,
     ) -> Result<Self, ReadError>
     where
         I: ?Sized + Iterator<Item = ParserResult<DataToken>>,
     {
         let mut entries: BTreeMap<Tag, InMemElement<D>> = BTreeMap::new();
+        let mut layout = if preserve_encoding {
+            Some(Box::new(EncodingLayout::new()))
+        } else {
+            None
+        };
         // perform a structured parsing of incoming tokens
         while let Some(token) = dataset.next() {
             let elem = match token.context(ReadTokenSnafu)? {
@@ -1935,7 +2077,8 @@ where
                     }
 
                     // delegate sequence building to another function
-                    let items = Self::build_sequence(tag, len, &mut *dataset, &dict)?;
+                    let items =
+                        Self::build_sequence(tag, len, &mut *dataset, &dict, preserve_encoding)?;
                     DataElement::new_with_len(
                         tag,
                         VR::SQ,
@@ -1950,11 +2093,17 @@ where
                         dict,
                         len,
                         charset_changed: false,
+                        layout,
                     });
                 }
                 token => return UnexpectedTokenSnafu { token }.fail(),
             };
-            entries.insert(elem.tag(), elem);
+            match &mut layout {
+                Some(layout) => layout.insert(&mut entries, elem),
+                None => {
+                    entries.insert(elem.tag(), elem);
+                }
+            }
         }
 
         Ok(InMemDicomObject {
@@ -1962,6 +2111,7 @@ where
             dict,
             len,
             charset_changed: false,
+            layout,
         })
     }
 
@@ -2028,6 +2178,7 @@ where
         _len: Length,
         dataset: &mut I,
         dict: &D,
+        preserve_encoding: bool,
     ) -> Result<C<InMemDicomObject<D>>, ReadError>
     where
         I: ?Sized + Iterator<Item = ParserResult<DataToken>>,
@@ -2042,6 +2193,7 @@ where
                         true,
                         len,
                         None,
+                        preserve_encoding,
                     )?);
                 }
                 DataToken::SequenceEnd => {
@@ -2063,6 +2215,57 @@ where
     }
 }
 
+impl<D> InMemDicomObject<D> {
+    /// Check whether this object keeps the original encoding
+    /// of the data set from which it was read.
+    ///
+    /// This is only the case if the object was read
+    /// with [encoding preservation](crate::OpenFileOptions::preserve_encoding)
+    /// and has not been modified since.
+    pub fn preserves_encoding(&self) -> bool {
+        self.layout.is_some()
+    }
+
+    /// Iterate over the data elements of this object
+    /// in the order in which they are to be encoded.
+    pub(crate) fn iter_in_encoding_order(&self) -> EncodingOrderIter<'_, D> {
+        EncodingOrderIter {
+            obj: self,
+            sorted: self.entries.values(),
+            slots: self.layout.as_ref().map(|layout| layout.order.iter()),
+        }
+    }
+
+    /// Convert this object into its data elements,
+    /// in the order in which they are to be encoded.
+    pub(crate) fn into_elements_in_encoding_order(self) -> Vec<InMemElement<D>> {
+        let mut entries = self.entries;
+        match self.layout {
+            None => entries.into_values().collect(),
+            Some(layout) => {
+                let EncodingLayout { order, duplicates } = *layout;
+                let mut duplicates: Vec<_> = duplicates.into_iter().map(Some).collect();
+                order
+                    .into_iter()
+                    .filter_map(|slot| match slot {
+                        LayoutSlot::Entry(tag) => entries.remove(&tag),
+                        LayoutSlot::Duplicate(index) => {
+                            duplicates.get_mut(index).and_then(Option::take)
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Discard the recorded length and original encoding layout of this object,
+    /// as they no longer apply after a modification.
+    fn invalidate_encoding(&mut self) {
+        self.len = Length::UNDEFINED;
+        self.layout = None;
+    }
+}
+
 impl<D> ApplyOp for InMemDicomObject<D>
 where
     D: DataDictionary,
@@ -2118,12 +2321,44 @@ impl<D> Iterator for Iter<D> {
     }
 }
 
+/// Iterator over the data elements of an in-memory DICOM object
+/// in encoding order.
+///
+/// This is the original order of the data elements
+/// if the object preserves its encoding,
+/// and ascending tag order otherwise.
+#[derive(Debug)]
+pub struct EncodingOrderIter<'a, D> {
+    obj: &'a InMemDicomObject<D>,
+    sorted: ::std::collections::btree_map::Values<'a, Tag, InMemElement<D>>,
+    slots: Option<::std::slice::Iter<'a, LayoutSlot>>,
+}
+
+impl<'a, D> Iterator for EncodingOrderIter<'a, D> {
+    type Item = &'a InMemElement<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let obj = self.obj;
+        let slots = match &mut self.slots {
+            Some(slots) => slots,
+            None => return self.sorted.next(),
+        };
+        slots.find_map(|slot| match *slot {
+            LayoutSlot::Entry(tag) => obj.entries.get(&tag),
+            LayoutSlot::Duplicate(index) => obj
+                .layout
+                .as_ref()
+                .and_then(|layout| layout.duplicates.get(index)),
+        })
+    }
+}
+
 impl<D> Extend<InMemElement<D>> for InMemDicomObject<D> {
     fn extend<I>(&mut self, iter: I)
     where
         I: IntoIterator<Item = InMemElement<D>>,
     {
-        self.len = Length::UNDEFINED;
+        self.invalidate_encoding();
         self.entries.extend(iter.into_iter().map(|e| (e.tag(), e)))
     }
 }
@@ -2135,7 +2370,7 @@ fn even_len(l: u32) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::open_file;
+    use crate::{open_file, OpenFileOptions};
     use byteordered::Endianness;
     use dicom_core::chrono::FixedOffset;
     use dicom_core::value::{DicomDate, DicomDateTime, DicomTime};
@@ -2429,6 +2664,335 @@ mod tests {
         assert_eq!(file_object, saved_object);
     }
 
+    /// Writes a file in Deflated Explicit VR Little Endian
+    /// and opens it to check that the data is equivalent.
+    #[test]
+    fn inmem_write_to_file_deflated() {
+        let sop_uid = "1.4.645.313131";
+        let mut obj = InMemDicomObject::new_empty();
+
+        obj.put(DataElement::new(
+            Tag(0x0010, 0x0010),
+            VR::PN,
+            dicom_value!(Strs, ["Doe^John"]),
+        ));
+        obj.put(DataElement::new(
+            Tag(0x0008, 0x0060),
+            VR::CS,
+            dicom_value!(Strs, ["CR"]),
+        ));
+        obj.put(DataElement::new(
+            Tag(0x0008, 0x0018),
+            VR::UI,
+            dicom_value!(Strs, [sop_uid]),
+        ));
+        obj.put(DataElement::new(
+            Tag(0x7FE0, 0x0010),
+            VR::OB,
+            PrimitiveValue::from(vec![0x55_u8; 1024]),
+        ));
+
+        let file_object = obj
+            .with_meta(
+                FileMetaTableBuilder::default()
+                    // Deflated Explicit VR Little Endian
+                    .transfer_syntax("1.2.840.10008.1.2.1.99")
+                    // Computed Radiography image storage
+                    .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.1")
+                    .media_storage_sop_instance_uid(sop_uid),
+            )
+            .unwrap();
+
+        // create temporary file path and write object to that file
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_path = dir.into_path();
+        file_path.push(format!("{}.dcm", sop_uid));
+
+        file_object.write_to_file(&file_path).unwrap();
+
+        // the data set is compressed, so the file is smaller than the pixel data
+        let file_len = std::fs::metadata(&file_path).unwrap().len();
+        assert!(file_len < 1024);
+
+        // read the file back to validate the outcome
+        let saved_object = open_file(&file_path).unwrap();
+        assert_eq!(file_object, saved_object);
+
+        // also works when writing to and reading from memory
+        let mut out = Vec::new();
+        file_object.write_all(&mut out).unwrap();
+        let saved_object = OpenFileOptions::new().from_reader(&out[..]).unwrap();
+        assert_eq!(file_object, saved_object);
+    }
+
+    /// Writing and reading a data set without meta group
+    /// in Deflated Explicit VR Little Endian
+    /// produces the same object.
+    #[test]
+    fn inmem_object_write_read_dataset_deflated() {
+        let mut obj = InMemDicomObject::new_empty();
+
+        obj.put(DataElement::new(
+            Tag(0x0010, 0x0010),
+            VR::PN,
+            dicom_value!(Str, "Doe^John"),
+        ));
+        obj.put(DataElement::new(
+            Tag(0x0010, 0x0020),
+            VR::LO,
+            dicom_value!(Str, "ID0001"),
+        ));
+
+        let ts = TransferSyntaxRegistry
+            .get("1.2.840.10008.1.2.1.99")
+            .unwrap();
+
+        let mut out = Vec::new();
+        obj.write_dataset_with_ts(&mut out, ts).unwrap();
+
+        // not in plain explicit VR little endian
+        assert_ne!(&out[0..4], &[0x10, 0x00, 0x10, 0x00]);
+
+        let saved_obj = InMemDicomObject::read_dataset_with_ts(&out[..], ts).unwrap();
+        assert_eq!(obj, saved_obj);
+
+        // failing to write the end of the deflated data set is an error
+        let mut out = [0_u8; 16];
+        let res = obj.write_dataset_with_ts(&mut out[..], ts);
+        assert!(matches!(res, Err(WriteError::WriteDataSet { .. })));
+    }
+
+    /// Reading a file with a diagnostics collector
+    /// records the anomalies found in the main data set.
+    #[test]
+    fn inmem_object_read_with_diagnostics() {
+        use dicom_parser::diagnostics::{DiagnosticKind, Diagnostics};
+
+        let meta = FileMetaTableBuilder::new()
+            .transfer_syntax("1.2.840.10008.1.2.1")
+            .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.7")
+            .media_storage_sop_instance_uid("2.25.1")
+            .build()
+            .unwrap();
+        let mut bytes = b"DICM".to_vec();
+        meta.write(&mut bytes).unwrap();
+        #[rustfmt::skip]
+        bytes.extend_from_slice(&[
+            0x10, 0x00, 0x10, 0x00, // (0010,0010) PatientName
+            b'P', b'N', // VR
+            0x03, 0x00, // len = 3
+            b'D', b'o', b'e',
+        ]);
+
+        let diagnostics = Diagnostics::new();
+        let obj = OpenFileOptions::new()
+            .diagnostics(diagnostics.clone())
+            .from_reader(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            obj.element(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe"
+        );
+
+        let diagnostics = diagnostics.take();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].tag, Some(tags::PATIENT_NAME));
+        assert_eq!(diagnostics[0].position, 0);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::OddLength { len: 3 });
+    }
+
+    /// Reading a file with encoding preservation
+    /// and writing it back produces the exact same bytes.
+    #[test]
+    fn inmem_object_preserve_encoding_round_trip() {
+        let meta = FileMetaTableBuilder::new()
+            .transfer_syntax("1.2.840.10008.1.2.1")
+            .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.7")
+            .media_storage_sop_instance_uid("2.25.1")
+            .build()
+            .unwrap();
+        let mut bytes = vec![0; 128];
+        bytes.extend_from_slice(b"DICM");
+        meta.write(&mut bytes).unwrap();
+        let dataset_start = bytes.len();
+        #[rustfmt::skip]
+        bytes.extend_from_slice(&[
+            // (0010,0020) PatientID, odd length
+            0x10, 0x00, 0x20, 0x00, b'L', b'O', 0x03, 0x00, b'A', b'B', b'C',
+            // (0010,0010) PatientName, out of order
+            0x10, 0x00, 0x10, 0x00, b'P', b'N', 0x04, 0x00, b'D', b'o', b'e', 0x00,
+            // (0008,1140) ReferencedImageSequence, defined length
+            0x08, 0x00, 0x40, 0x11, b'S', b'Q', 0x00, 0x00, 0x32, 0x00, 0x00, 0x00,
+            // item, undefined length
+            0xfe, 0xff, 0x00, 0xe0, 0xff, 0xff, 0xff, 0xff,
+            // (0008,1155) ReferencedSOPInstanceUID
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x04, 0x00, b'1', b'.', b'2', 0x00,
+            // (0008,1150) ReferencedSOPClassUID, out of order
+            0x08, 0x00, 0x50, 0x11, b'U', b'I', 0x04, 0x00, b'1', b'.', b'3', b' ',
+            // (0008,1155) ReferencedSOPInstanceUID, repeated
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x02, 0x00, b'4', 0x00,
+            // item delimiter
+            0xfe, 0xff, 0x0d, 0xe0, 0x00, 0x00, 0x00, 0x00,
+            // (0008,0060) Modality, out of order
+            0x08, 0x00, 0x60, 0x00, b'C', b'S', 0x02, 0x00, b'O', b'T',
+        ]);
+
+        let obj = OpenFileOptions::new()
+            .preserve_encoding(true)
+            .from_reader(&bytes[128..])
+            .unwrap();
+        assert!(obj.preserves_encoding());
+        // the last of the repeated elements is the one retrieved
+        let item = &obj
+            .get(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(
+            item.get(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "4"
+        );
+
+        let mut out = Vec::new();
+        obj.write_all(&mut out).unwrap();
+        assert_eq!(&out[dataset_start..], &bytes[dataset_start..]);
+
+        // without encoding preservation, the data set is normalized
+        let obj = OpenFileOptions::new().from_reader(&bytes[128..]).unwrap();
+        assert!(!obj.preserves_encoding());
+        let mut out = Vec::new();
+        obj.write_all(&mut out).unwrap();
+        assert_ne!(&out[dataset_start..], &bytes[dataset_start..]);
+
+        // modifying the object discards the original encoding
+        let mut obj = OpenFileOptions::new()
+            .preserve_encoding(true)
+            .from_reader(&bytes[128..])
+            .unwrap();
+        obj.put(DataElement::new(tags::PATIENT_ID, VR::LO, "XYZ"));
+        assert!(!obj.preserves_encoding());
+        let mut out = Vec::new();
+        obj.write_all(&mut out).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(
+            &out[dataset_start..dataset_start + 12],
+            &[
+                // (0008,0060) Modality comes first again
+                0x08, 0x00, 0x60, 0x00, b'C', b'S', 0x02, 0x00, b'O', b'T',
+                // (0008,1140) ReferencedImageSequence
+                0x08, 0x00,
+            ],
+        );
+        let patient_id = [
+            0x10, 0x00, 0x20, 0x00, b'L', b'O', 0x04, 0x00, b'X', b'Y', b'Z', b' ',
+        ];
+        assert!(out.ends_with(&patient_id));
+    }
+
+    /// Updating a nested value of an object with encoding preservation
+    /// discards the original encoding of every data set along the way.
+    #[test]
+    fn inmem_object_preserve_encoding_nested_update() {
+        let meta = FileMetaTableBuilder::new()
+            .transfer_syntax("1.2.840.10008.1.2.1")
+            .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.7")
+            .media_storage_sop_instance_uid("2.25.1")
+            .build()
+            .unwrap();
+        let mut bytes = vec![0; 128];
+        bytes.extend_from_slice(b"DICM");
+        meta.write(&mut bytes).unwrap();
+        #[rustfmt::skip]
+        bytes.extend_from_slice(&[
+            // (0008,1140) ReferencedImageSequence, defined length
+            0x08, 0x00, 0x40, 0x11, b'S', b'Q', 0x00, 0x00, 0x34, 0x00, 0x00, 0x00,
+            // item, defined length
+            0xfe, 0xff, 0x00, 0xe0, 0x2c, 0x00, 0x00, 0x00,
+            // (0008,1155) ReferencedSOPInstanceUID
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x04, 0x00, b'1', b'.', b'2', 0x00,
+            // (0008,1150) ReferencedSOPClassUID, out of order
+            0x08, 0x00, 0x50, 0x11, b'U', b'I', 0x04, 0x00, b'1', b'.', b'3', b' ',
+            // (0008,1155) ReferencedSOPInstanceUID, repeated
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x02, 0x00, b'4', 0x00,
+            // (0008,0060) Modality, out of order
+            0x08, 0x00, 0x60, 0x00, b'C', b'S', 0x02, 0x00, b'O', b'T',
+        ]);
+
+        let mut obj = OpenFileOptions::new()
+            .preserve_encoding(true)
+            .from_reader(&bytes[128..])
+            .unwrap();
+
+        let selector = (
+            tags::REFERENCED_IMAGE_SEQUENCE,
+            0,
+            tags::REFERENCED_SOP_CLASS_UID,
+        );
+        obj.update_value_at(selector, |value| {
+            *value = PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.7").into();
+        })
+        .unwrap();
+
+        assert!(!obj.preserves_encoding());
+        let item = &obj
+            .get(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert!(!item.preserves_encoding());
+
+        // the object is written consistently and can be read back
+        let mut out = Vec::new();
+        obj.write_all(&mut out).unwrap();
+        let saved = OpenFileOptions::new().from_reader(&out[128..]).unwrap();
+        assert_eq!(
+            saved.entry_at(selector).unwrap().to_str().unwrap(),
+            "1.2.840.10008.5.1.4.1.1.7"
+        );
+        assert_eq!(
+            saved
+                .entry_at((
+                    tags::REFERENCED_IMAGE_SEQUENCE,
+                    0,
+                    tags::REFERENCED_SOP_INSTANCE_UID,
+                ))
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "4"
+        );
+        assert_eq!(
+            saved
+                .entry_at((tags::REFERENCED_IMAGE_SEQUENCE, 0, tags::MODALITY))
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "OT"
+        );
+        let saved_item = &saved
+            .get(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(saved_item.iter().count(), 3);
+
+        // a missing element does not discard the original encoding
+        let mut obj = OpenFileOptions::new()
+            .preserve_encoding(true)
+            .from_reader(&bytes[128..])
+            .unwrap();
+        assert!(obj
+            .update_value_at(
+                (tags::REFERENCED_IMAGE_SEQUENCE, 0, tags::PATIENT_ID),
+                |_| {}
+            )
+            .is_err());
+        assert!(obj.preserves_encoding());
+    }
+
     /// Creating a file DICOM object from an in-mem DICOM object
     /// infers the SOP instance UID.
     #[test]
@@ -2799,6 +3363,7 @@ mod tests {
             false,
             Length::UNDEFINED,
             None,
+            false,
         )
         .unwrap();
 
@@ -2915,6 +3480,7 @@ mod tests {
             false,
             Length::UNDEFINED,
             None,
+            false,
         )
         .unwrap();
 
@@ -3023,6 +3589,7 @@ mod tests {
             false,
             Length::UNDEFINED,
             None,
+            false,
         )
         .unwrap();
 
@@ -3502,6 +4069,7 @@ mod tests {
             dict: StandardDataDictionary,
             len: Length(1),
             charset_changed: false,
+            layout: None,
         };
 
         assert!(obj.length().is_defined());
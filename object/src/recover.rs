@@ -0,0 +1,1377 @@
+//! Lenient reading of corrupt or non-conformant DICOM files.
+//!
+//! The functions in this module back the recovery methods of
+//! [`OpenFileOptions`](crate::OpenFileOptions),
+//! namely [`open_file_with_recovery`](crate::OpenFileOptions::open_file_with_recovery)
+//! and [`from_reader_with_recovery`](crate::OpenFileOptions::from_reader_with_recovery).
+//! Rather than failing on the first irregularity,
+//! the reader tries to work around it,
+//! salvaging as much of the data set as possible,
+//! and reports each applied workaround as a [`RecoveryWarning`].
+//!
+//! The following problems are currently handled:
+//!
+//! - missing DICM magic code or missing file meta group
+//!   (a new file meta group is synthesized from the data set);
+//! - wrong or missing file meta information group length;
+//! - a declared transfer syntax which does not match
+//!   the actual encoding of the data set
+//!   (implicit vs explicit VR and byte order are detected heuristically);
+//! - unknown transfer syntaxes;
+//! - truncated or otherwise unreadable trailing data,
+//!   including unterminated sequences and items;
+//! - sequence items in the wrong place,
+//!   such as an undefined-length item outside of a sequence
+//!   (moved into the sequence right before it,
+//!   or otherwise merged into the enclosing data set),
+//!   and sequence delimiters in place of an item delimiter
+//!   or outside of a sequence.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::OpenFileOptions;
+//! let (obj, warnings) = OpenFileOptions::new()
+//!     .open_file_with_recovery("path/to/broken.dcm")?;
+//! for warning in &warnings {
+//!     eprintln!("[WARN] {}", warning);
+//! }
+//! # Result::<(), Box<dyn std::error::Error>>::Ok(())
+//! ```
+use std::cell::Cell;
+use std::fmt;
+use std::io::{Chain, Cursor, Read};
+use std::rc::Rc;
+
+use dicom_core::{DataDictionary, DataElementHeader, Length, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_encoding::text::SpecificCharacterSet;
+use dicom_encoding::transfer_syntax::{Endianness, TransferSyntaxIndex};
+use dicom_encoding::{Codec, TransferSyntax};
+use dicom_parser::dataset::read::{DataSetReaderOptions, OddLengthStrategy};
+use dicom_parser::dataset::{DataSetReader, DataToken};
+use dicom_parser::diagnostics::Diagnostics;
+use dicom_parser::stateful::decode::DynStatefulDecoder;
+use dicom_transfer_syntax_registry::entries::{
+    EXPLICIT_VR_BIG_ENDIAN, EXPLICIT_VR_LITTLE_ENDIAN, IMPLICIT_VR_LITTLE_ENDIAN,
+};
+use snafu::ResultExt;
+
+use crate::file::ReadPreamble;
+use crate::mem::InMemDicomObject;
+use crate::meta::FileMetaTable;
+use crate::{
+    CreateParserSnafu, FileDicomObject, FileMetaTableBuilder, ParseMetaDataSetSnafu,
+    ParseSopAttributeSnafu, ReadError,
+};
+
+type Result<T, E = ReadError> = std::result::Result<T, E>;
+
+/// Item tag
+const ITEM: Tag = Tag(0xFFFE, 0xE000);
+/// Sequence Delimitation Item tag
+const SEQUENCE_DELIMITER: Tag = Tag(0xFFFE, 0xE0DD);
+
+/// A non-fatal problem found and worked around
+/// while reading a DICOM file in recovery mode.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RecoveryWarning {
+    /// The DICM magic code was not found at the expected position.
+    MissingMagicCode,
+    /// The file has no file meta group.
+    /// A new one was synthesized from the main data set.
+    MissingMetaGroup,
+    /// The file meta group could not be read.
+    /// A new one was synthesized from the main data set.
+    InvalidMetaGroup {
+        /// the reason why the file meta group was rejected
+        reason: String,
+    },
+    /// The file meta information group length
+    /// did not match the actual length of the file meta group.
+    WrongMetaGroupLength {
+        /// the declared group length, if any
+        declared: Option<u32>,
+        /// the actual group length
+        actual: u32,
+    },
+    /// The transfer syntax declared in the file meta group
+    /// did not match the actual encoding of the data set.
+    TransferSyntaxMismatch {
+        /// the declared transfer syntax UID
+        declared: String,
+        /// the UID of the transfer syntax used to read the data set
+        detected: String,
+    },
+    /// The declared transfer syntax is not known
+    /// to the transfer syntax index in use.
+    /// The data set was read with a detected base transfer syntax.
+    UnknownTransferSyntax {
+        /// the declared transfer syntax UID
+        uid: String,
+    },
+    /// Reading from the byte source failed before reaching its end.
+    /// Only the bytes read up to that point were used.
+    IncompleteSource {
+        /// the number of bytes read from the source
+        bytes_read: u64,
+        /// a description of the underlying problem
+        reason: String,
+    },
+    /// The data set could not be read to the end.
+    /// All data elements before the given position were kept.
+    TruncatedDataSet {
+        /// the byte position of the unreadable portion,
+        /// relative to the start of the main data set
+        position: u64,
+        /// a description of the underlying problem
+        reason: String,
+    },
+    /// A sequence item was found where a data element was expected.
+    /// The item was moved into the sequence right before it,
+    /// or otherwise its content was merged into the enclosing data set.
+    MisplacedItem {
+        /// the byte position of the item header,
+        /// relative to the start of the main data set
+        position: u64,
+    },
+    /// A sequence delimitation item was found where a data element was expected.
+    /// If inside of an item, both the item and the sequence were closed,
+    /// otherwise the delimiter was ignored.
+    MisplacedDelimiter {
+        /// the byte position of the delimiter,
+        /// relative to the start of the main data set
+        position: u64,
+    },
+    /// A data element was found where a sequence item was expected,
+    /// so the sequence was closed before it.
+    UnterminatedSequence {
+        /// the byte position of the data element,
+        /// relative to the start of the main data set
+        position: u64,
+    },
+}
+
+impl fmt::Display for RecoveryWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecoveryWarning::MissingMagicCode => f.write_str("DICM magic code not found"),
+            RecoveryWarning::MissingMetaGroup => {
+                f.write_str("file meta group not found, synthesized a new one")
+            }
+            RecoveryWarning::InvalidMetaGroup { reason } => write!(
+                f,
+                "invalid file meta group ({}), synthesized a new one",
+                reason
+            ),
+            RecoveryWarning::WrongMetaGroupLength {
+                declared: Some(declared),
+                actual,
+            } => write!(
+                f,
+                "file meta group length is {} but declared as {}",
+                actual, declared
+            ),
+            RecoveryWarning::WrongMetaGroupLength {
+                declared: None,
+                actual,
+            } => write!(
+                f,
+                "file meta group length is {} but was not declared",
+                actual
+            ),
+            RecoveryWarning::TransferSyntaxMismatch { declared, detected } => write!(
+                f,
+                "data set is not encoded in declared transfer syntax {}, read as {}",
+                declared, detected
+            ),
+            RecoveryWarning::UnknownTransferSyntax { uid } => {
+                write!(f, "unknown transfer syntax {}", uid)
+            }
+            RecoveryWarning::IncompleteSource { bytes_read, reason } => write!(
+                f,
+                "source could only be read up to {} bytes: {}",
+                bytes_read, reason
+            ),
+            RecoveryWarning::TruncatedDataSet { position, reason } => write!(
+                f,
+                "data set could not be read past position {:#x}: {}",
+                position, reason
+            ),
+            RecoveryWarning::MisplacedItem { position } => {
+                write!(f, "sequence item outside of a sequence at {:#x}", position)
+            }
+            RecoveryWarning::MisplacedDelimiter { position } => write!(
+                f,
+                "sequence delimiter outside of a sequence at {:#x}",
+                position
+            ),
+            RecoveryWarning::UnterminatedSequence { position } => write!(
+                f,
+                "data element inside of a sequence at {:#x}, sequence closed",
+                position
+            ),
+        }
+    }
+}
+
+/// Options for reading a DICOM file in recovery mode.
+pub(crate) struct RecoveryOptions<D, T> {
+    pub dict: D,
+    pub ts_index: T,
+    pub read_until: Option<Tag>,
+    pub read_preamble: ReadPreamble,
+    pub odd_length: OddLengthStrategy,
+    pub diagnostics: Option<Diagnostics>,
+    pub preserve_encoding: bool,
+}
+
+/// Read a DICOM file leniently from the full contents of the file.
+pub(crate) fn recover_from_bytes<D, T>(
+    bytes: &[u8],
+    options: RecoveryOptions<D, T>,
+    warnings: &mut Vec<RecoveryWarning>,
+) -> Result<FileDicomObject<InMemDicomObject<D>>>
+where
+    D: DataDictionary,
+    D: Clone,
+    T: TransferSyntaxIndex,
+{
+    let read_preamble = options.read_preamble;
+    // locate the magic code
+    let has_magic_at = |pos: usize| bytes.get(pos..pos + 4) == Some(b"DICM");
+    let start = match read_preamble {
+        ReadPreamble::Always if has_magic_at(128) => Some(132),
+        ReadPreamble::Never if has_magic_at(0) => Some(4),
+        ReadPreamble::Auto if has_magic_at(128) => Some(132),
+        ReadPreamble::Auto if has_magic_at(0) => Some(4),
+        _ => None,
+    };
+    let start = match start {
+        Some(start) => start,
+        None => {
+            warnings.push(RecoveryWarning::MissingMagicCode);
+            let skip_preamble = match read_preamble {
+                ReadPreamble::Always => true,
+                ReadPreamble::Never => false,
+                // a preamble is usually zeroed out
+                ReadPreamble::Auto => bytes.len() > 128 && bytes[..128].iter().all(|b| *b == 0),
+            };
+            if skip_preamble {
+                128.min(bytes.len())
+            } else {
+                0
+            }
+        }
+    };
+
+    // read the file meta group
+    let (meta_end, meta) = recover_meta(&bytes[start..], warnings);
+    let data = &bytes[start + meta_end..];
+
+    // resolve the transfer syntax of the main data set
+    let implicit_vr_le = IMPLICIT_VR_LITTLE_ENDIAN.erased();
+    let explicit_vr_le = EXPLICIT_VR_LITTLE_ENDIAN.erased();
+    let explicit_vr_be = EXPLICIT_VR_BIG_ENDIAN.erased();
+
+    let declared_uid = meta.as_ref().map(|meta| meta.transfer_syntax().to_string());
+    let declared_ts = declared_uid.as_deref().and_then(|uid| {
+        let ts = options.ts_index.get(uid);
+        if ts.is_none() {
+            warnings.push(RecoveryWarning::UnknownTransferSyntax {
+                uid: uid.to_string(),
+            });
+        }
+        ts
+    });
+
+    // undo data set level encoding, keeping as much data as possible
+    let inflated;
+    let (data, base_ts) = match declared_ts.map(|ts| ts.codec()) {
+        Some(Codec::Dataset(Some(adapter))) => {
+            let mut buf = Vec::new();
+            if let Err(e) = adapter
+                .adapt_reader(Box::new(std::io::Cursor::new(data.to_vec())))
+                .read_to_end(&mut buf)
+            {
+                warnings.push(RecoveryWarning::TruncatedDataSet {
+                    position: buf.len() as u64,
+                    reason: error_chain(&e),
+                });
+            }
+            inflated = buf;
+            // the inflated data set is in explicit VR little endian
+            (&inflated[..], Some(&explicit_vr_le))
+        }
+        Some(Codec::Dataset(None)) => {
+            warnings.push(RecoveryWarning::UnknownTransferSyntax {
+                uid: declared_uid.clone().unwrap_or_default(),
+            });
+            (data, None)
+        }
+        Some(_) => (data, declared_ts),
+        None => (data, None),
+    };
+
+    let fallback_endianness = base_ts
+        .map(|ts| ts.endianness())
+        .unwrap_or(Endianness::Little);
+    let detected_ts = match detect_encoding(data, fallback_endianness) {
+        Some((true, Endianness::Little)) => &explicit_vr_le,
+        Some((true, Endianness::Big)) => &explicit_vr_be,
+        Some((false, _)) => &implicit_vr_le,
+        // not enough data to tell, go with the declared one
+        None => base_ts.unwrap_or(&implicit_vr_le),
+    };
+
+    let mut mismatch = false;
+    let ts = match base_ts {
+        Some(ts)
+            if ts.explicit_vr() == detected_ts.explicit_vr()
+                && ts.endianness() == detected_ts.endianness() =>
+        {
+            ts
+        }
+        Some(_) => {
+            warnings.push(RecoveryWarning::TransferSyntaxMismatch {
+                declared: declared_uid.clone().unwrap_or_default(),
+                detected: detected_ts.uid().to_string(),
+            });
+            mismatch = true;
+            detected_ts
+        }
+        None => detected_ts,
+    };
+
+    let obj = recover_dataset(data, ts, &options, warnings)?;
+
+    let meta = match meta {
+        Some(mut meta) => {
+            // fix the declared transfer syntax,
+            // unless it still describes the encapsulated pixel data
+            let encapsulated = matches!(
+                declared_ts.map(|ts| ts.codec()),
+                Some(Codec::EncapsulatedPixelData(..))
+            );
+            if mismatch && !encapsulated {
+                meta.set_transfer_syntax(ts);
+            }
+
+            // if Media Storage SOP Class UID is empty attempt to infer from SOP Class UID
+            if meta.media_storage_sop_class_uid().is_empty() {
+                if let Some(elem) = obj.get(tags::SOP_CLASS_UID) {
+                    meta.media_storage_sop_class_uid = elem
+                        .value()
+                        .to_str()
+                        .context(ParseSopAttributeSnafu)?
+                        .to_string();
+                }
+            }
+
+            // if Media Storage SOP Instance UID is empty attempt to infer from SOP Instance UID
+            if meta.media_storage_sop_instance_uid().is_empty() {
+                if let Some(elem) = obj.get(tags::SOP_INSTANCE_UID) {
+                    meta.media_storage_sop_instance_uid = elem
+                        .value()
+                        .to_str()
+                        .context(ParseSopAttributeSnafu)?
+                        .to_string();
+                }
+            }
+            meta
+        }
+        None => {
+            let mut builder = FileMetaTableBuilder::new().transfer_syntax(ts.uid());
+            if let Some(elem) = obj.get(tags::SOP_CLASS_UID) {
+                builder = builder.media_storage_sop_class_uid(
+                    elem.value().to_str().context(ParseSopAttributeSnafu)?,
+                );
+            }
+            if let Some(elem) = obj.get(tags::SOP_INSTANCE_UID) {
+                builder = builder.media_storage_sop_instance_uid(
+                    elem.value().to_str().context(ParseSopAttributeSnafu)?,
+                );
+            }
+            builder.build().context(ParseMetaDataSetSnafu)?
+        }
+    };
+
+    Ok(FileDicomObject { meta, obj })
+}
+
+/// Read the file meta group leniently,
+/// starting right after the DICM magic code.
+///
+/// Returns the number of bytes in the file meta group
+/// and the file meta table, if it could be recovered.
+fn recover_meta(
+    bytes: &[u8],
+    warnings: &mut Vec<RecoveryWarning>,
+) -> (usize, Option<FileMetaTable>) {
+    let mut declared = None;
+    let mut elements: Vec<(Tag, VR, &[u8])> = Vec::new();
+    let mut pos = 0;
+
+    // collect all group 0002 elements,
+    // regardless of the declared group length
+    while let Some(header) = bytes.get(pos..pos + 8) {
+        let tag = Tag(
+            u16::from_le_bytes([header[0], header[1]]),
+            u16::from_le_bytes([header[2], header[3]]),
+        );
+        if tag.group() != 0x0002 {
+            break;
+        }
+        let (vr, header_len, len) = match VR::from_binary([header[4], header[5]]) {
+            Some(vr) if has_long_length(vr) => match bytes.get(pos + 8..pos + 12) {
+                Some(len) => (vr, 12, u32::from_le_bytes([len[0], len[1], len[2], len[3]])),
+                None => break,
+            },
+            Some(vr) => (vr, 8, u16::from_le_bytes([header[6], header[7]]) as u32),
+            // written in implicit VR
+            None => (
+                meta_vr(tag),
+                8,
+                u32::from_le_bytes([header[4], header[5], header[6], header[7]]),
+            ),
+        };
+        let value_start = pos + header_len;
+        let value = match bytes.get(value_start..value_start.saturating_add(len as usize)) {
+            Some(value) => value,
+            // undefined or out of bounds length
+            None => break,
+        };
+        if tag == Tag(0x0002, 0x0000) {
+            if value.len() == 4 {
+                declared = Some(u32::from_le_bytes([value[0], value[1], value[2], value[3]]));
+            }
+        } else {
+            elements.push((tag, vr, value));
+        }
+        pos = value_start + value.len();
+    }
+
+    if pos == 0 {
+        warnings.push(RecoveryWarning::MissingMetaGroup);
+        return (0, None);
+    }
+
+    // rebuild the group in explicit VR little endian
+    // with the correct group length
+    let mut group = Vec::new();
+    for (tag, vr, value) in elements {
+        group.extend_from_slice(&tag.group().to_le_bytes());
+        group.extend_from_slice(&tag.element().to_le_bytes());
+        group.extend_from_slice(vr.to_string().as_bytes());
+        if has_long_length(vr) {
+            group.extend_from_slice(&[0, 0]);
+            group.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        } else {
+            group.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        }
+        group.extend_from_slice(value);
+    }
+    let actual = group.len() as u32;
+    if declared != Some(actual) {
+        warnings.push(RecoveryWarning::WrongMetaGroupLength { declared, actual });
+    }
+
+    let mut buf = Vec::with_capacity(group.len() + 16);
+    buf.extend_from_slice(b"DICM");
+    buf.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, b'U', b'L', 0x04, 0x00]);
+    buf.extend_from_slice(&actual.to_le_bytes());
+    buf.extend_from_slice(&group);
+
+    match FileMetaTable::from_reader(&buf[..]) {
+        Ok(meta) => (pos, Some(meta)),
+        Err(e) => {
+            warnings.push(RecoveryWarning::InvalidMetaGroup {
+                reason: error_chain(&e),
+            });
+            (pos, None)
+        }
+    }
+}
+
+/// Whether the value length of an element with this VR
+/// is encoded in 4 bytes in explicit VR.
+fn has_long_length(vr: VR) -> bool {
+    matches!(
+        vr,
+        VR::OB
+            | VR::OD
+            | VR::OF
+            | VR::OL
+            | VR::OV
+            | VR::OW
+            | VR::SQ
+            | VR::SV
+            | VR::UC
+            | VR::UN
+            | VR::UR
+            | VR::UT
+            | VR::UV
+    )
+}
+
+/// The value representation of a file meta group element.
+fn meta_vr(tag: Tag) -> VR {
+    match tag.element() {
+        0x0000 => VR::UL,
+        0x0001 | 0x0102 => VR::OB,
+        0x0002 | 0x0003 | 0x0010 | 0x0012 | 0x0100 => VR::UI,
+        0x0013 => VR::SH,
+        0x0016..=0x0018 => VR::AE,
+        _ => VR::UN,
+    }
+}
+
+/// Guess whether the data set is in explicit VR
+/// and its byte order by looking at the first data element header.
+///
+/// The group number of the first element is expected to be small,
+/// so the byte order which yields the smaller group number wins.
+/// `fallback` is used if both are equal.
+fn detect_encoding(data: &[u8], fallback: Endianness) -> Option<(bool, Endianness)> {
+    let header = data.get(0..8)?;
+    let explicit_vr = VR::from_binary([header[4], header[5]]).is_some();
+    let group_le = u16::from_le_bytes([header[0], header[1]]);
+    let group_be = u16::from_be_bytes([header[0], header[1]]);
+    let endianness = match group_le.cmp(&group_be) {
+        std::cmp::Ordering::Less => Endianness::Little,
+        std::cmp::Ordering::Greater => Endianness::Big,
+        std::cmp::Ordering::Equal => fallback,
+    };
+    Some((explicit_vr, endianness))
+}
+
+/// A sequence or item left open while recovering a data set.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Container {
+    /// a data set sequence
+    Sequence {
+        tag: Tag,
+        /// the position right after the end of the sequence,
+        /// if it has a defined length
+        end: Option<u64>,
+        /// whether the sequence was reopened to host a misplaced item
+        reopened: bool,
+    },
+    /// an encapsulated pixel data sequence
+    PixelSequence,
+    /// a sequence item
+    Item {
+        /// the position right after the end of the item,
+        /// if it has a defined length
+        end: Option<u64>,
+    },
+    /// a misplaced item,
+    /// the content of which is merged into the enclosing data set
+    MisplacedItem,
+}
+
+/// The data set reader type used in recovery mode.
+type RecoveryReader<'a> =
+    DataSetReader<DynStatefulDecoder<CountingReader<Chain<Cursor<Vec<u8>>, &'a [u8]>>>>;
+
+/// Read the main data set leniently,
+/// keeping all data elements read before the first parsing error
+/// and closing any sequences and items left open.
+fn recover_dataset<D, T>(
+    data: &[u8],
+    ts: &TransferSyntax,
+    options: &RecoveryOptions<D, T>,
+    warnings: &mut Vec<RecoveryWarning>,
+) -> Result<InMemDicomObject<D>>
+where
+    D: DataDictionary,
+    D: Clone,
+{
+    let position = Rc::new(Cell::new(0));
+    let mut reader = reader_at(data, 0, &[], ts, options, &position)?;
+
+    let mut tokens = Vec::new();
+    let mut open: Vec<Container> = Vec::new();
+    // the sequence closed by the last token, if any
+    let mut closed_sequence = None;
+    let mut last_restart = None;
+    let mut header_position = 0;
+    let mut truncated = false;
+    loop {
+        let token_position = position.get();
+        // the position from which to read again
+        // in a different context, if any
+        let restart_at;
+        match reader.next() {
+            None => break,
+            Some(Ok(token)) => {
+                let in_root = open.iter().all(|c| *c == Container::MisplacedItem);
+                if in_root && reached_read_until(&token, options.read_until) {
+                    break;
+                }
+                match token {
+                    DataToken::SequenceStart { tag, .. }
+                    | DataToken::ElementHeader(DataElementHeader { tag, .. })
+                        if tag == ITEM =>
+                    {
+                        let undefined_length = matches!(token, DataToken::SequenceStart { .. });
+                        warnings.push(RecoveryWarning::MisplacedItem {
+                            position: token_position,
+                        });
+                        if let Some(Container::Sequence { tag, .. }) = closed_sequence.take() {
+                            // move the item into the sequence right before it
+                            tokens.pop();
+                            open.push(Container::Sequence {
+                                tag,
+                                end: None,
+                                reopened: true,
+                            });
+                            restart_at = token_position;
+                        } else {
+                            // skip the item header,
+                            // so that its content is read as part of the current data set
+                            if undefined_length && !open.is_empty() {
+                                open.push(Container::MisplacedItem);
+                            }
+                            restart_at = token_position + 8;
+                        }
+                    }
+                    DataToken::ElementHeader(DataElementHeader { tag, .. })
+                        if tag == SEQUENCE_DELIMITER =>
+                    {
+                        warnings.push(RecoveryWarning::MisplacedDelimiter {
+                            position: token_position,
+                        });
+                        // close the item and the sequence
+                        // if the item delimiter is missing
+                        if let [.., Container::Sequence { .. }, Container::Item { .. }] = open[..] {
+                            open.truncate(open.len() - 2);
+                            tokens.push(DataToken::ItemEnd);
+                            tokens.push(DataToken::SequenceEnd);
+                        }
+                        restart_at = token_position + 8;
+                    }
+                    DataToken::ItemEnd if open.last() == Some(&Container::MisplacedItem) => {
+                        // the reader took it as the end of the enclosing item
+                        open.pop();
+                        restart_at = position.get();
+                    }
+                    token => {
+                        closed_sequence = None;
+                        match token {
+                            DataToken::ElementHeader(_) => header_position = token_position,
+                            DataToken::SequenceStart { tag, len } => {
+                                open.push(Container::Sequence {
+                                    tag,
+                                    end: len.get().map(|len| position.get() + u64::from(len)),
+                                    reopened: false,
+                                })
+                            }
+                            DataToken::PixelSequenceStart => open.push(Container::PixelSequence),
+                            DataToken::ItemStart { len } => open.push(Container::Item {
+                                end: len.get().map(|len| position.get() + u64::from(len)),
+                            }),
+                            DataToken::ItemEnd => {
+                                open.pop();
+                            }
+                            DataToken::SequenceEnd => {
+                                closed_sequence = open.pop();
+                            }
+                            _ => {}
+                        }
+                        tokens.push(token);
+                        continue;
+                    }
+                }
+            }
+            Some(Err(e)) => {
+                // a data element where an item was expected
+                // means that the sequence was not terminated
+                let element_follows = read_tag(data, token_position, ts)
+                    .map(|tag| tag.group() != 0xFFFE)
+                    .unwrap_or(false);
+                match open.last() {
+                    Some(Container::Sequence { reopened, .. }) if element_follows => {
+                        if !reopened {
+                            warnings.push(RecoveryWarning::UnterminatedSequence {
+                                position: token_position,
+                            });
+                        }
+                        closed_sequence = open.pop();
+                        tokens.push(DataToken::SequenceEnd);
+                        restart_at = token_position;
+                    }
+                    _ => {
+                        // discard the element with the missing value
+                        let position = if let Some(DataToken::ElementHeader(_)) = tokens.last() {
+                            tokens.pop();
+                            header_position
+                        } else {
+                            token_position
+                        };
+                        warnings.push(RecoveryWarning::TruncatedDataSet {
+                            position,
+                            reason: error_chain(&e),
+                        });
+                        truncated = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        // continue reading in the new context,
+        // unless it would not make any progress
+        let restart = (restart_at, open.clone());
+        if last_restart.as_ref() == Some(&restart)
+            || open.contains(&Container::PixelSequence)
+            || restart_at > data.len() as u64
+        {
+            warnings.push(RecoveryWarning::TruncatedDataSet {
+                position: token_position,
+                reason: "could not recover from misplaced item".to_string(),
+            });
+            truncated = true;
+            break;
+        }
+        reader = reader_at(data, restart_at, &open, ts, options, &position)?;
+        last_restart = Some(restart);
+    }
+
+    if let Some(DataToken::ElementHeader(_)) = tokens.last() {
+        tokens.pop();
+        if !truncated {
+            warnings.push(RecoveryWarning::TruncatedDataSet {
+                position: header_position,
+                reason: "missing data element value".to_string(),
+            });
+            truncated = true;
+        }
+    }
+    open.retain(|c| *c != Container::MisplacedItem);
+    if !open.is_empty() && !truncated {
+        warnings.push(RecoveryWarning::TruncatedDataSet {
+            position: position.get(),
+            reason: "data set ended inside of a sequence or item".to_string(),
+        });
+    }
+    tokens.extend(open.into_iter().rev().map(|c| match c {
+        Container::Item { .. } => DataToken::ItemEnd,
+        _ => DataToken::SequenceEnd,
+    }));
+
+    InMemDicomObject::build_object(
+        &mut tokens.into_iter().map(Ok),
+        options.dict.clone(),
+        false,
+        Length::UNDEFINED,
+        options.read_until,
+        options.preserve_encoding,
+    )
+}
+
+/// Create a data set reader starting at the given position of the data set,
+/// in the context of the given open sequences and items.
+///
+/// The reader is primed with the headers of the open containers,
+/// which are consumed before returning.
+fn reader_at<'a, D, T>(
+    data: &'a [u8],
+    at: u64,
+    open: &[Container],
+    ts: &TransferSyntax,
+    options: &RecoveryOptions<D, T>,
+    position: &Rc<Cell<u64>>,
+) -> Result<RecoveryReader<'a>> {
+    let open: Vec<_> = open
+        .iter()
+        .filter(|c| **c != Container::MisplacedItem)
+        .collect();
+    let header_len = |c: &Container| match c {
+        Container::Sequence { .. } if ts.explicit_vr() => 12,
+        _ => 8,
+    };
+    let prefix_len: u64 = open.iter().map(|c| header_len(c)).sum();
+
+    // rebuild the container headers,
+    // with lengths which end at the same place in the data set
+    let mut prefix = Vec::with_capacity(prefix_len as usize);
+    let mut header_end = 0;
+    for c in open {
+        header_end += header_len(c);
+        let (tag, end) = match *c {
+            Container::Sequence { tag, end, .. } => (tag, end),
+            Container::Item { end } => (ITEM, end),
+            _ => unreachable!("pixel data sequences are never reopened"),
+        };
+        let len = match end {
+            Some(end) if end >= at => (end - at + prefix_len - header_end) as u32,
+            _ => Length::UNDEFINED.0,
+        };
+        match ts.endianness() {
+            Endianness::Little => {
+                prefix.extend_from_slice(&tag.group().to_le_bytes());
+                prefix.extend_from_slice(&tag.element().to_le_bytes());
+                if header_len(c) == 12 {
+                    prefix.extend_from_slice(b"SQ\0\0");
+                }
+                prefix.extend_from_slice(&len.to_le_bytes());
+            }
+            Endianness::Big => {
+                prefix.extend_from_slice(&tag.group().to_be_bytes());
+                prefix.extend_from_slice(&tag.element().to_be_bytes());
+                if header_len(c) == 12 {
+                    prefix.extend_from_slice(b"SQ\0\0");
+                }
+                prefix.extend_from_slice(&len.to_be_bytes());
+            }
+        }
+    }
+
+    position.set(0);
+    let source = CountingReader {
+        inner: Cursor::new(prefix).chain(data.get(at as usize..).unwrap_or_default()),
+        position: Rc::clone(position),
+    };
+    let mut reader_options = DataSetReaderOptions::default();
+    reader_options.odd_length = options.odd_length;
+    let mut reader = DataSetReader::new_with_ts_cs_options(
+        source,
+        ts,
+        SpecificCharacterSet::default(),
+        reader_options,
+    )
+    .context(CreateParserSnafu)?;
+    if let Some(diagnostics) = &options.diagnostics {
+        reader = reader.with_diagnostics(diagnostics.clone());
+    }
+
+    // skip the tokens of the rebuilt headers
+    while position.get() < prefix_len {
+        if let None | Some(Err(_)) = reader.next() {
+            break;
+        }
+    }
+    position.set(at);
+    Ok(reader)
+}
+
+/// Read the tag at the given position of the data set.
+fn read_tag(data: &[u8], at: u64, ts: &TransferSyntax) -> Option<Tag> {
+    let bytes = data.get(at as usize..(at as usize).checked_add(4)?)?;
+    Some(match ts.endianness() {
+        Endianness::Little => Tag(
+            u16::from_le_bytes([bytes[0], bytes[1]]),
+            u16::from_le_bytes([bytes[2], bytes[3]]),
+        ),
+        Endianness::Big => Tag(
+            u16::from_be_bytes([bytes[0], bytes[1]]),
+            u16::from_be_bytes([bytes[2], bytes[3]]),
+        ),
+    })
+}
+
+fn reached_read_until(token: &DataToken, read_until: Option<Tag>) -> bool {
+    let tag = match token {
+        DataToken::ElementHeader(header) => header.tag,
+        DataToken::SequenceStart { tag, .. } => *tag,
+        DataToken::PixelSequenceStart => Tag(0x7FE0, 0x0010),
+        _ => return false,
+    };
+    read_until.map(|t| t <= tag).unwrap_or(false)
+}
+
+/// Describe an error along with all of its sources.
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut out = e.to_string();
+    let mut source = e.source();
+    while let Some(e) = source {
+        out.push_str(": ");
+        out.push_str(&e.to_string());
+        source = e.source();
+    }
+    out
+}
+
+/// A reader which keeps track of the number of bytes read.
+struct CountingReader<R> {
+    inner: R,
+    position: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position.set(self.position.get() + n as u64);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecoveryWarning;
+    use crate::{FileMetaTableBuilder, InMemDicomObject, OpenFileOptions};
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_transfer_syntax_registry::entries::{
+        EXPLICIT_VR_BIG_ENDIAN, EXPLICIT_VR_LITTLE_ENDIAN, IMPLICIT_VR_LITTLE_ENDIAN,
+    };
+
+    const SOP_INSTANCE_UID: &str = "2.25.248155478428291419163208765834520591425";
+
+    /// A small data set with a sequence as its last element.
+    fn sample_dataset() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::from(uids::SECONDARY_CAPTURE_IMAGE_STORAGE),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(SOP_INSTANCE_UID),
+            ),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(64_u16)),
+            DataElement::new(
+                tags::REQUEST_ATTRIBUTES_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(
+                        tags::REQUESTED_PROCEDURE_ID,
+                        VR::SH,
+                        PrimitiveValue::from("P001"),
+                    ),
+                ])]),
+            ),
+        ])
+    }
+
+    /// Encode the sample data set as a DICOM file.
+    fn sample_file(ts: &str) -> Vec<u8> {
+        let obj = sample_dataset()
+            .with_meta(FileMetaTableBuilder::new().transfer_syntax(ts))
+            .unwrap();
+        let mut out = Vec::new();
+        obj.write_all(&mut out).unwrap();
+        out
+    }
+
+    fn assert_sample_content(obj: &InMemDicomObject) {
+        assert_eq!(
+            obj.element(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe^John"
+        );
+        assert_eq!(
+            obj.element(tags::ROWS).unwrap().to_int::<u16>().unwrap(),
+            64
+        );
+    }
+
+    #[test]
+    fn recover_intact_file_without_warnings() {
+        let bytes = sample_file(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(warnings, vec![]);
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            uids::EXPLICIT_VR_LITTLE_ENDIAN
+        );
+        assert_sample_content(&obj);
+        let items = obj
+            .element(tags::REQUEST_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+    }
+
+    #[test]
+    fn recover_wrong_meta_group_length() {
+        let mut bytes = sample_file(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        // group length value right after the magic code and element header
+        let actual = u32::from_le_bytes([bytes[140], bytes[141], bytes[142], bytes[143]]);
+        bytes[140..144].copy_from_slice(&(actual + 20).to_le_bytes());
+
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning::WrongMetaGroupLength {
+                declared: Some(actual + 20),
+                actual,
+            }]
+        );
+        assert_eq!(
+            obj.meta().media_storage_sop_instance_uid(),
+            SOP_INSTANCE_UID
+        );
+        assert_sample_content(&obj);
+    }
+
+    #[test]
+    fn recover_transfer_syntax_mismatch() {
+        // declare explicit VR little endian, but write implicit VR
+        let obj = sample_dataset()
+            .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .unwrap();
+        let mut bytes = b"DICM".to_vec();
+        obj.write_meta(&mut bytes).unwrap();
+        obj.write_dataset_with_ts(&mut bytes, &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+            .unwrap();
+
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning::TransferSyntaxMismatch {
+                declared: uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+                detected: uids::IMPLICIT_VR_LITTLE_ENDIAN.to_string(),
+            }]
+        );
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            uids::IMPLICIT_VR_LITTLE_ENDIAN
+        );
+        assert_sample_content(&obj);
+    }
+
+    #[test]
+    fn recover_truncated_sequence() {
+        let mut bytes = sample_file(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        // cut the sequence delimiter and half of the item delimiter
+        bytes.truncate(bytes.len() - 12);
+
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(warnings.len(), 1, "unexpected warnings {:?}", warnings);
+        assert!(matches!(
+            warnings[0],
+            RecoveryWarning::TruncatedDataSet { .. }
+        ));
+        assert_sample_content(&obj);
+        let items = obj
+            .element(tags::REQUEST_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0]
+                .element(tags::REQUESTED_PROCEDURE_ID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "P001"
+        );
+    }
+
+    #[test]
+    fn recover_truncated_element_value() {
+        let bytes = sample_file(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        // cut right into the value of Rows
+        let obj = sample_dataset();
+        let mut dataset = Vec::new();
+        obj.write_dataset_with_ts(&mut dataset, &EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .unwrap();
+        let rows_header = [0x28, 0x00, 0x10, 0x00, b'U', b'S', 0x02, 0x00];
+        let rows_pos = dataset.windows(8).position(|w| w == rows_header).unwrap();
+        let meta_end = bytes.len() - dataset.len();
+        let cut = meta_end + rows_pos + 9;
+
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..cut])
+            .unwrap();
+        assert_eq!(warnings.len(), 1, "unexpected warnings {:?}", warnings);
+        match &warnings[0] {
+            RecoveryWarning::TruncatedDataSet { position, .. } => {
+                assert_eq!(*position, rows_pos as u64);
+            }
+            w => panic!("unexpected warning {:?}", w),
+        }
+        assert_eq!(
+            obj.element(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe^John"
+        );
+        assert!(obj.element_opt(tags::ROWS).unwrap().is_none());
+    }
+
+    #[test]
+    fn recover_missing_meta_group() {
+        for ts in [
+            IMPLICIT_VR_LITTLE_ENDIAN.erased(),
+            EXPLICIT_VR_LITTLE_ENDIAN.erased(),
+            EXPLICIT_VR_BIG_ENDIAN.erased(),
+        ] {
+            let mut bytes = Vec::new();
+            sample_dataset()
+                .write_dataset_with_ts(&mut bytes, &ts)
+                .unwrap();
+
+            let (obj, warnings) = OpenFileOptions::new()
+                .from_reader_with_recovery(&bytes[..])
+                .unwrap();
+            assert_eq!(
+                warnings,
+                vec![
+                    RecoveryWarning::MissingMagicCode,
+                    RecoveryWarning::MissingMetaGroup
+                ]
+            );
+            assert_eq!(obj.meta().transfer_syntax(), ts.uid());
+            assert_eq!(
+                obj.meta().media_storage_sop_class_uid(),
+                uids::SECONDARY_CAPTURE_IMAGE_STORAGE
+            );
+            assert_eq!(
+                obj.meta().media_storage_sop_instance_uid(),
+                SOP_INSTANCE_UID
+            );
+            assert_sample_content(&obj);
+        }
+    }
+
+    #[test]
+    fn recover_read_until() {
+        let mut bytes = sample_file(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        // corruption past the requested tag is irrelevant
+        bytes.truncate(bytes.len() - 12);
+
+        let (obj, warnings) = OpenFileOptions::new()
+            .read_until(tags::ROWS)
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(warnings, vec![]);
+        assert!(obj.element_opt(tags::PATIENT_NAME).unwrap().is_some());
+        assert!(obj.element_opt(tags::ROWS).unwrap().is_none());
+    }
+
+    /// Encode a data element header in explicit VR little endian.
+    fn explicit_le_header(group: u16, element: u16, vr: &[u8; 2], len: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&group.to_le_bytes());
+        out.extend_from_slice(&element.to_le_bytes());
+        out.extend_from_slice(vr);
+        if vr == b"SQ" {
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(&len.to_le_bytes());
+        } else {
+            out.extend_from_slice(&(len as u16).to_le_bytes());
+        }
+        out
+    }
+
+    /// Encode a DICOM file in explicit VR little endian
+    /// with the given raw main data set.
+    fn file_with_dataset(dataset: &[u8]) -> Vec<u8> {
+        let obj = sample_dataset()
+            .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .unwrap();
+        let mut bytes = b"DICM".to_vec();
+        obj.write_meta(&mut bytes).unwrap();
+        bytes.extend_from_slice(dataset);
+        bytes
+    }
+
+    const ITEM_START: [u8; 8] = [0xFE, 0xFF, 0x00, 0xE0, 0xFF, 0xFF, 0xFF, 0xFF];
+    const ITEM_END: [u8; 8] = [0xFE, 0xFF, 0x0D, 0xE0, 0, 0, 0, 0];
+    const SEQUENCE_END: [u8; 8] = [0xFE, 0xFF, 0xDD, 0xE0, 0, 0, 0, 0];
+
+    #[test]
+    fn recover_item_after_sequence() {
+        // a sequence with a zero length,
+        // followed by an undefined length item
+        let mut dataset = explicit_le_header(0x0010, 0x0010, b"PN", 8);
+        dataset.extend_from_slice(b"Doe^John");
+        dataset.extend(explicit_le_header(0x0040, 0x0275, b"SQ", 0));
+        let item_position = dataset.len() as u64;
+        dataset.extend_from_slice(&ITEM_START);
+        dataset.extend(explicit_le_header(0x0040, 0x1001, b"SH", 4));
+        dataset.extend_from_slice(b"P001");
+        dataset.extend_from_slice(&ITEM_END);
+        dataset.extend(explicit_le_header(0x0028, 0x0010, b"US", 2));
+        dataset.extend_from_slice(&64_u16.to_le_bytes());
+
+        let bytes = file_with_dataset(&dataset);
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning::MisplacedItem {
+                position: item_position
+            }]
+        );
+        assert_sample_content(&obj);
+        // the item was moved into the sequence
+        let items = obj
+            .element(tags::REQUEST_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0]
+                .element(tags::REQUESTED_PROCEDURE_ID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "P001"
+        );
+    }
+
+    #[test]
+    fn recover_item_outside_of_sequence() {
+        let mut dataset = explicit_le_header(0x0008, 0x0060, b"CS", 2);
+        dataset.extend_from_slice(b"OT");
+        let item_position = dataset.len() as u64;
+        dataset.extend_from_slice(&ITEM_START);
+        dataset.extend(explicit_le_header(0x0010, 0x0010, b"PN", 8));
+        dataset.extend_from_slice(b"Doe^John");
+        dataset.extend_from_slice(&ITEM_END);
+        dataset.extend(explicit_le_header(0x0028, 0x0010, b"US", 2));
+        dataset.extend_from_slice(&64_u16.to_le_bytes());
+
+        let bytes = file_with_dataset(&dataset);
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning::MisplacedItem {
+                position: item_position
+            }]
+        );
+        // the content of the item was merged into the data set
+        assert_sample_content(&obj);
+        assert_eq!(obj.element(tags::MODALITY).unwrap().to_str().unwrap(), "OT");
+    }
+
+    #[test]
+    fn recover_item_inside_of_item() {
+        // an undefined length item directly inside of another item
+        let mut dataset = explicit_le_header(0x0010, 0x0010, b"PN", 8);
+        dataset.extend_from_slice(b"Doe^John");
+        dataset.extend(explicit_le_header(0x0040, 0x0275, b"SQ", 0xFFFF_FFFF));
+        dataset.extend_from_slice(&ITEM_START);
+        let item_position = dataset.len() as u64;
+        dataset.extend_from_slice(&ITEM_START);
+        dataset.extend(explicit_le_header(0x0040, 0x1001, b"SH", 4));
+        dataset.extend_from_slice(b"P001");
+        dataset.extend_from_slice(&ITEM_END);
+        dataset.extend(explicit_le_header(0x0040, 0x1002, b"LO", 4));
+        dataset.extend_from_slice(b"WHY ");
+        dataset.extend_from_slice(&ITEM_END);
+        dataset.extend_from_slice(&SEQUENCE_END);
+        dataset.extend(explicit_le_header(0x0028, 0x0010, b"US", 2));
+        dataset.extend_from_slice(&64_u16.to_le_bytes());
+
+        let bytes = file_with_dataset(&dataset);
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning::MisplacedItem {
+                position: item_position
+            }]
+        );
+        assert_sample_content(&obj);
+        let items = obj
+            .element(tags::REQUEST_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0]
+                .element(tags::REQUESTED_PROCEDURE_ID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "P001"
+        );
+        assert_eq!(
+            items[0]
+                .element(tags::REASON_FOR_THE_REQUESTED_PROCEDURE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "WHY"
+        );
+    }
+
+    #[test]
+    fn recover_missing_item_delimiter() {
+        let mut dataset = explicit_le_header(0x0010, 0x0010, b"PN", 8);
+        dataset.extend_from_slice(b"Doe^John");
+        dataset.extend(explicit_le_header(0x0040, 0x0275, b"SQ", 0xFFFF_FFFF));
+        dataset.extend_from_slice(&ITEM_START);
+        dataset.extend(explicit_le_header(0x0040, 0x1001, b"SH", 4));
+        dataset.extend_from_slice(b"P001");
+        let delimiter_position = dataset.len() as u64;
+        dataset.extend_from_slice(&SEQUENCE_END);
+        dataset.extend(explicit_le_header(0x0028, 0x0010, b"US", 2));
+        dataset.extend_from_slice(&64_u16.to_le_bytes());
+
+        let bytes = file_with_dataset(&dataset);
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning::MisplacedDelimiter {
+                position: delimiter_position
+            }]
+        );
+        assert_sample_content(&obj);
+        let items = obj
+            .element(tags::REQUEST_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        // Rows is not in the item
+        assert!(items[0].element_opt(tags::ROWS).unwrap().is_none());
+    }
+
+    #[test]
+    fn recover_unterminated_sequence() {
+        let mut dataset = explicit_le_header(0x0010, 0x0010, b"PN", 8);
+        dataset.extend_from_slice(b"Doe^John");
+        dataset.extend(explicit_le_header(0x0040, 0x0275, b"SQ", 0xFFFF_FFFF));
+        dataset.extend_from_slice(&ITEM_START);
+        dataset.extend(explicit_le_header(0x0040, 0x1001, b"SH", 4));
+        dataset.extend_from_slice(b"P001");
+        dataset.extend_from_slice(&ITEM_END);
+        let rows_position = dataset.len() as u64;
+        dataset.extend(explicit_le_header(0x0028, 0x0010, b"US", 2));
+        dataset.extend_from_slice(&64_u16.to_le_bytes());
+
+        let bytes = file_with_dataset(&dataset);
+        let (obj, warnings) = OpenFileOptions::new()
+            .from_reader_with_recovery(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning::UnterminatedSequence {
+                position: rows_position
+            }]
+        );
+        assert_sample_content(&obj);
+        let items = obj
+            .element(tags::REQUEST_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+    }
+}
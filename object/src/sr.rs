@@ -0,0 +1,1494 @@
+//! Structured Reporting (SR) content trees.
+//!
+//! The content of an SR document is a tree of content items,
+//! recorded in DICOM as nested _Content Sequence_ attributes
+//! starting at the root of the data set.
+//! This module provides a typed view of that tree:
+//! each [`ContentItem`] has an optional relationship with its parent,
+//! a concept name, a typed value ([`ContentValue`]),
+//! and child content items.
+//!
+//! Content trees can be read from an existing object
+//! with [`ContentItem::from_dataset`],
+//! built from scratch with the content item constructors,
+//! and written back to an object with [`ContentItem::put_into`].
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_object::InMemDicomObject;
+//! use dicom_object::sr::{Code, ContentItem, RelationshipType};
+//!
+//! let report = ContentItem::container(Code::new("126000", "DCM", "Imaging Measurement Report"))
+//!     .with_child(
+//!         RelationshipType::Contains,
+//!         ContentItem::num(
+//!             Code::new("410668003", "SCT", "Length"),
+//!             12.5,
+//!             Code::new("mm", "UCUM", "millimeter"),
+//!         ),
+//!     );
+//!
+//! let mut obj = InMemDicomObject::new_empty();
+//! report.put_into(&mut obj);
+//!
+//! let tree = ContentItem::from_dataset(&obj)?;
+//! let length = tree.find(&Code::new("410668003", "SCT", "Length")).unwrap();
+//! assert_eq!(length.relationship_type, Some(RelationshipType::Contains));
+//! # Ok::<_, dicom_object::sr::Error>(())
+//! ```
+use std::str::FromStr;
+
+use dicom_core::value::serialize::format_decimal_string;
+use dicom_core::value::{CastValueError, ConvertValueError, DataSetSequence};
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::InMemDicomObject;
+
+/// An error which may occur when reading an SR content tree.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// Missing attribute {name}
+    MissingAttribute { name: &'static str },
+    /// Could not read attribute {name}
+    ConvertAttribute {
+        name: &'static str,
+        source: ConvertValueError,
+    },
+    /// Could not read attribute {name}
+    CastAttribute {
+        name: &'static str,
+        source: CastValueError,
+    },
+    /// Sequence {name} has no items
+    EmptySequence { name: &'static str },
+    /// Unknown {name} `{value}`
+    UnknownTerm { name: &'static str, value: String },
+}
+
+/// Result type for reading SR content trees.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Declare an enumeration of the defined terms of a code string attribute.
+macro_rules! defined_terms {
+    (
+        $(#[$meta:meta])*
+        $name:ident ($attribute:literal) {
+            $($(#[$vmeta:meta])* $variant:ident => $term:literal,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)+
+        }
+
+        impl $name {
+            /// Obtain the code string of this term.
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $($name::$variant => $term,)+
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self> {
+                match s.trim_end() {
+                    $($term => Ok($name::$variant),)+
+                    value => UnknownTermSnafu {
+                        name: $attribute,
+                        value,
+                    }
+                    .fail(),
+                }
+            }
+        }
+    };
+}
+
+defined_terms! {
+    /// The relationship between a content item and its parent.
+    RelationshipType ("RelationshipType") {
+        /// `CONTAINS`
+        Contains => "CONTAINS",
+        /// `HAS PROPERTIES`
+        HasProperties => "HAS PROPERTIES",
+        /// `HAS OBS CONTEXT`
+        HasObsContext => "HAS OBS CONTEXT",
+        /// `HAS ACQ CONTEXT`
+        HasAcqContext => "HAS ACQ CONTEXT",
+        /// `INFERRED FROM`
+        InferredFrom => "INFERRED FROM",
+        /// `SELECTED FROM`
+        SelectedFrom => "SELECTED FROM",
+        /// `HAS CONCEPT MOD`
+        HasConceptMod => "HAS CONCEPT MOD",
+    }
+}
+
+defined_terms! {
+    /// Whether the children of a container are
+    /// to be interpreted separately or as continuous text.
+    ContinuityOfContent ("ContinuityOfContent") {
+        /// `SEPARATE`
+        Separate => "SEPARATE",
+        /// `CONTINUOUS`
+        Continuous => "CONTINUOUS",
+    }
+}
+
+defined_terms! {
+    /// The geometry of spatial coordinates.
+    GraphicType ("GraphicType") {
+        /// A single point
+        Point => "POINT",
+        /// Multiple unconnected points
+        Multipoint => "MULTIPOINT",
+        /// Connected line segments
+        Polyline => "POLYLINE",
+        /// A circle, given by its center and a point on its perimeter
+        Circle => "CIRCLE",
+        /// An ellipse, given by the end points of its axes
+        Ellipse => "ELLIPSE",
+    }
+}
+
+defined_terms! {
+    /// The kind of temporal region of temporal coordinates.
+    TemporalRangeType ("TemporalRangeType") {
+        /// A single temporal point
+        Point => "POINT",
+        /// Multiple temporal points
+        Multipoint => "MULTIPOINT",
+        /// A range between two temporal points
+        Segment => "SEGMENT",
+        /// Multiple ranges, each between two temporal points
+        Multisegment => "MULTISEGMENT",
+        /// A range starting at a temporal point
+        Begin => "BEGIN",
+        /// A range ending at a temporal point
+        End => "END",
+    }
+}
+
+/// A coded concept, as in an item of a code sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Code {
+    /// The code value
+    pub value: String,
+    /// The coding scheme designator
+    /// (empty for URN code values)
+    pub scheme_designator: String,
+    /// The coding scheme version, if any
+    pub scheme_version: Option<String>,
+    /// The human readable meaning of the code
+    pub meaning: String,
+}
+
+impl Code {
+    /// Create a new code.
+    pub fn new(
+        value: impl Into<String>,
+        scheme_designator: impl Into<String>,
+        meaning: impl Into<String>,
+    ) -> Self {
+        Code {
+            value: value.into(),
+            scheme_designator: scheme_designator.into(),
+            scheme_version: None,
+            meaning: meaning.into(),
+        }
+    }
+
+    /// Check whether this code denotes the same concept as another code,
+    /// by comparing their code values and coding scheme designators.
+    pub fn matches(&self, other: &Code) -> bool {
+        self.value == other.value && self.scheme_designator == other.scheme_designator
+    }
+
+    /// Read a code from an item of a code sequence.
+    pub fn from_item(item: &InMemDicomObject) -> Result<Self> {
+        let value = match opt_str(item, tags::CODE_VALUE, "CodeValue")? {
+            Some(value) => value,
+            None => match opt_str(item, tags::LONG_CODE_VALUE, "LongCodeValue")? {
+                Some(value) => value,
+                None => req_str(item, tags::URN_CODE_VALUE, "URNCodeValue")?,
+            },
+        };
+        Ok(Code {
+            value,
+            scheme_designator: opt_str(
+                item,
+                tags::CODING_SCHEME_DESIGNATOR,
+                "CodingSchemeDesignator",
+            )?
+            .unwrap_or_default(),
+            scheme_version: opt_str(item, tags::CODING_SCHEME_VERSION, "CodingSchemeVersion")?,
+            meaning: req_str(item, tags::CODE_MEANING, "CodeMeaning")?,
+        })
+    }
+
+    /// Create an item of a code sequence with this code.
+    ///
+    /// The code value is written to _Long Code Value_
+    /// if it does not fit in _Code Value_,
+    /// or to _URN Code Value_ if it is a URN or URL.
+    pub fn to_item(&self) -> InMemDicomObject {
+        let mut item = InMemDicomObject::new_empty();
+        if self.value.starts_with("urn:") || self.value.contains("://") {
+            put_str(&mut item, tags::URN_CODE_VALUE, VR::UR, &self.value);
+        } else if self.value.len() > 16 {
+            put_str(&mut item, tags::LONG_CODE_VALUE, VR::UC, &self.value);
+        } else {
+            put_str(&mut item, tags::CODE_VALUE, VR::SH, &self.value);
+        }
+        if !self.scheme_designator.is_empty() {
+            put_str(
+                &mut item,
+                tags::CODING_SCHEME_DESIGNATOR,
+                VR::SH,
+                &self.scheme_designator,
+            );
+        }
+        if let Some(version) = &self.scheme_version {
+            put_str(&mut item, tags::CODING_SCHEME_VERSION, VR::SH, version);
+        }
+        put_str(&mut item, tags::CODE_MEANING, VR::LO, &self.meaning);
+        item
+    }
+}
+
+/// An identifier of the template which a container conforms to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TemplateIdentifier {
+    /// The mapping resource defining the template, such as `DCMR`
+    pub mapping_resource: String,
+    /// The template identifier, such as `1500`
+    pub identifier: String,
+}
+
+/// A numeric measurement, with its units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// The numeric value
+    pub value: f64,
+    /// The units of measurement
+    pub unit: Code,
+}
+
+/// A reference to a composite SOP instance,
+/// such as an image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SopReference {
+    /// The referenced SOP class UID
+    pub sop_class_uid: String,
+    /// The referenced SOP instance UID
+    pub sop_instance_uid: String,
+    /// The referenced frame numbers, starting at 1,
+    /// or empty if the reference applies to all frames
+    pub frame_numbers: Vec<u32>,
+}
+
+/// The reference points of temporal coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemporalReference {
+    /// Sample positions, starting at 1
+    SamplePositions(Vec<u32>),
+    /// Time offsets in seconds
+    TimeOffsets(Vec<f64>),
+    /// Absolute date-times
+    DateTimes(Vec<String>),
+}
+
+/// The typed value of a content item.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ContentValue {
+    /// `CONTAINER`: a grouping of other content items
+    Container {
+        /// How the children are to be interpreted
+        continuity: ContinuityOfContent,
+        /// The template which the container conforms to, if any
+        template: Option<TemplateIdentifier>,
+    },
+    /// `TEXT`: free form text
+    Text(String),
+    /// `CODE`: a coded concept
+    Code(Code),
+    /// `NUM`: a numeric measurement,
+    /// which may be absent if a qualifier explains why
+    Num {
+        /// The measurement
+        measurement: Option<Measurement>,
+        /// A qualifier of the measurement, if any
+        qualifier: Option<Code>,
+    },
+    /// `UIDREF`: a unique identifier
+    UidRef(String),
+    /// `DATE`: a date in DICOM `DA` format
+    Date(String),
+    /// `TIME`: a time in DICOM `TM` format
+    Time(String),
+    /// `DATETIME`: a date-time in DICOM `DT` format
+    DateTime(String),
+    /// `PNAME`: a person's name in DICOM `PN` format
+    PersonName(String),
+    /// `IMAGE`: a reference to an image
+    Image(SopReference),
+    /// `COMPOSITE`: a reference to a composite SOP instance
+    Composite(SopReference),
+    /// `SCOORD`: spatial coordinates in an image
+    Scoord {
+        /// The geometry of the coordinates
+        graphic_type: GraphicType,
+        /// The column and row coordinate pairs
+        graphic_data: Vec<f32>,
+    },
+    /// `TCOORD`: temporal coordinates
+    Tcoord {
+        /// The kind of temporal region
+        temporal_range_type: TemporalRangeType,
+        /// The reference points of the region
+        reference: TemporalReference,
+    },
+    /// A by-reference relationship to another content item,
+    /// identified by the item's position in the content tree
+    /// (see _Referenced Content Item Identifier_)
+    Reference(Vec<u32>),
+    /// A content item of a value type not covered above,
+    /// kept as the data set of the item
+    /// without its relationship type, concept name and _Content Sequence_
+    Other(InMemDicomObject),
+}
+
+impl ContentValue {
+    /// Obtain the value type of this content item value,
+    /// or `None` for a by-reference relationship.
+    pub fn value_type(&self) -> Option<&str> {
+        Some(match self {
+            ContentValue::Container { .. } => "CONTAINER",
+            ContentValue::Text(_) => "TEXT",
+            ContentValue::Code(_) => "CODE",
+            ContentValue::Num { .. } => "NUM",
+            ContentValue::UidRef(_) => "UIDREF",
+            ContentValue::Date(_) => "DATE",
+            ContentValue::Time(_) => "TIME",
+            ContentValue::DateTime(_) => "DATETIME",
+            ContentValue::PersonName(_) => "PNAME",
+            ContentValue::Image(_) => "IMAGE",
+            ContentValue::Composite(_) => "COMPOSITE",
+            ContentValue::Scoord { .. } => "SCOORD",
+            ContentValue::Tcoord { .. } => "TCOORD",
+            ContentValue::Reference(_) => return None,
+            ContentValue::Other(obj) => obj.get(tags::VALUE_TYPE)?.string().ok()?.trim_end(),
+        })
+    }
+
+    /// The attributes of a content item data set
+    /// which hold this value.
+    fn value_tags(&self) -> &'static [Tag] {
+        match self {
+            ContentValue::Container { .. } => {
+                &[tags::CONTINUITY_OF_CONTENT, tags::CONTENT_TEMPLATE_SEQUENCE]
+            }
+            ContentValue::Text(_) => &[tags::TEXT_VALUE],
+            ContentValue::Code(_) => &[tags::CONCEPT_CODE_SEQUENCE],
+            ContentValue::Num { .. } => &[
+                tags::MEASURED_VALUE_SEQUENCE,
+                tags::NUMERIC_VALUE_QUALIFIER_CODE_SEQUENCE,
+            ],
+            ContentValue::UidRef(_) => &[tags::UID],
+            ContentValue::Date(_) => &[tags::DATE],
+            ContentValue::Time(_) => &[tags::TIME],
+            ContentValue::DateTime(_) => &[tags::DATE_TIME],
+            ContentValue::PersonName(_) => &[tags::PERSON_NAME],
+            ContentValue::Image(_) | ContentValue::Composite(_) => &[tags::REFERENCED_SOP_SEQUENCE],
+            ContentValue::Scoord { .. } => &[tags::GRAPHIC_TYPE, tags::GRAPHIC_DATA],
+            ContentValue::Tcoord { .. } => &[
+                tags::TEMPORAL_RANGE_TYPE,
+                tags::REFERENCED_SAMPLE_POSITIONS,
+                tags::REFERENCED_TIME_OFFSETS,
+                tags::REFERENCED_DATE_TIME,
+            ],
+            ContentValue::Reference(_) | ContentValue::Other(_) => &[],
+        }
+    }
+}
+
+/// The attributes of a content item data set
+/// which hold the relationship, concept name and children of the item.
+const ITEM_TAGS: [Tag; 5] = [
+    tags::RELATIONSHIP_TYPE,
+    tags::VALUE_TYPE,
+    tags::CONCEPT_NAME_CODE_SEQUENCE,
+    tags::REFERENCED_CONTENT_ITEM_IDENTIFIER,
+    tags::CONTENT_SEQUENCE,
+];
+
+/// A content item of an SR content tree.
+///
+/// The root of the tree is the SR document itself,
+/// which is a container without a relationship type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentItem {
+    /// The relationship with the parent content item
+    /// (`None` for the root)
+    pub relationship_type: Option<RelationshipType>,
+    /// The concept name of the content item
+    /// (optional for some content items which are not containers)
+    pub concept_name: Option<Code>,
+    /// The value of the content item
+    pub value: ContentValue,
+    /// The child content items
+    pub children: Vec<ContentItem>,
+    /// Other attributes of the content item data set
+    /// which are not covered by the fields above,
+    /// such as _Observation DateTime_ or _Observation UID_.
+    ///
+    /// These are written back as is by [`to_dataset`](Self::to_dataset).
+    pub attributes: InMemDicomObject,
+}
+
+impl ContentItem {
+    /// Create a new content item with the given concept name and value,
+    /// without children.
+    pub fn new(concept_name: Option<Code>, value: ContentValue) -> Self {
+        ContentItem {
+            relationship_type: None,
+            concept_name,
+            value,
+            children: Vec::new(),
+            attributes: InMemDicomObject::new_empty(),
+        }
+    }
+
+    /// Create a new `CONTAINER` content item
+    /// with separate content.
+    pub fn container(concept_name: Code) -> Self {
+        Self::new(
+            Some(concept_name),
+            ContentValue::Container {
+                continuity: ContinuityOfContent::Separate,
+                template: None,
+            },
+        )
+    }
+
+    /// Create a new `TEXT` content item.
+    pub fn text(concept_name: Code, text: impl Into<String>) -> Self {
+        Self::new(Some(concept_name), ContentValue::Text(text.into()))
+    }
+
+    /// Create a new `CODE` content item.
+    pub fn code(concept_name: Code, code: Code) -> Self {
+        Self::new(Some(concept_name), ContentValue::Code(code))
+    }
+
+    /// Create a new `NUM` content item.
+    pub fn num(concept_name: Code, value: f64, unit: Code) -> Self {
+        Self::new(
+            Some(concept_name),
+            ContentValue::Num {
+                measurement: Some(Measurement { value, unit }),
+                qualifier: None,
+            },
+        )
+    }
+
+    /// Create a new `UIDREF` content item.
+    pub fn uid_ref(concept_name: Code, uid: impl Into<String>) -> Self {
+        Self::new(Some(concept_name), ContentValue::UidRef(uid.into()))
+    }
+
+    /// Create a new `DATE` content item.
+    pub fn date(concept_name: Code, date: impl Into<String>) -> Self {
+        Self::new(Some(concept_name), ContentValue::Date(date.into()))
+    }
+
+    /// Create a new `TIME` content item.
+    pub fn time(concept_name: Code, time: impl Into<String>) -> Self {
+        Self::new(Some(concept_name), ContentValue::Time(time.into()))
+    }
+
+    /// Create a new `DATETIME` content item.
+    pub fn date_time(concept_name: Code, date_time: impl Into<String>) -> Self {
+        Self::new(Some(concept_name), ContentValue::DateTime(date_time.into()))
+    }
+
+    /// Create a new `PNAME` content item.
+    pub fn person_name(concept_name: Code, name: impl Into<String>) -> Self {
+        Self::new(Some(concept_name), ContentValue::PersonName(name.into()))
+    }
+
+    /// Create a new `IMAGE` content item
+    /// referencing all frames of an image.
+    pub fn image(
+        concept_name: Option<Code>,
+        sop_class_uid: impl Into<String>,
+        sop_instance_uid: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            concept_name,
+            ContentValue::Image(SopReference {
+                sop_class_uid: sop_class_uid.into(),
+                sop_instance_uid: sop_instance_uid.into(),
+                frame_numbers: Vec::new(),
+            }),
+        )
+    }
+
+    /// Create a new `COMPOSITE` content item.
+    pub fn composite(
+        concept_name: Option<Code>,
+        sop_class_uid: impl Into<String>,
+        sop_instance_uid: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            concept_name,
+            ContentValue::Composite(SopReference {
+                sop_class_uid: sop_class_uid.into(),
+                sop_instance_uid: sop_instance_uid.into(),
+                frame_numbers: Vec::new(),
+            }),
+        )
+    }
+
+    /// Create a new `SCOORD` content item.
+    pub fn scoord(concept_name: Option<Code>, graphic_type: GraphicType, data: Vec<f32>) -> Self {
+        Self::new(
+            concept_name,
+            ContentValue::Scoord {
+                graphic_type,
+                graphic_data: data,
+            },
+        )
+    }
+
+    /// Create a new `TCOORD` content item.
+    pub fn tcoord(
+        concept_name: Option<Code>,
+        temporal_range_type: TemporalRangeType,
+        reference: TemporalReference,
+    ) -> Self {
+        Self::new(
+            concept_name,
+            ContentValue::Tcoord {
+                temporal_range_type,
+                reference,
+            },
+        )
+    }
+
+    /// Add a child content item with the given relationship.
+    pub fn with_child(mut self, relationship_type: RelationshipType, child: ContentItem) -> Self {
+        self.push_child(relationship_type, child);
+        self
+    }
+
+    /// Add a child content item with the given relationship.
+    pub fn push_child(&mut self, relationship_type: RelationshipType, mut child: ContentItem) {
+        child.relationship_type = Some(relationship_type);
+        self.children.push(child);
+    }
+
+    /// Iterate over this content item and all of its descendants,
+    /// depth first and in document order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { stack: vec![self] }
+    }
+
+    /// Find the first descendant content item
+    /// (or this content item itself)
+    /// with the given concept name.
+    pub fn find(&self, concept_name: &Code) -> Option<&ContentItem> {
+        self.iter().find(|item| item.has_concept_name(concept_name))
+    }
+
+    /// Find all descendant content items
+    /// (including this content item itself)
+    /// with the given concept name.
+    pub fn find_all<'a>(
+        &'a self,
+        concept_name: &'a Code,
+    ) -> impl Iterator<Item = &'a ContentItem> + 'a {
+        self.iter()
+            .filter(move |item| item.has_concept_name(concept_name))
+    }
+
+    fn has_concept_name(&self, concept_name: &Code) -> bool {
+        self.concept_name
+            .as_ref()
+            .map(|c| c.matches(concept_name))
+            .unwrap_or(false)
+    }
+
+    /// Read a content tree from a data set,
+    /// which can be either the root of an SR document
+    /// or an item of a _Content Sequence_.
+    pub fn from_dataset(obj: &InMemDicomObject) -> Result<Self> {
+        let relationship_type = opt_str(obj, tags::RELATIONSHIP_TYPE, "RelationshipType")?
+            .map(|s| s.parse())
+            .transpose()?;
+
+        if let Some(e) = obj.get(tags::REFERENCED_CONTENT_ITEM_IDENTIFIER) {
+            let identifier = e.to_multi_int().context(ConvertAttributeSnafu {
+                name: "ReferencedContentItemIdentifier",
+            })?;
+            return Ok(ContentItem {
+                relationship_type,
+                concept_name: None,
+                value: ContentValue::Reference(identifier),
+                children: Vec::new(),
+                attributes: other_attributes(obj, &[]),
+            });
+        }
+
+        let value_type = req_str(obj, tags::VALUE_TYPE, "ValueType")?;
+        let concept_name = opt_code(
+            obj,
+            tags::CONCEPT_NAME_CODE_SEQUENCE,
+            "ConceptNameCodeSequence",
+        )?;
+
+        let value = match value_type.as_str() {
+            "CONTAINER" => ContentValue::Container {
+                continuity: req_str(obj, tags::CONTINUITY_OF_CONTENT, "ContinuityOfContent")?
+                    .parse()?,
+                template: first_item(obj, tags::CONTENT_TEMPLATE_SEQUENCE)
+                    .map(|item| {
+                        Ok::<_, Error>(TemplateIdentifier {
+                            mapping_resource: req_str(
+                                item,
+                                tags::MAPPING_RESOURCE,
+                                "MappingResource",
+                            )?,
+                            identifier: req_str(
+                                item,
+                                tags::TEMPLATE_IDENTIFIER,
+                                "TemplateIdentifier",
+                            )?,
+                        })
+                    })
+                    .transpose()?,
+            },
+            "TEXT" => ContentValue::Text(req_str(obj, tags::TEXT_VALUE, "TextValue")?),
+            "CODE" => ContentValue::Code(
+                opt_code(obj, tags::CONCEPT_CODE_SEQUENCE, "ConceptCodeSequence")?.context(
+                    MissingAttributeSnafu {
+                        name: "ConceptCodeSequence",
+                    },
+                )?,
+            ),
+            "NUM" => ContentValue::Num {
+                measurement: first_item(obj, tags::MEASURED_VALUE_SEQUENCE)
+                    .map(read_measurement)
+                    .transpose()?,
+                qualifier: opt_code(
+                    obj,
+                    tags::NUMERIC_VALUE_QUALIFIER_CODE_SEQUENCE,
+                    "NumericValueQualifierCodeSequence",
+                )?,
+            },
+            "UIDREF" => ContentValue::UidRef(req_str(obj, tags::UID, "UID")?),
+            "DATE" => ContentValue::Date(req_str(obj, tags::DATE, "Date")?),
+            "TIME" => ContentValue::Time(req_str(obj, tags::TIME, "Time")?),
+            "DATETIME" => ContentValue::DateTime(req_str(obj, tags::DATE_TIME, "DateTime")?),
+            "PNAME" => ContentValue::PersonName(req_str(obj, tags::PERSON_NAME, "PersonName")?),
+            "IMAGE" => ContentValue::Image(read_sop_reference(obj)?),
+            "COMPOSITE" => ContentValue::Composite(read_sop_reference(obj)?),
+            "SCOORD" => ContentValue::Scoord {
+                graphic_type: req_str(obj, tags::GRAPHIC_TYPE, "GraphicType")?.parse()?,
+                graphic_data: obj
+                    .get(tags::GRAPHIC_DATA)
+                    .context(MissingAttributeSnafu {
+                        name: "GraphicData",
+                    })?
+                    .to_multi_float32()
+                    .context(ConvertAttributeSnafu {
+                        name: "GraphicData",
+                    })?,
+            },
+            "TCOORD" => ContentValue::Tcoord {
+                temporal_range_type: req_str(obj, tags::TEMPORAL_RANGE_TYPE, "TemporalRangeType")?
+                    .parse()?,
+                reference: read_temporal_reference(obj)?,
+            },
+            _ => {
+                let mut other = obj.clone();
+                other.remove_element(tags::RELATIONSHIP_TYPE);
+                other.remove_element(tags::CONCEPT_NAME_CODE_SEQUENCE);
+                other.remove_element(tags::CONTENT_SEQUENCE);
+                ContentValue::Other(other)
+            }
+        };
+
+        let children = match obj.get(tags::CONTENT_SEQUENCE).and_then(|e| e.items()) {
+            Some(items) => items
+                .iter()
+                .map(ContentItem::from_dataset)
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+
+        let attributes = match &value {
+            ContentValue::Other(_) => InMemDicomObject::new_empty(),
+            ContentValue::Image(_) | ContentValue::Composite(_) => {
+                let mut attributes = other_attributes(obj, value.value_tags());
+                // keep the rest of the referenced SOP item,
+                // such as Referenced Segment Number
+                if let Some(item) = first_item(obj, tags::REFERENCED_SOP_SEQUENCE) {
+                    let mut item = item.clone();
+                    item.remove_element(tags::REFERENCED_SOP_CLASS_UID);
+                    item.remove_element(tags::REFERENCED_SOP_INSTANCE_UID);
+                    item.remove_element(tags::REFERENCED_FRAME_NUMBER);
+                    if item.iter().next().is_some() {
+                        put_items(&mut attributes, tags::REFERENCED_SOP_SEQUENCE, vec![item]);
+                    }
+                }
+                attributes
+            }
+            value => other_attributes(obj, value.value_tags()),
+        };
+
+        Ok(ContentItem {
+            relationship_type,
+            concept_name,
+            value,
+            children,
+            attributes,
+        })
+    }
+
+    /// Create an item of a _Content Sequence_ with this content tree,
+    /// starting from the other attributes of each content item.
+    pub fn to_dataset(&self) -> InMemDicomObject {
+        let mut obj = self.attributes.clone();
+        self.put_into(&mut obj);
+        obj
+    }
+
+    /// Write this content tree into the given data set,
+    /// which can be either the root of an SR document
+    /// or an item of a _Content Sequence_.
+    ///
+    /// Other attributes of the data set are kept as is,
+    /// whereas the child content items are written
+    /// with [`to_dataset`](Self::to_dataset).
+    pub fn put_into(&self, obj: &mut InMemDicomObject) {
+        if let Some(relationship_type) = self.relationship_type {
+            put_str(
+                obj,
+                tags::RELATIONSHIP_TYPE,
+                VR::CS,
+                relationship_type.as_str(),
+            );
+        }
+        if let Some(value_type) = self.value.value_type() {
+            put_str(obj, tags::VALUE_TYPE, VR::CS, value_type);
+        }
+        if let Some(concept_name) = &self.concept_name {
+            put_items(
+                obj,
+                tags::CONCEPT_NAME_CODE_SEQUENCE,
+                vec![concept_name.to_item()],
+            );
+        }
+
+        match &self.value {
+            ContentValue::Container {
+                continuity,
+                template,
+            } => {
+                put_str(
+                    obj,
+                    tags::CONTINUITY_OF_CONTENT,
+                    VR::CS,
+                    continuity.as_str(),
+                );
+                if let Some(template) = template {
+                    let mut item = InMemDicomObject::new_empty();
+                    put_str(
+                        &mut item,
+                        tags::MAPPING_RESOURCE,
+                        VR::CS,
+                        &template.mapping_resource,
+                    );
+                    put_str(
+                        &mut item,
+                        tags::TEMPLATE_IDENTIFIER,
+                        VR::CS,
+                        &template.identifier,
+                    );
+                    put_items(obj, tags::CONTENT_TEMPLATE_SEQUENCE, vec![item]);
+                }
+            }
+            ContentValue::Text(text) => put_str(obj, tags::TEXT_VALUE, VR::UT, text),
+            ContentValue::Code(code) => {
+                put_items(obj, tags::CONCEPT_CODE_SEQUENCE, vec![code.to_item()])
+            }
+            ContentValue::Num {
+                measurement,
+                qualifier,
+            } => {
+                let items = measurement
+                    .iter()
+                    .map(|measurement| {
+                        let mut item = InMemDicomObject::new_empty();
+                        let value = format_decimal_string(measurement.value);
+                        if value.parse::<f64>().ok() != Some(measurement.value) {
+                            item.put(DataElement::new(
+                                tags::FLOATING_POINT_VALUE,
+                                VR::FD,
+                                PrimitiveValue::from(measurement.value),
+                            ));
+                        }
+                        put_str(&mut item, tags::NUMERIC_VALUE, VR::DS, &value);
+                        put_items(
+                            &mut item,
+                            tags::MEASUREMENT_UNITS_CODE_SEQUENCE,
+                            vec![measurement.unit.to_item()],
+                        );
+                        item
+                    })
+                    .collect();
+                put_items(obj, tags::MEASURED_VALUE_SEQUENCE, items);
+                if let Some(qualifier) = qualifier {
+                    put_items(
+                        obj,
+                        tags::NUMERIC_VALUE_QUALIFIER_CODE_SEQUENCE,
+                        vec![qualifier.to_item()],
+                    );
+                }
+            }
+            ContentValue::UidRef(uid) => put_str(obj, tags::UID, VR::UI, uid),
+            ContentValue::Date(date) => put_str(obj, tags::DATE, VR::DA, date),
+            ContentValue::Time(time) => put_str(obj, tags::TIME, VR::TM, time),
+            ContentValue::DateTime(date_time) => put_str(obj, tags::DATE_TIME, VR::DT, date_time),
+            ContentValue::PersonName(name) => put_str(obj, tags::PERSON_NAME, VR::PN, name),
+            ContentValue::Image(reference) | ContentValue::Composite(reference) => {
+                let mut item = first_item(obj, tags::REFERENCED_SOP_SEQUENCE)
+                    .cloned()
+                    .unwrap_or_else(InMemDicomObject::new_empty);
+                put_str(
+                    &mut item,
+                    tags::REFERENCED_SOP_CLASS_UID,
+                    VR::UI,
+                    &reference.sop_class_uid,
+                );
+                put_str(
+                    &mut item,
+                    tags::REFERENCED_SOP_INSTANCE_UID,
+                    VR::UI,
+                    &reference.sop_instance_uid,
+                );
+                if !reference.frame_numbers.is_empty() {
+                    item.put(DataElement::new(
+                        tags::REFERENCED_FRAME_NUMBER,
+                        VR::IS,
+                        PrimitiveValue::Strs(
+                            reference
+                                .frame_numbers
+                                .iter()
+                                .map(|n| n.to_string())
+                                .collect(),
+                        ),
+                    ));
+                } else {
+                    item.remove_element(tags::REFERENCED_FRAME_NUMBER);
+                }
+                put_items(obj, tags::REFERENCED_SOP_SEQUENCE, vec![item]);
+            }
+            ContentValue::Scoord {
+                graphic_type,
+                graphic_data,
+            } => {
+                put_str(obj, tags::GRAPHIC_TYPE, VR::CS, graphic_type.as_str());
+                obj.put(DataElement::new(
+                    tags::GRAPHIC_DATA,
+                    VR::FL,
+                    PrimitiveValue::F32(graphic_data.iter().copied().collect()),
+                ));
+            }
+            ContentValue::Tcoord {
+                temporal_range_type,
+                reference,
+            } => {
+                put_str(
+                    obj,
+                    tags::TEMPORAL_RANGE_TYPE,
+                    VR::CS,
+                    temporal_range_type.as_str(),
+                );
+                obj.put(match reference {
+                    TemporalReference::SamplePositions(positions) => DataElement::new(
+                        tags::REFERENCED_SAMPLE_POSITIONS,
+                        VR::UL,
+                        PrimitiveValue::U32(positions.iter().copied().collect()),
+                    ),
+                    TemporalReference::TimeOffsets(offsets) => DataElement::new(
+                        tags::REFERENCED_TIME_OFFSETS,
+                        VR::DS,
+                        PrimitiveValue::Strs(
+                            offsets.iter().map(|&o| format_decimal_string(o)).collect(),
+                        ),
+                    ),
+                    TemporalReference::DateTimes(date_times) => DataElement::new(
+                        tags::REFERENCED_DATE_TIME,
+                        VR::DT,
+                        PrimitiveValue::Strs(date_times.iter().cloned().collect()),
+                    ),
+                });
+            }
+            ContentValue::Reference(identifier) => {
+                obj.put(DataElement::new(
+                    tags::REFERENCED_CONTENT_ITEM_IDENTIFIER,
+                    VR::UL,
+                    PrimitiveValue::U32(identifier.iter().copied().collect()),
+                ));
+            }
+            ContentValue::Other(other) => {
+                for e in other.iter() {
+                    obj.put(e.clone());
+                }
+            }
+        }
+
+        if self.children.is_empty() {
+            obj.remove_element(tags::CONTENT_SEQUENCE);
+        } else {
+            put_items(
+                obj,
+                tags::CONTENT_SEQUENCE,
+                self.children.iter().map(ContentItem::to_dataset).collect(),
+            );
+        }
+    }
+}
+
+/// An iterator over a content item and its descendants,
+/// depth first and in document order.
+///
+/// See [`ContentItem::iter`].
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    stack: Vec<&'a ContentItem>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a ContentItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.stack.pop()?;
+        self.stack.extend(item.children.iter().rev());
+        Some(item)
+    }
+}
+
+fn read_measurement(item: &InMemDicomObject) -> Result<Measurement> {
+    let value = match item.get(tags::FLOATING_POINT_VALUE) {
+        Some(e) => e.to_float64().context(ConvertAttributeSnafu {
+            name: "FloatingPointValue",
+        })?,
+        None => item
+            .get(tags::NUMERIC_VALUE)
+            .context(MissingAttributeSnafu {
+                name: "NumericValue",
+            })?
+            .to_float64()
+            .context(ConvertAttributeSnafu {
+                name: "NumericValue",
+            })?,
+    };
+    let unit = opt_code(
+        item,
+        tags::MEASUREMENT_UNITS_CODE_SEQUENCE,
+        "MeasurementUnitsCodeSequence",
+    )?
+    .context(MissingAttributeSnafu {
+        name: "MeasurementUnitsCodeSequence",
+    })?;
+    Ok(Measurement { value, unit })
+}
+
+fn read_sop_reference(obj: &InMemDicomObject) -> Result<SopReference> {
+    let item = first_item(obj, tags::REFERENCED_SOP_SEQUENCE).context(EmptySequenceSnafu {
+        name: "ReferencedSOPSequence",
+    })?;
+    let frame_numbers = match item.get(tags::REFERENCED_FRAME_NUMBER) {
+        Some(e) => e.to_multi_int().context(ConvertAttributeSnafu {
+            name: "ReferencedFrameNumber",
+        })?,
+        None => Vec::new(),
+    };
+    Ok(SopReference {
+        sop_class_uid: req_str(
+            item,
+            tags::REFERENCED_SOP_CLASS_UID,
+            "ReferencedSOPClassUID",
+        )?,
+        sop_instance_uid: req_str(
+            item,
+            tags::REFERENCED_SOP_INSTANCE_UID,
+            "ReferencedSOPInstanceUID",
+        )?,
+        frame_numbers,
+    })
+}
+
+fn read_temporal_reference(obj: &InMemDicomObject) -> Result<TemporalReference> {
+    if let Some(e) = obj.get(tags::REFERENCED_SAMPLE_POSITIONS) {
+        return Ok(TemporalReference::SamplePositions(
+            e.to_multi_int().context(ConvertAttributeSnafu {
+                name: "ReferencedSamplePositions",
+            })?,
+        ));
+    }
+    if let Some(e) = obj.get(tags::REFERENCED_TIME_OFFSETS) {
+        return Ok(TemporalReference::TimeOffsets(
+            e.to_multi_float64().context(ConvertAttributeSnafu {
+                name: "ReferencedTimeOffsets",
+            })?,
+        ));
+    }
+    let e = obj
+        .get(tags::REFERENCED_DATE_TIME)
+        .context(MissingAttributeSnafu {
+            name: "ReferencedDateTime",
+        })?;
+    Ok(TemporalReference::DateTimes(
+        e.to_multi_str()
+            .context(CastAttributeSnafu {
+                name: "ReferencedDateTime",
+            })?
+            .iter()
+            .map(|s| s.trim_end().to_string())
+            .collect(),
+    ))
+}
+
+/// Retrieve the first item of a sequence attribute, if any.
+fn first_item(obj: &InMemDicomObject, tag: Tag) -> Option<&InMemDicomObject> {
+    obj.get(tag)?.items()?.first()
+}
+
+/// Copy the attributes of a content item data set
+/// other than those of the item itself and of its value.
+fn other_attributes(obj: &InMemDicomObject, value_tags: &[Tag]) -> InMemDicomObject {
+    let mut attributes = obj.clone();
+    for &tag in ITEM_TAGS.iter().chain(value_tags) {
+        attributes.remove_element(tag);
+    }
+    attributes
+}
+
+/// Read the code in the first item of a code sequence attribute, if any.
+fn opt_code(obj: &InMemDicomObject, tag: Tag, name: &'static str) -> Result<Option<Code>> {
+    match obj.get(tag) {
+        None => Ok(None),
+        Some(e) => match e.items() {
+            Some([]) => Ok(None),
+            Some([item, ..]) => Code::from_item(item).map(Some),
+            None => EmptySequenceSnafu { name }.fail(),
+        },
+    }
+}
+
+fn opt_str(obj: &InMemDicomObject, tag: Tag, name: &'static str) -> Result<Option<String>> {
+    obj.get(tag)
+        .map(|e| {
+            e.to_str()
+                .map(|s| s.trim_end_matches([' ', '\0']).to_string())
+                .context(ConvertAttributeSnafu { name })
+        })
+        .transpose()
+}
+
+fn req_str(obj: &InMemDicomObject, tag: Tag, name: &'static str) -> Result<String> {
+    opt_str(obj, tag, name)?.context(MissingAttributeSnafu { name })
+}
+
+fn put_str(obj: &mut InMemDicomObject, tag: Tag, vr: VR, value: &str) {
+    obj.put(DataElement::new(tag, vr, value));
+}
+
+fn put_items(obj: &mut InMemDicomObject, tag: Tag, items: Vec<InMemDicomObject>) {
+    obj.put(DataElement::new(tag, VR::SQ, DataSetSequence::from(items)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(value: &str, meaning: &str) -> Code {
+        Code::new(value, "DCM", meaning)
+    }
+
+    fn report() -> ContentItem {
+        let mut root = ContentItem::container(code("126000", "Imaging Measurement Report"));
+        if let ContentValue::Container { template, .. } = &mut root.value {
+            *template = Some(TemplateIdentifier {
+                mapping_resource: "DCMR".to_string(),
+                identifier: "1500".to_string(),
+            });
+        }
+        root.with_child(
+            RelationshipType::HasConceptMod,
+            ContentItem::code(
+                code("121049", "Language of Content Item and Descendants"),
+                Code::new("eng", "RFC5646", "English"),
+            ),
+        )
+        .with_child(
+            RelationshipType::HasObsContext,
+            ContentItem::person_name(code("121008", "Person Observer Name"), "Doe^Jane"),
+        )
+        .with_child(
+            RelationshipType::Contains,
+            ContentItem::container(code("126010", "Imaging Measurements"))
+                .with_child(
+                    RelationshipType::Contains,
+                    ContentItem::num(
+                        Code::new("410668003", "SCT", "Length"),
+                        0.1 + 0.2,
+                        Code::new("mm", "UCUM", "millimeter"),
+                    )
+                    .with_child(
+                        RelationshipType::InferredFrom,
+                        ContentItem::scoord(None, GraphicType::Polyline, vec![1., 2., 30.5, 40.])
+                            .with_child(
+                                RelationshipType::SelectedFrom,
+                                ContentItem::image(None, "1.2.840.10008.5.1.4.1.1.2", "1.2.3.4"),
+                            ),
+                    ),
+                )
+                .with_child(
+                    RelationshipType::Contains,
+                    ContentItem::text(code("121106", "Comment"), "Within normal limits"),
+                )
+                .with_child(
+                    RelationshipType::Contains,
+                    ContentItem::tcoord(
+                        None,
+                        TemporalRangeType::Segment,
+                        TemporalReference::TimeOffsets(vec![0.5, 1.25]),
+                    ),
+                )
+                .with_child(
+                    RelationshipType::InferredFrom,
+                    ContentItem::new(None, ContentValue::Reference(vec![1, 3, 1])),
+                ),
+        )
+        .with_child(
+            RelationshipType::HasObsContext,
+            ContentItem::uid_ref(code("121018", "Procedure Study Instance UID"), "1.2.3"),
+        )
+        .with_child(
+            RelationshipType::HasObsContext,
+            ContentItem::date(code("111060", "Study Date"), "20240101"),
+        )
+    }
+
+    #[test]
+    fn write_and_read_content_tree() {
+        let report = report();
+        let mut obj = InMemDicomObject::new_empty();
+        report.put_into(&mut obj);
+
+        // root attributes
+        assert_eq!(
+            obj.element(tags::VALUE_TYPE).unwrap().to_str().unwrap(),
+            "CONTAINER"
+        );
+        assert!(obj.get(tags::RELATIONSHIP_TYPE).is_none());
+        let content = obj
+            .element(tags::CONTENT_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(content.len(), 5);
+        assert_eq!(
+            content[1]
+                .element(tags::RELATIONSHIP_TYPE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "HAS OBS CONTEXT"
+        );
+
+        let tree = ContentItem::from_dataset(&obj).unwrap();
+        assert_eq!(tree, report);
+    }
+
+    #[test]
+    fn traverse_content_tree() {
+        let report = report();
+        assert_eq!(report.iter().count(), 12);
+
+        let value_types: Vec<_> = report
+            .iter()
+            .take(7)
+            .map(|item| item.value.value_type().unwrap())
+            .collect();
+        assert_eq!(
+            value_types,
+            [
+                "CONTAINER",
+                "CODE",
+                "PNAME",
+                "CONTAINER",
+                "NUM",
+                "SCOORD",
+                "IMAGE"
+            ]
+        );
+
+        let length = report
+            .find(&Code::new("410668003", "SCT", "Length (any meaning)"))
+            .unwrap();
+        match &length.value {
+            ContentValue::Num {
+                measurement: Some(measurement),
+                ..
+            } => {
+                assert_eq!(measurement.value, 0.1 + 0.2);
+                assert_eq!(measurement.unit.value, "mm");
+            }
+            value => panic!("unexpected value {:?}", value),
+        }
+        assert!(report.find(&code("999999", "Missing")).is_none());
+    }
+
+    #[test]
+    fn num_value_precision() {
+        let item = ContentItem::num(code("1", "Value"), 0.1 + 0.2, code("1", "no units"));
+        let obj = item.to_dataset();
+        let measured = first_item(&obj, tags::MEASURED_VALUE_SEQUENCE).unwrap();
+        let numeric_value = measured
+            .element(tags::NUMERIC_VALUE)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(numeric_value.len() <= 16);
+        // the exact value is kept in Floating Point Value
+        assert_eq!(
+            measured
+                .element(tags::FLOATING_POINT_VALUE)
+                .unwrap()
+                .to_float64()
+                .unwrap(),
+            0.1 + 0.2
+        );
+
+        let item = ContentItem::num(code("1", "Value"), 12.5, code("1", "no units"));
+        let obj = item.to_dataset();
+        let measured = first_item(&obj, tags::MEASURED_VALUE_SEQUENCE).unwrap();
+        assert_eq!(
+            measured
+                .element(tags::NUMERIC_VALUE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "12.5"
+        );
+        assert!(measured.get(tags::FLOATING_POINT_VALUE).is_none());
+    }
+
+    #[test]
+    fn long_and_urn_code_values() {
+        let long = Code::new("12345678901234567890", "SCT", "Some concept");
+        let item = long.to_item();
+        assert!(item.get(tags::CODE_VALUE).is_none());
+        assert!(item.get(tags::LONG_CODE_VALUE).is_some());
+        assert_eq!(Code::from_item(&item).unwrap(), long);
+
+        let urn = Code::new(
+            "urn:lex:us:federal:codified.regulation:2013-04-25;45CFR164",
+            "",
+            "HIPAA",
+        );
+        let item = urn.to_item();
+        assert!(item.get(tags::URN_CODE_VALUE).is_some());
+        assert!(item.get(tags::CODING_SCHEME_DESIGNATOR).is_none());
+        assert_eq!(Code::from_item(&item).unwrap(), urn);
+    }
+
+    #[test]
+    fn reject_unknown_terms() {
+        let mut obj = ContentItem::text(code("121106", "Comment"), "text").to_dataset();
+        put_str(&mut obj, tags::RELATIONSHIP_TYPE, VR::CS, "HAS PARENT");
+        assert!(matches!(
+            ContentItem::from_dataset(&obj),
+            Err(Error::UnknownTerm { .. })
+        ));
+        assert!("HAS PARENT".parse::<RelationshipType>().is_err());
+        assert_eq!(
+            "SELECTED FROM ".parse::<RelationshipType>().unwrap(),
+            RelationshipType::SelectedFrom
+        );
+    }
+
+    #[test]
+    fn keep_other_value_types() {
+        let mut obj = InMemDicomObject::new_empty();
+        put_str(&mut obj, tags::RELATIONSHIP_TYPE, VR::CS, "CONTAINS");
+        put_str(&mut obj, tags::VALUE_TYPE, VR::CS, "WAVEFORM");
+        put_items(
+            &mut obj,
+            tags::CONCEPT_NAME_CODE_SEQUENCE,
+            vec![code("121112", "Source of Measurement").to_item()],
+        );
+        put_items(
+            &mut obj,
+            tags::REFERENCED_SOP_SEQUENCE,
+            vec![InMemDicomObject::from_element_iter([DataElement::new(
+                tags::REFERENCED_SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.3.4"),
+            )])],
+        );
+        let mut child = ContentItem::text(code("121106", "Comment"), "text").to_dataset();
+        put_str(
+            &mut child,
+            tags::RELATIONSHIP_TYPE,
+            VR::CS,
+            "HAS PROPERTIES",
+        );
+        put_items(&mut obj, tags::CONTENT_SEQUENCE, vec![child]);
+
+        let item = ContentItem::from_dataset(&obj).unwrap();
+        assert_eq!(item.relationship_type, Some(RelationshipType::Contains));
+        assert_eq!(item.value.value_type(), Some("WAVEFORM"));
+        assert_eq!(item.children.len(), 1);
+        match &item.value {
+            ContentValue::Other(other) => {
+                assert!(other.get(tags::REFERENCED_SOP_SEQUENCE).is_some());
+                assert!(other.get(tags::CONCEPT_NAME_CODE_SEQUENCE).is_none());
+                assert!(other.get(tags::CONTENT_SEQUENCE).is_none());
+            }
+            value => panic!("unexpected value {:?}", value),
+        }
+
+        let written = item.to_dataset();
+        let read = ContentItem::from_dataset(&written).unwrap();
+        assert_eq!(read.relationship_type, item.relationship_type);
+        assert_eq!(read.concept_name, item.concept_name);
+        assert_eq!(read.value.value_type(), Some("WAVEFORM"));
+        assert_eq!(read.children, item.children);
+        let reference = first_item(&written, tags::REFERENCED_SOP_SEQUENCE).unwrap();
+        assert_eq!(
+            reference
+                .element(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "1.2.3.4"
+        );
+    }
+
+    #[test]
+    fn put_into_removes_stale_content() {
+        let mut obj = InMemDicomObject::new_empty();
+        report().put_into(&mut obj);
+        assert!(obj.get(tags::CONTENT_SEQUENCE).is_some());
+
+        let root = ContentItem::container(code("126000", "Imaging Measurement Report"));
+        root.put_into(&mut obj);
+        assert!(obj.get(tags::CONTENT_SEQUENCE).is_none());
+        assert_eq!(
+            ContentItem::from_dataset(&obj).unwrap().children,
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn keep_other_attributes_of_content_items() {
+        let mut text = ContentItem::text(code("121106", "Comment"), "text").to_dataset();
+        put_str(&mut text, tags::RELATIONSHIP_TYPE, VR::CS, "CONTAINS");
+        put_str(
+            &mut text,
+            tags::OBSERVATION_DATE_TIME,
+            VR::DT,
+            "20240101120000",
+        );
+        let mut image =
+            ContentItem::image(None, "1.2.840.10008.5.1.4.1.1.66.4", "1.2.3.4").to_dataset();
+        put_str(&mut image, tags::RELATIONSHIP_TYPE, VR::CS, "CONTAINS");
+        put_items(
+            &mut image,
+            tags::REFERENCED_SOP_SEQUENCE,
+            vec![InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::REFERENCED_SOP_CLASS_UID,
+                    VR::UI,
+                    PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.66.4"),
+                ),
+                DataElement::new(
+                    tags::REFERENCED_SOP_INSTANCE_UID,
+                    VR::UI,
+                    PrimitiveValue::from("1.2.3.4"),
+                ),
+                DataElement::new(
+                    tags::REFERENCED_SEGMENT_NUMBER,
+                    VR::US,
+                    PrimitiveValue::from(2_u16),
+                ),
+            ])],
+        );
+        let mut obj =
+            ContentItem::container(code("126000", "Imaging Measurement Report")).to_dataset();
+        put_items(&mut obj, tags::CONTENT_SEQUENCE, vec![text, image]);
+
+        let mut item = ContentItem::from_dataset(&obj).unwrap();
+        assert!(item.children[0]
+            .attributes
+            .get(tags::OBSERVATION_DATE_TIME)
+            .is_some());
+        assert!(item.children[0].attributes.get(tags::TEXT_VALUE).is_none());
+        item.children[0].value = ContentValue::Text("modified".to_string());
+
+        let written = item.to_dataset();
+        let children = written
+            .get(tags::CONTENT_SEQUENCE)
+            .and_then(|e| e.items())
+            .unwrap();
+        assert_eq!(
+            children[0]
+                .element(tags::OBSERVATION_DATE_TIME)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "20240101120000"
+        );
+        assert_eq!(
+            children[0]
+                .element(tags::TEXT_VALUE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "modified"
+        );
+        let reference = first_item(&children[1], tags::REFERENCED_SOP_SEQUENCE).unwrap();
+        assert_eq!(
+            reference
+                .element(tags::REFERENCED_SEGMENT_NUMBER)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            reference
+                .element(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "1.2.3.4"
+        );
+    }
+}
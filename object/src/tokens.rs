@@ -1,5 +1,5 @@
 //! Conversion of DICOM objects into tokens.
-use crate::mem::InMemDicomObject;
+use crate::mem::{EncodingOrderIter, InMemDicomObject, InMemElement};
 use dicom_core::DataElement;
 use dicom_parser::dataset::{DataToken, IntoTokens, IntoTokensOptions};
 use std::collections::VecDeque;
@@ -86,16 +86,20 @@ where
 }
 
 impl<D> IntoTokens for InMemDicomObject<D> {
-    type Iter = InMemObjectTokens<<InMemDicomObject<D> as IntoIterator>::IntoIter>;
+    type Iter = InMemObjectTokens<std::vec::IntoIter<InMemElement<D>>>;
 
     fn into_tokens(self) -> Self::Iter {
-        InMemObjectTokens::new(self)
+        InMemObjectTokens::new(self.into_elements_in_encoding_order())
     }
 
     fn into_tokens_with_options(self, mut options: IntoTokensOptions) -> Self::Iter {
         //This is required for recursing with the correct option
         options.force_invalidate_sq_length |= self.charset_changed;
-        InMemObjectTokens::new_with_options(self, options)
+        InMemObjectTokens::new_with_options(self.into_elements_in_encoding_order(), options)
+    }
+
+    fn preserves_encoding(&self) -> bool {
+        InMemDicomObject::preserves_encoding(self)
     }
 }
 
@@ -103,8 +107,7 @@ impl<'a, D> IntoTokens for &'a InMemDicomObject<D>
 where
     D: Clone,
 {
-    type Iter =
-        InMemObjectTokens<std::iter::Cloned<<&'a InMemDicomObject<D> as IntoIterator>::IntoIter>>;
+    type Iter = InMemObjectTokens<std::iter::Cloned<EncodingOrderIter<'a, D>>>;
 
     fn into_tokens(self) -> Self::Iter {
         self.into_tokens_with_options(Default::default())
@@ -113,6 +116,10 @@ where
     fn into_tokens_with_options(self, mut options: IntoTokensOptions) -> Self::Iter {
         options.force_invalidate_sq_length |= self.charset_changed;
 
-        InMemObjectTokens::new_with_options(self.into_iter().cloned(), options)
+        InMemObjectTokens::new_with_options(self.iter_in_encoding_order().cloned(), options)
+    }
+
+    fn preserves_encoding(&self) -> bool {
+        InMemDicomObject::preserves_encoding(self)
     }
 }
@@ -0,0 +1,40 @@
+//! Generation of new unique identifiers (UIDs).
+//!
+//! UIDs are derived from random UUIDs under the `2.25` root,
+//! as described in the standard, part 5, section B.2.
+//! This is suitable for new SOP instances, series and studies
+//! without the need for an organization root.
+
+/// Generate a new random UID under the `2.25` root.
+///
+/// # Example
+///
+/// ```
+/// use dicom_object::uid::new_uid;
+///
+/// let uid = new_uid();
+/// assert!(uid.starts_with("2.25."));
+/// assert!(uid.len() <= 64);
+/// assert_ne!(uid, new_uid());
+/// ```
+pub fn new_uid() -> String {
+    format!("2.25.{}", uuid::Uuid::new_v4().as_u128())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::new_uid;
+
+    #[test]
+    fn new_uids_are_valid() {
+        for _ in 0..16 {
+            let uid = new_uid();
+            assert!(uid.len() <= 64, "UID {} is too long", uid);
+            let (root, number) = uid.split_at(5);
+            assert_eq!(root, "2.25.");
+            assert!(number.bytes().all(|c| c.is_ascii_digit()));
+            // no leading zeros in components
+            assert!(!number.starts_with('0'));
+        }
+    }
+}
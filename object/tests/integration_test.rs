@@ -153,3 +153,37 @@ fn test_expl_vr_le_no_meta() {
         "1.2.333.4444.5.6.7.8.9",
     );
 }
+
+#[test]
+fn test_read_deflated() {
+    let path =
+        dicom_test_files::path("pydicom/image_dfl.dcm").expect("test DICOM file should exist");
+    let object = open_file(&path).unwrap();
+
+    assert_eq!(object.meta().transfer_syntax(), "1.2.840.10008.1.2.1.99");
+
+    // data set was inflated correctly
+    let rows = object.element(tags::ROWS).unwrap().to_int::<u32>().unwrap();
+    let cols = object
+        .element(tags::COLUMNS)
+        .unwrap()
+        .to_int::<u32>()
+        .unwrap();
+    let bits_allocated = object
+        .element(tags::BITS_ALLOCATED)
+        .unwrap()
+        .to_int::<u32>()
+        .unwrap();
+    let pixel_data = object
+        .element(tags::PIXEL_DATA)
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+    assert_eq!(pixel_data.len() as u32, rows * cols * (bits_allocated / 8));
+
+    // write it back in memory and read it again
+    let mut out = Vec::new();
+    object.write_all(&mut out).unwrap();
+    let object2 = OpenFileOptions::new().from_reader(&out[..]).unwrap();
+    assert_eq!(object, object2);
+}
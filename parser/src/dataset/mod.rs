@@ -449,6 +449,15 @@ pub trait IntoTokens {
 
     fn into_tokens(self) -> Self::Iter;
     fn into_tokens_with_options(self, options: IntoTokensOptions) -> Self::Iter;
+
+    /// Whether the tokens reproduce the original encoding of a data set,
+    /// so that writers should keep the value lengths declared in them
+    /// (see [`DataSetWriterOptions`](write::DataSetWriterOptions)).
+    ///
+    /// The default implementation returns `false`.
+    fn preserves_encoding(&self) -> bool {
+        false
+    }
 }
 
 impl IntoTokens for dicom_core::header::EmptyObject {
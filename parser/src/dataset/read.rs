@@ -4,6 +4,7 @@
 //! The rest of the crate is used to obtain DICOM element headers and values.
 //! At this level, headers and values are treated as tokens which can be used
 //! to form a syntax tree of a full data set.
+use crate::diagnostics::{DiagnosticKind, Diagnostics};
 use crate::stateful::decode::{DynStatefulDecoder, Error as DecoderError, StatefulDecode};
 use dicom_core::header::{DataElementHeader, Header, Length, SequenceItemHeader};
 use dicom_core::{PrimitiveValue, Tag, VR};
@@ -199,6 +200,11 @@ pub struct DataSetReader<S> {
     last_header: Option<DataElementHeader>,
     /// if a peek was taken, this holds the token peeked
     peek: Option<DataToken>,
+    /// the collector of non-fatal anomalies, if any
+    diagnostics: Option<Diagnostics>,
+    /// the sequences currently open and the index of their current item,
+    /// to resolve the location of diagnostics
+    item_path: Vec<(Tag, u32)>,
 }
 
 impl<R> DataSetReader<DynStatefulDecoder<R>> {
@@ -264,6 +270,8 @@ impl<R> DataSetReader<DynStatefulDecoder<R>> {
             hard_break: false,
             last_header: None,
             peek: None,
+            diagnostics: None,
+            item_path: Vec::new(),
         })
     }
 }
@@ -281,6 +289,8 @@ impl<S> DataSetReader<S> {
             hard_break: false,
             last_header: None,
             peek: None,
+            diagnostics: None,
+            item_path: Vec::new(),
         }
     }
 }
@@ -292,6 +302,38 @@ where
     type Item = Result<DataToken>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let diagnostics_before = self.diagnostics.as_ref().map(|d| d.len());
+        let token = self.next_token();
+
+        if let (Some(diagnostics), Some(since)) = (&self.diagnostics, diagnostics_before) {
+            diagnostics.resolve_selectors(since, &self.item_path);
+        }
+        match &token {
+            Some(Ok(DataToken::SequenceStart { tag, .. })) => self.item_path.push((*tag, 0)),
+            Some(Ok(DataToken::PixelSequenceStart)) => {
+                self.item_path.push((Tag(0x7FE0, 0x0010), 0))
+            }
+            Some(Ok(DataToken::ItemEnd)) => {
+                if let Some((_, item)) = self.item_path.last_mut() {
+                    *item += 1;
+                }
+            }
+            Some(Ok(DataToken::SequenceEnd)) => {
+                self.item_path.pop();
+            }
+            _ => {}
+        }
+        token
+    }
+}
+
+impl<S> DataSetReader<S>
+where
+    S: StatefulDecode,
+{
+    /// Read the next token,
+    /// without keeping track of the current item path.
+    fn next_token(&mut self) -> Option<Result<DataToken>> {
         if self.hard_break {
             return None;
         }
@@ -319,7 +361,11 @@ where
                 Ok(header) => {
                     match header {
                         SequenceItemHeader::Item { len } => {
-                            let len = match self.sanitize_length(len) {
+                            let len = match self.sanitize_length(
+                                len,
+                                Tag(0xFFFE, 0xE000),
+                                self.parser.position() - 8,
+                            ) {
                                 Some(len) => len,
                                 None => {
                                     return Some(
@@ -440,7 +486,11 @@ where
                 match self.parser.decode_item_header() {
                     Ok(header) => match header {
                         SequenceItemHeader::Item { len } => {
-                            let len = match self.sanitize_length(len) {
+                            let len = match self.sanitize_length(
+                                len,
+                                Tag(0xFFFE, 0xE000),
+                                self.parser.position() - 8,
+                            ) {
                                 Some(len) => len,
                                 None => {
                                     return Some(
@@ -500,13 +550,14 @@ where
             }
         } else {
             // a data element header or item delimiter is expected
+            let position = self.parser.position();
             match self.parser.decode_header() {
                 Ok(DataElementHeader {
                     tag,
                     vr: VR::SQ,
                     len,
                 }) => {
-                    let len = match self.sanitize_length(len) {
+                    let len = match self.sanitize_length(len, tag, position) {
                         Some(len) => len,
                         None => {
                             return Some(
@@ -539,8 +590,15 @@ where
                         "Item delimitation item outside of a sequence in position {}",
                         self.parser.position()
                     );
+                    if let Some(diagnostics) = &self.diagnostics {
+                        diagnostics.record(
+                            position,
+                            Some(Tag(0xFFFE, 0xE00D)),
+                            DiagnosticKind::DelimiterOutsideSequence,
+                        );
+                    }
                     // return a new token by calling the method again
-                    self.next()
+                    self.next_token()
                 }
                 Ok(DataElementHeader {
                     tag: Tag(0xFFFE, 0xE00D),
@@ -573,7 +631,7 @@ where
                     Some(Ok(DataToken::SequenceStart { tag, len }))
                 }
                 Ok(mut header) => {
-                    match self.sanitize_length(header.len) {
+                    match self.sanitize_length(header.len, header.tag, position) {
                         Some(len) => header.len = len,
                         None => {
                             return Some(
@@ -616,6 +674,16 @@ impl<S> DataSetReader<S>
 where
     S: StatefulDecode,
 {
+    /// Attach a collector of non-fatal anomalies found while reading,
+    /// including those found by the underlying stateful decoder.
+    ///
+    /// See the [`diagnostics`](crate::diagnostics) module for more details.
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.parser.set_diagnostics(diagnostics.clone());
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
     /// Peek the next token from the source by
     /// reading a new token in the first call.
     /// Subsequent calls to `peek` will return the same token
@@ -697,8 +765,17 @@ where
     /// Check for a non-compliant length
     /// and handle it according to the current strategy.
     /// Returns `None` if the length cannot or should not be resolved.
-    fn sanitize_length(&self, length: Length) -> Option<Length> {
+    fn sanitize_length(&self, length: Length, tag: Tag, position: u64) -> Option<Length> {
         if length.is_defined() && length.0 & 1 != 0 {
+            if let Some(diagnostics) = &self.diagnostics {
+                if self.options.odd_length != OddLengthStrategy::Fail {
+                    diagnostics.record(
+                        position,
+                        Some(tag),
+                        DiagnosticKind::OddLength { len: length.0 },
+                    );
+                }
+            }
             match self.options.odd_length {
                 OddLengthStrategy::Accept => Some(length),
                 OddLengthStrategy::NextEven => Some(length + 1),
@@ -1477,4 +1554,142 @@ mod tests {
             })),
         ), "got: {:?}", token);
     }
+
+    #[test]
+    fn read_with_diagnostics() {
+        use crate::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics};
+
+        #[rustfmt::skip]
+        static DATA: &[u8] = &[
+            0x08, 0x00, 0x05, 0x00, // (0008,0005) SpecificCharacterSet
+            b'C', b'S', // VR
+            0x0a, 0x00, // len = 10
+            b'I', b'S', b'O', b'_', b'I', b'R', b' ', b'1', b'9', b'2',
+            0xFE, 0xFF, 0x0D, 0xE0, // (FFFE,E00D) ItemDelimitationItem
+            0x00, 0x00, 0x00, 0x00, // len = 0
+            0x08, 0x00, 0x16, 0x00, // (0008,0016) SOPClassUID
+            b'U', b'I', // VR
+            0x03, 0x00, // len = 3
+            b'1', b'.', b'2',
+            0x10, 0x00, 0x10, 0x00, // (0010,0010) PatientName
+            b'X', b'X', // unrecognized VR
+            0x00, 0x00, // reserved
+            0x02, 0x00, 0x00, 0x00, // len = 2
+            b'J', b'o',
+            0x10, 0x00, 0x20, 0x00, // (0010,0020) PatientID
+            b'L', b'O', // VR
+            0x02, 0x00, // len = 2
+            0xC3, 0x28, // invalid UTF-8
+        ];
+
+        let parser = StatefulDecoder::new(
+            DATA,
+            ExplicitVRLittleEndianDecoder::default(),
+            LittleEndianBasicDecoder,
+            SpecificCharacterSet::default(),
+        );
+        let diagnostics = Diagnostics::new();
+        let dset_reader =
+            DataSetReader::new(parser, Default::default()).with_diagnostics(diagnostics.clone());
+
+        let tokens: Vec<_> = dset_reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(tokens.len(), 8);
+
+        assert_eq!(
+            diagnostics.take(),
+            vec![
+                Diagnostic {
+                    position: 18,
+                    tag: Some(Tag(0xFFFE, 0xE00D)),
+                    selector: None,
+                    kind: DiagnosticKind::DelimiterOutsideSequence,
+                },
+                Diagnostic {
+                    position: 26,
+                    tag: Some(Tag(0x0008, 0x0016)),
+                    selector: Some(Tag(0x0008, 0x0016).into()),
+                    kind: DiagnosticKind::OddLength { len: 3 },
+                },
+                Diagnostic {
+                    position: 37,
+                    tag: Some(Tag(0x0010, 0x0010)),
+                    selector: Some(Tag(0x0010, 0x0010).into()),
+                    kind: DiagnosticKind::UnknownVr { expected: VR::PN },
+                },
+                Diagnostic {
+                    position: 59,
+                    tag: Some(Tag(0x0010, 0x0020)),
+                    selector: Some(Tag(0x0010, 0x0020).into()),
+                    kind: DiagnosticKind::InvalidText {
+                        charset: "ISO_IR 192".to_string(),
+                    },
+                },
+            ]
+        );
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn read_with_diagnostics_in_sequence() {
+        use crate::diagnostics::Diagnostics;
+        use dicom_core::ops::AttributeSelector;
+
+        #[rustfmt::skip]
+        static DATA: &[u8] = &[
+            0x08, 0x00, 0x16, 0x00, // (0008,0016) SOPClassUID
+            b'U', b'I', // VR
+            0x03, 0x00, // len = 3
+            b'1', b'.', b'2',
+            0x08, 0x00, 0x15, 0x11, // (0008,1115) ReferencedSeriesSequence
+            b'S', b'Q', // VR
+            0x00, 0x00, // reserved
+            0xFF, 0xFF, 0xFF, 0xFF, // len = undefined
+            0xFE, 0xFF, 0x00, 0xE0, // item start
+            0xFF, 0xFF, 0xFF, 0xFF, // len = undefined
+            0xFE, 0xFF, 0x0D, 0xE0, // item end
+            0x00, 0x00, 0x00, 0x00,
+            0xFE, 0xFF, 0x00, 0xE0, // item start
+            0xFF, 0xFF, 0xFF, 0xFF, // len = undefined
+            0x08, 0x00, 0x16, 0x00, // (0008,0016) SOPClassUID
+            b'U', b'I', // VR
+            0x03, 0x00, // len = 3
+            b'1', b'.', b'2',
+            0xFE, 0xFF, 0x0D, 0xE0, // item end
+            0x00, 0x00, 0x00, 0x00,
+            0xFE, 0xFF, 0xDD, 0xE0, // sequence end
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let parser = StatefulDecoder::new(
+            DATA,
+            ExplicitVRLittleEndianDecoder::default(),
+            LittleEndianBasicDecoder,
+            SpecificCharacterSet::default(),
+        );
+        let diagnostics = Diagnostics::new();
+        let dset_reader =
+            DataSetReader::new(parser, Default::default()).with_diagnostics(diagnostics.clone());
+        let _: Vec<_> = dset_reader.collect::<Result<_, _>>().unwrap();
+
+        // same tag, but in different places of the data set
+        let selectors: Vec<_> = diagnostics
+            .take()
+            .into_iter()
+            .map(|d| (d.position, d.selector))
+            .collect();
+        assert_eq!(
+            selectors,
+            vec![
+                (0, Some(AttributeSelector::from(Tag(0x0008, 0x0016)))),
+                (
+                    47,
+                    Some(AttributeSelector::from((
+                        Tag(0x0008, 0x1115),
+                        1,
+                        Tag(0x0008, 0x0016)
+                    )))
+                ),
+            ]
+        );
+    }
 }
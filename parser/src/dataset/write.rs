@@ -14,6 +14,7 @@ use dicom_encoding::text::SpecificCharacterSet;
 use dicom_encoding::transfer_syntax::DynEncoder;
 use dicom_encoding::TransferSyntax;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use std::convert::TryFrom;
 use std::io::Write;
 
 #[derive(Debug, Snafu)]
@@ -67,6 +68,16 @@ pub enum Error {
         #[snafu(backtrace)]
         source: crate::stateful::encode::Error,
     },
+
+    #[snafu(display("Could not calculate the length of a sequence or item"))]
+    CalculateLength {
+        #[snafu(backtrace)]
+        source: crate::stateful::encode::Error,
+    },
+
+    /// A sequence or item is too large to be written with an explicit length
+    #[snafu(display("Sequence or item of {} bytes is too long for an explicit length", len))]
+    LengthOverflow { len: u64, backtrace: Backtrace },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -79,6 +90,63 @@ struct SeqToken {
     /// The length of the value, as indicated by the starting element,
     /// can be unknown.
     len: Length,
+    /// Whether it is the start of an encapsulated pixel data sequence.
+    encapsulated: bool,
+}
+
+/// The strategy for encoding the lengths of sequences and items.
+#[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SequenceLengthStrategy {
+    /// Keep the lengths declared by the incoming tokens,
+    /// whether they are defined or undefined.
+    #[default]
+    Preserve,
+    /// Write all sequences and items with an explicit length,
+    /// calculated from their contents.
+    ///
+    /// The tokens of each sequence are retained in memory
+    /// until the end of the sequence,
+    /// so that its length and the length of its items
+    /// are known before the sequence header is written.
+    Explicit,
+    /// Write all sequences and items with an undefined length,
+    /// followed by the respective delimiters.
+    ///
+    /// Items of encapsulated pixel data always keep their explicit length.
+    Undefined,
+}
+
+/// The set of options for the data set writer.
+#[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct DataSetWriterOptions {
+    /// Whether to reproduce the original encoding of the data set
+    /// as described by the incoming tokens.
+    ///
+    /// When enabled, primitive values declared with an odd length
+    /// are written without the padding byte,
+    /// as long as the encoded value still has that exact length.
+    /// This is only meant for writing data sets
+    /// which were read with encoding preservation,
+    /// since the output is otherwise not guaranteed to be standard compliant.
+    pub preserve_encoding: bool,
+    /// How to encode the lengths of sequences and items.
+    pub sequence_length: SequenceLengthStrategy,
+}
+
+impl DataSetWriterOptions {
+    /// Replace the encoding preservation option.
+    pub fn preserve_encoding(mut self, preserve_encoding: bool) -> Self {
+        self.preserve_encoding = preserve_encoding;
+        self
+    }
+
+    /// Replace the strategy for encoding sequence and item lengths.
+    pub fn sequence_length(mut self, sequence_length: SequenceLengthStrategy) -> Self {
+        self.sequence_length = sequence_length;
+        self
+    }
 }
 
 /// A stateful device for printing a DICOM data set in sequential order.
@@ -89,6 +157,11 @@ pub struct DataSetWriter<W, E, T = SpecificCharacterSet> {
     printer: StatefulEncoder<W, E, T>,
     seq_tokens: Vec<SeqToken>,
     last_de: Option<DataElementHeader>,
+    options: DataSetWriterOptions,
+    /// Tokens of a sequence retained until its length is known
+    pending: Vec<DataToken>,
+    /// The sequence nesting depth of the retained tokens
+    pending_depth: u32,
 }
 
 impl<'w, W: 'w> DataSetWriter<W, DynEncoder<'w, W>>
@@ -131,6 +204,9 @@ impl<W, E> DataSetWriter<W, E> {
             printer: StatefulEncoder::new(to, encoder, SpecificCharacterSet::default()),
             seq_tokens: Vec::new(),
             last_de: None,
+            options: Default::default(),
+            pending: Vec::new(),
+            pending_depth: 0,
         }
     }
 }
@@ -141,8 +217,17 @@ impl<W, E, T> DataSetWriter<W, E, T> {
             printer: StatefulEncoder::new(to, encoder, text),
             seq_tokens: Vec::new(),
             last_de: None,
+            options: Default::default(),
+            pending: Vec::new(),
+            pending_depth: 0,
         }
     }
+
+    /// Replace the options of this data set writer.
+    pub fn with_options(mut self, options: DataSetWriterOptions) -> Self {
+        self.options = options;
+        self
+    }
 }
 
 impl<W, E> DataSetWriter<W, E>
@@ -165,6 +250,135 @@ where
 
     /// Feed the given data set token for writing the data set.
     pub fn write(&mut self, token: DataToken) -> Result<()> {
+        match self.options.sequence_length {
+            SequenceLengthStrategy::Preserve => self.write_token(token),
+            SequenceLengthStrategy::Explicit => self.write_explicit(token),
+            SequenceLengthStrategy::Undefined => {
+                let token = match token {
+                    DataToken::SequenceStart { tag, .. } => DataToken::SequenceStart {
+                        tag,
+                        len: Length::UNDEFINED,
+                    },
+                    DataToken::ItemStart { .. } if !self.in_pixel_sequence() => {
+                        DataToken::ItemStart {
+                            len: Length::UNDEFINED,
+                        }
+                    }
+                    token => token,
+                };
+                self.write_token(token)
+            }
+        }
+    }
+
+    /// Whether the writer is currently
+    /// inside an encapsulated pixel data sequence.
+    fn in_pixel_sequence(&self) -> bool {
+        self.seq_tokens
+            .last()
+            .map(|token| token.encapsulated)
+            .unwrap_or(false)
+    }
+
+    /// Feed a token while writing all sequences with explicit lengths,
+    /// retaining the tokens of each top level sequence
+    /// until the whole sequence is available.
+    fn write_explicit(&mut self, token: DataToken) -> Result<()> {
+        let retaining = self.pending_depth > 0;
+        match token {
+            DataToken::SequenceStart { .. } => self.pending_depth += 1,
+            DataToken::PixelSequenceStart if retaining => self.pending_depth += 1,
+            DataToken::SequenceEnd if retaining => self.pending_depth -= 1,
+            _ if !retaining => return self.write_token(token),
+            _ => {}
+        }
+
+        self.pending.push(token);
+        if self.pending_depth == 0 {
+            let mut tokens = std::mem::take(&mut self.pending);
+            self.resolve_lengths(&mut tokens)?;
+            for token in tokens {
+                self.write_token(token)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the lengths of all sequences and items in the given tokens
+    /// with the byte length of their encoded contents.
+    fn resolve_lengths(&mut self, tokens: &mut [DataToken]) -> Result<()> {
+        // measuring text values may change the character set,
+        // which has to be restored before writing
+        let charset = self.printer.text_codec().clone();
+
+        // the position of each open sequence or item start token
+        // and the byte length of its contents so far
+        let mut open: Vec<(usize, u64)> = Vec::new();
+        let mut last_de = None;
+        for i in 0..tokens.len() {
+            let len = match &tokens[i] {
+                DataToken::SequenceStart { tag, .. } => {
+                    let header = DataElementHeader::new(*tag, VR::SQ, Length::UNDEFINED);
+                    add_len(&mut open, self.printer.element_header_len(header) as u64);
+                    open.push((i, 0));
+                    continue;
+                }
+                DataToken::PixelSequenceStart => {
+                    let header =
+                        DataElementHeader::new(Tag(0x7fe0, 0x0010), VR::OB, Length::UNDEFINED);
+                    add_len(&mut open, self.printer.element_header_len(header) as u64);
+                    open.push((i, 0));
+                    continue;
+                }
+                DataToken::ItemStart { .. } => {
+                    add_len(&mut open, 8);
+                    open.push((i, 0));
+                    continue;
+                }
+                DataToken::ItemEnd | DataToken::SequenceEnd => {
+                    let (start, len) = open.pop().context(UnexpectedTokenSnafu {
+                        token: tokens[i].clone(),
+                    })?;
+                    let explicit_len = u32::try_from(len)
+                        .ok()
+                        .filter(|len| *len != Length::UNDEFINED.0)
+                        .context(LengthOverflowSnafu { len })?;
+                    match &mut tokens[start] {
+                        DataToken::SequenceStart { len, .. } | DataToken::ItemStart { len } => {
+                            *len = Length(explicit_len);
+                            len.0 as u64
+                        }
+                        // encapsulated pixel data is always delimited
+                        _ => len + 8,
+                    }
+                }
+                DataToken::ElementHeader(de) => {
+                    last_de = Some(*de);
+                    continue;
+                }
+                DataToken::PrimitiveValue(value) => {
+                    let de = last_de.take().with_context(|| UnexpectedTokenSnafu {
+                        token: tokens[i].clone(),
+                    })?;
+                    let value_len = self
+                        .printer
+                        .measure_primitive_element(&de, value, self.options.preserve_encoding)
+                        .context(CalculateLengthSnafu)?;
+                    let header = DataElementHeader::new(de.tag, de.vr, Length(value_len));
+                    self.printer.element_header_len(header) as u64 + value_len as u64
+                }
+                DataToken::OffsetTable(table) => table.len() as u64 * 4,
+                DataToken::ItemValue(data) => (data.len() as u64 + 1) & !1,
+            };
+            add_len(&mut open, len);
+        }
+
+        self.printer.set_text_codec(charset);
+        Ok(())
+    }
+
+    /// Write a single token, respecting the lengths which it declares.
+    fn write_token(&mut self, token: DataToken) -> Result<()> {
         // adjust the logic of sequence printing:
         // explicit length sequences or items should not print
         // the respective delimiter
@@ -174,6 +388,7 @@ where
                 self.seq_tokens.push(SeqToken {
                     typ: SeqTokenType::Sequence,
                     len,
+                    encapsulated: false,
                 });
                 self.write_impl(&token)?;
                 Ok(())
@@ -182,6 +397,7 @@ where
                 self.seq_tokens.push(SeqToken {
                     typ: SeqTokenType::Item,
                     len,
+                    encapsulated: false,
                 });
                 self.write_impl(&token)?;
                 Ok(())
@@ -215,6 +431,7 @@ where
                 self.seq_tokens.push(SeqToken {
                     typ: SeqTokenType::Sequence,
                     len: Length::UNDEFINED,
+                    encapsulated: true,
                 });
                 self.write_impl(&token)
             }
@@ -262,9 +479,15 @@ where
                     token: token.clone(),
                 })?;
 
-                self.printer
-                    .encode_primitive_element(&last_de, value)
-                    .context(WriteValueSnafu)?;
+                if self.options.preserve_encoding {
+                    self.printer
+                        .encode_primitive_element_preserved(&last_de, value)
+                        .context(WriteValueSnafu)?;
+                } else {
+                    self.printer
+                        .encode_primitive_element(&last_de, value)
+                        .context(WriteValueSnafu)?;
+                }
                 self.last_de = None;
             }
             DataToken::OffsetTable(table) => {
@@ -280,16 +503,27 @@ where
     }
 }
 
+/// Add the given byte length to the contents of the innermost open
+/// sequence or item, if any.
+fn add_len(open: &mut [(usize, u64)], len: u64) {
+    if let Some((_, total)) = open.last_mut() {
+        *total += len;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::DataToken;
-    use super::DataSetWriter;
+    use super::{DataSetWriter, DataSetWriterOptions, SequenceLengthStrategy};
     use dicom_core::{
         header::{DataElementHeader, Length},
         value::PrimitiveValue,
         Tag, VR,
     };
-    use dicom_encoding::encode::{explicit_le::ExplicitVRLittleEndianEncoder, EncoderFor};
+    use dicom_encoding::encode::{
+        explicit_le::ExplicitVRLittleEndianEncoder, implicit_le::ImplicitVRLittleEndianEncoder,
+        EncoderFor,
+    };
 
     fn validate_dataset_writer<I>(tokens: I, ground_truth: &[u8])
     where
@@ -406,6 +640,63 @@ mod tests {
         validate_dataset_writer(tokens, GROUND_TRUTH);
     }
 
+    #[test]
+    fn write_preserved_odd_length() {
+        let tokens = vec![
+            DataToken::ElementHeader(DataElementHeader {
+                // Patient ID (0010,0020)
+                tag: Tag(0x0010, 0x0020),
+                vr: VR::LO,
+                len: Length(3),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::from("ABC")),
+            DataToken::ElementHeader(DataElementHeader {
+                // Other Patient IDs (0010,1000)
+                tag: Tag(0x0010, 0x1000),
+                vr: VR::LO,
+                // length no longer matches the value
+                len: Length(5),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::from("DEF")),
+            DataToken::ElementHeader(DataElementHeader {
+                // Private data element
+                tag: Tag(0x0009, 0x1010),
+                vr: VR::OB,
+                len: Length(3),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::from(vec![1_u8, 2, 3])),
+        ];
+
+        #[rustfmt::skip]
+        static GROUND_TRUTH: &[u8] = &[
+            // Patient ID (0010,0020)
+            0x10, 0x00, 0x20, 0x00, b'L', b'O', 0x03, 0x00, // len = 3
+            b'A', b'B', b'C', // value = "ABC", no padding
+            // Other Patient IDs (0010,1000)
+            0x10, 0x00, 0x00, 0x10, b'L', b'O', 0x04, 0x00, // len = 4
+            b'D', b'E', b'F', b' ', // value = "DEF "
+            // Private data element (0009,1010)
+            0x09, 0x00, 0x10, 0x10, b'O', b'B', 0x00, 0x00, // reserved
+            0x03, 0x00, 0x00, 0x00, // len = 3
+            0x01, 0x02, 0x03, // no padding
+        ];
+
+        let mut raw_out: Vec<u8> = vec![];
+        let encoder = EncoderFor::new(ExplicitVRLittleEndianEncoder::default());
+        let mut dset_writer = DataSetWriter::new(&mut raw_out, encoder)
+            .with_options(DataSetWriterOptions::default().preserve_encoding(true));
+        dset_writer.write_sequence(tokens.clone()).unwrap();
+        assert_eq!(raw_out, GROUND_TRUTH);
+
+        // values are padded by default
+        let mut raw_out: Vec<u8> = vec![];
+        let encoder = EncoderFor::new(ExplicitVRLittleEndianEncoder::default());
+        let mut dset_writer = DataSetWriter::new(&mut raw_out, encoder);
+        dset_writer.write_sequence(tokens).unwrap();
+        assert_eq!(raw_out.len(), GROUND_TRUTH.len() + 2);
+        assert_eq!(&raw_out[6..12], &[0x04, 0x00, b'A', b'B', b'C', b' ']);
+    }
+
     #[test]
     fn write_sequence_implicit() {
         let tokens = vec![
@@ -600,4 +891,173 @@ mod tests {
 
         validate_dataset_writer(tokens, GROUND_TRUTH);
     }
+
+    fn write_with_options(tokens: Vec<DataToken>, options: DataSetWriterOptions) -> Vec<u8> {
+        let mut raw_out: Vec<u8> = vec![];
+        let encoder = EncoderFor::new(ExplicitVRLittleEndianEncoder::default());
+        let mut dset_writer = DataSetWriter::new(&mut raw_out, encoder).with_options(options);
+        dset_writer.write_sequence(tokens).unwrap();
+        raw_out
+    }
+
+    /// Tokens of a data set with nested sequences,
+    /// with the given sequence and item lengths in order of appearance.
+    fn nested_sequence_tokens(lengths: [Length; 4]) -> Vec<DataToken> {
+        vec![
+            DataToken::SequenceStart {
+                tag: Tag(0x0008, 0x1115),
+                len: lengths[0],
+            },
+            DataToken::ItemStart { len: lengths[1] },
+            DataToken::ElementHeader(DataElementHeader {
+                tag: Tag(0x0020, 0x000E),
+                vr: VR::UI,
+                len: Length(5),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::from("1.2.3")),
+            DataToken::SequenceStart {
+                tag: Tag(0x0008, 0x114A),
+                len: lengths[2],
+            },
+            DataToken::ItemStart { len: lengths[3] },
+            DataToken::ElementHeader(DataElementHeader {
+                tag: Tag(0x0008, 0x1155),
+                vr: VR::UI,
+                len: Length(3),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::from("1.2")),
+            DataToken::ItemEnd,
+            DataToken::SequenceEnd,
+            DataToken::ItemEnd,
+            DataToken::SequenceEnd,
+            DataToken::ElementHeader(DataElementHeader {
+                tag: Tag(0x0020, 0x4000),
+                vr: VR::LT,
+                len: Length(4),
+            }),
+            DataToken::PrimitiveValue(PrimitiveValue::from("TEST")),
+        ]
+    }
+
+    #[test]
+    fn write_sequences_with_explicit_length() {
+        let tokens = nested_sequence_tokens([Length::UNDEFINED; 4]);
+
+        #[rustfmt::skip]
+        static GROUND_TRUTH: &[u8] = &[
+            // (0008,1115) ReferencedSeriesSequence
+            0x08, 0x00, 0x15, 0x11, b'S', b'Q', 0x00, 0x00,
+            0x36, 0x00, 0x00, 0x00, // length: 54
+            0xfe, 0xff, 0x00, 0xe0, // item start tag
+            0x2e, 0x00, 0x00, 0x00, // item length: 46
+            // (0020,000E) SeriesInstanceUID, len = 6
+            0x20, 0x00, 0x0e, 0x00, b'U', b'I', 0x06, 0x00,
+            b'1', b'.', b'2', b'.', b'3', 0x00,
+            // (0008,114A) ReferencedInstanceSequence
+            0x08, 0x00, 0x4a, 0x11, b'S', b'Q', 0x00, 0x00,
+            0x14, 0x00, 0x00, 0x00, // length: 20
+            0xfe, 0xff, 0x00, 0xe0, // item start tag
+            0x0c, 0x00, 0x00, 0x00, // item length: 12
+            // (0008,1155) ReferencedSOPInstanceUID, len = 4
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x04, 0x00,
+            b'1', b'.', b'2', 0x00,
+            // (0020,4000) ImageComments, len = 4
+            0x20, 0x00, 0x00, 0x40, b'L', b'T', 0x04, 0x00,
+            b'T', b'E', b'S', b'T',
+        ];
+
+        let options =
+            DataSetWriterOptions::default().sequence_length(SequenceLengthStrategy::Explicit);
+        assert_eq!(write_with_options(tokens, options), GROUND_TRUTH);
+
+        // wrong explicit lengths are also replaced
+        let tokens = nested_sequence_tokens([Length(2), Length(4), Length(6), Length(8)]);
+        assert_eq!(write_with_options(tokens, options), GROUND_TRUTH);
+
+        // lengths depend on the transfer syntax
+        let tokens = nested_sequence_tokens([Length::UNDEFINED; 4]);
+        let mut raw_out: Vec<u8> = vec![];
+        let encoder = EncoderFor::new(ImplicitVRLittleEndianEncoder::default());
+        let mut dset_writer = DataSetWriter::new(&mut raw_out, encoder).with_options(options);
+        dset_writer.write_sequence(tokens).unwrap();
+        assert_eq!(raw_out.len(), GROUND_TRUTH.len() - 2 * 4);
+        // ReferencedSeriesSequence, length: 50
+        assert_eq!(
+            &raw_out[0..8],
+            &[0x08, 0x00, 0x15, 0x11, 0x32, 0x00, 0x00, 0x00]
+        );
+        // item length: 42
+        assert_eq!(
+            &raw_out[8..16],
+            &[0xfe, 0xff, 0x00, 0xe0, 0x2a, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn write_sequences_with_undefined_length() {
+        let tokens = nested_sequence_tokens([Length(54), Length(46), Length(20), Length(12)]);
+
+        #[rustfmt::skip]
+        static GROUND_TRUTH: &[u8] = &[
+            // (0008,1115) ReferencedSeriesSequence
+            0x08, 0x00, 0x15, 0x11, b'S', b'Q', 0x00, 0x00,
+            0xff, 0xff, 0xff, 0xff, // length: undefined
+            0xfe, 0xff, 0x00, 0xe0, // item start tag
+            0xff, 0xff, 0xff, 0xff, // item length: undefined
+            // (0020,000E) SeriesInstanceUID, len = 6
+            0x20, 0x00, 0x0e, 0x00, b'U', b'I', 0x06, 0x00,
+            b'1', b'.', b'2', b'.', b'3', 0x00,
+            // (0008,114A) ReferencedInstanceSequence
+            0x08, 0x00, 0x4a, 0x11, b'S', b'Q', 0x00, 0x00,
+            0xff, 0xff, 0xff, 0xff, // length: undefined
+            0xfe, 0xff, 0x00, 0xe0, // item start tag
+            0xff, 0xff, 0xff, 0xff, // item length: undefined
+            // (0008,1155) ReferencedSOPInstanceUID, len = 4
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x04, 0x00,
+            b'1', b'.', b'2', 0x00,
+            0xfe, 0xff, 0x0d, 0xe0, 0x00, 0x00, 0x00, 0x00, // item end
+            0xfe, 0xff, 0xdd, 0xe0, 0x00, 0x00, 0x00, 0x00, // sequence end
+            0xfe, 0xff, 0x0d, 0xe0, 0x00, 0x00, 0x00, 0x00, // item end
+            0xfe, 0xff, 0xdd, 0xe0, 0x00, 0x00, 0x00, 0x00, // sequence end
+            // (0020,4000) ImageComments, len = 4
+            0x20, 0x00, 0x00, 0x40, b'L', b'T', 0x04, 0x00,
+            b'T', b'E', b'S', b'T',
+        ];
+
+        let options =
+            DataSetWriterOptions::default().sequence_length(SequenceLengthStrategy::Undefined);
+        assert_eq!(write_with_options(tokens, options), GROUND_TRUTH);
+    }
+
+    #[test]
+    fn write_encapsulated_pixeldata_with_length_strategies() {
+        let tokens = vec![
+            DataToken::PixelSequenceStart,
+            DataToken::ItemStart { len: Length(0) },
+            DataToken::ItemEnd,
+            DataToken::ItemStart { len: Length(4) },
+            DataToken::ItemValue(vec![0x99; 4]),
+            DataToken::ItemEnd,
+            DataToken::SequenceEnd,
+        ];
+
+        #[rustfmt::skip]
+        static GROUND_TRUTH: &[u8] = &[
+            0xe0, 0x7f, 0x10, 0x00, b'O', b'B', 0x00, 0x00, // (7FE0, 0010) PixelData
+            0xff, 0xff, 0xff, 0xff, // length: undefined
+            0xfe, 0xff, 0x00, 0xe0, 0x00, 0x00, 0x00, 0x00, // empty offset table
+            0xfe, 0xff, 0x00, 0xe0, 0x04, 0x00, 0x00, 0x00, // fragment, len = 4
+            0x99, 0x99, 0x99, 0x99,
+            0xfe, 0xff, 0xdd, 0xe0, 0x00, 0x00, 0x00, 0x00, // sequence end
+        ];
+
+        // fragments always keep their explicit length
+        for strategy in [
+            SequenceLengthStrategy::Explicit,
+            SequenceLengthStrategy::Undefined,
+        ] {
+            let options = DataSetWriterOptions::default().sequence_length(strategy);
+            assert_eq!(write_with_options(tokens.clone(), options), GROUND_TRUTH);
+        }
+    }
 }
@@ -0,0 +1,209 @@
+//! Collection of non-fatal parsing anomalies.
+//!
+//! Parsers in this crate tolerate a few deviations from the standard,
+//! such as data elements with an odd length
+//! or text values with characters outside of the declared character set.
+//! When a [`Diagnostics`] collector is attached
+//! to a [`StatefulDecoder`](crate::StatefulDecoder)
+//! or to a [`DataSetReader`](crate::DataSetReader),
+//! each of these anomalies is recorded as a [`Diagnostic`]
+//! instead of disappearing silently.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_parser::{DataSetReader, StatefulDecoder};
+//! # use dicom_parser::diagnostics::{Diagnostics, DiagnosticKind};
+//! # use dicom_encoding::decode::basic::LittleEndianBasicDecoder;
+//! # use dicom_encoding::decode::explicit_le::ExplicitVRLittleEndianDecoder;
+//! # use dicom_encoding::text::SpecificCharacterSet;
+//! // (0010,0010) PatientName, PN, length 3
+//! let data: &[u8] = &[0x10, 0x00, 0x10, 0x00, b'P', b'N', 0x03, 0x00, b'D', b'o', b'e'];
+//! let decoder = StatefulDecoder::new(
+//!     data,
+//!     ExplicitVRLittleEndianDecoder::default(),
+//!     LittleEndianBasicDecoder,
+//!     SpecificCharacterSet::default(),
+//! );
+//!
+//! let diagnostics = Diagnostics::new();
+//! let reader = DataSetReader::new(decoder, Default::default())
+//!     .with_diagnostics(diagnostics.clone());
+//! let tokens = reader.collect::<Result<Vec<_>, _>>()?;
+//! assert_eq!(tokens.len(), 2);
+//!
+//! let found = diagnostics.take();
+//! assert_eq!(found.len(), 1);
+//! assert_eq!(found[0].position, 0);
+//! assert_eq!(found[0].kind, DiagnosticKind::OddLength { len: 3 });
+//! # Result::<(), Box<dyn std::error::Error>>::Ok(())
+//! ```
+use dicom_core::ops::{AttributeSelector, AttributeSelectorStep};
+use dicom_core::{Tag, VR};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// A non-fatal anomaly found while parsing a DICOM data set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The position of the offending data element or item header
+    /// relative to the start of the data source, in bytes.
+    pub position: u64,
+    /// The tag of the offending data element, if applicable.
+    pub tag: Option<Tag>,
+    /// The full path to the offending data element
+    /// from the root of the data set, if known.
+    ///
+    /// This is only resolved by a [`DataSetReader`](crate::DataSetReader),
+    /// which keeps track of the sequences and items being read.
+    pub selector: Option<AttributeSelector>,
+    /// The kind of anomaly found.
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.tag {
+            Some(tag) => write!(f, "{} at {:#x}: {}", tag, self.position, self.kind),
+            None => write!(f, "at {:#x}: {}", self.position, self.kind),
+        }
+    }
+}
+
+/// The kind of a parsing anomaly.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A data element or item has an odd value length,
+    /// which was accepted or adjusted
+    /// in accordance to the [odd length strategy](crate::dataset::read::OddLengthStrategy).
+    OddLength {
+        /// the length declared in the header
+        len: u32,
+    },
+    /// The data element was read with the _UN_ value representation,
+    /// even though it is known to have another one.
+    /// This usually means that the encoded VR was not recognized.
+    UnknownVr {
+        /// the value representation expected by the standard dictionary
+        expected: VR,
+    },
+    /// A text value contains bytes which are not valid
+    /// in the current character set.
+    /// They were replaced by escape sequences.
+    InvalidText {
+        /// the name of the character set used to decode the text
+        charset: String,
+    },
+    /// The specific character set declared in the data set
+    /// is not supported and was ignored.
+    UnsupportedCharacterSet {
+        /// the declared character set term
+        name: String,
+    },
+    /// An item delimitation item was found outside of a sequence
+    /// and was ignored.
+    DelimiterOutsideSequence,
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::OddLength { len } => write!(f, "odd value length {}", len),
+            DiagnosticKind::UnknownVr { expected } => {
+                write!(f, "value representation read as UN instead of {}", expected)
+            }
+            DiagnosticKind::InvalidText { charset } => {
+                write!(f, "text contains characters not valid in {}", charset)
+            }
+            DiagnosticKind::UnsupportedCharacterSet { name } => {
+                write!(f, "unsupported character set `{}` ignored", name)
+            }
+            DiagnosticKind::DelimiterOutsideSequence => {
+                f.write_str("item delimiter outside of a sequence ignored")
+            }
+        }
+    }
+}
+
+/// A shared collector of parsing diagnostics.
+///
+/// Cloning a collector yields another handle to the same list,
+/// so that the diagnostics recorded by a parser
+/// can be retrieved after the parser is consumed.
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostics {
+    inner: Arc<Mutex<Vec<Diagnostic>>>,
+}
+
+impl Diagnostics {
+    /// Create a new empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a new diagnostic.
+    pub fn push(&self, diagnostic: Diagnostic) {
+        self.lock().push(diagnostic);
+    }
+
+    /// Record a new diagnostic from its parts.
+    pub(crate) fn record(&self, position: u64, tag: Option<Tag>, kind: DiagnosticKind) {
+        self.push(Diagnostic {
+            position,
+            tag,
+            selector: None,
+            kind,
+        });
+    }
+
+    /// Resolve the attribute selector of the diagnostics
+    /// recorded from the given index onwards,
+    /// found inside of the given sequence items.
+    ///
+    /// Each item in `path` is a sequence tag and an item index.
+    pub(crate) fn resolve_selectors(&self, since: usize, path: &[(Tag, u32)]) {
+        let mut diagnostics = self.lock();
+        for diagnostic in diagnostics.iter_mut().skip(since) {
+            if diagnostic.selector.is_some() {
+                continue;
+            }
+            diagnostic.selector =
+                diagnostic
+                    .tag
+                    .filter(|tag| tag.group() != 0xFFFE)
+                    .and_then(|tag| {
+                        AttributeSelector::new(
+                            path.iter()
+                                .map(|&step| AttributeSelectorStep::from(step))
+                                .chain(std::iter::once(AttributeSelectorStep::Tag(tag))),
+                        )
+                    });
+        }
+    }
+
+    /// Obtain the number of diagnostics recorded so far.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check whether no diagnostics were recorded so far.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Obtain a copy of all diagnostics recorded so far.
+    pub fn to_vec(&self) -> Vec<Diagnostic> {
+        self.lock().clone()
+    }
+
+    /// Remove and return all diagnostics recorded so far.
+    pub fn take(&self) -> Vec<Diagnostic> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Diagnostic>> {
+        // a panic while holding the lock cannot leave the list inconsistent
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
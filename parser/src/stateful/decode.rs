@@ -1,13 +1,14 @@
 //! Module holding a stateful DICOM data decoding abstraction,
 //! which also supports text decoding.
 
+use crate::diagnostics::{DiagnosticKind, Diagnostics};
 use crate::util::n_times;
 use dicom_core::dictionary::VirtualVr;
 use dicom_core::header::{DataElementHeader, HasLength, Length, SequenceItemHeader, Tag, VR};
 use dicom_core::value::deserialize::{
     parse_date_partial, parse_datetime_partial, parse_time_partial,
 };
-use dicom_core::value::PrimitiveValue;
+use dicom_core::value::{PrimitiveValue, C};
 use dicom_dictionary_std::StandardDataDictionary;
 use dicom_encoding::decode::basic::{BasicDecoder, LittleEndianBasicDecoder};
 use dicom_encoding::decode::explicit_le::ExplicitVRLittleEndianDecoder;
@@ -206,6 +207,13 @@ pub trait StatefulDecode {
     /// If the stateful decoder was constructed at the beginning of the reader,
     /// this equals to the number of bytes read so far.
     fn position(&self) -> u64;
+
+    /// Attach a collector of non-fatal anomalies found while decoding.
+    ///
+    /// The default implementation ignores the collector.
+    fn set_diagnostics(&mut self, diagnostics: Diagnostics) {
+        let _ = diagnostics;
+    }
 }
 
 /// Alias for a dynamically resolved DICOM stateful decoder. Although the data
@@ -234,6 +242,8 @@ pub struct StatefulDecoder<D, S, BD = BasicDecoder, TC = SpecificCharacterSet> {
     /// the assumed position of the reader source
     position: u64,
     signed_pixeldata: Option<bool>,
+    /// the collector of non-fatal anomalies, if any
+    diagnostics: Option<Diagnostics>,
 }
 
 impl<S> StatefulDecoder<DynDecoder<S>, S> {
@@ -294,6 +304,7 @@ where
             buffer: Vec::with_capacity(PARSER_BUFFER_CAPACITY),
             position: 0,
             signed_pixeldata: None,
+            diagnostics: None,
         }
     }
 }
@@ -325,8 +336,15 @@ where
             buffer: Vec::with_capacity(PARSER_BUFFER_CAPACITY),
             position,
             signed_pixeldata: None,
+            diagnostics: None,
         }
     }
+
+    /// Attach a collector of non-fatal anomalies found while decoding.
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
 }
 
 impl<D, S, BD, TC> StatefulDecoder<D, S, BD, TC>
@@ -412,7 +430,7 @@ where
                 position: self.position,
             })?;
 
-        let parts: Result<_> = match header.vr() {
+        let parts: Result<C<String>> = match header.vr() {
             VR::AE | VR::CS | VR::AS => self
                 .buffer
                 .split(|v| *v == b'\\')
@@ -434,9 +452,28 @@ where
                 })
                 .collect(),
         };
+        let parts = parts?;
+
+        if let Some(diagnostics) = &self.diagnostics {
+            // values were split at backslashes,
+            // so any backslash left is a replacement escape sequence
+            if parts.iter().any(|part| part.contains('\\')) {
+                let charset = match header.vr() {
+                    VR::AE | VR::CS | VR::AS => DefaultCharacterSetCodec.name(),
+                    _ => self.text.name(),
+                };
+                diagnostics.record(
+                    self.position,
+                    Some(header.tag),
+                    DiagnosticKind::InvalidText {
+                        charset: charset.to_string(),
+                    },
+                );
+            }
+        }
 
         self.position += len as u64;
-        Ok(PrimitiveValue::Strs(parts?))
+        Ok(PrimitiveValue::Strs(parts))
     }
 
     fn read_value_str(&mut self, header: &DataElementHeader) -> Result<PrimitiveValue> {
@@ -449,14 +486,30 @@ where
             .context(ReadValueDataSnafu {
                 position: self.position,
             })?;
+        let position = self.position;
         self.position += len as u64;
-        Ok(PrimitiveValue::Str(
-            self.text
-                .decode(&self.buffer[..])
-                .context(DecodeTextSnafu {
-                    position: self.position,
-                })?,
-        ))
+        let text = self
+            .text
+            .decode(&self.buffer[..])
+            .context(DecodeTextSnafu {
+                position: self.position,
+            })?;
+
+        if let Some(diagnostics) = &self.diagnostics {
+            // replacement escape sequences start with a backslash
+            let backslashes = self.buffer.iter().filter(|b| **b == b'\\').count();
+            if text.matches('\\').count() > backslashes {
+                diagnostics.record(
+                    position,
+                    Some(header.tag),
+                    DiagnosticKind::InvalidText {
+                        charset: self.text.name().to_string(),
+                    },
+                );
+            }
+        }
+
+        Ok(PrimitiveValue::Str(text))
     }
 
     fn read_value_ss(&mut self, header: &DataElementHeader) -> Result<PrimitiveValue> {
@@ -808,6 +861,15 @@ where
             if let Some(charset) = parts.first().map(|x| x.as_ref()).and_then(|name| {
                 SpecificCharacterSet::from_code(name).or_else(|| {
                     tracing::warn!("Unsupported character set `{}`, ignoring", name);
+                    if let Some(diagnostics) = &self.diagnostics {
+                        diagnostics.record(
+                            self.position - header.len.0 as u64,
+                            Some(header.tag),
+                            DiagnosticKind::UnsupportedCharacterSet {
+                                name: name.to_string(),
+                            },
+                        );
+                    }
                     None
                 })
             }) {
@@ -869,6 +931,10 @@ where
         (**self).position()
     }
 
+    fn set_diagnostics(&mut self, diagnostics: Diagnostics) {
+        (**self).set_diagnostics(diagnostics)
+    }
+
     fn seek(&mut self, position: u64) -> Result<()>
     where
         Self::Reader: Seek,
@@ -886,6 +952,7 @@ where
     type Reader = S;
 
     fn decode_header(&mut self) -> Result<DataElementHeader> {
+        let position = self.position;
         let mut header = self
             .decoder
             .decode_header(&mut self.from)
@@ -903,6 +970,24 @@ where
             header.vr = vr;
         }
 
+        if header.vr == VR::UN && header.tag.group() != 0xFFFE {
+            if let Some(diagnostics) = &self.diagnostics {
+                use dicom_core::dictionary::DataDictionary;
+
+                if let Some(VirtualVr::Exact(expected)) =
+                    StandardDataDictionary.by_tag(header.tag).map(|e| e.vr)
+                {
+                    if expected != VR::UN {
+                        diagnostics.record(
+                            position,
+                            Some(header.tag),
+                            DiagnosticKind::UnknownVr { expected },
+                        );
+                    }
+                }
+            }
+        }
+
         Ok(header)
     }
 
@@ -1016,6 +1101,10 @@ where
         self.position
     }
 
+    fn set_diagnostics(&mut self, diagnostics: Diagnostics) {
+        self.diagnostics = Some(diagnostics);
+    }
+
     fn read_to_vec(&mut self, length: u32, vec: &mut Vec<u8>) -> Result<()> {
         self.read_to(length, vec)
     }
@@ -101,6 +101,12 @@ where
         if let Some(len) = de.len.get() {
             de.len = Length(even_len(len))
         }
+        self.write_element_header(de)
+    }
+
+    /// Encode and write a data element header as is,
+    /// without adjusting its length.
+    fn write_element_header(&mut self, de: DataElementHeader) -> Result<()> {
         let bytes = self
             .encoder
             .encode_element_header(&mut self.to, de)
@@ -214,15 +220,41 @@ where
         &mut self,
         de: &DataElementHeader,
         value: &PrimitiveValue,
+    ) -> Result<()> {
+        self.encode_primitive_element_impl(de, value, false)
+    }
+
+    /// Encode and write a data element with a primitive value,
+    /// keeping an odd value length declared in the header.
+    ///
+    /// This works like [`encode_primitive_element`](Self::encode_primitive_element),
+    /// except that no padding is added
+    /// when the header declares an odd length
+    /// which matches the byte length of the encoded value.
+    /// This is used to reproduce data sets
+    /// which were read with their original encoding preserved.
+    pub fn encode_primitive_element_preserved(
+        &mut self,
+        de: &DataElementHeader,
+        value: &PrimitiveValue,
+    ) -> Result<()> {
+        self.encode_primitive_element_impl(de, value, true)
+    }
+
+    fn encode_primitive_element_impl(
+        &mut self,
+        de: &DataElementHeader,
+        value: &PrimitiveValue,
+        keep_odd_length: bool,
     ) -> Result<()> {
         // intercept string encoding calls to use the text codec
         match value {
             PrimitiveValue::Str(text) => {
-                self.encode_text_element(text, *de)?;
+                self.encode_text_element(text, *de, keep_odd_length)?;
                 Ok(())
             }
             PrimitiveValue::Strs(texts) => {
-                self.encode_texts_element(&texts[..], *de)?;
+                self.encode_texts_element(&texts[..], *de, keep_odd_length)?;
                 Ok(())
             }
             _ => {
@@ -233,11 +265,17 @@ where
                 }
 
                 let byte_len = value.calculate_byte_len();
-                self.encode_element_header(DataElementHeader {
+                let header = DataElementHeader {
                     tag: de.tag,
                     vr: de.vr,
                     len: Length(byte_len as u32),
-                })?;
+                };
+                let unpadded = keep_odd_length && is_kept_odd_length(de, byte_len);
+                if unpadded {
+                    self.write_element_header(header)?;
+                } else {
+                    self.encode_element_header(header)?;
+                }
 
                 let bytes = self.encoder.encode_primitive(&mut self.to, value).context(
                     EncodeDataSnafu {
@@ -246,7 +284,7 @@ where
                 )?;
 
                 self.bytes_written += bytes as u64;
-                if bytes % 2 != 0 {
+                if bytes % 2 != 0 && !unpadded {
                     let padding = match de.vr {
                         VR::DA | VR::DT | VR::TM => b' ',
                         _ => 0,
@@ -262,6 +300,72 @@ where
         }
     }
 
+    /// Calculate the length of the value of a data element with a primitive value
+    /// as it would be written by
+    /// [`encode_primitive_element`](Self::encode_primitive_element)
+    /// (or by [`encode_primitive_element_preserved`](Self::encode_primitive_element_preserved)
+    /// if `keep_odd_length` is `true`),
+    /// including padding, without writing anything.
+    ///
+    /// Like the encoding methods,
+    /// measuring the value of a _Specific Character Set_ element
+    /// updates the inner text codec.
+    pub(crate) fn measure_primitive_element(
+        &mut self,
+        de: &DataElementHeader,
+        value: &PrimitiveValue,
+        keep_odd_length: bool,
+    ) -> Result<u32> {
+        let len = match value {
+            PrimitiveValue::Str(text) => {
+                let len = self.convert_text_untrailed(text, de.vr)?.len();
+                if de.tag == Tag(0x0008, 0x0005) {
+                    self.try_new_codec(text);
+                }
+                len
+            }
+            PrimitiveValue::Strs(texts) => {
+                let mut len = texts.len().saturating_sub(1);
+                for t in texts.iter() {
+                    len += self.convert_text_untrailed(t, de.vr)?.len();
+                }
+                if de.tag == Tag(0x0008, 0x0005) {
+                    if let Some(charset_name) = texts.first() {
+                        self.try_new_codec(charset_name);
+                    }
+                }
+                len
+            }
+            // numbers in DS and IS elements are always written as padded text
+            _ if matches!(de.vr, VR::DS | VR::IS) => {
+                return Ok(even_len(value.to_str().len() as u32));
+            }
+            _ => value.calculate_byte_len(),
+        };
+
+        if keep_odd_length && is_kept_odd_length(de, len) {
+            Ok(len as u32)
+        } else {
+            Ok(even_len(len as u32))
+        }
+    }
+
+    /// Obtain the number of bytes which the given data element header
+    /// occupies once encoded.
+    pub(crate) fn element_header_len(&self, de: DataElementHeader) -> u32 {
+        self.encoder.element_header_len(de) as u32
+    }
+
+    /// Retrieve the text codec currently in use.
+    pub(crate) fn text_codec(&self) -> &SpecificCharacterSet {
+        &self.text
+    }
+
+    /// Replace the text codec currently in use.
+    pub(crate) fn set_text_codec(&mut self, text: SpecificCharacterSet) {
+        self.text = text;
+    }
+
     fn try_new_codec(&mut self, name: &str) {
         if let Some(codec) = SpecificCharacterSet::from_code(name) {
             self.text = codec;
@@ -270,17 +374,23 @@ where
         }
     }
 
-    fn encode_text_element(&mut self, text: &str, de: DataElementHeader) -> Result<()> {
+    fn encode_text_element(
+        &mut self,
+        text: &str,
+        de: DataElementHeader,
+        keep_odd_length: bool,
+    ) -> Result<()> {
         // encode it in memory first so that we know the real length
         let mut encoded_value = self.convert_text_untrailed(text, de.vr)?;
         // pad to even length
-        if encoded_value.len() % 2 == 1 {
+        let unpadded = keep_odd_length && is_kept_odd_length(&de, encoded_value.len());
+        if encoded_value.len() % 2 == 1 && !unpadded {
             let pad = if de.vr == VR::UI { b'\0' } else { b' ' };
             encoded_value.push(pad);
         }
 
         // now we can write the header with the correct length
-        self.encode_element_header(DataElementHeader {
+        self.write_element_header(DataElementHeader {
             tag: de.tag,
             vr: de.vr,
             len: Length(encoded_value.len() as u32),
@@ -301,7 +411,12 @@ where
         Ok(())
     }
 
-    fn encode_texts_element<S>(&mut self, texts: &[S], de: DataElementHeader) -> Result<()>
+    fn encode_texts_element<S>(
+        &mut self,
+        texts: &[S],
+        de: DataElementHeader,
+        keep_odd_length: bool,
+    ) -> Result<()>
     where
         S: AsRef<str>,
     {
@@ -314,13 +429,14 @@ where
             }
         }
         // pad to even length
-        if self.buffer.len() % 2 == 1 {
+        let unpadded = keep_odd_length && is_kept_odd_length(&de, self.buffer.len());
+        if self.buffer.len() % 2 == 1 && !unpadded {
             let pad = if de.vr == VR::UI { b'\0' } else { b' ' };
             self.buffer.push(pad);
         }
 
         // now we can write the header with the correct length
-        self.encode_element_header(DataElementHeader {
+        self.write_element_header(DataElementHeader {
             tag: de.tag,
             vr: de.vr,
             len: Length(self.buffer.len() as u32),
@@ -421,6 +537,13 @@ fn even_len(l: u32) -> u32 {
     (l + 1) & !1
 }
 
+/// Whether the encoded value of the given byte length
+/// has the odd length declared in the original header.
+#[inline]
+fn is_kept_odd_length(de: &DataElementHeader, len: usize) -> bool {
+    len % 2 == 1 && de.len.get() == Some(len as u32)
+}
+
 #[cfg(test)]
 mod tests {
     use dicom_core::{
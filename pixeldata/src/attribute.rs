@@ -1,5 +1,9 @@
 //! Utility module for fetching key attributes from a DICOM object.
 
+use crate::overlay::{Overlay, OverlayType};
+use crate::palette::{expand_segmented_lut, PaletteColorLut};
+use crate::transform::LutTable;
+use byteorder::{ByteOrder, NativeEndian};
 use dicom_core::{header::HasLength, DataDictionary, Tag};
 use dicom_dictionary_std::tags;
 use dicom_object::{mem::InMemElement, FileDicomObject, InMemDicomObject};
@@ -30,12 +34,31 @@ pub enum AttributeName {
     VoiLutFunction,
     WindowCenter,
     WindowWidth,
+    RedPaletteColorLookupTableDescriptor,
+    GreenPaletteColorLookupTableDescriptor,
+    BluePaletteColorLookupTableDescriptor,
+    RedPaletteColorLookupTableData,
+    GreenPaletteColorLookupTableData,
+    BluePaletteColorLookupTableData,
+    OverlayRows,
+    OverlayColumns,
+    OverlayType,
+    OverlayOrigin,
+    NumberOfFramesInOverlay,
+    ImageFrameOrigin,
+    OverlayBitsAllocated,
+    OverlayBitPosition,
+    OverlayData,
+    LutDescriptor,
+    LutData,
 }
 
 impl std::fmt::Display for AttributeName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AttributeName::VoiLutFunction => f.write_str("VOILUTFunction"),
+            AttributeName::LutDescriptor => f.write_str("LUTDescriptor"),
+            AttributeName::LutData => f.write_str("LUTData"),
             _ => std::fmt::Debug::fmt(self, f),
         }
     }
@@ -92,22 +115,12 @@ pub fn rows<D: DataDictionary + Clone>(obj: &FileDicomObject<InMemDicomObject<D>
     retrieve_required_u16(obj, tags::ROWS, AttributeName::Rows)
 }
 
-/// Get the VOILUTFunction from the DICOM object
+/// Get the VOILUTFunction of each frame from the DICOM object,
+/// or `LINEAR` if it is not present
 pub fn voi_lut_function<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
 ) -> Result<Option<Vec<String>>> {
-    let elems = obj
-        .element(tags::VOILUT_FUNCTION)
-        .ok()
-        .map(|v| vec![v])
-        .or_else(|| {
-            get_from_shared(obj, [tags::FRAME_VOILUT_SEQUENCE, tags::VOILUT_FUNCTION])
-                .map(|inner| inner.collect())
-        })
-        .or_else(|| {
-            get_from_per_frame(obj, [tags::FRAME_VOILUT_SEQUENCE, tags::VOILUT_FUNCTION])
-                .map(|inner| inner.collect())
-        });
+    let elems = frame_elements(obj, tags::VOILUT_FUNCTION);
     if let Some(elems_inner) = elems {
         let res = elems_inner
             .iter()
@@ -154,122 +167,157 @@ pub fn high_bit<D: DataDictionary + Clone>(
     retrieve_required_u16(obj, tags::HIGH_BIT, AttributeName::HighBit)
 }
 
-/// Get the PixelData element from the DICOM object
+/// Get the PixelData element from the DICOM object,
+/// falling back to FloatPixelData or DoubleFloatPixelData
+/// if it is not present
 pub fn pixel_data<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
 ) -> Result<&InMemElement<D>> {
     let name = AttributeName::PixelData;
-    obj.element_opt(tags::PIXEL_DATA)
+    let tag = match pixel_data_kind(obj) {
+        PixelDataKind::Integer => tags::PIXEL_DATA,
+        PixelDataKind::Float => tags::FLOAT_PIXEL_DATA,
+        PixelDataKind::DoubleFloat => tags::DOUBLE_FLOAT_PIXEL_DATA,
+    };
+    obj.element_opt(tag)
         .context(RetrieveSnafu { name })?
         .context(MissingRequiredSnafu { name })
 }
 
-fn get_from_shared<D: DataDictionary + Clone>(
+/// The kind of pixel data attribute in a DICOM object,
+/// which determines the type of the pixel data sample values.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum PixelDataKind {
+    /// _Pixel Data_ (7FE0,0010):
+    /// integer sample values, either native or encapsulated
+    Integer,
+    /// _Float Pixel Data_ (7FE0,0008):
+    /// 32-bit floating point sample values
+    Float,
+    /// _Double Float Pixel Data_ (7FE0,0009):
+    /// 64-bit floating point sample values
+    DoubleFloat,
+}
+
+/// Get the kind of pixel data attribute in the DICOM object,
+/// returning [`Integer`](PixelDataKind::Integer)
+/// unless only _Float Pixel Data_ or _Double Float Pixel Data_ is present
+pub fn pixel_data_kind<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
-    selector: [Tag; 2],
-) -> Option<impl Iterator<Item = &InMemElement<D>>> {
-    let items = obj
-        .get(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)?
-        .items()?
-        .first()?
-        .get(selector[0])
-        .and_then(|inner| inner.items()?.first()?.get(selector[1]))
-        // Sometimes the tag is not in the properly nested sequence, but just flat in the first
-        // element of the SharedFunctionalGroupsSequence
+) -> PixelDataKind {
+    if obj.get(tags::PIXEL_DATA).is_some() {
+        PixelDataKind::Integer
+    } else if obj.get(tags::FLOAT_PIXEL_DATA).is_some() {
+        PixelDataKind::Float
+    } else if obj.get(tags::DOUBLE_FLOAT_PIXEL_DATA).is_some() {
+        PixelDataKind::DoubleFloat
+    } else {
+        PixelDataKind::Integer
+    }
+}
+
+/// Retrieve an attribute of the given frame (starting at 0)
+/// of a DICOM object.
+///
+/// In enhanced multi-frame objects,
+/// frame-specific attributes are nested in functional group macros.
+/// The attribute is looked up in this order:
+///
+/// 1. in the frame's item of the _Per-frame Functional Groups Sequence_;
+/// 2. in the item of the _Shared Functional Groups Sequence_;
+/// 3. in the top level of the data set.
+///
+/// In each functional groups item,
+/// the attribute is searched in the first item of each functional group
+/// (such as the _Pixel Value Transformation Sequence_),
+/// or directly in the functional groups item.
+///
+/// # Example
+///
+/// ```no_run
+/// # use dicom_dictionary_std::tags;
+/// # use dicom_object::open_file;
+/// use dicom_pixeldata::frame_attribute;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let obj = open_file("enhanced_ct.dcm")?;
+/// if let Some(e) = frame_attribute(&obj, 2, tags::RESCALE_INTERCEPT) {
+///     println!("Rescale intercept of frame #2: {}", e.to_float64()?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn frame_attribute<D: DataDictionary + Clone>(
+    obj: &InMemDicomObject<D>,
+    frame: u32,
+    tag: Tag,
+) -> Option<&InMemElement<D>> {
+    obj.get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+        .and_then(|e| e.items()?.get(frame as usize))
+        .and_then(|item| get_from_functional_groups(item, tag))
         .or_else(|| {
-            obj.get(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)?
+            let shared = obj
+                .get(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)?
                 .items()?
-                .first()?
-                .get(selector[1])
+                .first()?;
+            get_from_functional_groups(shared, tag)
         })
-        .map(std::iter::once);
+        .or_else(|| obj.get(tag))
+}
 
-    items.and_then(|items| {
-        let mut peekable = items.peekable();
-        peekable.peek().is_some().then_some(peekable)
-    })
+fn get_from_functional_groups<D: DataDictionary + Clone>(
+    item: &InMemDicomObject<D>,
+    tag: Tag,
+) -> Option<&InMemElement<D>> {
+    item.iter()
+        .find_map(|group| group.items()?.first()?.get(tag))
+        // Sometimes the tag is not in the properly nested sequence,
+        // but just flat in the functional groups item
+        .or_else(|| item.get(tag))
 }
 
-fn get_from_per_frame<D: DataDictionary + Clone>(
-    obj: &FileDicomObject<InMemDicomObject<D>>,
-    selector: [Tag; 2],
-) -> Option<impl Iterator<Item = &InMemElement<D>>> {
-    let mut items = obj
-        .get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)?
-        .items()?
-        .iter()
-        .filter_map(move |item| item.get(selector[0])?.items()?.first()?.get(selector[1]))
-        .peekable();
+/// Retrieve an attribute of every frame of a DICOM object
+/// with [`frame_attribute`].
+///
+/// Returns a single element if the object has no per-frame functional groups,
+/// or `None` if the attribute is missing in any of the frames.
+fn frame_elements<D: DataDictionary + Clone>(
+    obj: &InMemDicomObject<D>,
+    tag: Tag,
+) -> Option<Vec<&InMemElement<D>>> {
+    let frames = obj
+        .get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+        .and_then(|e| e.items())
+        .map(|items| items.len())
+        .filter(|&len| len > 0)
+        .unwrap_or(1);
+    (0..frames as u32)
+        .map(|frame| frame_attribute(obj, frame, tag))
+        .collect()
+}
 
-    items.peek().is_some().then_some(items)
+/// Retrieve a floating point attribute of every frame of a DICOM object.
+fn frame_floats<D: DataDictionary + Clone>(
+    obj: &InMemDicomObject<D>,
+    tag: Tag,
+) -> Option<Vec<f64>> {
+    frame_elements(obj, tag)?
+        .into_iter()
+        .map(|el| el.to_float64().ok())
+        .collect()
 }
 
-/// Get the RescaleIntercept from the DICOM object or returns 0
+/// Get the RescaleIntercept of each frame from the DICOM object or returns 0
 pub fn rescale_intercept<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
 ) -> Vec<f64> {
-    obj.element(tags::RESCALE_INTERCEPT)
-        .ok()
-        .and_then(|e| {
-            vec![e.to_float64().ok()]
-                .into_iter()
-                .collect::<Option<Vec<f64>>>()
-        })
-        .or_else(|| {
-            get_from_per_frame(
-                obj,
-                [
-                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
-                    tags::RESCALE_INTERCEPT,
-                ],
-            )
-            .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        })
-        .or_else(|| {
-            get_from_shared(
-                obj,
-                [
-                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
-                    tags::RESCALE_INTERCEPT,
-                ],
-            )
-            .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        })
-        .unwrap_or(vec![0.])
+    frame_floats(obj, tags::RESCALE_INTERCEPT).unwrap_or(vec![0.])
 }
 
-/// Get the RescaleSlope from the DICOM object or returns 1.0
+/// Get the RescaleSlope of each frame from the DICOM object or returns 1.0
 pub fn rescale_slope<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
 ) -> Vec<f64> {
-    obj.element(tags::RESCALE_SLOPE)
-        .ok()
-        .and_then(|e| {
-            vec![e.to_float64().ok()]
-                .into_iter()
-                .collect::<Option<Vec<f64>>>()
-        })
-        .or_else(|| {
-            get_from_per_frame(
-                obj,
-                [
-                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
-                    tags::RESCALE_SLOPE,
-                ],
-            )
-            .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        })
-        .or_else(|| {
-            get_from_shared(
-                obj,
-                [
-                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
-                    tags::RESCALE_SLOPE,
-                ],
-            )
-            .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        })
-        .unwrap_or(vec![1.0])
+    frame_floats(obj, tags::RESCALE_SLOPE).unwrap_or(vec![1.0])
 }
 
 /// Get the NumberOfFrames from the DICOM object,
@@ -304,48 +352,18 @@ pub fn number_of_frames<D: DataDictionary + Clone>(
     Ok(integer as u32)
 }
 
-/// Retrieve the WindowCenter from the DICOM object if it exists.
+/// Retrieve the WindowCenter of each frame from the DICOM object if it exists.
 pub fn window_center<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
 ) -> Option<Vec<f64>> {
-    let wc = obj
-        .get(tags::WINDOW_CENTER)
-        .and_then(|e| {
-            vec![e.to_float64().ok()]
-                .into_iter()
-                .collect::<Option<Vec<f64>>>()
-        })
-        .or_else(|| {
-            get_from_per_frame(obj, [tags::FRAME_VOILUT_SEQUENCE, tags::WINDOW_CENTER])
-                .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        })
-        .or_else(|| {
-            get_from_shared(obj, [tags::FRAME_VOILUT_SEQUENCE, tags::WINDOW_CENTER])
-                .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        });
-    wc
+    frame_floats(obj, tags::WINDOW_CENTER)
 }
 
-/// Retrieve the WindowWidth from the DICOM object if it exists.
+/// Retrieve the WindowWidth of each frame from the DICOM object if it exists.
 pub fn window_width<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
 ) -> Option<Vec<f64>> {
-    let ww = obj
-        .get(tags::WINDOW_WIDTH)
-        .and_then(|e| {
-            vec![e.to_float64().ok()]
-                .into_iter()
-                .collect::<Option<Vec<f64>>>()
-        })
-        .or_else(|| {
-            get_from_per_frame(obj, [tags::FRAME_VOILUT_SEQUENCE, tags::WINDOW_WIDTH])
-                .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        })
-        .or_else(|| {
-            get_from_shared(obj, [tags::FRAME_VOILUT_SEQUENCE, tags::WINDOW_WIDTH])
-                .and_then(|v| v.into_iter().map(|el| el.to_float64().ok()).collect())
-        });
-    ww
+    frame_floats(obj, tags::WINDOW_WIDTH)
 }
 
 #[inline]
@@ -364,6 +382,428 @@ where
         .context(CastValueSnafu { name })
 }
 
+/// Get the palette color look-up table from the DICOM object,
+/// as described by the red, green, and blue
+/// _Palette Color Lookup Table Descriptor_ and
+/// _Palette Color Lookup Table Data_ attributes.
+///
+/// _Segmented Palette Color Lookup Table Data_
+/// is used instead when the regular table data is not present,
+/// and is expanded into the full table.
+pub fn palette_color_lut<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> Result<PaletteColorLut> {
+    let (red_first, red_bits, red) = palette_color_lut_channel(
+        obj,
+        [
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            tags::SEGMENTED_RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+        ],
+        [
+            AttributeName::RedPaletteColorLookupTableDescriptor,
+            AttributeName::RedPaletteColorLookupTableData,
+        ],
+    )?;
+    let (_, _, green) = palette_color_lut_channel(
+        obj,
+        [
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            tags::SEGMENTED_GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+        ],
+        [
+            AttributeName::GreenPaletteColorLookupTableDescriptor,
+            AttributeName::GreenPaletteColorLookupTableData,
+        ],
+    )?;
+    let (_, _, blue) = palette_color_lut_channel(
+        obj,
+        [
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            tags::SEGMENTED_BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+        ],
+        [
+            AttributeName::BluePaletteColorLookupTableDescriptor,
+            AttributeName::BluePaletteColorLookupTableData,
+        ],
+    )?;
+
+    // all descriptors are expected to be equal,
+    // so the red one is taken as reference
+    Ok(PaletteColorLut::new(red_first, red_bits, red, green, blue))
+}
+
+/// Retrieve the first mapped value, the number of bits per entry,
+/// and the expanded table of a single palette color LUT channel,
+/// given the tags of its descriptor, data, and segmented data.
+fn palette_color_lut_channel<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    [descriptor_tag, data_tag, segmented_data_tag]: [Tag; 3],
+    [descriptor_name, data_name]: [AttributeName; 2],
+) -> Result<(i32, u16, Vec<u16>)> {
+    let descriptor = obj
+        .element_opt(descriptor_tag)
+        .context(RetrieveSnafu {
+            name: descriptor_name,
+        })?
+        .context(MissingRequiredSnafu {
+            name: descriptor_name,
+        })?
+        .to_multi_int::<i32>()
+        .context(ConvertValueSnafu {
+            name: descriptor_name,
+        })?;
+
+    let (entries, first_mapped, bits) = match descriptor[..] {
+        [entries, first_mapped, bits @ (8 | 16)] => {
+            // 0 means 2^16 entries
+            let entries = if entries == 0 { 0x1_0000 } else { entries };
+            (entries as usize, first_mapped, bits as u16)
+        }
+        _ => {
+            return InvalidValueSnafu {
+                name: descriptor_name,
+                value: format!("{:?}", descriptor),
+            }
+            .fail()
+        }
+    };
+
+    let (data_elem, segmented) = match obj
+        .element_opt(data_tag)
+        .context(RetrieveSnafu { name: data_name })?
+    {
+        Some(elem) => (elem, false),
+        None => (
+            obj.element_opt(segmented_data_tag)
+                .context(RetrieveSnafu { name: data_name })?
+                .context(MissingRequiredSnafu { name: data_name })?,
+            true,
+        ),
+    };
+    let bytes = data_elem
+        .to_bytes()
+        .context(ConvertValueSnafu { name: data_name })?;
+
+    let table = if bits == 8 && !segmented && bytes.len() < entries * 2 {
+        // one entry per byte
+        bytes.iter().map(|b| u16::from(*b)).collect()
+    } else {
+        let mut words = vec![0; bytes.len() / 2];
+        NativeEndian::read_u16_into(&bytes[..words.len() * 2], &mut words);
+        if segmented {
+            expand_segmented_lut(&words).with_context(|| InvalidValueSnafu {
+                name: data_name,
+                value: "<malformed segmented table>",
+            })?
+        } else if bits == 8 && words.iter().any(|w| *w > 0xFF) {
+            // 8-bit entries stored in the high byte of each word
+            words.into_iter().map(|w| w >> 8).collect()
+        } else {
+            words
+        }
+    };
+
+    ensure!(
+        !table.is_empty(),
+        InvalidValueSnafu {
+            name: data_name,
+            value: "<empty table>",
+        }
+    );
+
+    Ok((first_mapped, bits, table))
+}
+
+/// Retrieve a sequence attribute of every frame of a DICOM object
+/// with [`frame_attribute`].
+///
+/// Returns a single element if all frames share the same one,
+/// so that look-up tables are only read once.
+fn frame_sequences<D: DataDictionary + Clone>(
+    obj: &InMemDicomObject<D>,
+    tag: Tag,
+) -> Option<Vec<&InMemElement<D>>> {
+    let mut elements = frame_elements(obj, tag)?;
+    let first = elements[0];
+    if elements.iter().all(|&e| std::ptr::eq(e, first)) {
+        elements.truncate(1);
+    }
+    Some(elements)
+}
+
+/// Get the look-up table of the _Modality LUT Sequence_ of each frame
+/// from the DICOM object,
+/// or a single one if it applies to all frames.
+///
+/// Returns an empty vector if the DICOM object has none.
+pub fn modality_lut<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> Result<Vec<LutTable>> {
+    let signed = is_signed(obj);
+    frame_sequences(obj, tags::MODALITY_LUT_SEQUENCE)
+        .and_then(|elements| {
+            elements
+                .into_iter()
+                .map(|e| e.items()?.first())
+                .collect::<Option<Vec<_>>>()
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| lut_table(item, signed))
+        .collect()
+}
+
+/// Get the look-up tables of the _VOI LUT Sequence_ of each frame
+/// from the DICOM object,
+/// or a single list if they apply to all frames.
+///
+/// The tables of each frame are in the order
+/// in which they appear in the DICOM object.
+/// Returns an empty vector if the DICOM object has none.
+pub fn voi_luts<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> Result<Vec<Vec<LutTable>>> {
+    let signed = is_signed(obj);
+    frame_sequences(obj, tags::VOILUT_SEQUENCE)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| {
+            e.items()
+                .unwrap_or_default()
+                .iter()
+                .map(|item| lut_table(item, signed))
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether the pixel data samples are signed,
+/// which determines the interpretation of the first mapped value
+/// in LUT descriptors.
+fn is_signed<D: DataDictionary + Clone>(obj: &FileDicomObject<InMemDicomObject<D>>) -> bool {
+    matches!(pixel_representation(obj), Ok(PixelRepresentation::Signed))
+}
+
+/// Read the look-up table described by
+/// the _LUT Descriptor_ and _LUT Data_ in the given sequence item.
+///
+/// If `signed` is true,
+/// the first mapped value is interpreted as a signed 16-bit integer.
+pub(crate) fn lut_table<D: DataDictionary + Clone>(
+    item: &InMemDicomObject<D>,
+    signed: bool,
+) -> Result<LutTable> {
+    let descriptor = item
+        .get(tags::LUT_DESCRIPTOR)
+        .context(MissingRequiredSnafu {
+            name: AttributeName::LutDescriptor,
+        })?
+        .to_multi_int::<i32>()
+        .context(ConvertValueSnafu {
+            name: AttributeName::LutDescriptor,
+        })?;
+
+    let (entries, first_mapped, bits) = match descriptor[..] {
+        [entries, first_mapped, bits @ 1..=16] => {
+            // 0 means 2^16 entries
+            let entries = if entries == 0 { 0x1_0000 } else { entries };
+            // the value may have been read as unsigned
+            let first_mapped = if signed && first_mapped > 0x7FFF {
+                first_mapped - 0x1_0000
+            } else {
+                first_mapped
+            };
+            (entries as usize, first_mapped, bits as u16)
+        }
+        _ => {
+            return InvalidValueSnafu {
+                name: AttributeName::LutDescriptor,
+                value: format!("{:?}", descriptor),
+            }
+            .fail()
+        }
+    };
+
+    let bytes = item
+        .get(tags::LUT_DATA)
+        .context(MissingRequiredSnafu {
+            name: AttributeName::LutData,
+        })?
+        .to_bytes()
+        .context(ConvertValueSnafu {
+            name: AttributeName::LutData,
+        })?;
+
+    let table: Vec<u16> = if bits <= 8 && bytes.len() < entries * 2 {
+        // one entry per byte
+        bytes.iter().map(|b| u16::from(*b)).collect()
+    } else {
+        let mut words = vec![0; bytes.len() / 2];
+        NativeEndian::read_u16_into(&bytes[..words.len() * 2], &mut words);
+        words
+    };
+
+    ensure!(
+        !table.is_empty(),
+        InvalidValueSnafu {
+            name: AttributeName::LutData,
+            value: "<empty table>",
+        }
+    );
+
+    Ok(LutTable::new(first_mapped, bits, table))
+}
+
+/// Get all overlay planes described in the DICOM object,
+/// in the repeating groups `6000` to `601E`.
+///
+/// Groups without _Overlay Rows_ are skipped.
+/// A group with invalid overlay attributes is skipped with a warning,
+/// so that it does not prevent reading the other overlays.
+/// The bits of overlays embedded in the pixel data
+/// are not extracted here.
+pub fn overlays<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> Vec<Overlay> {
+    overlay_groups(obj)
+        .filter_map(|group| match overlay(obj, group) {
+            Ok(overlay) => Some(overlay),
+            Err(e) => {
+                tracing::warn!("Ignoring overlay in group {:04X}: {}", group, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Iterate over the overlay repeating groups present in the DICOM object.
+fn overlay_groups<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> impl Iterator<Item = u16> + '_ {
+    (0x6000..=0x601E)
+        .step_by(2)
+        .filter(move |group| obj.get(Tag(*group, 0x0010)).is_some())
+}
+
+/// Get the overlay plane in the given repeating group.
+fn overlay<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    group: u16,
+) -> Result<Overlay> {
+    let rows = retrieve_required_u16(obj, Tag(group, 0x0010), AttributeName::OverlayRows)?;
+    let columns = retrieve_required_u16(obj, Tag(group, 0x0011), AttributeName::OverlayColumns)?;
+
+    let name = AttributeName::OverlayType;
+    let overlay_type = match obj.get(Tag(group, 0x0040)) {
+        Some(elem) => match elem.to_str().context(ConvertValueSnafu { name })?.trim() {
+            "G" => OverlayType::Graphics,
+            "R" => OverlayType::Roi,
+            value => {
+                return InvalidValueSnafu {
+                    name,
+                    value: value.to_string(),
+                }
+                .fail()
+            }
+        },
+        // tolerate a missing overlay type
+        None => OverlayType::Graphics,
+    };
+
+    let name = AttributeName::OverlayOrigin;
+    let origin = match obj.get(Tag(group, 0x0050)) {
+        Some(elem) => match elem
+            .to_multi_int::<i16>()
+            .context(ConvertValueSnafu { name })?[..]
+        {
+            [row, column] => [row, column],
+            ref value => {
+                return InvalidValueSnafu {
+                    name,
+                    value: format!("{:?}", value),
+                }
+                .fail()
+            }
+        },
+        None => [1, 1],
+    };
+
+    let optional_u32 = |element: u16, name: AttributeName| -> Result<u32> {
+        match obj.get(Tag(group, element)) {
+            Some(elem) if !elem.is_empty() => {
+                let value = elem.to_int::<i32>().context(ConvertValueSnafu { name })?;
+                ensure!(
+                    value > 0,
+                    InvalidValueSnafu {
+                        name,
+                        value: value.to_string(),
+                    }
+                );
+                Ok(value as u32)
+            }
+            _ => Ok(1),
+        }
+    };
+    let number_of_frames = optional_u32(0x0015, AttributeName::NumberOfFramesInOverlay)?;
+    let image_frame_origin = optional_u32(0x0051, AttributeName::ImageFrameOrigin)?;
+
+    let optional_string = |element: u16| {
+        obj.get(Tag(group, element))
+            .and_then(|elem| elem.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let description = optional_string(0x0022);
+    let label = optional_string(0x1500);
+
+    let name = AttributeName::OverlayData;
+    let (embedded_bit, data) = match obj.get(Tag(group, 0x3000)) {
+        Some(elem) => {
+            let data = match elem.uint16_slice() {
+                // bits are packed from the least significant bit of each word
+                Ok(words) => words
+                    .iter()
+                    .flat_map(|w| w.to_le_bytes().to_vec())
+                    .collect(),
+                Err(_) => elem
+                    .to_bytes()
+                    .context(ConvertValueSnafu { name })?
+                    .into_owned(),
+            };
+            (None, data)
+        }
+        None => {
+            // the overlay may be embedded in the pixel data (retired)
+            let bits_allocated = retrieve_required_u16(
+                obj,
+                Tag(group, 0x0100),
+                AttributeName::OverlayBitsAllocated,
+            )?;
+            ensure!(bits_allocated > 1, MissingRequiredSnafu { name });
+            let bit_position =
+                retrieve_required_u16(obj, Tag(group, 0x0102), AttributeName::OverlayBitPosition)?;
+            (Some(bit_position), Vec::new())
+        }
+    };
+
+    Ok(Overlay::new(
+        group,
+        rows,
+        columns,
+        overlay_type,
+        origin,
+        number_of_frames,
+        image_frame_origin,
+        label,
+        description,
+        embedded_bit,
+        data,
+    ))
+}
+
 /// A decoded representation of the DICOM _Pixel Representation_ attribute.
 #[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
 #[repr(u16)]
@@ -620,7 +1060,7 @@ pub fn photometric_interpretation<D: DataDictionary + Clone>(
 
 #[cfg(test)]
 mod tests {
-    use super::rescale_intercept;
+    use super::{frame_attribute, rescale_intercept, window_center};
     use dicom_core::{
         dicom_value,
         ops::{ApplyOp, AttributeAction, AttributeOp},
@@ -639,7 +1079,8 @@ mod tests {
         assert!(
             size <= max_size,
             "GetAttributeError size is too large ({} > {})",
-            size, max_size
+            size,
+            max_size
         );
     }
 
@@ -789,4 +1230,58 @@ mod tests {
         // Check the fn still returns the correct value, falling back to SharedFunctionalGroupsSequence
         assert_eq!(rescale_intercept(&dcm), vec![3.0]);
     }
+
+    #[test]
+    fn get_frame_attribute_with_fallback() {
+        let mut dcm = dummy_dicom();
+        let voi = |center: f64| {
+            DataElement::new(
+                tags::FRAME_VOILUT_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::WINDOW_CENTER, VR::DS, dicom_value!(F64, center)),
+                ])]),
+            )
+        };
+        dcm.put(DataElement::new(
+            tags::WINDOW_CENTER,
+            VR::DS,
+            dicom_value!(F64, 10.0),
+        ));
+        assert_eq!(
+            frame_attribute(&dcm, 0, tags::WINDOW_CENTER)
+                .unwrap()
+                .to_float64()
+                .unwrap(),
+            10.0
+        );
+
+        // shared functional groups take precedence over the top level
+        dcm.put(DataElement::new(
+            tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([voi(20.0)])]),
+        ));
+        // per-frame functional groups take precedence over the shared ones,
+        // but the second frame does not have a window
+        dcm.put(DataElement::new(
+            tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![
+                InMemDicomObject::from_element_iter([voi(30.0)]),
+                InMemDicomObject::new_empty(),
+            ]),
+        ));
+
+        let center = |frame| {
+            frame_attribute(&dcm, frame, tags::WINDOW_CENTER)
+                .unwrap()
+                .to_float64()
+                .unwrap()
+        };
+        assert_eq!(center(0), 30.0);
+        assert_eq!(center(1), 20.0);
+        assert!(frame_attribute(&dcm, 0, tags::WINDOW_WIDTH).is_none());
+        assert_eq!(window_center(&dcm), Some(vec![30.0, 20.0]));
+    }
 }
@@ -0,0 +1,170 @@
+//! A CLI tool for wrapping an MP4 video file
+//! into a new DICOM video object.
+use clap::{Parser, ValueEnum};
+use dicom_core::{DataElement, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_pixeldata::video::{VideoInfo, VideoObjectBuilder};
+use snafu::{Report, ResultExt, Whatever};
+use std::path::PathBuf;
+use tracing::Level;
+
+/// Exit code for when an error emerged while reading the video file.
+const ERROR_READ: i32 = -2;
+/// Exit code for when an error emerged while building the DICOM object.
+const ERROR_BUILD: i32 = -3;
+/// Exit code for when an error emerged while writing the file.
+const ERROR_WRITE: i32 = -4;
+/// Exit code for any other error.
+const ERROR_OTHER: i32 = -128;
+
+/// Wrap an MP4 video file (H.264 or HEVC) into a new DICOM file
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    file: PathBuf,
+    /// The output file (default is to change the extension to .dcm)
+    #[clap(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+
+    /// The kind of video object to create
+    #[clap(long = "kind", value_enum, default_value_t = VideoKind::Photographic)]
+    kind: VideoKind,
+
+    /// The transfer syntax UID
+    /// (default is to choose one based on the video profile and level)
+    #[clap(long = "ts")]
+    ts: Option<String>,
+
+    /// Split the video into fragments of this size in bytes
+    /// (only in fragmentable transfer syntaxes)
+    #[clap(long = "fragment-size")]
+    fragment_size: Option<u32>,
+
+    /// The patient's name
+    #[clap(long = "patient-name")]
+    patient_name: Option<String>,
+
+    /// The patient ID
+    #[clap(long = "patient-id")]
+    patient_id: Option<String>,
+
+    /// Verbose mode
+    #[clap(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+/// The kind of video object, which determines its SOP class
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum VideoKind {
+    /// Video Endoscopic Image
+    Endoscopic,
+    /// Video Microscopic Image
+    Microscopic,
+    /// Video Photographic Image
+    Photographic,
+}
+
+impl VideoKind {
+    fn sop_class_uid(self) -> &'static str {
+        match self {
+            VideoKind::Endoscopic => uids::VIDEO_ENDOSCOPIC_IMAGE_STORAGE,
+            VideoKind::Microscopic => uids::VIDEO_MICROSCOPIC_IMAGE_STORAGE,
+            VideoKind::Photographic => uids::VIDEO_PHOTOGRAPHIC_IMAGE_STORAGE,
+        }
+    }
+}
+
+fn main() {
+    run().unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(ERROR_OTHER);
+    });
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        file,
+        output,
+        kind,
+        ts,
+        fragment_size,
+        patient_name,
+        patient_id,
+        verbose,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("{}", snafu::Report::from_error(e));
+    });
+
+    let output = output.unwrap_or_else(|| {
+        let mut file = file.clone();
+        file.set_extension("dcm");
+        file
+    });
+
+    let data = std::fs::read(&file).unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(ERROR_READ);
+    });
+
+    if verbose {
+        let info = VideoInfo::from_mp4(&data).whatever_context("Could not read MP4 file")?;
+        println!(
+            "{:?} video, profile {}, level {}, {}x{}, {} frames of {:.2} ms",
+            info.codec,
+            info.profile,
+            info.level,
+            info.columns,
+            info.rows,
+            info.number_of_frames,
+            info.frame_time
+        );
+    }
+
+    let mut builder = VideoObjectBuilder::new().sop_class_uid(kind.sop_class_uid());
+    if let Some(ts) = ts {
+        builder = builder.transfer_syntax(ts);
+    }
+    if let Some(fragment_size) = fragment_size {
+        builder = builder.fragment_size(fragment_size);
+    }
+    if let Some(patient_name) = patient_name {
+        builder = builder.attribute(DataElement::new(tags::PATIENT_NAME, VR::PN, patient_name));
+    }
+    if let Some(patient_id) = patient_id {
+        builder = builder.attribute(DataElement::new(tags::PATIENT_ID, VR::LO, patient_id));
+    }
+
+    let obj = builder.build_from_mp4(data).unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(ERROR_BUILD);
+    });
+
+    obj.write_to_file(&output).unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(ERROR_WRITE);
+    });
+
+    if verbose {
+        println!("DICOM file saved to {}", output.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -0,0 +1,142 @@
+//! A CLI tool for assembling the images of a DICOM series
+//! into a 3D volume in the NIfTI-1 format.
+use clap::Parser;
+use dicom_pixeldata::volume::VolumeBuilder;
+use snafu::{Report, ResultExt, Whatever};
+use std::path::PathBuf;
+use tracing::Level;
+
+/// Exit code for when an error emerged while reading the input files.
+const ERROR_READ: i32 = -2;
+/// Exit code for when an error emerged while assembling the volume.
+const ERROR_BUILD: i32 = -3;
+/// Exit code for when an error emerged while writing the file.
+const ERROR_WRITE: i32 = -4;
+/// Exit code for any other error.
+const ERROR_OTHER: i32 = -128;
+
+/// Convert a DICOM series into a NIfTI-1 volume
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The DICOM files of the series,
+    /// or directories containing them
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+    /// The output file
+    #[clap(short = 'o', long = "output", default_value = "volume.nii")]
+    output: PathBuf,
+
+    /// Keep the stored pixel values,
+    /// instead of applying the modality LUT function (rescale)
+    #[clap(long = "no-rescale")]
+    no_rescale: bool,
+
+    /// Accept slices which are not evenly spaced
+    #[clap(long = "allow-irregular-spacing")]
+    allow_irregular_spacing: bool,
+
+    /// The maximum difference in millimeters
+    /// between positions considered to be the same
+    #[clap(long = "tolerance", default_value_t = 0.01)]
+    tolerance: f64,
+
+    /// Verbose mode
+    #[clap(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+fn main() {
+    run().unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(ERROR_OTHER);
+    });
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        files,
+        output,
+        no_rescale,
+        allow_irregular_spacing,
+        tolerance,
+        verbose,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("{}", snafu::Report::from_error(e));
+    });
+
+    let mut paths = Vec::new();
+    for file in files {
+        if file.is_dir() {
+            let mut entries = std::fs::read_dir(&file)
+                .with_whatever_context(|_| format!("Could not read {}", file.display()))?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()
+                .with_whatever_context(|_| format!("Could not read {}", file.display()))?;
+            entries.retain(|path| path.is_file());
+            entries.sort();
+            paths.extend(entries);
+        } else {
+            paths.push(file);
+        }
+    }
+
+    let objects = paths
+        .iter()
+        .map(dicom_object::open_file)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", Report::from_error(e));
+            std::process::exit(ERROR_READ);
+        });
+
+    let volume = VolumeBuilder::new()
+        .tolerance(tolerance)
+        .modality_lut(!no_rescale)
+        .allow_irregular_spacing(allow_irregular_spacing)
+        .build(&objects)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", Report::from_error(e));
+            std::process::exit(ERROR_BUILD);
+        });
+
+    volume.save_nifti(&output).unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(ERROR_WRITE);
+    });
+
+    if verbose {
+        let [slices, rows, columns] = volume.shape();
+        let [dx, dy, dz] = volume.voxel_spacing;
+        println!(
+            "Volume of {}x{}x{} voxels ({} x {} x {} mm) saved to {}",
+            columns,
+            rows,
+            slices,
+            dx,
+            dy,
+            dz,
+            output.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
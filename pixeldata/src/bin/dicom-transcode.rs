@@ -1,10 +1,10 @@
 //! A CLI tool for transcoding a DICOM file
 //! to another transfer syntax.
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use dicom_dictionary_std::uids;
 use dicom_encoding::adapters::EncodeOptions;
 use dicom_encoding::{TransferSyntax, TransferSyntaxIndex};
-use dicom_object::open_file;
+use dicom_object::{open_file, DataSetWriterOptions, SequenceLengthStrategy};
 use dicom_pixeldata::Transcode;
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use snafu::{OptionExt, Report, Whatever};
@@ -44,11 +44,36 @@ struct App {
     #[clap(long)]
     retain_implementation: bool,
 
+    /// How to encode the lengths of sequences and items
+    #[clap(long = "sequence-length", value_enum, default_value_t = SequenceLength::Preserve)]
+    sequence_length: SequenceLength,
+
     /// Verbose mode
     #[clap(short = 'v', long = "verbose")]
     verbose: bool,
 }
 
+/// Encoding of sequence and item lengths in the output file
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum SequenceLength {
+    /// Keep the lengths of the original file
+    Preserve,
+    /// Write all sequences and items with an explicit length
+    Explicit,
+    /// Write all sequences and items with an undefined length
+    Undefined,
+}
+
+impl From<SequenceLength> for SequenceLengthStrategy {
+    fn from(value: SequenceLength) -> Self {
+        match value {
+            SequenceLength::Preserve => SequenceLengthStrategy::Preserve,
+            SequenceLength::Explicit => SequenceLengthStrategy::Explicit,
+            SequenceLength::Undefined => SequenceLengthStrategy::Undefined,
+        }
+    }
+}
+
 /// Specifier for the target transfer syntax
 #[derive(Debug, Parser)]
 #[group(required = true, multiple = false, id = "transfer_syntax")]
@@ -70,6 +95,21 @@ struct TargetTransferSyntax {
     #[clap(long = "jpeg-baseline")]
     jpeg_baseline: bool,
 
+    /// Transcode to JPEG lossless (first-order prediction)
+    #[cfg(feature = "jpeg")]
+    #[clap(long = "jpeg-lossless")]
+    jpeg_lossless: bool,
+
+    /// Transcode to JPEG 2000 lossless
+    #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+    #[clap(long = "jpeg2k-lossless")]
+    jpeg2k_lossless: bool,
+
+    /// Transcode to JPEG 2000
+    #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+    #[clap(long = "jpeg2k")]
+    jpeg2k: bool,
+
     /// Transcode to JPEG-LS lossless
     #[cfg(feature = "charls")]
     #[clap(long = "jpeg-ls-lossless")]
@@ -101,6 +141,12 @@ impl TargetTransferSyntax {
                 implicit_vr_le: false,
                 #[cfg(feature = "jpeg")]
                     jpeg_baseline: false,
+                #[cfg(feature = "jpeg")]
+                    jpeg_lossless: false,
+                #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+                    jpeg2k_lossless: false,
+                #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+                    jpeg2k: false,
                 #[cfg(feature = "charls")]
                     jpeg_ls_lossless: false,
                 #[cfg(feature = "charls")]
@@ -132,6 +178,27 @@ impl TargetTransferSyntax {
             } => TransferSyntaxRegistry
                 .get(uids::JPEG_BASELINE8_BIT)
                 .whatever_context("Missing specifier for JPEG Baseline (8-bit)"),
+            // JPEG lossless SV1
+            #[cfg(feature = "jpeg")]
+            TargetTransferSyntax {
+                jpeg_lossless: true,
+                ..
+            } => TransferSyntaxRegistry
+                .get(uids::JPEG_LOSSLESS_SV1)
+                .whatever_context("Missing specifier for JPEG Lossless (SV1)"),
+            // JPEG 2000 lossless
+            #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+            TargetTransferSyntax {
+                jpeg2k_lossless: true,
+                ..
+            } => TransferSyntaxRegistry
+                .get(uids::JPEG2000_LOSSLESS)
+                .whatever_context("Missing specifier for JPEG 2000 Lossless"),
+            // JPEG 2000
+            #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+            TargetTransferSyntax { jpeg2k: true, .. } => TransferSyntaxRegistry
+                .get(uids::JPEG2000)
+                .whatever_context("Missing specifier for JPEG 2000"),
             // JPEG-LS lossless
             #[cfg(feature = "charls")]
             TargetTransferSyntax {
@@ -183,6 +250,7 @@ fn run() -> Result<(), Whatever> {
         effort,
         target_ts,
         retain_implementation,
+        sequence_length,
         verbose,
     } = App::parse();
 
@@ -228,10 +296,12 @@ fn run() -> Result<(), Whatever> {
     }
 
     // write to file
-    obj.write_to_file(output).unwrap_or_else(|e| {
-        eprintln!("{}", Report::from_error(e));
-        std::process::exit(ERROR_WRITE);
-    });
+    let write_options = DataSetWriterOptions::default().sequence_length(sequence_length.into());
+    obj.write_to_file_with_options(output, write_options)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", Report::from_error(e));
+            std::process::exit(ERROR_WRITE);
+        });
 
     Ok(())
 }
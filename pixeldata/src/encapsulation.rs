@@ -1,8 +1,17 @@
 //! DICOM Pixel encapsulation
 //!
 //! This module implements encapsulation for pixel data.
+//!
+//! The _Basic Offset Table_ can only address
+//! up to 4 GiB of encapsulated pixel data.
+//! For larger multi-frame objects,
+//! the frames can be located through an [`ExtendedOffsetTable`] instead.
 use dicom_core::value::fragments::Fragments;
-use dicom_core::value::Value;
+use dicom_core::value::{InMemFragment, PixelFragmentSequence, Value};
+use dicom_core::{DataDictionary, DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use std::convert::TryFrom;
 use std::vec;
 
 /// Encapsulate the pixel data of a list of frames.
@@ -55,6 +64,137 @@ pub fn encapsulate_single_frame(frame: Vec<u8>, fragment_size: u32) -> Value {
     Value::PixelSequence(fragments.into())
 }
 
+/// Encapsulate the pixel data of a list of frames,
+/// one fragment per frame,
+/// describing the position of each frame
+/// with an _Extended Offset Table_
+/// instead of the _Basic Offset Table_,
+/// which is left empty.
+///
+/// The extended offset table returned
+/// should be recorded in the same object as the pixel data,
+/// via [`ExtendedOffsetTable::put_into`].
+///
+/// # Example
+/// ```
+/// use dicom_core::DataElement;
+/// use dicom_core::VR::OB;
+/// use dicom_dictionary_std::tags;
+/// use dicom_object::InMemDicomObject;
+/// use dicom_pixeldata::encapsulation::encapsulate_with_extended_offset_table;
+///
+/// // two 512x512 frames
+/// let frames = vec![vec![0; 262144], vec![0; 262144]];
+/// let (pixel_data, offset_table) = encapsulate_with_extended_offset_table(frames);
+/// assert_eq!(offset_table.offsets(), &[0, 262152]);
+///
+/// let mut obj = InMemDicomObject::new_empty();
+/// obj.put(DataElement::new(tags::PIXEL_DATA, OB, pixel_data));
+/// offset_table.put_into(&mut obj);
+/// ```
+pub fn encapsulate_with_extended_offset_table(
+    frames: Vec<Vec<u8>>,
+) -> (PixelFragmentSequence<InMemFragment>, ExtendedOffsetTable) {
+    let fragments: Vec<Vec<u8>> = frames
+        .into_iter()
+        .map(|mut frame| {
+            // fragments must have an even length
+            if frame.len() % 2 != 0 {
+                frame.push(0);
+            }
+            frame
+        })
+        .collect();
+    let offset_table =
+        ExtendedOffsetTable::from_frame_lengths(fragments.iter().map(|f| f.len() as u64));
+
+    (
+        PixelFragmentSequence::new_fragments(fragments),
+        offset_table,
+    )
+}
+
+/// The _Extended Offset Table_ of encapsulated pixel data
+/// and the respective _Extended Offset Table Lengths_,
+/// for pixel data with one fragment per frame.
+///
+/// Unlike the _Basic Offset Table_,
+/// the offsets are 64-bit wide,
+/// and so can locate frames beyond the first 4 GiB of pixel data.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExtendedOffsetTable {
+    /// the byte offset of the item of each frame,
+    /// relative to the first fragment item
+    offsets: Vec<u64>,
+    /// the length in bytes of each frame fragment
+    lengths: Vec<u64>,
+}
+
+impl ExtendedOffsetTable {
+    /// Build the extended offset table
+    /// from the length in bytes of each frame,
+    /// one fragment per frame.
+    ///
+    /// Odd lengths are rounded up to even,
+    /// as fragments are padded when encapsulated.
+    pub fn from_frame_lengths<I>(lengths: I) -> Self
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let lengths: Vec<u64> = lengths.into_iter().map(|len| (len + 1) & !1).collect();
+        let mut offset = 0;
+        let offsets = lengths
+            .iter()
+            .map(|len| {
+                let frame_offset = offset;
+                // account for the item header
+                offset += len + 8;
+                frame_offset
+            })
+            .collect();
+        ExtendedOffsetTable { offsets, lengths }
+    }
+
+    /// Get the byte offset of each frame,
+    /// relative to the first fragment item.
+    pub fn offsets(&self) -> &[u64] {
+        &self.offsets
+    }
+
+    /// Get the length in bytes of each frame.
+    pub fn lengths(&self) -> &[u64] {
+        &self.lengths
+    }
+
+    /// Convert the offsets into a _Basic Offset Table_,
+    /// or return `None` if any of the offsets does not fit in 32 bits.
+    pub fn to_basic_offset_table(&self) -> Option<Vec<u32>> {
+        self.offsets
+            .iter()
+            .map(|offset| u32::try_from(*offset).ok())
+            .collect()
+    }
+
+    /// Record this table in the given object,
+    /// as the _Extended Offset Table_
+    /// and _Extended Offset Table Lengths_ attributes.
+    pub fn put_into<D>(self, obj: &mut InMemDicomObject<D>)
+    where
+        D: DataDictionary + Clone,
+    {
+        obj.put(DataElement::new(
+            tags::EXTENDED_OFFSET_TABLE,
+            VR::OV,
+            PrimitiveValue::U64(self.offsets.into()),
+        ));
+        obj.put(DataElement::new(
+            tags::EXTENDED_OFFSET_TABLE_LENGTHS,
+            VR::OV,
+            PrimitiveValue::U64(self.lengths.into()),
+        ));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,4 +227,34 @@ mod tests {
             unreachable!("encapsulate should always return a PixelSequence");
         }
     }
+
+    #[test]
+    fn test_encapsulate_with_extended_offset_table() {
+        let (enc, offset_table) =
+            encapsulate_with_extended_offset_table(vec![vec![20, 30, 40], vec![50, 60, 70, 80]]);
+        assert!(enc.offset_table().is_empty());
+        assert_eq!(enc.fragments().len(), 2);
+        assert_eq!(enc.fragments()[0], vec![20, 30, 40, 0]);
+        assert_eq!(offset_table.offsets(), &[0, 12]);
+        assert_eq!(offset_table.lengths(), &[4, 4]);
+        assert_eq!(offset_table.to_basic_offset_table(), Some(vec![0, 12]));
+
+        let mut obj = InMemDicomObject::new_empty();
+        offset_table.put_into(&mut obj);
+        assert_eq!(
+            obj.get(tags::EXTENDED_OFFSET_TABLE)
+                .unwrap()
+                .to_multi_int::<u64>()
+                .unwrap(),
+            vec![0, 12],
+        );
+    }
+
+    #[test]
+    fn test_extended_offset_table_beyond_4gib() {
+        // three frames of 3 GiB each
+        let offset_table = ExtendedOffsetTable::from_frame_lengths(vec![3 << 30; 3]);
+        assert_eq!(offset_table.offsets(), &[0, (3 << 30) + 8, (6 << 30) + 16]);
+        assert_eq!(offset_table.to_basic_offset_table(), None);
+    }
 }
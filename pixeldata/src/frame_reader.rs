@@ -0,0 +1,818 @@
+//! Frame-level random access to pixel data in DICOM files.
+//!
+//! See [`FrameReader`] for more information.
+use crate::PixelDataKind;
+use byteorder::{ByteOrder, LittleEndian};
+use dicom_core::Tag;
+use dicom_dictionary_std::tags;
+use dicom_encoding::adapters::{FrameIndex, PixelDataObject, RawPixelData};
+use dicom_encoding::text::SpecificCharacterSet;
+use dicom_encoding::transfer_syntax::{Endianness, TransferSyntaxIndex};
+use dicom_encoding::{Codec, TransferSyntax};
+use dicom_object::{DefaultDicomObject, FileMetaTable, OpenFileOptions};
+use dicom_parser::dataset::lazy_read::LazyDataSetReader;
+use dicom_parser::dataset::LazyDataToken;
+use dicom_parser::StatefulDecode;
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+#[cfg(not(feature = "gdcm"))]
+use crate::DecodedPixelData;
+
+/// An error occurred while reading frames from a DICOM file.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Could not read the DICOM object
+    ReadObject {
+        #[snafu(source(from(dicom_object::ReadError, Box::new)))]
+        source: Box<dicom_object::ReadError>,
+    },
+
+    /// Could not read the file meta group
+    ReadMetaGroup {
+        #[snafu(source(from(dicom_object::meta::Error, Box::new)))]
+        source: Box<dicom_object::meta::Error>,
+    },
+
+    /// Could not read from the data source
+    ReadSource { source: std::io::Error },
+
+    /// Could not parse the data set
+    ParseDataSet {
+        source: dicom_parser::dataset::lazy_read::Error,
+    },
+
+    /// Could not skip data element value
+    SkipValue {
+        source: dicom_parser::stateful::decode::Error,
+    },
+
+    /// Unsupported transfer syntax `{ts_uid}`
+    UnsupportedTransferSyntax { ts_uid: String },
+
+    /// Missing pixel data
+    MissingPixelData,
+
+    /// Missing required attribute `{name}`
+    MissingAttribute { name: &'static str },
+
+    /// Invalid pixel data item at {position} bytes
+    InvalidItem { position: u64 },
+
+    /// Could not determine the fragments of each frame
+    LocateFrames,
+
+    /// Frame #{frame} is out of range
+    FrameOutOfRange { frame: u32 },
+
+    /// Could not decode pixel data
+    DecodePixelData { source: crate::Error },
+}
+
+/// Alias for the result of reading frames from a DICOM file.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The position of a pixel data fragment in the data source.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Fragment {
+    /// the byte position of the fragment value
+    position: u64,
+    /// the length of the fragment value in bytes
+    len: u32,
+}
+
+/// The layout of the pixel data in the data source.
+#[derive(Debug, Clone, PartialEq)]
+enum PixelDataLayout {
+    /// native pixel data,
+    /// with all frames contiguous in a single value
+    Native {
+        /// the byte position of the pixel data value
+        position: u64,
+        /// the length of the pixel data value in bytes
+        len: u64,
+        /// the pixel data attribute holding the value
+        kind: PixelDataKind,
+    },
+    /// encapsulated pixel data
+    Encapsulated {
+        /// the fragments of the pixel data,
+        /// excluding the basic offset table
+        fragments: Vec<Fragment>,
+        /// the fragments making up each frame
+        index: FrameIndex,
+    },
+}
+
+/// A reader of individual pixel data frames from a DICOM file,
+/// without loading the pixel data of the other frames.
+///
+/// Upon construction,
+/// the attributes preceding the _Pixel Data_
+/// (or _Float Pixel Data_ or _Double Float Pixel Data_)
+/// are read into memory,
+/// and the position of each frame is determined
+/// by skipping through the pixel data fragments.
+/// In encapsulated pixel data,
+/// frames are located using the _Extended Offset Table_
+/// or the _Basic Offset Table_,
+/// falling back to indexing the fragments of each frame
+/// when neither is available
+/// (see [`FrameIndex`] for the full set of rules).
+/// Only the bytes of the requested frame are then read from the source.
+///
+/// The data set must be encoded in a little endian transfer syntax
+/// which is not deflated.
+///
+/// # Example
+///
+/// ```no_run
+/// use dicom_pixeldata::FrameReader;
+///
+/// let mut reader = FrameReader::open_file("cine.dcm")?;
+/// let last_frame = reader.number_of_frames() - 1;
+/// let frame = reader.decode_frame(last_frame)?;
+/// let values = frame.to_vec::<u16>()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    /// the data source
+    source: R,
+    /// the DICOM object without the pixel data
+    obj: DefaultDicomObject,
+    /// where to find the pixel data in the source
+    layout: PixelDataLayout,
+}
+
+impl FrameReader<BufReader<File>> {
+    /// Open the DICOM file at the given path for reading frames.
+    pub fn open_file<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path).context(ReadSourceSnafu)?;
+        Self::from_reader(BufReader::new(file))
+    }
+}
+
+impl<R> FrameReader<R>
+where
+    R: Read + Seek,
+{
+    /// Prepare the DICOM file in the given data source for reading frames.
+    ///
+    /// The source is expected to contain the full DICOM file,
+    /// including the file meta group
+    /// and optionally the 128-byte preamble.
+    pub fn from_reader(mut source: R) -> Result<Self> {
+        source.rewind().context(ReadSourceSnafu)?;
+        let obj = OpenFileOptions::new()
+            // stop at any of the pixel data attributes
+            .read_until(tags::FLOAT_PIXEL_DATA)
+            .from_reader(&mut source)
+            .context(ReadObjectSnafu)?;
+
+        let ts_uid = obj.meta().transfer_syntax();
+        let ts = TransferSyntaxRegistry
+            .get(ts_uid)
+            .filter(|ts| {
+                ts.endianness() == Endianness::Little
+                    && matches!(ts.codec(), Codec::None | Codec::EncapsulatedPixelData(..))
+            })
+            .with_context(|| UnsupportedTransferSyntaxSnafu { ts_uid })?;
+
+        let position = data_set_position(&mut source)?;
+        source
+            .seek(SeekFrom::Start(position))
+            .context(ReadSourceSnafu)?;
+
+        let layout = match locate_pixel_data(&mut source, ts)? {
+            PixelDataPosition::Native {
+                position,
+                len,
+                kind,
+            } => PixelDataLayout::Native {
+                position,
+                len,
+                kind,
+            },
+            PixelDataPosition::Encapsulated { position } => {
+                let (basic_offset_table, fragments) = read_fragments(&mut source, position)?;
+                let offset_table: Vec<u64> = match obj.extended_offset_table() {
+                    Some(table) if !table.is_empty() => table.into_owned(),
+                    _ => basic_offset_table.into_iter().map(u64::from).collect(),
+                };
+                let fragment_lengths: Vec<u32> = fragments.iter().map(|f| f.len).collect();
+
+                let mut read_error = None;
+                let index = FrameIndex::new(
+                    obj.number_of_frames().unwrap_or(1),
+                    &fragment_lengths,
+                    &offset_table,
+                    |i| match read_fragment_end(&mut source, fragments[i]) {
+                        Ok(end) => FrameIndex::ends_with_eoi_marker(&end),
+                        Err(e) => {
+                            read_error.get_or_insert(e);
+                            false
+                        }
+                    },
+                );
+                if let Some(e) = read_error {
+                    return Err(e).context(ReadSourceSnafu)?;
+                }
+                let index = index.context(LocateFramesSnafu)?;
+                PixelDataLayout::Encapsulated { fragments, index }
+            }
+        };
+
+        Ok(FrameReader {
+            source,
+            obj,
+            layout,
+        })
+    }
+
+    /// Retrieve the DICOM object read from the source,
+    /// which contains all attributes preceding the _Pixel Data_.
+    pub fn object(&self) -> &DefaultDicomObject {
+        &self.obj
+    }
+
+    /// Get the number of frames available.
+    pub fn number_of_frames(&self) -> u32 {
+        match &self.layout {
+            PixelDataLayout::Native { .. } => self.obj.number_of_frames().unwrap_or(1),
+            PixelDataLayout::Encapsulated { index, .. } => index.len() as u32,
+        }
+    }
+
+    /// Read the pixel data of a single frame by index
+    /// (where 0 is the first frame),
+    /// as it is encoded in the data source.
+    ///
+    /// For encapsulated pixel data,
+    /// the fragments making up the frame are concatenated.
+    pub fn read_frame_data(&mut self, frame: u32) -> Result<Vec<u8>> {
+        ensure!(
+            frame < self.number_of_frames(),
+            FrameOutOfRangeSnafu { frame }
+        );
+
+        match &self.layout {
+            PixelDataLayout::Native { position, len, .. } => {
+                // frames of single bit samples are not byte aligned
+                let frame_bits = native_frame_bits(&self.obj)?;
+                let start_bit = frame_bits * u64::from(frame);
+                let end_bit = start_bit + frame_bits;
+                ensure!(end_bit <= *len * 8, FrameOutOfRangeSnafu { frame });
+                let start = start_bit / 8;
+                let end = (end_bit + 7) / 8;
+
+                let mut data = vec![0; (end - start) as usize];
+                self.source
+                    .seek(SeekFrom::Start(position + start))
+                    .context(ReadSourceSnafu)?;
+                self.source.read_exact(&mut data).context(ReadSourceSnafu)?;
+                Ok(align_bits(data, (start_bit % 8) as u32, frame_bits))
+            }
+            PixelDataLayout::Encapsulated { fragments, index } => {
+                let range = index
+                    .fragments(frame)
+                    .context(FrameOutOfRangeSnafu { frame })?;
+                let fragments = &fragments[range];
+                let mut data = Vec::with_capacity(
+                    fragments.iter().map(|fragment| fragment.len as usize).sum(),
+                );
+                for fragment in fragments {
+                    self.source
+                        .seek(SeekFrom::Start(fragment.position))
+                        .context(ReadSourceSnafu)?;
+                    (&mut self.source)
+                        .take(u64::from(fragment.len))
+                        .read_to_end(&mut data)
+                        .context(ReadSourceSnafu)?;
+                }
+                Ok(data)
+            }
+        }
+    }
+
+    /// Read and decode a single frame by index
+    /// (where 0 is the first frame).
+    ///
+    /// This is equivalent to
+    /// [`decode_pixel_data_frame`](crate::PixelDecoder::decode_pixel_data_frame)
+    /// on the fully loaded object,
+    /// but only the bytes of the requested frame are read.
+    #[cfg(not(feature = "gdcm"))]
+    pub fn decode_frame(&mut self, frame: u32) -> Result<DecodedPixelData<'static>> {
+        let data = self.read_frame_data(frame)?;
+        let obj = &self.obj;
+        match self.layout {
+            PixelDataLayout::Native { kind, .. } => {
+                let view = FrameView {
+                    obj,
+                    frame,
+                    data: &[],
+                };
+                crate::decode_pixel_data_frame_from(obj, &view, kind, frame, |_| Ok(data))
+            }
+            PixelDataLayout::Encapsulated { .. } => {
+                let view = FrameView {
+                    obj,
+                    frame,
+                    data: &data,
+                };
+                crate::decode_pixel_data_frame_from(
+                    obj,
+                    &view,
+                    PixelDataKind::Integer,
+                    frame,
+                    |_| Ok(data.clone()),
+                )
+            }
+        }
+        .context(DecodePixelDataSnafu)
+        .map_err(Error::from)
+    }
+
+    /// Recover the underlying data source.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+/// A view of a single frame of encapsulated pixel data,
+/// for feeding to a pixel data decoder.
+///
+/// All attributes are taken from the original object,
+/// whereas the only pixel data available
+/// is the one of the frame already read.
+#[cfg(not(feature = "gdcm"))]
+struct FrameView<'a> {
+    obj: &'a DefaultDicomObject,
+    frame: u32,
+    data: &'a [u8],
+}
+
+#[cfg(not(feature = "gdcm"))]
+impl PixelDataObject for FrameView<'_> {
+    fn transfer_syntax_uid(&self) -> &str {
+        self.obj.transfer_syntax_uid()
+    }
+
+    fn rows(&self) -> Option<u16> {
+        self.obj.rows()
+    }
+
+    fn cols(&self) -> Option<u16> {
+        self.obj.cols()
+    }
+
+    fn samples_per_pixel(&self) -> Option<u16> {
+        self.obj.samples_per_pixel()
+    }
+
+    fn bits_allocated(&self) -> Option<u16> {
+        self.obj.bits_allocated()
+    }
+
+    fn bits_stored(&self) -> Option<u16> {
+        self.obj.bits_stored()
+    }
+
+    fn photometric_interpretation(&self) -> Option<&str> {
+        self.obj.photometric_interpretation()
+    }
+
+    fn pixel_representation(&self) -> Option<u16> {
+        self.obj.pixel_representation()
+    }
+
+    fn planar_configuration(&self) -> Option<u16> {
+        self.obj.planar_configuration()
+    }
+
+    fn number_of_frames(&self) -> Option<u32> {
+        self.obj.number_of_frames()
+    }
+
+    fn number_of_fragments(&self) -> Option<u32> {
+        None
+    }
+
+    fn fragment(&self, _fragment: usize) -> Option<Cow<'_, [u8]>> {
+        None
+    }
+
+    fn offset_table(&self) -> Option<Cow<'_, [u32]>> {
+        None
+    }
+
+    fn frame_data(&self, frame: u32) -> Option<Cow<'_, [u8]>> {
+        if frame == self.frame {
+            Some(Cow::Borrowed(self.data))
+        } else {
+            None
+        }
+    }
+
+    fn raw_pixel_data(&self) -> Option<RawPixelData> {
+        None
+    }
+}
+
+/// The position of the pixel data element in the data source.
+enum PixelDataPosition {
+    /// native pixel data value
+    Native {
+        position: u64,
+        len: u64,
+        kind: PixelDataKind,
+    },
+    /// encapsulated pixel data,
+    /// with the position of the first item (the basic offset table)
+    Encapsulated { position: u64 },
+}
+
+/// Determine the byte position of the main data set,
+/// right after the file meta group.
+fn data_set_position<R>(source: &mut R) -> Result<u64>
+where
+    R: Read + Seek,
+{
+    // detect the 128-byte preamble
+    let mut magic = [0; 4];
+    source.seek(SeekFrom::Start(128)).context(ReadSourceSnafu)?;
+    let start = match source.read_exact(&mut magic) {
+        Ok(()) if &magic == b"DICM" => 128,
+        _ => 0,
+    };
+
+    source
+        .seek(SeekFrom::Start(start))
+        .context(ReadSourceSnafu)?;
+    FileMetaTable::from_reader(&mut *source).context(ReadMetaGroupSnafu)?;
+    Ok(source.stream_position().context(ReadSourceSnafu)?)
+}
+
+/// Skip through the data set until the top-level pixel data element,
+/// which may also be _Float Pixel Data_ or _Double Float Pixel Data_.
+fn locate_pixel_data<R>(source: &mut R, ts: &TransferSyntax) -> Result<PixelDataPosition>
+where
+    R: Read + Seek,
+{
+    let mut reader = LazyDataSetReader::new_with_ts_cs(
+        BufReader::new(source),
+        ts,
+        SpecificCharacterSet::default(),
+    )
+    .context(ParseDataSetSnafu)?;
+
+    let mut depth = 0;
+    while let Some(token) = reader.advance() {
+        match token.context(ParseDataSetSnafu)? {
+            LazyDataToken::PixelSequenceStart if depth == 0 => {
+                // the item reader is now right before the offset table
+                let position = reader.into_decoder().position();
+                return Ok(PixelDataPosition::Encapsulated { position });
+            }
+            LazyDataToken::LazyValue { header, decoder } if depth == 0 => {
+                let kind = match header.tag {
+                    tags::PIXEL_DATA => PixelDataKind::Integer,
+                    tags::FLOAT_PIXEL_DATA => PixelDataKind::Float,
+                    tags::DOUBLE_FLOAT_PIXEL_DATA => PixelDataKind::DoubleFloat,
+                    _ => {
+                        LazyDataToken::LazyValue { header, decoder }
+                            .skip()
+                            .context(SkipValueSnafu)?;
+                        continue;
+                    }
+                };
+                return Ok(PixelDataPosition::Native {
+                    position: decoder.position(),
+                    len: u64::from(header.len.0),
+                    kind,
+                });
+            }
+            LazyDataToken::SequenceStart { .. } | LazyDataToken::PixelSequenceStart => {
+                depth += 1;
+            }
+            LazyDataToken::SequenceEnd => {
+                depth -= 1;
+            }
+            token => token.skip().context(SkipValueSnafu)?,
+        }
+    }
+
+    MissingPixelDataSnafu.fail()?
+}
+
+/// Read the basic offset table
+/// and the position of each pixel data fragment,
+/// starting from the first item of the encapsulated pixel data.
+fn read_fragments<R>(source: &mut R, mut position: u64) -> Result<(Vec<u32>, Vec<Fragment>)>
+where
+    R: Read + Seek,
+{
+    source
+        .seek(SeekFrom::Start(position))
+        .context(ReadSourceSnafu)?;
+
+    let mut offset_table = None;
+    let mut fragments = Vec::new();
+    loop {
+        let mut header = [0; 8];
+        source.read_exact(&mut header).context(ReadSourceSnafu)?;
+        let tag = Tag(
+            LittleEndian::read_u16(&header[0..2]),
+            LittleEndian::read_u16(&header[2..4]),
+        );
+        let len = LittleEndian::read_u32(&header[4..8]);
+
+        match tag {
+            Tag(0xFFFE, 0xE0DD) => break,
+            Tag(0xFFFE, 0xE000) if len != u32::MAX => {
+                position += 8;
+                if offset_table.is_none() {
+                    let mut data = vec![0; len as usize];
+                    source.read_exact(&mut data).context(ReadSourceSnafu)?;
+                    offset_table = Some(data.chunks_exact(4).map(LittleEndian::read_u32).collect());
+                } else {
+                    fragments.push(Fragment { position, len });
+                    source
+                        .seek(SeekFrom::Current(i64::from(len)))
+                        .context(ReadSourceSnafu)?;
+                }
+                position += u64::from(len);
+            }
+            _ => InvalidItemSnafu { position }.fail()?,
+        }
+    }
+
+    Ok((offset_table.unwrap_or_default(), fragments))
+}
+
+/// Read the last few bytes of a fragment,
+/// enough to look for an end of image marker.
+fn read_fragment_end<R>(source: &mut R, fragment: Fragment) -> std::io::Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let len = fragment.len.min(3);
+    source.seek(SeekFrom::Start(
+        fragment.position + u64::from(fragment.len - len),
+    ))?;
+    let mut data = vec![0; len as usize];
+    source.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Calculate the size in bits of a single frame of native pixel data.
+fn native_frame_bits(obj: &DefaultDicomObject) -> Result<u64> {
+    let rows = obj.rows().context(MissingAttributeSnafu { name: "Rows" })?;
+    let cols = obj
+        .cols()
+        .context(MissingAttributeSnafu { name: "Columns" })?;
+    let samples_per_pixel = obj.samples_per_pixel().context(MissingAttributeSnafu {
+        name: "SamplesPerPixel",
+    })?;
+    let bits_allocated = obj.bits_allocated().context(MissingAttributeSnafu {
+        name: "BitsAllocated",
+    })?;
+
+    Ok(
+        u64::from(bits_allocated)
+            * u64::from(samples_per_pixel)
+            * u64::from(rows)
+            * u64::from(cols),
+    )
+}
+
+/// Take `len` bits from the given data,
+/// starting at the given bit of the first byte,
+/// so that they start at the first bit of the output.
+///
+/// Bits are packed from the least significant bit of each byte.
+/// Unused bits in the last byte of the output are cleared.
+fn align_bits(mut data: Vec<u8>, first_bit: u32, len: u64) -> Vec<u8> {
+    if first_bit > 0 {
+        for i in 0..data.len() {
+            let next = data.get(i + 1).copied().unwrap_or(0);
+            data[i] = (data[i] >> first_bit) | (next << (8 - first_bit));
+        }
+    }
+    data.truncate(((len + 7) / 8) as usize);
+    if len % 8 != 0 {
+        if let Some(last) = data.last_mut() {
+            *last &= (1 << (len % 8)) - 1;
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PixelDecoder;
+    use dicom_core::value::{DataSetSequence, PixelFragmentSequence};
+    use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::uids;
+    use dicom_object::{FileDicomObject, FileMetaTableBuilder, InMemDicomObject};
+    use std::io::Cursor;
+
+    /// Create an object with 3 frames of 2x2 8-bit samples,
+    /// without the pixel data.
+    fn multi_frame_dicom(ts: &str) -> DefaultDicomObject {
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(ts)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.94766187067244888884745908966163363746")
+                .build()
+                .unwrap(),
+        );
+        for (tag, value) in [
+            (tags::ROWS, 2),
+            (tags::COLUMNS, 2),
+            (tags::SAMPLES_PER_PIXEL, 1),
+            (tags::BITS_ALLOCATED, 8),
+            (tags::BITS_STORED, 8),
+            (tags::HIGH_BIT, 7),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ]
+        .iter()
+        {
+            obj.put(DataElement::new(*tag, VR::US, dicom_value!(U16, *value)));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            dicom_value!(Str, "MONOCHROME2"),
+        ));
+        obj.put(DataElement::new(
+            tags::NUMBER_OF_FRAMES,
+            VR::IS,
+            dicom_value!(Str, "3"),
+        ));
+        // nested pixel data which should not be mistaken for the main one
+        obj.put(DataElement::new(
+            tags::ICON_IMAGE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::PIXEL_DATA,
+                    VR::OB,
+                    dicom_value!(U8, [0xFF, 0xFF, 0xFF, 0xFF]),
+                ),
+            ])]),
+        ));
+        obj
+    }
+
+    fn frame_reader(obj: &DefaultDicomObject) -> FrameReader<Cursor<Vec<u8>>> {
+        let mut data = Vec::new();
+        obj.write_all(&mut data).unwrap();
+        FrameReader::from_reader(Cursor::new(data)).unwrap()
+    }
+
+    #[test]
+    fn read_native_frames() {
+        let mut obj = multi_frame_dicom(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::U8((0..12).collect()),
+        ));
+
+        let mut reader = frame_reader(&obj);
+        assert_eq!(reader.number_of_frames(), 3);
+        assert_eq!(reader.object().element(tags::PIXEL_DATA).ok(), None);
+        assert_eq!(reader.read_frame_data(1).unwrap(), vec![4, 5, 6, 7]);
+        assert_eq!(reader.read_frame_data(0).unwrap(), vec![0, 1, 2, 3]);
+        assert!(reader.read_frame_data(3).is_err());
+
+        let decoded = reader.decode_frame(2).unwrap();
+        assert_eq!(decoded.number_of_frames(), 1);
+        assert_eq!(decoded.to_vec::<u8>().unwrap(), vec![8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn read_encapsulated_frames_with_offset_table() {
+        let mut obj = multi_frame_dicom(uids::ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN);
+        // the second frame is split in two fragments
+        let fragments = vec![vec![0, 1, 2, 3], vec![4, 5], vec![6, 7], vec![8, 9, 10, 11]];
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PixelFragmentSequence::new(vec![0, 12, 32], fragments),
+        ));
+
+        let mut reader = frame_reader(&obj);
+        assert_eq!(reader.number_of_frames(), 3);
+        assert_eq!(reader.read_frame_data(1).unwrap(), vec![4, 5, 6, 7]);
+        assert_eq!(reader.read_frame_data(2).unwrap(), vec![8, 9, 10, 11]);
+        assert!(reader.read_frame_data(3).is_err());
+
+        let decoded = reader.decode_frame(1).unwrap();
+        assert_eq!(decoded.to_vec::<u8>().unwrap(), vec![4, 5, 6, 7]);
+
+        // same outcome as decoding the frame from memory
+        let decoded = obj.decode_pixel_data_frame(1).unwrap();
+        assert_eq!(decoded.to_vec::<u8>().unwrap(), vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn read_encapsulated_frames_with_extended_offset_table() {
+        let mut obj = multi_frame_dicom(uids::ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN);
+        obj.put(DataElement::new(
+            tags::EXTENDED_OFFSET_TABLE,
+            VR::OV,
+            dicom_value!(U64, [0, 20, 32]),
+        ));
+        // the first frame is split in two fragments,
+        // and there is no basic offset table
+        let fragments = vec![vec![0, 1], vec![2, 3], vec![4, 5, 6, 7], vec![8, 9, 10, 11]];
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PixelFragmentSequence::new_fragments(fragments),
+        ));
+
+        let mut reader = frame_reader(&obj);
+        assert_eq!(reader.number_of_frames(), 3);
+        assert_eq!(reader.read_frame_data(0).unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(reader.read_frame_data(2).unwrap(), vec![8, 9, 10, 11]);
+
+        let decoded = reader.decode_frame(0).unwrap();
+        assert_eq!(decoded.to_vec::<u8>().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn read_single_bit_frames() {
+        let mut obj = multi_frame_dicom(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        for (tag, value) in [
+            (tags::BITS_ALLOCATED, 1),
+            (tags::BITS_STORED, 1),
+            (tags::HIGH_BIT, 0),
+        ] {
+            obj.put(DataElement::new(tag, VR::US, dicom_value!(U16, value)));
+        }
+        // 4 bits per frame, so the second frame starts mid-byte
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            dicom_value!(U8, [0b1010_0110, 0b1111_1001]),
+        ));
+
+        let mut reader = frame_reader(&obj);
+        assert_eq!(reader.read_frame_data(0).unwrap(), vec![0b0110]);
+        assert_eq!(reader.read_frame_data(1).unwrap(), vec![0b1010]);
+        assert_eq!(reader.read_frame_data(2).unwrap(), vec![0b1001]);
+        assert!(reader.read_frame_data(3).is_err());
+    }
+
+    #[test]
+    fn read_float_frames() {
+        let mut obj = multi_frame_dicom(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        for tag in [
+            tags::BITS_STORED,
+            tags::HIGH_BIT,
+            tags::PIXEL_REPRESENTATION,
+        ] {
+            obj.remove_element(tag);
+        }
+        obj.put(DataElement::new(
+            tags::BITS_ALLOCATED,
+            VR::US,
+            dicom_value!(U16, 32),
+        ));
+        let values: Vec<f32> = (0..12).map(|v| v as f32 * 0.5).collect();
+        obj.put(DataElement::new(
+            tags::FLOAT_PIXEL_DATA,
+            VR::OF,
+            PrimitiveValue::F32(values.into()),
+        ));
+
+        let mut reader = frame_reader(&obj);
+        assert_eq!(reader.object().element(tags::FLOAT_PIXEL_DATA).ok(), None);
+        assert_eq!(reader.number_of_frames(), 3);
+        assert_eq!(reader.read_frame_data(1).unwrap().len(), 16);
+
+        let decoded = reader.decode_frame(1).unwrap();
+        assert_eq!(decoded.pixel_data_kind(), PixelDataKind::Float);
+        assert_eq!(decoded.to_vec::<f32>().unwrap(), vec![2., 2.5, 3., 3.5]);
+
+        // same outcome as decoding the frame from memory
+        let decoded = obj.decode_pixel_data_frame(1).unwrap();
+        assert_eq!(decoded.to_vec::<f32>().unwrap(), vec![2., 2.5, 3., 3.5]);
+    }
+}
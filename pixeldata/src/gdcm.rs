@@ -163,6 +163,26 @@ where
             None
         };
 
+        // without a palette color look-up table,
+        // the pixel data can still be read as is
+        let palette = if photometric_interpretation == PhotometricInterpretation::PaletteColor {
+            palette_color_lut(self).map(Some).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid palette color look-up table: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+        let modality_lut = modality_lut(self).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid Modality LUT Sequence: {}", e);
+            Vec::new()
+        });
+        let voi_luts = voi_luts(self).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid VOI LUT Sequence: {}", e);
+            Vec::new()
+        });
+        let overlays = overlays(self);
+
         Ok(DecodedPixelData {
             data: Cow::from(decoded_pixel_data),
             cols: cols.into(),
@@ -175,11 +195,17 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            pixel_data_kind: pixel_data_kind(self),
             rescale,
             voi_lut_function,
             window,
+            modality_lut,
+            voi_luts,
+            palette,
+            overlays,
             enforce_frame_fg_vm_match: false,
-        })
+        }
+        .with_embedded_overlays())
     }
 
     fn decode_pixel_data_frame(&self, frame: u32) -> Result<DecodedPixelData<'_>> {
@@ -341,6 +367,41 @@ where
             })
             .collect();
 
+        // without a palette color look-up table,
+        // the pixel data can still be read as is
+        let palette = if photometric_interpretation == PhotometricInterpretation::PaletteColor {
+            palette_color_lut(self).map(Some).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid palette color look-up table: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+        let modality_lut = modality_lut(self).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid Modality LUT Sequence: {}", e);
+            Vec::new()
+        });
+        let voi_luts = voi_luts(self).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid VOI LUT Sequence: {}", e);
+            Vec::new()
+        });
+        let modality_lut = modality_lut
+            .get(frame as usize)
+            .or(modality_lut.first())
+            .cloned()
+            .into_iter()
+            .collect();
+        let voi_luts = voi_luts
+            .get(frame as usize)
+            .or(voi_luts.first())
+            .cloned()
+            .into_iter()
+            .collect();
+        let overlays = overlays(self)
+            .into_iter()
+            .filter_map(|overlay| overlay.select_frame(frame))
+            .collect();
+
         Ok(DecodedPixelData {
             data: Cow::from(decoded_pixel_data),
             cols: cols.into(),
@@ -353,11 +414,17 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            pixel_data_kind: pixel_data_kind(self),
             rescale: rescale,
             voi_lut_function,
             window,
+            modality_lut,
+            voi_luts,
+            palette,
+            overlays,
             enforce_frame_fg_vm_match: false,
-        })
+        }
+        .with_embedded_overlays())
     }
 }
 
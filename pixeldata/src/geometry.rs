@@ -0,0 +1,561 @@
+//! Image plane geometry in the patient coordinate system.
+//!
+//! This module maps pixel positions to the patient coordinate system
+//! from the _Image Plane_ module attributes
+//! (see [section C.7.6.2][1] of the standard):
+//! _Image Position (Patient)_, _Image Orientation (Patient)_
+//! and _Pixel Spacing_.
+//! In enhanced multi-frame objects,
+//! these attributes are read per frame
+//! from the _Per-frame_ or _Shared Functional Groups Sequence_.
+//!
+//! [`ImagePlane`] describes the plane of a single frame,
+//! with its pixel to patient affine transform and its normal.
+//! [`slice_order`] and [`slice_spacing`]
+//! help assembling several planes into a volume,
+//! by sorting them along their normal
+//! and detecting missing or duplicate slices.
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_C.7.6.2.html
+
+use crate::frame_attribute;
+use dicom_core::value::ConvertValueError;
+use dicom_core::DataDictionary;
+use dicom_dictionary_std::tags;
+use dicom_object::mem::InMemElement;
+use dicom_object::InMemDicomObject;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use std::convert::TryFrom;
+
+/// An error occurred while reading or comparing image planes.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Missing attribute `{name}`
+    MissingAttribute { name: &'static str },
+
+    /// Could not convert the value of attribute `{name}`
+    ConvertValue {
+        name: &'static str,
+        source: ConvertValueError,
+    },
+
+    /// Invalid value of attribute `{name}`: {value}
+    InvalidValue { name: &'static str, value: String },
+
+    /// Frame #{frame} is out of range ({number_of_frames} frames)
+    FrameOutOfRange { frame: u32, number_of_frames: u32 },
+
+    /// Expected at least 2 image planes, got {count}
+    NotEnoughPlanes { count: usize },
+
+    /// Image plane #{index} is not parallel to the first image plane
+    NotParallel { index: usize },
+}
+
+/// Alias for the result of reading or comparing image planes.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A 3D vector or point in the patient coordinate system, in millimeters.
+pub type Vector3 = [f64; 3];
+
+/// A 4x4 affine transform matrix, in row-major order.
+pub type Affine = [[f64; 4]; 4];
+
+/// The plane of an image frame in the patient coordinate system.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImagePlane {
+    /// the position of the center of the first transmitted pixel
+    /// (top left hand corner)
+    pub position: Vector3,
+    /// the direction cosines of the first row,
+    /// along which the column index increases
+    pub row_direction: Vector3,
+    /// the direction cosines of the first column,
+    /// along which the row index increases
+    pub column_direction: Vector3,
+    /// the physical distance between the centers of adjacent pixels,
+    /// as `[row spacing, column spacing]`
+    /// (the same order as in _Pixel Spacing_)
+    pub pixel_spacing: [f64; 2],
+}
+
+impl ImagePlane {
+    /// Read the plane of the given frame (starting at 0) of an image.
+    ///
+    /// Each attribute is retrieved from the frame's item
+    /// in the _Per-frame Functional Groups Sequence_,
+    /// then from the _Shared Functional Groups Sequence_,
+    /// then from the top level of the object.
+    /// _Imager Pixel Spacing_ is used
+    /// when _Pixel Spacing_ is not available.
+    pub fn from_obj<D>(obj: &InMemDicomObject<D>, frame: u32) -> Result<Self>
+    where
+        D: DataDictionary + Clone,
+    {
+        let number_of_frames = number_of_frames(obj)?;
+        ensure!(
+            frame < number_of_frames,
+            FrameOutOfRangeSnafu {
+                frame,
+                number_of_frames,
+            }
+        );
+
+        let position = floats::<_, 3>(
+            frame_attribute(obj, frame, tags::IMAGE_POSITION_PATIENT),
+            "ImagePositionPatient",
+        )?;
+        let orientation = floats::<_, 6>(
+            frame_attribute(obj, frame, tags::IMAGE_ORIENTATION_PATIENT),
+            "ImageOrientationPatient",
+        )?;
+        let pixel_spacing = match frame_attribute(obj, frame, tags::PIXEL_SPACING) {
+            Some(e) => floats::<_, 2>(Some(e), "PixelSpacing")?,
+            None => floats::<_, 2>(obj.get(tags::IMAGER_PIXEL_SPACING), "ImagerPixelSpacing")
+                .map_err(|_| {
+                    Error(InnerError::MissingAttribute {
+                        name: "PixelSpacing",
+                    })
+                })?,
+        };
+        ensure!(
+            pixel_spacing.iter().all(|&v| v > 0.),
+            InvalidValueSnafu {
+                name: "PixelSpacing",
+                value: format!("{:?}", pixel_spacing),
+            }
+        );
+
+        let row_direction = normalize([orientation[0], orientation[1], orientation[2]]);
+        let column_direction = normalize([orientation[3], orientation[4], orientation[5]]);
+        let (row_direction, column_direction) = row_direction
+            .zip(column_direction)
+            .filter(|(r, c)| norm(cross(*r, *c)) > 1e-6)
+            .with_context(|| InvalidValueSnafu {
+                name: "ImageOrientationPatient",
+                value: format!("{:?}", orientation),
+            })?;
+
+        Ok(ImagePlane {
+            position,
+            row_direction,
+            column_direction,
+            pixel_spacing,
+        })
+    }
+
+    /// The unit normal of the plane,
+    /// the cross product of the row and column directions.
+    pub fn normal(&self) -> Vector3 {
+        normalize(cross(self.row_direction, self.column_direction)).unwrap_or([0., 0., 0.])
+    }
+
+    /// The affine transform from pixel indices
+    /// `(column, row, slice, 1)` to patient coordinates `(x, y, z, 1)`,
+    /// with the slice axis along the plane normal
+    /// scaled by the given slice spacing.
+    ///
+    /// Use a slice spacing of 1 for the transform of a single frame,
+    /// or the result of [`slice_spacing`]
+    /// for the first plane of a sorted volume.
+    pub fn affine(&self, slice_spacing: f64) -> Affine {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        let r = self.row_direction;
+        let c = self.column_direction;
+        let n = self.normal();
+        let p = self.position;
+        [
+            [
+                r[0] * column_spacing,
+                c[0] * row_spacing,
+                n[0] * slice_spacing,
+                p[0],
+            ],
+            [
+                r[1] * column_spacing,
+                c[1] * row_spacing,
+                n[1] * slice_spacing,
+                p[1],
+            ],
+            [
+                r[2] * column_spacing,
+                c[2] * row_spacing,
+                n[2] * slice_spacing,
+                p[2],
+            ],
+            [0., 0., 0., 1.],
+        ]
+    }
+
+    /// Map a pixel position, as fractional column and row indices,
+    /// to a point in the patient coordinate system.
+    pub fn pixel_to_patient(&self, column: f64, row: f64) -> Vector3 {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        let mut point = self.position;
+        for (i, p) in point.iter_mut().enumerate() {
+            *p += self.row_direction[i] * column_spacing * column
+                + self.column_direction[i] * row_spacing * row;
+        }
+        point
+    }
+
+    /// Map a point in the patient coordinate system
+    /// to fractional column and row indices in this plane,
+    /// along with the signed distance of the point to the plane
+    /// along its normal.
+    pub fn patient_to_pixel(&self, point: Vector3) -> [f64; 3] {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        let d = sub(point, self.position);
+        // the directions are not necessarily orthogonal,
+        // so solve the linear system instead of projecting
+        let n = self.normal();
+        let det = dot(cross(self.row_direction, self.column_direction), n);
+        let column = dot(cross(d, self.column_direction), n) / det;
+        let row = dot(cross(self.row_direction, d), n) / det;
+        [column / column_spacing, row / row_spacing, dot(d, n)]
+    }
+
+    /// The signed distance of this plane's position
+    /// along the given direction,
+    /// typically the normal of the first plane of a series.
+    pub fn distance_along(&self, direction: Vector3) -> f64 {
+        dot(self.position, direction)
+    }
+
+    /// Whether this plane is parallel to another plane,
+    /// with their normals differing by at most
+    /// the given tolerance (as the norm of their cross product).
+    pub fn is_parallel_to(&self, other: &ImagePlane, tolerance: f64) -> bool {
+        norm(cross(self.normal(), other.normal())) <= tolerance
+    }
+}
+
+/// Read the planes of all frames of an image.
+///
+/// See [`ImagePlane::from_obj`] for how the attributes are retrieved.
+pub fn image_planes<D>(obj: &InMemDicomObject<D>) -> Result<Vec<ImagePlane>>
+where
+    D: DataDictionary + Clone,
+{
+    (0..number_of_frames(obj)?)
+        .map(|frame| ImagePlane::from_obj(obj, frame))
+        .collect()
+}
+
+/// Compute the order of the given image planes
+/// along the normal of the first plane,
+/// from the lowest to the highest position.
+///
+/// Returns the indices of the planes in sorted order.
+/// Planes at the same position keep their relative order.
+pub fn slice_order(planes: &[ImagePlane]) -> Vec<usize> {
+    let normal = match planes.first() {
+        Some(plane) => plane.normal(),
+        None => return Vec::new(),
+    };
+    let mut order: Vec<usize> = (0..planes.len()).collect();
+    order.sort_by(|&a, &b| {
+        planes[a]
+            .distance_along(normal)
+            .total_cmp(&planes[b].distance_along(normal))
+    });
+    order
+}
+
+/// The spacing between a set of parallel image planes.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SliceSpacing {
+    /// the most common distance between adjacent planes,
+    /// in millimeters
+    pub spacing: f64,
+    /// the sorted distances of the planes along the normal of the first plane
+    pub positions: Vec<f64>,
+    /// the indices, in sorted order, of the planes
+    /// which are followed by a gap larger than the spacing
+    pub gaps: Vec<usize>,
+    /// the indices, in sorted order, of the planes
+    /// which are at the same position as the previous plane
+    pub duplicates: Vec<usize>,
+}
+
+impl SliceSpacing {
+    /// Whether all planes are evenly spaced,
+    /// without gaps or duplicates.
+    pub fn is_uniform(&self) -> bool {
+        self.gaps.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+/// Analyze the spacing between the given image planes,
+/// in any order.
+///
+/// Two positions are considered the same
+/// if they differ by at most `tolerance` millimeters,
+/// which also applies to the comparison of normals.
+/// The planes must all be parallel to the first one.
+pub fn slice_spacing(planes: &[ImagePlane], tolerance: f64) -> Result<SliceSpacing> {
+    ensure!(
+        planes.len() >= 2,
+        NotEnoughPlanesSnafu {
+            count: planes.len()
+        }
+    );
+    let first = &planes[0];
+    if let Some(index) = planes
+        .iter()
+        .position(|plane| !plane.is_parallel_to(first, tolerance))
+    {
+        return NotParallelSnafu { index }.fail().map_err(Error::from);
+    }
+
+    let normal = first.normal();
+    let positions: Vec<f64> = slice_order(planes)
+        .into_iter()
+        .map(|i| planes[i].distance_along(normal))
+        .collect();
+
+    let duplicates: Vec<usize> = (1..positions.len())
+        .filter(|&i| positions[i] - positions[i - 1] <= tolerance)
+        .collect();
+    let mut distances: Vec<f64> = positions
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|&d| d > tolerance)
+        .collect();
+    distances.sort_by(f64::total_cmp);
+
+    // the most common distance, in clusters of distances within tolerance
+    let mut spacing = 0.;
+    let mut best = 0;
+    let mut start = 0;
+    for end in 0..distances.len() {
+        while distances[end] - distances[start] > tolerance {
+            start += 1;
+        }
+        if end - start + 1 > best {
+            best = end - start + 1;
+            spacing = distances[(start + end) / 2];
+        }
+    }
+
+    let gaps = positions
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[1] - w[0] > spacing + tolerance)
+        .map(|(i, _)| i)
+        .collect();
+
+    Ok(SliceSpacing {
+        spacing,
+        positions,
+        gaps,
+        duplicates,
+    })
+}
+
+fn number_of_frames<D>(obj: &InMemDicomObject<D>) -> Result<u32>
+where
+    D: DataDictionary + Clone,
+{
+    match obj.get(tags::NUMBER_OF_FRAMES) {
+        Some(e) => Ok(e
+            .to_int::<u32>()
+            .context(ConvertValueSnafu {
+                name: "NumberOfFrames",
+            })?
+            .max(1)),
+        None => Ok(1),
+    }
+}
+
+/// Read exactly `N` floating point values from an element.
+fn floats<D, const N: usize>(
+    element: Option<&InMemElement<D>>,
+    name: &'static str,
+) -> Result<[f64; N]>
+where
+    D: DataDictionary + Clone,
+{
+    let values = element
+        .context(MissingAttributeSnafu { name })?
+        .to_multi_float64()
+        .context(ConvertValueSnafu { name })?;
+    <[f64; N]>::try_from(&values[..])
+        .ok()
+        .filter(|values| values.iter().all(|v| v.is_finite()))
+        .with_context(|| InvalidValueSnafu {
+            name,
+            value: format!("{:?}", values),
+        })
+        .map_err(Error::from)
+}
+
+fn dot(a: Vector3, b: Vector3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vector3, b: Vector3) -> Vector3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn sub(a: Vector3, b: Vector3) -> Vector3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(a: Vector3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: Vector3) -> Option<Vector3> {
+    let n = norm(a);
+    (n > 0.).then(|| [a[0] / n, a[1] / n, a[2] / n])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{dicom_value, DataElement, PrimitiveValue, Tag, VR};
+
+    fn plane(z: f64) -> ImagePlane {
+        ImagePlane {
+            position: [-100., -120., z],
+            row_direction: [1., 0., 0.],
+            column_direction: [0., 1., 0.],
+            pixel_spacing: [0.5, 0.8],
+        }
+    }
+
+    fn ds(values: &[f64]) -> PrimitiveValue {
+        PrimitiveValue::Strs(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    fn group(sequence: Tag, tag: Tag, values: &[f64]) -> DataElement<InMemDicomObject> {
+        DataElement::new(
+            sequence,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(tag, VR::DS, ds(values)),
+            ])]),
+        )
+    }
+
+    #[test]
+    fn map_pixels_to_patient() {
+        let plane = plane(10.);
+        assert_eq!(plane.normal(), [0., 0., 1.]);
+        assert_eq!(plane.pixel_to_patient(0., 0.), [-100., -120., 10.]);
+        // columns move along x by the column spacing
+        assert_eq!(plane.pixel_to_patient(10., 4.), [-92., -118., 10.]);
+        assert_eq!(plane.patient_to_pixel([-92., -118., 12.]), [10., 4., 2.]);
+
+        let affine = plane.affine(2.);
+        assert_eq!(
+            affine,
+            [
+                [0.8, 0., 0., -100.],
+                [0., 0.5, 0., -120.],
+                [0., 0., 2., 10.],
+                [0., 0., 0., 1.],
+            ]
+        );
+    }
+
+    #[test]
+    fn read_planes_from_functional_groups() {
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"),
+            // overridden by the functional groups
+            DataElement::new(tags::PIXEL_SPACING, VR::DS, ds(&[9., 9.])),
+        ]);
+        obj.put(DataElement::new(
+            tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                group(
+                    tags::PLANE_ORIENTATION_SEQUENCE,
+                    tags::IMAGE_ORIENTATION_PATIENT,
+                    &[0., 1., 0., 0., 0., -1.],
+                ),
+                group(
+                    tags::PIXEL_MEASURES_SEQUENCE,
+                    tags::PIXEL_SPACING,
+                    &[0.5, 0.8],
+                ),
+            ])]),
+        ));
+        obj.put(DataElement::new(
+            tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(
+                [0., 3.]
+                    .iter()
+                    .map(|&x| {
+                        InMemDicomObject::from_element_iter([group(
+                            tags::PLANE_POSITION_SEQUENCE,
+                            tags::IMAGE_POSITION_PATIENT,
+                            &[x, 0., 0.],
+                        )])
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+        ));
+
+        let planes = image_planes(&obj).unwrap();
+        assert_eq!(planes.len(), 2);
+        assert_eq!(planes[1].position, [3., 0., 0.]);
+        assert_eq!(planes[1].pixel_spacing, [0.5, 0.8]);
+        // sagittal plane, with the normal towards the patient right
+        assert_eq!(planes[0].normal(), [-1., 0., 0.]);
+        assert!(ImagePlane::from_obj(&obj, 2).is_err());
+
+        // single frame image with top level attributes
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::IMAGE_POSITION_PATIENT, VR::DS, ds(&[1., 2., 3.])),
+            DataElement::new(
+                tags::IMAGE_ORIENTATION_PATIENT,
+                VR::DS,
+                ds(&[1., 0., 0., 0., 1., 0.]),
+            ),
+            DataElement::new(
+                tags::PIXEL_SPACING,
+                VR::DS,
+                dicom_value!(Strs, ["0.5", "0.5"]),
+            ),
+        ]);
+        let plane = ImagePlane::from_obj(&obj, 0).unwrap();
+        assert_eq!(plane.position, [1., 2., 3.]);
+    }
+
+    #[test]
+    fn sort_and_analyze_slices() {
+        let planes: Vec<_> = [5., 0., 2.5, 12.5, 10., 10.]
+            .iter()
+            .map(|&z| plane(z))
+            .collect();
+        assert_eq!(slice_order(&planes), vec![1, 2, 0, 4, 5, 3]);
+
+        let spacing = slice_spacing(&planes, 0.01).unwrap();
+        assert_eq!(spacing.spacing, 2.5);
+        assert_eq!(spacing.positions, vec![0., 2.5, 5., 10., 10., 12.5]);
+        assert_eq!(spacing.gaps, vec![2]);
+        assert_eq!(spacing.duplicates, vec![4]);
+        assert!(!spacing.is_uniform());
+
+        let uniform = slice_spacing(&planes[..3], 0.01).unwrap();
+        assert!(uniform.is_uniform());
+
+        let mut tilted = plane(20.);
+        tilted.column_direction = [0., 0.8, 0.6];
+        assert!(slice_spacing(&[plane(0.), tilted], 0.01).is_err());
+        assert!(slice_spacing(&planes[..1], 0.01).is_err());
+    }
+}
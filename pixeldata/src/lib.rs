@@ -101,12 +101,44 @@
 //! See [`ConvertOptions`] for the options available,
 //! including the default behavior for each method.
 //!
+//! To decode individual frames of a large multi-frame file
+//! without loading the pixel data of the other frames,
+//! see [`FrameReader`].
+//!
+//! To display an image as described by
+//! a grayscale softcopy presentation state,
+//! see [`PresentationState`].
+//!
+//! To extract the video stream of an object
+//! in an MPEG-2, H.264 or HEVC transfer syntax,
+//! or to create a new video object from an MP4 file,
+//! see the [`video`] module.
+//! H.264 video frames can also be decoded there
+//! (requires the `openh264` feature).
+//!
+//! To create a new Secondary Capture image
+//! from general purpose images,
+//! see the [`secondary_capture`] module.
+//!
+//! To read or create segmentation objects
+//! (requires the `ndarray` feature),
+//! see the `segmentation` module.
+//!
+//! To map pixel positions to the patient coordinate system
+//! and to sort the frames of a series along their normal,
+//! see the [`geometry`] module.
+//! To assemble a series into a 3D volume and save it in the NIfTI format
+//! (requires the `ndarray` feature),
+//! see the `volume` module.
+//!
 
 use byteorder::{ByteOrder, NativeEndian};
 #[cfg(not(feature = "gdcm"))]
 use dicom_core::{DataDictionary, DicomValue};
 use dicom_encoding::adapters::DecodeError;
 #[cfg(not(feature = "gdcm"))]
+use dicom_encoding::adapters::PixelDataObject;
+#[cfg(not(feature = "gdcm"))]
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 #[cfg(not(feature = "gdcm"))]
 use dicom_encoding::Codec;
@@ -125,7 +157,6 @@ use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterato
 use rayon::slice::ParallelSliceMut;
 #[cfg(not(feature = "gdcm"))]
 use snafu::ensure;
-#[cfg(any(not(feature = "gdcm"), feature = "image"))]
 use snafu::OptionExt;
 use snafu::{Backtrace, ResultExt, Snafu};
 use std::borrow::Cow;
@@ -138,17 +169,43 @@ pub use image;
 pub use ndarray;
 
 mod attribute;
+mod frame_reader;
 mod lut;
+mod overlay;
+mod palette;
+mod presentation;
+mod stats;
 mod transcode;
 
 pub mod encapsulation;
+pub mod geometry;
+pub mod secondary_capture;
+#[cfg(feature = "ndarray")]
+pub mod segmentation;
 pub(crate) mod transform;
+pub mod video;
+#[cfg(feature = "ndarray")]
+pub mod volume;
 
 // re-exports
-pub use attribute::{PhotometricInterpretation, PixelRepresentation, PlanarConfiguration};
+pub use attribute::{
+    frame_attribute, PhotometricInterpretation, PixelDataKind, PixelRepresentation,
+    PlanarConfiguration,
+};
+pub use frame_reader::{Error as FrameReaderError, FrameReader, Result as FrameReaderResult};
 pub use lut::{CreateLutError, Lut};
+pub use overlay::{Overlay, OverlayType};
+pub use palette::PaletteColorLut;
+pub use presentation::{
+    AnnotationUnits, DisplayedArea, Error as PresentationStateError, GraphicAnnotation,
+    GraphicObject, GraphicType, PresentationLutShape, PresentationSizeMode, PresentationState,
+    Result as PresentationStateResult, SoftcopyVoi, SoftcopyVoiTransform, TextObject,
+};
+pub use stats::{percentiles, AutoWindowLevel, Histogram};
 pub use transcode::{Error as TranscodeError, Result as TranscodeResult, Transcode};
-pub use transform::{Rescale, VoiLutFunction, WindowLevel, WindowLevelTransform};
+pub use transform::{
+    LutTable, ModalityTransform, Rescale, VoiLutFunction, WindowLevel, WindowLevelTransform,
+};
 
 #[cfg(feature = "gdcm")]
 mod gdcm;
@@ -169,7 +226,9 @@ pub enum InnerError {
     #[snafu(display("PixelData attribute is not a primitive value or pixel sequence"))]
     InvalidPixelData { backtrace: Backtrace },
 
-    #[snafu(display("Invalid BitsAllocated, must be 8 or 16"))]
+    #[snafu(display(
+        "Invalid BitsAllocated, must be 8 or 16 (32 or 64 for floating point pixel data)"
+    ))]
     InvalidBitsAllocated { backtrace: Backtrace },
 
     #[snafu(display("Unsupported PhotometricInterpretation `{}`", pi))]
@@ -222,6 +281,9 @@ pub enum InnerError {
     #[snafu(display("Could not decode pixel data"))]
     DecodePixelData { source: DecodeError },
 
+    #[snafu(display("VOI LUT #{} is not described in the pixel data", index))]
+    MissingVoiLut { index: usize, backtrace: Backtrace },
+
     #[snafu(display("Frame #{} is out of range", frame_number))]
     FrameOutOfRange {
         frame_number: u32,
@@ -266,6 +328,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// 3. In the case of converting to an image,
 ///    the transformed values are extended or narrowed
 ///    to the range of the target bit depth (`bit_depth`).
+/// 4. In the case of converting to an image,
+///    the overlay planes are burned into the image
+///    if requested (`burn_in_overlays`).
 #[derive(Debug, Default, Clone, PartialEq)]
 #[non_exhaustive]
 pub struct ConvertOptions {
@@ -275,6 +340,8 @@ pub struct ConvertOptions {
     pub voi_lut: VoiLutOption,
     /// Output image bit depth
     pub bit_depth: BitDepthOption,
+    /// Whether to burn the overlay planes into the output image
+    pub burn_in_overlays: bool,
 }
 
 impl ConvertOptions {
@@ -315,6 +382,13 @@ impl ConvertOptions {
         self.bit_depth = BitDepthOption::Force16Bit;
         self
     }
+
+    /// Set whether to burn the overlay planes into the output image,
+    /// setting the pixels covered by an overlay to the maximum sample value.
+    pub fn with_burn_in_overlays(mut self, burn_in_overlays: bool) -> Self {
+        self.burn_in_overlays = burn_in_overlays;
+        self
+    }
 }
 
 /// Modality LUT function specifier.
@@ -325,12 +399,18 @@ impl ConvertOptions {
 pub enum ModalityLutOption {
     /// _Default behavior:_
     /// rescale the pixel data values
-    /// as described in the decoded pixel data.
+    /// as described in the decoded pixel data,
+    /// or transform them through the _Modality LUT Sequence_
+    /// if the object has one.
     #[default]
     Default,
     /// Rescale the pixel data values
     /// according to the given rescale parameters
     Override(Rescale),
+    /// Transform the pixel data values
+    /// according to the given modality LUT table,
+    /// instead of the one described in the pixel data
+    Lut(LutTable),
     /// Do not rescale nor transform the pixel data value samples.
     ///
     /// This also overrides any option to apply VOI LUT transformations
@@ -359,11 +439,23 @@ pub enum VoiLutOption {
     Default,
     /// Apply the first VOI LUT function transformation
     /// described in the pixel data.
+    ///
+    /// If the object does not define a window level,
+    /// the first table of the _VOI LUT Sequence_ is applied instead.
     First,
     /// Apply a custom window level instead of the one described in the object.
     Custom(WindowLevel),
     /// Apply a custom window level and a custom function instead of the one described in the object.
     CustomWithFunction(WindowLevel, VoiLutFunction),
+    /// Apply the VOI LUT table at the given index (0-based)
+    /// of the _VOI LUT Sequence_ described in the pixel data.
+    LutSequence(usize),
+    /// Apply a custom VOI LUT table instead of the one described in the object.
+    Lut(LutTable),
+    /// Apply a linear window level
+    /// determined from the statistics of the sample values of each frame,
+    /// instead of the one described in the object.
+    Auto(AutoWindowLevel),
     /// Perform a min-max normalization instead,
     /// so that the lowest value is 0 and
     /// the highest value is the maximum value of the target type.
@@ -429,6 +521,9 @@ pub struct DecodedPixelData<'a> {
     high_bit: u16,
     /// the pixel representation: 0 for unsigned, 1 for signed
     pixel_representation: PixelRepresentation,
+    /// the kind of pixel data attribute,
+    /// which determines whether samples are integers or floating point numbers
+    pixel_data_kind: PixelDataKind,
     /// Multiframe dicom objects can have rescale information, voi LUT and
     /// window level information once in the shared functional group sequence,
     /// or multiple times in the per-frame functional group sequence. This is a
@@ -440,6 +535,17 @@ pub struct DecodedPixelData<'a> {
     voi_lut_function: Option<Vec<VoiLutFunction>>,
     /// the window level specified via width and center
     window: Option<Vec<WindowLevel>>,
+    /// the look-up table of the Modality LUT Sequence of each frame,
+    /// or a single one for all frames, if any
+    modality_lut: Vec<LutTable>,
+    /// the look-up tables of the VOI LUT Sequence of each frame,
+    /// or a single list for all frames
+    voi_luts: Vec<Vec<LutTable>>,
+    /// the palette color look-up table,
+    /// only available for `PALETTE COLOR` pixel data
+    palette: Option<PaletteColorLut>,
+    /// the overlay planes described in the object
+    overlays: Vec<Overlay>,
 
     /// Enforce frame functional groups VMs match `number_of_frames`
     enforce_frame_fg_vm_match: bool,
@@ -560,6 +666,13 @@ impl DecodedPixelData<'_> {
         self.pixel_representation
     }
 
+    /// Retrieve the kind of pixel data attribute the samples were read from,
+    /// which determines whether they are integers or floating point numbers.
+    #[inline]
+    pub fn pixel_data_kind(&self) -> PixelDataKind {
+        self.pixel_data_kind
+    }
+
     /// Retrieve object's rescale parameters.
     #[inline]
     pub fn rescale(&self) -> Result<&[Rescale]> {
@@ -644,6 +757,209 @@ impl DecodedPixelData<'_> {
         }
     }
 
+    /// Retrieve the look-up table of the _Modality LUT Sequence_
+    /// of each frame,
+    /// or a single one if it applies to all frames.
+    /// The slice is empty if the object has none.
+    ///
+    /// When present,
+    /// it is applied instead of the rescale parameters by default.
+    #[inline]
+    pub fn modality_lut(&self) -> &[LutTable] {
+        &self.modality_lut
+    }
+
+    /// Retrieve the look-up tables of the _VOI LUT Sequence_
+    /// of each frame,
+    /// or a single list if they apply to all frames.
+    ///
+    /// The first table of a frame is applied by default
+    /// when the object does not define a window level.
+    #[inline]
+    pub fn voi_luts(&self) -> &[Vec<LutTable>] {
+        &self.voi_luts
+    }
+
+    /// Retrieve the look-up table of the _Modality LUT Sequence_
+    /// of the given frame number (0-based), if any.
+    fn frame_modality_lut(&self, frame: u32) -> Option<&LutTable> {
+        self.modality_lut
+            .get(frame as usize)
+            .or(self.modality_lut.first())
+    }
+
+    /// Retrieve the look-up tables of the _VOI LUT Sequence_
+    /// of the given frame number (0-based).
+    fn frame_voi_luts(&self, frame: u32) -> &[LutTable] {
+        self.voi_luts
+            .get(frame as usize)
+            .or(self.voi_luts.first())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Build a histogram of the sample values
+    /// of the given frame number (0-based)
+    /// with the given number of bins.
+    ///
+    /// The sample values are taken
+    /// after applying the modality LUT transformation
+    /// described in the pixel data,
+    /// as in [`to_vec_frame`](Self::to_vec_frame).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bins` is 0.
+    pub fn histogram(&self, frame: u32, bins: usize) -> Result<Histogram> {
+        let values = self.to_vec_frame::<f64>(frame)?;
+        Ok(Histogram::from_values(&values, bins))
+    }
+
+    /// Calculate the given percentiles (from 0 to 100)
+    /// of the sample values of the given frame number (0-based).
+    ///
+    /// The sample values are taken
+    /// after applying the modality LUT transformation
+    /// described in the pixel data,
+    /// as in [`to_vec_frame`](Self::to_vec_frame).
+    /// See [`percentiles`](crate::percentiles) for more details.
+    pub fn percentiles(&self, frame: u32, percentiles: &[f64]) -> Result<Vec<f64>> {
+        let values = self.to_vec_frame::<f64>(frame)?;
+        Ok(stats::percentiles(&values, percentiles))
+    }
+
+    /// Determine a window level
+    /// for the given frame number (0-based)
+    /// from the statistics of its sample values,
+    /// using the given strategy.
+    ///
+    /// The sample values are taken
+    /// after applying the modality LUT transformation
+    /// described in the pixel data,
+    /// as in [`to_vec_frame`](Self::to_vec_frame).
+    /// Returns `None` if the frame has no samples.
+    pub fn auto_window_level(
+        &self,
+        frame: u32,
+        strategy: &AutoWindowLevel,
+    ) -> Result<Option<WindowLevel>> {
+        let values = self.to_vec_frame::<f64>(frame)?;
+        Ok(strategy.window_level(&values))
+    }
+
+    /// Determine the modality LUT transformation
+    /// to apply on the given frame number (0-based),
+    /// according to the given option.
+    fn modality_transform<'a>(
+        &'a self,
+        frame: u32,
+        modality_lut: &'a ModalityLutOption,
+    ) -> Result<ModalityTransform<'a>> {
+        Ok(match (modality_lut, self.frame_modality_lut(frame)) {
+            (ModalityLutOption::Override(rescale), _) => (*rescale).into(),
+            (ModalityLutOption::Lut(table), _) => table.into(),
+            (_, Some(table)) => table.into(),
+            _ => {
+                let default = self.rescale()?;
+                if default.len() > 1 {
+                    default[frame as usize].into()
+                } else {
+                    default[0].into()
+                }
+            }
+        })
+    }
+
+    /// Retrieve the VOI LUT table at the given index of the VOI LUT Sequence
+    /// of the given frame number (0-based).
+    fn voi_lut(&self, frame: u32, index: usize) -> Result<&LutTable> {
+        Ok(self
+            .frame_voi_luts(frame)
+            .get(index)
+            .context(MissingVoiLutSnafu { index })?)
+    }
+
+    /// Retrieve the palette color look-up table,
+    /// if the photometric interpretation is `PALETTE COLOR`.
+    #[inline]
+    pub fn palette_color_lut(&self) -> Option<&PaletteColorLut> {
+        self.palette.as_ref()
+    }
+
+    /// Retrieve the overlay planes described in the object.
+    ///
+    /// When decoding a single frame,
+    /// only the overlays applying to that frame are retained,
+    /// and they apply to the first frame of the decoded pixel data.
+    #[inline]
+    pub fn overlays(&self) -> &[Overlay] {
+        &self.overlays
+    }
+
+    /// Extract the bits of the overlays embedded in the pixel data.
+    fn with_embedded_overlays(mut self) -> Self {
+        for overlay in &mut self.overlays {
+            overlay.extract_embedded(
+                &self.data,
+                self.bits_allocated,
+                self.samples_per_pixel,
+                self.number_of_frames,
+            );
+        }
+        self
+    }
+
+    /// The number of samples per pixel
+    /// produced by the conversion methods.
+    ///
+    /// This is 3 for `PALETTE COLOR` pixel data with a palette,
+    /// which is expanded to red, green, and blue values.
+    #[cfg(feature = "ndarray")]
+    fn converted_samples_per_pixel(&self) -> u16 {
+        if self.photometric_interpretation == PhotometricInterpretation::PaletteColor
+            && self.palette.is_some()
+        {
+            3
+        } else {
+            self.samples_per_pixel
+        }
+    }
+
+    /// Map the given `PALETTE COLOR` sample values
+    /// into interleaved red, green, and blue values
+    /// through the palette color look-up table.
+    fn apply_palette(&self, data: &[u8]) -> Result<Vec<u16>> {
+        let palette =
+            self.palette
+                .as_ref()
+                .with_context(|| UnsupportedPhotometricInterpretationSnafu {
+                    pi: self.photometric_interpretation.clone(),
+                })?;
+
+        let signed = self.pixel_representation == PixelRepresentation::Signed;
+        // ignore any bits beyond the bits stored
+        let mask = (1_u32 << self.bits_stored.clamp(1, 16)) - 1;
+        let values: Vec<i32> = match self.bits_allocated {
+            8 if signed => data.iter().map(|v| *v as i8 as i32).collect(),
+            8 => data.iter().map(|v| (*v as u32 & mask) as i32).collect(),
+            16 if signed => bytes_to_vec_u16(data)
+                .into_iter()
+                .map(|v| v as i16 as i32)
+                .collect(),
+            16 => bytes_to_vec_u16(data)
+                .into_iter()
+                .map(|v| (v as u32 & mask) as i32)
+                .collect(),
+            _ => InvalidBitsAllocatedSnafu.fail()?,
+        };
+
+        let mut out = Vec::with_capacity(values.len() * 3);
+        for v in values {
+            out.extend_from_slice(&palette.get(v));
+        }
+        Ok(out)
+    }
+
     // converter methods
 
     /// Convert the decoded pixel data of a specific frame into a dynamic image.
@@ -689,7 +1005,10 @@ impl DecodedPixelData<'_> {
         frame: u32,
         options: &ConvertOptions,
     ) -> Result<DynamicImage> {
-        match self.samples_per_pixel {
+        let mut image = match self.samples_per_pixel {
+            1 if self.photometric_interpretation == PhotometricInterpretation::PaletteColor => {
+                self.build_palette_color_image(frame, options.bit_depth)
+            }
             1 => self.build_monochrome_image(frame, options),
             3 => {
                 // Modality LUT and VOI LUT
@@ -745,7 +1064,15 @@ impl DecodedPixelData<'_> {
                 }
             }
             spp => UnsupportedSamplesPerPixelSnafu { spp }.fail()?,
+        }?;
+
+        if options.burn_in_overlays {
+            for overlay in &self.overlays {
+                overlay.burn_in(&mut image, frame);
+            }
         }
+
+        Ok(image)
     }
 
     #[cfg(feature = "image")]
@@ -889,12 +1216,29 @@ impl DecodedPixelData<'_> {
         }
     }
 
+    #[cfg(feature = "image")]
+    fn build_palette_color_image(
+        &self,
+        frame: u32,
+        bit_depth: BitDepthOption,
+    ) -> Result<DynamicImage> {
+        let pixels = self.apply_palette(self.frame_data(frame)?)?;
+        match self.palette.as_ref().map(|p| p.bits_per_entry()) {
+            Some(8) => {
+                let pixels = pixels.into_iter().map(|v| v as u8).collect();
+                self.rgb_image_with_extend(pixels, bit_depth)
+            }
+            _ => self.rgb_image_with_narrow(pixels, bit_depth),
+        }
+    }
+
     #[cfg(feature = "image")]
     fn build_monochrome_image(&self, frame: u32, options: &ConvertOptions) -> Result<DynamicImage> {
         let ConvertOptions {
             modality_lut,
             voi_lut,
             bit_depth,
+            burn_in_overlays: _,
         } = options;
 
         let mut image = match self.bits_allocated {
@@ -907,17 +1251,10 @@ impl DecodedPixelData<'_> {
                         self.mono_image_with_extend(data.iter().copied(), *bit_depth)?
                     }
                     // other
-                    ModalityLutOption::Default | ModalityLutOption::Override(..) => {
-                        let rescale = {
-                            let default = self.rescale()?;
-                            if let ModalityLutOption::Override(rescale) = modality_lut {
-                                *rescale
-                            } else if default.len() > 1 {
-                                default[frame as usize]
-                            } else {
-                                default[0]
-                            }
-                        };
+                    ModalityLutOption::Default
+                    | ModalityLutOption::Override(..)
+                    | ModalityLutOption::Lut(..) => {
+                        let rescale = self.modality_transform(frame, modality_lut)?;
 
                         let signed = self.pixel_representation == PixelRepresentation::Signed;
 
@@ -950,6 +1287,17 @@ impl DecodedPixelData<'_> {
                                 )
                                 .context(CreateLutSnafu)?
                             }
+                            (VoiLutOption::Default | VoiLutOption::First, None)
+                                if !self.frame_voi_luts(frame).is_empty() =>
+                            {
+                                Lut::new_rescale_and_voi_lut(
+                                    8,
+                                    signed,
+                                    rescale,
+                                    &self.frame_voi_luts(frame)[0],
+                                )
+                                .context(CreateLutSnafu)?
+                            }
                             (VoiLutOption::Default | VoiLutOption::First, None) => {
                                 tracing::warn!("Could not find window level for object");
                                 Lut::new_rescale_and_normalize(
@@ -995,6 +1343,25 @@ impl DecodedPixelData<'_> {
                                 data.iter().copied(),
                             )
                             .context(CreateLutSnafu)?,
+                            (VoiLutOption::LutSequence(index), _) => Lut::new_rescale_and_voi_lut(
+                                8,
+                                signed,
+                                rescale,
+                                self.voi_lut(frame, *index)?,
+                            )
+                            .context(CreateLutSnafu)?,
+                            (VoiLutOption::Lut(table), _) => {
+                                Lut::new_rescale_and_voi_lut(8, signed, rescale, table)
+                                    .context(CreateLutSnafu)?
+                            }
+                            (VoiLutOption::Auto(strategy), _) => Lut::new_rescale_and_auto_window(
+                                8,
+                                signed,
+                                rescale,
+                                strategy,
+                                data.iter().copied(),
+                            )
+                            .context(CreateLutSnafu)?,
                         };
 
                         #[cfg(feature = "rayon")]
@@ -1049,17 +1416,10 @@ impl DecodedPixelData<'_> {
                         self.mono_image_with_narrow(buffer.into_iter(), *bit_depth)?
                     }
 
-                    ModalityLutOption::Default | ModalityLutOption::Override(..) => {
-                        let rescale = {
-                            let default = self.rescale()?;
-                            if let ModalityLutOption::Override(rescale) = modality_lut {
-                                *rescale
-                            } else if default.len() > 1 {
-                                self.rescale[frame as usize]
-                            } else {
-                                default[0]
-                            }
-                        };
+                    ModalityLutOption::Default
+                    | ModalityLutOption::Override(..)
+                    | ModalityLutOption::Lut(..) => {
+                        let rescale = self.modality_transform(frame, modality_lut)?;
 
                         // fetch pixel data as a slice of u16 values,
                         // irrespective of pixel signedness
@@ -1099,6 +1459,16 @@ impl DecodedPixelData<'_> {
                                     ),
                                 )
                             }
+                            (VoiLutOption::Default | VoiLutOption::First, None)
+                                if !self.frame_voi_luts(frame).is_empty() =>
+                            {
+                                Lut::new_rescale_and_voi_lut(
+                                    self.bits_stored,
+                                    signed,
+                                    rescale,
+                                    &self.frame_voi_luts(frame)[0],
+                                )
+                            }
                             (VoiLutOption::Default | VoiLutOption::First, None) => {
                                 tracing::warn!("Could not find window level for object");
 
@@ -1141,6 +1511,25 @@ impl DecodedPixelData<'_> {
                                 rescale,
                                 samples.iter().copied(),
                             ),
+                            (VoiLutOption::LutSequence(index), _) => Lut::new_rescale_and_voi_lut(
+                                self.bits_stored,
+                                signed,
+                                rescale,
+                                self.voi_lut(frame, *index)?,
+                            ),
+                            (VoiLutOption::Lut(table), _) => Lut::new_rescale_and_voi_lut(
+                                self.bits_stored,
+                                signed,
+                                rescale,
+                                table,
+                            ),
+                            (VoiLutOption::Auto(strategy), _) => Lut::new_rescale_and_auto_window(
+                                self.bits_stored,
+                                signed,
+                                rescale,
+                                strategy,
+                                samples.iter().copied(),
+                            ),
                         }
                         .context(CreateLutSnafu)?;
 
@@ -1173,9 +1562,12 @@ impl DecodedPixelData<'_> {
     /// pixels first, then columns, then rows, then frames.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
-    /// Photometric interpretation is ignored.
+    /// Photometric interpretation is ignored,
+    /// except for `PALETTE COLOR`,
+    /// where each sample is expanded to red, green, and blue values.
     ///
     /// The default pixel data process pipeline
     /// applies only the Modality LUT function.
@@ -1213,9 +1605,12 @@ impl DecodedPixelData<'_> {
     /// pixel first, then column, then row, with frames traversed last.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
-    /// Photometric interpretation is ignored.
+    /// Photometric interpretation is ignored,
+    /// except for `PALETTE COLOR`,
+    /// where each sample is expanded to red, green, and blue values.
     ///
     /// The `options` value allows you to specify
     /// which transformations should be done to the pixel data
@@ -1241,9 +1636,12 @@ impl DecodedPixelData<'_> {
     /// pixels first, then columns, then rows.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
-    /// Photometric interpretation is ignored.
+    /// Photometric interpretation is ignored,
+    /// except for `PALETTE COLOR`,
+    /// where each sample is expanded to red, green, and blue values.
     ///
     /// The default pixel data process pipeline
     /// applies only the Modality LUT function.
@@ -1263,7 +1661,8 @@ impl DecodedPixelData<'_> {
     /// pixels first, then columns, then rows.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
     /// Photometric interpretation is considered
     /// to identify whether rescaling should be applied.
@@ -1318,8 +1717,32 @@ impl DecodedPixelData<'_> {
             modality_lut,
             voi_lut,
             bit_depth: _,
+            burn_in_overlays: _,
         } = options;
 
+        if self.photometric_interpretation == PhotometricInterpretation::PaletteColor
+            && self.palette.is_some()
+        {
+            // expand to RGB, no other transformations apply
+            let pixels = self.apply_palette(data)?;
+
+            #[cfg(feature = "rayon")]
+            let converted: Result<Vec<T>, _> = pixels
+                .par_iter()
+                .map(|v| T::from(*v).ok_or(snafu::NoneError))
+                .collect();
+            #[cfg(not(feature = "rayon"))]
+            let converted: Result<Vec<T>, _> = pixels
+                .iter()
+                .map(|v| T::from(*v).ok_or(snafu::NoneError))
+                .collect();
+            return converted.context(InvalidDataTypeSnafu).map_err(Error::from);
+        }
+
+        if self.pixel_data_kind != PixelDataKind::Integer {
+            return self.convert_float_pixel_slice(data, frame, modality_lut);
+        }
+
         if self.samples_per_pixel > 1 && self.planar_configuration != PlanarConfiguration::Standard
         {
             // TODO #129
@@ -1333,19 +1756,12 @@ impl DecodedPixelData<'_> {
         match self.bits_allocated {
             8 => {
                 match modality_lut {
-                    ModalityLutOption::Default | ModalityLutOption::Override(_)
+                    ModalityLutOption::Default
+                    | ModalityLutOption::Override(_)
+                    | ModalityLutOption::Lut(_)
                         if self.photometric_interpretation.is_monochrome() =>
                     {
-                        let rescale = {
-                            let default = self.rescale()?;
-                            if let ModalityLutOption::Override(rescale) = modality_lut {
-                                *rescale
-                            } else if default.len() > 1 {
-                                default[frame as usize]
-                            } else {
-                                default[0]
-                            }
-                        };
+                        let rescale = self.modality_transform(frame, modality_lut)?;
                         let signed = self.pixel_representation == PixelRepresentation::Signed;
 
                         let lut: Lut<T> = match (voi_lut, self.window()?) {
@@ -1374,6 +1790,16 @@ impl DecodedPixelData<'_> {
                                     },
                                 ),
                             ),
+                            (VoiLutOption::First, None)
+                                if !self.frame_voi_luts(frame).is_empty() =>
+                            {
+                                Lut::new_rescale_and_voi_lut(
+                                    8,
+                                    signed,
+                                    rescale,
+                                    &self.frame_voi_luts(frame)[0],
+                                )
+                            }
                             (VoiLutOption::First, None) => {
                                 tracing::warn!("Could not find window level for object");
                                 Lut::new_rescale(8, signed, rescale)
@@ -1410,6 +1836,22 @@ impl DecodedPixelData<'_> {
                                 rescale,
                                 data.iter().copied(),
                             ),
+                            (VoiLutOption::LutSequence(index), _) => Lut::new_rescale_and_voi_lut(
+                                8,
+                                signed,
+                                rescale,
+                                self.voi_lut(frame, *index)?,
+                            ),
+                            (VoiLutOption::Lut(table), _) => {
+                                Lut::new_rescale_and_voi_lut(8, signed, rescale, table)
+                            }
+                            (VoiLutOption::Auto(strategy), _) => Lut::new_rescale_and_auto_window(
+                                8,
+                                signed,
+                                rescale,
+                                strategy,
+                                data.iter().copied(),
+                            ),
                         }
                         .context(CreateLutSnafu)?;
 
@@ -1440,21 +1882,14 @@ impl DecodedPixelData<'_> {
             }
             16 => {
                 match modality_lut {
-                    ModalityLutOption::Default | ModalityLutOption::Override(_)
+                    ModalityLutOption::Default
+                    | ModalityLutOption::Override(_)
+                    | ModalityLutOption::Lut(_)
                         if self.photometric_interpretation.is_monochrome() =>
                     {
                         let samples = bytes_to_vec_u16(data);
 
-                        let rescale = {
-                            let default = self.rescale()?;
-                            if let ModalityLutOption::Override(rescale) = modality_lut {
-                                *rescale
-                            } else if default.len() > 1 {
-                                default[frame as usize]
-                            } else {
-                                default[0]
-                            }
-                        };
+                        let rescale = self.modality_transform(frame, modality_lut)?;
 
                         let signed = self.pixel_representation == PixelRepresentation::Signed;
 
@@ -1484,6 +1919,16 @@ impl DecodedPixelData<'_> {
                                     },
                                 ),
                             ),
+                            (VoiLutOption::First, None)
+                                if !self.frame_voi_luts(frame).is_empty() =>
+                            {
+                                Lut::new_rescale_and_voi_lut(
+                                    self.bits_stored,
+                                    signed,
+                                    rescale,
+                                    &self.frame_voi_luts(frame)[0],
+                                )
+                            }
                             (VoiLutOption::First, None) => {
                                 tracing::warn!("Could not find window level for object");
                                 Lut::new_rescale_and_normalize(
@@ -1525,6 +1970,25 @@ impl DecodedPixelData<'_> {
                                 rescale,
                                 samples.iter().copied(),
                             ),
+                            (VoiLutOption::LutSequence(index), _) => Lut::new_rescale_and_voi_lut(
+                                self.bits_stored,
+                                signed,
+                                rescale,
+                                self.voi_lut(frame, *index)?,
+                            ),
+                            (VoiLutOption::Lut(table), _) => Lut::new_rescale_and_voi_lut(
+                                self.bits_stored,
+                                signed,
+                                rescale,
+                                table,
+                            ),
+                            (VoiLutOption::Auto(strategy), _) => Lut::new_rescale_and_auto_window(
+                                self.bits_stored,
+                                signed,
+                                rescale,
+                                strategy,
+                                samples.iter().copied(),
+                            ),
                         }
                         .context(CreateLutSnafu)?;
 
@@ -1582,11 +2046,64 @@ impl DecodedPixelData<'_> {
         }
     }
 
+    /// Convert floating point pixel data samples
+    /// into a vector of the given type `T`.
+    ///
+    /// Only the modality LUT function is applied,
+    /// as an affine function on the floating point values.
+    fn convert_float_pixel_slice<T>(
+        &self,
+        data: &[u8],
+        frame: u32,
+        modality_lut: &ModalityLutOption,
+    ) -> Result<Vec<T>>
+    where
+        T: NumCast + Send + Sync + Copy + 'static,
+    {
+        let samples: Vec<f64> = match self.pixel_data_kind {
+            PixelDataKind::Float => {
+                let mut samples = vec![0.; data.len() / 4];
+                NativeEndian::read_f32_into(&data[..samples.len() * 4], &mut samples);
+                samples.into_iter().map(|v: f32| v as f64).collect()
+            }
+            PixelDataKind::DoubleFloat => {
+                let mut samples = vec![0.; data.len() / 8];
+                NativeEndian::read_f64_into(&data[..samples.len() * 8], &mut samples);
+                samples
+            }
+            PixelDataKind::Integer => InvalidBitsAllocatedSnafu.fail()?,
+        };
+
+        let rescale = match modality_lut {
+            ModalityLutOption::None => None,
+            _ => Some(self.modality_transform(frame, modality_lut)?),
+        };
+
+        #[cfg(feature = "rayon")]
+        let converted: Result<Vec<T>, _> = samples
+            .par_iter()
+            .map(|v| {
+                let v = rescale.map(|r| r.apply(*v)).unwrap_or(*v);
+                T::from(v).ok_or(snafu::NoneError)
+            })
+            .collect();
+        #[cfg(not(feature = "rayon"))]
+        let converted: Result<Vec<T>, _> = samples
+            .iter()
+            .map(|v| {
+                let v = rescale.map(|r| r.apply(*v)).unwrap_or(*v);
+                T::from(v).ok_or(snafu::NoneError)
+            })
+            .collect();
+        converted.context(InvalidDataTypeSnafu).map_err(Error::from)
+    }
+
     /// Convert all of the decoded pixel data
     /// into a four dimensional array of a given type `T`.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
     /// Photometric interpretation is considered
     /// to identify whether rescaling should be applied.
@@ -1597,7 +2114,8 @@ impl DecodedPixelData<'_> {
     /// where `N` is the number of frames,
     /// `R` is the number of rows,
     /// `C` is the number of columns,
-    /// and `S` is the number of samples per pixel.
+    /// and `S` is the number of samples per pixel
+    /// (3 for `PALETTE COLOR` pixel data).
     ///
     /// The default pixel data process pipeline
     /// applies only the Modality LUT function described in the object,
@@ -1618,7 +2136,8 @@ impl DecodedPixelData<'_> {
     /// into a four dimensional array of a given type `T`.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
     /// Photometric interpretation is considered
     /// to identify whether rescaling should be applied.
@@ -1629,7 +2148,8 @@ impl DecodedPixelData<'_> {
     /// where `N` is the number of frames,
     /// `R` is the number of rows,
     /// `C` is the number of columns,
-    /// and `S` is the number of samples per pixel.
+    /// and `S` is the number of samples per pixel
+    /// (3 for `PALETTE COLOR` pixel data).
     ///
     /// The `options` value allows you to specify
     /// which transformations should be done to the pixel data
@@ -1651,7 +2171,7 @@ impl DecodedPixelData<'_> {
             self.number_of_frames as usize,
             self.rows as usize,
             self.cols as usize,
-            self.samples_per_pixel as usize,
+            self.converted_samples_per_pixel() as usize,
         ];
 
         let converted = self.to_vec_with_options::<T>(options)?;
@@ -1664,7 +2184,8 @@ impl DecodedPixelData<'_> {
     /// into a three dimensional array of a given type `T`.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
     /// Photometric interpretation is considered
     /// to identify whether rescaling should be applied.
@@ -1674,7 +2195,8 @@ impl DecodedPixelData<'_> {
     /// The shape of the array will be `[R, C, S]`,
     /// where `R` is the number of rows,
     /// `C` is the number of columns,
-    /// and `S` is the number of samples per pixel.
+    /// and `S` is the number of samples per pixel
+    /// (3 for `PALETTE COLOR` pixel data).
     ///
     /// The default pixel data process pipeline
     /// applies only the Modality LUT function described in the object,
@@ -1695,7 +2217,8 @@ impl DecodedPixelData<'_> {
     /// into a three dimensional array of a given type `T`.
     ///
     /// The underlying pixel data type is extracted based on
-    /// the bits allocated and pixel representation,
+    /// the bits allocated and pixel representation
+    /// (or on whether the samples are floating point numbers),
     /// which is then converted to the requested type.
     /// Photometric interpretation is considered
     /// to identify whether rescaling should be applied.
@@ -1705,7 +2228,8 @@ impl DecodedPixelData<'_> {
     /// The shape of the array will be `[R, C, S]`,
     /// where `R` is the number of rows,
     /// `C` is the number of columns,
-    /// and `S` is the number of samples per pixel.
+    /// and `S` is the number of samples per pixel
+    /// (3 for `PALETTE COLOR` pixel data).
     ///
     /// The `options` value allows you to specify
     /// which transformations should be done to the pixel data
@@ -1730,7 +2254,7 @@ impl DecodedPixelData<'_> {
         let shape = [
             self.rows as usize,
             self.cols as usize,
-            self.samples_per_pixel as usize,
+            self.converted_samples_per_pixel() as usize,
         ];
 
         let converted = self.to_vec_frame_with_options::<T>(frame, options)?;
@@ -1767,6 +2291,7 @@ impl DecodedPixelData<'_> {
             bits_stored: self.bits_stored,
             high_bit: self.high_bit,
             pixel_representation: self.pixel_representation,
+            pixel_data_kind: self.pixel_data_kind,
             photometric_interpretation: self.photometric_interpretation.clone(),
             planar_configuration: self.planar_configuration,
             number_of_frames: self.number_of_frames,
@@ -1776,6 +2301,10 @@ impl DecodedPixelData<'_> {
             rescale: self.rescale.to_vec(),
             voi_lut_function: self.voi_lut_function.clone(),
             window: self.window.clone(),
+            modality_lut: self.modality_lut.clone(),
+            voi_luts: self.voi_luts.clone(),
+            palette: self.palette.clone(),
+            overlays: self.overlays.clone(),
             enforce_frame_fg_vm_match: self.enforce_frame_fg_vm_match,
         }
     }
@@ -1955,6 +2484,7 @@ pub(crate) struct ImagingProperties {
     pub(crate) bits_stored: u16,
     pub(crate) high_bit: u16,
     pub(crate) pixel_representation: PixelRepresentation,
+    pub(crate) pixel_data_kind: PixelDataKind,
     pub(crate) planar_configuration: PlanarConfiguration,
     pub(crate) photometric_interpretation: PhotometricInterpretation,
     pub(crate) rescale_intercept: Vec<f64>,
@@ -1962,11 +2492,28 @@ pub(crate) struct ImagingProperties {
     pub(crate) number_of_frames: u32,
     pub(crate) voi_lut_function: Option<Vec<VoiLutFunction>>,
     pub(crate) window: Option<Vec<WindowLevel>>,
+    pub(crate) modality_lut: Vec<LutTable>,
+    pub(crate) voi_luts: Vec<Vec<LutTable>>,
+    pub(crate) palette: Option<PaletteColorLut>,
+    pub(crate) overlays: Vec<Overlay>,
 }
 
 #[cfg(not(feature = "gdcm"))]
 impl ImagingProperties {
     fn from_obj<D>(obj: &FileDicomObject<InMemDicomObject<D>>) -> Result<Self>
+    where
+        D: Clone + DataDictionary,
+    {
+        Self::from_obj_with_kind(obj, attribute::pixel_data_kind(obj))
+    }
+
+    /// Read the imaging properties of an object
+    /// with the given kind of pixel data attribute,
+    /// which may not be present in the object.
+    fn from_obj_with_kind<D>(
+        obj: &FileDicomObject<InMemDicomObject<D>>,
+        kind: PixelDataKind,
+    ) -> Result<Self>
     where
         D: Clone + DataDictionary,
     {
@@ -1980,9 +2527,28 @@ impl ImagingProperties {
         let samples_per_pixel = samples_per_pixel(obj).context(GetAttributeSnafu)?;
         let planar_configuration = planar_configuration(obj).context(GetAttributeSnafu)?;
         let bits_allocated = bits_allocated(obj).context(GetAttributeSnafu)?;
-        let bits_stored = bits_stored(obj).context(GetAttributeSnafu)?;
-        let high_bit = high_bit(obj).context(GetAttributeSnafu)?;
-        let pixel_representation = pixel_representation(obj).context(GetAttributeSnafu)?;
+        let pixel_data_kind = kind;
+        let (bits_stored, high_bit, pixel_representation) = match pixel_data_kind {
+            PixelDataKind::Integer => (
+                bits_stored(obj).context(GetAttributeSnafu)?,
+                high_bit(obj).context(GetAttributeSnafu)?,
+                pixel_representation(obj).context(GetAttributeSnafu)?,
+            ),
+            // not defined for floating point pixel data
+            PixelDataKind::Float | PixelDataKind::DoubleFloat => {
+                let expected_bits = if pixel_data_kind == PixelDataKind::Float {
+                    32
+                } else {
+                    64
+                };
+                ensure!(bits_allocated == expected_bits, InvalidBitsAllocatedSnafu);
+                (
+                    bits_allocated,
+                    bits_allocated - 1,
+                    PixelRepresentation::Signed,
+                )
+            }
+        };
         let rescale_intercept = rescale_intercept(obj);
         let rescale_slope = rescale_slope(obj);
         let number_of_frames = number_of_frames(obj).context(GetAttributeSnafu)?;
@@ -2026,6 +2592,29 @@ impl ImagingProperties {
             None
         };
 
+        // without a palette color look-up table,
+        // the pixel data can still be read as is
+        let palette = if photometric_interpretation == PhotometricInterpretation::PaletteColor {
+            palette_color_lut(obj).map(Some).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid palette color look-up table: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+
+        // look-up tables and overlays which cannot be read are ignored,
+        // so that they do not prevent decoding the image
+        let modality_lut = modality_lut(obj).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid Modality LUT Sequence: {}", e);
+            Vec::new()
+        });
+        let voi_luts = voi_luts(obj).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid VOI LUT Sequence: {}", e);
+            Vec::new()
+        });
+        let overlays = overlays(obj);
+
         Ok(Self {
             cols,
             rows,
@@ -2034,6 +2623,7 @@ impl ImagingProperties {
             bits_stored,
             high_bit,
             pixel_representation,
+            pixel_data_kind,
             planar_configuration,
             photometric_interpretation,
             rescale_intercept,
@@ -2041,6 +2631,10 @@ impl ImagingProperties {
             number_of_frames,
             voi_lut_function,
             window,
+            modality_lut,
+            voi_luts,
+            palette,
+            overlays,
         })
     }
 }
@@ -2061,6 +2655,7 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            pixel_data_kind,
             planar_configuration,
             photometric_interpretation,
             rescale_intercept,
@@ -2068,6 +2663,10 @@ where
             number_of_frames,
             voi_lut_function,
             window,
+            modality_lut,
+            voi_luts,
+            palette,
+            overlays,
         } = ImagingProperties::from_obj(self)?;
 
         let transfer_syntax = &self.meta().transfer_syntax;
@@ -2117,11 +2716,17 @@ where
                 bits_stored,
                 high_bit,
                 pixel_representation,
+                pixel_data_kind,
                 rescale,
                 voi_lut_function,
                 window,
+                modality_lut,
+                voi_luts,
+                palette,
+                overlays,
                 enforce_frame_fg_vm_match: false,
-            });
+            }
+            .with_embedded_overlays());
         }
 
         let decoded_pixel_data = match pixel_data.value() {
@@ -2149,158 +2754,219 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            pixel_data_kind,
             rescale,
             voi_lut_function,
             window,
+            modality_lut,
+            voi_luts,
+            palette,
+            overlays,
             enforce_frame_fg_vm_match: false,
-        })
+        }
+        .with_embedded_overlays())
     }
 
     fn decode_pixel_data_frame(&self, frame: u32) -> Result<DecodedPixelData<'_>> {
-        let pixel_data = attribute::pixel_data(self).context(GetAttributeSnafu)?;
-
-        let ImagingProperties {
-            cols,
-            rows,
-            samples_per_pixel,
-            bits_allocated,
-            bits_stored,
-            high_bit,
-            pixel_representation,
-            planar_configuration,
-            photometric_interpretation,
-            rescale_intercept,
-            rescale_slope,
-            number_of_frames,
-            voi_lut_function,
-            window,
-        } = ImagingProperties::from_obj(self)?;
-
-        let transfer_syntax = &self.meta().transfer_syntax;
-        let ts = TransferSyntaxRegistry
-            .get(transfer_syntax)
-            .with_context(|| UnknownTransferSyntaxSnafu {
-                ts_uid: transfer_syntax,
-            })?;
-
-        if !ts.can_decode_all() {
-            return UnsupportedTransferSyntaxSnafu {
-                ts: transfer_syntax,
+        let pixel_data_kind = attribute::pixel_data_kind(self);
+        decode_pixel_data_frame_from(self, self, pixel_data_kind, frame, |frame_size| {
+            let pixel_data = attribute::pixel_data(self).context(GetAttributeSnafu)?;
+            match pixel_data.value() {
+                DicomValue::PixelSequence(..) => Ok(self
+                    .frame_data(frame)
+                    .with_context(|| FrameOutOfRangeSnafu {
+                        frame_number: frame,
+                    })?
+                    .into_owned()),
+                DicomValue::Primitive(p) => {
+                    // Non-encoded, just return the pixel data for a single frame
+                    let frame_offset = frame_size * frame as usize;
+                    let data = p.to_bytes();
+                    Ok(data
+                        .get(frame_offset..frame_offset + frame_size)
+                        .with_context(|| FrameOutOfRangeSnafu {
+                            frame_number: frame,
+                        })?
+                        .to_vec())
+                }
+                DicomValue::Sequence(..) => InvalidPixelDataSnafu.fail()?,
             }
-            .fail()?;
+        })
+    }
+}
+
+/// Decode a single frame of pixel data.
+///
+/// The imaging properties and transfer syntax are read from `obj`,
+/// whereas the encoded pixel data of the frame is taken from `src`,
+/// as the value of a pixel data attribute of the given kind.
+/// When the transfer syntax does not have a pixel data decoder,
+/// the frame data is fetched with `read_frame`,
+/// which receives the expected size of the frame in bytes.
+#[cfg(not(feature = "gdcm"))]
+pub(crate) fn decode_pixel_data_frame_from<D>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    src: &dyn PixelDataObject,
+    pixel_data_kind: PixelDataKind,
+    frame: u32,
+    read_frame: impl FnOnce(usize) -> Result<Vec<u8>>,
+) -> Result<DecodedPixelData<'static>>
+where
+    D: DataDictionary + Clone,
+{
+    let ImagingProperties {
+        cols,
+        rows,
+        samples_per_pixel,
+        bits_allocated,
+        bits_stored,
+        high_bit,
+        pixel_representation,
+        pixel_data_kind,
+        planar_configuration,
+        photometric_interpretation,
+        rescale_intercept,
+        rescale_slope,
+        number_of_frames: _,
+        voi_lut_function,
+        window,
+        modality_lut,
+        voi_luts,
+        palette,
+        overlays,
+    } = ImagingProperties::from_obj_with_kind(obj, pixel_data_kind)?;
+
+    let transfer_syntax = &obj.meta().transfer_syntax;
+    let ts = TransferSyntaxRegistry
+        .get(transfer_syntax)
+        .with_context(|| UnknownTransferSyntaxSnafu {
+            ts_uid: transfer_syntax,
+        })?;
+
+    if !ts.can_decode_all() {
+        return UnsupportedTransferSyntaxSnafu {
+            ts: transfer_syntax,
         }
+        .fail()?;
+    }
 
-        let rescale_data = zip(&rescale_intercept, &rescale_slope)
-            .map(|(intercept, slope)| Rescale {
-                intercept: *intercept,
-                slope: *slope,
-            })
-            .collect::<Vec<Rescale>>();
+    let rescale_data = zip(&rescale_intercept, &rescale_slope)
+        .map(|(intercept, slope)| Rescale {
+            intercept: *intercept,
+            slope: *slope,
+        })
+        .collect::<Vec<Rescale>>();
 
-        let rescale = rescale_data
+    let rescale = rescale_data
+        .get(frame as usize)
+        .or(rescale_data.first())
+        .copied()
+        .map(|inner| vec![inner])
+        .unwrap_or_default();
+
+    let window = window.and_then(|inner| {
+        inner
             .get(frame as usize)
-            .or(rescale_data.first())
+            .or(inner.first())
             .copied()
-            .map(|inner| vec![inner])
-            .unwrap_or_default();
-
-        let window = window
-            .and_then(|inner| {
-                inner
-                    .get(frame as usize)
-                    .or(inner.first())
-                    .copied()
-                    .map(|el| vec![el])
-            });
-
-        let voi_lut_function = voi_lut_function
-            .and_then(|inner| {
-                inner
-                    .get(frame as usize)
-                    .or(inner.first())
-                    .copied()
-                    .map(|el| vec![el])
-            });
-
-        // Try decoding it using a registered pixel data decoder
-        if let Codec::EncapsulatedPixelData(Some(decoder), _) = ts.codec() {
-            let mut data: Vec<u8> = Vec::new();
-            (*decoder)
-                .decode_frame(self, frame, &mut data)
-                .context(DecodePixelDataSnafu)?;
-
-            // pixels are already interpreted,
-            // set new photometric interpretation if necessary
-            let new_pi = match samples_per_pixel {
-                3 => PhotometricInterpretation::Rgb,
-                _ => photometric_interpretation,
-            };
+            .map(|el| vec![el])
+    });
 
-            return Ok(DecodedPixelData {
-                data: Cow::from(data),
-                cols: cols.into(),
-                rows: rows.into(),
-                number_of_frames: 1,
-                photometric_interpretation: new_pi,
-                samples_per_pixel,
-                planar_configuration: PlanarConfiguration::Standard,
-                bits_allocated,
-                bits_stored,
-                high_bit,
-                pixel_representation,
-                rescale,
-                voi_lut_function,
-                window,
-                enforce_frame_fg_vm_match: false,
-            });
-        }
+    let voi_lut_function = voi_lut_function.and_then(|inner| {
+        inner
+            .get(frame as usize)
+            .or(inner.first())
+            .copied()
+            .map(|el| vec![el])
+    });
 
-        let decoded_pixel_data = match pixel_data.value() {
-            DicomValue::PixelSequence(v) => {
-                let fragments = v.fragments();
-                if number_of_frames as usize == fragments.len() {
-                    // return a single fragment
-                    fragments[frame as usize].to_vec()
-                } else {
-                    // not supported, return an error
-                    InvalidPixelDataSnafu.fail()?
-                }
-            }
-            DicomValue::Primitive(p) => {
-                // Non-encoded, just return the pixel data for a single frame
-                let frame_size = ((bits_allocated + 7) / 8) as usize
-                    * samples_per_pixel as usize
-                    * rows as usize
-                    * cols as usize;
-                let frame_offset = frame_size * frame as usize;
-                let data = p.to_bytes();
-                data.get(frame_offset..frame_offset + frame_size)
-                    .with_context(|| FrameOutOfRangeSnafu {
-                        frame_number: frame,
-                    })?
-                    .to_vec()
-            }
-            DicomValue::Sequence(..) => InvalidPixelDataSnafu.fail()?,
+    let modality_lut = modality_lut
+        .get(frame as usize)
+        .or(modality_lut.first())
+        .cloned()
+        .into_iter()
+        .collect();
+
+    let voi_luts = voi_luts
+        .get(frame as usize)
+        .or(voi_luts.first())
+        .cloned()
+        .into_iter()
+        .collect();
+
+    let overlays = overlays
+        .into_iter()
+        .filter_map(|overlay| overlay.select_frame(frame))
+        .collect();
+
+    // Try decoding it using a registered pixel data decoder
+    if let Codec::EncapsulatedPixelData(Some(decoder), _) = ts.codec() {
+        let mut data: Vec<u8> = Vec::new();
+        (*decoder)
+            .decode_frame(src, frame, &mut data)
+            .context(DecodePixelDataSnafu)?;
+
+        // pixels are already interpreted,
+        // set new photometric interpretation if necessary
+        let new_pi = match samples_per_pixel {
+            3 => PhotometricInterpretation::Rgb,
+            _ => photometric_interpretation,
         };
 
-        Ok(DecodedPixelData {
-            data: Cow::from(decoded_pixel_data),
+        return Ok(DecodedPixelData {
+            data: Cow::from(data),
             cols: cols.into(),
             rows: rows.into(),
             number_of_frames: 1,
-            photometric_interpretation,
+            photometric_interpretation: new_pi,
             samples_per_pixel,
-            planar_configuration,
+            planar_configuration: PlanarConfiguration::Standard,
             bits_allocated,
             bits_stored,
             high_bit,
             pixel_representation,
+            pixel_data_kind,
             rescale,
             voi_lut_function,
             window,
+            modality_lut,
+            voi_luts,
+            palette,
+            overlays,
             enforce_frame_fg_vm_match: false,
-        })
+        }
+        .with_embedded_overlays());
     }
+
+    let frame_size = ((bits_allocated + 7) / 8) as usize
+        * samples_per_pixel as usize
+        * rows as usize
+        * cols as usize;
+    let decoded_pixel_data = read_frame(frame_size)?;
+
+    Ok(DecodedPixelData {
+        data: Cow::from(decoded_pixel_data),
+        cols: cols.into(),
+        rows: rows.into(),
+        number_of_frames: 1,
+        photometric_interpretation,
+        samples_per_pixel,
+        planar_configuration,
+        bits_allocated,
+        bits_stored,
+        high_bit,
+        pixel_representation,
+        pixel_data_kind,
+        rescale,
+        voi_lut_function,
+        window,
+        modality_lut,
+        voi_luts,
+        palette,
+        overlays,
+        enforce_frame_fg_vm_match: false,
+    }
+    .with_embedded_overlays())
 }
 
 #[cfg(test)]
@@ -2884,6 +3550,724 @@ mod tests {
         }
     }
 
+    /// Create an Explicit VR Little Endian image object
+    /// of the given SOP class and instance,
+    /// with the given image attributes of VR US
+    /// and the given photometric interpretation.
+    fn image_dicom(
+        sop_class_uid: &str,
+        sop_instance_uid: &str,
+        attributes: &[(dicom_core::Tag, u16)],
+        photometric_interpretation: &str,
+    ) -> dicom_object::DefaultDicomObject {
+        use dicom_core::{dicom_value, DataElement, VR};
+        use dicom_dictionary_std::{tags, uids};
+        use dicom_object::{FileDicomObject, FileMetaTableBuilder};
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(sop_class_uid)
+                .media_storage_sop_instance_uid(sop_instance_uid)
+                .build()
+                .unwrap(),
+        );
+        for (tag, value) in attributes {
+            obj.put(DataElement::new(*tag, VR::US, dicom_value!(U16, *value)));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            dicom_value!(Str, photometric_interpretation),
+        ));
+        obj
+    }
+
+    /// Create an 8-bit `PALETTE COLOR` image of 2x2 pixels
+    /// with a 16-bit palette of 4 entries,
+    /// the blue channel being encoded as segmented palette color LUT data.
+    fn palette_color_dicom() -> dicom_object::DefaultDicomObject {
+        use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let mut obj = image_dicom(
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            "2.25.221743183549175336412959299516406387775",
+            &[
+                (tags::ROWS, 2),
+                (tags::COLUMNS, 2),
+                (tags::SAMPLES_PER_PIXEL, 1),
+                (tags::BITS_ALLOCATED, 8),
+                (tags::BITS_STORED, 8),
+                (tags::HIGH_BIT, 7),
+                (tags::PIXEL_REPRESENTATION, 0),
+            ],
+            "PALETTE COLOR",
+        );
+        for tag in [
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+        ]
+        .iter()
+        {
+            obj.put(DataElement::new(
+                *tag,
+                VR::US,
+                dicom_value!(U16, [4, 0, 16]),
+            ));
+        }
+        obj.put(DataElement::new(
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0_u16, 0x4000, 0x8000, 0xFFFF].into()),
+        ));
+        obj.put(DataElement::new(
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0xFFFF_u16, 0x8000, 0x4000, 0].into()),
+        ));
+        // discrete segment of 1 entry, then linear segment of 3 entries
+        obj.put(DataElement::new(
+            tags::SEGMENTED_BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0_u16, 1, 0, 1, 3, 0x3000].into()),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(vec![0_u8, 1, 2, 3]),
+        ));
+        obj
+    }
+
+    #[test]
+    fn test_palette_color_to_vec() {
+        let obj = palette_color_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let palette = decoded.palette_color_lut().unwrap();
+        assert_eq!(palette.first_mapped_value(), 0);
+        assert_eq!(palette.bits_per_entry(), 16);
+        assert_eq!(palette.blue(), &[0, 0x1000, 0x2000, 0x3000]);
+
+        let values = decoded.to_vec::<u16>().unwrap();
+        assert_eq!(
+            values,
+            vec![
+                0, 0xFFFF, 0, // 0
+                0x4000, 0x8000, 0x1000, // 1
+                0x8000, 0x4000, 0x2000, // 2
+                0xFFFF, 0, 0x3000, // 3
+            ]
+        );
+    }
+
+    #[test]
+    fn test_palette_color_without_palette_to_vec() {
+        use dicom_dictionary_std::tags;
+
+        let mut obj = palette_color_dicom();
+        for tag in [
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+        ]
+        .iter()
+        {
+            obj.remove_element(*tag);
+        }
+
+        // the pixel data is still decoded, without a palette
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert!(decoded.palette_color_lut().is_none());
+        assert_eq!(decoded.to_vec::<u8>().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn test_palette_color_to_ndarray() {
+        let obj = palette_color_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let values = decoded.to_ndarray::<u16>().unwrap();
+        assert_eq!(values.shape(), &[1, 2, 2, 3]);
+        assert_eq!(values[[0, 1, 0, 0]], 0x8000);
+        assert_eq!(values[[0, 1, 1, 2]], 0x3000);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn test_palette_color_to_dynamic_image() {
+        let obj = palette_color_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let image = decoded.to_dynamic_image(0).unwrap();
+        let image = image.as_rgb16().expect("image should be 16-bit RGB");
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(1, 0).0, [0x4000, 0x8000, 0x1000]);
+
+        let options = ConvertOptions::new().force_8bit();
+        let image = decoded.to_dynamic_image_with_options(0, &options).unwrap();
+        let image = image.as_rgb8().expect("image should be 8-bit RGB");
+        assert_eq!(image.get_pixel(1, 1).0, [0xFF, 0, 0x30]);
+    }
+
+    /// Create a 16-bit `MONOCHROME2` image of 4x4 pixels
+    /// with a dedicated 2x2 overlay in group 6000 at (2, 3)
+    /// and an overlay in group 6002 embedded in bit 15 of the pixel data.
+    fn overlay_dicom() -> dicom_object::DefaultDicomObject {
+        use dicom_core::{dicom_value, DataElement, PrimitiveValue, Tag, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let mut obj = image_dicom(
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            "2.25.296133296727815735436254427359427387183",
+            &[
+                (tags::ROWS, 4),
+                (tags::COLUMNS, 4),
+                (tags::SAMPLES_PER_PIXEL, 1),
+                (tags::BITS_ALLOCATED, 16),
+                (tags::BITS_STORED, 12),
+                (tags::HIGH_BIT, 11),
+                (tags::PIXEL_REPRESENTATION, 0),
+                // dedicated overlay
+                (Tag(0x6000, 0x0010), 2),
+                (Tag(0x6000, 0x0011), 2),
+                (Tag(0x6000, 0x0100), 1),
+                (Tag(0x6000, 0x0102), 0),
+                // embedded overlay
+                (Tag(0x6002, 0x0010), 4),
+                (Tag(0x6002, 0x0011), 4),
+                (Tag(0x6002, 0x0100), 16),
+                (Tag(0x6002, 0x0102), 15),
+            ],
+            "MONOCHROME2",
+        );
+        for group in [0x6000, 0x6002].iter() {
+            obj.put(DataElement::new(
+                Tag(*group, 0x0040),
+                VR::CS,
+                dicom_value!(Str, "G"),
+            ));
+        }
+        obj.put(DataElement::new(
+            Tag(0x6000, 0x0050),
+            VR::SS,
+            dicom_value!(I16, [2, 3]),
+        ));
+        obj.put(DataElement::new(
+            Tag(0x6000, 0x1500),
+            VR::LO,
+            dicom_value!(Str, "MARK"),
+        ));
+        // bits 0 and 3 are set: top left and bottom right
+        obj.put(DataElement::new(
+            Tag(0x6000, 0x3000),
+            VR::OB,
+            PrimitiveValue::from(vec![0b1001_u8]),
+        ));
+        // embedded overlay in the first pixel only
+        let mut pixels = vec![0x0100_u16; 16];
+        pixels[0] |= 0x8000;
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(pixels.into()),
+        ));
+        obj
+    }
+
+    #[test]
+    fn test_overlays() {
+        let obj = overlay_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let overlays = decoded.overlays();
+        assert_eq!(overlays.len(), 2);
+
+        let overlay = &overlays[0];
+        assert_eq!(overlay.group(), 0x6000);
+        assert_eq!(overlay.overlay_type(), OverlayType::Graphics);
+        assert_eq!((overlay.rows(), overlay.columns()), (2, 2));
+        assert_eq!(overlay.origin(), [2, 3]);
+        assert_eq!(overlay.label(), Some("MARK"));
+        assert!(!overlay.is_embedded());
+        assert_eq!(overlay.bitmap(0), Some(vec![true, false, false, true]));
+
+        let overlay = &overlays[1];
+        assert_eq!(overlay.group(), 0x6002);
+        assert!(overlay.is_embedded());
+        let bitmap = overlay.bitmap(0).unwrap();
+        assert_eq!(bitmap.len(), 16);
+        assert!(bitmap[0]);
+        assert!(bitmap[1..].iter().all(|b| !b));
+
+        // overlays are retained when decoding a single frame
+        let decoded = obj.decode_pixel_data_frame(0).unwrap();
+        assert_eq!(decoded.overlays().len(), 2);
+        assert!(decoded.overlays()[1].bitmap(0).unwrap()[0]);
+    }
+
+    #[test]
+    fn test_overlays_skip_invalid_group() {
+        use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+
+        let mut obj = overlay_dicom();
+        // an overlay with an unknown type and without columns
+        obj.put(DataElement::new(
+            Tag(0x6004, 0x0010),
+            VR::US,
+            PrimitiveValue::from(2_u16),
+        ));
+        obj.put(DataElement::new(
+            Tag(0x6004, 0x0040),
+            VR::CS,
+            PrimitiveValue::from("X"),
+        ));
+
+        // only the invalid group is left out
+        let decoded = obj.decode_pixel_data().unwrap();
+        let groups: Vec<_> = decoded.overlays().iter().map(|o| o.group()).collect();
+        assert_eq!(groups, vec![0x6000, 0x6002]);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn test_overlays_burn_in() {
+        let obj = overlay_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let options = ConvertOptions::new()
+            .with_voi_lut(VoiLutOption::Identity)
+            .force_8bit();
+        let image = decoded.to_dynamic_image_with_options(0, &options).unwrap();
+        let image = image.as_luma8().unwrap();
+        assert_eq!(image.get_pixel(2, 1).0, [1]);
+
+        let options = options.with_burn_in_overlays(true);
+        let image = decoded.to_dynamic_image_with_options(0, &options).unwrap();
+        let image = image.as_luma8().unwrap();
+        // embedded overlay
+        assert_eq!(image.get_pixel(0, 0).0, [0xFF]);
+        // dedicated overlay
+        assert_eq!(image.get_pixel(2, 1).0, [0xFF]);
+        assert_eq!(image.get_pixel(3, 2).0, [0xFF]);
+        assert_eq!(image.get_pixel(3, 1).0, [1]);
+        assert_eq!(image.get_pixel(2, 2).0, [1]);
+    }
+
+    /// Create a `MONOCHROME2` image of 2 frames of 2x1 pixels
+    /// with the given floating point pixel data element.
+    fn float_pixel_data_dicom(
+        bits_allocated: u16,
+        pixel_data: dicom_core::DataElement<dicom_object::InMemDicomObject>,
+    ) -> dicom_object::DefaultDicomObject {
+        use dicom_core::{dicom_value, DataElement, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let mut obj = image_dicom(
+            uids::PARAMETRIC_MAP_STORAGE,
+            "2.25.80306537386524396357462163425616458474",
+            &[
+                (tags::ROWS, 1),
+                (tags::COLUMNS, 2),
+                (tags::SAMPLES_PER_PIXEL, 1),
+                (tags::BITS_ALLOCATED, bits_allocated),
+            ],
+            "MONOCHROME2",
+        );
+        obj.put(DataElement::new(
+            tags::NUMBER_OF_FRAMES,
+            VR::IS,
+            dicom_value!(Str, "2"),
+        ));
+        obj.put(pixel_data);
+        obj
+    }
+
+    #[test]
+    fn test_float_pixel_data() {
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::tags;
+
+        let obj = float_pixel_data_dicom(
+            32,
+            DataElement::new(
+                tags::FLOAT_PIXEL_DATA,
+                VR::OF,
+                PrimitiveValue::F32(vec![-1.5_f32, 0.25, 3.75, 1e6].into()),
+            ),
+        );
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.pixel_data_kind(), PixelDataKind::Float);
+        assert_eq!(decoded.bits_allocated(), 32);
+
+        let values = decoded.to_vec::<f32>().unwrap();
+        assert_eq!(values, vec![-1.5, 0.25, 3.75, 1e6]);
+        let values = decoded.to_vec_frame::<f64>(1).unwrap();
+        assert_eq!(values, vec![3.75, 1e6]);
+
+        let options = ConvertOptions::new()
+            .with_modality_lut(ModalityLutOption::Override(Rescale::new(2., 1.)));
+        let values = decoded.to_vec_with_options::<f32>(&options).unwrap();
+        assert_eq!(values, vec![-2., 1.5, 8.5, 2e6 + 1.]);
+
+        // a single frame can be decoded
+        let decoded = obj.decode_pixel_data_frame(1).unwrap();
+        assert_eq!(decoded.to_vec::<f32>().unwrap(), vec![3.75, 1e6]);
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn test_double_float_pixel_data_to_ndarray() {
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::tags;
+
+        let obj = float_pixel_data_dicom(
+            64,
+            DataElement::new(
+                tags::DOUBLE_FLOAT_PIXEL_DATA,
+                VR::OD,
+                PrimitiveValue::F64(vec![0.1, -0.2, 1e-9, 42.].into()),
+            ),
+        );
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.pixel_data_kind(), PixelDataKind::DoubleFloat);
+
+        let values = decoded.to_ndarray::<f64>().unwrap();
+        assert_eq!(values.shape(), &[2, 1, 2, 1]);
+        assert_eq!(values[[0, 0, 1, 0]], -0.2);
+        assert_eq!(values[[1, 0, 0, 0]], 1e-9);
+        assert_eq!(values[[1, 0, 1, 0]], 42.);
+    }
+
+    /// Create a `MONOCHROME2` image of 4x1 pixels with 12 bits stored,
+    /// a _Modality LUT Sequence_ which inverts the sample values,
+    /// and a _VOI LUT Sequence_ with a single 8-bit table.
+    fn lut_sequences_dicom() -> dicom_object::DefaultDicomObject {
+        use dicom_core::value::DataSetSequence;
+        use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+        use dicom_object::InMemDicomObject;
+
+        let mut obj = image_dicom(
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            "2.25.181077287040373934069493581946203498813",
+            &[
+                (tags::ROWS, 1),
+                (tags::COLUMNS, 4),
+                (tags::SAMPLES_PER_PIXEL, 1),
+                (tags::BITS_ALLOCATED, 16),
+                (tags::BITS_STORED, 12),
+                (tags::HIGH_BIT, 11),
+                (tags::PIXEL_REPRESENTATION, 0),
+            ],
+            "MONOCHROME2",
+        );
+        obj.put(DataElement::new(
+            tags::MODALITY_LUT_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(tags::LUT_DESCRIPTOR, VR::US, dicom_value!(U16, [4, 0, 12])),
+                DataElement::new(tags::LUT_DATA, VR::OW, dicom_value!(U16, [3, 2, 1, 0])),
+            ])]),
+        ));
+        obj.put(DataElement::new(
+            tags::VOILUT_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(tags::LUT_DESCRIPTOR, VR::US, dicom_value!(U16, [4, 0, 8])),
+                DataElement::new(tags::LUT_DATA, VR::OW, dicom_value!(U16, [0, 85, 170, 255])),
+            ])]),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0, 1, 2, 3].into()),
+        ));
+        obj
+    }
+
+    #[test]
+    fn test_lut_sequences() {
+        let obj = lut_sequences_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(
+            decoded.modality_lut(),
+            &[LutTable::new(0, 12, vec![3, 2, 1, 0])]
+        );
+        assert_eq!(
+            decoded.voi_luts(),
+            &[vec![LutTable::new(0, 8, vec![0, 85, 170, 255])]]
+        );
+
+        // the modality LUT table is applied by default
+        let values = decoded.to_vec::<u16>().unwrap();
+        assert_eq!(values, vec![3, 2, 1, 0]);
+
+        // followed by the VOI LUT table
+        let options = ConvertOptions::new().with_voi_lut(VoiLutOption::First);
+        let values = decoded.to_vec_with_options::<u16>(&options).unwrap();
+        assert_eq!(values, vec![0xFFFF, 0xAAAA, 0x5555, 0]);
+
+        // rescale parameters can still be enforced
+        let options = ConvertOptions::new()
+            .with_modality_lut(ModalityLutOption::Override(Rescale::new(1., 0.)))
+            .with_voi_lut(VoiLutOption::LutSequence(0));
+        let values = decoded.to_vec_with_options::<u16>(&options).unwrap();
+        assert_eq!(values, vec![0, 0x5555, 0xAAAA, 0xFFFF]);
+
+        // custom tables
+        let modality_lut = LutTable::new(0, 12, vec![0, 0, 3, 3]);
+        let voi_lut = LutTable::new(0, 16, vec![0, 0, 0, 0xFFFF]);
+        let options = ConvertOptions::new()
+            .with_modality_lut(ModalityLutOption::Lut(modality_lut))
+            .with_voi_lut(VoiLutOption::Lut(voi_lut));
+        let values = decoded.to_vec_with_options::<u16>(&options).unwrap();
+        assert_eq!(values, vec![0, 0, 0xFFFF, 0xFFFF]);
+
+        // no such VOI LUT
+        let options = ConvertOptions::new().with_voi_lut(VoiLutOption::LutSequence(1));
+        assert!(decoded.to_vec_with_options::<u16>(&options).is_err());
+    }
+
+    #[test]
+    fn test_lut_sequences_in_functional_groups() {
+        use dicom_core::value::DataSetSequence;
+        use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::tags;
+        use dicom_object::InMemDicomObject;
+
+        fn lut_item(tag: dicom_core::Tag, bits: u16, data: [u16; 4]) -> InMemDicomObject {
+            InMemDicomObject::from_element_iter([DataElement::new(
+                tag,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(
+                        tags::LUT_DESCRIPTOR,
+                        VR::US,
+                        dicom_value!(U16, [4, 0, bits]),
+                    ),
+                    DataElement::new(
+                        tags::LUT_DATA,
+                        VR::OW,
+                        PrimitiveValue::U16(data.iter().copied().collect()),
+                    ),
+                ])]),
+            )])
+        }
+
+        fn functional_groups(groups: Vec<(dicom_core::Tag, InMemDicomObject)>) -> InMemDicomObject {
+            InMemDicomObject::from_element_iter(groups.into_iter().map(|(tag, item)| {
+                DataElement::new(tag, VR::SQ, DataSetSequence::from(vec![item]))
+            }))
+        }
+
+        let mut obj = lut_sequences_dicom();
+        obj.remove_element(tags::MODALITY_LUT_SEQUENCE);
+        obj.remove_element(tags::VOILUT_SEQUENCE);
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0, 1, 2, 3, 0, 1, 2, 3].into()),
+        ));
+        // a different Modality LUT per frame, a shared VOI LUT
+        obj.put(DataElement::new(
+            tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![
+                functional_groups(vec![(
+                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
+                    lut_item(tags::MODALITY_LUT_SEQUENCE, 12, [3, 2, 1, 0]),
+                )]),
+                functional_groups(vec![(
+                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
+                    lut_item(tags::MODALITY_LUT_SEQUENCE, 12, [0, 0, 3, 3]),
+                )]),
+            ]),
+        ));
+        obj.put(DataElement::new(
+            tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![functional_groups(vec![(
+                tags::FRAME_VOILUT_SEQUENCE,
+                lut_item(tags::VOILUT_SEQUENCE, 8, [0, 85, 170, 255]),
+            )])]),
+        ));
+
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(
+            decoded.modality_lut(),
+            &[
+                LutTable::new(0, 12, vec![3, 2, 1, 0]),
+                LutTable::new(0, 12, vec![0, 0, 3, 3]),
+            ]
+        );
+        assert_eq!(
+            decoded.voi_luts(),
+            &[vec![LutTable::new(0, 8, vec![0, 85, 170, 255])]]
+        );
+        assert_eq!(decoded.to_vec_frame::<u16>(0).unwrap(), vec![3, 2, 1, 0]);
+        assert_eq!(decoded.to_vec_frame::<u16>(1).unwrap(), vec![0, 0, 3, 3]);
+
+        // decoding a single frame keeps the tables of that frame
+        let decoded = obj.decode_pixel_data_frame(1).unwrap();
+        assert_eq!(
+            decoded.modality_lut(),
+            &[LutTable::new(0, 12, vec![0, 0, 3, 3])]
+        );
+        let options = ConvertOptions::new().with_voi_lut(VoiLutOption::First);
+        let values = decoded.to_vec_with_options::<u16>(&options).unwrap();
+        assert_eq!(values, vec![0, 0, 0xFFFF, 0xFFFF]);
+    }
+
+    #[test]
+    fn test_invalid_lut_sequences_are_ignored() {
+        use dicom_core::value::DataSetSequence;
+        use dicom_core::{dicom_value, DataElement, VR};
+        use dicom_dictionary_std::tags;
+        use dicom_object::InMemDicomObject;
+
+        let mut obj = lut_sequences_dicom();
+        // LUT items without LUT Data
+        for tag in [tags::MODALITY_LUT_SEQUENCE, tags::VOILUT_SEQUENCE] {
+            obj.put(DataElement::new(
+                tag,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::LUT_DESCRIPTOR, VR::US, dicom_value!(U16, [4, 0, 8])),
+                ])]),
+            ));
+        }
+
+        // the image is still decoded, without the look-up tables
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert!(decoded.modality_lut().is_empty());
+        assert!(decoded.voi_luts().is_empty());
+        let values = decoded.to_vec::<u16>().unwrap();
+        assert_eq!(values, vec![0, 1, 2, 3]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_lut_sequences_to_dynamic_image() {
+        let obj = lut_sequences_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        // the VOI LUT table is applied by default in the absence of a window
+        let image = decoded.to_dynamic_image(0).unwrap().to_luma16();
+        let values: Vec<u16> = image.pixels().map(|p| p.0[0]).collect();
+        assert_eq!(values, vec![0xFFFF, 0xAAAA, 0x5555, 0]);
+    }
+
+    /// Create a CT-like `MONOCHROME2` image of 8x1 pixels
+    /// with 12 bits stored, a rescale intercept of -1024,
+    /// a couple of outliers, and no window level.
+    fn auto_window_dicom() -> dicom_object::DefaultDicomObject {
+        use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let mut obj = image_dicom(
+            uids::CT_IMAGE_STORAGE,
+            "2.25.307813287632851040123960402452387262436",
+            &[
+                (tags::ROWS, 1),
+                (tags::COLUMNS, 8),
+                (tags::SAMPLES_PER_PIXEL, 1),
+                (tags::BITS_ALLOCATED, 16),
+                (tags::BITS_STORED, 12),
+                (tags::HIGH_BIT, 11),
+                (tags::PIXEL_REPRESENTATION, 0),
+            ],
+            "MONOCHROME2",
+        );
+        obj.put(DataElement::new(
+            tags::RESCALE_INTERCEPT,
+            VR::DS,
+            dicom_value!(Str, "-1024"),
+        ));
+        obj.put(DataElement::new(
+            tags::RESCALE_SLOPE,
+            VR::DS,
+            dicom_value!(Str, "1"),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0, 100, 101, 102, 103, 104, 105, 4095].into()),
+        ));
+        obj
+    }
+
+    #[test]
+    fn test_pixel_statistics() {
+        let obj = auto_window_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let histogram = decoded.histogram(0, 4).unwrap();
+        assert_eq!(histogram.min(), -1024.);
+        assert_eq!(histogram.max(), 3071.);
+        assert_eq!(histogram.counts(), &[7, 0, 0, 1]);
+
+        let values = decoded.percentiles(0, &[0., 25., 100.]).unwrap();
+        assert_eq!(values, vec![-1024., -923.25, 3071.]);
+
+        let window = decoded
+            .auto_window_level(0, &AutoWindowLevel::MinMax)
+            .unwrap();
+        assert_eq!(
+            window,
+            Some(WindowLevel {
+                width: 4096.,
+                center: 1023.5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_auto_window_level_to_vec() {
+        let obj = auto_window_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let strategy = AutoWindowLevel::Percentile {
+            low: 25.,
+            high: 75.,
+        };
+        let options = ConvertOptions::new().with_voi_lut(VoiLutOption::Auto(strategy));
+        let values = decoded.to_vec_with_options::<u16>(&options).unwrap();
+        // outliers are clipped
+        assert_eq!(values[0], 0);
+        assert_eq!(values[7], 0xFFFF);
+        // the values in between spread over the output range
+        assert!(
+            values[3] > 0x4000 && values[3] < 0xC000,
+            "unexpected value {}",
+            values[3]
+        );
+        assert!(values[1..7].windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_auto_window_level_to_dynamic_image() {
+        let obj = auto_window_dicom();
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let options = ConvertOptions::new()
+            .with_voi_lut(VoiLutOption::Auto(AutoWindowLevel::MinMax))
+            .force_8bit();
+        let image = decoded
+            .to_dynamic_image_with_options(0, &options)
+            .unwrap()
+            .to_luma8();
+        let values: Vec<u8> = image.pixels().map(|p| p.0[0]).collect();
+        assert_eq!(values[0], 0);
+        assert_eq!(values[7], 0xFF);
+        assert!(values[1] < 0x10);
+    }
+
     /// Loading a MONOCHROME1 image with encapsulated pixel data
     /// should not change the photometric interpretation
     /// (this rule does not apply to decoding via GDCM)
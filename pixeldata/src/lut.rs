@@ -13,7 +13,7 @@ use num_traits::{NumCast, ToPrimitive};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use snafu::{OptionExt, Snafu};
 
-use crate::{Rescale, WindowLevelTransform};
+use crate::{AutoWindowLevel, LutTable, ModalityTransform, WindowLevelTransform};
 
 /// The LUT could not be created:
 /// entry #{index} was mapped to {y_value},
@@ -49,7 +49,10 @@ impl CreateLutError {
 /// The LUT can be populated with common transformations
 /// via the functions [`new_rescale`](Lut::new_rescale),
 /// [`new_window`](Lut::new_window),
-/// and [`new_rescale_and_window`](Lut::new_rescale_and_window).
+/// [`new_rescale_and_window`](Lut::new_rescale_and_window),
+/// and [`new_rescale_and_voi_lut`](Lut::new_rescale_and_voi_lut).
+/// Wherever a rescale function is expected,
+/// a modality [`LutTable`] may be given instead.
 /// The function [`new_with_fn`](Lut::new_with_fn)
 /// can be used to create a LUT with a custom function.
 ///
@@ -148,16 +151,17 @@ where
     /// - `signed`:
     ///   whether the input sample values are expected to be signed
     ///   (_Pixel Representation_ = 1)
-    /// - `rescale`: the rescale parameters
+    /// - `rescale`: the rescale parameters or the modality LUT table
     ///
     /// # Panics
     ///
     /// Panics if `bits_stored` is 0 or too large.
-    pub fn new_rescale(
+    pub fn new_rescale<'a>(
         bits_stored: u16,
         signed: bool,
-        rescale: Rescale,
+        rescale: impl Into<ModalityTransform<'a>>,
     ) -> Result<Self, CreateLutError> {
+        let rescale = rescale.into();
         Self::new_with_fn(bits_stored, signed, |v| rescale.apply(v))
     }
 
@@ -173,16 +177,16 @@ where
     /// - `signed`:
     ///   whether the input sample values are expected to be signed
     ///   (_Pixel Representation_ = 1)
-    /// - `rescale`: the rescale parameters
+    /// - `rescale`: the rescale parameters or the modality LUT table
     /// - `samples`: the raw pixel data samples expected to be fed to the LUT
     ///
     /// # Panics
     ///
     /// Panics if `bits_stored` is 0 or too large.
-    pub(crate) fn new_rescale_and_normalize<I>(
+    pub(crate) fn new_rescale_and_normalize<'a, I>(
         bits_stored: u16,
         signed: bool,
-        rescale: Rescale,
+        rescale: impl Into<ModalityTransform<'a>>,
         samples: I,
     ) -> Result<Self, CreateLutError>
     where
@@ -193,20 +197,17 @@ where
         I::Item: NumCast,
         I::Item: ToPrimitive,
     {
-        let samples_f64 = samples.into_iter().filter_map(|v| v.to_f64());
+        let rescale = rescale.into();
+
+        // the modality transformation may decrease the values,
+        // so the bounds are taken after applying it
+        let samples_f64 = samples
+            .into_iter()
+            .filter_map(|v| v.to_f64())
+            .map(|v| rescale.apply(v));
         let min: f64 = samples_f64.clone().fold(f64::MAX, |a, b| a.min(b));
         let max: f64 = samples_f64.fold(f64::MIN, |a, b| a.max(b));
 
-        // convert to f64 and swap if negative slope
-        let (min, max) = if rescale.slope < 0. {
-            (max, min)
-        } else {
-            (min, max)
-        };
-
-        let max = max * rescale.slope + rescale.intercept;
-        let min = min * rescale.slope + rescale.intercept;
-
         // create a linear window level transform
         let voi = WindowLevelTransform::linear(crate::WindowLevel {
             width: max - min + 1.,
@@ -216,6 +217,66 @@ where
         Self::new_rescale_and_window(bits_stored, signed, rescale, voi)
     }
 
+    /// Create a new LUT containing the given modality rescale transformation
+    /// and a linear window level
+    /// determined from the statistics of the raw samples given.
+    /// The sample type `I` is expected to be either `u8` or `u16`,
+    /// even if the sample is meant to be interpreted as signed.
+    ///
+    /// - `bits_stored`:
+    ///   the number of bits effectively used to represent the sample values
+    ///   (the _Bits Stored_ DICOM attribute)
+    /// - `signed`:
+    ///   whether the input sample values are expected to be signed
+    ///   (_Pixel Representation_ = 1)
+    /// - `rescale`: the rescale parameters or the modality LUT table
+    /// - `strategy`: how to determine the window level from the samples
+    /// - `samples`: the raw pixel data samples expected to be fed to the LUT
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits_stored` is 0 or too large.
+    pub(crate) fn new_rescale_and_auto_window<'a, I>(
+        bits_stored: u16,
+        signed: bool,
+        rescale: impl Into<ModalityTransform<'a>>,
+        strategy: &AutoWindowLevel,
+        samples: I,
+    ) -> Result<Self, CreateLutError>
+    where
+        I: IntoIterator,
+        I::Item: ToPrimitive,
+    {
+        let rescale = rescale.into();
+
+        let mask = (1_u64 << bits_stored) - 1;
+        let sign_bit = 1_u64 << (bits_stored - 1);
+        let values: Vec<f64> = samples
+            .into_iter()
+            .filter_map(|v| v.to_u64())
+            .map(|v| {
+                // account for signedness to determine input pixel value
+                let v = v & mask;
+                if signed && v & sign_bit != 0 {
+                    v as f64 - (mask + 1) as f64
+                } else {
+                    v as f64
+                }
+            })
+            .map(|v| rescale.apply(v))
+            .collect();
+
+        match strategy.window_level(&values) {
+            Some(window) => Self::new_rescale_and_window(
+                bits_stored,
+                signed,
+                rescale,
+                WindowLevelTransform::linear(window),
+            ),
+            None => Self::new_rescale(bits_stored, signed, rescale),
+        }
+    }
+
     /// Create a new LUT containing the modality rescale transformation
     /// and the VOI transformation defined by a window level.
     ///
@@ -231,18 +292,19 @@ where
     /// - `signed`:
     ///   whether the input sample values are expected to be signed
     ///   (_Pixel Representation_ = 1)
-    /// - `rescale`: the rescale parameters
+    /// - `rescale`: the rescale parameters or the modality LUT table
     /// - `voi`: the value of interest (VOI) function and parameters
     ///
     /// # Panics
     ///
     /// Panics if `bits_stored` is 0 or too large.
-    pub fn new_rescale_and_window(
+    pub fn new_rescale_and_window<'a>(
         bits_stored: u16,
         signed: bool,
-        rescale: Rescale,
+        rescale: impl Into<ModalityTransform<'a>>,
         voi: WindowLevelTransform,
     ) -> Result<Self, CreateLutError> {
+        let rescale = rescale.into();
         let bits_allocated = (bits_stored as usize).next_power_of_two();
         let y_max = ((1 << bits_allocated) - 1) as f64;
         Self::new_with_fn(bits_stored, signed, |v| {
@@ -281,6 +343,38 @@ where
         Self::new_with_fn(bits_stored, signed, |v| voi.apply(v, y_max))
     }
 
+    /// Create a new LUT containing the modality rescale transformation
+    /// and the VOI transformation defined by a VOI LUT table.
+    ///
+    /// The output values of the VOI LUT table are scaled
+    /// to go from 0 to `2^n - 1`, where `n` is the power of two
+    /// which follows `bits_stored` (or itself if it is a power of two).
+    ///
+    /// - `bits_stored`:
+    ///   the number of bits effectively used to represent the sample values
+    ///   (the _Bits Stored_ DICOM attribute)
+    /// - `signed`:
+    ///   whether the input sample values are expected to be signed
+    ///   (_Pixel Representation_ = 1)
+    /// - `rescale`: the rescale parameters or the modality LUT table
+    /// - `voi`: the VOI LUT table
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits_stored` is 0 or too large.
+    pub fn new_rescale_and_voi_lut<'a>(
+        bits_stored: u16,
+        signed: bool,
+        rescale: impl Into<ModalityTransform<'a>>,
+        voi: &LutTable,
+    ) -> Result<Self, CreateLutError> {
+        let rescale = rescale.into();
+        let bits_allocated = (bits_stored as usize).next_power_of_two();
+        let y_max = ((1 << bits_allocated) - 1) as f64;
+        let scale = y_max / voi.max_output();
+        Self::new_with_fn(bits_stored, signed, |v| voi.apply(rescale.apply(v)) * scale)
+    }
+
     /// Apply the transformation to a single pixel sample value.
     ///
     /// Although the input is expected to be one of `u8`, `u16`, or `u32`,
@@ -328,7 +422,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::{VoiLutFunction, WindowLevel};
+    use crate::{LutTable, Rescale, VoiLutFunction, WindowLevel};
 
     use super::*;
 
@@ -460,4 +554,26 @@ mod tests {
         let y = lut.get(498_u16);
         assert!(y > 0 && y < 0xFFFF);
     }
+
+    #[test]
+    fn lut_rescale_and_voi_lut_12bit() {
+        let modality = LutTable::new(0, 12, vec![3, 2, 1, 0]);
+        let voi = LutTable::new(0, 8, vec![0, 85, 170, 255]);
+
+        // modality LUT table followed by the VOI LUT table
+        let lut: Lut<u16> = Lut::new_rescale_and_voi_lut(12, false, &modality, &voi).unwrap();
+        assert_eq!(lut.get(0_u16), 0xFFFF);
+        assert_eq!(lut.get(1_u16), 0xAAAA);
+        assert_eq!(lut.get(2_u16), 0x5555);
+        assert_eq!(lut.get(3_u16), 0);
+        // out of the modality LUT range
+        assert_eq!(lut.get(100_u16), 0);
+
+        // rescale followed by the VOI LUT table
+        let lut: Lut<u8> =
+            Lut::new_rescale_and_voi_lut(8, false, Rescale::new(1., -1.), &voi).unwrap();
+        assert_eq!(lut.get(0_u8), 0);
+        assert_eq!(lut.get(2_u8), 85);
+        assert_eq!(lut.get(4_u8), 255);
+    }
 }
@@ -0,0 +1,379 @@
+//! Overlay plane implementation.
+//!
+//! This module contains the [`Overlay`] data type,
+//! which holds the bitmap of an overlay plane
+//! described by one of the repeating groups _60xx_
+//! (see [section C.9.2][1] of the standard).
+//!
+//! Overlay bits may be stored in a dedicated _Overlay Data_ attribute,
+//! or embedded in the unused high bits of the _Pixel Data_ (retired).
+//! Both forms are exposed as the same bitmap.
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_C.9.2.html
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+/// The kind of content of an overlay plane.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OverlayType {
+    /// Graphics (`G`), such as annotations
+    Graphics,
+    /// Region of interest (`R`)
+    Roi,
+}
+
+/// A decoded overlay plane.
+///
+/// The overlay holds one bit per pixel for each of its frames,
+/// which can be retrieved with [`get`](Overlay::get)
+/// or as a full bitmap with [`bitmap`](Overlay::bitmap).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlay {
+    /// the group number of the overlay, in `0x6000..=0x601E`
+    group: u16,
+    rows: u16,
+    columns: u16,
+    overlay_type: OverlayType,
+    /// the position of the top left overlay pixel
+    /// relative to the image, as (row, column), 1-based
+    origin: [i16; 2],
+    number_of_frames: u32,
+    /// the image frame matching the first overlay frame, 1-based
+    image_frame_origin: u32,
+    label: Option<String>,
+    description: Option<String>,
+    /// the bit position of the overlay in the pixel data,
+    /// if the overlay is embedded
+    embedded_bit: Option<u16>,
+    /// the overlay bits, packed in bytes starting from the least significant bit
+    data: Vec<u8>,
+}
+
+impl Overlay {
+    /// Create an overlay from its attributes and its packed bits,
+    /// or from its bit position in the pixel data
+    /// if the overlay is embedded.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        group: u16,
+        rows: u16,
+        columns: u16,
+        overlay_type: OverlayType,
+        origin: [i16; 2],
+        number_of_frames: u32,
+        image_frame_origin: u32,
+        label: Option<String>,
+        description: Option<String>,
+        embedded_bit: Option<u16>,
+        data: Vec<u8>,
+    ) -> Self {
+        Overlay {
+            group,
+            rows,
+            columns,
+            overlay_type,
+            origin,
+            number_of_frames,
+            image_frame_origin,
+            label,
+            description,
+            embedded_bit,
+            data,
+        }
+    }
+
+    /// Get the group number of the overlay (`0x6000` to `0x601E`).
+    pub fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Get the number of rows of the overlay.
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// Get the number of columns of the overlay.
+    pub fn columns(&self) -> u16 {
+        self.columns
+    }
+
+    /// Get the kind of content of the overlay.
+    pub fn overlay_type(&self) -> OverlayType {
+        self.overlay_type
+    }
+
+    /// Get the position of the top left pixel of the overlay
+    /// relative to the image, as `[row, column]`.
+    ///
+    /// As in the standard, `[1, 1]` is the top left pixel of the image.
+    pub fn origin(&self) -> [i16; 2] {
+        self.origin
+    }
+
+    /// Get the number of frames in the overlay.
+    pub fn number_of_frames(&self) -> u32 {
+        self.number_of_frames
+    }
+
+    /// Get the image frame number (1-based)
+    /// to which the first overlay frame applies.
+    pub fn image_frame_origin(&self) -> u32 {
+        self.image_frame_origin
+    }
+
+    /// Get the overlay label, if present.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Get the overlay description, if present.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Whether the overlay was embedded in the pixel data
+    /// rather than stored in _Overlay Data_.
+    pub fn is_embedded(&self) -> bool {
+        self.embedded_bit.is_some()
+    }
+
+    /// Map the given image frame number (0-based)
+    /// to the corresponding overlay frame number (0-based),
+    /// if the overlay applies to that image frame.
+    fn overlay_frame(&self, frame: u32) -> Option<u32> {
+        let overlay_frame = (frame + 1).checked_sub(self.image_frame_origin.max(1))?;
+        (overlay_frame < self.number_of_frames).then_some(overlay_frame)
+    }
+
+    /// Check whether the overlay bit at the given position is set,
+    /// for the given image frame number (0-based).
+    ///
+    /// `row` and `column` are relative to the overlay, 0-based.
+    /// Returns `false` if the position is outside of the overlay
+    /// or if the overlay does not apply to the frame.
+    pub fn get(&self, frame: u32, row: u32, column: u32) -> bool {
+        if row >= u32::from(self.rows) || column >= u32::from(self.columns) {
+            return false;
+        }
+        let overlay_frame = match self.overlay_frame(frame) {
+            Some(f) => f as usize,
+            None => return false,
+        };
+        let frame_size = self.rows as usize * self.columns as usize;
+        let index =
+            overlay_frame * frame_size + row as usize * self.columns as usize + column as usize;
+        self.data
+            .get(index / 8)
+            .map(|byte| byte & (1 << (index % 8)) != 0)
+            .unwrap_or(false)
+    }
+
+    /// Obtain the bitmap of the overlay for the given image frame number (0-based),
+    /// in row-major order with `rows * columns` values.
+    ///
+    /// Returns `None` if the overlay does not apply to the frame.
+    pub fn bitmap(&self, frame: u32) -> Option<Vec<bool>> {
+        self.overlay_frame(frame)?;
+        let mut out = Vec::with_capacity(self.rows as usize * self.columns as usize);
+        for row in 0..u32::from(self.rows) {
+            out.extend((0..u32::from(self.columns)).map(|column| self.get(frame, row, column)));
+        }
+        Some(out)
+    }
+
+    /// Retain only the overlay frame for the given image frame number (0-based),
+    /// so that it applies to the first image frame.
+    ///
+    /// Embedded overlays are kept as is,
+    /// as they are only extracted from the pixel data later.
+    /// Returns `None` if the overlay does not apply to the frame.
+    pub(crate) fn select_frame(self, frame: u32) -> Option<Overlay> {
+        if self.embedded_bit.is_some() {
+            return Some(self);
+        }
+        let bitmap = self.bitmap(frame)?;
+        Some(Overlay {
+            number_of_frames: 1,
+            image_frame_origin: 1,
+            data: pack_bits(bitmap),
+            ..self
+        })
+    }
+
+    /// Extract the bits of an embedded overlay
+    /// from the given native pixel data,
+    /// which covers the whole image.
+    ///
+    /// Does nothing if the overlay is not embedded.
+    pub(crate) fn extract_embedded(
+        &mut self,
+        pixel_data: &[u8],
+        bits_allocated: u16,
+        samples_per_pixel: u16,
+        number_of_frames: u32,
+    ) {
+        let bit = match self.embedded_bit {
+            Some(bit) => bit,
+            None => return,
+        };
+        let sample_size = match bits_allocated {
+            8 => 1,
+            16 => 2,
+            _ => return,
+        };
+        let pixel_size = sample_size * samples_per_pixel as usize;
+        let bits = pixel_data.chunks_exact(pixel_size).map(|pixel| {
+            let value = if sample_size == 2 {
+                u16::from_le_bytes([pixel[0], pixel[1]])
+            } else {
+                u16::from(pixel[0])
+            };
+            bit < 16 && value & (1 << bit) != 0
+        });
+        self.data = pack_bits(bits);
+        self.number_of_frames = number_of_frames;
+        self.image_frame_origin = 1;
+    }
+
+    /// Set all pixels covered by the overlay in the given image
+    /// to the maximum sample value (white),
+    /// for the given image frame number (0-based).
+    #[cfg(feature = "image")]
+    pub(crate) fn burn_in(&self, image: &mut DynamicImage, frame: u32) {
+        if self.overlay_frame(frame).is_none() {
+            return;
+        }
+        for row in 0..u32::from(self.rows) {
+            for column in 0..u32::from(self.columns) {
+                if !self.get(frame, row, column) {
+                    continue;
+                }
+                let y = i64::from(self.origin[0]) - 1 + i64::from(row);
+                let x = i64::from(self.origin[1]) - 1 + i64::from(column);
+                put_white_pixel(image, x, y);
+            }
+        }
+    }
+}
+
+/// Set the pixel at the given position (0-based)
+/// to the maximum sample value (white).
+///
+/// Positions outside of the image are ignored,
+/// as are image types which are not produced by the decoder.
+#[cfg(feature = "image")]
+pub(crate) fn put_white_pixel(image: &mut DynamicImage, x: i64, y: i64) {
+    if y < 0 || x < 0 || y >= i64::from(image.height()) || x >= i64::from(image.width()) {
+        return;
+    }
+    let (x, y) = (x as u32, y as u32);
+    match image {
+        DynamicImage::ImageLuma8(img) => img.put_pixel(x, y, image::Luma([u8::MAX])),
+        DynamicImage::ImageLuma16(img) => img.put_pixel(x, y, image::Luma([u16::MAX])),
+        DynamicImage::ImageRgb8(img) => img.put_pixel(x, y, image::Rgb([u8::MAX; 3])),
+        DynamicImage::ImageRgb16(img) => img.put_pixel(x, y, image::Rgb([u16::MAX; 3])),
+        _ => {}
+    }
+}
+
+/// Pack the given sequence of bits into bytes,
+/// starting from the least significant bit.
+pub(crate) fn pack_bits(bits: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (i, bit) in bits.into_iter().enumerate() {
+        if i % 8 == 0 {
+            out.push(0);
+        }
+        if bit {
+            *out.last_mut().unwrap() |= 1 << (i % 8);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack_bits, Overlay, OverlayType};
+
+    /// 3x4 overlay with 2 frames, applying from the second image frame
+    fn overlay() -> Overlay {
+        #[rustfmt::skip]
+        let bits = [
+            // frame 1
+            true, false, false, false,
+            false, true, false, false,
+            false, false, true, false,
+            // frame 2
+            false, false, false, true,
+            false, false, false, true,
+            false, false, false, true,
+        ];
+        Overlay::new(
+            0x6002,
+            3,
+            4,
+            OverlayType::Graphics,
+            [1, 1],
+            2,
+            2,
+            None,
+            None,
+            None,
+            pack_bits(bits.iter().copied()),
+        )
+    }
+
+    #[test]
+    fn overlay_bitmap_by_frame() {
+        let overlay = overlay();
+        assert_eq!(overlay.bitmap(0), None);
+        assert_eq!(overlay.bitmap(3), None);
+
+        let bitmap = overlay.bitmap(1).unwrap();
+        assert_eq!(bitmap.len(), 12);
+        assert_eq!(bitmap.iter().filter(|b| **b).count(), 3);
+        assert!(overlay.get(1, 2, 2));
+        assert!(!overlay.get(1, 2, 3));
+
+        assert!(overlay.get(2, 0, 3));
+        assert!(overlay.get(2, 2, 3));
+        assert!(!overlay.get(2, 0, 0));
+        // out of bounds
+        assert!(!overlay.get(2, 3, 3));
+    }
+
+    #[test]
+    fn overlay_select_frame() {
+        let overlay = overlay().select_frame(2).unwrap();
+        assert_eq!(overlay.number_of_frames(), 1);
+        assert_eq!(overlay.image_frame_origin(), 1);
+        assert!(overlay.get(0, 1, 3));
+        assert!(!overlay.get(0, 1, 1));
+        assert_eq!(overlay.bitmap(1), None);
+    }
+
+    #[test]
+    fn overlay_extract_embedded() {
+        let mut overlay = Overlay::new(
+            0x6000,
+            2,
+            2,
+            OverlayType::Graphics,
+            [1, 1],
+            1,
+            1,
+            None,
+            None,
+            Some(12),
+            Vec::new(),
+        );
+        let pixels: Vec<u8> = [0x0FFF_u16, 0x1000, 0x1ABC, 0x0000]
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+        overlay.extract_embedded(&pixels, 16, 1, 1);
+        assert!(overlay.is_embedded());
+        assert_eq!(overlay.bitmap(0), Some(vec![false, true, true, false]));
+    }
+}
@@ -0,0 +1,225 @@
+//! Palette color look-up table implementation.
+//!
+//! This module contains the [`PaletteColorLut`] data type,
+//! which turns the sample values of images
+//! with the `PALETTE COLOR` photometric interpretation
+//! into red, green, and blue values.
+//!
+//! The type also provides the expansion of segmented palette color LUT data,
+//! as described in [section C.7.9.2][1] of the standard.
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_C.7.9.2.html
+
+/// A palette color look-up table,
+/// mapping each stored sample value to a red, green, and blue value.
+///
+/// Each channel is kept in its expanded form,
+/// regardless of whether it was encoded as
+/// regular or segmented palette color LUT data.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_pixeldata::PaletteColorLut;
+/// let lut = PaletteColorLut::new(
+///     16,
+///     8,
+///     vec![0, 128, 255],
+///     vec![0, 64, 0],
+///     vec![255, 32, 0],
+/// );
+///
+/// assert_eq!(lut.get(17), [128, 64, 32]);
+/// // values outside of the table are clamped to the first or last entry
+/// assert_eq!(lut.get(0), [0, 0, 255]);
+/// assert_eq!(lut.get(99), [255, 0, 0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteColorLut {
+    /// the sample value mapped to the first entry of each table
+    first_mapped: i32,
+    /// the number of bits of each entry (8 or 16)
+    bits: u16,
+    red: Vec<u16>,
+    green: Vec<u16>,
+    blue: Vec<u16>,
+}
+
+impl PaletteColorLut {
+    /// Create a new palette color LUT
+    /// from the first mapped sample value,
+    /// the number of bits per entry,
+    /// and the expanded red, green, and blue tables.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the tables is empty.
+    pub fn new(
+        first_mapped: i32,
+        bits: u16,
+        red: Vec<u16>,
+        green: Vec<u16>,
+        blue: Vec<u16>,
+    ) -> Self {
+        assert!(
+            !red.is_empty() && !green.is_empty() && !blue.is_empty(),
+            "palette color LUT tables must not be empty"
+        );
+        PaletteColorLut {
+            first_mapped,
+            bits,
+            red,
+            green,
+            blue,
+        }
+    }
+
+    /// Get the sample value mapped to the first entry of the table.
+    pub fn first_mapped_value(&self) -> i32 {
+        self.first_mapped
+    }
+
+    /// Get the number of bits of each entry in the table,
+    /// which is either 8 or 16.
+    pub fn bits_per_entry(&self) -> u16 {
+        self.bits
+    }
+
+    /// Get the expanded table of red values.
+    pub fn red(&self) -> &[u16] {
+        &self.red
+    }
+
+    /// Get the expanded table of green values.
+    pub fn green(&self) -> &[u16] {
+        &self.green
+    }
+
+    /// Get the expanded table of blue values.
+    pub fn blue(&self) -> &[u16] {
+        &self.blue
+    }
+
+    /// Obtain the red, green, and blue values of the given sample value.
+    ///
+    /// Sample values below the first mapped value
+    /// are mapped to the first entry,
+    /// and sample values beyond the end of the table
+    /// are mapped to the last entry.
+    pub fn get(&self, value: i32) -> [u16; 3] {
+        let index = (value as i64 - self.first_mapped as i64).max(0) as usize;
+        [
+            lookup(&self.red, index),
+            lookup(&self.green, index),
+            lookup(&self.blue, index),
+        ]
+    }
+}
+
+#[inline]
+fn lookup(table: &[u16], index: usize) -> u16 {
+    table[index.min(table.len() - 1)]
+}
+
+/// Expand the given segmented palette color LUT data
+/// into the full list of table entries.
+///
+/// Returns `None` if the data is malformed.
+pub(crate) fn expand_segmented_lut(data: &[u16]) -> Option<Vec<u16>> {
+    let mut out = Vec::new();
+    expand_segments(data, usize::MAX, &mut out, true)?;
+    Some(out)
+}
+
+/// Expand up to `max_segments` segments from the given data into `out`.
+///
+/// Indirect segments may not refer to other indirect segments.
+fn expand_segments(
+    data: &[u16],
+    max_segments: usize,
+    out: &mut Vec<u16>,
+    allow_indirect: bool,
+) -> Option<()> {
+    let mut i = 0;
+    let mut segments = 0;
+    while i < data.len() && segments < max_segments {
+        let opcode = data[i];
+        let length = *data.get(i + 1)? as usize;
+        i += 2;
+        match opcode {
+            // discrete segment
+            0 => {
+                out.extend_from_slice(data.get(i..i + length)?);
+                i += length;
+            }
+            // linear segment
+            1 => {
+                let y0 = *out.last()? as f64;
+                let y1 = *data.get(i)? as f64;
+                let step = (y1 - y0) / length as f64;
+                out.extend((1..=length).map(|k| (y0 + step * k as f64).round() as u16));
+                i += 1;
+            }
+            // indirect segment
+            2 if allow_indirect => {
+                let offset = *data.get(i)? as usize | (*data.get(i + 1)? as usize) << 16;
+                expand_segments(data.get(offset..)?, length, out, false)?;
+                i += 2;
+            }
+            _ => return None,
+        }
+        segments += 1;
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_segmented_lut;
+
+    #[test]
+    fn expand_discrete_and_linear_segments() {
+        #[rustfmt::skip]
+        let data = [
+            // discrete segment of 2 entries
+            0, 2, 0, 10,
+            // linear segment of 4 entries up to 50
+            1, 4, 50,
+            // discrete segment of 1 entry
+            0, 1, 7,
+        ];
+
+        assert_eq!(
+            expand_segmented_lut(&data),
+            Some(vec![0, 10, 20, 30, 40, 50, 7]),
+        );
+    }
+
+    #[test]
+    fn expand_indirect_segment() {
+        #[rustfmt::skip]
+        let data = [
+            // discrete segment of 3 entries
+            0, 3, 1, 2, 3,
+            // linear segment of 2 entries up to 5
+            1, 2, 5,
+            // copy both segments from the start (word offset 0)
+            2, 2, 0, 0,
+        ];
+
+        assert_eq!(
+            expand_segmented_lut(&data),
+            Some(vec![1, 2, 3, 4, 5, 1, 2, 3, 4, 5]),
+        );
+    }
+
+    #[test]
+    fn reject_malformed_segments() {
+        // linear segment without a previous value
+        assert_eq!(expand_segmented_lut(&[1, 2, 5]), None);
+        // discrete segment past the end of the data
+        assert_eq!(expand_segmented_lut(&[0, 4, 1, 2]), None);
+        // unknown opcode
+        assert_eq!(expand_segmented_lut(&[3, 1, 0]), None);
+    }
+}
@@ -0,0 +1,1511 @@
+//! Grayscale softcopy presentation state implementation.
+//!
+//! This module contains the [`PresentationState`] data type,
+//! which collects the parts of a
+//! _Grayscale Softcopy Presentation State_ (GSPS) object
+//! affecting how the referenced images are displayed
+//! (see [section A.33.1][1] of the standard):
+//!
+//! - the Modality LUT, as a rescale function or a look-up table;
+//! - the Softcopy VOI LUT, as a window level or a look-up table;
+//! - the Presentation LUT shape;
+//! - the displayed area;
+//! - the image rotation and horizontal flip;
+//! - and the graphic and text annotations.
+//!
+//! With the `image` feature,
+//! [`render`](PresentationState::render)
+//! applies all of them to produce a dynamic image.
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_A.33.html
+
+use dicom_core::{value::ConvertValueError, DataDictionary, Tag};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::{ResultExt, Snafu};
+use std::convert::TryFrom;
+
+use crate::attribute::{lut_table, GetAttributeError};
+use crate::transform::{LutTable, Rescale, VoiLutFunction, WindowLevel};
+
+#[cfg(feature = "image")]
+use crate::{
+    attribute::PhotometricInterpretation, overlay::put_white_pixel, ConvertOptions,
+    ModalityLutOption, PixelDecoder, VoiLutOption,
+};
+#[cfg(feature = "image")]
+use dicom_object::FileDicomObject;
+#[cfg(feature = "image")]
+use image::{imageops::FilterType, DynamicImage, ImageBuffer, Pixel};
+
+/// An error occurred while reading or applying a presentation state.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Could not convert the value of attribute `{name}`
+    ConvertValue {
+        name: &'static str,
+        source: ConvertValueError,
+    },
+
+    /// Invalid value of attribute `{name}`: {value}
+    InvalidValue { name: &'static str, value: String },
+
+    /// Could not read the look-up table of `{name}`
+    ReadLut {
+        name: &'static str,
+        source: GetAttributeError,
+    },
+
+    /// Could not decode the pixel data of the image
+    #[cfg(feature = "image")]
+    DecodePixelData { source: crate::Error },
+
+    /// Could not convert the pixel data to an image
+    #[cfg(feature = "image")]
+    ConvertImage { source: crate::Error },
+}
+
+/// Alias for the result of reading or applying a presentation state.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The shape of the Presentation LUT,
+/// applied after the VOI LUT.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PresentationLutShape {
+    /// `IDENTITY`: the output of the VOI LUT is displayed as is,
+    /// with higher values being brighter
+    #[default]
+    Identity,
+    /// `INVERSE`: the output of the VOI LUT is inverted,
+    /// with higher values being darker
+    Inverse,
+}
+
+/// How the displayed area is meant to be presented.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PresentationSizeMode {
+    /// `SCALE TO FIT`: the displayed area should fill the display
+    ScaleToFit,
+    /// `TRUE SIZE`: the displayed area should be shown in its physical size
+    TrueSize,
+    /// `MAGNIFY`: the displayed area is magnified by the given ratio
+    /// of displayed pixels to image pixels
+    Magnify(f32),
+}
+
+/// The units of the coordinates of an annotation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AnnotationUnits {
+    /// `PIXEL`: image relative coordinates,
+    /// where `[0, 0]` is the top left corner of the top left pixel
+    /// and `[1, 1]` is the bottom right corner of the same pixel
+    Pixel,
+    /// `DISPLAY`: coordinates relative to the displayed area,
+    /// where `[0, 0]` is its top left corner
+    /// and `[1, 1]` is its bottom right corner
+    Display,
+}
+
+/// The kind of shape of a graphic object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GraphicType {
+    /// A single point
+    Point,
+    /// A series of connected line segments
+    Polyline,
+    /// A series of points to be connected by a smooth curve,
+    /// rendered here as a polyline
+    Interpolated,
+    /// A circle, defined by its center and a point on its perimeter
+    Circle,
+    /// An ellipse, defined by the end points of its major axis
+    /// and the end points of its minor axis
+    Ellipse,
+}
+
+/// A Softcopy VOI LUT to apply to some or all of the referenced images.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SoftcopyVoi {
+    /// the VOI transformation
+    pub transform: SoftcopyVoiTransform,
+    references: Vec<ImageReference>,
+}
+
+/// The VOI transformation of a Softcopy VOI LUT.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoftcopyVoiTransform {
+    /// A window level,
+    /// applied with the given VOI LUT function
+    Window(WindowLevel, VoiLutFunction),
+    /// The first look-up table of the _VOI LUT Sequence_
+    Table(LutTable),
+}
+
+/// The area of an image to be displayed.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct DisplayedArea {
+    /// the top left corner of the area as `[column, row]`,
+    /// where `[1, 1]` is the top left pixel of the image
+    pub top_left: [i32; 2],
+    /// the bottom right corner of the area as `[column, row]`,
+    /// inclusive
+    pub bottom_right: [i32; 2],
+    /// how the area is meant to be presented
+    pub size_mode: PresentationSizeMode,
+    references: Vec<ImageReference>,
+}
+
+/// A graphic annotation,
+/// comprising graphic and text objects on a graphic layer.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct GraphicAnnotation {
+    /// the graphic layer of the annotation
+    pub layer: String,
+    /// the graphic objects of the annotation
+    pub graphic_objects: Vec<GraphicObject>,
+    /// the text objects of the annotation
+    pub text_objects: Vec<TextObject>,
+    references: Vec<ImageReference>,
+}
+
+/// A graphic object, such as a line or a circle.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct GraphicObject {
+    /// the units of the points
+    pub units: AnnotationUnits,
+    /// the kind of shape of the object
+    pub graphic_type: GraphicType,
+    /// the points of the object as `[column, row]`
+    pub points: Vec<[f32; 2]>,
+    /// whether the shape is filled
+    pub filled: bool,
+}
+
+/// A text object, placed in a bounding box
+/// and/or tied to an anchor point.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TextObject {
+    /// the text to display
+    pub text: String,
+    /// the units and the top left and bottom right corners
+    /// of the bounding box of the text, as `[column, row]`
+    pub bounding_box: Option<(AnnotationUnits, [[f32; 2]; 2])>,
+    /// the units and the position of the anchor point, as `[column, row]`,
+    /// and whether the anchor point should be visible
+    pub anchor_point: Option<(AnnotationUnits, [f32; 2], bool)>,
+}
+
+/// A reference to an image, or to some of its frames.
+#[derive(Debug, Clone, PartialEq)]
+struct ImageReference {
+    sop_instance_uid: String,
+    /// the referenced frame numbers (1-based), or empty for all frames
+    frames: Vec<u32>,
+}
+
+/// Check whether an item with the given image references
+/// applies to the given image and frame number (0-based).
+///
+/// Items without references apply to all images.
+fn applies_to(references: &[ImageReference], sop_instance_uid: &str, frame: u32) -> bool {
+    references.is_empty()
+        || references.iter().any(|r| {
+            r.sop_instance_uid == sop_instance_uid
+                && (r.frames.is_empty() || r.frames.contains(&(frame + 1)))
+        })
+}
+
+/// The display parameters of a grayscale softcopy presentation state.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// use dicom_object::open_file;
+/// use dicom_pixeldata::{ConvertOptions, PresentationState};
+/// # #[cfg(feature = "image")]
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let gsps = open_file("gsps.dcm")?;
+/// let presentation_state = PresentationState::from_obj(&gsps)?;
+///
+/// let obj = open_file("dicom.dcm")?;
+/// let image = presentation_state.render(&obj, 0, &ConvertOptions::new())?;
+/// image.save("out.png")?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "image"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresentationState {
+    rescale: Option<Rescale>,
+    modality_lut: Option<LutTable>,
+    softcopy_voi: Vec<SoftcopyVoi>,
+    presentation_lut_shape: PresentationLutShape,
+    displayed_areas: Vec<DisplayedArea>,
+    image_rotation: u16,
+    image_horizontal_flip: bool,
+    graphic_annotations: Vec<GraphicAnnotation>,
+}
+
+impl PresentationState {
+    /// Read the display parameters from a presentation state object.
+    ///
+    /// A _Presentation LUT Sequence_ is not supported,
+    /// and is replaced with the identity shape.
+    /// A Softcopy VOI LUT item
+    /// without a window level nor a _VOI LUT Sequence_ is ignored.
+    pub fn from_obj<D>(obj: &InMemDicomObject<D>) -> Result<Self>
+    where
+        D: DataDictionary + Clone,
+    {
+        let slope = float(obj, tags::RESCALE_SLOPE, "RescaleSlope")?;
+        let intercept = float(obj, tags::RESCALE_INTERCEPT, "RescaleIntercept")?;
+        let rescale = match (slope, intercept) {
+            (None, None) => None,
+            (slope, intercept) => Some(Rescale::new(slope.unwrap_or(1.), intercept.unwrap_or(0.))),
+        };
+        let modality_lut = first_lut(obj, tags::MODALITY_LUT_SEQUENCE, "ModalityLUTSequence")?;
+
+        let mut softcopy_voi = Vec::new();
+        for item in items(obj, tags::SOFTCOPY_VOILUT_SEQUENCE) {
+            let center = float(item, tags::WINDOW_CENTER, "WindowCenter")?;
+            let width = float(item, tags::WINDOW_WIDTH, "WindowWidth")?;
+            let transform = match (center, width) {
+                (Some(center), Some(width)) => {
+                    let function = match string(item, tags::VOILUT_FUNCTION, "VOILUTFunction")? {
+                        Some(function) => VoiLutFunction::try_from(function.as_str())
+                            .ok()
+                            .ok_or_else(|| invalid_value("VOILUTFunction", function))?,
+                        None => VoiLutFunction::Linear,
+                    };
+                    SoftcopyVoiTransform::Window(WindowLevel { center, width }, function)
+                }
+                _ => match first_lut(item, tags::VOILUT_SEQUENCE, "VOILUTSequence")? {
+                    Some(table) => SoftcopyVoiTransform::Table(table),
+                    None => {
+                        tracing::warn!(
+                            "Softcopy VOI LUT without a window level nor a VOI LUT Sequence, ignoring"
+                        );
+                        continue;
+                    }
+                },
+            };
+            softcopy_voi.push(SoftcopyVoi {
+                transform,
+                references: references(item)?,
+            });
+        }
+
+        let presentation_lut_shape =
+            match string(obj, tags::PRESENTATION_LUT_SHAPE, "PresentationLUTShape")?.as_deref() {
+                Some("IDENTITY") => PresentationLutShape::Identity,
+                Some("INVERSE") => PresentationLutShape::Inverse,
+                Some(shape) => return Err(invalid_value("PresentationLUTShape", shape)),
+                None => {
+                    if obj.get(tags::PRESENTATION_LUT_SEQUENCE).is_some() {
+                        tracing::warn!("Presentation LUT Sequence is not supported, ignoring");
+                    }
+                    PresentationLutShape::Identity
+                }
+            };
+
+        let mut displayed_areas = Vec::new();
+        for item in items(obj, tags::DISPLAYED_AREA_SELECTION_SEQUENCE) {
+            let top_left = point(
+                item,
+                tags::DISPLAYED_AREA_TOP_LEFT_HAND_CORNER,
+                "DisplayedAreaTopLeftHandCorner",
+            )?;
+            let bottom_right = point(
+                item,
+                tags::DISPLAYED_AREA_BOTTOM_RIGHT_HAND_CORNER,
+                "DisplayedAreaBottomRightHandCorner",
+            )?;
+            let (top_left, bottom_right) = match (top_left, bottom_right) {
+                (Some(top_left), Some(bottom_right)) => (top_left, bottom_right),
+                _ => continue,
+            };
+            let size_mode = match string(
+                item,
+                tags::PRESENTATION_SIZE_MODE,
+                "PresentationSizeMode",
+            )?
+            .as_deref()
+            {
+                Some("TRUE SIZE") => PresentationSizeMode::TrueSize,
+                Some("MAGNIFY") => PresentationSizeMode::Magnify(
+                    float(
+                        item,
+                        tags::PRESENTATION_PIXEL_MAGNIFICATION_RATIO,
+                        "PresentationPixelMagnificationRatio",
+                    )?
+                    .unwrap_or(1.) as f32,
+                ),
+                Some("SCALE TO FIT") | None => PresentationSizeMode::ScaleToFit,
+                Some(mode) => return Err(invalid_value("PresentationSizeMode", mode)),
+            };
+            displayed_areas.push(DisplayedArea {
+                top_left,
+                bottom_right,
+                size_mode,
+                references: references(item)?,
+            });
+        }
+
+        let image_rotation = match obj.get(tags::IMAGE_ROTATION) {
+            Some(e) => e.to_int::<u16>().context(ConvertValueSnafu {
+                name: "ImageRotation",
+            })?,
+            None => 0,
+        };
+        if ![0, 90, 180, 270].contains(&image_rotation) {
+            return Err(invalid_value("ImageRotation", image_rotation));
+        }
+        let image_horizontal_flip =
+            string(obj, tags::IMAGE_HORIZONTAL_FLIP, "ImageHorizontalFlip")?.as_deref()
+                == Some("Y");
+
+        let mut graphic_annotations = Vec::new();
+        for item in items(obj, tags::GRAPHIC_ANNOTATION_SEQUENCE) {
+            let mut graphic_objects = Vec::new();
+            for object in items(item, tags::GRAPHIC_OBJECT_SEQUENCE) {
+                graphic_objects.push(graphic_object(object)?);
+            }
+            let mut text_objects = Vec::new();
+            for object in items(item, tags::TEXT_OBJECT_SEQUENCE) {
+                text_objects.push(text_object(object)?);
+            }
+            graphic_annotations.push(GraphicAnnotation {
+                layer: string(item, tags::GRAPHIC_LAYER, "GraphicLayer")?.unwrap_or_default(),
+                graphic_objects,
+                text_objects,
+                references: references(item)?,
+            });
+        }
+
+        Ok(PresentationState {
+            rescale,
+            modality_lut,
+            softcopy_voi,
+            presentation_lut_shape,
+            displayed_areas,
+            image_rotation,
+            image_horizontal_flip,
+            graphic_annotations,
+        })
+    }
+
+    /// Get the rescale function replacing the one of the images,
+    /// if the presentation state defines one.
+    pub fn rescale(&self) -> Option<Rescale> {
+        self.rescale
+    }
+
+    /// Get the look-up table of the _Modality LUT Sequence_
+    /// replacing the modality LUT of the images,
+    /// if the presentation state defines one.
+    /// It takes precedence over the rescale function.
+    pub fn modality_lut(&self) -> Option<&LutTable> {
+        self.modality_lut.as_ref()
+    }
+
+    /// Get the Softcopy VOI LUT
+    /// applying to the given image and frame number (0-based),
+    /// if any.
+    pub fn softcopy_voi(&self, sop_instance_uid: &str, frame: u32) -> Option<&SoftcopyVoi> {
+        self.softcopy_voi
+            .iter()
+            .find(|voi| applies_to(&voi.references, sop_instance_uid, frame))
+    }
+
+    /// Get the shape of the Presentation LUT.
+    pub fn presentation_lut_shape(&self) -> PresentationLutShape {
+        self.presentation_lut_shape
+    }
+
+    /// Get the displayed area
+    /// applying to the given image and frame number (0-based),
+    /// if any.
+    pub fn displayed_area(&self, sop_instance_uid: &str, frame: u32) -> Option<&DisplayedArea> {
+        self.displayed_areas
+            .iter()
+            .find(|area| applies_to(&area.references, sop_instance_uid, frame))
+    }
+
+    /// Get the clockwise rotation of the images in degrees
+    /// (0, 90, 180, or 270),
+    /// applied after the horizontal flip.
+    pub fn image_rotation(&self) -> u16 {
+        self.image_rotation
+    }
+
+    /// Whether the images are flipped horizontally.
+    pub fn image_horizontal_flip(&self) -> bool {
+        self.image_horizontal_flip
+    }
+
+    /// Iterate over the graphic annotations
+    /// applying to the given image and frame number (0-based).
+    pub fn graphic_annotations<'a>(
+        &'a self,
+        sop_instance_uid: &'a str,
+        frame: u32,
+    ) -> impl Iterator<Item = &'a GraphicAnnotation> + 'a {
+        self.graphic_annotations
+            .iter()
+            .filter(move |a| applies_to(&a.references, sop_instance_uid, frame))
+    }
+
+    /// Render a frame (0-based) of the given image
+    /// as described by the presentation state.
+    ///
+    /// The modality LUT and VOI LUT options are replaced
+    /// with the Modality LUT and Softcopy VOI LUT of the presentation state,
+    /// and the other conversion options are applied as usual.
+    /// The output image is then inverted according to the Presentation LUT shape,
+    /// cropped to the displayed area,
+    /// flipped and rotated,
+    /// and the annotations are drawn in white.
+    #[cfg(feature = "image")]
+    pub fn render<D>(
+        &self,
+        image: &FileDicomObject<InMemDicomObject<D>>,
+        frame: u32,
+        options: &ConvertOptions,
+    ) -> Result<DynamicImage>
+    where
+        D: DataDictionary + Clone,
+    {
+        let sop_instance_uid =
+            string(image, tags::SOP_INSTANCE_UID, "SOPInstanceUID")?.unwrap_or_default();
+        let sop_instance_uid = sop_instance_uid.as_str();
+
+        let pixel_data = image
+            .decode_pixel_data_frame(frame)
+            .context(DecodePixelDataSnafu)?;
+        let mut options = options.clone();
+        options.modality_lut = match &self.modality_lut {
+            Some(table) => ModalityLutOption::Lut(table.clone()),
+            None => ModalityLutOption::Override(self.rescale.unwrap_or(Rescale::new(1., 0.))),
+        };
+        options.voi_lut = match self
+            .softcopy_voi(sop_instance_uid, frame)
+            .map(|v| &v.transform)
+        {
+            Some(SoftcopyVoiTransform::Window(window, function)) => {
+                VoiLutOption::CustomWithFunction(*window, *function)
+            }
+            Some(SoftcopyVoiTransform::Table(table)) => VoiLutOption::Lut(table.clone()),
+            None => VoiLutOption::Identity,
+        };
+        let mut out = pixel_data
+            .to_dynamic_image_with_options(0, &options)
+            .context(ConvertImageSnafu)?;
+
+        // the conversion already inverts MONOCHROME1 images,
+        // whereas the presentation LUT shape applies regardless
+        let inverted =
+            *pixel_data.photometric_interpretation() == PhotometricInterpretation::Monochrome1;
+        if inverted != (self.presentation_lut_shape == PresentationLutShape::Inverse) {
+            out.invert();
+        }
+
+        let annotations: Vec<_> = self.graphic_annotations(sop_instance_uid, frame).collect();
+        for annotation in &annotations {
+            draw_annotation(&mut out, annotation, AnnotationUnits::Pixel, |p| p);
+        }
+
+        let area = self.displayed_area(sop_instance_uid, frame);
+        if let Some(area) = area {
+            out = crop(&out, area.top_left, area.bottom_right);
+        }
+        if self.image_horizontal_flip {
+            out = out.fliph();
+        }
+        out = match self.image_rotation {
+            90 => out.rotate90(),
+            180 => out.rotate180(),
+            270 => out.rotate270(),
+            _ => out,
+        };
+        if let Some(PresentationSizeMode::Magnify(ratio)) = area.map(|a| a.size_mode) {
+            if ratio > 0. && ratio != 1. {
+                let width = (out.width() as f32 * ratio).round().max(1.) as u32;
+                let height = (out.height() as f32 * ratio).round().max(1.) as u32;
+                out = out.resize_exact(width, height, FilterType::Triangle);
+            }
+        }
+
+        let (width, height) = (out.width() as f32, out.height() as f32);
+        for annotation in &annotations {
+            draw_annotation(&mut out, annotation, AnnotationUnits::Display, |[x, y]| {
+                [x * width, y * height]
+            });
+        }
+
+        Ok(out)
+    }
+}
+
+/// Read the look-up table in the first item of the given sequence,
+/// if there is one.
+///
+/// The first mapped value of the LUT descriptor is read as is,
+/// since the pixel representation of the images is not known here.
+fn first_lut<D>(obj: &InMemDicomObject<D>, tag: Tag, name: &'static str) -> Result<Option<LutTable>>
+where
+    D: DataDictionary + Clone,
+{
+    items(obj, tag)
+        .next()
+        .map(|item| lut_table(item, false).context(ReadLutSnafu { name }))
+        .transpose()
+        .map_err(Error::from)
+}
+
+fn invalid_value(name: &'static str, value: impl std::fmt::Display) -> Error {
+    Error(InnerError::InvalidValue {
+        name,
+        value: value.to_string(),
+    })
+}
+
+/// Iterate over the items of the given sequence, if present.
+fn items<D>(obj: &InMemDicomObject<D>, tag: Tag) -> impl Iterator<Item = &InMemDicomObject<D>>
+where
+    D: DataDictionary + Clone,
+{
+    obj.get(tag)
+        .and_then(|e| e.items())
+        .unwrap_or_default()
+        .iter()
+}
+
+/// Read a trimmed, non-empty string value.
+fn string<D>(obj: &InMemDicomObject<D>, tag: Tag, name: &'static str) -> Result<Option<String>>
+where
+    D: DataDictionary + Clone,
+{
+    let e = match obj.get(tag) {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    let value = e.to_str().context(ConvertValueSnafu { name })?;
+    let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    Ok(Some(value.to_string()).filter(|v| !v.is_empty()))
+}
+
+/// Read the first value of a numeric attribute.
+fn float<D>(obj: &InMemDicomObject<D>, tag: Tag, name: &'static str) -> Result<Option<f64>>
+where
+    D: DataDictionary + Clone,
+{
+    match obj.get(tag) {
+        Some(e) => Ok(Some(e.to_float64().context(ConvertValueSnafu { name })?)),
+        None => Ok(None),
+    }
+}
+
+/// Read a pair of integers.
+fn point<D>(obj: &InMemDicomObject<D>, tag: Tag, name: &'static str) -> Result<Option<[i32; 2]>>
+where
+    D: DataDictionary + Clone,
+{
+    let e = match obj.get(tag) {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    match e
+        .to_multi_int::<i32>()
+        .context(ConvertValueSnafu { name })?[..]
+    {
+        [x, y] => Ok(Some([x, y])),
+        ref values => Err(invalid_value(name, format!("{:?}", values))),
+    }
+}
+
+/// Read a list of pairs of floating point numbers.
+fn float_points<D>(
+    obj: &InMemDicomObject<D>,
+    tag: Tag,
+    name: &'static str,
+) -> Result<Option<Vec<[f32; 2]>>>
+where
+    D: DataDictionary + Clone,
+{
+    let e = match obj.get(tag) {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    let values = e.to_multi_float32().context(ConvertValueSnafu { name })?;
+    if values.len() % 2 != 0 {
+        return Err(invalid_value(name, format!("{:?}", values)));
+    }
+    Ok(Some(values.chunks_exact(2).map(|p| [p[0], p[1]]).collect()))
+}
+
+fn annotation_units<D>(
+    obj: &InMemDicomObject<D>,
+    tag: Tag,
+    name: &'static str,
+) -> Result<AnnotationUnits>
+where
+    D: DataDictionary + Clone,
+{
+    match string(obj, tag, name)?.as_deref() {
+        Some("PIXEL") => Ok(AnnotationUnits::Pixel),
+        Some("DISPLAY") => Ok(AnnotationUnits::Display),
+        value => Err(invalid_value(name, value.unwrap_or_default())),
+    }
+}
+
+/// Read the images referenced by an item of the presentation state.
+fn references<D>(item: &InMemDicomObject<D>) -> Result<Vec<ImageReference>>
+where
+    D: DataDictionary + Clone,
+{
+    let mut out = Vec::new();
+    for reference in items(item, tags::REFERENCED_IMAGE_SEQUENCE) {
+        let sop_instance_uid = string(
+            reference,
+            tags::REFERENCED_SOP_INSTANCE_UID,
+            "ReferencedSOPInstanceUID",
+        )?
+        .unwrap_or_default();
+        let frames = match reference.get(tags::REFERENCED_FRAME_NUMBER) {
+            Some(e) => e.to_multi_int::<u32>().context(ConvertValueSnafu {
+                name: "ReferencedFrameNumber",
+            })?,
+            None => Vec::new(),
+        };
+        out.push(ImageReference {
+            sop_instance_uid,
+            frames,
+        });
+    }
+    Ok(out)
+}
+
+fn graphic_object<D>(obj: &InMemDicomObject<D>) -> Result<GraphicObject>
+where
+    D: DataDictionary + Clone,
+{
+    let units = annotation_units(
+        obj,
+        tags::GRAPHIC_ANNOTATION_UNITS,
+        "GraphicAnnotationUnits",
+    )?;
+    let graphic_type = match string(obj, tags::GRAPHIC_TYPE, "GraphicType")?.as_deref() {
+        Some("POINT") => GraphicType::Point,
+        Some("POLYLINE") => GraphicType::Polyline,
+        Some("INTERPOLATED") => GraphicType::Interpolated,
+        Some("CIRCLE") => GraphicType::Circle,
+        Some("ELLIPSE") => GraphicType::Ellipse,
+        value => return Err(invalid_value("GraphicType", value.unwrap_or_default())),
+    };
+    let points = float_points(obj, tags::GRAPHIC_DATA, "GraphicData")?.unwrap_or_default();
+    let filled = string(obj, tags::GRAPHIC_FILLED, "GraphicFilled")?.as_deref() == Some("Y");
+    Ok(GraphicObject {
+        units,
+        graphic_type,
+        points,
+        filled,
+    })
+}
+
+fn text_object<D>(obj: &InMemDicomObject<D>) -> Result<TextObject>
+where
+    D: DataDictionary + Clone,
+{
+    let text =
+        string(obj, tags::UNFORMATTED_TEXT_VALUE, "UnformattedTextValue")?.unwrap_or_default();
+    let top_left = float_points(
+        obj,
+        tags::BOUNDING_BOX_TOP_LEFT_HAND_CORNER,
+        "BoundingBoxTopLeftHandCorner",
+    )?;
+    let bottom_right = float_points(
+        obj,
+        tags::BOUNDING_BOX_BOTTOM_RIGHT_HAND_CORNER,
+        "BoundingBoxBottomRightHandCorner",
+    )?;
+    let bounding_box = match (top_left.as_deref(), bottom_right.as_deref()) {
+        (Some([top_left]), Some([bottom_right])) => Some((
+            annotation_units(
+                obj,
+                tags::BOUNDING_BOX_ANNOTATION_UNITS,
+                "BoundingBoxAnnotationUnits",
+            )?,
+            [*top_left, *bottom_right],
+        )),
+        _ => None,
+    };
+    let anchor_point = match float_points(obj, tags::ANCHOR_POINT, "AnchorPoint")?.as_deref() {
+        Some([point]) => Some((
+            annotation_units(
+                obj,
+                tags::ANCHOR_POINT_ANNOTATION_UNITS,
+                "AnchorPointAnnotationUnits",
+            )?,
+            *point,
+            string(obj, tags::ANCHOR_POINT_VISIBILITY, "AnchorPointVisibility")?.as_deref()
+                == Some("Y"),
+        )),
+        _ => None,
+    };
+    Ok(TextObject {
+        text,
+        bounding_box,
+        anchor_point,
+    })
+}
+
+/// Crop the image to the given area,
+/// given as 1-based inclusive `[column, row]` corners.
+///
+/// Parts of the area outside of the image are left black.
+#[cfg(feature = "image")]
+fn crop(image: &DynamicImage, top_left: [i32; 2], bottom_right: [i32; 2]) -> DynamicImage {
+    let x0 = i64::from(top_left[0].min(bottom_right[0])) - 1;
+    let y0 = i64::from(top_left[1].min(bottom_right[1])) - 1;
+    let width = (i64::from(top_left[0].max(bottom_right[0])) - x0) as u32;
+    let height = (i64::from(top_left[1].max(bottom_right[1])) - y0) as u32;
+
+    fn pad<P: Pixel>(
+        image: &ImageBuffer<P, Vec<P::Subpixel>>,
+        width: u32,
+        height: u32,
+        x0: i64,
+        y0: i64,
+    ) -> ImageBuffer<P, Vec<P::Subpixel>> {
+        let mut out = ImageBuffer::new(width, height);
+        image::imageops::replace(&mut out, image, -x0, -y0);
+        out
+    }
+
+    match image {
+        DynamicImage::ImageLuma8(img) => pad(img, width, height, x0, y0).into(),
+        DynamicImage::ImageLuma16(img) => pad(img, width, height, x0, y0).into(),
+        DynamicImage::ImageRgb8(img) => pad(img, width, height, x0, y0).into(),
+        DynamicImage::ImageRgb16(img) => pad(img, width, height, x0, y0).into(),
+        // other image types are not produced by the decoder
+        _ => image.clone(),
+    }
+}
+
+/// Draw the objects of an annotation expressed in the given units,
+/// mapping their points to image coordinates with `map`.
+#[cfg(feature = "image")]
+fn draw_annotation(
+    image: &mut DynamicImage,
+    annotation: &GraphicAnnotation,
+    units: AnnotationUnits,
+    map: impl Fn([f32; 2]) -> [f32; 2],
+) {
+    for object in annotation.graphic_objects.iter() {
+        if object.units != units {
+            continue;
+        }
+        let points: Vec<_> = object.points.iter().map(|p| map(*p)).collect();
+        let outline = match (object.graphic_type, &points[..]) {
+            (GraphicType::Point, _) => {
+                for [x, y] in points.iter() {
+                    draw_line(image, [x - 1., *y], [x + 1., *y]);
+                    draw_line(image, [*x, y - 1.], [*x, y + 1.]);
+                }
+                continue;
+            }
+            (GraphicType::Polyline, _) | (GraphicType::Interpolated, _) => points.clone(),
+            (GraphicType::Circle, [center, edge, ..]) => {
+                let radius = distance(*center, *edge);
+                ellipse(*center, radius, radius, 0.)
+            }
+            (GraphicType::Ellipse, [a0, a1, b0, b1, ..]) => {
+                let center = [(a0[0] + a1[0]) / 2., (a0[1] + a1[1]) / 2.];
+                let angle = (a1[1] - a0[1]).atan2(a1[0] - a0[0]);
+                ellipse(
+                    center,
+                    distance(*a0, *a1) / 2.,
+                    distance(*b0, *b1) / 2.,
+                    angle,
+                )
+            }
+            _ => continue,
+        };
+        if object.filled {
+            fill_polygon(image, &outline);
+        }
+        for segment in outline.windows(2) {
+            draw_line(image, segment[0], segment[1]);
+        }
+    }
+
+    for object in annotation.text_objects.iter() {
+        let scale = 1 + image.height() / 256;
+        let bounding_box = object
+            .bounding_box
+            .filter(|(u, _)| *u == units)
+            .map(|(_, [a, b])| {
+                let (a, b) = (map(a), map(b));
+                [
+                    [a[0].min(b[0]), a[1].min(b[1])],
+                    [a[0].max(b[0]), a[1].max(b[1])],
+                ]
+            });
+        let anchor_point = object
+            .anchor_point
+            .filter(|(u, _, _)| *u == units)
+            .map(|(_, p, visible)| (map(p), visible));
+
+        match (bounding_box, anchor_point) {
+            (Some([top_left, bottom_right]), anchor_point) => {
+                draw_text(image, top_left, &object.text, scale);
+                if let Some(([x, y], true)) = anchor_point {
+                    // connect the anchor point to the closest point of the box
+                    let closest = [
+                        x.clamp(top_left[0], bottom_right[0]),
+                        y.clamp(top_left[1], bottom_right[1]),
+                    ];
+                    draw_line(image, [x, y], closest);
+                }
+            }
+            // without a bounding box in these units,
+            // place the text next to the anchor point
+            // unless the bounding box was drawn in the other units
+            (None, Some(([x, y], _))) if object.bounding_box.is_none() => {
+                let offset = 2. * scale as f32;
+                draw_text(image, [x + offset, y + offset], &object.text, scale);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+/// Approximate an ellipse with a closed polygon.
+#[cfg(feature = "image")]
+fn ellipse(center: [f32; 2], a: f32, b: f32, angle: f32) -> Vec<[f32; 2]> {
+    let steps = (a.max(b) * 2.).clamp(16., 360.) as usize;
+    let (sin, cos) = angle.sin_cos();
+    (0..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32 * std::f32::consts::TAU;
+            let (x, y) = (a * t.cos(), b * t.sin());
+            [center[0] + x * cos - y * sin, center[1] + x * sin + y * cos]
+        })
+        .collect()
+}
+
+#[cfg(feature = "image")]
+fn draw_line(image: &mut DynamicImage, [x0, y0]: [f32; 2], [x1, y1]: [f32; 2]) {
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.) as usize;
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let x = (x0 + (x1 - x0) * t).floor() as i64;
+        let y = (y0 + (y1 - y0) * t).floor() as i64;
+        put_white_pixel(image, x, y);
+    }
+}
+
+/// Fill a polygon using the even-odd rule,
+/// sampling at pixel centers.
+#[cfg(feature = "image")]
+fn fill_polygon(image: &mut DynamicImage, points: &[[f32; 2]]) {
+    if points.len() < 3 {
+        return;
+    }
+    let min_y = points.iter().map(|p| p[1]).fold(f32::INFINITY, f32::min);
+    let max_y = points
+        .iter()
+        .map(|p| p[1])
+        .fold(f32::NEG_INFINITY, f32::max);
+    let min_y = min_y.floor().max(0.) as i64;
+    let max_y = max_y.ceil().min(image.height() as f32) as i64;
+    for y in min_y..max_y {
+        let yc = y as f32 + 0.5;
+        let mut crossings: Vec<f32> = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .filter(|(a, b)| (a[1] <= yc) != (b[1] <= yc))
+            .map(|(a, b)| a[0] + (yc - a[1]) / (b[1] - a[1]) * (b[0] - a[0]))
+            .collect();
+        crossings.sort_by(f32::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            let start = (pair[0] - 0.5).ceil() as i64;
+            let end = (pair[1] - 0.5).floor() as i64;
+            for x in start..=end {
+                put_white_pixel(image, x, y);
+            }
+        }
+    }
+}
+
+/// Draw text with a small built-in 3x5 pixel font,
+/// starting from the given top left position.
+#[cfg(feature = "image")]
+fn draw_text(image: &mut DynamicImage, [x, y]: [f32; 2], text: &str, scale: u32) {
+    let scale = i64::from(scale);
+    let (x, y) = (x.floor() as i64, y.floor() as i64);
+    for (line_index, line) in text.lines().enumerate() {
+        let top = y + line_index as i64 * 6 * scale;
+        for (char_index, c) in line.chars().enumerate() {
+            let left = x + char_index as i64 * 4 * scale;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            put_white_pixel(
+                                image,
+                                left + column * scale + dx,
+                                top + row as i64 * scale + dy,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Obtain the rows of the 3x5 glyph of a character,
+/// with the most significant of the 3 bits on the left.
+///
+/// Letters are shown in upper case,
+/// and unknown characters are shown as a question mark.
+#[cfg(feature = "image")]
+#[rustfmt::skip]
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b110, 0b101, 0b010],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b010, 0b101, 0b010, 0b101, 0b010],
+        '9' => [0b010, 0b101, 0b011, 0b001, 0b110],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AnnotationUnits, GraphicType, PresentationLutShape, PresentationSizeMode,
+        PresentationState, SoftcopyVoiTransform,
+    };
+    use crate::transform::{LutTable, Rescale, VoiLutFunction, WindowLevel};
+    use dicom_core::{dicom_value, value::DataSetSequence, DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::tags;
+    use dicom_object::InMemDicomObject;
+
+    const IMAGE_UID: &str = "2.25.170934836404564466524153785361462146402";
+
+    fn referenced_image(frame: Option<i32>) -> DataElement<InMemDicomObject> {
+        let mut item = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::REFERENCED_SOP_INSTANCE_UID,
+            VR::UI,
+            dicom_value!(Str, IMAGE_UID),
+        )]);
+        if let Some(frame) = frame {
+            item.put(DataElement::new(
+                tags::REFERENCED_FRAME_NUMBER,
+                VR::IS,
+                dicom_value!(Str, frame.to_string()),
+            ));
+        }
+        DataElement::new(
+            tags::REFERENCED_IMAGE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![item]),
+        )
+    }
+
+    /// Presentation state with a VOI LUT for the first frame of the image,
+    /// an inverse presentation LUT,
+    /// a displayed area of 2x2 pixels starting from the second column,
+    /// a horizontal flip and a rotation of 90 degrees,
+    /// and a polyline over the first row of the image.
+    fn gsps() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::RESCALE_SLOPE, VR::DS, dicom_value!(Str, "2")),
+            DataElement::new(tags::RESCALE_INTERCEPT, VR::DS, dicom_value!(Str, "-1")),
+            DataElement::new(
+                tags::SOFTCOPY_VOILUT_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    referenced_image(Some(1)),
+                    DataElement::new(tags::WINDOW_CENTER, VR::DS, dicom_value!(Str, "6")),
+                    DataElement::new(tags::WINDOW_WIDTH, VR::DS, dicom_value!(Str, "14")),
+                    DataElement::new(
+                        tags::VOILUT_FUNCTION,
+                        VR::CS,
+                        dicom_value!(Str, "LINEAR_EXACT"),
+                    ),
+                ])]),
+            ),
+            DataElement::new(
+                tags::PRESENTATION_LUT_SHAPE,
+                VR::CS,
+                dicom_value!(Str, "INVERSE"),
+            ),
+            DataElement::new(
+                tags::DISPLAYED_AREA_SELECTION_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(
+                        tags::DISPLAYED_AREA_TOP_LEFT_HAND_CORNER,
+                        VR::SL,
+                        dicom_value!(I32, [2, 1]),
+                    ),
+                    DataElement::new(
+                        tags::DISPLAYED_AREA_BOTTOM_RIGHT_HAND_CORNER,
+                        VR::SL,
+                        dicom_value!(I32, [3, 2]),
+                    ),
+                    DataElement::new(
+                        tags::PRESENTATION_SIZE_MODE,
+                        VR::CS,
+                        dicom_value!(Str, "MAGNIFY"),
+                    ),
+                    DataElement::new(
+                        tags::PRESENTATION_PIXEL_MAGNIFICATION_RATIO,
+                        VR::FL,
+                        dicom_value!(F32, [1.]),
+                    ),
+                ])]),
+            ),
+            DataElement::new(tags::IMAGE_HORIZONTAL_FLIP, VR::CS, dicom_value!(Str, "Y")),
+            DataElement::new(tags::IMAGE_ROTATION, VR::US, dicom_value!(U16, [90])),
+            DataElement::new(
+                tags::GRAPHIC_ANNOTATION_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    referenced_image(None),
+                    DataElement::new(tags::GRAPHIC_LAYER, VR::CS, dicom_value!(Str, "LAYER")),
+                    DataElement::new(
+                        tags::GRAPHIC_OBJECT_SEQUENCE,
+                        VR::SQ,
+                        DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                            DataElement::new(
+                                tags::GRAPHIC_ANNOTATION_UNITS,
+                                VR::CS,
+                                dicom_value!(Str, "PIXEL"),
+                            ),
+                            DataElement::new(
+                                tags::GRAPHIC_DIMENSIONS,
+                                VR::US,
+                                dicom_value!(U16, [2]),
+                            ),
+                            DataElement::new(
+                                tags::NUMBER_OF_GRAPHIC_POINTS,
+                                VR::US,
+                                dicom_value!(U16, [2]),
+                            ),
+                            DataElement::new(
+                                tags::GRAPHIC_DATA,
+                                VR::FL,
+                                dicom_value!(F32, [0.5, 0.5, 3.5, 0.5]),
+                            ),
+                            DataElement::new(
+                                tags::GRAPHIC_TYPE,
+                                VR::CS,
+                                dicom_value!(Str, "POLYLINE"),
+                            ),
+                        ])]),
+                    ),
+                    DataElement::new(
+                        tags::TEXT_OBJECT_SEQUENCE,
+                        VR::SQ,
+                        DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                            DataElement::new(
+                                tags::UNFORMATTED_TEXT_VALUE,
+                                VR::ST,
+                                dicom_value!(Str, "A"),
+                            ),
+                            DataElement::new(
+                                tags::ANCHOR_POINT_ANNOTATION_UNITS,
+                                VR::CS,
+                                dicom_value!(Str, "DISPLAY"),
+                            ),
+                            DataElement::new(
+                                tags::ANCHOR_POINT,
+                                VR::FL,
+                                dicom_value!(F32, [0.5, 0.5]),
+                            ),
+                            DataElement::new(
+                                tags::ANCHOR_POINT_VISIBILITY,
+                                VR::CS,
+                                dicom_value!(Str, "N"),
+                            ),
+                        ])]),
+                    ),
+                ])]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn read_presentation_state() {
+        let ps = PresentationState::from_obj(&gsps()).unwrap();
+
+        assert_eq!(ps.rescale(), Some(Rescale::new(2., -1.)));
+        assert_eq!(ps.modality_lut(), None);
+        let voi = ps.softcopy_voi(IMAGE_UID, 0).unwrap();
+        assert_eq!(
+            voi.transform,
+            SoftcopyVoiTransform::Window(
+                WindowLevel {
+                    center: 6.,
+                    width: 14.
+                },
+                VoiLutFunction::LinearExact
+            )
+        );
+        // only the first frame of the image is referenced
+        assert!(ps.softcopy_voi(IMAGE_UID, 1).is_none());
+        assert!(ps.softcopy_voi("1.2.3", 0).is_none());
+
+        assert_eq!(ps.presentation_lut_shape(), PresentationLutShape::Inverse);
+
+        // the displayed area applies to all images
+        let area = ps.displayed_area("1.2.3", 5).unwrap();
+        assert_eq!(area.top_left, [2, 1]);
+        assert_eq!(area.bottom_right, [3, 2]);
+        assert_eq!(area.size_mode, PresentationSizeMode::Magnify(1.));
+
+        assert_eq!(ps.image_rotation(), 90);
+        assert!(ps.image_horizontal_flip());
+
+        let annotations: Vec<_> = ps.graphic_annotations(IMAGE_UID, 1).collect();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].layer, "LAYER");
+        let object = &annotations[0].graphic_objects[0];
+        assert_eq!(object.units, AnnotationUnits::Pixel);
+        assert_eq!(object.graphic_type, GraphicType::Polyline);
+        assert_eq!(object.points, vec![[0.5, 0.5], [3.5, 0.5]]);
+        assert!(!object.filled);
+        let text = &annotations[0].text_objects[0];
+        assert_eq!(text.text, "A");
+        assert_eq!(text.bounding_box, None);
+        assert_eq!(
+            text.anchor_point,
+            Some((AnnotationUnits::Display, [0.5, 0.5], false))
+        );
+        assert_eq!(ps.graphic_annotations("1.2.3", 0).count(), 0);
+    }
+
+    /// Create a sequence with a single item
+    /// holding a look-up table of 8-bit entries.
+    fn lut_sequence(
+        tag: dicom_core::Tag,
+        first_mapped: u16,
+        data: &[u8],
+    ) -> DataElement<InMemDicomObject> {
+        DataElement::new(
+            tag,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::LUT_DESCRIPTOR,
+                    VR::US,
+                    dicom_value!(U16, [data.len() as u16, first_mapped, 8]),
+                ),
+                DataElement::new(
+                    tags::LUT_DATA,
+                    VR::OW,
+                    PrimitiveValue::U16(data.iter().map(|v| u16::from(*v)).collect()),
+                ),
+            ])]),
+        )
+    }
+
+    /// Presentation state with a Modality LUT Sequence
+    /// and a table-based Softcopy VOI LUT for all images.
+    fn gsps_with_luts() -> InMemDicomObject {
+        let mut obj = gsps();
+        obj.remove_element(tags::DISPLAYED_AREA_SELECTION_SEQUENCE);
+        obj.remove_element(tags::IMAGE_HORIZONTAL_FLIP);
+        obj.remove_element(tags::IMAGE_ROTATION);
+        obj.remove_element(tags::GRAPHIC_ANNOTATION_SEQUENCE);
+        obj.remove_element(tags::RESCALE_SLOPE);
+        obj.remove_element(tags::RESCALE_INTERCEPT);
+        obj.put(DataElement::new(
+            tags::PRESENTATION_LUT_SHAPE,
+            VR::CS,
+            dicom_value!(Str, "IDENTITY"),
+        ));
+        // reverse the sample values 0 to 7
+        obj.put(lut_sequence(
+            tags::MODALITY_LUT_SEQUENCE,
+            0,
+            &[7, 6, 5, 4, 3, 2, 1, 0],
+        ));
+        obj.put(DataElement::new(
+            tags::SOFTCOPY_VOILUT_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([lut_sequence(
+                tags::VOILUT_SEQUENCE,
+                0,
+                &[0, 0, 0, 0, 0, 0, 255, 255],
+            )])]),
+        ));
+        obj
+    }
+
+    #[test]
+    fn read_presentation_state_luts() {
+        let ps = PresentationState::from_obj(&gsps_with_luts()).unwrap();
+
+        assert_eq!(ps.rescale(), None);
+        assert_eq!(
+            ps.modality_lut(),
+            Some(&LutTable::new(0, 8, vec![7, 6, 5, 4, 3, 2, 1, 0]))
+        );
+        let voi = ps.softcopy_voi("1.2.3", 0).unwrap();
+        assert_eq!(
+            voi.transform,
+            SoftcopyVoiTransform::Table(LutTable::new(0, 8, vec![0, 0, 0, 0, 0, 0, 255, 255]))
+        );
+    }
+
+    #[test]
+    fn reject_invalid_image_rotation() {
+        let mut obj = gsps();
+        obj.put(DataElement::new(
+            tags::IMAGE_ROTATION,
+            VR::US,
+            dicom_value!(U16, [45]),
+        ));
+        assert!(PresentationState::from_obj(&obj).is_err());
+    }
+
+    /// Create a `MONOCHROME2` image of 4x2 pixels
+    /// with the values 0 to 7 in row-major order.
+    #[cfg(feature = "image")]
+    fn image_dicom() -> dicom_object::DefaultDicomObject {
+        use dicom_dictionary_std::uids;
+        use dicom_object::{FileDicomObject, FileMetaTableBuilder};
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid(IMAGE_UID)
+                .build()
+                .unwrap(),
+        );
+        obj.put(DataElement::new(
+            tags::SOP_INSTANCE_UID,
+            VR::UI,
+            dicom_value!(Str, IMAGE_UID),
+        ));
+        for (tag, value) in [
+            (tags::ROWS, 2),
+            (tags::COLUMNS, 4),
+            (tags::SAMPLES_PER_PIXEL, 1),
+            (tags::BITS_ALLOCATED, 8),
+            (tags::BITS_STORED, 8),
+            (tags::HIGH_BIT, 7),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ]
+        .iter()
+        {
+            obj.put(DataElement::new(*tag, VR::US, dicom_value!(U16, [*value])));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            dicom_value!(Str, "MONOCHROME2"),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            dicom_value!(U8, [0, 1, 2, 3, 4, 5, 6, 7]),
+        ));
+        obj
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_voi_and_presentation_lut() {
+        let mut obj = gsps();
+        obj.remove_element(tags::DISPLAYED_AREA_SELECTION_SEQUENCE);
+        obj.remove_element(tags::IMAGE_HORIZONTAL_FLIP);
+        obj.remove_element(tags::IMAGE_ROTATION);
+        obj.remove_element(tags::GRAPHIC_ANNOTATION_SEQUENCE);
+        obj.put(DataElement::new(
+            tags::PRESENTATION_LUT_SHAPE,
+            VR::CS,
+            dicom_value!(Str, "IDENTITY"),
+        ));
+        let ps = PresentationState::from_obj(&obj).unwrap();
+
+        let image = ps
+            .render(
+                &image_dicom(),
+                0,
+                &crate::ConvertOptions::new().force_8bit(),
+            )
+            .unwrap();
+        let image = image.as_luma8().unwrap();
+        assert_eq!(image.dimensions(), (4, 2));
+        // rescaled values -1 to 13 over the window [-1, 13]
+        assert_eq!(image.get_pixel(0, 0).0, [0]);
+        assert_eq!(image.get_pixel(3, 1).0, [255]);
+
+        obj.put(DataElement::new(
+            tags::PRESENTATION_LUT_SHAPE,
+            VR::CS,
+            dicom_value!(Str, "INVERSE"),
+        ));
+        let ps = PresentationState::from_obj(&obj).unwrap();
+        let image = ps
+            .render(
+                &image_dicom(),
+                0,
+                &crate::ConvertOptions::new().force_8bit(),
+            )
+            .unwrap();
+        let image = image.as_luma8().unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [255]);
+        assert_eq!(image.get_pixel(3, 1).0, [0]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_modality_and_voi_lut_tables() {
+        let ps = PresentationState::from_obj(&gsps_with_luts()).unwrap();
+
+        let image = ps
+            .render(
+                &image_dicom(),
+                0,
+                &crate::ConvertOptions::new().force_8bit(),
+            )
+            .unwrap();
+        let image = image.as_luma8().unwrap();
+        // sample values 0 and 1 are mapped to 7 and 6,
+        // then to the highest value of the VOI LUT
+        let actual: Vec<u8> = image.pixels().map(|p| p.0[0]).collect();
+        assert_eq!(actual, [255, 255, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_spatial_transformations_and_annotations() {
+        let mut obj = gsps();
+        obj.remove_element(tags::SOFTCOPY_VOILUT_SEQUENCE);
+        obj.remove_element(tags::RESCALE_SLOPE);
+        obj.remove_element(tags::RESCALE_INTERCEPT);
+        obj.put(DataElement::new(
+            tags::PRESENTATION_LUT_SHAPE,
+            VR::CS,
+            dicom_value!(Str, "IDENTITY"),
+        ));
+        let ps = PresentationState::from_obj(&obj).unwrap();
+
+        let image = ps
+            .render(
+                &image_dicom(),
+                0,
+                &crate::ConvertOptions::new().force_8bit(),
+            )
+            .unwrap();
+        let image = image.as_luma8().unwrap();
+        // columns 2 and 3 of the image, with the first row drawn over:
+        // [255, 255]
+        // [  5,   6]
+        // flipped horizontally and rotated clockwise:
+        // [6, 255]
+        // [5, 255]
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(0, 0).0, [6]);
+        assert_eq!(image.get_pixel(1, 0).0, [255]);
+        assert_eq!(image.get_pixel(0, 1).0, [5]);
+        assert_eq!(image.get_pixel(1, 1).0, [255]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn draw_text_glyphs() {
+        let mut image = image::DynamicImage::new_luma8(8, 6);
+        super::draw_text(&mut image, [1., 1.], "a1", 1);
+        let image = image.as_luma8().unwrap();
+        #[rustfmt::skip]
+        let expected = [
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 1, 0,
+            0, 1, 0, 1, 0, 1, 1, 0,
+            0, 1, 1, 1, 0, 0, 1, 0,
+            0, 1, 0, 1, 0, 0, 1, 0,
+            0, 1, 0, 1, 0, 1, 1, 1,
+        ];
+        let actual: Vec<u8> = image.pixels().map(|p| p.0[0] / 255).collect();
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,690 @@
+//! Creation of new Secondary Capture images.
+//!
+//! See [`SecondaryCaptureBuilder`] for more information.
+use dicom_core::value::PixelFragmentSequence;
+use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::instance::{put_empty_attributes, put_empty_image_attributes, put_missing_uids};
+use dicom_object::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+/// An error occurred while creating a Secondary Capture image.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// No images were given
+    NoImages,
+
+    /// Image #{index} does not have the same properties as the first image
+    InconsistentImage { index: usize },
+
+    /// Image dimensions {width}x{height} are too large
+    #[cfg(feature = "image")]
+    ImageTooLarge { width: u32, height: u32 },
+
+    /// Invalid or truncated JPEG data in image #{index}
+    InvalidJpeg { index: usize },
+
+    /// Unsupported JPEG coding process (SOF{process}) in image #{index}
+    UnsupportedJpegProcess { index: usize, process: u8 },
+
+    /// No multi-frame Secondary Capture SOP class
+    /// supports {samples_per_pixel} samples per pixel of {bits_allocated} bits
+    UnsupportedMultiFrame {
+        samples_per_pixel: u16,
+        bits_allocated: u16,
+    },
+
+    /// Could not build the file meta table
+    BuildMeta { source: dicom_object::WithMetaError },
+}
+
+/// Alias for the result of creating a Secondary Capture image.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The pixel data properties shared by all frames.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct ImageProperties {
+    rows: u16,
+    columns: u16,
+    samples_per_pixel: u16,
+    bits_allocated: u16,
+    bits_stored: u16,
+    photometric_interpretation: &'static str,
+}
+
+/// A builder for a new Secondary Capture image
+/// from one or more general purpose images.
+///
+/// A single image produces a _Secondary Capture Image_,
+/// whereas multiple images produce a multi-frame Secondary Capture image
+/// (_Multi-frame Grayscale Byte_, _Multi-frame Grayscale Word_
+/// or _Multi-frame True Color_, depending on the images),
+/// with one frame per image.
+///
+/// Images can be either decoded into native pixel data
+/// in Explicit VR Little Endian
+/// (see [`build_from_images`](Self::build_from_images),
+/// requires the `image` feature),
+/// or JPEG files can be encapsulated as is,
+/// without re-encoding
+/// (see [`build_from_jpeg`](Self::build_from_jpeg)).
+///
+/// The captured images are recorded as a derived, secondary image
+/// (modality `OT`) in a new study and series,
+/// unless their instance UIDs are provided as attributes.
+/// The patient and study attributes are left empty unless provided,
+/// as are the series and instance numbers.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// use dicom_core::{DataElement, VR};
+/// use dicom_dictionary_std::tags;
+/// use dicom_pixeldata::secondary_capture::SecondaryCaptureBuilder;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let jpeg = std::fs::read("screenshot.jpg")?;
+/// let obj = SecondaryCaptureBuilder::new()
+///     .attribute(DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"))
+///     .build_from_jpeg(vec![jpeg])?;
+/// obj.write_to_file("screenshot.dcm")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecondaryCaptureBuilder {
+    conversion_type: String,
+    multi_frame: bool,
+    attributes: InMemDicomObject,
+}
+
+impl Default for SecondaryCaptureBuilder {
+    fn default() -> Self {
+        SecondaryCaptureBuilder {
+            conversion_type: "WSD".to_string(),
+            multi_frame: false,
+            attributes: InMemDicomObject::new_empty(),
+        }
+    }
+}
+
+impl SecondaryCaptureBuilder {
+    /// Create a new builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define the Conversion Type of the image
+    /// (such as `DI` for digitized video or `SD` for scanned document).
+    ///
+    /// The default is `WSD` (workstation).
+    pub fn conversion_type(mut self, conversion_type: impl Into<String>) -> Self {
+        self.conversion_type = conversion_type.into();
+        self
+    }
+
+    /// Always create a multi-frame Secondary Capture image,
+    /// even from a single image.
+    pub fn multi_frame(mut self, multi_frame: bool) -> Self {
+        self.multi_frame = multi_frame;
+        self
+    }
+
+    /// Add an attribute to the new object,
+    /// such as the patient's name or the study date.
+    ///
+    /// Attributes describing the image pixel data are always replaced
+    /// by those of the images.
+    pub fn attribute(mut self, element: DataElement<InMemDicomObject>) -> Self {
+        self.attributes.put(element);
+        self
+    }
+
+    /// Add all attributes of the given data set to the new object.
+    ///
+    /// Attributes describing the image pixel data are always replaced
+    /// by those of the images.
+    pub fn attributes(mut self, attributes: InMemDicomObject) -> Self {
+        for element in attributes {
+            self.attributes.put(element);
+        }
+        self
+    }
+
+    /// Build the new Secondary Capture image
+    /// from the given images, one per frame,
+    /// as native pixel data in Explicit VR Little Endian.
+    ///
+    /// Grayscale images are written as `MONOCHROME2`
+    /// and all other images as `RGB`, discarding any alpha channel.
+    /// Floating point images are converted to 16 bits per sample.
+    #[cfg(feature = "image")]
+    pub fn build_from_images(self, images: &[image::DynamicImage]) -> Result<DefaultDicomObject> {
+        use image::ColorType;
+
+        let first = images.first().context(NoImagesSnafu)?;
+        let (width, height) = (first.width(), first.height());
+        ensure!(
+            width <= u16::MAX as u32 && height <= u16::MAX as u32,
+            ImageTooLargeSnafu { width, height }
+        );
+        let (samples_per_pixel, bits_allocated) = match first.color() {
+            ColorType::L8 | ColorType::La8 => (1, 8),
+            ColorType::L16 | ColorType::La16 => (1, 16),
+            ColorType::Rgb8 | ColorType::Rgba8 => (3, 8),
+            ColorType::Rgb16 | ColorType::Rgba16 | ColorType::Rgb32F | ColorType::Rgba32F => {
+                (3, 16)
+            }
+            // other color types, such as floating point grayscale
+            color if !color.has_color() => (1, 16),
+            _ => (3, 16),
+        };
+        let properties = ImageProperties {
+            rows: height as u16,
+            columns: width as u16,
+            samples_per_pixel,
+            bits_allocated,
+            bits_stored: bits_allocated,
+            photometric_interpretation: if samples_per_pixel == 1 {
+                "MONOCHROME2"
+            } else {
+                "RGB"
+            },
+        };
+
+        let mut pixel_data = Vec::new();
+        for (index, image) in images.iter().enumerate() {
+            ensure!(
+                image.width() == width && image.height() == height,
+                InconsistentImageSnafu { index }
+            );
+            match (samples_per_pixel, bits_allocated) {
+                (1, 8) => pixel_data.extend(image.to_luma8().into_raw()),
+                (3, 8) => pixel_data.extend(image.to_rgb8().into_raw()),
+                (1, _) => pixel_data.extend(
+                    image
+                        .to_luma16()
+                        .into_raw()
+                        .into_iter()
+                        .flat_map(u16::to_le_bytes),
+                ),
+                _ => pixel_data.extend(
+                    image
+                        .to_rgb16()
+                        .into_raw()
+                        .into_iter()
+                        .flat_map(u16::to_le_bytes),
+                ),
+            }
+        }
+        if pixel_data.len() % 2 != 0 {
+            pixel_data.push(0);
+        }
+
+        let vr = if bits_allocated == 8 { VR::OB } else { VR::OW };
+        self.build(
+            properties,
+            images.len() as u32,
+            DataElement::new(tags::PIXEL_DATA, vr, PrimitiveValue::from(pixel_data)),
+            uids::EXPLICIT_VR_LITTLE_ENDIAN,
+        )
+    }
+
+    /// Build the new Secondary Capture image
+    /// from the given JPEG files, one per frame,
+    /// encapsulated as is without re-encoding.
+    ///
+    /// The transfer syntax is chosen based on the JPEG coding process,
+    /// which must be baseline, extended (sequential)
+    /// or lossless (non-hierarchical).
+    /// Color JPEG images are assumed to be in the YCbCr color space,
+    /// unless declared otherwise by an Adobe marker segment.
+    pub fn build_from_jpeg(self, frames: Vec<Vec<u8>>) -> Result<DefaultDicomObject> {
+        ensure!(!frames.is_empty(), NoImagesSnafu);
+
+        let (properties, ts_uid) = read_jpeg_header(&frames[0], 0)?;
+        for (index, frame) in frames.iter().enumerate().skip(1) {
+            ensure!(
+                read_jpeg_header(frame, index)? == (properties, ts_uid),
+                InconsistentImageSnafu { index }
+            );
+        }
+
+        let number_of_frames = frames.len() as u32;
+        let fragments: Vec<Vec<u8>> = frames
+            .into_iter()
+            .map(|mut frame| {
+                if frame.len() % 2 != 0 {
+                    frame.push(0);
+                }
+                frame
+            })
+            .collect();
+
+        let lossless = ts_uid == uids::JPEG_LOSSLESS || ts_uid == uids::JPEG_LOSSLESS_SV1;
+        let mut obj = self.build(
+            properties,
+            number_of_frames,
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PixelFragmentSequence::new(vec![], fragments),
+            ),
+            ts_uid,
+        )?;
+        if !lossless {
+            obj.put(DataElement::new(
+                tags::LOSSY_IMAGE_COMPRESSION,
+                VR::CS,
+                "01",
+            ));
+            obj.put(DataElement::new(
+                tags::LOSSY_IMAGE_COMPRESSION_METHOD,
+                VR::CS,
+                "ISO_10918_1",
+            ));
+        }
+        Ok(obj)
+    }
+
+    fn build(
+        self,
+        properties: ImageProperties,
+        number_of_frames: u32,
+        pixel_data: DataElement<InMemDicomObject>,
+        ts_uid: &str,
+    ) -> Result<DefaultDicomObject> {
+        let ImageProperties {
+            rows,
+            columns,
+            samples_per_pixel,
+            bits_allocated,
+            bits_stored,
+            photometric_interpretation,
+        } = properties;
+
+        let multi_frame = self.multi_frame || number_of_frames > 1;
+        let sop_class_uid = if multi_frame {
+            match (samples_per_pixel, bits_allocated) {
+                (1, 8) => uids::MULTI_FRAME_GRAYSCALE_BYTE_SECONDARY_CAPTURE_IMAGE_STORAGE,
+                (1, 16) => uids::MULTI_FRAME_GRAYSCALE_WORD_SECONDARY_CAPTURE_IMAGE_STORAGE,
+                (3, 8) => uids::MULTI_FRAME_TRUE_COLOR_SECONDARY_CAPTURE_IMAGE_STORAGE,
+                _ => {
+                    return UnsupportedMultiFrameSnafu {
+                        samples_per_pixel,
+                        bits_allocated,
+                    }
+                    .fail()?
+                }
+            }
+        } else {
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE
+        };
+
+        let mut obj = InMemDicomObject::new_empty();
+
+        // type 2 attributes of the IOD, left empty unless provided
+        put_empty_attributes(&mut obj);
+        put_empty_image_attributes(&mut obj);
+        obj.put(DataElement::new(tags::MODALITY, VR::CS, "OT"));
+        obj.put(DataElement::new(
+            tags::IMAGE_TYPE,
+            VR::CS,
+            dicom_value!(Strs, ["DERIVED", "SECONDARY"]),
+        ));
+        if multi_frame {
+            obj.put(DataElement::new(tags::BURNED_IN_ANNOTATION, VR::CS, "NO"));
+        }
+
+        // provided attributes
+        for element in self.attributes {
+            obj.put(element);
+        }
+
+        // generated identifiers
+        put_missing_uids(&mut obj);
+        obj.put(DataElement::new(tags::SOP_CLASS_UID, VR::UI, sop_class_uid));
+
+        // SC equipment module
+        obj.put(DataElement::new(
+            tags::CONVERSION_TYPE,
+            VR::CS,
+            self.conversion_type,
+        ));
+
+        // image pixel module
+        for (tag, value) in [
+            (tags::SAMPLES_PER_PIXEL, samples_per_pixel),
+            (tags::ROWS, rows),
+            (tags::COLUMNS, columns),
+            (tags::BITS_ALLOCATED, bits_allocated),
+            (tags::BITS_STORED, bits_stored),
+            (tags::HIGH_BIT, bits_stored - 1),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ] {
+            obj.put(DataElement::new(tag, VR::US, dicom_value!(U16, [value])));
+        }
+        if samples_per_pixel > 1 {
+            obj.put(DataElement::new(
+                tags::PLANAR_CONFIGURATION,
+                VR::US,
+                dicom_value!(U16, [0]),
+            ));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            photometric_interpretation,
+        ));
+
+        // multi-frame modules
+        if multi_frame {
+            obj.put(DataElement::new(
+                tags::NUMBER_OF_FRAMES,
+                VR::IS,
+                number_of_frames.to_string(),
+            ));
+            obj.put(DataElement::new(
+                tags::FRAME_INCREMENT_POINTER,
+                VR::AT,
+                PrimitiveValue::from(tags::PAGE_NUMBER_VECTOR),
+            ));
+            obj.put(DataElement::new(
+                tags::PAGE_NUMBER_VECTOR,
+                VR::IS,
+                PrimitiveValue::Strs((1..=number_of_frames).map(|i| i.to_string()).collect()),
+            ));
+            if samples_per_pixel == 1 {
+                obj.put(DataElement::new(
+                    tags::PRESENTATION_LUT_SHAPE,
+                    VR::CS,
+                    "IDENTITY",
+                ));
+                if bits_allocated == 16 {
+                    obj.put(DataElement::new(tags::RESCALE_INTERCEPT, VR::DS, "0"));
+                    obj.put(DataElement::new(tags::RESCALE_SLOPE, VR::DS, "1"));
+                    obj.put(DataElement::new(tags::RESCALE_TYPE, VR::LO, "US"));
+                }
+            }
+        }
+
+        obj.put(pixel_data);
+
+        obj.with_meta(FileMetaTableBuilder::new().transfer_syntax(ts_uid))
+            .context(BuildMetaSnafu)
+            .map_err(Error::from)
+    }
+}
+
+/// Read the image properties and the suitable transfer syntax
+/// from the header of a JPEG file.
+fn read_jpeg_header(data: &[u8], index: usize) -> Result<(ImageProperties, &'static str)> {
+    ensure!(data.starts_with(&[0xFF, 0xD8]), InvalidJpegSnafu { index });
+
+    let mut pos = 2;
+    let mut adobe_transform = None;
+    let mut frame = None;
+    loop {
+        // skip fill bytes before the marker
+        while data.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let marker = match data.get(pos..pos + 2) {
+            Some([0xFF, marker]) => *marker,
+            _ => return InvalidJpegSnafu { index }.fail()?,
+        };
+        let len = data
+            .get(pos + 2..pos + 4)
+            .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+            .context(InvalidJpegSnafu { index })?;
+        let segment = data
+            .get(pos + 4..pos + 2 + len)
+            .context(InvalidJpegSnafu { index })?;
+
+        match marker {
+            // APP14, Adobe
+            0xEE if segment.starts_with(b"Adobe") && segment.len() >= 12 => {
+                adobe_transform = Some(segment[11]);
+            }
+            // start of frame, except for DHT, JPG and DAC
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let process = marker - 0xC0;
+                ensure!(
+                    matches!(process, 0 | 1 | 3),
+                    UnsupportedJpegProcessSnafu { index, process }
+                );
+                ensure!(segment.len() >= 6, InvalidJpegSnafu { index });
+                let precision = segment[0] as u16;
+                let rows = u16::from_be_bytes([segment[1], segment[2]]);
+                let columns = u16::from_be_bytes([segment[3], segment[4]]);
+                let components = segment[5] as u16;
+                // whether all components have the same sampling factors
+                let subsampled = segment
+                    .get(6..6 + 3 * components as usize)
+                    .context(InvalidJpegSnafu { index })?
+                    .chunks(3)
+                    .any(|c| c[1] != segment[7]);
+                frame = Some((process, precision, rows, columns, components, subsampled));
+            }
+            // start of scan
+            0xDA => {
+                let (process, precision, rows, columns, components, subsampled) =
+                    frame.context(InvalidJpegSnafu { index })?;
+                ensure!(
+                    rows > 0 && matches!(components, 1 | 3),
+                    InvalidJpegSnafu { index }
+                );
+
+                let ts_uid = match process {
+                    0 => uids::JPEG_BASELINE8_BIT,
+                    1 => uids::JPEG_EXTENDED12_BIT,
+                    _ => {
+                        let scan_components =
+                            *segment.first().context(InvalidJpegSnafu { index })? as usize;
+                        // predictor selection value
+                        if segment.get(1 + 2 * scan_components) == Some(&1) {
+                            uids::JPEG_LOSSLESS_SV1
+                        } else {
+                            uids::JPEG_LOSSLESS
+                        }
+                    }
+                };
+                let photometric_interpretation = match (components, adobe_transform, process) {
+                    (1, ..) => "MONOCHROME2",
+                    (_, Some(0), _) | (_, _, 3) => "RGB",
+                    _ if subsampled => "YBR_FULL_422",
+                    _ => "YBR_FULL",
+                };
+                let bits_allocated = if precision > 8 { 16 } else { 8 };
+
+                return Ok((
+                    ImageProperties {
+                        rows,
+                        columns,
+                        samples_per_pixel: components,
+                        bits_allocated,
+                        bits_stored: precision,
+                        photometric_interpretation,
+                    },
+                    ts_uid,
+                ));
+            }
+            _ => {}
+        }
+        pos += 2 + len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a JPEG header up to the start of scan,
+    /// with the given coding process, precision and number of components.
+    fn jpeg_header(process: u8, precision: u8, components: u8, adobe: bool) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        if adobe {
+            data.extend([0xFF, 0xEE, 0, 14]);
+            data.extend(b"Adobe");
+            data.extend([0, 100, 0, 0, 0, 0, 0]);
+        }
+        data.extend([0xFF, 0xC0 + process, 0, 8 + 3 * components]);
+        data.extend([precision, 0, 32, 0, 48, components]);
+        for c in 0..components {
+            // subsample chroma components
+            let sampling = if c == 0 && components > 1 { 0x22 } else { 0x11 };
+            data.extend([c + 1, sampling, 0]);
+        }
+        data.extend([0xFF, 0xDA, 0, 6 + 2 * components, components]);
+        for c in 0..components {
+            data.extend([c + 1, 0]);
+        }
+        // predictor selection value, end of spectral selection, approximation
+        data.extend([if process == 3 { 1 } else { 0 }, 63, 0]);
+        data.extend([0x12, 0x34, 0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn read_jpeg_headers() {
+        let (properties, ts_uid) = read_jpeg_header(&jpeg_header(0, 8, 3, false), 0).unwrap();
+        assert_eq!(ts_uid, uids::JPEG_BASELINE8_BIT);
+        assert_eq!(
+            properties,
+            ImageProperties {
+                rows: 32,
+                columns: 48,
+                samples_per_pixel: 3,
+                bits_allocated: 8,
+                bits_stored: 8,
+                photometric_interpretation: "YBR_FULL_422",
+            }
+        );
+
+        let (properties, _) = read_jpeg_header(&jpeg_header(0, 8, 3, true), 0).unwrap();
+        assert_eq!(properties.photometric_interpretation, "RGB");
+
+        let (properties, ts_uid) = read_jpeg_header(&jpeg_header(1, 12, 1, false), 0).unwrap();
+        assert_eq!(ts_uid, uids::JPEG_EXTENDED12_BIT);
+        assert_eq!(properties.bits_allocated, 16);
+        assert_eq!(properties.bits_stored, 12);
+        assert_eq!(properties.photometric_interpretation, "MONOCHROME2");
+
+        let (_, ts_uid) = read_jpeg_header(&jpeg_header(3, 16, 1, false), 0).unwrap();
+        assert_eq!(ts_uid, uids::JPEG_LOSSLESS_SV1);
+
+        // progressive JPEG is not supported
+        assert!(read_jpeg_header(&jpeg_header(2, 8, 3, false), 0).is_err());
+        // truncated file
+        assert!(read_jpeg_header(&jpeg_header(0, 8, 3, false)[..12], 0).is_err());
+        // empty start of scan segment
+        let mut data = jpeg_header(3, 16, 1, false);
+        let sos = data.windows(2).position(|w| w == [0xFF, 0xDA]).unwrap();
+        data.truncate(sos);
+        data.extend([0xFF, 0xDA, 0, 2, 0xFF, 0xD9]);
+        assert!(read_jpeg_header(&data, 0).is_err());
+    }
+
+    #[test]
+    fn build_secondary_capture_from_jpeg() {
+        let frames = vec![jpeg_header(1, 12, 1, false), jpeg_header(1, 12, 1, false)];
+        let obj = SecondaryCaptureBuilder::new()
+            .conversion_type("DI")
+            .attribute(DataElement::new(tags::PATIENT_ID, VR::LO, "12345"))
+            .build_from_jpeg(frames)
+            .unwrap();
+
+        assert_eq!(obj.meta().transfer_syntax(), uids::JPEG_EXTENDED12_BIT);
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::MULTI_FRAME_GRAYSCALE_WORD_SECONDARY_CAPTURE_IMAGE_STORAGE
+        );
+        let str_of = |tag| obj.element(tag).unwrap().to_str().unwrap().into_owned();
+        assert_eq!(str_of(tags::PATIENT_ID), "12345");
+        assert_eq!(str_of(tags::PATIENT_NAME), "");
+        assert_eq!(str_of(tags::CONVERSION_TYPE), "DI");
+        assert_eq!(str_of(tags::NUMBER_OF_FRAMES), "2");
+        assert_eq!(str_of(tags::PAGE_NUMBER_VECTOR), "1\\2");
+        assert_eq!(str_of(tags::LOSSY_IMAGE_COMPRESSION), "01");
+        assert_eq!(
+            obj.element(tags::BITS_STORED)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            12
+        );
+        assert_eq!(
+            obj.element(tags::PIXEL_DATA)
+                .unwrap()
+                .fragments()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // frames must be consistent
+        assert!(SecondaryCaptureBuilder::new()
+            .build_from_jpeg(vec![
+                jpeg_header(0, 8, 3, false),
+                jpeg_header(0, 8, 1, false),
+            ])
+            .is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn build_secondary_capture_from_images() {
+        use crate::PixelDecoder;
+        use image::{DynamicImage, GrayImage, Rgb, RgbImage};
+
+        let image = RgbImage::from_fn(4, 3, |x, y| Rgb([x as u8, y as u8, 200]));
+        let obj = SecondaryCaptureBuilder::new()
+            .build_from_images(&[DynamicImage::ImageRgb8(image.clone())])
+            .unwrap();
+
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            uids::EXPLICIT_VR_LITTLE_ENDIAN
+        );
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE
+        );
+        assert!(obj.element(tags::NUMBER_OF_FRAMES).is_err());
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.to_vec::<u8>().unwrap(), image.into_raw());
+
+        // 16-bit grayscale frames
+        let frames: Vec<_> = (0..3_u16)
+            .map(|i| {
+                DynamicImage::ImageLuma16(image::ImageBuffer::from_fn(2, 2, |x, _| {
+                    image::Luma([i * 1000 + x as u16])
+                }))
+            })
+            .collect();
+        let obj = SecondaryCaptureBuilder::new()
+            .build_from_images(&frames)
+            .unwrap();
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::MULTI_FRAME_GRAYSCALE_WORD_SECONDARY_CAPTURE_IMAGE_STORAGE
+        );
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.number_of_frames(), 3);
+        assert_eq!(
+            decoded.to_vec::<u16>().unwrap(),
+            vec![0, 1, 0, 1, 1000, 1001, 1000, 1001, 2000, 2001, 2000, 2001]
+        );
+
+        // images must have the same size
+        assert!(SecondaryCaptureBuilder::new()
+            .build_from_images(&[
+                DynamicImage::ImageLuma8(GrayImage::new(2, 2)),
+                DynamicImage::ImageLuma8(GrayImage::new(2, 3)),
+            ])
+            .is_err());
+    }
+}
@@ -0,0 +1,1365 @@
+//! Segmentation (SEG) objects.
+//!
+//! This module supports reading and writing objects
+//! of the _Segmentation_ IOD (see [section A.51][1] of the standard),
+//! in which each frame holds the mask of one segment
+//! over one frame of a source image.
+//!
+//! [`Segmentation`] decodes the frames of an existing object,
+//! either `BINARY` (1 bit per pixel, packed across frames)
+//! or `FRACTIONAL` (8 bits per pixel),
+//! and identifies the segment, source image and position of each frame
+//! through the _Per-frame Functional Groups Sequence_.
+//!
+//! [`SegmentationBuilder`] creates a new object
+//! from one mask array per segment.
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_A.51.html
+
+use crate::PixelDecoder;
+use dicom_core::value::serialize::format_decimal_string;
+use dicom_core::value::{ConvertValueError, DataSetSequence};
+use dicom_core::{dicom_value, DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::instance::{put_empty_attributes, put_empty_image_attributes, put_missing_uids};
+use dicom_object::sr::Code;
+use dicom_object::uid::new_uid;
+use dicom_object::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject};
+use ndarray::{Array2, Array3, ArrayView2, ArrayView3, Axis};
+use num_traits::ToPrimitive;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+/// An error occurred while reading or writing a segmentation.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Not a segmentation object (SOP class UID {sop_class_uid})
+    NotSegmentation { sop_class_uid: String },
+
+    /// Missing attribute `{name}`
+    MissingAttribute { name: &'static str },
+
+    /// Could not convert the value of attribute `{name}`
+    ConvertValue {
+        name: &'static str,
+        source: ConvertValueError,
+    },
+
+    /// Invalid value of attribute `{name}`: {value}
+    InvalidValue { name: &'static str, value: String },
+
+    /// Could not decode the pixel data
+    DecodePixelData { source: crate::Error },
+
+    /// Encapsulated binary segmentations are not supported
+    EncapsulatedBinary,
+
+    /// Pixel data is too short: expected {expected} bytes, got {actual}
+    PixelDataTooShort { expected: usize, actual: usize },
+
+    /// No segments were given
+    NoSegments,
+
+    /// Segment #{index} should have number {expected}, but has {number}
+    InvalidSegmentNumber {
+        index: usize,
+        expected: u16,
+        number: u16,
+    },
+
+    /// Masks of segment {number} have shape {shape:?}, expected {expected:?}
+    InconsistentMask {
+        number: u16,
+        shape: Vec<usize>,
+        expected: Vec<usize>,
+    },
+
+    /// Expected {expected} {what}, one per mask frame, but got {actual}
+    FrameCount {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// Frames of {rows}x{columns} are too large
+    FrameTooLarge { rows: usize, columns: usize },
+
+    /// Could not build the file meta table
+    BuildMeta { source: dicom_object::WithMetaError },
+}
+
+/// Alias for the result of reading or writing a segmentation.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The meaning of the fractional values in a segmentation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FractionalType {
+    /// `PROBABILITY`: the probability of the pixel belonging to the segment
+    Probability,
+    /// `OCCUPANCY`: the fraction of the pixel occupied by the segment
+    Occupancy,
+}
+
+/// The kind of segmentation,
+/// which determines how the mask values are encoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SegmentationType {
+    /// `BINARY`: each pixel is either in the segment or not,
+    /// encoded in 1 bit
+    Binary,
+    /// `FRACTIONAL`: each pixel has a value between 0 and `max_value`,
+    /// encoded in 8 bits
+    Fractional {
+        /// the meaning of the values
+        fractional_type: FractionalType,
+        /// the value representing a full membership to the segment
+        max_value: u8,
+    },
+}
+
+/// How a segment was created.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SegmentAlgorithmType {
+    /// `AUTOMATIC`: calculated by an algorithm without user intervention
+    Automatic,
+    /// `SEMIAUTOMATIC`: calculated with user assistance
+    Semiautomatic,
+    /// `MANUAL`: drawn by a user
+    Manual,
+}
+
+impl SegmentAlgorithmType {
+    fn as_str(self) -> &'static str {
+        match self {
+            SegmentAlgorithmType::Automatic => "AUTOMATIC",
+            SegmentAlgorithmType::Semiautomatic => "SEMIAUTOMATIC",
+            SegmentAlgorithmType::Manual => "MANUAL",
+        }
+    }
+}
+
+/// The description of a segment.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Segment {
+    /// the segment number, starting at 1
+    pub number: u16,
+    /// the segment label
+    pub label: String,
+    /// a description of the segment, if any
+    pub description: Option<String>,
+    /// how the segment was created
+    pub algorithm_type: SegmentAlgorithmType,
+    /// the name of the algorithm which created the segment, if any
+    pub algorithm_name: Option<String>,
+    /// the general category of the segmented property,
+    /// such as _Tissue_ or _Anatomical Structure_
+    pub category: Code,
+    /// the specific segmented property, such as _Liver_
+    pub property_type: Code,
+}
+
+impl Segment {
+    /// Create a new manually drawn segment.
+    pub fn new(number: u16, label: impl Into<String>, category: Code, property_type: Code) -> Self {
+        Segment {
+            number,
+            label: label.into(),
+            description: None,
+            algorithm_type: SegmentAlgorithmType::Manual,
+            algorithm_name: None,
+            category,
+            property_type,
+        }
+    }
+
+    /// Set the description of the segment.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the algorithm which created the segment.
+    pub fn algorithm(
+        mut self,
+        algorithm_type: SegmentAlgorithmType,
+        algorithm_name: impl Into<String>,
+    ) -> Self {
+        self.algorithm_type = algorithm_type;
+        self.algorithm_name = Some(algorithm_name.into());
+        self
+    }
+}
+
+/// A reference to a frame of a source image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceImage {
+    /// the SOP class UID of the source image
+    pub sop_class_uid: String,
+    /// the SOP instance UID of the source image
+    pub sop_instance_uid: String,
+    /// the frame number in the source image, starting at 1,
+    /// for multi-frame images
+    pub frame_number: Option<u32>,
+}
+
+impl SourceImage {
+    /// Create a reference to a single frame image.
+    pub fn new(sop_class_uid: impl Into<String>, sop_instance_uid: impl Into<String>) -> Self {
+        SourceImage {
+            sop_class_uid: sop_class_uid.into(),
+            sop_instance_uid: sop_instance_uid.into(),
+            frame_number: None,
+        }
+    }
+}
+
+/// The description of one frame of a segmentation.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SegmentFrame {
+    /// the number of the segment in this frame
+    pub segment_number: u16,
+    /// the source image frame which this frame is aligned with, if known
+    pub source: Option<SourceImage>,
+    /// the position of the top left pixel in the patient coordinate system,
+    /// if known
+    pub image_position: Option<[f64; 3]>,
+}
+
+/// A decoded segmentation object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segmentation {
+    /// the kind of segmentation
+    pub segmentation_type: SegmentationType,
+    /// the segments, in order of segment number
+    pub segments: Vec<Segment>,
+    /// the description of each frame
+    pub frames: Vec<SegmentFrame>,
+    /// the stored values of all frames, as `[frame, row, column]`
+    data: Array3<u8>,
+}
+
+impl Segmentation {
+    /// Read and decode a segmentation object.
+    pub fn from_obj(obj: &DefaultDicomObject) -> Result<Self> {
+        let sop_class_uid = string(obj, tags::SOP_CLASS_UID, "SOPClassUID")?.unwrap_or_default();
+        ensure!(
+            sop_class_uid == uids::SEGMENTATION_STORAGE,
+            NotSegmentationSnafu { sop_class_uid }
+        );
+
+        let segmentation_type =
+            match required_string(obj, tags::SEGMENTATION_TYPE, "SegmentationType")?.as_str() {
+                "BINARY" => SegmentationType::Binary,
+                "FRACTIONAL" => SegmentationType::Fractional {
+                    fractional_type: match required_string(
+                        obj,
+                        tags::SEGMENTATION_FRACTIONAL_TYPE,
+                        "SegmentationFractionalType",
+                    )?
+                    .as_str()
+                    {
+                        "PROBABILITY" => FractionalType::Probability,
+                        "OCCUPANCY" => FractionalType::Occupancy,
+                        value => return Err(invalid_value("SegmentationFractionalType", value)),
+                    },
+                    max_value: required_int(
+                        obj,
+                        tags::MAXIMUM_FRACTIONAL_VALUE,
+                        "MaximumFractionalValue",
+                    )?,
+                },
+                value => return Err(invalid_value("SegmentationType", value)),
+            };
+
+        let segments = items(obj, tags::SEGMENT_SEQUENCE)
+            .map(read_segment)
+            .collect::<Result<Vec<_>>>()?;
+
+        let rows: usize = required_int(obj, tags::ROWS, "Rows")?;
+        let columns: usize = required_int(obj, tags::COLUMNS, "Columns")?;
+        let number_of_frames: usize = match obj.get(tags::NUMBER_OF_FRAMES) {
+            Some(e) => e.to_int().context(ConvertValueSnafu {
+                name: "NumberOfFrames",
+            })?,
+            None => 1,
+        };
+
+        // frame descriptions, possibly shared by all frames
+        let shared = items(obj, tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE).next();
+        let per_frame: Vec<_> = items(obj, tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE).collect();
+        let frames = (0..number_of_frames)
+            .map(|i| read_frame(per_frame.get(i).copied(), shared))
+            .collect::<Result<Vec<_>>>()?;
+
+        // frame data
+        let len = number_of_frames * rows * columns;
+        let pixel_data = obj
+            .get(tags::PIXEL_DATA)
+            .context(MissingAttributeSnafu { name: "PixelData" })?;
+        let values = if pixel_data.fragments().is_some() {
+            ensure!(
+                segmentation_type != SegmentationType::Binary,
+                EncapsulatedBinarySnafu
+            );
+            obj.decode_pixel_data()
+                .context(DecodePixelDataSnafu)?
+                .data()
+                .to_vec()
+        } else {
+            let bytes = pixel_data
+                .to_bytes()
+                .context(ConvertValueSnafu { name: "PixelData" })?;
+            match segmentation_type {
+                // bits are packed across frames, least significant bit first
+                SegmentationType::Binary => {
+                    ensure!(
+                        bytes.len() * 8 >= len,
+                        PixelDataTooShortSnafu {
+                            expected: (len + 7) / 8,
+                            actual: bytes.len(),
+                        }
+                    );
+                    (0..len).map(|i| (bytes[i / 8] >> (i % 8)) & 1).collect()
+                }
+                SegmentationType::Fractional { .. } => bytes.into_owned(),
+            }
+        };
+        ensure!(
+            values.len() >= len,
+            PixelDataTooShortSnafu {
+                expected: len,
+                actual: values.len(),
+            }
+        );
+        let mut values = values;
+        values.truncate(len);
+        let data = Array3::from_shape_vec((number_of_frames, rows, columns), values)
+            .expect("data length should match the frame dimensions");
+
+        Ok(Segmentation {
+            segmentation_type,
+            segments,
+            frames,
+            data,
+        })
+    }
+
+    /// Get the description of a segment by its number.
+    pub fn segment(&self, number: u16) -> Option<&Segment> {
+        self.segments.iter().find(|s| s.number == number)
+    }
+
+    /// Get the stored values of the frame at the given index (starting at 0),
+    /// as `[row, column]`.
+    ///
+    /// Values are either 0 or 1 in binary segmentations,
+    /// and between 0 and the maximum fractional value
+    /// in fractional segmentations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn frame_data(&self, index: usize) -> ArrayView2<'_, u8> {
+        self.data.index_axis(Axis(0), index)
+    }
+
+    /// Get the mask of the frame at the given index (starting at 0),
+    /// as `[row, column]`,
+    /// with values between 0 and 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn frame_mask(&self, index: usize) -> Array2<f32> {
+        let max = match self.segmentation_type {
+            SegmentationType::Binary => 1.,
+            SegmentationType::Fractional { max_value, .. } => f32::from(max_value.max(1)),
+        };
+        self.frame_data(index).mapv(|v| f32::from(v) / max)
+    }
+
+    /// Iterate over the indices of the frames of the given segment,
+    /// in the order in which they are stored.
+    pub fn segment_frames(&self, segment_number: u16) -> impl Iterator<Item = usize> + '_ {
+        self.frames
+            .iter()
+            .enumerate()
+            .filter(move |(_, f)| f.segment_number == segment_number)
+            .map(|(i, _)| i)
+    }
+
+    /// Find the index of the frame of the given segment
+    /// which is aligned with the given source image frame.
+    ///
+    /// `frame_number` is the frame number in the source image,
+    /// starting at 1, or `None` for single frame images.
+    pub fn find_frame(
+        &self,
+        segment_number: u16,
+        sop_instance_uid: &str,
+        frame_number: Option<u32>,
+    ) -> Option<usize> {
+        self.segment_frames(segment_number).find(|&i| {
+            self.frames[i]
+                .source
+                .as_ref()
+                .map(|source| {
+                    source.sop_instance_uid == sop_instance_uid
+                        && source.frame_number.unwrap_or(1) == frame_number.unwrap_or(1)
+                })
+                .unwrap_or(false)
+        })
+    }
+}
+
+fn read_segment(item: &InMemDicomObject) -> Result<Segment> {
+    let code = |tag, name| {
+        let item = items(item, tag)
+            .next()
+            .context(MissingAttributeSnafu { name })?;
+        Code::from_item(item).map_err(|e| invalid_value(name, e))
+    };
+    Ok(Segment {
+        number: required_int(item, tags::SEGMENT_NUMBER, "SegmentNumber")?,
+        label: string(item, tags::SEGMENT_LABEL, "SegmentLabel")?.unwrap_or_default(),
+        description: string(item, tags::SEGMENT_DESCRIPTION, "SegmentDescription")?,
+        algorithm_type: match required_string(
+            item,
+            tags::SEGMENT_ALGORITHM_TYPE,
+            "SegmentAlgorithmType",
+        )?
+        .as_str()
+        {
+            "AUTOMATIC" => SegmentAlgorithmType::Automatic,
+            "SEMIAUTOMATIC" => SegmentAlgorithmType::Semiautomatic,
+            "MANUAL" => SegmentAlgorithmType::Manual,
+            value => return Err(invalid_value("SegmentAlgorithmType", value)),
+        },
+        algorithm_name: string(item, tags::SEGMENT_ALGORITHM_NAME, "SegmentAlgorithmName")?,
+        category: code(
+            tags::SEGMENTED_PROPERTY_CATEGORY_CODE_SEQUENCE,
+            "SegmentedPropertyCategoryCodeSequence",
+        )?,
+        property_type: code(
+            tags::SEGMENTED_PROPERTY_TYPE_CODE_SEQUENCE,
+            "SegmentedPropertyTypeCodeSequence",
+        )?,
+    })
+}
+
+/// Read the description of a frame from its functional groups,
+/// falling back to the shared functional groups.
+fn read_frame(
+    per_frame: Option<&InMemDicomObject>,
+    shared: Option<&InMemDicomObject>,
+) -> Result<SegmentFrame> {
+    // the first item of the given functional group macro
+    let group = |tag| {
+        per_frame
+            .and_then(|f| items(f, tag).next())
+            .or_else(|| shared.and_then(|s| items(s, tag).next()))
+    };
+
+    let segment_number = match group(tags::SEGMENT_IDENTIFICATION_SEQUENCE) {
+        Some(item) => required_int(
+            item,
+            tags::REFERENCED_SEGMENT_NUMBER,
+            "ReferencedSegmentNumber",
+        )?,
+        None => {
+            return MissingAttributeSnafu {
+                name: "SegmentIdentificationSequence",
+            }
+            .fail()
+            .map_err(Error::from)
+        }
+    };
+
+    let source = match group(tags::DERIVATION_IMAGE_SEQUENCE)
+        .and_then(|item| items(item, tags::SOURCE_IMAGE_SEQUENCE).next())
+    {
+        Some(item) => Some(SourceImage {
+            sop_class_uid: string(
+                item,
+                tags::REFERENCED_SOP_CLASS_UID,
+                "ReferencedSOPClassUID",
+            )?
+            .unwrap_or_default(),
+            sop_instance_uid: required_string(
+                item,
+                tags::REFERENCED_SOP_INSTANCE_UID,
+                "ReferencedSOPInstanceUID",
+            )?,
+            frame_number: match item.get(tags::REFERENCED_FRAME_NUMBER) {
+                Some(e) => Some(e.to_int().context(ConvertValueSnafu {
+                    name: "ReferencedFrameNumber",
+                })?),
+                None => None,
+            },
+        }),
+        None => None,
+    };
+
+    let image_position = match group(tags::PLANE_POSITION_SEQUENCE)
+        .and_then(|item| item.get(tags::IMAGE_POSITION_PATIENT))
+    {
+        Some(e) => match e.to_multi_float64().context(ConvertValueSnafu {
+            name: "ImagePositionPatient",
+        })?[..]
+        {
+            [x, y, z] => Some([x, y, z]),
+            ref values => {
+                return Err(invalid_value(
+                    "ImagePositionPatient",
+                    format!("{:?}", values),
+                ))
+            }
+        },
+        None => None,
+    };
+
+    Ok(SegmentFrame {
+        segment_number,
+        source,
+        image_position,
+    })
+}
+
+/// A builder for a new segmentation object
+/// from mask arrays.
+///
+/// Each segment is given as an array of masks of shape
+/// `[frame, row, column]`,
+/// where each frame is aligned with the source image frame
+/// and image position of the same index, if provided.
+/// All segments must have masks of the same shape.
+///
+/// The new object is encoded in Explicit VR Little Endian.
+/// The segmentation gets a series of its own unless provided,
+/// but it belongs to the study of the segmented images,
+/// so the study instance UID and the study and patient attributes
+/// should usually be copied
+/// from the source images with [`attribute`](Self::attribute).
+///
+/// # Example
+///
+/// ```
+/// # use dicom_pixeldata::segmentation::*;
+/// # use dicom_object::sr::Code;
+/// # use ndarray::Array3;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let liver = Array3::<u8>::zeros((2, 64, 64));
+/// let obj = SegmentationBuilder::new(SegmentationType::Binary)
+///     .source_images(vec![
+///         SourceImage::new("1.2.840.10008.5.1.4.1.1.2", "1.2.3.1"),
+///         SourceImage::new("1.2.840.10008.5.1.4.1.1.2", "1.2.3.2"),
+///     ])
+///     .add_segment(
+///         Segment::new(
+///             1,
+///             "Liver",
+///             Code::new("123037004", "SCT", "Anatomical Structure"),
+///             Code::new("10200004", "SCT", "Liver"),
+///         )
+///         .algorithm(SegmentAlgorithmType::Automatic, "Model X"),
+///         liver.view(),
+///     )
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SegmentationBuilder {
+    segmentation_type: SegmentationType,
+    segments: Vec<(Segment, Array3<u8>)>,
+    source_images: Vec<SourceImage>,
+    image_positions: Vec<[f64; 3]>,
+    image_orientation: Option<[f64; 6]>,
+    pixel_spacing: Option<[f64; 2]>,
+    slice_thickness: Option<f64>,
+    referenced_series: Option<String>,
+    attributes: InMemDicomObject,
+}
+
+impl SegmentationBuilder {
+    /// Create a new builder for the given kind of segmentation.
+    pub fn new(segmentation_type: SegmentationType) -> Self {
+        SegmentationBuilder {
+            segmentation_type,
+            segments: Vec::new(),
+            source_images: Vec::new(),
+            image_positions: Vec::new(),
+            image_orientation: None,
+            pixel_spacing: None,
+            slice_thickness: None,
+            referenced_series: None,
+            attributes: InMemDicomObject::new_empty(),
+        }
+    }
+
+    /// Define the source image frames, one per mask frame,
+    /// from which the segmentation is derived.
+    pub fn source_images(mut self, source_images: Vec<SourceImage>) -> Self {
+        self.source_images = source_images;
+        self
+    }
+
+    /// Define the series instance UID of the source images.
+    pub fn referenced_series(mut self, series_instance_uid: impl Into<String>) -> Self {
+        self.referenced_series = Some(series_instance_uid.into());
+        self
+    }
+
+    /// Define the image positions (patient), one per mask frame.
+    pub fn image_positions(mut self, image_positions: Vec<[f64; 3]>) -> Self {
+        self.image_positions = image_positions;
+        self
+    }
+
+    /// Define the image orientation (patient) shared by all frames.
+    pub fn image_orientation(mut self, image_orientation: [f64; 6]) -> Self {
+        self.image_orientation = Some(image_orientation);
+        self
+    }
+
+    /// Define the pixel spacing shared by all frames,
+    /// as `[row spacing, column spacing]` in millimeters.
+    pub fn pixel_spacing(mut self, pixel_spacing: [f64; 2]) -> Self {
+        self.pixel_spacing = Some(pixel_spacing);
+        self
+    }
+
+    /// Define the slice thickness shared by all frames, in millimeters.
+    pub fn slice_thickness(mut self, slice_thickness: f64) -> Self {
+        self.slice_thickness = Some(slice_thickness);
+        self
+    }
+
+    /// Add an attribute to the new object,
+    /// such as the patient's name or the study instance UID.
+    pub fn attribute(mut self, element: DataElement<InMemDicomObject>) -> Self {
+        self.attributes.put(element);
+        self
+    }
+
+    /// Add all attributes of the given data set to the new object.
+    pub fn attributes(mut self, attributes: InMemDicomObject) -> Self {
+        for element in attributes {
+            self.attributes.put(element);
+        }
+        self
+    }
+
+    /// Add a segment with its masks, as `[frame, row, column]`.
+    ///
+    /// In binary segmentations,
+    /// pixels with a value greater than 0 belong to the segment.
+    /// In fractional segmentations,
+    /// values are expected to be between 0 and 1,
+    /// and are scaled to the maximum fractional value.
+    pub fn add_segment<T>(mut self, segment: Segment, masks: ArrayView3<'_, T>) -> Self
+    where
+        T: ToPrimitive + Copy,
+    {
+        let values = match self.segmentation_type {
+            SegmentationType::Binary => {
+                masks.mapv(|v| u8::from(v.to_f64().map(|v| v > 0.).unwrap_or(false)))
+            }
+            SegmentationType::Fractional { max_value, .. } => masks.mapv(|v| {
+                let v = v.to_f64().unwrap_or(0.).clamp(0., 1.);
+                (v * f64::from(max_value)).round() as u8
+            }),
+        };
+        self.segments.push((segment, values));
+        self
+    }
+
+    /// Build the new segmentation object.
+    pub fn build(self) -> Result<DefaultDicomObject> {
+        let (_, first) = self.segments.first().context(NoSegmentsSnafu)?;
+        let (number_of_source_frames, rows, columns) = first.dim();
+        ensure!(
+            rows <= u16::MAX as usize && columns <= u16::MAX as usize,
+            FrameTooLargeSnafu { rows, columns }
+        );
+        for (index, (segment, masks)) in self.segments.iter().enumerate() {
+            ensure!(
+                segment.number as usize == index + 1,
+                InvalidSegmentNumberSnafu {
+                    index,
+                    expected: index as u16 + 1,
+                    number: segment.number,
+                }
+            );
+            ensure!(
+                masks.dim() == first.dim(),
+                InconsistentMaskSnafu {
+                    number: segment.number,
+                    shape: masks.shape().to_vec(),
+                    expected: first.shape().to_vec(),
+                }
+            );
+        }
+        for (what, actual) in [
+            ("source images", self.source_images.len()),
+            ("image positions", self.image_positions.len()),
+        ] {
+            ensure!(
+                actual == 0 || actual == number_of_source_frames,
+                FrameCountSnafu {
+                    what,
+                    expected: number_of_source_frames,
+                    actual,
+                }
+            );
+        }
+
+        let mut obj = InMemDicomObject::new_empty();
+
+        // type 2 attributes of the IOD, left empty unless provided
+        put_empty_attributes(&mut obj);
+        put_empty_image_attributes(&mut obj);
+        obj.put(DataElement::new(tags::SERIES_NUMBER, VR::IS, "1"));
+        obj.put(DataElement::new(tags::INSTANCE_NUMBER, VR::IS, "1"));
+        obj.put(DataElement::new(
+            tags::CONTENT_CREATOR_NAME,
+            VR::PN,
+            PrimitiveValue::Empty,
+        ));
+        obj.put(DataElement::new(
+            tags::CONTENT_LABEL,
+            VR::CS,
+            "SEGMENTATION",
+        ));
+
+        // enhanced general equipment module
+        obj.put(DataElement::new(tags::MANUFACTURER, VR::LO, "DICOM-rs"));
+        obj.put(DataElement::new(
+            tags::MANUFACTURER_MODEL_NAME,
+            VR::LO,
+            "dicom-pixeldata",
+        ));
+        obj.put(DataElement::new(tags::DEVICE_SERIAL_NUMBER, VR::LO, "0"));
+        obj.put(DataElement::new(
+            tags::SOFTWARE_VERSIONS,
+            VR::LO,
+            env!("CARGO_PKG_VERSION"),
+        ));
+
+        // provided attributes
+        for element in self.attributes.iter().cloned() {
+            obj.put(element);
+        }
+
+        // generated identifiers
+        put_missing_uids(&mut obj);
+        obj.put(DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            uids::SEGMENTATION_STORAGE,
+        ));
+        obj.put(DataElement::new(tags::MODALITY, VR::CS, "SEG"));
+        obj.put(DataElement::new(
+            tags::IMAGE_TYPE,
+            VR::CS,
+            dicom_value!(Strs, ["DERIVED", "PRIMARY"]),
+        ));
+
+        // segmentation image module
+        let bits = match self.segmentation_type {
+            SegmentationType::Binary => 1,
+            SegmentationType::Fractional { .. } => 8,
+        };
+        for (tag, value) in [
+            (tags::SAMPLES_PER_PIXEL, 1),
+            (tags::ROWS, rows as u16),
+            (tags::COLUMNS, columns as u16),
+            (tags::BITS_ALLOCATED, bits),
+            (tags::BITS_STORED, bits),
+            (tags::HIGH_BIT, bits - 1),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ] {
+            obj.put(DataElement::new(tag, VR::US, dicom_value!(U16, [value])));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(DataElement::new(
+            tags::LOSSY_IMAGE_COMPRESSION,
+            VR::CS,
+            "00",
+        ));
+        match self.segmentation_type {
+            SegmentationType::Binary => {
+                obj.put(DataElement::new(tags::SEGMENTATION_TYPE, VR::CS, "BINARY"));
+            }
+            SegmentationType::Fractional {
+                fractional_type,
+                max_value,
+            } => {
+                obj.put(DataElement::new(
+                    tags::SEGMENTATION_TYPE,
+                    VR::CS,
+                    "FRACTIONAL",
+                ));
+                obj.put(DataElement::new(
+                    tags::SEGMENTATION_FRACTIONAL_TYPE,
+                    VR::CS,
+                    match fractional_type {
+                        FractionalType::Probability => "PROBABILITY",
+                        FractionalType::Occupancy => "OCCUPANCY",
+                    },
+                ));
+                obj.put(DataElement::new(
+                    tags::MAXIMUM_FRACTIONAL_VALUE,
+                    VR::US,
+                    dicom_value!(U16, [u16::from(max_value)]),
+                ));
+            }
+        }
+        obj.put(sequence(
+            tags::SEGMENT_SEQUENCE,
+            self.segments
+                .iter()
+                .map(|(segment, _)| segment_item(segment))
+                .collect(),
+        ));
+
+        // multi-frame dimension module
+        let dimension_organization_uid = new_uid();
+        obj.put(sequence(
+            tags::DIMENSION_ORGANIZATION_SEQUENCE,
+            vec![InMemDicomObject::from_element_iter([DataElement::new(
+                tags::DIMENSION_ORGANIZATION_UID,
+                VR::UI,
+                dimension_organization_uid.as_str(),
+            )])],
+        ));
+        let mut dimensions = vec![(
+            tags::REFERENCED_SEGMENT_NUMBER,
+            tags::SEGMENT_IDENTIFICATION_SEQUENCE,
+            "Segment Number",
+        )];
+        if !self.image_positions.is_empty() {
+            dimensions.push((
+                tags::IMAGE_POSITION_PATIENT,
+                tags::PLANE_POSITION_SEQUENCE,
+                "Image Position Patient",
+            ));
+        }
+        obj.put(sequence(
+            tags::DIMENSION_INDEX_SEQUENCE,
+            dimensions
+                .into_iter()
+                .map(|(pointer, group, label)| {
+                    InMemDicomObject::from_element_iter([
+                        DataElement::new(
+                            tags::DIMENSION_ORGANIZATION_UID,
+                            VR::UI,
+                            dimension_organization_uid.as_str(),
+                        ),
+                        DataElement::new(
+                            tags::DIMENSION_INDEX_POINTER,
+                            VR::AT,
+                            PrimitiveValue::from(pointer),
+                        ),
+                        DataElement::new(
+                            tags::FUNCTIONAL_GROUP_POINTER,
+                            VR::AT,
+                            PrimitiveValue::from(group),
+                        ),
+                        DataElement::new(tags::DIMENSION_DESCRIPTION_LABEL, VR::LO, label),
+                    ])
+                })
+                .collect(),
+        ));
+
+        // multi-frame functional groups module
+        let mut shared = InMemDicomObject::new_empty();
+        if self.pixel_spacing.is_some() || self.slice_thickness.is_some() {
+            let mut item = InMemDicomObject::new_empty();
+            if let Some([row, column]) = self.pixel_spacing {
+                item.put(DataElement::new(
+                    tags::PIXEL_SPACING,
+                    VR::DS,
+                    PrimitiveValue::Strs(
+                        [format_decimal_string(row), format_decimal_string(column)].into(),
+                    ),
+                ));
+            }
+            if let Some(slice_thickness) = self.slice_thickness {
+                item.put(DataElement::new(
+                    tags::SLICE_THICKNESS,
+                    VR::DS,
+                    format_decimal_string(slice_thickness),
+                ));
+            }
+            shared.put(sequence(tags::PIXEL_MEASURES_SEQUENCE, vec![item]));
+        }
+        if let Some(orientation) = self.image_orientation {
+            shared.put(sequence(
+                tags::PLANE_ORIENTATION_SEQUENCE,
+                vec![InMemDicomObject::from_element_iter([DataElement::new(
+                    tags::IMAGE_ORIENTATION_PATIENT,
+                    VR::DS,
+                    PrimitiveValue::Strs(
+                        orientation
+                            .iter()
+                            .map(|&v| format_decimal_string(v))
+                            .collect(),
+                    ),
+                )])],
+            ));
+        }
+        obj.put(sequence(
+            tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+            vec![shared],
+        ));
+
+        let mut per_frame = Vec::new();
+        for (segment, _) in &self.segments {
+            for i in 0..number_of_source_frames {
+                per_frame.push(self.frame_item(segment.number, i));
+            }
+        }
+        obj.put(sequence(
+            tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+            per_frame,
+        ));
+        let number_of_frames = self.segments.len() * number_of_source_frames;
+        obj.put(DataElement::new(
+            tags::NUMBER_OF_FRAMES,
+            VR::IS,
+            number_of_frames.to_string(),
+        ));
+
+        // common instance reference module
+        if let Some(series_instance_uid) = &self.referenced_series {
+            let mut instances: Vec<&SourceImage> = Vec::new();
+            for source in &self.source_images {
+                if !instances
+                    .iter()
+                    .any(|s| s.sop_instance_uid == source.sop_instance_uid)
+                {
+                    instances.push(source);
+                }
+            }
+            obj.put(sequence(
+                tags::REFERENCED_SERIES_SEQUENCE,
+                vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(
+                        tags::SERIES_INSTANCE_UID,
+                        VR::UI,
+                        series_instance_uid.as_str(),
+                    ),
+                    sequence(
+                        tags::REFERENCED_INSTANCE_SEQUENCE,
+                        instances
+                            .into_iter()
+                            .map(|source| {
+                                InMemDicomObject::from_element_iter([
+                                    DataElement::new(
+                                        tags::REFERENCED_SOP_CLASS_UID,
+                                        VR::UI,
+                                        source.sop_class_uid.as_str(),
+                                    ),
+                                    DataElement::new(
+                                        tags::REFERENCED_SOP_INSTANCE_UID,
+                                        VR::UI,
+                                        source.sop_instance_uid.as_str(),
+                                    ),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ])],
+            ));
+        }
+
+        // pixel data, with bits packed across frames in binary segmentations
+        let values = self.segments.iter().flat_map(|(_, masks)| masks.iter());
+        let mut pixel_data = match self.segmentation_type {
+            SegmentationType::Binary => {
+                let mut data = vec![0_u8; (number_of_frames * rows * columns + 7) / 8];
+                for (i, &v) in values.enumerate() {
+                    data[i / 8] |= v << (i % 8);
+                }
+                data
+            }
+            SegmentationType::Fractional { .. } => values.copied().collect(),
+        };
+        if pixel_data.len() % 2 != 0 {
+            pixel_data.push(0);
+        }
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(pixel_data),
+        ));
+
+        obj.with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .context(BuildMetaSnafu)
+            .map_err(Error::from)
+    }
+
+    /// Create the per-frame functional groups
+    /// of the given segment over the source frame at the given index.
+    fn frame_item(&self, segment_number: u16, index: usize) -> InMemDicomObject {
+        let mut item = InMemDicomObject::new_empty();
+
+        let mut dimension_index = vec![u32::from(segment_number)];
+        if let Some(position) = self.image_positions.get(index) {
+            dimension_index.push(index as u32 + 1);
+            item.put(sequence(
+                tags::PLANE_POSITION_SEQUENCE,
+                vec![InMemDicomObject::from_element_iter([DataElement::new(
+                    tags::IMAGE_POSITION_PATIENT,
+                    VR::DS,
+                    PrimitiveValue::Strs(
+                        position.iter().map(|&v| format_decimal_string(v)).collect(),
+                    ),
+                )])],
+            ));
+        }
+        item.put(sequence(
+            tags::FRAME_CONTENT_SEQUENCE,
+            vec![InMemDicomObject::from_element_iter([DataElement::new(
+                tags::DIMENSION_INDEX_VALUES,
+                VR::UL,
+                PrimitiveValue::U32(dimension_index.into()),
+            )])],
+        ));
+
+        if let Some(source) = self.source_images.get(index) {
+            let mut source_item = InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::REFERENCED_SOP_CLASS_UID,
+                    VR::UI,
+                    source.sop_class_uid.as_str(),
+                ),
+                DataElement::new(
+                    tags::REFERENCED_SOP_INSTANCE_UID,
+                    VR::UI,
+                    source.sop_instance_uid.as_str(),
+                ),
+                sequence(
+                    tags::PURPOSE_OF_REFERENCE_CODE_SEQUENCE,
+                    vec![Code::new(
+                        "121322",
+                        "DCM",
+                        "Source image for image processing operation",
+                    )
+                    .to_item()],
+                ),
+            ]);
+            if let Some(frame_number) = source.frame_number {
+                source_item.put(DataElement::new(
+                    tags::REFERENCED_FRAME_NUMBER,
+                    VR::IS,
+                    frame_number.to_string(),
+                ));
+            }
+            item.put(sequence(
+                tags::DERIVATION_IMAGE_SEQUENCE,
+                vec![InMemDicomObject::from_element_iter([
+                    sequence(tags::SOURCE_IMAGE_SEQUENCE, vec![source_item]),
+                    sequence(
+                        tags::DERIVATION_CODE_SEQUENCE,
+                        vec![Code::new("113076", "DCM", "Segmentation").to_item()],
+                    ),
+                ])],
+            ));
+        }
+
+        item.put(sequence(
+            tags::SEGMENT_IDENTIFICATION_SEQUENCE,
+            vec![InMemDicomObject::from_element_iter([DataElement::new(
+                tags::REFERENCED_SEGMENT_NUMBER,
+                VR::US,
+                dicom_value!(U16, [segment_number]),
+            )])],
+        ));
+        item
+    }
+}
+
+fn segment_item(segment: &Segment) -> InMemDicomObject {
+    let mut item = InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SEGMENT_NUMBER,
+            VR::US,
+            dicom_value!(U16, [segment.number]),
+        ),
+        DataElement::new(tags::SEGMENT_LABEL, VR::LO, segment.label.as_str()),
+        DataElement::new(
+            tags::SEGMENT_ALGORITHM_TYPE,
+            VR::CS,
+            segment.algorithm_type.as_str(),
+        ),
+        sequence(
+            tags::SEGMENTED_PROPERTY_CATEGORY_CODE_SEQUENCE,
+            vec![segment.category.to_item()],
+        ),
+        sequence(
+            tags::SEGMENTED_PROPERTY_TYPE_CODE_SEQUENCE,
+            vec![segment.property_type.to_item()],
+        ),
+    ]);
+    if let Some(description) = &segment.description {
+        item.put(DataElement::new(
+            tags::SEGMENT_DESCRIPTION,
+            VR::ST,
+            description.as_str(),
+        ));
+    }
+    if let Some(algorithm_name) = &segment.algorithm_name {
+        item.put(DataElement::new(
+            tags::SEGMENT_ALGORITHM_NAME,
+            VR::LO,
+            algorithm_name.as_str(),
+        ));
+    }
+    item
+}
+
+fn sequence(tag: Tag, items: Vec<InMemDicomObject>) -> DataElement<InMemDicomObject> {
+    DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+}
+
+fn invalid_value(name: &'static str, value: impl std::fmt::Display) -> Error {
+    Error(InnerError::InvalidValue {
+        name,
+        value: value.to_string(),
+    })
+}
+
+/// Iterate over the items of the given sequence, if present.
+fn items(obj: &InMemDicomObject, tag: Tag) -> impl Iterator<Item = &InMemDicomObject> {
+    obj.get(tag)
+        .and_then(|e| e.items())
+        .unwrap_or_default()
+        .iter()
+}
+
+/// Read a trimmed, non-empty string value.
+fn string(obj: &InMemDicomObject, tag: Tag, name: &'static str) -> Result<Option<String>> {
+    let e = match obj.get(tag) {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    let value = e.to_str().context(ConvertValueSnafu { name })?;
+    let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    Ok(Some(value.to_string()).filter(|v| !v.is_empty()))
+}
+
+fn required_string(obj: &InMemDicomObject, tag: Tag, name: &'static str) -> Result<String> {
+    string(obj, tag, name)?
+        .context(MissingAttributeSnafu { name })
+        .map_err(Error::from)
+}
+
+fn required_int<T>(obj: &InMemDicomObject, tag: Tag, name: &'static str) -> Result<T>
+where
+    T: Clone + num_traits::NumCast + std::str::FromStr<Err = std::num::ParseIntError>,
+{
+    obj.get(tag)
+        .context(MissingAttributeSnafu { name })?
+        .to_int()
+        .context(ConvertValueSnafu { name })
+        .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{s, Array3};
+
+    fn liver() -> Segment {
+        Segment::new(
+            1,
+            "Liver",
+            Code::new("123037004", "SCT", "Anatomical Structure"),
+            Code::new("10200004", "SCT", "Liver"),
+        )
+        .algorithm(SegmentAlgorithmType::Automatic, "Model X")
+    }
+
+    fn tumor() -> Segment {
+        Segment::new(
+            2,
+            "Tumor",
+            Code::new("49755003", "SCT", "Morphologically Altered Structure"),
+            Code::new("108369006", "SCT", "Neoplasm"),
+        )
+        .description("Lesion 1")
+    }
+
+    fn sources() -> Vec<SourceImage> {
+        (1..=3)
+            .map(|i| SourceImage::new(uids::CT_IMAGE_STORAGE, format!("1.2.3.{}", i)))
+            .collect()
+    }
+
+    #[test]
+    fn build_and_read_binary_segmentation() {
+        // odd dimensions, so that frames do not end on a byte boundary
+        let mut liver_mask = Array3::<u8>::zeros((3, 5, 7));
+        liver_mask.slice_mut(s![.., 1..4, 2..6]).fill(1);
+        let mut tumor_mask = Array3::<bool>::from_elem((3, 5, 7), false);
+        tumor_mask[[1, 2, 3]] = true;
+
+        let obj = SegmentationBuilder::new(SegmentationType::Binary)
+            .source_images(sources())
+            .referenced_series("1.2.3")
+            .image_positions(vec![[0., 0., 0.], [0., 0., 2.5], [0., 0., 5.]])
+            .image_orientation([1., 0., 0., 0., 1., 0.])
+            .pixel_spacing([0.5, 0.5])
+            .attribute(DataElement::new(tags::PATIENT_ID, VR::LO, "12345"))
+            .add_segment(liver(), liver_mask.view())
+            .add_segment(tumor(), tumor_mask.mapv(u8::from).view())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::SEGMENTATION_STORAGE
+        );
+        // 6 frames of 35 bits
+        assert_eq!(
+            obj.element(tags::PIXEL_DATA)
+                .unwrap()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            28
+        );
+
+        let seg = Segmentation::from_obj(&obj).unwrap();
+        assert_eq!(seg.segmentation_type, SegmentationType::Binary);
+        assert_eq!(seg.segments, vec![liver(), tumor()]);
+        assert_eq!(seg.frames.len(), 6);
+        assert_eq!(seg.segment_frames(2).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(seg.frames[4].source, Some(sources()[1].clone()));
+        assert_eq!(seg.frames[4].image_position, Some([0., 0., 2.5]));
+
+        for i in 0..3 {
+            assert_eq!(seg.frame_data(i), liver_mask.index_axis(Axis(0), i));
+        }
+        let tumor_frame = seg.find_frame(2, "1.2.3.2", None).unwrap();
+        assert_eq!(tumor_frame, 4);
+        assert_eq!(seg.frame_mask(tumor_frame).sum(), 1.);
+        assert_eq!(seg.frame_mask(tumor_frame)[[2, 3]], 1.);
+        assert_eq!(seg.find_frame(2, "1.2.3.4", None), None);
+    }
+
+    #[test]
+    fn build_and_read_fractional_segmentation() {
+        let mut mask = Array3::<f32>::zeros((1, 4, 4));
+        mask[[0, 1, 1]] = 0.5;
+        mask[[0, 2, 2]] = 1.;
+
+        let obj = SegmentationBuilder::new(SegmentationType::Fractional {
+            fractional_type: FractionalType::Probability,
+            max_value: 200,
+        })
+        .add_segment(liver(), mask.view())
+        .build()
+        .unwrap();
+
+        let seg = Segmentation::from_obj(&obj).unwrap();
+        assert_eq!(
+            seg.segmentation_type,
+            SegmentationType::Fractional {
+                fractional_type: FractionalType::Probability,
+                max_value: 200,
+            }
+        );
+        assert_eq!(seg.frames[0].source, None);
+        assert_eq!(seg.frame_data(0)[[1, 1]], 100);
+        assert_eq!(seg.frame_mask(0), mask.index_axis(Axis(0), 0));
+    }
+
+    #[test]
+    fn geometry_fits_in_decimal_strings() {
+        let mask = Array3::<u8>::ones((1, 2, 2));
+        let angle = std::f64::consts::FRAC_PI_6;
+        let position = [-123.456789012345, 1. / 3., 0.1 + 0.2];
+        let obj = SegmentationBuilder::new(SegmentationType::Binary)
+            .image_positions(vec![position])
+            .image_orientation([angle.cos(), angle.sin(), 0., -angle.sin(), angle.cos(), 0.])
+            .pixel_spacing([1. / 3., 2. / 3.])
+            .slice_thickness(0.1 + 0.2)
+            .add_segment(liver(), mask.view())
+            .build()
+            .unwrap();
+
+        let shared = &obj
+            .element(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        let per_frame = &obj
+            .element(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        for (item, sequence, tag) in [
+            (shared, tags::PIXEL_MEASURES_SEQUENCE, tags::PIXEL_SPACING),
+            (shared, tags::PIXEL_MEASURES_SEQUENCE, tags::SLICE_THICKNESS),
+            (
+                shared,
+                tags::PLANE_ORIENTATION_SEQUENCE,
+                tags::IMAGE_ORIENTATION_PATIENT,
+            ),
+            (
+                per_frame,
+                tags::PLANE_POSITION_SEQUENCE,
+                tags::IMAGE_POSITION_PATIENT,
+            ),
+        ] {
+            let element = item.element(sequence).unwrap().items().unwrap()[0]
+                .element(tag)
+                .unwrap();
+            for value in element.to_multi_str().unwrap().iter() {
+                assert!(value.len() <= 16, "{} is too long", value);
+            }
+        }
+
+        let seg = Segmentation::from_obj(&obj).unwrap();
+        let read_position = seg.frames[0].image_position.unwrap();
+        for (read, expected) in read_position.iter().zip(position) {
+            assert!((read - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_segments() {
+        let mask = Array3::<u8>::zeros((3, 4, 4));
+        // segments must be numbered from 1
+        assert!(SegmentationBuilder::new(SegmentationType::Binary)
+            .add_segment(tumor(), mask.view())
+            .build()
+            .is_err());
+        // all masks must have the same shape
+        assert!(SegmentationBuilder::new(SegmentationType::Binary)
+            .add_segment(liver(), mask.view())
+            .add_segment(tumor(), mask.slice(s![..2, .., ..]))
+            .build()
+            .is_err());
+        // one source image per frame
+        assert!(SegmentationBuilder::new(SegmentationType::Binary)
+            .source_images(sources()[..2].to_vec())
+            .add_segment(liver(), mask.view())
+            .build()
+            .is_err());
+        assert!(SegmentationBuilder::new(SegmentationType::Binary)
+            .build()
+            .is_err());
+    }
+}
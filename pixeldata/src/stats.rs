@@ -0,0 +1,319 @@
+//! Pixel sample value statistics.
+//!
+//! This module contains the [`Histogram`] data type
+//! and percentile computation functions,
+//! which serve as the basis of the automatic windowing strategies
+//! described by [`AutoWindowLevel`].
+
+use crate::WindowLevel;
+
+/// A histogram of pixel sample values,
+/// with bins of equal width
+/// spanning from the lowest to the highest value.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_pixeldata::Histogram;
+/// let histogram = Histogram::from_values(&[0., 1., 1., 2., 10.], 5);
+///
+/// assert_eq!(histogram.min(), 0.);
+/// assert_eq!(histogram.max(), 10.);
+/// assert_eq!(histogram.bin_width(), 2.);
+/// assert_eq!(histogram.counts(), &[3, 1, 0, 0, 1]);
+/// assert_eq!(histogram.total(), 5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// the lowest sample value
+    min: f64,
+    /// the highest sample value
+    max: f64,
+    /// the number of samples in each bin
+    counts: Vec<u64>,
+}
+
+impl Histogram {
+    /// Build a histogram of the given sample values
+    /// with the given number of bins.
+    ///
+    /// Values which are not finite numbers (NaN or infinity) are ignored.
+    /// The last bin is closed on both ends,
+    /// so that the highest value is counted in it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bins` is 0.
+    pub fn from_values(values: &[f64], bins: usize) -> Self {
+        assert!(bins > 0, "histogram must have at least one bin");
+
+        let (min, max) = min_max(values).unwrap_or((0., 0.));
+        let mut counts = vec![0; bins];
+        let width = (max - min) / bins as f64;
+        for v in values.iter().filter(|v| v.is_finite()) {
+            let i = if width > 0. {
+                (((v - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[i] += 1;
+        }
+
+        Histogram { min, max, counts }
+    }
+
+    /// Get the lowest sample value,
+    /// which is also the lower bound of the first bin.
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+
+    /// Get the highest sample value,
+    /// which is also the upper bound of the last bin.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// Get the width of each bin.
+    pub fn bin_width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len() as f64
+    }
+
+    /// Get the lower and upper bounds of the bin at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn bin_range(&self, index: usize) -> (f64, f64) {
+        assert!(index < self.counts.len(), "bin index out of bounds");
+        let width = self.bin_width();
+        (
+            self.min + width * index as f64,
+            self.min + width * (index + 1) as f64,
+        )
+    }
+
+    /// Get the number of samples in each bin.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Get the total number of samples in the histogram.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// Calculate the given percentiles (from 0 to 100) of the sample values.
+///
+/// Percentiles are linearly interpolated between the closest ranks.
+/// Values which are not numbers (NaN) are ignored,
+/// and percentiles outside of the range `[0, 100]` are clamped.
+/// All percentiles are NaN if there are no values.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_pixeldata::percentiles;
+/// let values = [4., 1., 3., 2., 5.];
+/// assert_eq!(percentiles(&values, &[0., 50., 75., 100.]), vec![1., 3., 4., 5.]);
+/// ```
+pub fn percentiles(values: &[f64], percentiles: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+    sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+    percentiles
+        .iter()
+        .map(|p| percentile_of_sorted(&sorted, *p))
+        .collect()
+}
+
+fn percentile_of_sorted(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let rank = p.clamp(0., 100.) / 100. * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Get the lowest and highest finite values.
+fn min_max(values: &[f64]) -> Option<(f64, f64)> {
+    values
+        .iter()
+        .copied()
+        .filter(|v| v.is_finite())
+        .fold(None, |acc, v| match acc {
+            None => Some((v, v)),
+            Some((min, max)) => Some((min.min(v), max.max(v))),
+        })
+}
+
+/// A strategy for determining a window level
+/// from the statistics of the sample values in a frame,
+/// for when the object does not define one
+/// or the one defined is not suitable.
+///
+/// The statistics are taken
+/// after applying the modality LUT transformation.
+///
+/// See also [`VoiLutOption::Auto`](crate::VoiLutOption::Auto).
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum AutoWindowLevel {
+    /// Cover the full range of sample values,
+    /// from the lowest to the highest.
+    MinMax,
+    /// Cover the range between two percentiles (from 0 to 100),
+    /// clipping the outliers at both ends.
+    Percentile {
+        /// the percentile mapped to the lowest output value
+        low: f64,
+        /// the percentile mapped to the highest output value
+        high: f64,
+    },
+    /// Build a histogram of the sample values
+    /// and cover the range of bins
+    /// between the first and the last bin
+    /// containing at least the given fraction of all samples,
+    /// thus ignoring sparsely populated bins at both ends.
+    Histogram {
+        /// the number of bins in the histogram
+        bins: usize,
+        /// the minimum fraction of samples (from 0 to 1)
+        /// for a bin to be included in the window
+        threshold: f64,
+    },
+}
+
+impl AutoWindowLevel {
+    /// Determine the window level for the given sample values.
+    ///
+    /// The resulting window is meant for a linear VOI LUT function
+    /// and spans the range of values selected by the strategy.
+    /// Returns `None` if there are no values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the strategy is `Histogram` with 0 bins.
+    pub fn window_level(&self, values: &[f64]) -> Option<WindowLevel> {
+        let (low, high) = match *self {
+            AutoWindowLevel::MinMax => min_max(values)?,
+            AutoWindowLevel::Percentile { low, high } => {
+                match percentiles(values, &[low, high])[..] {
+                    [low, high] if !low.is_nan() => (low, high),
+                    _ => return None,
+                }
+            }
+            AutoWindowLevel::Histogram { bins, threshold } => {
+                let histogram = Histogram::from_values(values, bins);
+                let total = histogram.total();
+                if total == 0 {
+                    return None;
+                }
+                let threshold = threshold * total as f64;
+                let counts = histogram.counts();
+                let first = counts.iter().position(|c| *c as f64 >= threshold);
+                let last = counts.iter().rposition(|c| *c as f64 >= threshold);
+                match (first, last) {
+                    (Some(first), Some(last)) => {
+                        (histogram.bin_range(first).0, histogram.bin_range(last).1)
+                    }
+                    // no bin is populated enough, fall back to the full range
+                    _ => (histogram.min(), histogram.max()),
+                }
+            }
+        };
+        let high = high.max(low);
+
+        Some(WindowLevel {
+            width: high - low + 1.,
+            center: (low + high) / 2.,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_of_constant_values() {
+        let histogram = Histogram::from_values(&[5., 5., 5.], 4);
+        assert_eq!(histogram.min(), 5.);
+        assert_eq!(histogram.max(), 5.);
+        assert_eq!(histogram.counts(), &[3, 0, 0, 0]);
+
+        let histogram = Histogram::from_values(&[], 4);
+        assert_eq!(histogram.total(), 0);
+    }
+
+    #[test]
+    fn histogram_ignores_non_finite_values() {
+        let values = [f64::NEG_INFINITY, 0., 1., f64::NAN, 3., f64::INFINITY];
+        let histogram = Histogram::from_values(&values, 3);
+        assert_eq!(histogram.min(), 0.);
+        assert_eq!(histogram.max(), 3.);
+        assert_eq!(histogram.counts(), &[1, 1, 1]);
+
+        let window = AutoWindowLevel::MinMax.window_level(&values).unwrap();
+        assert_eq!(
+            window,
+            WindowLevel {
+                width: 4.,
+                center: 1.5,
+            }
+        );
+    }
+
+    #[test]
+    fn percentiles_are_interpolated() {
+        let values: Vec<f64> = (0..=10).map(|v| v as f64 * 10.).collect();
+        assert_eq!(
+            percentiles(&values, &[-5., 25., 50., 75., 150.]),
+            vec![0., 25., 50., 75., 100.]
+        );
+        assert!(percentiles(&[f64::NAN], &[50.])[0].is_nan());
+    }
+
+    #[test]
+    fn auto_window_level_strategies() {
+        // mostly between 0 and 100, with a couple of outliers
+        let mut values: Vec<f64> = (0..1000).map(|v| (v % 101) as f64).collect();
+        values.push(-3000.);
+        values.push(3000.);
+
+        let window = AutoWindowLevel::MinMax.window_level(&values).unwrap();
+        assert_eq!(
+            window,
+            WindowLevel {
+                width: 6001.,
+                center: 0.,
+            }
+        );
+
+        let window = AutoWindowLevel::Percentile { low: 1., high: 99. }
+            .window_level(&values)
+            .unwrap();
+        assert!(window.width > 90. && window.width < 101., "{:?}", window);
+        assert!(window.center > 45. && window.center < 55., "{:?}", window);
+
+        let window = AutoWindowLevel::Histogram {
+            bins: 60,
+            threshold: 0.01,
+        }
+        .window_level(&values)
+        .unwrap();
+        // only the bins around 0 to 100 are populated enough
+        assert_eq!(
+            window,
+            WindowLevel {
+                width: 101.,
+                center: 50.,
+            }
+        );
+
+        assert_eq!(AutoWindowLevel::MinMax.window_level(&[]), None);
+    }
+}
@@ -6,16 +6,14 @@
 //! to different transfer syntaxes.
 //!
 //! See the [`Transcode`] trait for more information.
-use dicom_core::{
-    ops::ApplyOp, value::PixelFragmentSequence, DataDictionary, DataElement, Length,
-    PrimitiveValue, VR,
-};
+use dicom_core::{ops::ApplyOp, DataDictionary, DataElement, Length, PrimitiveValue, VR};
 use dicom_dictionary_std::{tags, uids};
 use dicom_encoding::{adapters::EncodeOptions, Codec, TransferSyntax, TransferSyntaxIndex};
 use dicom_object::{FileDicomObject, InMemDicomObject};
 use dicom_transfer_syntax_registry::{entries::EXPLICIT_VR_LITTLE_ENDIAN, TransferSyntaxRegistry};
 use snafu::{OptionExt, ResultExt, Snafu};
 
+use crate::encapsulation::encapsulate_with_extended_offset_table;
 use crate::PixelDecoder;
 
 /// An error occurred during the object transcoding process.
@@ -30,9 +28,6 @@ pub(crate) enum InnerError {
     /// Unsupported target transfer syntax
     UnsupportedTransferSyntax,
 
-    /// Unsupported transcoding capability
-    UnsupportedTranscoding,
-
     /// Could not decode pixel data of receiving object  
     DecodePixelData { source: crate::Error },
 
@@ -125,7 +120,7 @@ where
                 ts: current_ts_uid.to_string(),
             })?;
 
-        match (current_ts.is_codec_free(), ts.is_codec_free()) {
+        match (current_ts.is_native_pixel_data(), ts.is_native_pixel_data()) {
             (true, true) => {
                 // no pixel data conversion is necessary:
                 // change transfer syntax and return
@@ -156,9 +151,8 @@ where
                         return UnsupportedTransferSyntaxSnafu.fail()?
                     }
                     Codec::Dataset(None) => return UnsupportedTransferSyntaxSnafu.fail()?,
-                    Codec::Dataset(Some(_)) => return UnsupportedTranscodingSnafu.fail()?,
-                    Codec::None => {
-                        // already tested in `is_codec_free`
+                    Codec::None | Codec::Dataset(Some(_)) => {
+                        // already tested in `is_native_pixel_data`
                         unreachable!("Unexpected codec from transfer syntax")
                     }
                 };
@@ -169,29 +163,7 @@ where
                 match writer.encode(&*self, options.clone(), &mut fragments, &mut offset_table) {
                     Ok(ops) => {
                         // success!
-                        let num_frames = offset_table.len();
-                        let total_pixeldata_len: u64 =
-                            fragments.iter().map(|f| f.len() as u64).sum();
-
-                        self.put(DataElement::new_with_len(
-                            tags::PIXEL_DATA,
-                            VR::OB,
-                            Length::UNDEFINED,
-                            PixelFragmentSequence::new(offset_table, fragments),
-                        ));
-
-                        self.put(DataElement::new(
-                            tags::NUMBER_OF_FRAMES,
-                            VR::IS,
-                            num_frames.to_string(),
-                        ));
-
-                        // provide Encapsulated Pixel Data Value Total Length
-                        self.put(DataElement::new(
-                            tags::ENCAPSULATED_PIXEL_DATA_VALUE_TOTAL_LENGTH,
-                            VR::UV,
-                            PrimitiveValue::from(total_pixeldata_len),
-                        ));
+                        put_encapsulated_pixel_data(self, offset_table.len(), fragments);
 
                         // try to apply operations
                         for (n, op) in ops.into_iter().enumerate() {
@@ -263,6 +235,10 @@ where
         _ => return UnsupportedBitsAllocatedSnafu { bits_allocated }.fail()?,
     };
 
+    // offset tables no longer apply to native pixel data
+    obj.remove_element(tags::EXTENDED_OFFSET_TABLE);
+    obj.remove_element(tags::EXTENDED_OFFSET_TABLE_LENGTHS);
+
     // change transfer syntax to Explicit VR little endian
     obj.update_meta(|meta| meta.set_transfer_syntax(ts));
 
@@ -283,9 +259,8 @@ where
         Codec::EncapsulatedPixelData(_, Some(writer)) => writer,
         Codec::EncapsulatedPixelData(..) => return UnsupportedTransferSyntaxSnafu.fail()?,
         Codec::Dataset(None) => return UnsupportedTransferSyntaxSnafu.fail()?,
-        Codec::Dataset(Some(_)) => return UnsupportedTranscodingSnafu.fail()?,
-        Codec::None => {
-            // already tested in `is_codec_free`
+        Codec::None | Codec::Dataset(Some(_)) => {
+            // already tested in `is_native_pixel_data`
             unreachable!("Unexpected codec from transfer syntax")
         }
     };
@@ -301,14 +276,56 @@ where
         .encode(&*obj, options, &mut fragments, &mut offset_table)
         .context(EncodePixelDataSnafu)?;
 
-    let num_frames = offset_table.len();
+    put_encapsulated_pixel_data(obj, offset_table.len(), fragments);
+
+    // try to apply operations
+    for (n, op) in ops.into_iter().enumerate() {
+        match obj.apply(op) {
+            Ok(_) => (),
+            Err(e) => {
+                tracing::warn!("Could not apply transcoding step #{}: {}", n, e)
+            }
+        }
+    }
+
+    // change transfer syntax
+    obj.update_meta(|meta| meta.set_transfer_syntax(ts));
+
+    Ok(())
+}
+
+/// replace the object's pixel data with the given encoded fragments,
+/// one fragment per frame,
+/// along with the attributes describing them
+fn put_encapsulated_pixel_data<D>(
+    obj: &mut FileDicomObject<InMemDicomObject<D>>,
+    num_frames: usize,
+    fragments: Vec<Vec<u8>>,
+) where
+    D: Clone + DataDictionary,
+{
     let total_pixeldata_len: u64 = fragments.iter().map(|f| f.len() as u64).sum();
 
+    // the fragments are padded to an even length,
+    // so that the offsets match the positions of the written items
+    let (mut pixel_data, offset_table) = encapsulate_with_extended_offset_table(fragments);
+
+    // the basic offset table cannot locate frames beyond 4 GiB,
+    // use the extended offset table in that case
+    match offset_table.to_basic_offset_table() {
+        Some(basic_offset_table) => {
+            obj.remove_element(tags::EXTENDED_OFFSET_TABLE);
+            obj.remove_element(tags::EXTENDED_OFFSET_TABLE_LENGTHS);
+            *pixel_data.offset_table_mut() = basic_offset_table.into();
+        }
+        None => offset_table.put_into(obj),
+    }
+
     obj.put(DataElement::new_with_len(
         tags::PIXEL_DATA,
         VR::OB,
         Length::UNDEFINED,
-        PixelFragmentSequence::new(offset_table, fragments),
+        pixel_data,
     ));
 
     obj.put(DataElement::new(
@@ -323,21 +340,6 @@ where
         VR::UV,
         PrimitiveValue::from(total_pixeldata_len),
     ));
-
-    // try to apply operations
-    for (n, op) in ops.into_iter().enumerate() {
-        match obj.apply(op) {
-            Ok(_) => (),
-            Err(e) => {
-                tracing::warn!("Could not apply transcoding step #{}: {}", n, e)
-            }
-        }
-    }
-
-    // change transfer syntax
-    obj.update_meta(|meta| meta.set_transfer_syntax(ts));
-
-    Ok(())
 }
 
 #[cfg(test)]
@@ -348,7 +350,8 @@ mod tests {
     #[cfg(feature = "native")]
     use dicom_transfer_syntax_registry::entries::JPEG_BASELINE;
     use dicom_transfer_syntax_registry::entries::{
-        ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN, JPEG_EXTENDED,
+        DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN, ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN,
+        JPEG_EXTENDED,
     };
 
     #[cfg(feature = "native")]
@@ -578,6 +581,45 @@ mod tests {
         }
     }
 
+    /// converting from and to Deflated Explicit VR Little Endian
+    /// only changes the transfer syntax
+    #[test]
+    fn test_transcode_deflated() {
+        let test_file = dicom_test_files::path("pydicom/image_dfl.dcm").unwrap();
+        let mut obj = open_file(test_file).unwrap();
+
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            uids::DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN
+        );
+        let original_pixel_data = obj
+            .get(tags::PIXEL_DATA)
+            .unwrap()
+            .to_bytes()
+            .unwrap()
+            .to_vec();
+
+        obj.transcode(&EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .expect("Should have transcoded successfully");
+
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            EXPLICIT_VR_LITTLE_ENDIAN.uid()
+        );
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap();
+        assert_eq!(pixel_data, &original_pixel_data[..]);
+
+        obj.transcode(&DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .expect("Should have transcoded successfully");
+
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            uids::DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN
+        );
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap();
+        assert_eq!(pixel_data, &original_pixel_data[..]);
+    }
+
     /// converting to Encapsulated Uncompressed Explicit VR Little Endian
     /// should split each frame into separate fragments in native form
     #[test]
@@ -601,4 +643,255 @@ mod tests {
         assert_eq!(fragments[0].len(), 100 * 100 * 3);
         assert_eq!(fragments[1].len(), 100 * 100 * 3);
     }
+
+    /// encapsulating frames should produce a basic offset table
+    /// pointing to each frame,
+    /// and discard any previous extended offset table
+    #[test]
+    fn test_transcode_encapsulated_offset_table() {
+        use dicom_core::dicom_value;
+        use dicom_object::FileMetaTableBuilder;
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.208718453011836744325616478962436232212")
+                .build()
+                .unwrap(),
+        );
+        for (tag, value) in [
+            (tags::ROWS, 2),
+            (tags::COLUMNS, 3),
+            (tags::SAMPLES_PER_PIXEL, 1),
+            (tags::BITS_ALLOCATED, 8),
+            (tags::BITS_STORED, 8),
+            (tags::HIGH_BIT, 7),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ]
+        .iter()
+        {
+            obj.put(DataElement::new(*tag, VR::US, dicom_value!(U16, *value)));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            dicom_value!(Str, "MONOCHROME2"),
+        ));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "3"));
+        obj.put(DataElement::new(
+            tags::EXTENDED_OFFSET_TABLE,
+            VR::OV,
+            dicom_value!(U64, [0, 1, 2]),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::U8((0..18).collect()),
+        ));
+
+        obj.transcode(&ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .expect("Should have transcoded successfully");
+
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap();
+        assert_eq!(pixel_data.fragments().unwrap().len(), 3);
+        assert_eq!(pixel_data.offset_table().unwrap(), &[0, 14, 28]);
+        assert!(obj.get(tags::EXTENDED_OFFSET_TABLE).is_none());
+        assert!(obj.get(tags::EXTENDED_OFFSET_TABLE_LENGTHS).is_none());
+    }
+
+    /// the offset table should point to the written items
+    /// when encoded frames have an odd length
+    #[test]
+    fn test_transcode_encapsulated_odd_frame_lengths() {
+        use byteorder::{ByteOrder, LittleEndian};
+        use dicom_core::dicom_value;
+        use dicom_object::FileMetaTableBuilder;
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.208718453011836744325616478962436232212")
+                .build()
+                .unwrap(),
+        );
+        // frames of 3 bytes
+        for (tag, value) in [
+            (tags::ROWS, 1),
+            (tags::COLUMNS, 3),
+            (tags::SAMPLES_PER_PIXEL, 1),
+            (tags::BITS_ALLOCATED, 8),
+            (tags::BITS_STORED, 8),
+            (tags::HIGH_BIT, 7),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ]
+        .iter()
+        {
+            obj.put(DataElement::new(*tag, VR::US, dicom_value!(U16, *value)));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            dicom_value!(Str, "MONOCHROME2"),
+        ));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "3"));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::U8((1..=9).collect()),
+        ));
+
+        obj.transcode(&ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .expect("Should have transcoded successfully");
+
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap();
+        let offset_table = pixel_data.offset_table().unwrap().to_vec();
+        assert_eq!(offset_table, &[0, 12, 24]);
+
+        // locate the fragment items in the written data set
+        let mut data = Vec::new();
+        obj.write_dataset(&mut data).unwrap();
+        let pixel_data_start = data
+            .windows(4)
+            .rposition(|w| w == [0xE0, 0x7F, 0x10, 0x00])
+            .unwrap();
+        // skip the element header and the basic offset table item
+        let mut position = pixel_data_start + 12;
+        let bot_len = LittleEndian::read_u32(&data[position + 4..]) as usize;
+        position += 8 + bot_len;
+        let first_fragment = position;
+        let mut item_positions = Vec::new();
+        let mut fragments = Vec::new();
+        while data[position..position + 4] == [0xFE, 0xFF, 0x00, 0xE0] {
+            let len = LittleEndian::read_u32(&data[position + 4..]) as usize;
+            item_positions.push((position - first_fragment) as u32);
+            fragments.push(&data[position + 8..position + 8 + 3]);
+            position += 8 + len;
+        }
+        assert_eq!(item_positions, offset_table);
+        assert_eq!(fragments, [&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]]);
+    }
+
+    /// transcoding to JPEG lossless and back to native
+    /// should preserve the pixel data exactly
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_transcode_to_jpeg_lossless_and_back() {
+        use dicom_core::dicom_value;
+        use dicom_object::FileMetaTableBuilder;
+        use dicom_transfer_syntax_registry::entries::{
+            EXPLICIT_VR_LITTLE_ENDIAN, JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION,
+        };
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.208718453011836744325616478962436232212")
+                .build()
+                .unwrap(),
+        );
+        for (tag, value) in [
+            (tags::ROWS, 6),
+            (tags::COLUMNS, 7),
+            (tags::SAMPLES_PER_PIXEL, 1),
+            (tags::BITS_ALLOCATED, 16),
+            (tags::BITS_STORED, 12),
+            (tags::HIGH_BIT, 11),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ]
+        .iter()
+        {
+            obj.put(DataElement::new(*tag, VR::US, dicom_value!(U16, *value)));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            dicom_value!(Str, "MONOCHROME2"),
+        ));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        let original_pixel_data: Vec<u16> = (0..2 * 6 * 7).map(|i: u16| i * 97 % 4096).collect();
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(original_pixel_data.clone().into()),
+        ));
+
+        obj.transcode(&JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION.erased())
+            .expect("Should have transcoded to JPEG lossless");
+
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION.uid()
+        );
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap();
+        assert_eq!(pixel_data.fragments().unwrap().len(), 2);
+
+        obj.transcode(&EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .expect("Should have transcoded back to native");
+
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap().uint16_slice().unwrap();
+        assert_eq!(pixel_data, &original_pixel_data[..]);
+    }
+
+    /// transcoding to RLE lossless and back to native
+    /// should preserve the pixel data exactly
+    #[cfg(feature = "rle")]
+    #[test]
+    fn test_transcode_to_rle_and_back() {
+        use dicom_core::dicom_value;
+        use dicom_object::FileMetaTableBuilder;
+        use dicom_transfer_syntax_registry::entries::{EXPLICIT_VR_LITTLE_ENDIAN, RLE_LOSSLESS};
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.208718453011836744325616478962436232212")
+                .build()
+                .unwrap(),
+        );
+        for (tag, value) in [
+            (tags::ROWS, 4),
+            (tags::COLUMNS, 5),
+            (tags::SAMPLES_PER_PIXEL, 3),
+            (tags::PLANAR_CONFIGURATION, 0),
+            (tags::BITS_ALLOCATED, 16),
+            (tags::BITS_STORED, 16),
+            (tags::HIGH_BIT, 15),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ]
+        .iter()
+        {
+            obj.put(DataElement::new(*tag, VR::US, dicom_value!(U16, *value)));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            dicom_value!(Str, "RGB"),
+        ));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        let original_pixel_data: Vec<u8> = (0..2 * 4 * 5 * 3 * 2)
+            .map(|i: u32| (i * 7 / 5) as u8)
+            .collect();
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U8(original_pixel_data.clone().into()),
+        ));
+
+        obj.transcode(&RLE_LOSSLESS.erased())
+            .expect("Should have transcoded to RLE lossless");
+
+        assert_eq!(obj.meta().transfer_syntax(), RLE_LOSSLESS.uid());
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap();
+        assert_eq!(pixel_data.fragments().unwrap().len(), 2);
+
+        obj.transcode(&EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .expect("Should have transcoded back to native");
+
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap();
+        assert_eq!(pixel_data, &original_pixel_data[..]);
+    }
 }
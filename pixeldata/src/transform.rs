@@ -135,6 +135,117 @@ impl WindowLevelTransform {
     }
 }
 
+/// A look-up table (LUT) transformation of sample values,
+/// as described by a _LUT Descriptor_ and _LUT Data_
+/// in an item of the _Modality LUT Sequence_ or the _VOI LUT Sequence_.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_pixeldata::LutTable;
+/// let table = LutTable::new(-2, 12, vec![0, 1000, 4095]);
+///
+/// assert_eq!(table.apply(-1.), 1000.);
+/// // values outside of the table are clamped to the first or last entry
+/// assert_eq!(table.apply(-10.), 0.);
+/// assert_eq!(table.apply(10.), 4095.);
+/// assert_eq!(table.max_output(), 4095.);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LutTable {
+    /// the input value mapped to the first entry of the table
+    first_mapped: i32,
+    /// the number of bits of each entry
+    bits: u16,
+    data: Vec<u16>,
+}
+
+impl LutTable {
+    /// Create a new LUT table
+    /// from the first mapped input value,
+    /// the number of bits per entry,
+    /// and the table entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is empty.
+    pub fn new(first_mapped: i32, bits: u16, data: Vec<u16>) -> Self {
+        assert!(!data.is_empty(), "LUT table must not be empty");
+        LutTable {
+            first_mapped,
+            bits,
+            data,
+        }
+    }
+
+    /// Get the input value mapped to the first entry of the table.
+    pub fn first_mapped_value(&self) -> i32 {
+        self.first_mapped
+    }
+
+    /// Get the number of bits of each entry in the table.
+    pub fn bits_per_entry(&self) -> u16 {
+        self.bits
+    }
+
+    /// Get the entries of the table.
+    pub fn data(&self) -> &[u16] {
+        &self.data
+    }
+
+    /// Get the highest output value which can be represented
+    /// with the number of bits per entry.
+    pub fn max_output(&self) -> f64 {
+        2_f64.powi(i32::from(self.bits)) - 1.
+    }
+
+    /// Apply the look-up table to a value.
+    ///
+    /// Values below the first mapped value
+    /// are mapped to the first entry,
+    /// and values beyond the end of the table
+    /// are mapped to the last entry.
+    pub fn apply(&self, value: f64) -> f64 {
+        let index = (value.round() - f64::from(self.first_mapped))
+            .max(0.)
+            .min((self.data.len() - 1) as f64);
+        f64::from(self.data[index as usize])
+    }
+}
+
+/// The modality LUT transformation of sample values,
+/// either as a rescale function or as a look-up table.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ModalityTransform<'a> {
+    /// A rescale function
+    Rescale(Rescale),
+    /// A look-up table
+    Table(&'a LutTable),
+}
+
+impl ModalityTransform<'_> {
+    /// Apply the modality LUT transformation to a value.
+    #[inline]
+    pub fn apply(&self, value: f64) -> f64 {
+        match self {
+            ModalityTransform::Rescale(rescale) => rescale.apply(value),
+            ModalityTransform::Table(table) => table.apply(value),
+        }
+    }
+}
+
+impl From<Rescale> for ModalityTransform<'_> {
+    fn from(rescale: Rescale) -> Self {
+        ModalityTransform::Rescale(rescale)
+    }
+}
+
+impl<'a> From<&'a LutTable> for ModalityTransform<'a> {
+    fn from(table: &'a LutTable) -> Self {
+        ModalityTransform::Table(table)
+    }
+}
+
 fn window_level_linear(value: f64, window_width: f64, window_center: f64, y_max: f64) -> f64 {
     let ww = window_width;
     let wc = window_center;
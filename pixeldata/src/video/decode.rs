@@ -0,0 +1,143 @@
+//! Decoding of H.264 video frames through OpenH264.
+use super::{
+    write_video_stream, DecodeVideoSnafu, Result, UnsupportedVideoCodecSnafu, VideoCodec,
+    VideoFrameOutOfRangeSnafu,
+};
+use crate::{
+    DecodedPixelData, PhotometricInterpretation, PixelDataKind, PixelRepresentation,
+    PlanarConfiguration, Rescale,
+};
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use openh264::decoder::{DecodedYUV, Decoder};
+use openh264::formats::YUVSource;
+use openh264::nal_units;
+use snafu::{ensure, ResultExt};
+use std::borrow::Cow;
+
+/// Decode all frames of a video object into RGB pixel data.
+///
+/// Only H.264 transfer syntaxes are supported,
+/// with the stream kept as a sequence of NAL units in Annex B format.
+/// The decoded frames have 3 samples per pixel
+/// and 8 bits per sample.
+pub fn decode_video_frames(
+    obj: &FileDicomObject<InMemDicomObject>,
+) -> Result<DecodedPixelData<'static>> {
+    decode_frames(obj, None)
+}
+
+/// Decode a single frame of a video object into RGB pixel data.
+///
+/// Since video frames depend on the frames before them,
+/// all frames up to the requested one are decoded.
+/// See [`decode_video_frames`] for the supported transfer syntaxes.
+pub fn decode_video_frame(
+    obj: &FileDicomObject<InMemDicomObject>,
+    frame: u32,
+) -> Result<DecodedPixelData<'static>> {
+    decode_frames(obj, Some(frame))
+}
+
+/// Decode the frames of a video object,
+/// keeping only the given frame if any.
+fn decode_frames(
+    obj: &FileDicomObject<InMemDicomObject>,
+    frame: Option<u32>,
+) -> Result<DecodedPixelData<'static>> {
+    let mut stream = Vec::new();
+    let codec = write_video_stream(obj, &mut stream)?;
+    ensure!(
+        codec == VideoCodec::H264,
+        UnsupportedVideoCodecSnafu { codec }
+    );
+
+    let mut decoder = Decoder::new().context(DecodeVideoSnafu)?;
+    let mut frames = DecodedFrames::new(frame);
+    for nal in nal_units(&stream) {
+        if frames.is_done() {
+            break;
+        }
+        if let Some(yuv) = decoder.decode(nal).context(DecodeVideoSnafu)? {
+            frames.push(&yuv);
+        }
+    }
+    if !frames.is_done() {
+        for yuv in decoder.flush_remaining().context(DecodeVideoSnafu)? {
+            frames.push(&yuv);
+        }
+    }
+
+    // there must be at least one frame
+    let last_frame = frame.unwrap_or(0);
+    ensure!(
+        frames.count > last_frame,
+        VideoFrameOutOfRangeSnafu { frame: last_frame }
+    );
+
+    Ok(DecodedPixelData {
+        data: Cow::Owned(frames.data),
+        rows: frames.rows,
+        cols: frames.cols,
+        number_of_frames: frames.kept,
+        photometric_interpretation: PhotometricInterpretation::Rgb,
+        samples_per_pixel: 3,
+        planar_configuration: PlanarConfiguration::Standard,
+        bits_allocated: 8,
+        bits_stored: 8,
+        high_bit: 7,
+        pixel_representation: PixelRepresentation::Unsigned,
+        pixel_data_kind: PixelDataKind::Integer,
+        rescale: vec![Rescale::new(1., 0.)],
+        voi_lut_function: None,
+        window: None,
+        modality_lut: None,
+        voi_luts: Vec::new(),
+        palette: None,
+        overlays: Vec::new(),
+        enforce_frame_fg_vm_match: false,
+    })
+}
+
+/// The RGB samples of the decoded frames.
+struct DecodedFrames {
+    /// the frame to keep, or `None` to keep all frames
+    frame: Option<u32>,
+    /// the number of frames decoded so far
+    count: u32,
+    /// the number of frames kept
+    kept: u32,
+    rows: u32,
+    cols: u32,
+    data: Vec<u8>,
+}
+
+impl DecodedFrames {
+    fn new(frame: Option<u32>) -> Self {
+        DecodedFrames {
+            frame,
+            count: 0,
+            kept: 0,
+            rows: 0,
+            cols: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// Whether the requested frame was already decoded.
+    fn is_done(&self) -> bool {
+        matches!(self.frame, Some(frame) if self.count > frame)
+    }
+
+    fn push(&mut self, yuv: &DecodedYUV) {
+        if self.frame.map_or(true, |frame| frame == self.count) {
+            let (cols, rows) = yuv.dimensions();
+            self.cols = cols as u32;
+            self.rows = rows as u32;
+            let start = self.data.len();
+            self.data.resize(start + cols * rows * 3, 0);
+            yuv.write_rgb8(&mut self.data[start..]);
+            self.kept += 1;
+        }
+        self.count += 1;
+    }
+}
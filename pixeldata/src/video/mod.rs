@@ -0,0 +1,813 @@
+//! Encapsulated video streams.
+//!
+//! DICOM objects in one of the MPEG-2, MPEG-4 AVC/H.264 or HEVC/H.265
+//! transfer syntaxes keep the whole video bit stream
+//! in the fragments of the encapsulated _Pixel Data_,
+//! rather than one compressed image per frame.
+//! This module recovers that bit stream
+//! so that it can be played or decoded by general purpose video tools,
+//! and creates new video objects from MP4 files
+//! (see [`VideoObjectBuilder`]).
+//!
+//! With the `openh264` Cargo feature,
+//! the frames of H.264 video objects can also be decoded
+//! into [`DecodedPixelData`](crate::DecodedPixelData)
+//! (see [`decode_video_frames`] and [`decode_video_frame`]).
+//! The feature builds the bundled OpenH264 decoder,
+//! and requires a more recent Rust compiler than the rest of the crate.
+//! Decoding MPEG-2 and HEVC video is not supported.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! use dicom_object::open_file;
+//! use dicom_pixeldata::video::write_video_stream;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let obj = open_file("video.dcm")?;
+//! let mut out = Vec::new();
+//! let codec = write_video_stream(&obj, &mut out)?;
+//! std::fs::write(format!("video.{}", codec.extension()), out)?;
+//! # Ok(())
+//! # }
+//! ```
+use dicom_core::value::{DataSetSequence, PixelFragmentSequence, Value};
+use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::instance::{put_empty_attributes, put_empty_image_attributes, put_missing_uids};
+use dicom_object::{DefaultDicomObject, FileDicomObject, FileMetaTableBuilder, InMemDicomObject};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use std::io::Write;
+
+#[cfg(feature = "openh264")]
+mod decode;
+mod mp4;
+
+#[cfg(feature = "openh264")]
+pub use decode::{decode_video_frame, decode_video_frames};
+
+/// An error occurred while reading or writing a video stream.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Transfer syntax `{ts_uid}` is not a video transfer syntax
+    NotVideo { ts_uid: String },
+
+    /// Missing pixel data
+    MissingPixelData,
+
+    /// Pixel data is not encapsulated
+    NotEncapsulated,
+
+    /// Could not write the video stream
+    WriteStream { source: std::io::Error },
+
+    /// Invalid or truncated MP4 file
+    InvalidMp4,
+
+    /// Missing `{kind}` box in MP4 file
+    MissingMp4Box { kind: String },
+
+    /// MP4 file has no video track
+    NoVideoTrack,
+
+    /// Unsupported video sample entry `{kind}`
+    UnsupportedSampleEntry { kind: String },
+
+    /// Video ({codec:?} profile {profile}, level {level}, {columns}x{rows})
+    /// is not supported by any video transfer syntax
+    UnsupportedVideo {
+        codec: VideoCodec,
+        profile: u8,
+        level: u8,
+        rows: u16,
+        columns: u16,
+    },
+
+    /// Video is not compatible with transfer syntax `{ts_uid}`
+    IncompatibleTransferSyntax { ts_uid: String },
+
+    /// Unsupported SOP class `{sop_class_uid}`
+    UnsupportedSopClass { sop_class_uid: String },
+
+    /// Video stream is too large for a single fragment
+    StreamTooLarge,
+
+    /// Could not build the file meta table
+    BuildMeta { source: dicom_object::WithMetaError },
+
+    /// Decoding {codec:?} video is not supported
+    #[cfg(feature = "openh264")]
+    UnsupportedVideoCodec { codec: VideoCodec },
+
+    /// Could not decode the video stream
+    #[cfg(feature = "openh264")]
+    DecodeVideo { source: openh264::Error },
+
+    /// Video frame #{frame} is out of range
+    #[cfg(feature = "openh264")]
+    VideoFrameOutOfRange { frame: u32 },
+}
+
+/// Alias for the result of video stream operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A video compression format supported by DICOM transfer syntaxes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum VideoCodec {
+    /// MPEG-2 video
+    Mpeg2,
+    /// MPEG-4 AVC/H.264
+    H264,
+    /// HEVC/H.265
+    Hevc,
+}
+
+impl VideoCodec {
+    /// Identify the video codec of the given transfer syntax,
+    /// returning `None` if it is not a video transfer syntax.
+    ///
+    /// Trailing padding in the UID is ignored.
+    pub fn from_transfer_syntax(ts_uid: &str) -> Option<Self> {
+        match ts_uid.trim_end_matches(|c: char| c.is_whitespace() || c == '\0') {
+            uids::MPEG2MPML | uids::MPEG2MPMLF | uids::MPEG2MPHL | uids::MPEG2MPHLF => {
+                Some(VideoCodec::Mpeg2)
+            }
+            uids::MPEG4HP41
+            | uids::MPEG4HP41F
+            | uids::MPEG4HP41BD
+            | uids::MPEG4HP41BDF
+            | uids::MPEG4HP422D
+            | uids::MPEG4HP422DF
+            | uids::MPEG4HP423D
+            | uids::MPEG4HP423DF
+            | uids::MPEG4HP42STEREO
+            | uids::MPEG4HP42STEREOF => Some(VideoCodec::H264),
+            uids::HEVCMP51 | uids::HEVCM10P51 => Some(VideoCodec::Hevc),
+            _ => None,
+        }
+    }
+
+    /// Get the conventional file extension (without the leading dot)
+    /// of a file containing only the extracted video stream.
+    pub fn extension(self) -> &'static str {
+        match self {
+            VideoCodec::Mpeg2 => "mpg",
+            VideoCodec::H264 => "h264",
+            VideoCodec::Hevc => "hevc",
+        }
+    }
+}
+
+/// Write the video stream of a DICOM object to the given writer,
+/// returning the codec of the stream.
+///
+/// The stream is the concatenation of all pixel data fragments,
+/// which is the same whether the transfer syntax is fragmentable or not.
+/// The data is written as is,
+/// so it retains the container or elementary stream format
+/// prescribed by the transfer syntax.
+pub fn write_video_stream<W>(
+    obj: &FileDicomObject<InMemDicomObject>,
+    mut to: W,
+) -> Result<VideoCodec>
+where
+    W: Write,
+{
+    let ts_uid = obj.meta().transfer_syntax();
+    let codec = VideoCodec::from_transfer_syntax(ts_uid).context(NotVideoSnafu { ts_uid })?;
+
+    let pixel_data = obj.get(tags::PIXEL_DATA).context(MissingPixelDataSnafu)?;
+    let Value::PixelSequence(seq) = pixel_data.value() else {
+        return NotEncapsulatedSnafu.fail()?;
+    };
+
+    for fragment in seq.fragments() {
+        to.write_all(fragment).context(WriteStreamSnafu)?;
+    }
+    to.flush().context(WriteStreamSnafu)?;
+
+    Ok(codec)
+}
+
+/// Get the file extension (without the leading dot)
+/// most suitable for the given video stream.
+///
+/// Video streams wrapped in an MP4 container or an MPEG-2 transport stream
+/// are recognized by their first bytes.
+/// Otherwise, the conventional extension of the codec is returned.
+pub fn video_file_extension(codec: VideoCodec, stream: &[u8]) -> &'static str {
+    if stream.get(4..8) == Some(b"ftyp") {
+        "mp4"
+    } else if stream.first() == Some(&0x47) && stream.get(188) == Some(&0x47) {
+        "ts"
+    } else {
+        codec.extension()
+    }
+}
+
+/// The properties of a video track in an MP4 file.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct VideoInfo {
+    /// the video codec
+    pub codec: VideoCodec,
+    /// the codec profile indicator
+    /// (`profile_idc` in H.264, `general_profile_idc` in HEVC)
+    pub profile: u8,
+    /// the codec level indicator,
+    /// 10 times the level number in H.264
+    /// and 30 times the level number in HEVC
+    pub level: u8,
+    /// the height of the video in pixels
+    pub rows: u16,
+    /// the width of the video in pixels
+    pub columns: u16,
+    /// the bit depth of the luma samples
+    pub bit_depth: u8,
+    /// the number of video frames
+    pub number_of_frames: u32,
+    /// the mean duration of each frame in milliseconds
+    pub frame_time: f64,
+}
+
+impl VideoInfo {
+    /// Read the properties of the first video track in an MP4 file.
+    ///
+    /// Only H.264 and HEVC video tracks are supported.
+    pub fn from_mp4(data: &[u8]) -> Result<Self> {
+        mp4::read_video_info(data)
+    }
+
+    /// Check whether the video complies with the given transfer syntax,
+    /// as far as profile, level and resolution are concerned.
+    pub fn fits_transfer_syntax(&self, ts_uid: &str) -> bool {
+        let ts_uid = ts_uid.trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+        let (max_rows, max_columns) = match self.codec {
+            VideoCodec::H264 => (1080, 1920),
+            VideoCodec::Hevc => (2160, 4096),
+            VideoCodec::Mpeg2 => return false,
+        };
+        if self.rows > max_rows || self.columns > max_columns {
+            return false;
+        }
+
+        // Main profile streams are also valid High profile streams
+        let high_profile = matches!(self.profile, 77 | 100) && self.bit_depth == 8;
+        match (self.codec, ts_uid) {
+            (VideoCodec::H264, uids::MPEG4HP41 | uids::MPEG4HP41F)
+            | (VideoCodec::H264, uids::MPEG4HP41BD | uids::MPEG4HP41BDF) => {
+                high_profile && self.level <= 41
+            }
+            (VideoCodec::H264, uids::MPEG4HP422D | uids::MPEG4HP422DF)
+            | (VideoCodec::H264, uids::MPEG4HP423D | uids::MPEG4HP423DF) => {
+                high_profile && self.level <= 42
+            }
+            (VideoCodec::H264, uids::MPEG4HP42STEREO | uids::MPEG4HP42STEREOF) => {
+                self.profile == 128 && self.level <= 42
+            }
+            (VideoCodec::Hevc, uids::HEVCMP51) => {
+                self.profile == 1 && self.bit_depth == 8 && self.level <= 153
+            }
+            // Main profile streams are also valid Main 10 profile streams
+            (VideoCodec::Hevc, uids::HEVCM10P51) => {
+                matches!(self.profile, 1 | 2) && self.bit_depth <= 10 && self.level <= 153
+            }
+            _ => false,
+        }
+    }
+
+    /// Choose the most suitable transfer syntax for the video,
+    /// returning `None` if there is none.
+    ///
+    /// Non-fragmentable 2D video transfer syntaxes are preferred.
+    pub fn transfer_syntax(&self) -> Option<&'static str> {
+        [
+            uids::MPEG4HP41,
+            uids::MPEG4HP422D,
+            uids::HEVCMP51,
+            uids::HEVCM10P51,
+        ]
+        .iter()
+        .copied()
+        .find(|ts_uid| self.fits_transfer_syntax(ts_uid))
+    }
+}
+
+/// A builder for a new video DICOM object
+/// from the contents of an MP4 file.
+///
+/// The video track is extracted from the MP4 file
+/// into an elementary stream in the byte stream format of Annex B
+/// of H.264 and HEVC,
+/// with the parameter sets of the decoder configuration
+/// (SPS and PPS, plus VPS for HEVC) at the start.
+/// This stream is kept in the encapsulated pixel data,
+/// while the attributes of the _Image Pixel_, _Cine_ and _Multi-frame_ modules
+/// (such as Rows, Number of Frames, Frame Time and Cine Rate)
+/// are filled in from the properties of the video track.
+/// Unless provided as attributes,
+/// the video is placed in a new study and series,
+/// and the patient and study attributes,
+/// as well as the acquisition context, are left empty.
+///
+/// By default, a _Video Photographic Image_ is created,
+/// in the transfer syntax best suited for the video.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// use dicom_core::{DataElement, VR};
+/// use dicom_dictionary_std::{tags, uids};
+/// use dicom_pixeldata::video::VideoObjectBuilder;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let data = std::fs::read("recording.mp4")?;
+/// let obj = VideoObjectBuilder::new()
+///     .sop_class_uid(uids::VIDEO_ENDOSCOPIC_IMAGE_STORAGE)
+///     .attribute(DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"))
+///     .build_from_mp4(data)?;
+/// obj.write_to_file("recording.dcm")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VideoObjectBuilder {
+    sop_class_uid: String,
+    transfer_syntax: Option<String>,
+    fragment_size: u32,
+    attributes: InMemDicomObject,
+}
+
+impl Default for VideoObjectBuilder {
+    fn default() -> Self {
+        VideoObjectBuilder {
+            sop_class_uid: uids::VIDEO_PHOTOGRAPHIC_IMAGE_STORAGE.to_string(),
+            transfer_syntax: None,
+            fragment_size: 0,
+            attributes: InMemDicomObject::new_empty(),
+        }
+    }
+}
+
+impl VideoObjectBuilder {
+    /// Create a new builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define the SOP class of the new object,
+    /// which must be one of
+    /// _Video Endoscopic Image Storage_,
+    /// _Video Microscopic Image Storage_
+    /// or _Video Photographic Image Storage_.
+    pub fn sop_class_uid(mut self, sop_class_uid: impl Into<String>) -> Self {
+        self.sop_class_uid = sop_class_uid.into();
+        self
+    }
+
+    /// Define the transfer syntax of the new object.
+    ///
+    /// Building fails if the video does not comply with it.
+    pub fn transfer_syntax(mut self, ts_uid: impl Into<String>) -> Self {
+        self.transfer_syntax = Some(ts_uid.into());
+        self
+    }
+
+    /// Split the video stream into fragments of up to the given size,
+    /// which is only possible in fragmentable transfer syntaxes.
+    ///
+    /// The default of 0 keeps the whole stream in a single fragment.
+    pub fn fragment_size(mut self, fragment_size: u32) -> Self {
+        self.fragment_size = fragment_size;
+        self
+    }
+
+    /// Add an attribute to the new object,
+    /// such as the patient's name or the study date.
+    ///
+    /// Attributes describing the video are always replaced
+    /// by those read from the MP4 file.
+    pub fn attribute(mut self, element: DataElement<InMemDicomObject>) -> Self {
+        self.attributes.put(element);
+        self
+    }
+
+    /// Add all attributes of the given data set to the new object.
+    ///
+    /// Attributes describing the video are always replaced
+    /// by those read from the MP4 file.
+    pub fn attributes(mut self, attributes: InMemDicomObject) -> Self {
+        for element in attributes {
+            self.attributes.put(element);
+        }
+        self
+    }
+
+    /// Build the new video object from the contents of an MP4 file.
+    pub fn build_from_mp4(self, data: Vec<u8>) -> Result<DefaultDicomObject> {
+        let info = VideoInfo::from_mp4(&data)?;
+        let stream = mp4::read_video_stream(&data)?;
+
+        let ts_uid = match &self.transfer_syntax {
+            Some(ts_uid) => {
+                ensure!(
+                    info.fits_transfer_syntax(ts_uid),
+                    IncompatibleTransferSyntaxSnafu { ts_uid }
+                );
+                ts_uid.as_str()
+            }
+            None => info.transfer_syntax().context(UnsupportedVideoSnafu {
+                codec: info.codec,
+                profile: info.profile,
+                level: info.level,
+                rows: info.rows,
+                columns: info.columns,
+            })?,
+        };
+
+        let modality = match self.sop_class_uid.as_str() {
+            uids::VIDEO_ENDOSCOPIC_IMAGE_STORAGE => "ES",
+            uids::VIDEO_MICROSCOPIC_IMAGE_STORAGE => "GM",
+            uids::VIDEO_PHOTOGRAPHIC_IMAGE_STORAGE => "XC",
+            _ => {
+                return UnsupportedSopClassSnafu {
+                    sop_class_uid: self.sop_class_uid,
+                }
+                .fail()?
+            }
+        };
+
+        let mut obj = InMemDicomObject::new_empty();
+
+        // type 2 attributes of the IOD, left empty unless provided
+        put_empty_attributes(&mut obj);
+        put_empty_image_attributes(&mut obj);
+        obj.put(DataElement::new(
+            tags::ACQUISITION_CONTEXT_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::empty(),
+        ));
+        obj.put(DataElement::new(tags::MODALITY, VR::CS, modality));
+        obj.put(DataElement::new(
+            tags::IMAGE_TYPE,
+            VR::CS,
+            dicom_value!(Strs, ["ORIGINAL", "PRIMARY"]),
+        ));
+
+        // provided attributes
+        for element in self.attributes {
+            obj.put(element);
+        }
+
+        // generated identifiers
+        put_missing_uids(&mut obj);
+        obj.put(DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            self.sop_class_uid,
+        ));
+
+        // image pixel module
+        let (bits_allocated, bits_stored) = if info.bit_depth > 8 {
+            (16, info.bit_depth as u16)
+        } else {
+            (8, 8)
+        };
+        for (tag, value) in [
+            (tags::SAMPLES_PER_PIXEL, 3),
+            (tags::PLANAR_CONFIGURATION, 0),
+            (tags::ROWS, info.rows),
+            (tags::COLUMNS, info.columns),
+            (tags::BITS_ALLOCATED, bits_allocated),
+            (tags::BITS_STORED, bits_stored),
+            (tags::HIGH_BIT, bits_stored - 1),
+            (tags::PIXEL_REPRESENTATION, 0),
+        ] {
+            obj.put(DataElement::new(tag, VR::US, dicom_value!(U16, [value])));
+        }
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "YBR_PARTIAL_420",
+        ));
+
+        // cine and multi-frame modules
+        let frame_rate = if info.frame_time > 0. {
+            (1000. / info.frame_time).round() as i32
+        } else {
+            0
+        };
+        obj.put(DataElement::new(
+            tags::NUMBER_OF_FRAMES,
+            VR::IS,
+            info.number_of_frames.to_string(),
+        ));
+        obj.put(DataElement::new(
+            tags::FRAME_INCREMENT_POINTER,
+            VR::AT,
+            PrimitiveValue::from(tags::FRAME_TIME),
+        ));
+        obj.put(DataElement::new(
+            tags::FRAME_TIME,
+            VR::DS,
+            format!("{:.4}", info.frame_time)
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string(),
+        ));
+        obj.put(DataElement::new(
+            tags::CINE_RATE,
+            VR::IS,
+            frame_rate.to_string(),
+        ));
+        obj.put(DataElement::new(
+            tags::RECOMMENDED_DISPLAY_FRAME_RATE,
+            VR::IS,
+            frame_rate.to_string(),
+        ));
+
+        // lossy compression
+        obj.put(DataElement::new(
+            tags::LOSSY_IMAGE_COMPRESSION,
+            VR::CS,
+            "01",
+        ));
+        obj.put(DataElement::new(
+            tags::LOSSY_IMAGE_COMPRESSION_METHOD,
+            VR::CS,
+            match info.codec {
+                VideoCodec::Hevc => "ISO_23008_2",
+                _ => "ISO_14496_10",
+            },
+        ));
+
+        // pixel data, with an empty basic offset table
+        let fragmentable = matches!(
+            ts_uid,
+            uids::MPEG4HP41F
+                | uids::MPEG4HP41BDF
+                | uids::MPEG4HP422DF
+                | uids::MPEG4HP423DF
+                | uids::MPEG4HP42STEREOF
+        );
+        let fragment_size = if fragmentable && self.fragment_size > 0 {
+            // fragments must have an even length
+            (self.fragment_size & !1).max(2) as usize
+        } else {
+            ensure!(stream.len() < u32::MAX as usize, StreamTooLargeSnafu);
+            stream.len().max(1)
+        };
+        let fragments: Vec<Vec<u8>> = stream
+            .chunks(fragment_size)
+            .map(|chunk| {
+                let mut fragment = chunk.to_vec();
+                if fragment.len() % 2 != 0 {
+                    fragment.push(0);
+                }
+                fragment
+            })
+            .collect();
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PixelFragmentSequence::new(vec![], fragments),
+        ));
+
+        obj.with_meta(FileMetaTableBuilder::new().transfer_syntax(ts_uid))
+            .context(BuildMetaSnafu)
+            .map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mp4::tests::{avcc, hvcc, mp4_file};
+    use super::*;
+
+    fn video_object(ts: &str, fragments: Vec<Vec<u8>>) -> FileDicomObject<InMemDicomObject> {
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(ts)
+                .media_storage_sop_class_uid(uids::VIDEO_ENDOSCOPIC_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.163734474452616337513735946218584539104")
+                .build()
+                .unwrap(),
+        );
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PixelFragmentSequence::new(vec![], fragments),
+        ));
+        obj
+    }
+
+    #[test]
+    fn identify_video_codecs() {
+        assert_eq!(
+            VideoCodec::from_transfer_syntax(uids::MPEG2MPHL),
+            Some(VideoCodec::Mpeg2)
+        );
+        assert_eq!(
+            VideoCodec::from_transfer_syntax("1.2.840.10008.1.2.4.102.1\0"),
+            Some(VideoCodec::H264)
+        );
+        assert_eq!(
+            VideoCodec::from_transfer_syntax(uids::HEVCM10P51),
+            Some(VideoCodec::Hevc)
+        );
+        assert_eq!(
+            VideoCodec::from_transfer_syntax(uids::JPEG_BASELINE8_BIT),
+            None
+        );
+        assert_eq!(VideoCodec::H264.extension(), "h264");
+    }
+
+    #[test]
+    fn write_fragmented_video_stream() {
+        // H.264 access unit delimiters split across fragments
+        let obj = video_object(
+            uids::MPEG4HP41F,
+            vec![
+                vec![0, 0, 0, 1, 0x09, 0x10],
+                vec![0, 0, 0, 1],
+                vec![0x09, 0x10],
+            ],
+        );
+
+        let mut out = Vec::new();
+        let codec = write_video_stream(&obj, &mut out).unwrap();
+        assert_eq!(codec, VideoCodec::H264);
+        assert_eq!(out, [0, 0, 0, 1, 0x09, 0x10, 0, 0, 0, 1, 0x09, 0x10]);
+    }
+
+    #[test]
+    fn write_video_stream_fails_on_other_transfer_syntaxes() {
+        let obj = video_object(uids::JPEG_BASELINE8_BIT, vec![vec![0xFF, 0xD8]]);
+        let mut out = Vec::new();
+        assert!(write_video_stream(&obj, &mut out).is_err());
+        assert!(out.is_empty());
+    }
+
+    #[cfg(feature = "openh264")]
+    #[test]
+    fn decode_h264_video_frames() {
+        use openh264::encoder::Encoder;
+        use openh264::formats::{RgbSliceU8, YUVBuffer};
+
+        // one red, one green and one blue frame
+        let (rows, columns) = (48, 64);
+        let colors: [[u8; 3]; 3] = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        let mut encoder = Encoder::new().unwrap();
+        let mut stream = Vec::new();
+        for color in colors {
+            let rgb = color.repeat(rows * columns);
+            let yuv = YUVBuffer::from_rgb8_source(RgbSliceU8::new(&rgb, (columns, rows)));
+            encoder.encode(&yuv).unwrap().write_vec(&mut stream);
+        }
+        if stream.len() % 2 != 0 {
+            stream.push(0);
+        }
+        let obj = video_object(uids::MPEG4HP41, vec![stream]);
+
+        let assert_color = |pixels: &[u8], color: [u8; 3]| {
+            // sample the center of the frame, allowing for compression loss
+            let i = (rows / 2 * columns + columns / 2) * 3;
+            for (got, expected) in pixels[i..i + 3].iter().zip(color) {
+                assert!(
+                    got.abs_diff(expected) < 16,
+                    "expected {:?}, got {:?}",
+                    color,
+                    &pixels[i..i + 3]
+                );
+            }
+        };
+
+        let decoded = decode_video_frames(&obj).unwrap();
+        assert_eq!(decoded.number_of_frames(), 3);
+        assert_eq!(decoded.rows(), rows as u32);
+        assert_eq!(decoded.columns(), columns as u32);
+        assert_eq!(decoded.samples_per_pixel(), 3);
+        for (frame, color) in colors.iter().enumerate() {
+            assert_color(decoded.frame_data(frame as u32).unwrap(), *color);
+        }
+
+        let decoded = decode_video_frame(&obj, 1).unwrap();
+        assert_eq!(decoded.number_of_frames(), 1);
+        assert_color(decoded.data(), colors[1]);
+
+        assert!(decode_video_frame(&obj, 3).is_err());
+
+        // HEVC is not supported
+        let obj = video_object(uids::HEVCMP51, vec![vec![0, 0, 0, 1]]);
+        assert!(decode_video_frames(&obj).is_err());
+    }
+
+    #[test]
+    fn choose_video_transfer_syntax() {
+        let info =
+            VideoInfo::from_mp4(&mp4_file(b"avc1", avcc(100, 40), 1080, 1920, 1, 1, 25)).unwrap();
+        assert_eq!(info.transfer_syntax(), Some(uids::MPEG4HP41));
+        assert!(info.fits_transfer_syntax(uids::MPEG4HP41F));
+        assert!(info.fits_transfer_syntax(uids::MPEG4HP422D));
+        assert!(!info.fits_transfer_syntax(uids::HEVCMP51));
+
+        let info =
+            VideoInfo::from_mp4(&mp4_file(b"avc1", avcc(77, 42), 720, 1280, 1, 1, 25)).unwrap();
+        assert_eq!(info.transfer_syntax(), Some(uids::MPEG4HP422D));
+
+        // too large for H.264 transfer syntaxes
+        let info =
+            VideoInfo::from_mp4(&mp4_file(b"avc1", avcc(100, 51), 2160, 3840, 1, 1, 25)).unwrap();
+        assert_eq!(info.transfer_syntax(), None);
+
+        let info =
+            VideoInfo::from_mp4(&mp4_file(b"hvc1", hvcc(1, 153, 8), 2160, 3840, 1, 1, 25)).unwrap();
+        assert_eq!(info.transfer_syntax(), Some(uids::HEVCMP51));
+        assert!(info.fits_transfer_syntax(uids::HEVCM10P51));
+
+        let info = VideoInfo::from_mp4(&mp4_file(b"hvc1", hvcc(2, 150, 10), 2160, 3840, 1, 1, 25))
+            .unwrap();
+        assert_eq!(info.transfer_syntax(), Some(uids::HEVCM10P51));
+    }
+
+    #[test]
+    fn build_video_object_from_mp4() {
+        let data = mp4_file(b"avc1", avcc(100, 41), 576, 720, 100, 1000, 25000);
+        let obj = VideoObjectBuilder::new()
+            .sop_class_uid(uids::VIDEO_ENDOSCOPIC_IMAGE_STORAGE)
+            .attribute(DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"))
+            .build_from_mp4(data.clone())
+            .unwrap();
+
+        assert_eq!(obj.meta().transfer_syntax(), uids::MPEG4HP41);
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::VIDEO_ENDOSCOPIC_IMAGE_STORAGE
+        );
+        let str_of = |tag| obj.element(tag).unwrap().to_str().unwrap().into_owned();
+        assert_eq!(str_of(tags::PATIENT_NAME), "Doe^John");
+        assert_eq!(str_of(tags::PATIENT_ID), "");
+        assert_eq!(str_of(tags::MODALITY), "ES");
+        assert_eq!(str_of(tags::PHOTOMETRIC_INTERPRETATION), "YBR_PARTIAL_420");
+        assert_eq!(str_of(tags::NUMBER_OF_FRAMES), "100");
+        assert_eq!(str_of(tags::FRAME_TIME), "40");
+        assert_eq!(str_of(tags::CINE_RATE), "25");
+        assert!(str_of(tags::STUDY_INSTANCE_UID).starts_with("2.25."));
+        assert_eq!(
+            obj.element(tags::ROWS).unwrap().to_int::<u16>().unwrap(),
+            576
+        );
+        assert_eq!(
+            obj.element(tags::COLUMNS).unwrap().to_int::<u16>().unwrap(),
+            720
+        );
+
+        // the elementary stream is kept in a single fragment
+        let Value::PixelSequence(seq) = obj.element(tags::PIXEL_DATA).unwrap().value() else {
+            panic!("pixel data should be encapsulated");
+        };
+        assert!(seq.offset_table().is_empty());
+        assert_eq!(seq.fragments().len(), 1);
+        let mut out = Vec::new();
+        assert_eq!(
+            write_video_stream(&obj, &mut out).unwrap(),
+            VideoCodec::H264
+        );
+        let stream = mp4::read_video_stream(&data).unwrap();
+        assert_eq!(&out[..stream.len()], &stream[..]);
+        // starts with the sequence parameter set
+        assert_eq!(&out[..5], &[0, 0, 0, 1, 0x67]);
+        assert_eq!(video_file_extension(VideoCodec::H264, &out), "h264");
+    }
+
+    #[test]
+    fn build_fragmented_video_object() {
+        let data = mp4_file(b"avc1", avcc(100, 41), 576, 720, 100, 1000, 25000);
+        let obj = VideoObjectBuilder::new()
+            .transfer_syntax(uids::MPEG4HP41F)
+            .fragment_size(255)
+            .build_from_mp4(data.clone())
+            .unwrap();
+
+        assert_eq!(obj.meta().transfer_syntax(), uids::MPEG4HP41F);
+        let Value::PixelSequence(seq) = obj.element(tags::PIXEL_DATA).unwrap().value() else {
+            panic!("pixel data should be encapsulated");
+        };
+        let stream = mp4::read_video_stream(&data).unwrap();
+        assert_eq!(seq.fragments().len(), (stream.len() + 253) / 254);
+        assert!(seq
+            .fragments()
+            .iter()
+            .all(|f| f.len() <= 254 && f.len() % 2 == 0));
+
+        // incompatible transfer syntax
+        assert!(VideoObjectBuilder::new()
+            .transfer_syntax(uids::HEVCMP51)
+            .build_from_mp4(data)
+            .is_err());
+    }
+}
@@ -0,0 +1,569 @@
+//! A minimal reader of MP4 (ISO base media file format) files,
+//! covering only what is needed to describe the first video track
+//! and to extract its elementary stream.
+use super::{
+    InvalidMp4Snafu, MissingMp4BoxSnafu, NoVideoTrackSnafu, Result, UnsupportedSampleEntrySnafu,
+    VideoCodec, VideoInfo,
+};
+use byteorder::{BigEndian, ByteOrder};
+use snafu::{ensure, OptionExt};
+use std::convert::TryFrom;
+
+/// An MP4 box, with its type and contents (excluding the header).
+#[derive(Debug, Copy, Clone)]
+struct Mp4Box<'a> {
+    kind: [u8; 4],
+    data: &'a [u8],
+}
+
+/// Iterate over the consecutive boxes in the given data.
+fn boxes(mut data: &[u8]) -> impl Iterator<Item = Result<Mp4Box<'_>>> {
+    std::iter::from_fn(move || {
+        if data.is_empty() {
+            return None;
+        }
+        let out = next_box(data).map(|(b, rest)| {
+            data = rest;
+            b
+        });
+        if out.is_err() {
+            data = &[];
+        }
+        Some(out)
+    })
+}
+
+fn next_box(data: &[u8]) -> Result<(Mp4Box<'_>, &[u8])> {
+    ensure!(data.len() >= 8, InvalidMp4Snafu);
+    let size = BigEndian::read_u32(&data[0..4]) as u64;
+    let kind = [data[4], data[5], data[6], data[7]];
+    let (header_len, size) = match size {
+        // box extends to the end of the file
+        0 => (8, data.len() as u64),
+        // 64-bit box size follows the box type
+        1 => {
+            ensure!(data.len() >= 16, InvalidMp4Snafu);
+            (16, BigEndian::read_u64(&data[8..16]))
+        }
+        size => (8, size),
+    };
+    ensure!(
+        size >= header_len && size <= data.len() as u64,
+        InvalidMp4Snafu
+    );
+    let (b, rest) = data.split_at(size as usize);
+    Ok((
+        Mp4Box {
+            kind,
+            data: &b[header_len as usize..],
+        },
+        rest,
+    ))
+}
+
+/// Find the first box of the given type among the consecutive boxes.
+fn find_box<'a>(data: &'a [u8], kind: &'static [u8; 4]) -> Result<Mp4Box<'a>> {
+    for b in boxes(data) {
+        let b = b?;
+        if &b.kind == kind {
+            return Ok(b);
+        }
+    }
+    MissingMp4BoxSnafu {
+        kind: String::from_utf8_lossy(kind).into_owned(),
+    }
+    .fail()?
+}
+
+/// Read a big endian integer of `len` bytes at the given position.
+fn read_uint(data: &[u8], pos: usize, len: usize) -> Result<u64> {
+    let bytes = data.get(pos..pos + len).context(InvalidMp4Snafu)?;
+    Ok(BigEndian::read_uint(bytes, len))
+}
+
+/// Find the media box (`mdia`) of the first video track in an MP4 file.
+fn find_video_track(data: &[u8]) -> Result<Mp4Box<'_>> {
+    find_box(data, b"ftyp")?;
+    let moov = find_box(data, b"moov")?;
+
+    for trak in boxes(moov.data) {
+        let trak = trak?;
+        if &trak.kind != b"trak" {
+            continue;
+        }
+        let mdia = find_box(trak.data, b"mdia")?;
+        let hdlr = find_box(mdia.data, b"hdlr")?;
+        // version & flags, pre_defined, then the handler type
+        if hdlr.data.get(8..12) == Some(b"vide") {
+            return Ok(mdia);
+        }
+    }
+
+    NoVideoTrackSnafu.fail()?
+}
+
+/// Find the sample table box (`stbl`) of a track's media box.
+fn find_sample_table(mdia: Mp4Box<'_>) -> Result<Mp4Box<'_>> {
+    let minf = find_box(mdia.data, b"minf")?;
+    find_box(minf.data, b"stbl")
+}
+
+/// Find the first sample entry of a sample table,
+/// returning it along with its child boxes.
+fn find_sample_entry(stbl: Mp4Box<'_>) -> Result<(Mp4Box<'_>, &[u8])> {
+    let stsd = find_box(stbl.data, b"stsd")?;
+    let entry = boxes(stsd.data.get(8..).context(InvalidMp4Snafu)?)
+        .next()
+        .context(InvalidMp4Snafu)??;
+    // visual sample entry fields precede the child boxes
+    let children = entry.data.get(78..).context(InvalidMp4Snafu)?;
+    Ok((entry, children))
+}
+
+/// Read the parameter set NAL units in an H.264 decoder configuration
+/// (the contents of an `avcC` box),
+/// returning them along with the data which follows them.
+fn avcc_parameter_sets(avcc: &[u8]) -> Result<(Vec<&[u8]>, &[u8])> {
+    let mut parameter_sets = Vec::new();
+    let mut data = avcc.get(5..).context(InvalidMp4Snafu)?;
+    // sequence parameter sets, then picture parameter sets
+    for count_mask in [0x1F, 0xFF] {
+        let (count, rest) = data.split_first().context(InvalidMp4Snafu)?;
+        data = rest;
+        for _ in 0..(count & count_mask) {
+            let (nal, rest) = read_nal_unit(data, 2)?;
+            parameter_sets.push(nal);
+            data = rest;
+        }
+    }
+    Ok((parameter_sets, data))
+}
+
+/// Read the parameter set NAL units in an HEVC decoder configuration
+/// (the contents of an `hvcC` box).
+fn hvcc_parameter_sets(hvcc: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut parameter_sets = Vec::new();
+    let array_count = read_uint(hvcc, 22, 1)?;
+    let mut data = hvcc.get(23..).context(InvalidMp4Snafu)?;
+    for _ in 0..array_count {
+        // NAL unit type, then the number of NAL units
+        let count = read_uint(data, 1, 2)?;
+        data = &data[3..];
+        for _ in 0..count {
+            let (nal, rest) = read_nal_unit(data, 2)?;
+            parameter_sets.push(nal);
+            data = rest;
+        }
+    }
+    Ok(parameter_sets)
+}
+
+/// Read a NAL unit preceded by its length in `length_size` bytes,
+/// returning it along with the data which follows it.
+fn read_nal_unit(data: &[u8], length_size: usize) -> Result<(&[u8], &[u8])> {
+    let len = read_uint(data, 0, length_size)? as usize;
+    let end = length_size + len;
+    ensure!(data.len() >= end, InvalidMp4Snafu);
+    Ok((&data[length_size..end], &data[end..]))
+}
+
+/// Read the properties of the first video track in an MP4 file.
+pub(crate) fn read_video_info(data: &[u8]) -> Result<VideoInfo> {
+    read_track_info(find_video_track(data)?)
+}
+
+/// Read the elementary stream of the first video track in an MP4 file,
+/// in the byte stream format of Annex B of H.264 and HEVC.
+///
+/// The parameter sets of the decoder configuration come first,
+/// followed by the NAL units of each sample in decoding order,
+/// each one preceded by a start code
+/// instead of the length prefix used in MP4 files.
+pub(crate) fn read_video_stream(data: &[u8]) -> Result<Vec<u8>> {
+    const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+    let stbl = find_sample_table(find_video_track(data)?)?;
+    let (entry, children) = find_sample_entry(stbl)?;
+    let (parameter_sets, length_size) = match &entry.kind {
+        b"avc1" | b"avc3" => {
+            let avcc = find_box(children, b"avcC")?;
+            let length_size = (read_uint(avcc.data, 4, 1)? & 0x03) + 1;
+            (avcc_parameter_sets(avcc.data)?.0, length_size)
+        }
+        b"hvc1" | b"hev1" => {
+            let hvcc = find_box(children, b"hvcC")?;
+            let length_size = (read_uint(hvcc.data, 21, 1)? & 0x03) + 1;
+            (hvcc_parameter_sets(hvcc.data)?, length_size)
+        }
+        kind => {
+            return UnsupportedSampleEntrySnafu {
+                kind: String::from_utf8_lossy(kind).into_owned(),
+            }
+            .fail()?
+        }
+    };
+
+    let mut stream = Vec::new();
+    for nal in parameter_sets {
+        stream.extend(START_CODE);
+        stream.extend(nal);
+    }
+
+    for sample in samples(data, stbl)? {
+        let mut sample = sample?;
+        while !sample.is_empty() {
+            let (nal, rest) = read_nal_unit(sample, length_size as usize)?;
+            stream.extend(START_CODE);
+            stream.extend(nal);
+            sample = rest;
+        }
+    }
+
+    Ok(stream)
+}
+
+/// Iterate over the data of each sample of a track,
+/// as located by its sample table.
+fn samples<'a>(
+    data: &'a [u8],
+    stbl: Mp4Box<'_>,
+) -> Result<impl Iterator<Item = Result<&'a [u8]>> + 'a> {
+    // sample sizes, either all the same or one per sample
+    let stsz = find_box(stbl.data, b"stsz")?;
+    let sample_size = read_uint(stsz.data, 4, 4)?;
+    let sample_count = read_uint(stsz.data, 8, 4)? as usize;
+    let sample_sizes = (0..sample_count)
+        .map(|i| match sample_size {
+            0 => read_uint(stsz.data, 12 + i * 4, 4),
+            size => Ok(size),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // chunk offsets, in 32 or 64 bits
+    let chunk_offsets = match find_box(stbl.data, b"stco") {
+        Ok(stco) => {
+            let count = read_uint(stco.data, 4, 4)? as usize;
+            (0..count)
+                .map(|i| read_uint(stco.data, 8 + i * 4, 4))
+                .collect::<Result<Vec<_>>>()?
+        }
+        Err(_) => {
+            let co64 = find_box(stbl.data, b"co64")?;
+            let count = read_uint(co64.data, 4, 4)? as usize;
+            (0..count)
+                .map(|i| read_uint(co64.data, 8 + i * 8, 8))
+                .collect::<Result<Vec<_>>>()?
+        }
+    };
+
+    // runs of chunks with the same number of samples,
+    // as (first chunk index, samples per chunk)
+    let stsc = find_box(stbl.data, b"stsc")?;
+    let entry_count = read_uint(stsc.data, 4, 4)? as usize;
+    let runs = (0..entry_count)
+        .map(|i| {
+            let first_chunk = read_uint(stsc.data, 8 + i * 12, 4)?;
+            let samples_per_chunk = read_uint(stsc.data, 12 + i * 12, 4)?;
+            ensure!(first_chunk > 0, InvalidMp4Snafu);
+            Ok((first_chunk as usize - 1, samples_per_chunk as usize))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // the file position of each sample
+    let mut positions = Vec::with_capacity(sample_count);
+    let mut sizes = sample_sizes.iter();
+    for (i, (first_chunk, samples_per_chunk)) in runs.iter().enumerate() {
+        let last_chunk = runs
+            .get(i + 1)
+            .map(|(next_chunk, _)| *next_chunk)
+            .unwrap_or(chunk_offsets.len());
+        for offset in chunk_offsets.get(*first_chunk..last_chunk).unwrap_or(&[]) {
+            let mut position = *offset;
+            for size in sizes.by_ref().take(*samples_per_chunk) {
+                positions.push((position, *size));
+                position += size;
+            }
+        }
+    }
+    ensure!(positions.len() == sample_count, InvalidMp4Snafu);
+
+    Ok(positions.into_iter().map(move |(position, size)| {
+        let start = usize::try_from(position).ok();
+        let end = position
+            .checked_add(size)
+            .and_then(|end| usize::try_from(end).ok());
+        start
+            .zip(end)
+            .and_then(|(start, end)| data.get(start..end))
+            .context(InvalidMp4Snafu)
+            .map_err(Into::into)
+    }))
+}
+
+fn read_track_info(mdia: Mp4Box) -> Result<VideoInfo> {
+    // media timescale, in time units per second
+    let mdhd = find_box(mdia.data, b"mdhd")?;
+    let timescale = match mdhd.data.first() {
+        Some(1) => read_uint(mdhd.data, 20, 4)?,
+        _ => read_uint(mdhd.data, 12, 4)?,
+    };
+    ensure!(timescale > 0, InvalidMp4Snafu);
+
+    let stbl = find_sample_table(mdia)?;
+
+    // number of samples, one per frame
+    let stsz = find_box(stbl.data, b"stsz")?;
+    let number_of_frames = read_uint(stsz.data, 8, 4)? as u32;
+
+    // total duration of all samples
+    let stts = find_box(stbl.data, b"stts")?;
+    let entry_count = read_uint(stts.data, 4, 4)? as usize;
+    let mut duration = 0;
+    for i in 0..entry_count {
+        let sample_count = read_uint(stts.data, 8 + i * 8, 4)?;
+        let sample_delta = read_uint(stts.data, 12 + i * 8, 4)?;
+        duration += sample_count * sample_delta;
+    }
+    let frame_time = if number_of_frames > 0 {
+        duration as f64 * 1000. / timescale as f64 / number_of_frames as f64
+    } else {
+        0.
+    };
+
+    // the first sample entry describes the coded video
+    let (entry, children) = find_sample_entry(stbl)?;
+    let columns = read_uint(entry.data, 24, 2)? as u16;
+    let rows = read_uint(entry.data, 26, 2)? as u16;
+
+    let (codec, profile, level, bit_depth) = match &entry.kind {
+        b"avc1" | b"avc3" => {
+            let avcc = find_box(children, b"avcC")?;
+            let profile = read_uint(avcc.data, 1, 1)? as u8;
+            let level = read_uint(avcc.data, 3, 1)? as u8;
+            // bit depth is only recorded for the high profiles
+            let bit_depth = match profile {
+                100 | 110 | 122 | 144 => avcc_parameter_sets(avcc.data)
+                    .ok()
+                    .and_then(|(_, extension)| Some((extension.get(1)? & 0x07) + 8))
+                    .unwrap_or(8),
+                _ => 8,
+            };
+            (VideoCodec::H264, profile, level, bit_depth)
+        }
+        b"hvc1" | b"hev1" => {
+            let hvcc = find_box(children, b"hvcC")?;
+            let profile = read_uint(hvcc.data, 1, 1)? as u8 & 0x1F;
+            let level = read_uint(hvcc.data, 12, 1)? as u8;
+            let bit_depth = (read_uint(hvcc.data, 17, 1)? as u8 & 0x07) + 8;
+            (VideoCodec::Hevc, profile, level, bit_depth)
+        }
+        kind => {
+            return UnsupportedSampleEntrySnafu {
+                kind: String::from_utf8_lossy(kind).into_owned(),
+            }
+            .fail()?
+        }
+    };
+
+    Ok(VideoInfo {
+        codec,
+        profile,
+        level,
+        rows,
+        columns,
+        bit_depth,
+        number_of_frames,
+        frame_time,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Write an MP4 box with the given type and contents.
+    pub(crate) fn mp4_box(kind: &[u8; 4], contents: &[&[u8]]) -> Vec<u8> {
+        let len: usize = contents.iter().map(|c| c.len()).sum();
+        let mut out = Vec::with_capacity(8 + len);
+        out.extend(((8 + len) as u32).to_be_bytes());
+        out.extend(kind);
+        for c in contents {
+            out.extend(*c);
+        }
+        out
+    }
+
+    /// Build the data of a sample in an MP4 file:
+    /// an access unit delimiter and a coded slice,
+    /// each preceded by its length.
+    pub(crate) fn mp4_sample(frame: u32) -> Vec<u8> {
+        vec![0, 0, 0, 2, 0x09, 0xF0, 0, 0, 0, 2, 0x65, frame as u8]
+    }
+
+    /// Build a minimal MP4 file with a single video track
+    /// using the given sample entry type and decoder configuration box.
+    ///
+    /// Each sample is built by [`mp4_sample`],
+    /// and there are 2 samples per chunk.
+    pub(crate) fn mp4_file(
+        entry_kind: &[u8; 4],
+        config: Vec<u8>,
+        rows: u16,
+        columns: u16,
+        number_of_frames: u32,
+        frame_duration: u32,
+        timescale: u32,
+    ) -> Vec<u8> {
+        let ftyp = mp4_box(b"ftyp", &[b"isom", &[0, 0, 2, 0], b"isomavc1"]);
+
+        let mut mdhd = vec![0; 12];
+        mdhd.extend(timescale.to_be_bytes());
+        mdhd.extend((number_of_frames * frame_duration).to_be_bytes());
+        mdhd.extend([0; 4]);
+        let mdhd = mp4_box(b"mdhd", &[&mdhd]);
+        let hdlr = mp4_box(b"hdlr", &[&[0; 8], b"vide", &[0; 13]]);
+
+        let mut visual = vec![0; 24];
+        visual[7] = 1;
+        visual.extend(columns.to_be_bytes());
+        visual.extend(rows.to_be_bytes());
+        visual.extend([0, 0x48, 0, 0, 0, 0x48, 0, 0, 0, 0, 0, 0, 0, 1]);
+        visual.extend([0; 32]);
+        visual.extend([0, 0x18, 0xFF, 0xFF]);
+        let entry = mp4_box(entry_kind, &[&visual, &config]);
+        let stsd = mp4_box(b"stsd", &[&[0, 0, 0, 0, 0, 0, 0, 1], &entry]);
+
+        let mut stts = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stts.extend(number_of_frames.to_be_bytes());
+        stts.extend(frame_duration.to_be_bytes());
+        let stts = mp4_box(b"stts", &[&stts]);
+
+        let samples: Vec<u8> = (0..number_of_frames).flat_map(mp4_sample).collect();
+        let sample_size = mp4_sample(0).len() as u32;
+        let mut stsz = vec![0; 4];
+        stsz.extend(sample_size.to_be_bytes());
+        stsz.extend(number_of_frames.to_be_bytes());
+        let stsz = mp4_box(b"stsz", &[&stsz]);
+
+        // one run of chunks with 2 samples each
+        let mut stsc = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsc.extend(1_u32.to_be_bytes());
+        stsc.extend(2_u32.to_be_bytes());
+        stsc.extend(1_u32.to_be_bytes());
+        let stsc = mp4_box(b"stsc", &[&stsc]);
+
+        // the chunks are consecutive in the media data box,
+        // which follows the movie box
+        let moov = |mdat_start: u32| {
+            let chunk_count = (number_of_frames + 1) / 2;
+            let mut stco = vec![0; 4];
+            stco.extend(chunk_count.to_be_bytes());
+            for chunk in 0..chunk_count {
+                stco.extend((mdat_start + chunk * 2 * sample_size).to_be_bytes());
+            }
+            let stco = mp4_box(b"stco", &[&stco]);
+
+            let stbl = mp4_box(b"stbl", &[&stsd, &stts, &stsz, &stsc, &stco]);
+            let minf = mp4_box(b"minf", &[&stbl]);
+            let mdia = mp4_box(b"mdia", &[&mdhd, &hdlr, &minf]);
+            let trak = mp4_box(b"trak", &[&mdia]);
+            mp4_box(b"moov", &[&trak])
+        };
+        let moov_len = moov(0).len();
+        let moov = moov((ftyp.len() + moov_len + 8) as u32);
+        let mdat = mp4_box(b"mdat", &[&samples]);
+
+        [ftyp, moov, mdat].concat()
+    }
+
+    /// Build an H.264 decoder configuration box (`avcC`),
+    /// with one sequence parameter set and one picture parameter set.
+    pub(crate) fn avcc(profile: u8, level: u8) -> Vec<u8> {
+        mp4_box(
+            b"avcC",
+            &[&[
+                1, profile, 0, level, 0xFF, 0xE1, 0, 4, 0x67, profile, 0, level, 1, 0, 2, 0x68,
+                0xCE,
+            ]],
+        )
+    }
+
+    /// Build an HEVC decoder configuration box (`hvcC`),
+    /// with one video parameter set, one sequence parameter set
+    /// and one picture parameter set.
+    pub(crate) fn hvcc(profile: u8, level: u8, bit_depth: u8) -> Vec<u8> {
+        let mut config = vec![0; 23];
+        config[0] = 1;
+        config[1] = profile;
+        config[12] = level;
+        // 4:2:0 chroma format
+        config[16] = 0xFD;
+        config[17] = 0xF8 | (bit_depth - 8);
+        config[18] = 0xF8 | (bit_depth - 8);
+        // 4-byte NAL unit lengths
+        config[21] = 0x0F;
+        // arrays of VPS, SPS and PPS NAL units
+        config[22] = 3;
+        for nal_type in [32_u8, 33, 34] {
+            config.extend([nal_type, 0, 1, 0, 2, nal_type << 1, 1]);
+        }
+        mp4_box(b"hvcC", &[&config])
+    }
+
+    #[test]
+    fn read_h264_video_info() {
+        let data = mp4_file(b"avc1", avcc(100, 41), 1080, 1920, 250, 1001, 30000);
+        let info = read_video_info(&data).unwrap();
+        assert_eq!(info.codec, VideoCodec::H264);
+        assert_eq!((info.profile, info.level), (100, 41));
+        assert_eq!((info.rows, info.columns), (1080, 1920));
+        assert_eq!(info.number_of_frames, 250);
+        assert!((info.frame_time - 33.3667).abs() < 1e-3);
+    }
+
+    #[test]
+    fn read_hevc_video_info() {
+        let data = mp4_file(b"hvc1", hvcc(2, 153, 10), 720, 1280, 10, 1, 25);
+        let info = read_video_info(&data).unwrap();
+        assert_eq!(info.codec, VideoCodec::Hevc);
+        assert_eq!((info.profile, info.level), (2, 153));
+        assert_eq!(info.bit_depth, 10);
+        assert_eq!(info.frame_time, 40.);
+    }
+
+    #[test]
+    fn read_h264_video_stream() {
+        let data = mp4_file(b"avc1", avcc(100, 41), 480, 640, 3, 1, 25);
+        let stream = read_video_stream(&data).unwrap();
+
+        let mut expected = vec![0, 0, 0, 1, 0x67, 100, 0, 41, 0, 0, 0, 1, 0x68, 0xCE];
+        for frame in 0..3 {
+            expected.extend([0, 0, 0, 1, 0x09, 0xF0, 0, 0, 0, 1, 0x65, frame]);
+        }
+        assert_eq!(stream, expected);
+    }
+
+    #[test]
+    fn read_hevc_video_stream() {
+        let data = mp4_file(b"hev1", hvcc(1, 120, 8), 480, 640, 2, 1, 25);
+        let stream = read_video_stream(&data).unwrap();
+
+        let mut expected = vec![
+            0, 0, 0, 1, 0x40, 1, 0, 0, 0, 1, 0x42, 1, 0, 0, 0, 1, 0x44, 1,
+        ];
+        for frame in 0..2 {
+            expected.extend([0, 0, 0, 1, 0x09, 0xF0, 0, 0, 0, 1, 0x65, frame]);
+        }
+        assert_eq!(stream, expected);
+    }
+
+    #[test]
+    fn reject_invalid_mp4() {
+        assert!(read_video_info(b"not an MP4 file").is_err());
+        // truncated file
+        let data = mp4_file(b"avc1", avcc(100, 41), 480, 640, 1, 1, 25);
+        assert!(read_video_info(&data[..data.len() / 2]).is_err());
+        // samples out of the file
+        assert!(read_video_stream(&data[..data.len() - 4]).is_err());
+    }
+}
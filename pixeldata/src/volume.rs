@@ -0,0 +1,669 @@
+//! Assembly of image series into 3D volumes.
+//!
+//! [`VolumeBuilder`] takes the images of a series,
+//! such as the slices of a CT or MR acquisition
+//! or the frames of an enhanced multi-frame object,
+//! sorts them along the normal of their image plane,
+//! and stacks their pixel data into a single [`Volume`]
+//! with its voxel spacing and voxel to patient affine transform.
+//!
+//! Volumes can be saved in the [NIfTI-1][1] format
+//! with [`Volume::write_nifti`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_pixeldata::volume::Volume;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let files = std::fs::read_dir("series")?
+//!     .map(|entry| entry.map(|e| e.path()))
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! let volume = Volume::from_files(&files)?;
+//! let [slices, rows, columns] = volume.shape();
+//! println!("{}x{}x{} voxels of {:?} mm", columns, rows, slices, volume.voxel_spacing);
+//! volume.save_nifti("series.nii")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [1]: https://nifti.nimh.nih.gov/nifti-1
+
+use crate::geometry::{self, Affine, ImagePlane};
+use crate::{ConvertOptions, ModalityLutOption, PixelDecoder};
+use byteorder::{LittleEndian, WriteBytesExt};
+use dicom_object::DefaultDicomObject;
+use ndarray::{s, Array3, Axis};
+use snafu::{ensure, ResultExt, Snafu};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// An error occurred while assembling or saving a volume.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// No images were given
+    NoImages,
+
+    /// Could not open file {}
+    #[snafu(display("Could not open file {}", path.display()))]
+    OpenFile {
+        path: PathBuf,
+        #[snafu(source(from(dicom_object::ReadError, Box::from)))]
+        source: Box<dicom_object::ReadError>,
+    },
+
+    /// Could not decode the pixel data of image #{index}
+    DecodePixelData { index: usize, source: crate::Error },
+
+    /// Could not read the image plane of image #{index}
+    ReadImagePlane {
+        index: usize,
+        source: geometry::Error,
+    },
+
+    /// Image #{index} has {samples_per_pixel} samples per pixel,
+    /// only single sample images are supported
+    UnsupportedSamplesPerPixel {
+        index: usize,
+        samples_per_pixel: u16,
+    },
+
+    /// Image #{index} has frames of {rows}x{columns},
+    /// expected {expected_rows}x{expected_columns}
+    InconsistentDimensions {
+        index: usize,
+        rows: u32,
+        columns: u32,
+        expected_rows: u32,
+        expected_columns: u32,
+    },
+
+    /// Image #{index} frame #{frame} has a different orientation
+    InconsistentOrientation { index: usize, frame: u32 },
+
+    /// Image #{index} frame #{frame} has a different pixel spacing
+    InconsistentPixelSpacing { index: usize, frame: u32 },
+
+    /// Could not determine the spacing between slices
+    SliceSpacing { source: geometry::Error },
+
+    /// More than one slice at position {position} mm
+    DuplicateSlice { position: f64 },
+
+    /// Slices are not evenly spaced, with gaps after slices {gaps:?}
+    IrregularSpacing { gaps: Vec<usize> },
+
+    /// Could not write the NIfTI file
+    WriteNifti { source: std::io::Error },
+}
+
+/// Alias for the result of assembling or saving a volume.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The maximum difference between the direction cosines
+/// of two image planes with the same orientation.
+const ORIENTATION_TOLERANCE: f64 = 1e-4;
+
+/// A 3D volume assembled from the frames of one or more images.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+    /// the voxel values, as `[slice, row, column]`,
+    /// with slices sorted along the normal of the image planes
+    pub data: Array3<f32>,
+    /// the distance between the centers of adjacent voxels,
+    /// as `[column spacing, row spacing, slice spacing]` in millimeters
+    /// (the reverse order of the data axes,
+    /// matching the voxel indices of [`affine`](Self::affine))
+    pub voxel_spacing: [f64; 3],
+    /// the affine transform from voxel indices `(column, row, slice, 1)`
+    /// to coordinates `(x, y, z, 1)`
+    /// in the DICOM patient coordinate system (LPS)
+    pub affine: Affine,
+    /// the image index and frame number (starting at 0)
+    /// from which each slice was taken
+    pub slices: Vec<(usize, u32)>,
+}
+
+/// A builder for a volume from the images of a series.
+///
+/// Each frame of each image becomes a slice of the volume.
+/// All frames must have the same dimensions, orientation and pixel spacing,
+/// and must be evenly spaced along the normal of their plane.
+/// Since the slice axis of the affine transform follows the plane normal,
+/// series acquired with a gantry tilt are not represented accurately.
+#[derive(Debug, Clone)]
+pub struct VolumeBuilder {
+    tolerance: f64,
+    modality_lut: bool,
+    allow_irregular_spacing: bool,
+}
+
+impl Default for VolumeBuilder {
+    fn default() -> Self {
+        VolumeBuilder {
+            tolerance: 0.01,
+            modality_lut: true,
+            allow_irregular_spacing: false,
+        }
+    }
+}
+
+impl VolumeBuilder {
+    /// Create a new volume builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum difference in millimeters
+    /// between positions and pixel spacings considered to be the same
+    /// (0.01 by default).
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Set whether to apply the modality LUT function,
+    /// such as the rescale slope and intercept of CT images
+    /// (enabled by default).
+    pub fn modality_lut(mut self, modality_lut: bool) -> Self {
+        self.modality_lut = modality_lut;
+        self
+    }
+
+    /// Set whether to accept slices which are not evenly spaced
+    /// (disabled by default).
+    ///
+    /// If enabled, the slices are stacked contiguously
+    /// and the volume uses the most common spacing between them,
+    /// so the affine transform is not accurate beyond the first gap.
+    pub fn allow_irregular_spacing(mut self, allow_irregular_spacing: bool) -> Self {
+        self.allow_irregular_spacing = allow_irregular_spacing;
+        self
+    }
+
+    /// Open the given DICOM files and assemble them into a volume.
+    pub fn build_from_files<P>(&self, paths: impl IntoIterator<Item = P>) -> Result<Volume>
+    where
+        P: AsRef<Path>,
+    {
+        let objects = paths
+            .into_iter()
+            .map(|path| {
+                dicom_object::open_file(path.as_ref()).context(OpenFileSnafu {
+                    path: path.as_ref(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.build(&objects)
+    }
+
+    /// Assemble the given DICOM objects into a volume.
+    pub fn build(&self, objects: &[DefaultDicomObject]) -> Result<Volume> {
+        ensure!(!objects.is_empty(), NoImagesSnafu);
+
+        let options = if self.modality_lut {
+            ConvertOptions::new()
+        } else {
+            ConvertOptions::new().with_modality_lut(ModalityLutOption::None)
+        };
+
+        // decode all frames and read their image planes
+        let mut frames = Vec::with_capacity(objects.len());
+        let mut planes = Vec::new();
+        let mut slices = Vec::new();
+        let mut dimensions = None;
+        for (index, obj) in objects.iter().enumerate() {
+            let pixel_data = obj
+                .decode_pixel_data()
+                .context(DecodePixelDataSnafu { index })?;
+            let samples_per_pixel = pixel_data.samples_per_pixel();
+            ensure!(
+                samples_per_pixel == 1,
+                UnsupportedSamplesPerPixelSnafu {
+                    index,
+                    samples_per_pixel,
+                }
+            );
+            let (rows, columns) = (pixel_data.rows(), pixel_data.columns());
+            let (expected_rows, expected_columns) = *dimensions.get_or_insert((rows, columns));
+            ensure!(
+                (rows, columns) == (expected_rows, expected_columns),
+                InconsistentDimensionsSnafu {
+                    index,
+                    rows,
+                    columns,
+                    expected_rows,
+                    expected_columns,
+                }
+            );
+
+            let image_planes =
+                geometry::image_planes(obj).context(ReadImagePlaneSnafu { index })?;
+            for (frame, plane) in (0..).zip(image_planes) {
+                if let Some(first) = planes.first() {
+                    self.check_plane(first, &plane, index, frame)?;
+                }
+                planes.push(plane);
+                slices.push((index, frame));
+            }
+
+            let data = pixel_data
+                .to_ndarray_with_options::<f32>(&options)
+                .context(DecodePixelDataSnafu { index })?;
+            frames.push(data.index_axis_move(Axis(3), 0));
+        }
+
+        // sort the slices along the normal
+        let slice_spacing = if planes.len() > 1 {
+            let spacing =
+                geometry::slice_spacing(&planes, self.tolerance).context(SliceSpacingSnafu)?;
+            if let Some(&i) = spacing.duplicates.first() {
+                return DuplicateSliceSnafu {
+                    position: spacing.positions[i],
+                }
+                .fail()
+                .map_err(Error::from);
+            }
+            ensure!(
+                self.allow_irregular_spacing || spacing.gaps.is_empty(),
+                IrregularSpacingSnafu { gaps: spacing.gaps }
+            );
+            spacing.spacing
+        } else {
+            1.
+        };
+        let order = geometry::slice_order(&planes);
+
+        let (rows, columns) = dimensions.unwrap_or_default();
+        let mut data = Array3::zeros((order.len(), rows as usize, columns as usize));
+        for (k, &i) in order.iter().enumerate() {
+            let (index, frame) = slices[i];
+            data.slice_mut(s![k, .., ..])
+                .assign(&frames[index].index_axis(Axis(0), frame as usize));
+        }
+
+        let first = &planes[order[0]];
+        let [row_spacing, column_spacing] = first.pixel_spacing;
+        Ok(Volume {
+            data,
+            voxel_spacing: [column_spacing, row_spacing, slice_spacing],
+            affine: first.affine(slice_spacing),
+            slices: order.into_iter().map(|i| slices[i]).collect(),
+        })
+    }
+
+    /// Check that a frame's plane is compatible with the first plane.
+    fn check_plane(
+        &self,
+        first: &ImagePlane,
+        plane: &ImagePlane,
+        index: usize,
+        frame: u32,
+    ) -> Result<()> {
+        let same_direction = |a: [f64; 3], b: [f64; 3]| {
+            a.iter()
+                .zip(b.iter())
+                .all(|(a, b)| (a - b).abs() <= ORIENTATION_TOLERANCE)
+        };
+        ensure!(
+            same_direction(first.row_direction, plane.row_direction)
+                && same_direction(first.column_direction, plane.column_direction),
+            InconsistentOrientationSnafu { index, frame }
+        );
+        ensure!(
+            first
+                .pixel_spacing
+                .iter()
+                .zip(plane.pixel_spacing.iter())
+                .all(|(a, b)| (a - b).abs() <= self.tolerance),
+            InconsistentPixelSpacingSnafu { index, frame }
+        );
+        Ok(())
+    }
+}
+
+impl Volume {
+    /// Open the given DICOM files and assemble them into a volume
+    /// with the default options.
+    ///
+    /// See [`VolumeBuilder`] for more options.
+    pub fn from_files<P>(paths: impl IntoIterator<Item = P>) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        VolumeBuilder::new().build_from_files(paths)
+    }
+
+    /// Assemble the given DICOM objects into a volume
+    /// with the default options.
+    ///
+    /// See [`VolumeBuilder`] for more options.
+    pub fn from_objects(objects: &[DefaultDicomObject]) -> Result<Self> {
+        VolumeBuilder::new().build(objects)
+    }
+
+    /// The shape of the volume, as `[slices, rows, columns]`.
+    pub fn shape(&self) -> [usize; 3] {
+        let (slices, rows, columns) = self.data.dim();
+        [slices, rows, columns]
+    }
+
+    /// Map voxel indices to a point in the patient coordinate system.
+    pub fn voxel_to_patient(&self, column: f64, row: f64, slice: f64) -> [f64; 3] {
+        let a = &self.affine;
+        let mut point = [0.; 3];
+        for (p, r) in point.iter_mut().zip(a.iter()) {
+            *p = r[0] * column + r[1] * row + r[2] * slice + r[3];
+        }
+        point
+    }
+
+    /// Save the volume to a NIfTI-1 file (`.nii`).
+    ///
+    /// See [`write_nifti`](Self::write_nifti) for more information.
+    pub fn save_nifti(&self, path: impl AsRef<Path>) -> Result<()> {
+        let file = std::fs::File::create(path).context(WriteNiftiSnafu)?;
+        let mut writer = std::io::BufWriter::new(file);
+        self.write_nifti(&mut writer)?;
+        writer.flush().context(WriteNiftiSnafu).map_err(Error::from)
+    }
+
+    /// Write the volume in the single file NIfTI-1 format.
+    ///
+    /// The voxel values are written as 32-bit floating point numbers.
+    /// The affine transform is converted to the RAS coordinate system
+    /// used by NIfTI,
+    /// and written both as the quaternion (`qform`)
+    /// and as the affine matrix (`sform`) of the scanner coordinates.
+    pub fn write_nifti(&self, mut to: impl Write) -> Result<()> {
+        self.write_nifti_impl(&mut to).context(WriteNiftiSnafu)?;
+        Ok(())
+    }
+
+    fn write_nifti_impl(&self, to: &mut impl Write) -> std::io::Result<()> {
+        let [slices, rows, columns] = self.shape();
+
+        // from LPS to RAS
+        let mut affine = self.affine;
+        for row in &mut affine[..2] {
+            for v in row.iter_mut() {
+                *v = -*v;
+            }
+        }
+        let (quaternion, qfac) = quaternion(&affine);
+
+        let mut header = Vec::with_capacity(352);
+        header.write_i32::<LittleEndian>(348)?;
+        // data_type, db_name, extents, session_error
+        header.extend_from_slice(&[0; 34]);
+        // regular, dim_info
+        header.extend_from_slice(&[b'r', 0]);
+        for dim in [3, columns, rows, slices, 1, 1, 1, 1].iter().copied() {
+            header.write_i16::<LittleEndian>(dim.min(i16::MAX as usize) as i16)?;
+        }
+        // intent_p1, intent_p2, intent_p3, intent_code
+        header.extend_from_slice(&[0; 14]);
+        // datatype: FLOAT32
+        header.write_i16::<LittleEndian>(16)?;
+        // bitpix
+        header.write_i16::<LittleEndian>(32)?;
+        // slice_start
+        header.write_i16::<LittleEndian>(0)?;
+        let [dx, dy, dz] = self.voxel_spacing;
+        for pixdim in [qfac, dx, dy, dz, 0., 0., 0., 0.].iter().copied() {
+            header.write_f32::<LittleEndian>(pixdim as f32)?;
+        }
+        // vox_offset
+        header.write_f32::<LittleEndian>(352.)?;
+        // scl_slope, scl_inter
+        header.write_f32::<LittleEndian>(1.)?;
+        header.write_f32::<LittleEndian>(0.)?;
+        // slice_end, slice_code
+        header.extend_from_slice(&[0; 3]);
+        // xyzt_units: millimeters
+        header.write_u8(2)?;
+        // cal_max, cal_min, slice_duration, toffset, glmax, glmin
+        header.extend_from_slice(&[0; 24]);
+        let mut description = [0; 80];
+        let text = concat!("DICOM-rs ", env!("CARGO_PKG_VERSION"));
+        description[..text.len()].copy_from_slice(text.as_bytes());
+        header.extend_from_slice(&description);
+        // aux_file
+        header.extend_from_slice(&[0; 24]);
+        // qform_code, sform_code: scanner-based anatomical coordinates
+        header.write_i16::<LittleEndian>(1)?;
+        header.write_i16::<LittleEndian>(1)?;
+        for q in quaternion.iter().copied() {
+            header.write_f32::<LittleEndian>(q as f32)?;
+        }
+        for row in &affine[..3] {
+            header.write_f32::<LittleEndian>(row[3] as f32)?;
+        }
+        for row in &affine[..3] {
+            for v in row.iter().copied() {
+                header.write_f32::<LittleEndian>(v as f32)?;
+            }
+        }
+        // intent_name
+        header.extend_from_slice(&[0; 16]);
+        header.extend_from_slice(b"n+1\0");
+        // no extensions
+        header.extend_from_slice(&[0; 4]);
+        debug_assert_eq!(header.len(), 352);
+        to.write_all(&header)?;
+
+        // columns vary the fastest in both the array and NIfTI
+        let mut data = Vec::with_capacity(self.data.len() * 4);
+        for v in self.data.iter().copied() {
+            data.write_f32::<LittleEndian>(v)?;
+        }
+        to.write_all(&data)
+    }
+}
+
+/// Compute the quaternion parameters `[b, c, d]`
+/// and the `qfac` of the rotation in the given affine transform,
+/// as defined by NIfTI-1.
+fn quaternion(affine: &Affine) -> ([f64; 3], f64) {
+    // normalized columns of the rotation matrix
+    let mut r = [[0.; 3]; 3];
+    for j in 0..3 {
+        let norm = (0..3)
+            .map(|i| affine[i][j] * affine[i][j])
+            .sum::<f64>()
+            .sqrt();
+        for i in 0..3 {
+            r[i][j] = if norm > 0. { affine[i][j] / norm } else { 0. };
+        }
+    }
+    let det = r[0][0] * (r[1][1] * r[2][2] - r[1][2] * r[2][1])
+        - r[0][1] * (r[1][0] * r[2][2] - r[1][2] * r[2][0])
+        + r[0][2] * (r[1][0] * r[2][1] - r[1][1] * r[2][0]);
+    let qfac = if det < 0. {
+        for row in r.iter_mut() {
+            row[2] = -row[2];
+        }
+        -1.
+    } else {
+        1.
+    };
+
+    let a = r[0][0] + r[1][1] + r[2][2] + 1.;
+    let (a, b, c, d) = if a > 0.5 {
+        let a = 0.5 * a.sqrt();
+        (
+            a,
+            0.25 * (r[2][1] - r[1][2]) / a,
+            0.25 * (r[0][2] - r[2][0]) / a,
+            0.25 * (r[1][0] - r[0][1]) / a,
+        )
+    } else {
+        let xd = 1. + r[0][0] - (r[1][1] + r[2][2]);
+        let yd = 1. + r[1][1] - (r[0][0] + r[2][2]);
+        let zd = 1. + r[2][2] - (r[0][0] + r[1][1]);
+        if xd > 1. {
+            let b = 0.5 * xd.sqrt();
+            (
+                0.25 * (r[2][1] - r[1][2]) / b,
+                b,
+                0.25 * (r[0][1] + r[1][0]) / b,
+                0.25 * (r[0][2] + r[2][0]) / b,
+            )
+        } else if yd > 1. {
+            let c = 0.5 * yd.sqrt();
+            (
+                0.25 * (r[0][2] - r[2][0]) / c,
+                0.25 * (r[0][1] + r[1][0]) / c,
+                c,
+                0.25 * (r[1][2] + r[2][1]) / c,
+            )
+        } else {
+            let d = 0.5 * zd.sqrt();
+            (
+                0.25 * (r[1][0] - r[0][1]) / d,
+                0.25 * (r[0][2] + r[2][0]) / d,
+                0.25 * (r[1][2] + r[2][1]) / d,
+                d,
+            )
+        }
+    };
+    if a < 0. {
+        ([-b, -c, -d], qfac)
+    } else {
+        ([b, c, d], qfac)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{dicom_value, DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+
+    /// A 2x3 CT slice at the given height,
+    /// with stored values starting at `value`.
+    fn slice(z: f64, value: u16) -> DefaultDicomObject {
+        let pixels: Vec<u16> = (value..value + 6).collect();
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, format!("2.25.{}", value)),
+            DataElement::new(
+                tags::IMAGE_POSITION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["-10".to_string(), "20".to_string(), z.to_string()]),
+            ),
+            DataElement::new(
+                tags::IMAGE_ORIENTATION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["1", "0", "0", "0", "1", "0"]),
+            ),
+            DataElement::new(
+                tags::PIXEL_SPACING,
+                VR::DS,
+                dicom_value!(Strs, ["0.5", "0.25"]),
+            ),
+            DataElement::new(tags::SAMPLES_PER_PIXEL, VR::US, dicom_value!(U16, [1])),
+            DataElement::new(tags::PHOTOMETRIC_INTERPRETATION, VR::CS, "MONOCHROME2"),
+            DataElement::new(tags::ROWS, VR::US, dicom_value!(U16, [2])),
+            DataElement::new(tags::COLUMNS, VR::US, dicom_value!(U16, [3])),
+            DataElement::new(tags::BITS_ALLOCATED, VR::US, dicom_value!(U16, [16])),
+            DataElement::new(tags::BITS_STORED, VR::US, dicom_value!(U16, [16])),
+            DataElement::new(tags::HIGH_BIT, VR::US, dicom_value!(U16, [15])),
+            DataElement::new(tags::PIXEL_REPRESENTATION, VR::US, dicom_value!(U16, [0])),
+            DataElement::new(tags::RESCALE_INTERCEPT, VR::DS, "-1024"),
+            DataElement::new(tags::RESCALE_SLOPE, VR::DS, "1"),
+            DataElement::new(tags::PIXEL_DATA, VR::OW, PrimitiveValue::U16(pixels.into())),
+        ])
+        .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+        .unwrap()
+    }
+
+    #[test]
+    fn assemble_volume() {
+        // given out of order
+        let objects = vec![slice(5., 200), slice(0., 0), slice(2.5, 100)];
+        let volume = Volume::from_objects(&objects).unwrap();
+
+        assert_eq!(volume.shape(), [3, 2, 3]);
+        assert_eq!(volume.slices, vec![(1, 0), (2, 0), (0, 0)]);
+        assert_eq!(volume.voxel_spacing, [0.25, 0.5, 2.5]);
+        assert_eq!(volume.data[[0, 0, 0]], -1024.);
+        assert_eq!(volume.data[[1, 1, 2]], 105. - 1024.);
+        assert_eq!(volume.data[[2, 0, 1]], 201. - 1024.);
+        assert_eq!(volume.voxel_to_patient(2., 1., 2.), [-9.5, 20.5, 5.]);
+
+        let volume = VolumeBuilder::new()
+            .modality_lut(false)
+            .build(&objects)
+            .unwrap();
+        assert_eq!(volume.data[[2, 0, 1]], 201.);
+    }
+
+    #[test]
+    fn reject_invalid_series() {
+        // missing slice
+        let objects = vec![slice(0., 0), slice(2.5, 100), slice(7.5, 200)];
+        assert!(Volume::from_objects(&objects).is_err());
+        let volume = VolumeBuilder::new()
+            .allow_irregular_spacing(true)
+            .build(&objects)
+            .unwrap();
+        assert_eq!(volume.voxel_spacing[2], 2.5);
+
+        // duplicate slice
+        let objects = vec![slice(0., 0), slice(2.5, 100), slice(2.5, 200)];
+        assert!(Volume::from_objects(&objects).is_err());
+
+        // different dimensions
+        let mut other = slice(2.5, 100);
+        other.put(DataElement::new(
+            tags::COLUMNS,
+            VR::US,
+            dicom_value!(U16, [2]),
+        ));
+        other.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0; 4].into()),
+        ));
+        assert!(Volume::from_objects(&[slice(0., 0), other]).is_err());
+
+        assert!(Volume::from_objects(&[]).is_err());
+    }
+
+    #[test]
+    fn write_nifti() {
+        let volume = Volume::from_objects(&[slice(0., 0), slice(2.5, 100)]).unwrap();
+        let mut out = Vec::new();
+        volume.write_nifti(&mut out).unwrap();
+
+        assert_eq!(out.len(), 352 + 2 * 2 * 3 * 4);
+        assert_eq!(&out[0..4], &348_i32.to_le_bytes());
+        assert_eq!(&out[344..348], b"n+1\0");
+        // dim
+        assert_eq!(&out[40..48], &[3, 0, 3, 0, 2, 0, 2, 0]);
+        // qfac and pixdim
+        let pixdim: Vec<f32> = out[76..92]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(pixdim, vec![1., 0.25, 0.5, 2.5]);
+        // srow_x, in RAS
+        let srow_x: Vec<f32> = out[280..296]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(srow_x, vec![-0.25, 0., 0., 10.]);
+        // quaternion of a 180 degree rotation around z
+        let quatern: Vec<f32> = out[256..268]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(quatern, vec![0., 0., 1.]);
+        // first voxel
+        assert_eq!(&out[352..356], &(-1024_f32).to_le_bytes());
+    }
+}
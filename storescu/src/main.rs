@@ -603,13 +603,13 @@ fn check_presentation_contexts(
     let pc = pcs.iter().find(|pc| {
         // Check support for this transfer syntax.
         // If it is the same as the file, we're good.
-        // Otherwise, uncompressed data set encoding
-        // and native pixel data is required on both ends.
+        // Otherwise, native pixel data is required on both ends
+        // (the data set may still be deflated).
         let ts = &pc.transfer_syntax;
         ts == file_ts.uid()
             || TransferSyntaxRegistry
                 .get(&pc.transfer_syntax)
-                .filter(|ts| file_ts.is_codec_free() && ts.is_codec_free())
+                .filter(|ts| file_ts.is_native_pixel_data() && ts.is_native_pixel_data())
                 .map(|_| true)
                 .unwrap_or(false)
     });
@@ -6,7 +6,10 @@ use clap::Parser;
 use dicom_core::prelude::*;
 use dicom_dictionary_std::{tags, uids};
 use dicom_object::{open_file, FileDicomObject, InMemDicomObject};
-use dicom_pixeldata::{ConvertOptions, PixelDecoder};
+#[cfg(feature = "openh264")]
+use dicom_pixeldata::video::{decode_video_frame, decode_video_frames, VideoCodec};
+use dicom_pixeldata::video::{video_file_extension, write_video_stream};
+use dicom_pixeldata::{ConvertOptions, DecodedPixelData, PixelDecoder, PresentationState};
 use snafu::{OptionExt, Report, ResultExt, Snafu, Whatever};
 use tracing::{error, warn, Level};
 
@@ -44,6 +47,15 @@ struct App {
     #[clap(flatten)]
     image_options: ImageOptions,
 
+    /// Path to a grayscale softcopy presentation state file
+    /// to apply to the images
+    #[arg(
+        long = "presentation-state",
+        value_name = "FILE",
+        conflicts_with = "unwrap"
+    )]
+    presentation_state: Option<PathBuf>,
+
     /// Stop on the first failed conversion
     #[arg(long)]
     fail_first: bool,
@@ -71,6 +83,25 @@ struct ImageOptions {
         conflicts_with = "force_16bit"
     )]
     unwrap: bool,
+
+    /// Extract the video stream of MPEG-2, H.264 or HEVC encoded files
+    /// (replaces input extension with `.mp4`, `.mpg`, `.h264` or `.hevc` by default)
+    #[arg(
+        long = "video",
+        conflicts_with = "unwrap",
+        conflicts_with = "force_8bit",
+        conflicts_with = "force_16bit"
+    )]
+    video: bool,
+
+    /// Burn the overlay planes into the output image
+    #[arg(
+        long = "burn-in-overlays",
+        conflicts_with = "unwrap",
+        conflicts_with = "video"
+    )]
+    burn_in_overlays: bool,
+
     /// Decode all pixel data frames instead of just the one intended
     #[arg(hide(true), long)]
     decode_all: bool,
@@ -84,6 +115,12 @@ enum Error {
         source: Box<dicom_object::ReadError>,
         path: PathBuf,
     },
+    #[snafu(display("could not read presentation state {}", path.display()))]
+    ReadPresentationState {
+        #[snafu(source(from(dicom_pixeldata::PresentationStateError, Box::new)))]
+        source: Box<dicom_pixeldata::PresentationStateError>,
+        path: PathBuf,
+    },
     /// failed to decode pixel data
     DecodePixelData {
         #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
@@ -106,6 +143,11 @@ enum Error {
         #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
         source: Box<dicom_pixeldata::Error>,
     },
+    /// failed to apply presentation state
+    ApplyPresentationState {
+        #[snafu(source(from(dicom_pixeldata::PresentationStateError, Box::new)))]
+        source: Box<dicom_pixeldata::PresentationStateError>,
+    },
     /// failed to save image to file
     SaveImage {
         #[snafu(source(from(dicom_pixeldata::image::ImageError, Box::new)))]
@@ -115,6 +157,17 @@ enum Error {
     SaveData { source: std::io::Error },
     /// Unexpected DICOM pixel data as data set sequence
     UnexpectedPixelData,
+    /// could not extract video stream
+    ExtractVideo {
+        #[snafu(source(from(dicom_pixeldata::video::Error, Box::new)))]
+        source: Box<dicom_pixeldata::video::Error>,
+    },
+    /// could not decode video frames
+    #[cfg(feature = "openh264")]
+    DecodeVideo {
+        #[snafu(source(from(dicom_pixeldata::video::Error, Box::new)))]
+        source: Box<dicom_pixeldata::video::Error>,
+    },
     /// No files given
     NoFiles,
     /// Read dir error
@@ -124,14 +177,17 @@ enum Error {
 impl Error {
     fn to_exit_code(&self) -> i32 {
         match self {
-            Error::ReadFile { .. } => -1,
+            Error::ReadFile { .. } | Error::ReadPresentationState { .. } => -1,
             Error::DecodePixelData { .. }
             | Error::MissingOffsetEntry { .. }
             | Error::MissingProperty { .. }
             | Error::InvalidPropertyValue { .. }
             | Error::FrameOutOfBounds { .. } => -2,
-            Error::ConvertImage { .. } => -3,
+            #[cfg(feature = "openh264")]
+            Error::DecodeVideo { .. } => -2,
+            Error::ConvertImage { .. } | Error::ApplyPresentationState { .. } => -3,
             Error::SaveData { .. } | Error::SaveImage { .. } => -4,
+            Error::ExtractVideo { .. } => -5,
             Error::UnexpectedPixelData => -7,
             Error::NoFiles => -8,
             Error::ReadDir { .. } => -9,
@@ -172,6 +228,7 @@ fn run(args: App) -> Result<(), Error> {
         ext,
         frame_number,
         image_options,
+        presentation_state,
         fail_first,
         verbose,
     } = args;
@@ -180,6 +237,14 @@ fn run(args: App) -> Result<(), Error> {
         return Err(Error::NoFiles);
     };
 
+    let presentation_state = presentation_state
+        .map(|path| {
+            let obj = open_file(&path).with_context(|_| ReadFileSnafu { path: path.clone() })?;
+            PresentationState::from_obj(&obj).context(ReadPresentationStateSnafu { path })
+        })
+        .transpose()?;
+    let presentation_state = presentation_state.as_ref();
+
     if files.len() == 1 {
         let file = &files[0];
         if file.is_dir() {
@@ -197,11 +262,19 @@ fn run(args: App) -> Result<(), Error> {
                     file.1.clone(),
                     outdir.clone(),
                     ext.clone(),
-                    image_options.unwrap,
+                    image_options.unwrap || image_options.video,
                 );
 
-                convert_single_file(&file.0, false, output, frame_number, image_options, verbose)
-                    .or_else(|e| {
+                convert_single_file(
+                    &file.0,
+                    false,
+                    output,
+                    frame_number,
+                    image_options,
+                    presentation_state,
+                    verbose,
+                )
+                .or_else(|e| {
                     if fail_first {
                         Err(e)
                     } else {
@@ -221,7 +294,7 @@ fn run(args: App) -> Result<(), Error> {
                 output.unwrap_or(files[0].clone()),
                 outdir.clone(),
                 ext.clone(),
-                image_options.unwrap,
+                image_options.unwrap || image_options.video,
             );
 
             convert_single_file(
@@ -230,6 +303,7 @@ fn run(args: App) -> Result<(), Error> {
                 output,
                 frame_number,
                 image_options,
+                presentation_state,
                 verbose,
             )?;
         }
@@ -254,7 +328,7 @@ fn run(args: App) -> Result<(), Error> {
                 file.clone(),
                 outdir.clone(),
                 ext.clone(),
-                image_options.unwrap,
+                image_options.unwrap || image_options.video,
             );
 
             convert_single_file(
@@ -263,6 +337,7 @@ fn run(args: App) -> Result<(), Error> {
                 output,
                 frame_number,
                 image_options,
+                presentation_state,
                 verbose,
             )
             .or_else(|e| {
@@ -316,16 +391,31 @@ fn convert_single_file(
     mut output: PathBuf,
     frame_number: u32,
     image_options: ImageOptions,
+    presentation_state: Option<&PresentationState>,
     verbose: bool,
 ) -> Result<(), Error> {
     let ImageOptions {
         force_8bit,
         force_16bit,
         unwrap,
+        video,
+        burn_in_overlays,
         decode_all,
     } = image_options;
 
-    if unwrap {
+    if video {
+        let mut out_data = Vec::new();
+        let codec = write_video_stream(file, &mut out_data).context(ExtractVideoSnafu)?;
+        if !output_is_set {
+            output.set_extension(video_file_extension(codec, &out_data));
+        }
+        std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+        std::fs::write(&output, out_data).context(SaveDataSnafu)?;
+
+        if verbose {
+            println!("Video stream saved to {}", output.display());
+        }
+    } else if unwrap {
         if !output_is_set {
             match file.meta().transfer_syntax() {
                 uids::JPEG_BASELINE8_BIT
@@ -461,23 +551,6 @@ fn convert_single_file(
         std::fs::create_dir_all(output.parent().unwrap()).unwrap();
         std::fs::write(output, out_data).context(SaveDataSnafu)?;
     } else {
-        let pixel = if decode_all {
-            file.decode_pixel_data().context(DecodePixelDataSnafu)?
-        } else {
-            file.decode_pixel_data_frame(frame_number)
-                .context(DecodePixelDataSnafu)?
-        };
-
-        if verbose {
-            println!(
-                "{}x{}x{} image, {}-bit",
-                pixel.columns(),
-                pixel.rows(),
-                pixel.samples_per_pixel(),
-                pixel.bits_stored()
-            );
-        }
-
         let mut options = ConvertOptions::new();
 
         if force_16bit {
@@ -486,11 +559,33 @@ fn convert_single_file(
             options = options.force_8bit();
         }
 
-        // the effective frame number
-        let frame_num = if decode_all { frame_number } else { 0 };
-        let image = pixel
-            .to_dynamic_image_with_options(frame_num, &options)
-            .context(ConvertImageSnafu)?;
+        if burn_in_overlays {
+            options = options.with_burn_in_overlays(true);
+        }
+
+        let image = if let Some(presentation_state) = presentation_state {
+            presentation_state
+                .render(file, frame_number, &options)
+                .context(ApplyPresentationStateSnafu)?
+        } else {
+            let pixel = decode_pixel_data(file, frame_number, decode_all)?;
+
+            if verbose {
+                println!(
+                    "{}x{}x{} image, {}-bit",
+                    pixel.columns(),
+                    pixel.rows(),
+                    pixel.samples_per_pixel(),
+                    pixel.bits_stored()
+                );
+            }
+
+            // the effective frame number
+            let frame_num = if decode_all { frame_number } else { 0 };
+            pixel
+                .to_dynamic_image_with_options(frame_num, &options)
+                .context(ConvertImageSnafu)?
+        };
 
         std::fs::create_dir_all(output.parent().unwrap()).unwrap();
 
@@ -504,6 +599,31 @@ fn convert_single_file(
     Ok(())
 }
 
+/// Decode all frames or the given frame of the file,
+/// decoding H.264 video frames if supported.
+fn decode_pixel_data(
+    file: &FileDicomObject<InMemDicomObject>,
+    frame_number: u32,
+    decode_all: bool,
+) -> Result<DecodedPixelData<'_>, Error> {
+    #[cfg(feature = "openh264")]
+    if VideoCodec::from_transfer_syntax(file.meta().transfer_syntax()) == Some(VideoCodec::H264) {
+        let pixel = if decode_all {
+            decode_video_frames(file)
+        } else {
+            decode_video_frame(file, frame_number)
+        };
+        return pixel.context(DecodeVideoSnafu);
+    }
+
+    if decode_all {
+        file.decode_pixel_data().context(DecodePixelDataSnafu)
+    } else {
+        file.decode_pixel_data_frame(frame_number)
+            .context(DecodePixelDataSnafu)
+    }
+}
+
 fn collect_dicom_files(
     file: &PathBuf,
     recursive: bool,
@@ -1,4 +1,4 @@
-//! Support for JPG image decoding.
+//! Support for JPEG image decoding and encoding.
 
 use dicom_core::ops::{AttributeAction, AttributeOp};
 use dicom_core::{PrimitiveValue, Tag};
@@ -16,6 +16,14 @@ use std::io::Cursor;
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct JpegAdapter;
 
+/// Pixel data encoder specifically for JPEG lossless compression
+/// (Process 14, Selection Value 1).
+///
+/// Supports sample precisions from 2 to 16 bits,
+/// with one or three samples per pixel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JpegLosslessEncoder;
+
 impl PixelDataReader for JpegAdapter {
     /// Decode DICOM image data with jpeg encoding.
     fn decode(&self, src: &dyn PixelDataObject, dst: &mut Vec<u8>) -> DecodeResult<()> {
@@ -170,53 +178,9 @@ impl PixelDataReader for JpegAdapter {
         let base_offset = dst.len();
         dst.resize(base_offset + (samples_per_pixel as usize * stride), 0);
 
-        let raw = src
-            .raw_pixel_data()
-            .whatever_context("Expected to have raw pixel data available")?;
-
-        let frame_data = if raw.fragments.len() == 1 || raw.fragments.len() == nr_frames {
-            // assuming 1:1 frame-to-fragment mapping
-            Cow::Borrowed(
-                raw.fragments
-                    .get(frame as usize)
-                    .with_whatever_context(|| {
-                        format!("Missing fragment #{} for the frame requested", frame)
-                    })?,
-            )
-        } else {
-            // Some embedded JPEGs might span multiple fragments.
-            // In this case we look up the basic offset table
-            // and gather all of the frame's fragments in a single vector.
-            // Note: not the most efficient way to do this,
-            // consider optimizing later with byte chunk readers
-            let base_offset = raw.offset_table.get(frame as usize).copied();
-            let base_offset = if frame == 0 {
-                base_offset.unwrap_or(0) as usize
-            } else {
-                base_offset
-                    .with_whatever_context(|| format!("Missing offset for frame #{}", frame))?
-                    as usize
-            };
-            let next_offset = raw.offset_table.get(frame as usize + 1);
-
-            let mut offset = 0;
-            let mut fragments = Vec::new();
-            for fragment in &raw.fragments {
-                // include it
-                if offset >= base_offset {
-                    fragments.extend_from_slice(fragment);
-                }
-                offset += fragment.len() + 8;
-                if let Some(&next_offset) = next_offset {
-                    if offset >= next_offset as usize {
-                        // next fragment is for the next frame
-                        break;
-                    }
-                }
-            }
-
-            Cow::Owned(fragments)
-        };
+        let frame_data = src
+            .frame_data(frame)
+            .with_whatever_context(|| format!("Missing pixel data for frame #{}", frame))?;
 
         let mut cursor = Cursor::new(&*frame_data);
         let dst_offset = base_offset;
@@ -358,6 +322,358 @@ impl PixelDataWriter for JpegAdapter {
     }
 }
 
+impl PixelDataWriter for JpegLosslessEncoder {
+    /// Encode a single frame in JPEG lossless
+    /// with first-order prediction (selection value 1).
+    ///
+    /// The sample precision of the encoded image follows Bits Stored,
+    /// but it is never lower than the number of bits
+    /// required to keep the samples in the size given by Bits Allocated.
+    fn encode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        _options: EncodeOptions,
+        dst: &mut Vec<u8>,
+    ) -> EncodeResult<Vec<AttributeOp>> {
+        let cols = src
+            .cols()
+            .context(encode_error::MissingAttributeSnafu { name: "Columns" })?;
+        let rows = src
+            .rows()
+            .context(encode_error::MissingAttributeSnafu { name: "Rows" })?;
+        let samples_per_pixel =
+            src.samples_per_pixel()
+                .context(encode_error::MissingAttributeSnafu {
+                    name: "SamplesPerPixel",
+                })?;
+        let bits_allocated = src
+            .bits_allocated()
+            .context(encode_error::MissingAttributeSnafu {
+                name: "BitsAllocated",
+            })?;
+        let bits_stored = src
+            .bits_stored()
+            .context(encode_error::MissingAttributeSnafu { name: "BitsStored" })?;
+
+        ensure_whatever!(
+            bits_allocated == 8 || bits_allocated == 16,
+            "BitsAllocated other than 8 or 16 is not supported"
+        );
+        ensure_whatever!(
+            (2..=bits_allocated).contains(&bits_stored),
+            "Unsupported Bits Stored {}",
+            bits_stored
+        );
+        ensure_whatever!(
+            samples_per_pixel == 1 || samples_per_pixel == 3,
+            "Unsupported samples per pixel: {}",
+            samples_per_pixel
+        );
+        ensure_whatever!(cols > 0 && rows > 0, "Image is empty");
+
+        let precision = if bits_allocated == 8 {
+            8
+        } else {
+            bits_stored.max(9)
+        } as u8;
+
+        let bytes_per_sample = (bits_allocated / 8) as usize;
+        let frame_size =
+            cols as usize * rows as usize * samples_per_pixel as usize * bytes_per_sample;
+
+        // identify frame data using the frame index
+        let pixeldata_uncompressed = &src
+            .raw_pixel_data()
+            .context(encode_error::MissingAttributeSnafu { name: "Pixel Data" })?
+            .fragments[0];
+
+        let frame_data = pixeldata_uncompressed
+            .get(frame_size * frame as usize..frame_size * (frame as usize + 1))
+            .whatever_context("Frame index out of bounds")?;
+
+        // collect the samples, discarding the bits beyond Bits Stored
+        let mask = ((1_u32 << precision) - 1) as u16;
+        let samples: Vec<u16> = if bytes_per_sample == 1 {
+            frame_data.iter().map(|b| u16::from(*b)).collect()
+        } else {
+            frame_data
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]) & mask)
+                .collect()
+        };
+
+        encode_lossless(
+            &samples,
+            cols,
+            rows,
+            samples_per_pixel as u8,
+            precision,
+            dst,
+        );
+
+        Ok(vec![])
+    }
+}
+
+/// The predictor used by [`JpegLosslessEncoder`]:
+/// the sample to the left (Ra).
+const SELECTION_VALUE: u8 = 1;
+
+/// Encode the interleaved samples of a frame
+/// into a JPEG lossless (Process 14) image
+/// with first-order prediction and an optimized Huffman table.
+fn encode_lossless(
+    samples: &[u16],
+    cols: u16,
+    rows: u16,
+    components: u8,
+    precision: u8,
+    dst: &mut Vec<u8>,
+) {
+    let cols_ = cols as usize;
+    let nc = components as usize;
+
+    // compute the prediction differences (Annex H.1.2),
+    // modulo 2^16
+    let differences: Vec<i32> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &sample)| {
+            let pixel = i / nc;
+            let (x, y) = (pixel % cols_, pixel / cols_);
+            let prediction = match (x, y) {
+                (0, 0) => 1 << (precision - 1),
+                // first row: use the sample to the left
+                (_, 0) => i32::from(samples[i - nc]),
+                // first column: use the sample above
+                (0, _) => i32::from(samples[i - nc * cols_]),
+                // selection value 1
+                _ => i32::from(samples[i - nc]),
+            };
+            let diff = (i32::from(sample) - prediction).rem_euclid(0x1_0000);
+            if diff > 0x8000 {
+                diff - 0x1_0000
+            } else {
+                diff
+            }
+        })
+        .collect();
+
+    // build Huffman table from the frequency of each magnitude category
+    let mut frequencies = [0_u32; 17];
+    for diff in &differences {
+        frequencies[magnitude_category(*diff) as usize] += 1;
+    }
+    let (bits, values) = optimal_huffman_table(&frequencies);
+    let codes = huffman_codes(&bits, &values);
+
+    // SOI
+    dst.extend_from_slice(&[0xFF, 0xD8]);
+
+    // SOF3: lossless (sequential), Huffman coding
+    dst.extend_from_slice(&[0xFF, 0xC3]);
+    dst.extend_from_slice(&(8 + 3 * u16::from(components)).to_be_bytes());
+    dst.push(precision);
+    dst.extend_from_slice(&rows.to_be_bytes());
+    dst.extend_from_slice(&cols.to_be_bytes());
+    dst.push(components);
+    for c in 0..components {
+        // component identifier, sampling factors 1x1, no quantization
+        dst.extend_from_slice(&[c + 1, 0x11, 0]);
+    }
+
+    // DHT: a single DC table shared by all components
+    dst.extend_from_slice(&[0xFF, 0xC4]);
+    dst.extend_from_slice(&(19 + values.len() as u16).to_be_bytes());
+    dst.push(0x00);
+    dst.extend_from_slice(&bits);
+    dst.extend_from_slice(&values);
+
+    // SOS
+    dst.extend_from_slice(&[0xFF, 0xDA]);
+    dst.extend_from_slice(&(6 + 2 * u16::from(components)).to_be_bytes());
+    dst.push(components);
+    for c in 0..components {
+        dst.extend_from_slice(&[c + 1, 0x00]);
+    }
+    // predictor, end of spectral selection (unused), point transform
+    dst.extend_from_slice(&[SELECTION_VALUE, 0, 0]);
+
+    // entropy-coded segment
+    let mut writer = BitWriter::new(dst);
+    for diff in differences {
+        let category = magnitude_category(diff);
+        let (code, size) = codes[category as usize];
+        writer.write(u32::from(code), size);
+        // difference 32768 is coded with no additional bits
+        if category > 0 && category < 16 {
+            let bits = if diff < 0 { diff - 1 } else { diff };
+            writer.write(bits as u32 & ((1 << category) - 1), category);
+        }
+    }
+    writer.flush();
+
+    // EOI
+    dst.extend_from_slice(&[0xFF, 0xD9]);
+}
+
+/// Get the magnitude category (SSSS) of a prediction difference
+/// (Table H.2).
+fn magnitude_category(diff: i32) -> u8 {
+    (32 - diff.unsigned_abs().leading_zeros()) as u8
+}
+
+/// Generate the code lengths and values of an optimal Huffman table
+/// for the given symbol frequencies,
+/// limited to code lengths of 16 bits (Annex K.2).
+fn optimal_huffman_table(frequencies: &[u32; 17]) -> ([u8; 16], Vec<u8>) {
+    // one extra symbol with frequency 1 is reserved
+    // so that no code consists of all 1-bits
+    let mut freq: Vec<u64> = frequencies.iter().map(|f| u64::from(*f)).collect();
+    freq.push(1);
+    let n = freq.len();
+    let mut code_size = vec![0_usize; n];
+    let mut others: Vec<Option<usize>> = vec![None; n];
+
+    loop {
+        // find the two least frequent symbols (preferring higher indices)
+        let mut c1 = None;
+        let mut c2 = None;
+        for i in 0..n {
+            if freq[i] == 0 {
+                continue;
+            }
+            if c1.map_or(true, |c: usize| freq[i] <= freq[c]) {
+                c2 = c1;
+                c1 = Some(i);
+            } else if c2.map_or(true, |c: usize| freq[i] <= freq[c]) {
+                c2 = Some(i);
+            }
+        }
+        let (Some(mut c1), Some(mut c2)) = (c1, c2) else {
+            break;
+        };
+        // merge the two branches into c1
+        freq[c1] += freq[c2];
+        freq[c2] = 0;
+
+        code_size[c1] += 1;
+        while let Some(next) = others[c1] {
+            c1 = next;
+            code_size[c1] += 1;
+        }
+        others[c1] = Some(c2);
+
+        code_size[c2] += 1;
+        while let Some(next) = others[c2] {
+            c2 = next;
+            code_size[c2] += 1;
+        }
+    }
+
+    let mut bits = [0_u32; 33];
+    for size in code_size.iter().filter(|s| **s > 0) {
+        bits[*size] += 1;
+    }
+
+    // adjust code lengths so that none is longer than 16 bits
+    for i in (17..=32).rev() {
+        while bits[i] > 0 {
+            let mut j = i - 2;
+            while bits[j] == 0 {
+                j -= 1;
+            }
+            bits[i] -= 2;
+            bits[i - 1] += 1;
+            bits[j + 1] += 2;
+            bits[j] -= 1;
+        }
+    }
+    // remove the reserved symbol from the longest code length
+    let mut i = 16;
+    while bits[i] == 0 {
+        i -= 1;
+    }
+    bits[i] -= 1;
+
+    // symbols in order of code length
+    let mut values = Vec::with_capacity(17);
+    for size in 1..=32 {
+        for (symbol, _) in code_size[..n - 1]
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| **s == size)
+        {
+            values.push(symbol as u8);
+        }
+    }
+
+    let mut table_bits = [0_u8; 16];
+    for (dst, src) in table_bits.iter_mut().zip(&bits[1..=16]) {
+        *dst = *src as u8;
+    }
+    (table_bits, values)
+}
+
+/// Generate the Huffman code and code length of each symbol
+/// from a Huffman table specification (Annex C).
+fn huffman_codes(bits: &[u8; 16], values: &[u8]) -> [(u16, u8); 17] {
+    let mut codes = [(0, 0); 17];
+    let mut code = 0_u16;
+    let mut values = values.iter();
+    for (i, count) in bits.iter().enumerate() {
+        for _ in 0..*count {
+            let symbol = *values.next().expect("inconsistent Huffman table");
+            codes[symbol as usize] = (code, i as u8 + 1);
+            code += 1;
+        }
+        code <<= 1;
+    }
+    codes
+}
+
+/// Bit-level writer of JPEG entropy-coded data,
+/// with byte stuffing after each 0xFF byte.
+struct BitWriter<'a> {
+    dst: &'a mut Vec<u8>,
+    acc: u64,
+    nbits: u8,
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(dst: &'a mut Vec<u8>) -> Self {
+        BitWriter {
+            dst,
+            acc: 0,
+            nbits: 0,
+        }
+    }
+
+    /// Write the `size` lowest bits of `bits`, most significant first.
+    fn write(&mut self, bits: u32, size: u8) {
+        self.acc = (self.acc << size) | u64::from(bits);
+        self.nbits += size;
+        while self.nbits >= 8 {
+            self.nbits -= 8;
+            let byte = (self.acc >> self.nbits) as u8;
+            self.dst.push(byte);
+            if byte == 0xFF {
+                self.dst.push(0x00);
+            }
+        }
+        self.acc &= (1 << self.nbits) - 1;
+    }
+
+    /// Pad the remaining bits with 1-bits up to a full byte.
+    fn flush(&mut self) {
+        if self.nbits > 0 {
+            let padding = 8 - self.nbits;
+            self.write((1 << padding) - 1, padding);
+        }
+    }
+}
+
 fn next_even(l: u64) -> u64 {
     (l + 1) & !1
 }
@@ -1,11 +1,36 @@
-//! Support for JPEG 2000 image decoding.
+//! Support for JPEG 2000 image decoding and encoding.
+//!
+//! Decoding is done through the `jpeg2k` crate.
+//! Encoding uses the OpenJPEG API of the same backend directly,
+//! and only supports JPEG 2000 Part 1 code streams.
+//! The High-Throughput JPEG 2000 transfer syntaxes are decode only,
+//! since OpenJPEG cannot produce HTJ2K code streams,
+//! so they have no pixel data writer
+//! and transcoding to them fails as unsupported.
+//!
+//! Unlike the rest of the crate,
+//! this module uses unsafe code to call into OpenJPEG.
+#![allow(unsafe_code)]
 
-use dicom_encoding::adapters::{decode_error, DecodeResult, PixelDataObject, PixelDataReader};
+use dicom_core::ops::{AttributeAction, AttributeOp};
+use dicom_core::{PrimitiveValue, Tag};
+use dicom_encoding::adapters::{
+    decode_error, encode_error, DecodeResult, EncodeOptions, EncodeResult, PixelDataObject,
+    PixelDataReader, PixelDataWriter,
+};
 use dicom_encoding::snafu::prelude::*;
 use jpeg2k::Image;
-use std::borrow::Cow;
+use std::ffi::c_void;
 use tracing::warn;
 
+#[cfg(feature = "openjp2")]
+mod sys {
+    pub use openjp2::image::opj_image_cmptparm_t;
+    pub use openjp2::openjpeg::*;
+}
+#[cfg(feature = "openjpeg-sys")]
+use openjpeg_sys as sys;
+
 // Check jpeg2k backend conflicts
 #[cfg(all(feature = "openjp2", feature = "openjpeg-sys"))]
 compile_error!(
@@ -16,6 +41,10 @@ compile_error!(
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Jpeg2000Adapter;
 
+/// Pixel data encoder specifically for JPEG 2000 lossless compression.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Jpeg2000LosslessEncoder;
+
 impl PixelDataReader for Jpeg2000Adapter {
     /// Decode a single frame in JPEG 2000 from a DICOM object.
     fn decode_frame(
@@ -61,53 +90,9 @@ impl PixelDataReader for Jpeg2000Adapter {
         let base_offset = dst.len();
         dst.resize(base_offset + (samples_per_pixel as usize * stride), 0);
 
-        let raw = src
-            .raw_pixel_data()
-            .whatever_context("Expected to have raw pixel data available")?;
-
-        let frame_data = if raw.fragments.len() == 1 || raw.fragments.len() == nr_frames {
-            // assuming 1:1 frame-to-fragment mapping
-            Cow::Borrowed(
-                raw.fragments
-                    .get(frame as usize)
-                    .with_whatever_context(|| {
-                        format!("Missing fragment #{} for the frame requested", frame)
-                    })?,
-            )
-        } else {
-            // Some embedded JPEGs might span multiple fragments.
-            // In this case we look up the basic offset table
-            // and gather all of the frame's fragments in a single vector.
-            // Note: not the most efficient way to do this,
-            // consider optimizing later with byte chunk readers
-            let base_offset = raw.offset_table.get(frame as usize).copied();
-            let base_offset = if frame == 0 {
-                base_offset.unwrap_or(0) as usize
-            } else {
-                base_offset
-                    .with_whatever_context(|| format!("Missing offset for frame #{}", frame))?
-                    as usize
-            };
-            let next_offset = raw.offset_table.get(frame as usize + 1);
-
-            let mut offset = 0;
-            let mut fragments = Vec::new();
-            for fragment in &raw.fragments {
-                // include it
-                if offset >= base_offset {
-                    fragments.extend_from_slice(fragment);
-                }
-                offset += fragment.len() + 8;
-                if let Some(&next_offset) = next_offset {
-                    if offset >= next_offset as usize {
-                        // next fragment is for the next frame
-                        break;
-                    }
-                }
-            }
-
-            Cow::Owned(fragments)
-        };
+        let frame_data = src
+            .frame_data(frame)
+            .with_whatever_context(|| format!("Missing pixel data for frame #{}", frame))?;
 
         let image = Image::from_bytes(&frame_data).whatever_context("jpeg2k decoder failure")?;
 
@@ -139,3 +124,358 @@ impl PixelDataReader for Jpeg2000Adapter {
         Ok(())
     }
 }
+
+impl PixelDataWriter for Jpeg2000Adapter {
+    /// Encode a single frame in JPEG 2000.
+    ///
+    /// The encoding quality (85 by default)
+    /// determines the target compression ratio of the code stream,
+    /// from about 1:1 at quality 99 down to 51:1 at quality 0.
+    /// The image is encoded losslessly if the quality is 100.
+    fn encode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        options: EncodeOptions,
+        dst: &mut Vec<u8>,
+    ) -> EncodeResult<Vec<AttributeOp>> {
+        let quality = options.quality.unwrap_or(85).min(100);
+        encode_frame_j2k(src, frame, Some(quality).filter(|q| *q < 100), dst)
+    }
+}
+
+impl PixelDataWriter for Jpeg2000LosslessEncoder {
+    /// Encode a single frame in JPEG 2000 lossless,
+    /// using the reversible wavelet transform.
+    fn encode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        _options: EncodeOptions,
+        dst: &mut Vec<u8>,
+    ) -> EncodeResult<Vec<AttributeOp>> {
+        encode_frame_j2k(src, frame, None, dst)
+    }
+}
+
+/// Encode a frame into a JPEG 2000 code stream,
+/// losslessly if `quality` is `None`.
+fn encode_frame_j2k(
+    src: &dyn PixelDataObject,
+    frame: u32,
+    quality: Option<u8>,
+    dst: &mut Vec<u8>,
+) -> EncodeResult<Vec<AttributeOp>> {
+    let cols = src
+        .cols()
+        .context(encode_error::MissingAttributeSnafu { name: "Columns" })?;
+    let rows = src
+        .rows()
+        .context(encode_error::MissingAttributeSnafu { name: "Rows" })?;
+    let samples_per_pixel =
+        src.samples_per_pixel()
+            .context(encode_error::MissingAttributeSnafu {
+                name: "SamplesPerPixel",
+            })?;
+    let bits_allocated = src
+        .bits_allocated()
+        .context(encode_error::MissingAttributeSnafu {
+            name: "BitsAllocated",
+        })?;
+    let bits_stored = src
+        .bits_stored()
+        .context(encode_error::MissingAttributeSnafu { name: "BitsStored" })?;
+
+    ensure_whatever!(
+        bits_allocated == 8 || bits_allocated == 16,
+        "BitsAllocated other than 8 or 16 is not supported"
+    );
+    ensure_whatever!(
+        (1..=bits_allocated).contains(&bits_stored),
+        "Unsupported Bits Stored {}",
+        bits_stored
+    );
+    ensure_whatever!(
+        samples_per_pixel == 1 || samples_per_pixel == 3,
+        "Unsupported samples per pixel: {}",
+        samples_per_pixel
+    );
+    ensure_whatever!(cols > 0 && rows > 0, "Image is empty");
+    let signed = src.pixel_representation().unwrap_or(0) == 1;
+
+    let bytes_per_sample = (bits_allocated / 8) as usize;
+    let samples_per_pixel = samples_per_pixel as usize;
+    let nr_pixels = cols as usize * rows as usize;
+    let frame_size = nr_pixels * samples_per_pixel * bytes_per_sample;
+
+    // identify frame data using the frame index
+    let pixeldata_uncompressed = &src
+        .raw_pixel_data()
+        .context(encode_error::MissingAttributeSnafu { name: "Pixel Data" })?
+        .fragments[0];
+
+    let frame_data = pixeldata_uncompressed
+        .get(frame_size * frame as usize..frame_size * (frame as usize + 1))
+        .whatever_context("Frame index out of bounds")?;
+
+    // create the image, one component per sample
+    let mut component_params: Vec<sys::opj_image_cmptparm_t> = (0..samples_per_pixel)
+        .map(|_| {
+            // safety: the parameters are plain data
+            let mut params: sys::opj_image_cmptparm_t = unsafe { std::mem::zeroed() };
+            params.dx = 1;
+            params.dy = 1;
+            params.w = cols as _;
+            params.h = rows as _;
+            params.prec = bits_stored as _;
+            params.sgnd = signed as _;
+            params
+        })
+        .collect();
+    let color_space = if samples_per_pixel == 3 {
+        sys::OPJ_COLOR_SPACE::OPJ_CLRSPC_SRGB
+    } else {
+        sys::OPJ_COLOR_SPACE::OPJ_CLRSPC_GRAY
+    };
+
+    // safety: the component parameters are valid and outlive the call
+    let image = ImageHandle(unsafe {
+        sys::opj_image_create(
+            samples_per_pixel as _,
+            component_params.as_mut_ptr(),
+            color_space,
+        )
+    });
+    ensure_whatever!(!image.0.is_null(), "Could not create JPEG 2000 image");
+
+    // safety: the image was created with `samples_per_pixel` components
+    // of `nr_pixels` samples each
+    unsafe {
+        let img = &mut *image.0;
+        img.x0 = 0;
+        img.y0 = 0;
+        img.x1 = cols as _;
+        img.y1 = rows as _;
+        let components = std::slice::from_raw_parts_mut(img.comps, samples_per_pixel);
+        for (component_i, component) in components.iter_mut().enumerate() {
+            let data = std::slice::from_raw_parts_mut(component.data, nr_pixels);
+            let mask = (1_u32 << bits_stored) - 1;
+            let shift = 32 - u32::from(bits_stored);
+            for (i, sample) in data.iter_mut().enumerate() {
+                let offset = (i * samples_per_pixel + component_i) * bytes_per_sample;
+                let value = if bytes_per_sample == 1 {
+                    u32::from(frame_data[offset])
+                } else {
+                    u32::from(u16::from_le_bytes([
+                        frame_data[offset],
+                        frame_data[offset + 1],
+                    ]))
+                };
+                let value = value & mask;
+                *sample = if signed {
+                    // sign extend from the most significant bit stored
+                    ((value << shift) as i32) >> shift
+                } else {
+                    value as i32
+                };
+            }
+        }
+    }
+
+    // set up encoding parameters
+    // safety: the parameters are plain data, initialized by OpenJPEG
+    let mut parameters: sys::opj_cparameters_t = unsafe { std::mem::zeroed() };
+    unsafe { sys::opj_set_default_encoder_parameters(&mut parameters) };
+    parameters.tcp_numlayers = 1;
+    parameters.cp_disto_alloc = 1;
+    match quality {
+        None => {
+            parameters.irreversible = 0;
+            parameters.tcp_rates[0] = 0.;
+        }
+        Some(quality) => {
+            parameters.irreversible = 1;
+            parameters.tcp_rates[0] = 1. + f32::from(100 - quality) / 2.;
+        }
+    }
+    // multi-component transform for color images
+    parameters.tcp_mct = if samples_per_pixel == 3 { 1 } else { 0 };
+    // the number of resolutions is limited by the image size
+    let mut numresolution = 6;
+    while numresolution > 1 && u32::from(cols.min(rows)) < 1 << (numresolution - 1) {
+        numresolution -= 1;
+    }
+    parameters.numresolution = numresolution;
+
+    // safety: codec, image, parameters and stream are valid
+    // for the duration of the encoding,
+    // and the output buffer outlives the stream
+    let len_before = dst.len();
+    let mut output = OutputBuffer {
+        data: &mut *dst,
+        start: len_before,
+        position: len_before,
+    };
+    let success = unsafe {
+        let codec = CodecHandle(sys::opj_create_compress(
+            sys::OPJ_CODEC_FORMAT::OPJ_CODEC_J2K,
+        ));
+        ensure_whatever!(!codec.0.is_null(), "Could not create JPEG 2000 encoder");
+        ensure_whatever!(
+            sys::opj_setup_encoder(codec.0, &mut parameters, image.0) != 0,
+            "Could not set up JPEG 2000 encoder"
+        );
+
+        let stream = StreamHandle(sys::opj_stream_create(STREAM_CHUNK_SIZE, 0));
+        ensure_whatever!(
+            !stream.0.is_null(),
+            "Could not create JPEG 2000 output stream"
+        );
+        sys::opj_stream_set_user_data(
+            stream.0,
+            &mut output as *mut OutputBuffer as *mut c_void,
+            None,
+        );
+        sys::opj_stream_set_write_function(stream.0, Some(write_output));
+        sys::opj_stream_set_skip_function(stream.0, Some(skip_output));
+        sys::opj_stream_set_seek_function(stream.0, Some(seek_output));
+
+        sys::opj_start_compress(codec.0, image.0, stream.0) != 0
+            && sys::opj_encode(codec.0, stream.0) != 0
+            && sys::opj_end_compress(codec.0, stream.0) != 0
+    };
+    ensure_whatever!(success, "JPEG 2000 encoding failed");
+
+    let mut changes = vec![];
+
+    if quality.is_some() {
+        let compressed_frame_size = dst.len() - len_before;
+        let compression_ratio = frame_size as f64 / compressed_frame_size as f64;
+        let compression_ratio = format!("{:.6}", compression_ratio);
+
+        changes.extend([
+            // lossy image compression
+            AttributeOp::new(Tag(0x0028, 0x2110), AttributeAction::SetStr("01".into())),
+            // lossy image compression ratio
+            AttributeOp::new(
+                Tag(0x0028, 0x2112),
+                AttributeAction::PushStr(compression_ratio.into()),
+            ),
+            // lossy image compression method
+            AttributeOp::new(
+                Tag(0x0028, 0x2114),
+                AttributeAction::PushStr("ISO_15444_1".into()),
+            ),
+        ]);
+    }
+
+    if samples_per_pixel == 3 {
+        // color images are encoded with a multi-component transform
+        let pmi = if quality.is_some() {
+            "YBR_ICT"
+        } else {
+            "YBR_RCT"
+        };
+        changes.push(AttributeOp::new(
+            Tag(0x0028, 0x0004),
+            AttributeAction::SetStr(pmi.into()),
+        ));
+        // planar configuration
+        changes.push(AttributeOp::new(
+            Tag(0x0028, 0x0006),
+            AttributeAction::Set(PrimitiveValue::from(0_u16)),
+        ));
+    }
+
+    Ok(changes)
+}
+
+/// The size of the chunks written to the output stream
+const STREAM_CHUNK_SIZE: usize = 0x10_0000;
+
+/// Owned OpenJPEG image, destroyed on drop.
+struct ImageHandle(*mut sys::opj_image_t);
+
+impl Drop for ImageHandle {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            // safety: the image was created by OpenJPEG and is not used after this
+            unsafe { sys::opj_image_destroy(self.0) };
+        }
+    }
+}
+
+/// Owned OpenJPEG codec, destroyed on drop.
+struct CodecHandle(*mut sys::opj_codec_t);
+
+impl Drop for CodecHandle {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            // safety: the codec was created by OpenJPEG and is not used after this
+            unsafe { sys::opj_destroy_codec(self.0) };
+        }
+    }
+}
+
+/// Owned OpenJPEG stream, destroyed on drop.
+struct StreamHandle(*mut sys::opj_stream_t);
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            // safety: the stream was created by OpenJPEG and is not used after this
+            unsafe { sys::opj_stream_destroy(self.0) };
+        }
+    }
+}
+
+/// The destination of an OpenJPEG output stream,
+/// appending the code stream to the end of a byte vector.
+struct OutputBuffer<'a> {
+    data: &'a mut Vec<u8>,
+    /// the position of the start of the code stream
+    start: usize,
+    /// the current writing position
+    position: usize,
+}
+
+unsafe extern "C" fn write_output(
+    buffer: *mut c_void,
+    nb_bytes: usize,
+    user_data: *mut c_void,
+) -> usize {
+    let output = &mut *(user_data as *mut OutputBuffer);
+    let bytes = std::slice::from_raw_parts(buffer as *const u8, nb_bytes);
+    let end = output.position + nb_bytes;
+    if end > output.data.len() {
+        output.data.resize(end, 0);
+    }
+    output.data[output.position..end].copy_from_slice(bytes);
+    output.position = end;
+    nb_bytes
+}
+
+unsafe extern "C" fn skip_output(nb_bytes: i64, user_data: *mut c_void) -> i64 {
+    let output = &mut *(user_data as *mut OutputBuffer);
+    let position = output.position as i64 + nb_bytes;
+    if position < output.start as i64 {
+        return -1;
+    }
+    output.position = position as usize;
+    if output.position > output.data.len() {
+        output.data.resize(output.position, 0);
+    }
+    nb_bytes
+}
+
+unsafe extern "C" fn seek_output(position: i64, user_data: *mut c_void) -> i32 {
+    let output = &mut *(user_data as *mut OutputBuffer);
+    if position < 0 {
+        return 0;
+    }
+    output.position = output.start + position as usize;
+    if output.position > output.data.len() {
+        output.data.resize(output.position, 0);
+    }
+    1
+}
@@ -5,21 +5,22 @@
 //!
 //! - [`jpeg`](jpeg) provides native JPEG decoding
 //!   (baseline and lossless)
-//!   and encoding (baseline).
+//!   and encoding (baseline and lossless).
 //!   Requires the `jpeg` feature,
 //!   enabled by default.
 //! - [`jpeg2k`](jpeg2k) contains JPEG 2000 support,
 //!   which is currently available through [OpenJPEG].
-//!   The `openjp2` feature provides native JPEG 2000 decoding
+//!   The `openjp2` feature provides native JPEG 2000 decoding and encoding
 //!   via the [Rust port of OpenJPEG][OpenJPEG-rs],
 //!   which works on Linux and Mac OS, but not on Windows.
 //!   Alternatively, enable the `openjpeg-sys` feature
 //!   to statically link to the OpenJPEG reference implementation.
 //!   `openjp2` is enabled by the feature `native`.
 //!   To build on Windows, enable `native_windows` instead.
+//!   Encoding is not available for High-Throughput JPEG 2000.
 //! - [`jpegxl`](jpegxl) provides JPEG XL decoding and encoding,
 //!   through `jxl-oxide` and `zune-jpegxl`, respectively.
-//! - [`rle_lossless`](rle_lossless) provides native RLE lossless decoding.
+//! - [`rle_lossless`](rle_lossless) provides native RLE lossless decoding and encoding.
 //!   Requires the `rle` feature,
 //!   enabled by default.
 //!
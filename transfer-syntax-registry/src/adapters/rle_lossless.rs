@@ -1,6 +1,6 @@
-//! Support for RLE Lossless image decoding.
+//! Support for RLE Lossless image decoding and encoding.
 //!
-//! implementation taken from Pydicom:
+//! Decoding implementation taken from Pydicom:
 //! <https://github.com/pydicom/pydicom/blob/master/pydicom/pixel_data_handlers/rle_handler.py>
 //!
 //! Copyright 2008-2021 pydicom authors.
@@ -8,7 +8,11 @@
 //! License: <https://github.com/pydicom/pydicom/blob/master/LICENSE>
 use byteordered::byteorder::{ByteOrder, LittleEndian};
 
-use dicom_encoding::adapters::{decode_error, DecodeResult, PixelDataObject, PixelDataReader};
+use dicom_core::ops::AttributeOp;
+use dicom_encoding::adapters::{
+    decode_error, encode_error, DecodeResult, EncodeOptions, EncodeResult, PixelDataObject,
+    PixelDataReader, PixelDataWriter,
+};
 use dicom_encoding::snafu::prelude::*;
 use std::io::{self, Read, Seek};
 
@@ -39,8 +43,8 @@ impl PixelDataReader for RleLosslessAdapter {
                 name: "BitsAllocated",
             })?;
 
-        if bits_allocated != 8 && bits_allocated != 16 {
-            whatever!("BitsAllocated other than 8 or 16 is not supported");
+        if bits_allocated != 8 && bits_allocated != 16 && bits_allocated != 32 {
+            whatever!("BitsAllocated other than 8, 16 or 32 is not supported");
         }
         // For RLE the number of fragments = number of frames
         // therefore, we can fetch the fragments one by one
@@ -100,11 +104,8 @@ impl PixelDataReader for RleLosslessAdapter {
                     // LSB G channel: 4, 10, 16, ...
                     let frame_start = i * frame_size;
                     let start = frame_start
-                        + if samples_per_pixel == 3 {
-                            sample_number * bytes_per_sample + byte_offset
-                        } else {
-                            sample_number * bytes_per_sample + samples_per_pixel - byte_offset
-                        };
+                        + sample_number * bytes_per_sample
+                        + (bytes_per_sample - 1 - byte_offset);
 
                     let end = (i + 1) * frame_size;
                     for (decoded_index, dst_index) in (start..end)
@@ -145,14 +146,10 @@ impl PixelDataReader for RleLosslessAdapter {
                 name: "BitsAllocated",
             })?;
 
-        if bits_allocated != 8 && bits_allocated != 16 {
-            whatever!("BitsAllocated other than 8 or 16 is not supported");
+        if bits_allocated != 8 && bits_allocated != 16 && bits_allocated != 32 {
+            whatever!("BitsAllocated other than 8, 16 or 32 is not supported");
         }
-        // For RLE the number of fragments = number of frames
-        // therefore, we can fetch the fragments one by one
-        let nr_frames =
-            src.number_of_fragments()
-                .whatever_context("Invalid pixel data, no fragments found")? as usize;
+        let nr_frames = src.number_of_frames().unwrap_or(1) as usize;
         ensure!(
             nr_frames > frame as usize,
             decode_error::FrameRangeOutOfBoundsSnafu
@@ -179,8 +176,10 @@ impl PixelDataReader for RleLosslessAdapter {
         //    Red         Green       Blue        | ...
         //    LSB R MSB R LSB G MSB G LSB B MSB B | ...
 
+        // For RLE the number of fragments = number of frames,
+        // therefore each frame is fetched from a single fragment
         let fragment = &src
-            .fragment(frame as usize)
+            .frame_data(frame)
             .whatever_context("No pixel data found for frame")?;
         let mut offsets = read_rle_header(fragment);
         offsets.push(fragment.len() as u32);
@@ -202,11 +201,7 @@ impl PixelDataReader for RleLosslessAdapter {
                     .unwrap();
 
                 // Interleave pixels as described in the example above.
-                let start = if samples_per_pixel == 3 {
-                    sample_number * bytes_per_sample + byte_offset
-                } else {
-                    sample_number * bytes_per_sample + samples_per_pixel - byte_offset
-                };
+                let start = sample_number * bytes_per_sample + (bytes_per_sample - 1 - byte_offset);
 
                 let end = frame_size;
                 for (decoded_index, dst_index) in (start..end)
@@ -221,7 +216,123 @@ impl PixelDataReader for RleLosslessAdapter {
     }
 }
 
-// TODO(#125) implement `encode`
+/// Pixel data encoder for RLE Lossless (UID `1.2.840.10008.1.2.5`)
+impl PixelDataWriter for RleLosslessAdapter {
+    /// Encode a single frame of native pixel data into RLE Lossless.
+    ///
+    /// The source pixel data is expected to be native and in little endian.
+    /// Samples stored by plane (planar configuration of 1)
+    /// are interleaved before encoding.
+    /// Each byte of each sample is written to a separate RLE segment,
+    /// from the most significant to the least significant byte,
+    /// as required by the standard.
+    ///
+    /// See <https://dicom.nema.org/medical/dicom/2023e/output/chtml/part05/chapter_G.html>
+    fn encode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        _options: EncodeOptions,
+        dst: &mut Vec<u8>,
+    ) -> EncodeResult<Vec<AttributeOp>> {
+        let cols = src
+            .cols()
+            .context(encode_error::MissingAttributeSnafu { name: "Columns" })?;
+        let rows = src
+            .rows()
+            .context(encode_error::MissingAttributeSnafu { name: "Rows" })?;
+        let samples_per_pixel =
+            src.samples_per_pixel()
+                .context(encode_error::MissingAttributeSnafu {
+                    name: "SamplesPerPixel",
+                })?;
+        let bits_allocated = src
+            .bits_allocated()
+            .context(encode_error::MissingAttributeSnafu {
+                name: "BitsAllocated",
+            })?;
+
+        ensure_whatever!(
+            bits_allocated == 8 || bits_allocated == 16 || bits_allocated == 32,
+            "BitsAllocated other than 8, 16 or 32 is not supported"
+        );
+
+        let bytes_per_sample = (bits_allocated / 8) as usize;
+        let samples_per_pixel = samples_per_pixel as usize;
+        let nr_segments = bytes_per_sample * samples_per_pixel;
+        ensure_whatever!(
+            (1..=15).contains(&nr_segments),
+            "Unsupported number of RLE segments: {}",
+            nr_segments
+        );
+
+        let pixel_size = bytes_per_sample * samples_per_pixel;
+        let row_size = cols as usize * pixel_size;
+        let frame_size = row_size * rows as usize;
+
+        // only native pixel data can be encoded
+        ensure_whatever!(
+            src.offset_table().is_none(),
+            "Encapsulated pixel data cannot be encoded into RLE Lossless"
+        );
+
+        let pixeldata_uncompressed = &src
+            .raw_pixel_data()
+            .context(encode_error::MissingAttributeSnafu { name: "Pixel Data" })?
+            .fragments[0];
+
+        let frame_data = pixeldata_uncompressed
+            .get(frame_size * frame as usize..frame_size * (frame as usize + 1))
+            .whatever_context("Frame index out of bounds")?;
+
+        // bring samples to an interleaved layout if they are stored by plane
+        let interleaved;
+        let frame_data = match src.planar_configuration().unwrap_or(0) {
+            0 => frame_data,
+            1 => {
+                interleaved = interleave_planes(frame_data, samples_per_pixel, bytes_per_sample);
+                &interleaved[..]
+            }
+            planar_configuration => {
+                whatever!("Unsupported planar configuration: {}", planar_configuration)
+            }
+        };
+
+        // RLE header: number of segments followed by 15 segment offsets,
+        // filled in as the segments are written
+        let header_start = dst.len();
+        dst.resize(header_start + 64, 0);
+        LittleEndian::write_u32(&mut dst[header_start..], nr_segments as u32);
+
+        let mut segment_row = Vec::with_capacity(cols as usize);
+        for sample_number in 0..samples_per_pixel {
+            for byte_offset in 0..bytes_per_sample {
+                let ii = sample_number * bytes_per_sample + byte_offset;
+                let segment_offset = (dst.len() - header_start) as u32;
+                let header_pos = header_start + 4 * (ii + 1);
+                LittleEndian::write_u32(&mut dst[header_pos..header_pos + 4], segment_offset);
+
+                // segment 0 holds the most significant byte,
+                // which comes last in little endian samples
+                let start = sample_number * bytes_per_sample + (bytes_per_sample - 1 - byte_offset);
+
+                // each row is encoded separately
+                for row in frame_data.chunks_exact(row_size) {
+                    segment_row.clear();
+                    segment_row.extend(row[start..].iter().step_by(pixel_size));
+                    pack_bits(&segment_row, dst);
+                }
+
+                // segments have an even length
+                if (dst.len() - header_start) % 2 != 0 {
+                    dst.push(0);
+                }
+            }
+        }
+
+        Ok(vec![])
+    }
+}
 
 // Read the RLE header and return the offsets
 fn read_rle_header(fragment: &[u8]) -> Vec<u32> {
@@ -231,6 +342,54 @@ fn read_rle_header(fragment: &[u8]) -> Vec<u32> {
     offsets
 }
 
+// Convert a frame with one plane per sample into interleaved samples
+fn interleave_planes(
+    frame_data: &[u8],
+    samples_per_pixel: usize,
+    bytes_per_sample: usize,
+) -> Vec<u8> {
+    let plane_size = frame_data.len() / samples_per_pixel;
+    let mut out = Vec::with_capacity(frame_data.len());
+    for start in (0..plane_size).step_by(bytes_per_sample) {
+        for plane in frame_data.chunks_exact(plane_size) {
+            out.extend_from_slice(&plane[start..start + bytes_per_sample]);
+        }
+    }
+    out
+}
+
+// Encode the given bytes using the PackBits algorithm
+fn pack_bits(data: &[u8], dst: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        // look for a run of the same byte
+        let run = data[i..]
+            .iter()
+            .take(128)
+            .take_while(|b| **b == data[i])
+            .count();
+        if run > 1 {
+            // replicate run: header in -1..=-127
+            dst.push((1 - run as i16) as i8 as u8);
+            dst.push(data[i]);
+            i += run;
+        } else {
+            // literal run, until the next pair of equal bytes
+            let start = i;
+            i += 1;
+            while i < data.len() && i - start < 128 {
+                if i + 1 < data.len() && data[i] == data[i + 1] {
+                    break;
+                }
+                i += 1;
+            }
+            // literal run: header in 0..=127
+            dst.push((i - start - 1) as u8);
+            dst.extend_from_slice(&data[start..i]);
+        }
+    }
+}
+
 /// PackBits Reader from the image-tiff crate
 /// Copyright 2018-2021 PistonDevelopers.
 /// License: <https://github.com/image-rs/image-tiff/blob/master/LICENSE>
@@ -310,4 +469,39 @@ mod test {
         ];
         assert_eq!(decoded, expected);
     }
+
+    #[test]
+    fn test_interleave_planes() {
+        // 2 pixels of 16-bit RGB, one plane per sample
+        let planar = [
+            0x01, 0x00, 0x02, 0x00, // R
+            0x03, 0x00, 0x04, 0x00, // G
+            0x05, 0x00, 0x06, 0x00, // B
+        ];
+        assert_eq!(
+            interleave_planes(&planar, 3, 2),
+            vec![0x01, 0x00, 0x03, 0x00, 0x05, 0x00, 0x02, 0x00, 0x04, 0x00, 0x06, 0x00],
+        );
+    }
+
+    #[test]
+    fn test_pack_bits_roundtrip() {
+        let mut data = vec![0xAA; 3];
+        data.extend([0x80, 0x00, 0x2A]);
+        data.extend([0x10; 300]);
+        data.extend((0..=255).collect::<Vec<u8>>());
+        data.push(0x22);
+
+        let mut encoded = Vec::new();
+        pack_bits(&data, &mut encoded);
+        assert_eq!(&encoded[..6], &[0xFE, 0xAA, 0x02, 0x80, 0x00, 0x2A]);
+
+        let encoded_len = encoded.len();
+        let buff = io::Cursor::new(encoded);
+        let (_, mut decoder) = PackBitsReader::new(buff, encoded_len).unwrap();
+
+        let mut decoded = Vec::new();
+        decoder.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+    }
 }
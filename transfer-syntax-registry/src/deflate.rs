@@ -1,43 +1,90 @@
 //! Implementation of Deflated Explicit VR Little Endian.
 use std::io::{Read, Write};
 
-use byteordered::Endianness;
-use dicom_encoding::{Codec, TransferSyntax, transfer_syntax::DataRWAdapter};
-use flate2;
+use dicom_encoding::transfer_syntax::DataRWAdapter;
 use flate2::Compression;
 
 /// Immaterial type representing an adapter for deflated data.
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FlateAdapter;
 
-/// **Fully implemented**: Deflated Explicit VR Little Endian
-pub const DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN: TransferSyntax<FlateAdapter> = TransferSyntax::new(
-    "1.2.840.10008.1.2.1.99",
-    "Deflated Explicit VR Little Endian",
-    Endianness::Little,
-    true,
-    Codec::Dataset(FlateAdapter),
-);
-
-impl<R: 'static, W: 'static> DataRWAdapter<R, W> for FlateAdapter
+impl<R, W> DataRWAdapter<R, W> for FlateAdapter
 where
-    R: Read,
-    W: Write,
+    R: Read + 'static,
+    W: Write + 'static,
 {
-    type Reader = flate2::read::DeflateDecoder<R>;
-    type Writer = flate2::write::DeflateEncoder<W>;
+    type Reader = Box<dyn Read>;
+    type Writer = Box<dyn Write>;
 
     fn adapt_reader(&self, reader: R) -> Self::Reader
     where
         R: Read,
     {
-        flate2::read::DeflateDecoder::new(reader)
+        Box::new(flate2::read::DeflateDecoder::new(reader))
     }
 
+    /// Adapt a byte writer.
+    ///
+    /// The deflate stream is only terminated
+    /// when the returned writer is dropped,
+    /// at which point errors can no longer be reported.
+    /// Writing to an in-memory buffer first is recommended.
     fn adapt_writer(&self, writer: W) -> Self::Writer
     where
         W: Write,
     {
-        flate2::write::DeflateEncoder::new(writer, Compression::fast())
+        Box::new(flate2::write::DeflateEncoder::new(
+            writer,
+            Compression::fast(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlateAdapter;
+    use dicom_encoding::transfer_syntax::DataRWAdapter;
+    use std::cell::RefCell;
+    use std::io::{Cursor, Read, Write};
+    use std::rc::Rc;
+
+    /// An in-memory writer which can still be inspected
+    /// after the adapted writer took ownership of it.
+    #[derive(Default, Clone)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn deflate_round_trip() {
+        let data: Vec<u8> = (0..4096_u32).map(|x| (x % 251) as u8).collect();
+
+        let buffer = SharedBuffer::default();
+        {
+            let mut writer = DataRWAdapter::<Box<dyn Read>, Box<dyn Write>>::adapt_writer(
+                &FlateAdapter,
+                Box::new(buffer.clone()),
+            );
+            writer.write_all(&data).unwrap();
+        }
+        let compressed = buffer.0.take();
+        assert!(!compressed.is_empty());
+        assert!(compressed.len() < data.len());
+
+        let mut reader = DataRWAdapter::<Box<dyn Read>, Box<dyn Write>>::adapt_reader(
+            &FlateAdapter,
+            Box::new(Cursor::new(compressed)),
+        );
+        let mut decompressed = Vec::new();
+        reader.read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
     }
 }
@@ -27,19 +27,21 @@ use dicom_encoding::transfer_syntax::{AdapterFreeTransferSyntax as Ts, Codec};
 
 use dicom_encoding::transfer_syntax::{NeverAdapter, TransferSyntax};
 
-#[cfg(any(feature = "rle", feature = "openjp2", feature = "openjpeg-sys"))]
+#[cfg(any(feature = "deflate", feature = "openjp2", feature = "openjpeg-sys"))]
 use dicom_encoding::NeverPixelAdapter;
 
 #[cfg(feature = "jpeg")]
-use crate::adapters::jpeg::JpegAdapter;
+use crate::adapters::jpeg::{JpegAdapter, JpegLosslessEncoder};
 #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
-use crate::adapters::jpeg2k::Jpeg2000Adapter;
+use crate::adapters::jpeg2k::{Jpeg2000Adapter, Jpeg2000LosslessEncoder};
 #[cfg(feature = "charls")]
 use crate::adapters::jpegls::{JpegLsAdapter, JpegLsLosslessWriter};
 #[cfg(feature = "jpegxl")]
 use crate::adapters::jpegxl::{JpegXlAdapter, JpegXlLosslessEncoder};
 #[cfg(feature = "rle")]
 use crate::adapters::rle_lossless::RleLosslessAdapter;
+#[cfg(feature = "deflate")]
+use crate::deflate::FlateAdapter;
 
 // -- the three base transfer syntaxes, fully supported --
 
@@ -83,11 +85,11 @@ pub const ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN: TransferSyntax<
 
 /// **Implemented:** RLE Lossless
 #[cfg(feature = "rle")]
-pub const RLE_LOSSLESS: TransferSyntax<NeverAdapter, RleLosslessAdapter, NeverPixelAdapter> =
+pub const RLE_LOSSLESS: TransferSyntax<NeverAdapter, RleLosslessAdapter, RleLosslessAdapter> =
     TransferSyntax::new_ele(
         "1.2.840.10008.1.2.5",
         "RLE Lossless",
-        Codec::EncapsulatedPixelData(Some(RleLosslessAdapter), None),
+        Codec::EncapsulatedPixelData(Some(RleLosslessAdapter), Some(RleLosslessAdapter)),
     );
 /// **Stub:** RLE Lossless
 ///
@@ -144,12 +146,15 @@ pub const JPEG_EXTENDED: Ts =
     create_ts_stub("1.2.840.10008.1.2.4.51", "JPEG Extended (Process 2 & 4)");
 
 /// **Implemented:** JPEG Lossless, Non-Hierarchical (Process 14)
+///
+/// Encoding is done with first-order prediction (selection value 1).
 #[cfg(feature = "jpeg")]
-pub const JPEG_LOSSLESS_NON_HIERARCHICAL: JpegTs = create_ts_jpeg(
-    "1.2.840.10008.1.2.4.57",
-    "JPEG Lossless, Non-Hierarchical (Process 14)",
-    false,
-);
+pub const JPEG_LOSSLESS_NON_HIERARCHICAL: JpegTs<JpegAdapter, JpegLosslessEncoder> =
+    TransferSyntax::new_ele(
+        "1.2.840.10008.1.2.4.57",
+        "JPEG Lossless, Non-Hierarchical (Process 14)",
+        Codec::EncapsulatedPixelData(Some(JpegAdapter), Some(JpegLosslessEncoder)),
+    );
 /// **Stub descriptor:** JPEG Lossless, Non-Hierarchical (Process 14)
 ///
 /// A native implementation is available
@@ -164,10 +169,13 @@ pub const JPEG_LOSSLESS_NON_HIERARCHICAL: Ts = create_ts_stub(
 /// (Process 14 [Selection Value 1]):
 /// Default Transfer Syntax for Lossless JPEG Image Compression
 #[cfg(feature = "jpeg")]
-pub const JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION: JpegTs = create_ts_jpeg(
+pub const JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION: JpegTs<
+    JpegAdapter,
+    JpegLosslessEncoder,
+> = TransferSyntax::new_ele(
     "1.2.840.10008.1.2.4.70",
     "JPEG Lossless, Non-Hierarchical, First-Order Prediction",
-    false,
+    Codec::EncapsulatedPixelData(Some(JpegAdapter), Some(JpegLosslessEncoder)),
 );
 /// **Stub descriptor:** JPEG Lossless, Non-Hierarchical, First-Order Prediction
 /// (Process 14 [Selection Value 1]):
@@ -181,15 +189,32 @@ pub const JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION: Ts = create_ts_
     "JPEG Lossless, Non-Hierarchical, First-Order Prediction",
 );
 
-// --- stub transfer syntaxes, known but not supported ---
+// -- transfer syntaxes with data set adapters --
 
+/// **Fully implemented:** Deflated Explicit VR Little Endian
+#[cfg(feature = "deflate")]
+pub const DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN: TransferSyntax<
+    FlateAdapter,
+    NeverPixelAdapter,
+    NeverPixelAdapter,
+> = TransferSyntax::new_ele(
+    "1.2.840.10008.1.2.1.99",
+    "Deflated Explicit VR Little Endian",
+    Codec::Dataset(Some(FlateAdapter)),
+);
 /// **Stub descriptor:** Deflated Explicit VR Little Endian
+///
+/// A native implementation is available
+/// by enabling the `deflate` Cargo feature.
+#[cfg(not(feature = "deflate"))]
 pub const DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN: Ts = Ts::new_ele(
     "1.2.840.10008.1.2.1.99",
     "Deflated Explicit VR Little Endian",
     Codec::Dataset(None),
 );
 
+// --- stub transfer syntaxes, known but not supported ---
+
 /// **Stub descriptor:** JPIP Referenced Deflate
 pub const JPIP_REFERENCED_DEFLATE: Ts = Ts::new_ele(
     "1.2.840.10008.1.2.4.95",
@@ -207,8 +232,8 @@ pub const JPIP_HTJ2K_REFERENCED_DEFLATE: Ts = Ts::new_ele(
 // --- JPEG 2000 support ---
 
 /// An alias for a transfer syntax specifier with [`Jpeg2000Adapter`]
-/// (supports decoding and encoding to JPEG baseline,
-/// support for JPEG extended and JPEG lossless may vary).
+/// (supports decoding,
+/// encoding is only available for JPEG 2000 Part 1).
 #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
 type Jpeg2000Ts<R = Jpeg2000Adapter, W = NeverPixelAdapter> = TransferSyntax<NeverAdapter, R, W>;
 
@@ -222,11 +247,15 @@ const fn create_ts_jpeg2k(uid: &'static str, name: &'static str) -> Jpeg2000Ts {
     )
 }
 
-/// **Decoder implementation:** JPEG 2000 Image Compression (Lossless Only)
+/// **Implemented:** JPEG 2000 Image Compression (Lossless Only)
 #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
-pub const JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY: Jpeg2000Ts = create_ts_jpeg2k(
+pub const JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY: Jpeg2000Ts<
+    Jpeg2000Adapter,
+    Jpeg2000LosslessEncoder,
+> = TransferSyntax::new_ele(
     "1.2.840.10008.1.2.4.90",
     "JPEG 2000 Image Compression (Lossless Only)",
+    Codec::EncapsulatedPixelData(Some(Jpeg2000Adapter), Some(Jpeg2000LosslessEncoder)),
 );
 /// **Stub descriptor:** JPEG 2000 Image Compression (Lossless Only)
 #[cfg(not(any(feature = "openjp2", feature = "openjpeg-sys")))]
@@ -235,10 +264,14 @@ pub const JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY: Ts = create_ts_stub(
     "JPEG 2000 Image Compression (Lossless Only)",
 );
 
-/// **Decoder implementation:** JPEG 2000 Image Compression
+/// **Implemented:** JPEG 2000 Image Compression
 #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
-pub const JPEG_2000_IMAGE_COMPRESSION: Jpeg2000Ts =
-    create_ts_jpeg2k("1.2.840.10008.1.2.4.91", "JPEG 2000 Image Compression");
+pub const JPEG_2000_IMAGE_COMPRESSION: Jpeg2000Ts<Jpeg2000Adapter, Jpeg2000Adapter> =
+    TransferSyntax::new_ele(
+        "1.2.840.10008.1.2.4.91",
+        "JPEG 2000 Image Compression",
+        Codec::EncapsulatedPixelData(Some(Jpeg2000Adapter), Some(Jpeg2000Adapter)),
+    );
 /// **Stub descriptor:** JPEG 2000 Image Compression
 #[cfg(not(any(feature = "openjp2", feature = "openjpeg-sys")))]
 pub const JPEG_2000_IMAGE_COMPRESSION: Ts =
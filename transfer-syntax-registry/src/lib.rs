@@ -48,18 +48,20 @@
 //! _Explicit VR Little Endian_,
 //! and _Explicit VR Big Endian_
 //! are fully supported.
+//! _Deflated Explicit VR Little Endian_ is also supported
+//! through the Cargo feature `deflate`, which is enabled by default.
 //! Support may vary for transfer syntaxes which rely on encapsulated pixel data.
 //!
 //! | transfer syntax               | decoding support     | encoding support |
 //! |-------------------------------|----------------------|------------------|
 //! | JPEG Baseline (Process 1)     | Cargo feature `jpeg` | ✓ |
 //! | JPEG Extended (Process 2 & 4) | Cargo feature `jpeg` | x |
-//! | JPEG Lossless, Non-Hierarchical (Process 14) | Cargo feature `jpeg` | x |
-//! | JPEG Lossless, Non-Hierarchical, First-Order Prediction (Process 14 [Selection Value 1]) | Cargo feature `jpeg` | x |
+//! | JPEG Lossless, Non-Hierarchical (Process 14) | Cargo feature `jpeg` | ✓ |
+//! | JPEG Lossless, Non-Hierarchical, First-Order Prediction (Process 14 [Selection Value 1]) | Cargo feature `jpeg` | ✓ |
 //! | JPEG-LS Lossless              | Cargo feature `charls` | ✓ |
 //! | JPEG-LS Lossy (Near-Lossless) | Cargo feature `charls` | ✓ |
-//! | JPEG 2000 (Lossless Only)     | Cargo feature `openjp2` or `openjpeg-sys` | x |
-//! | JPEG 2000                     | Cargo feature `openjp2` or `openjpeg-sys` | x |
+//! | JPEG 2000 (Lossless Only)     | Cargo feature `openjp2` or `openjpeg-sys` | ✓ |
+//! | JPEG 2000                     | Cargo feature `openjp2` or `openjpeg-sys` | ✓ |
 //! | JPEG 2000 Part 2 Multi-component Image Compression (Lossless Only) | Cargo feature `openjp2` or `openjpeg-sys` | x |
 //! | JPEG 2000 Part 2 Multi-component Image Compression | Cargo feature `openjp2` or `openjpeg-sys` | x |
 //! | High-Throughput JPEG 2000 (Lossless Only) | Cargo feature `openjp2` or `openjpeg-sys` | x |
@@ -68,7 +70,7 @@
 //! | JPEG XL Lossless              | Cargo feature `jpegxl` | ✓ |
 //! | JPEG XL Recompression         | Cargo feature `jpegxl` | x |
 //! | JPEG XL                       | Cargo feature `jpegxl` | ✓ |
-//! | RLE Lossless                  | Cargo feature `rle` | x |
+//! | RLE Lossless                  | Cargo feature `rle` | ✓ |
 //!
 //! Cargo features behind `native` (`jpeg`, `rle`) are added by default.
 //! They provide implementations that are written in pure Rust
@@ -114,6 +116,8 @@ pub use dicom_encoding::TransferSyntax;
 pub mod entries;
 
 mod adapters;
+#[cfg(feature = "deflate")]
+pub mod deflate;
 
 #[cfg(feature = "inventory-registry")]
 pub use dicom_encoding::inventory;
@@ -15,6 +15,7 @@ pub(crate) struct TestDataObject {
     pub columns: u16,
     pub bits_allocated: u16,
     pub bits_stored: u16,
+    pub pixel_representation: u16,
     pub samples_per_pixel: u16,
     pub photometric_interpretation: &'static str,
     pub number_of_frames: u32,
@@ -47,6 +48,10 @@ impl PixelDataObject for TestDataObject {
         Some(self.bits_stored)
     }
 
+    fn pixel_representation(&self) -> Option<u16> {
+        Some(self.pixel_representation)
+    }
+
     fn photometric_interpretation(&self) -> Option<&str> {
         Some(&self.photometric_interpretation)
     }
@@ -15,7 +15,9 @@ use dicom_encoding::{
     adapters::{EncodeOptions, PixelDataReader, PixelDataWriter},
     Codec,
 };
-use dicom_transfer_syntax_registry::entries::JPEG_BASELINE;
+use dicom_transfer_syntax_registry::entries::{
+    JPEG_BASELINE, JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION,
+};
 
 fn read_data_piece(test_file: impl AsRef<Path>, offset: u64, length: usize) -> Vec<u8> {
     let mut file = File::open(test_file).unwrap();
@@ -77,6 +79,7 @@ fn read_jpeg_baseline_1() {
         columns: 100,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -131,6 +134,7 @@ fn read_jpeg_lossless_1() {
         columns: 100,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -209,6 +213,7 @@ fn write_and_read_jpeg_baseline() {
         columns,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -241,6 +246,7 @@ fn write_and_read_jpeg_baseline() {
         columns,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -271,9 +277,134 @@ fn write_and_read_jpeg_baseline() {
     }
 }
 
+/// Encode the given samples in JPEG lossless (SV1)
+/// and decode them back,
+/// checking that the pixel data is exactly the same.
+fn check_jpeg_lossless_roundtrip(
+    samples: Vec<u8>,
+    rows: u16,
+    columns: u16,
+    bits_allocated: u16,
+    bits_stored: u16,
+    samples_per_pixel: u16,
+    number_of_frames: u32,
+) {
+    let photometric_interpretation = if samples_per_pixel == 3 {
+        "RGB"
+    } else {
+        "MONOCHROME2"
+    };
+
+    // create test object of native encoding
+    let obj = TestDataObject {
+        // Explicit VR Little Endian
+        ts_uid: "1.2.840.10008.1.2.1".to_string(),
+        rows,
+        columns,
+        bits_allocated,
+        bits_stored,
+        pixel_representation: 0,
+        samples_per_pixel,
+        photometric_interpretation,
+        number_of_frames,
+        flat_pixel_data: Some(samples.clone()),
+        pixel_data_sequence: None,
+    };
+
+    let Codec::EncapsulatedPixelData(Some(reader), Some(writer)) =
+        JPEG_LOSSLESS_NON_HIERARCHICAL_FIRST_ORDER_PREDICTION.codec()
+    else {
+        panic!("JPEG lossless pixel data adapters not found")
+    };
+
+    let mut fragments = vec![];
+    let mut offset_table = vec![];
+    let ops = writer
+        .encode(
+            &obj,
+            EncodeOptions::default(),
+            &mut fragments,
+            &mut offset_table,
+        )
+        .expect("JPEG lossless encoding failed");
+    assert!(
+        ops.is_empty(),
+        "lossless encoding should not change attributes"
+    );
+    assert_eq!(fragments.len(), number_of_frames as usize);
+
+    // instantiate new object representing the compressed version
+    let obj = TestDataObject {
+        // JPEG lossless SV1
+        ts_uid: "1.2.840.10008.1.2.4.70".to_string(),
+        flat_pixel_data: None,
+        pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], fragments)),
+        ..obj
+    };
+
+    let frame_size = samples.len() / number_of_frames as usize;
+    for frame in 0..number_of_frames {
+        let mut decoded = vec![];
+        reader
+            .decode_frame(&obj, frame, &mut decoded)
+            .expect("JPEG lossless frame decoding failed");
+
+        let start = frame as usize * frame_size;
+        assert_eq!(
+            decoded,
+            &samples[start..start + frame_size],
+            "pixel data mismatch in frame #{}",
+            frame
+        );
+    }
+}
+
+/// writing to JPEG lossless and back should yield exactly the same pixel data
 #[test]
-fn test_decode_jpeg_trailing_bytes() {
+fn write_and_read_jpeg_lossless() {
+    // use linear congruence to make some noise
+    let mut seed = 0xcfcf_acab_u32;
+    let mut gen_sample = || {
+        seed = seed.wrapping_mul(4_294_967_291).wrapping_add(67291);
+        seed >> 7
+    };
 
+    // 8-bit RGB gradient with noise
+    let (rows, columns) = (48_u16, 64_u16);
+    let samples: Vec<u8> = (0..rows as usize * columns as usize * 3)
+        .map(|i| (i / 3 % 64) as u8 + (gen_sample() % 8) as u8)
+        .collect();
+    check_jpeg_lossless_roundtrip(samples, rows, columns, 8, 8, 3, 1);
+
+    // 12-bit monochrome, 2 frames
+    let (rows, columns) = (30_u16, 41_u16);
+    let samples: Vec<u8> = (0..rows as usize * columns as usize * 2)
+        .flat_map(|i| (((i * 13) as u32 + gen_sample() % 64) as u16 & 0x0FFF).to_le_bytes())
+        .collect();
+    check_jpeg_lossless_roundtrip(samples, rows, columns, 16, 12, 1, 2);
+
+    // 16-bit monochrome with extreme differences
+    let (rows, columns) = (16_u16, 16_u16);
+    let samples: Vec<u8> = (0..rows as usize * columns as usize)
+        .flat_map(|i| {
+            match i % 4 {
+                0 => 0_u16,
+                1 => 0x8000,
+                2 => 0xFFFF,
+                _ => gen_sample() as u16,
+            }
+            .to_le_bytes()
+        })
+        .collect();
+    check_jpeg_lossless_roundtrip(samples, rows, columns, 16, 16, 1, 1);
+
+    // 16-bit RGB, constant image
+    let samples = vec![0x34; 8 * 8 * 3 * 2];
+    check_jpeg_lossless_roundtrip(samples, 8, 8, 16, 16, 3, 1);
+}
+
+#[test]
+fn test_decode_jpeg_trailing_bytes() {
     let test_file = dicom_test_files::path("pydicom/SC_rgb_jpeg_lossy_gdcm.dcm").unwrap();
 
     // manually fetch the pixel data fragment from the file
@@ -292,6 +423,7 @@ fn test_decode_jpeg_trailing_bytes() {
         columns: 100,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
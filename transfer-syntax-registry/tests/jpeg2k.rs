@@ -0,0 +1,256 @@
+//! Test suite for JPEG 2000 pixel data writing and reading
+#![cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+
+mod adapters;
+
+use adapters::TestDataObject;
+use dicom_core::{
+    ops::{AttributeAction, AttributeSelector},
+    value::PixelFragmentSequence,
+    Tag,
+};
+use dicom_encoding::{
+    adapters::{EncodeOptions, PixelDataReader, PixelDataWriter},
+    Codec,
+};
+use dicom_transfer_syntax_registry::entries::{
+    HIGH_THROUGHPUT_JPEG_2000_IMAGE_COMPRESSION,
+    HIGH_THROUGHPUT_JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY,
+    HIGH_THROUGHPUT_JPEG_2000_WITH_RPCL_OPTIONS_IMAGE_COMPRESSION_LOSSLESS_ONLY,
+    JPEG_2000_IMAGE_COMPRESSION, JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY,
+};
+
+/// build an image with smooth gradients and a bit of noise
+fn gen_samples(rows: u16, columns: u16, samples_per_pixel: u16, bits_stored: u16) -> Vec<u8> {
+    // use linear congruence to make noise
+    let mut seed = 0xcfcf_acab_u32;
+    let mut gen_noise = || {
+        seed = seed.wrapping_mul(4_294_967_291).wrapping_add(67291);
+        (seed >> 7) % 4
+    };
+
+    let max = (1_u32 << bits_stored) - 1;
+    let mut samples = Vec::new();
+    for y in 0..rows as u32 {
+        for x in 0..columns as u32 {
+            for c in 0..samples_per_pixel as u32 {
+                let value = ((x + y * (c + 1)) * max / (columns as u32 + rows as u32 * 3)
+                    + gen_noise())
+                .min(max);
+                if bits_stored > 8 {
+                    samples.extend((value as u16).to_le_bytes());
+                } else {
+                    samples.push(value as u8);
+                }
+            }
+        }
+    }
+    samples
+}
+
+fn test_object(
+    rows: u16,
+    columns: u16,
+    bits_allocated: u16,
+    bits_stored: u16,
+    samples_per_pixel: u16,
+    samples: Vec<u8>,
+) -> TestDataObject {
+    TestDataObject {
+        // Explicit VR Little Endian
+        ts_uid: "1.2.840.10008.1.2.1".to_string(),
+        rows,
+        columns,
+        bits_allocated,
+        bits_stored,
+        pixel_representation: 0,
+        samples_per_pixel,
+        photometric_interpretation: if samples_per_pixel == 3 {
+            "RGB"
+        } else {
+            "MONOCHROME2"
+        },
+        number_of_frames: 1,
+        flat_pixel_data: Some(samples),
+        pixel_data_sequence: None,
+    }
+}
+
+/// writing to JPEG 2000 lossless and back should yield the same pixel data
+#[test]
+fn write_and_read_jpeg2k_lossless() {
+    let Codec::EncapsulatedPixelData(Some(reader), Some(writer)) =
+        JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY.codec()
+    else {
+        panic!("JPEG 2000 pixel data adapters not found")
+    };
+
+    for (bits_allocated, bits_stored, samples_per_pixel) in [(16, 12, 1), (16, 16, 1), (8, 8, 3)] {
+        let (rows, columns) = (100, 120);
+        let samples = gen_samples(rows, columns, samples_per_pixel, bits_stored);
+        let obj = test_object(
+            rows,
+            columns,
+            bits_allocated,
+            bits_stored,
+            samples_per_pixel,
+            samples.clone(),
+        );
+
+        let mut encoded = vec![];
+        let ops = writer
+            .encode_frame(&obj, 0, EncodeOptions::default(), &mut encoded)
+            .expect("JPEG 2000 frame encoding failed");
+
+        // no lossy compression should be reported
+        assert!(ops
+            .iter()
+            .all(|op| op.selector != AttributeSelector::from(Tag(0x0028, 0x2110))));
+
+        let obj = TestDataObject {
+            // JPEG 2000 (lossless only)
+            ts_uid: "1.2.840.10008.1.2.4.90".to_string(),
+            flat_pixel_data: None,
+            pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![encoded])),
+            ..obj
+        };
+
+        let mut decoded = vec![];
+        reader
+            .decode_frame(&obj, 0, &mut decoded)
+            .expect("JPEG 2000 frame decoding failed");
+
+        assert_eq!(
+            decoded, samples,
+            "pixel data mismatch ({} bits stored, {} samples per pixel)",
+            bits_stored, samples_per_pixel
+        );
+    }
+}
+
+/// writing signed samples to JPEG 2000 lossless and back
+/// should yield the same pixel data
+#[test]
+fn write_and_read_jpeg2k_lossless_signed() {
+    let Codec::EncapsulatedPixelData(Some(reader), Some(writer)) =
+        JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY.codec()
+    else {
+        panic!("JPEG 2000 pixel data adapters not found")
+    };
+
+    for bits_stored in [12, 16] {
+        let (rows, columns) = (64, 80);
+        // centered gradient, sign extended to 16 bits
+        let min = -(1_i32 << (bits_stored - 1));
+        let max = (1_i32 << (bits_stored - 1)) - 1;
+        let samples: Vec<u8> = (0..rows as i32 * columns as i32)
+            .flat_map(|i| {
+                let value = (min + i * 37 % (max - min + 1)).min(max);
+                (value as i16).to_le_bytes()
+            })
+            .collect();
+
+        let obj = TestDataObject {
+            pixel_representation: 1,
+            ..test_object(rows, columns, 16, bits_stored, 1, samples.clone())
+        };
+
+        let mut encoded = vec![];
+        writer
+            .encode_frame(&obj, 0, EncodeOptions::default(), &mut encoded)
+            .expect("JPEG 2000 frame encoding failed");
+
+        let obj = TestDataObject {
+            // JPEG 2000 (lossless only)
+            ts_uid: "1.2.840.10008.1.2.4.90".to_string(),
+            flat_pixel_data: None,
+            pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![encoded])),
+            ..obj
+        };
+
+        let mut decoded = vec![];
+        reader
+            .decode_frame(&obj, 0, &mut decoded)
+            .expect("JPEG 2000 frame decoding failed");
+
+        assert_eq!(
+            decoded, samples,
+            "pixel data mismatch ({} bits stored, signed)",
+            bits_stored
+        );
+    }
+}
+
+/// High-Throughput JPEG 2000 is only supported for decoding
+#[test]
+fn htj2k_has_no_encoder() {
+    for ts in [
+        &HIGH_THROUGHPUT_JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY,
+        &HIGH_THROUGHPUT_JPEG_2000_WITH_RPCL_OPTIONS_IMAGE_COMPRESSION_LOSSLESS_ONLY,
+        &HIGH_THROUGHPUT_JPEG_2000_IMAGE_COMPRESSION,
+    ] {
+        assert!(
+            matches!(ts.codec(), Codec::EncapsulatedPixelData(Some(_), None)),
+            "{} should only have a pixel data reader",
+            ts.uid()
+        );
+    }
+}
+
+/// writing to lossy JPEG 2000 and back should yield approximately the same pixel data
+#[test]
+fn write_and_read_jpeg2k_lossy() {
+    let Codec::EncapsulatedPixelData(Some(reader), Some(writer)) =
+        JPEG_2000_IMAGE_COMPRESSION.codec()
+    else {
+        panic!("JPEG 2000 pixel data adapters not found")
+    };
+
+    let (rows, columns) = (128, 128);
+    let samples = gen_samples(rows, columns, 3, 8);
+    let obj = test_object(rows, columns, 8, 8, 3, samples.clone());
+
+    let mut options = EncodeOptions::default();
+    options.quality = Some(90);
+
+    let mut encoded = vec![];
+    let ops = writer
+        .encode_frame(&obj, 0, options, &mut encoded)
+        .expect("JPEG 2000 frame encoding failed");
+
+    assert!(
+        encoded.len() < samples.len() / 2,
+        "lossy JPEG 2000 should compress the image"
+    );
+
+    // lossy compression should be reported
+    let lossy_op = ops
+        .iter()
+        .find(|op| op.selector == AttributeSelector::from(Tag(0x0028, 0x2110)))
+        .expect("Lossy Image Compression should be set");
+    assert_eq!(lossy_op.action, AttributeAction::SetStr("01".into()));
+
+    let obj = TestDataObject {
+        // JPEG 2000
+        ts_uid: "1.2.840.10008.1.2.4.91".to_string(),
+        flat_pixel_data: None,
+        pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![encoded])),
+        ..obj
+    };
+
+    let mut decoded = vec![];
+    reader
+        .decode_frame(&obj, 0, &mut decoded)
+        .expect("JPEG 2000 frame decoding failed");
+
+    assert_eq!(samples.len(), decoded.len(), "pixel data length mismatch");
+
+    // compare with error margin
+    let total_error: u64 = samples
+        .iter()
+        .zip(&decoded)
+        .map(|(a, b)| u64::from(a.abs_diff(*b)))
+        .sum();
+    let mean_error = total_error as f64 / samples.len() as f64;
+    assert!(mean_error < 4., "mean error too high: {}", mean_error);
+}
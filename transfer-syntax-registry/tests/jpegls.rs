@@ -72,6 +72,7 @@ fn read_jpeg_ls_1() {
         columns: 256,
         bits_allocated: 16,
         bits_stored: 16,
+        pixel_representation: 0,
         samples_per_pixel: 1,
         photometric_interpretation: "MONOCHROME2",
         number_of_frames: 1,
@@ -130,6 +131,7 @@ fn read_jpeg_ls_lossless_1() {
         columns: cols,
         bits_allocated: 16,
         bits_stored: 16,
+        pixel_representation: 0,
         samples_per_pixel: 1,
         photometric_interpretation: "MONOCHROME2",
         number_of_frames: 1,
@@ -211,6 +213,7 @@ fn write_and_read_jpeg_ls() {
         columns,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -242,6 +245,7 @@ fn write_and_read_jpeg_ls() {
         columns,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
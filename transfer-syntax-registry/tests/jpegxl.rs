@@ -78,6 +78,7 @@ fn read_jpeg_xl_1() {
         columns: 100,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -133,6 +134,7 @@ fn read_jpeg_xl_lossless_1() {
         columns: 100,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -211,6 +213,7 @@ fn write_and_read_jpeg_xl() {
         columns,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -239,6 +242,7 @@ fn write_and_read_jpeg_xl() {
         columns,
         bits_allocated: 8,
         bits_stored: 8,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
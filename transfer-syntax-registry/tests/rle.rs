@@ -11,7 +11,10 @@ use std::{
 
 use adapters::TestDataObject;
 use dicom_core::value::PixelFragmentSequence;
-use dicom_encoding::{adapters::PixelDataReader, Codec};
+use dicom_encoding::{
+    adapters::{EncodeOptions, PixelDataReader, PixelDataWriter},
+    Codec,
+};
 use dicom_transfer_syntax_registry::entries::RLE_LOSSLESS;
 
 fn read_data_piece(test_file: impl AsRef<Path>, offset: u64, length: usize) -> Vec<u8> {
@@ -69,6 +72,7 @@ fn read_rle_1() {
         columns: 512,
         bits_allocated: 16,
         bits_stored: 16,
+        pixel_representation: 0,
         samples_per_pixel: 1,
         photometric_interpretation: "MONOCHROME2",
         number_of_frames: 1,
@@ -132,6 +136,7 @@ fn read_rle_2() {
         columns: 100,
         bits_allocated: 16,
         bits_stored: 16,
+        pixel_representation: 0,
         samples_per_pixel: 3,
         photometric_interpretation: "RGB",
         number_of_frames: 1,
@@ -165,3 +170,242 @@ fn read_rle_2() {
 
     check_u16_rgb_pixel(&dest, 100, 10, 95, [0xFFFF, 0xFFFF, 0xFFFF]);
 }
+
+/// Build an RLE fragment with one PackBits literal run per segment.
+fn rle_fragment(segments: &[&[u8]]) -> Vec<u8> {
+    let mut header = vec![0; 64];
+    header[0..4].copy_from_slice(&(segments.len() as u32).to_le_bytes());
+    let mut data = vec![];
+    for (i, segment) in segments.iter().enumerate() {
+        let offset = 64 + data.len() as u32;
+        header[4 * (i + 1)..4 * (i + 2)].copy_from_slice(&offset.to_le_bytes());
+        data.push(segment.len() as u8 - 1);
+        data.extend_from_slice(segment);
+    }
+    header.extend(data);
+    header
+}
+
+#[test]
+fn read_rle_most_significant_byte_first() {
+    let Codec::EncapsulatedPixelData(Some(adapter), _) = RLE_LOSSLESS.codec() else {
+        panic!("RLE lossless pixel data reader not found")
+    };
+
+    // 16-bit RGB, 2 pixels:
+    // [0x0102, 0x0304, 0x0506], [0x0708, 0x090A, 0x0B0C]
+    let fragment = rle_fragment(&[
+        &[0x01, 0x07],
+        &[0x02, 0x08],
+        &[0x03, 0x09],
+        &[0x04, 0x0A],
+        &[0x05, 0x0B],
+        &[0x06, 0x0C],
+    ]);
+    let obj = TestDataObject {
+        ts_uid: "1.2.840.10008.1.2.5".to_string(),
+        rows: 1,
+        columns: 2,
+        bits_allocated: 16,
+        bits_stored: 16,
+        pixel_representation: 0,
+        samples_per_pixel: 3,
+        photometric_interpretation: "RGB",
+        number_of_frames: 1,
+        flat_pixel_data: None,
+        pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![fragment])),
+    };
+
+    let mut dest = vec![];
+    adapter
+        .decode(&obj, &mut dest)
+        .expect("RLE decoding failed");
+    check_u16_rgb_pixel(&dest, 2, 0, 0, [0x0102, 0x0304, 0x0506]);
+    check_u16_rgb_pixel(&dest, 2, 1, 0, [0x0708, 0x090A, 0x0B0C]);
+
+    let mut frame = vec![];
+    adapter
+        .decode_frame(&obj, 0, &mut frame)
+        .expect("RLE frame decoding failed");
+    assert_eq!(frame, dest);
+
+    // 32-bit monochrome, 2 pixels: 0x01020304, 0x05060708
+    let fragment = rle_fragment(&[&[0x01, 0x05], &[0x02, 0x06], &[0x03, 0x07], &[0x04, 0x08]]);
+    let obj = TestDataObject {
+        bits_allocated: 32,
+        bits_stored: 32,
+        pixel_representation: 0,
+        samples_per_pixel: 1,
+        photometric_interpretation: "MONOCHROME2",
+        pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![fragment])),
+        ..obj
+    };
+
+    let mut dest = vec![];
+    adapter
+        .decode(&obj, &mut dest)
+        .expect("RLE decoding failed");
+    assert_eq!(
+        dest,
+        [0x01020304_u32.to_le_bytes(), 0x05060708_u32.to_le_bytes()].concat()
+    );
+
+    let mut frame = vec![];
+    adapter
+        .decode_frame(&obj, 0, &mut frame)
+        .expect("RLE frame decoding failed");
+    assert_eq!(frame, dest);
+}
+
+#[test]
+fn write_rle_rejects_encapsulated_pixel_data() {
+    let Codec::EncapsulatedPixelData(_, Some(writer)) = RLE_LOSSLESS.codec() else {
+        panic!("RLE lossless pixel data writer not found")
+    };
+
+    let obj = TestDataObject {
+        ts_uid: "1.2.840.10008.1.2.5".to_string(),
+        rows: 1,
+        columns: 2,
+        bits_allocated: 8,
+        bits_stored: 8,
+        pixel_representation: 0,
+        samples_per_pixel: 1,
+        photometric_interpretation: "MONOCHROME2",
+        number_of_frames: 1,
+        flat_pixel_data: None,
+        pixel_data_sequence: Some(PixelFragmentSequence::new(
+            vec![],
+            vec![rle_fragment(&[&[0x01, 0x02]])],
+        )),
+    };
+
+    let mut dest = vec![];
+    assert!(writer
+        .encode_frame(&obj, 0, EncodeOptions::new(), &mut dest)
+        .is_err());
+}
+
+/// Encode the given native pixel data to RLE lossless
+/// and decode it back, checking that the samples are preserved.
+fn check_rle_roundtrip(
+    rows: u16,
+    columns: u16,
+    bits_allocated: u16,
+    samples_per_pixel: u16,
+    number_of_frames: u32,
+) {
+    let frame_size = rows as usize
+        * columns as usize
+        * samples_per_pixel as usize
+        * (bits_allocated / 8) as usize;
+
+    // build some pixel data with runs and noise
+    let mut seed = 0xcfcf_acab_u32;
+    let samples: Vec<u8> = (0..frame_size * number_of_frames as usize)
+        .map(|i| {
+            if (i / 37) % 2 == 0 {
+                (i / 37) as u8
+            } else {
+                seed = seed.wrapping_mul(4_294_967_291).wrapping_add(67291);
+                (seed >> 7) as u8
+            }
+        })
+        .collect();
+
+    let pmi = if samples_per_pixel == 3 {
+        "RGB"
+    } else {
+        "MONOCHROME2"
+    };
+
+    // create test object of native encoding
+    let obj = TestDataObject {
+        // Explicit VR Little Endian
+        ts_uid: "1.2.840.10008.1.2.1".to_string(),
+        rows,
+        columns,
+        bits_allocated,
+        bits_stored: bits_allocated,
+        pixel_representation: 0,
+        samples_per_pixel,
+        photometric_interpretation: pmi,
+        number_of_frames,
+        flat_pixel_data: Some(samples.clone()),
+        pixel_data_sequence: None,
+    };
+
+    let Codec::EncapsulatedPixelData(Some(reader), Some(writer)) = RLE_LOSSLESS.codec() else {
+        panic!("RLE lossless pixel data adapters not found")
+    };
+
+    // encode all frames
+    let mut fragments = vec![];
+    let mut offset_table = vec![];
+    writer
+        .encode(
+            &obj,
+            EncodeOptions::new(),
+            &mut fragments,
+            &mut offset_table,
+        )
+        .expect("RLE encoding failed");
+
+    assert_eq!(fragments.len(), number_of_frames as usize);
+    for fragment in &fragments {
+        assert_eq!(
+            fragment.len() % 2,
+            0,
+            "RLE fragment should have even length"
+        );
+        let nr_segments = u32::from_le_bytes([fragment[0], fragment[1], fragment[2], fragment[3]]);
+        assert_eq!(
+            nr_segments,
+            samples_per_pixel as u32 * bits_allocated as u32 / 8
+        );
+    }
+
+    // create RLE lossless object
+    let obj = TestDataObject {
+        ts_uid: "1.2.840.10008.1.2.5".to_string(),
+        flat_pixel_data: None,
+        pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], fragments)),
+        ..obj
+    };
+
+    // decode all frames
+    let mut dest = vec![];
+    reader.decode(&obj, &mut dest).expect("RLE decoding failed");
+    assert_eq!(dest, samples);
+
+    // decode each frame
+    for frame in 0..number_of_frames {
+        let mut dest = vec![];
+        reader
+            .decode_frame(&obj, frame, &mut dest)
+            .expect("RLE frame decoding failed");
+        let start = frame as usize * frame_size;
+        assert_eq!(dest, &samples[start..start + frame_size]);
+    }
+}
+
+#[test]
+fn write_and_read_rle_8bit_monochrome() {
+    check_rle_roundtrip(64, 100, 8, 1, 1);
+}
+
+#[test]
+fn write_and_read_rle_16bit_rgb() {
+    check_rle_roundtrip(33, 45, 16, 3, 1);
+}
+
+#[test]
+fn write_and_read_rle_32bit_monochrome() {
+    check_rle_roundtrip(20, 300, 32, 1, 1);
+}
+
+#[test]
+fn write_and_read_rle_multiframe() {
+    check_rle_roundtrip(16, 16, 8, 3, 3);
+    check_rle_roundtrip(15, 17, 16, 1, 4);
+}